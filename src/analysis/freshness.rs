@@ -0,0 +1,347 @@
+//! Freshness of the requirements of a package repository.
+//!
+//! Every package of a repository is resolved against the package index and
+//! the version picked for each of its requirements is compared to the latest
+//! indexed version of the family. Requirements lagging behind by more than a
+//! threshold of versions are reported, as JSON or CSV for pipeline dashboards.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+use super::GraphStats;
+use crate::core::{DependencyResolver, Package, PackageIndex, Requirement, Version};
+
+/// Output format of a freshness report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Pretty-printed JSON
+    #[default]
+    Json,
+    /// CSV with one row per outdated requirement
+    Csv,
+}
+
+impl ReportFormat {
+    /// Parse a format name, `json` or `csv`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// A requirement resolving to a version behind the latest of its family.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutdatedRequirement {
+    /// Package declaring the requirement
+    pub package: String,
+    /// Version of the declaring package
+    pub version: String,
+    /// Requirement as declared, e.g. `python-3.9`
+    pub requirement: String,
+    /// Version the requirement resolves to
+    pub resolved: String,
+    /// Latest indexed version of the required family
+    pub latest: String,
+    /// Number of indexed versions newer than the resolved one
+    pub versions_behind: usize,
+}
+
+/// A package whose requirements could not be resolved together.
+///
+/// Its requirements are still audited one by one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FreshnessError {
+    /// Package name and version, e.g. `maya_tools-1.0.0`
+    pub package: String,
+    /// Resolve error
+    pub message: String,
+}
+
+/// Result of auditing the freshness of a repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FreshnessReport {
+    /// Audited repository
+    pub repository: PathBuf,
+    /// Number of audited packages
+    pub audited: usize,
+    /// Versions a requirement may lag behind before it is reported
+    pub threshold: usize,
+    /// Outdated requirements, ordered by package
+    pub outdated: Vec<OutdatedRequirement>,
+    /// Packages that failed to resolve
+    pub errors: Vec<FreshnessError>,
+    /// Cycles, depth and hubs of the requirement graph of the index
+    pub graph: GraphStats,
+}
+
+impl FreshnessReport {
+    /// Audit the requirements of `packages` against the indexed packages.
+    ///
+    /// Packages are resolved in parallel, at most `concurrency` at a time.
+    /// Weak and conflict requirements are not audited.
+    pub async fn audit(
+        repository: PathBuf,
+        packages: Vec<Arc<Package>>,
+        index: Arc<PackageIndex>,
+        resolver: Arc<dyn DependencyResolver>,
+        threshold: usize,
+        concurrency: usize,
+    ) -> Self {
+        let mut report = Self {
+            repository,
+            audited: packages.len(),
+            threshold,
+            ..Default::default()
+        };
+
+        let mut tasks = JoinSet::new();
+        let mut results = Vec::new();
+        for package in packages {
+            if tasks.len() >= concurrency.max(1) {
+                if let Some(Ok(result)) = tasks.join_next().await {
+                    results.push(result);
+                }
+            }
+            let index = Arc::clone(&index);
+            let resolver = Arc::clone(&resolver);
+            tasks.spawn(async move {
+                audit_package(&package, &index, resolver.as_ref(), threshold).await
+            });
+        }
+        while let Some(result) = tasks.join_next().await {
+            if let Ok(result) = result {
+                results.push(result);
+            }
+        }
+
+        for (outdated, error) in results {
+            report.outdated.extend(outdated);
+            report.errors.extend(error);
+        }
+        report.outdated.sort_by(|a, b| {
+            (&a.package, &a.version, &a.requirement).cmp(&(&b.package, &b.version, &b.requirement))
+        });
+        report.errors.sort_by(|a, b| a.package.cmp(&b.package));
+        report
+    }
+
+    /// Check whether any requirement is outdated.
+    pub fn has_outdated(&self) -> bool {
+        !self.outdated.is_empty()
+    }
+
+    /// Render the outdated requirements as CSV with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("package,version,requirement,resolved,latest,versions_behind\n");
+        for entry in &self.outdated {
+            let fields = [
+                csv_field(&entry.package),
+                csv_field(&entry.version),
+                csv_field(&entry.requirement),
+                csv_field(&entry.resolved),
+                csv_field(&entry.latest),
+                entry.versions_behind.to_string(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// Audit the requirements of one package.
+async fn audit_package(
+    package: &Package,
+    index: &PackageIndex,
+    resolver: &dyn DependencyResolver,
+    threshold: usize,
+) -> (Vec<OutdatedRequirement>, Option<FreshnessError>) {
+    let requirements: Vec<Requirement> = package
+        .requires
+        .iter()
+        .filter(|requirement| !requirement.weak && !requirement.conflict)
+        .cloned()
+        .collect();
+    if requirements.is_empty() {
+        return (Vec::new(), None);
+    }
+
+    let (resolved, error) = match resolver.resolve(&requirements).await {
+        Ok(context) => (context.packages, None),
+        Err(e) => (
+            Vec::new(),
+            Some(FreshnessError {
+                package: format!("{}-{}", package.name, package.version),
+                message: e.to_string(),
+            }),
+        ),
+    };
+
+    let mut outdated = Vec::new();
+    for requirement in &requirements {
+        let version = match resolved.iter().find(|p| *p.name == requirement.name) {
+            Some(resolved) => Some(resolved.version.clone()),
+            // Without a resolved context, take the version picked on its own
+            None => resolver
+                .get_latest_version(&requirement.name, &requirement.constraint)
+                .await
+                .ok()
+                .flatten(),
+        };
+        let Some(version) = version.filter(|version| !version.is_unversioned()) else {
+            continue;
+        };
+
+        let versions = index.get(&requirement.name).map(Vec::as_slice);
+        let newer: Vec<&Version> = versions
+            .unwrap_or_default()
+            .iter()
+            .map(|p| &p.version)
+            .filter(|v| **v > version && (version.is_prerelease() || !v.is_prerelease()))
+            .collect();
+        if newer.len() <= threshold {
+            continue;
+        }
+        let latest = newer
+            .iter()
+            .max()
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+        outdated.push(OutdatedRequirement {
+            package: package.name.to_string(),
+            version: package.version.to_string(),
+            requirement: requirement.to_string(),
+            resolved: version.to_string(),
+            latest,
+            versions_behind: newer.len(),
+        });
+    }
+
+    (outdated, error)
+}
+
+/// Quote a CSV field containing separators, quotes or line breaks.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::resolver::create_resolver;
+
+    fn package(name: &str, version: &str, requires: &[&str]) -> Arc<Package> {
+        let mut package = Package::builder().name(name).version(version).build();
+        package.requires = requires
+            .iter()
+            .map(|r| Requirement::parse(r).unwrap())
+            .collect();
+        Arc::new(package)
+    }
+
+    #[tokio::test]
+    async fn test_audit_reports_requirements_behind_threshold() {
+        let mut index = PackageIndex::new();
+        for version in ["3.7", "3.9", "3.10", "3.11", "3.12-rc1"] {
+            index
+                .entry("python".to_string())
+                .or_default()
+                .push(package("python", version, &[]));
+        }
+        for version in ["1.0", "1.1"] {
+            index
+                .entry("numpy".to_string())
+                .or_default()
+                .push(package("numpy", version, &[]));
+        }
+        let index = Arc::new(index);
+        let resolver: Arc<dyn DependencyResolver> =
+            Arc::from(create_resolver(&Config::new(), (*index).clone()));
+
+        let packages = vec![
+            package(
+                "old_tool",
+                "1.0",
+                &["python-3.7", "numpy-1.0", "~numpy-1.0"],
+            ),
+            package("new_tool", "2.0", &["python-3.11", "numpy"]),
+            package("broken_tool", "1.0", &["python-3.9", "missing_lib"]),
+            package("empty", "1.0", &[]),
+        ];
+        let report = FreshnessReport::audit(
+            PathBuf::from("/repo"),
+            packages.clone(),
+            Arc::clone(&index),
+            Arc::clone(&resolver),
+            0,
+            2,
+        )
+        .await;
+        assert_eq!(report.audited, 4);
+        let found: Vec<(&str, &str, &str, usize)> = report
+            .outdated
+            .iter()
+            .map(|o| {
+                (
+                    o.package.as_str(),
+                    o.requirement.as_str(),
+                    o.latest.as_str(),
+                    o.versions_behind,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("broken_tool", "python-3.9", "3.11", 2),
+                ("old_tool", "numpy-1.0", "1.1", 1),
+                ("old_tool", "python-3.7", "3.11", 3),
+            ]
+        );
+        assert!(report.has_outdated());
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].package, "broken_tool-1.0");
+
+        // A threshold keeps requirements that are only slightly behind
+        let report =
+            FreshnessReport::audit(PathBuf::from("/repo"), packages, index, resolver, 2, 1).await;
+        assert_eq!(report.outdated.len(), 1);
+        assert_eq!(report.outdated[0].requirement, "python-3.7");
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let report = FreshnessReport {
+            outdated: vec![OutdatedRequirement {
+                package: "tool".to_string(),
+                version: "1.0".to_string(),
+                requirement: "python-3.7|3.9".to_string(),
+                resolved: "3.9".to_string(),
+                latest: "3.11".to_string(),
+                versions_behind: 2,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            report.to_csv(),
+            "package,version,requirement,resolved,latest,versions_behind\n\
+             tool,1.0,python-3.7|3.9,3.9,3.11,2\n"
+        );
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+        assert_eq!(ReportFormat::parse("CSV"), Some(ReportFormat::Csv));
+        assert_eq!(ReportFormat::parse("xml"), None);
+    }
+}
@@ -0,0 +1,228 @@
+//! Shape of the requirement graph of the package index.
+//!
+//! Families are the nodes of the graph, with an edge to every family
+//! required by any of their versions. Cycles are found as strongly connected
+//! components, and dependency depths are measured on the acyclic graph of
+//! those components, so a cycle counts as a single level.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Number of families listed in [`GraphStats::most_depended_on`].
+pub const MOST_DEPENDED_ON_LIMIT: usize = 10;
+
+/// A family and the number of families requiring it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependedOnFamily {
+    /// Required family
+    pub family: String,
+    /// Number of distinct families requiring it
+    pub dependents: usize,
+}
+
+/// Analytics of the requirement graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphStats {
+    /// Number of families whose requirements lead back to themselves
+    pub cyclic_families: usize,
+    /// Longest chain of requirements, in edges, e.g. 2 for `a -> b -> c`
+    pub max_depth: usize,
+    /// Families required by the most other families, most first
+    pub most_depended_on: Vec<DependedOnFamily>,
+}
+
+impl GraphStats {
+    /// Analyze a requirement graph mapping each family to the families it
+    /// requires.
+    pub fn analyze(graph: &BTreeMap<String, BTreeSet<String>>) -> Self {
+        let components = strongly_connected_components(graph);
+        let mut component_of: HashMap<&str, usize> = HashMap::new();
+        for (index, component) in components.iter().enumerate() {
+            for family in component {
+                component_of.insert(*family, index);
+            }
+        }
+
+        let cyclic_families = components
+            .iter()
+            .filter(|component| {
+                component.len() > 1
+                    || graph
+                        .get(component[0])
+                        .is_some_and(|required| required.contains(component[0]))
+            })
+            .map(Vec::len)
+            .sum();
+
+        // Components come out of Tarjan's algorithm dependencies first, so
+        // the depth of everything a component requires is already known
+        let mut depths = vec![0usize; components.len()];
+        for (index, component) in components.iter().enumerate() {
+            depths[index] = component
+                .iter()
+                .flat_map(|family| graph.get(*family).into_iter().flatten())
+                .filter_map(|required| component_of.get(required.as_str()))
+                .filter(|&&other| other != index)
+                .map(|&other| depths[other] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+
+        let mut dependents: BTreeMap<&str, usize> = BTreeMap::new();
+        for required in graph.values().flatten() {
+            *dependents.entry(required).or_default() += 1;
+        }
+        let mut most_depended_on: Vec<DependedOnFamily> = dependents
+            .into_iter()
+            .map(|(family, dependents)| DependedOnFamily {
+                family: family.to_string(),
+                dependents,
+            })
+            .collect();
+        most_depended_on.sort_by(|a, b| {
+            b.dependents
+                .cmp(&a.dependents)
+                .then_with(|| a.family.cmp(&b.family))
+        });
+        most_depended_on.truncate(MOST_DEPENDED_ON_LIMIT);
+
+        Self {
+            cyclic_families,
+            max_depth: depths.into_iter().max().unwrap_or(0),
+            most_depended_on,
+        }
+    }
+}
+
+/// Find the strongly connected components of a graph with Tarjan's
+/// algorithm, iteratively so deep graphs cannot overflow the stack.
+///
+/// Components are returned in reverse topological order: a component comes
+/// after every component it has edges to.
+fn strongly_connected_components(graph: &BTreeMap<String, BTreeSet<String>>) -> Vec<Vec<&str>> {
+    let mut nodes: BTreeSet<&str> = graph.keys().map(String::as_str).collect();
+    nodes.extend(graph.values().flatten().map(String::as_str));
+
+    let mut index_of: HashMap<&str, usize> = HashMap::new();
+    let mut low_link: HashMap<&str, usize> = HashMap::new();
+    let mut on_stack: BTreeSet<&str> = BTreeSet::new();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut components = Vec::new();
+
+    for &root in &nodes {
+        if index_of.contains_key(root) {
+            continue;
+        }
+        // Frames of the depth-first search: a node and its remaining edges
+        let mut frames: Vec<(&str, Vec<&str>)> = Vec::new();
+        let mut unvisited = Some(root);
+        loop {
+            if let Some(node) = unvisited.take() {
+                let index = index_of.len();
+                index_of.insert(node, index);
+                low_link.insert(node, index);
+                stack.push(node);
+                on_stack.insert(node);
+                let mut remaining: Vec<&str> = graph
+                    .get(node)
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect();
+                remaining.reverse();
+                frames.push((node, remaining));
+            }
+            let Some((node, remaining)) = frames.last_mut() else {
+                break;
+            };
+            let node = *node;
+            if let Some(next) = remaining.pop() {
+                if !index_of.contains_key(next) {
+                    unvisited = Some(next);
+                } else if on_stack.contains(next) {
+                    let low = low_link[node].min(index_of[next]);
+                    low_link.insert(node, low);
+                }
+                continue;
+            }
+
+            frames.pop();
+            if let Some((parent, _)) = frames.last() {
+                let low = low_link[*parent].min(low_link[node]);
+                low_link.insert(parent, low);
+            }
+            if low_link[node] == index_of[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack.remove(member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        edges
+            .iter()
+            .map(|(family, required)| {
+                (
+                    family.to_string(),
+                    required.iter().map(|r| r.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_graph() {
+        let stats = GraphStats::analyze(&graph(&[
+            ("maya_tools", &["maya", "python", "pyside"]),
+            ("nuke_tools", &["nuke", "python"]),
+            ("pyside", &["qt", "python"]),
+            ("qt", &["pyside"]),
+            ("python", &[]),
+            ("loop", &["loop"]),
+        ]));
+
+        // pyside and qt require each other; loop requires itself
+        assert_eq!(stats.cyclic_families, 3);
+        // maya_tools -> {pyside, qt} -> python
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(
+            stats.most_depended_on[0],
+            DependedOnFamily {
+                family: "python".to_string(),
+                dependents: 3,
+            }
+        );
+        assert_eq!(stats.most_depended_on.len(), 6);
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        let names: Vec<String> = (0..10_000).map(|i| format!("family_{}", i)).collect();
+        let graph: BTreeMap<String, BTreeSet<String>> = names
+            .windows(2)
+            .map(|pair| (pair[0].clone(), BTreeSet::from([pair[1].clone()])))
+            .collect();
+
+        let stats = GraphStats::analyze(&graph);
+        assert_eq!(stats.max_depth, 9_999);
+        assert_eq!(stats.cyclic_families, 0);
+        assert!(GraphStats::analyze(&BTreeMap::new()) == GraphStats::default());
+    }
+}
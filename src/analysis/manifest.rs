@@ -0,0 +1,331 @@
+//! Software composition manifests of resolved packages.
+//!
+//! A [`DependencyManifest`] lists every package of the resolved dependency
+//! closure of a package definition, with its version, installation path and
+//! source repository, and the requirements linking them. It is rendered as
+//! SPDX-lite or CycloneDX JSON for studios that document the software
+//! composition of their pipeline tools.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::core::{Package, Requirement, ResolvedContext};
+
+/// Output format of a dependency manifest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// SPDX 2.3 JSON with packages and relationships only
+    #[default]
+    Spdx,
+    /// CycloneDX 1.5 JSON
+    CycloneDx,
+}
+
+impl ManifestFormat {
+    /// Parse a format name, `spdx` or `cyclonedx`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "spdx" => Some(Self::Spdx),
+            "cyclonedx" => Some(Self::CycloneDx),
+            _ => None,
+        }
+    }
+}
+
+/// A resolved package of a manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestComponent {
+    /// Family name
+    pub name: String,
+    /// Resolved version
+    pub version: String,
+    /// Installation directory of the version
+    pub path: PathBuf,
+    /// Repository the package was found in
+    pub repository: PathBuf,
+    /// Families of the closure the package requires
+    pub depends_on: Vec<String>,
+}
+
+/// The resolved dependency closure of a package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyManifest {
+    /// Name of the described package
+    pub package: String,
+    /// Version of the described package
+    pub version: String,
+    /// RFC 3339 time the manifest was created
+    pub created: String,
+    /// Families the described package requires directly
+    pub depends_on: Vec<String>,
+    /// Resolved packages, sorted by name
+    pub components: Vec<ManifestComponent>,
+}
+
+impl DependencyManifest {
+    /// Build the manifest of a package from the context its requirements
+    /// resolved to.
+    pub fn new(
+        package: &str,
+        version: &str,
+        requires: &[Requirement],
+        context: &ResolvedContext,
+    ) -> Self {
+        let mut packages: Vec<&Package> = context.packages.iter().collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        let resolved = |requires: &[Requirement]| -> Vec<String> {
+            let mut names: Vec<String> = requires
+                .iter()
+                .filter(|req| !req.conflict && !req.is_ephemeral())
+                .filter(|req| packages.iter().any(|package| *package.name == req.name))
+                .map(|req| req.name.clone())
+                .collect();
+            names.sort();
+            names.dedup();
+            names
+        };
+
+        Self {
+            package: package.to_string(),
+            version: version.to_string(),
+            created: chrono::Utc::now().to_rfc3339(),
+            depends_on: resolved(requires),
+            components: packages
+                .iter()
+                .map(|package| ManifestComponent {
+                    name: package.name.to_string(),
+                    version: package.version.to_string(),
+                    path: package.path.clone(),
+                    repository: repository_of(&package.path),
+                    depends_on: resolved(&package.requires),
+                })
+                .collect(),
+        }
+    }
+
+    /// Render the manifest in `format`.
+    pub fn render(&self, format: ManifestFormat) -> Value {
+        match format {
+            ManifestFormat::Spdx => self.to_spdx(),
+            ManifestFormat::CycloneDx => self.to_cyclonedx(),
+        }
+    }
+
+    /// Render the manifest as SPDX JSON.
+    pub fn to_spdx(&self) -> Value {
+        let root = spdx_id(&self.package, &self.version);
+        let id_of = |name: &str| {
+            self.component(name)
+                .map(|component| spdx_id(&component.name, &component.version))
+        };
+
+        let mut packages = vec![json!({
+            "SPDXID": root,
+            "name": self.package,
+            "versionInfo": self.version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+        })];
+        let mut relationships = vec![json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": root,
+        })];
+        let depends_on = |from: &str, names: &[String]| -> Vec<Value> {
+            names
+                .iter()
+                .filter_map(|name| id_of(name))
+                .map(|to| {
+                    json!({
+                        "spdxElementId": from,
+                        "relationshipType": "DEPENDS_ON",
+                        "relatedSpdxElement": to,
+                    })
+                })
+                .collect()
+        };
+        relationships.extend(depends_on(&root, &self.depends_on));
+        for component in &self.components {
+            let id = spdx_id(&component.name, &component.version);
+            packages.push(json!({
+                "SPDXID": id,
+                "name": component.name,
+                "versionInfo": component.version,
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "packageFileName": component.path.display().to_string(),
+                "sourceInfo": format!("rez package repository {}", component.repository.display()),
+            }));
+            relationships.extend(depends_on(&id, &component.depends_on));
+        }
+
+        json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": format!("{}-{}", self.package, self.version),
+            "documentNamespace": format!(
+                "https://spdx.org/spdxdocs/rez-{}-{}-{}",
+                self.package, self.version, self.created
+            ),
+            "creationInfo": {
+                "created": self.created,
+                "creators": [format!("Tool: rez-lsp-server-{}", env!("CARGO_PKG_VERSION"))],
+            },
+            "packages": packages,
+            "relationships": relationships,
+        })
+    }
+
+    /// Render the manifest as CycloneDX JSON.
+    pub fn to_cyclonedx(&self) -> Value {
+        let root = bom_ref(&self.package, &self.version);
+        let refs_of = |names: &[String]| -> Vec<String> {
+            names
+                .iter()
+                .filter_map(|name| self.component(name))
+                .map(|component| bom_ref(&component.name, &component.version))
+                .collect()
+        };
+
+        let mut dependencies = vec![json!({
+            "ref": root,
+            "dependsOn": refs_of(&self.depends_on),
+        })];
+        let components: Vec<Value> = self
+            .components
+            .iter()
+            .map(|component| {
+                let reference = bom_ref(&component.name, &component.version);
+                dependencies.push(json!({
+                    "ref": reference,
+                    "dependsOn": refs_of(&component.depends_on),
+                }));
+                json!({
+                    "type": "library",
+                    "bom-ref": reference,
+                    "name": component.name,
+                    "version": component.version,
+                    "properties": [
+                        {"name": "rez:path", "value": component.path.display().to_string()},
+                        {"name": "rez:repository", "value": component.repository.display().to_string()},
+                    ],
+                })
+            })
+            .collect();
+
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "version": 1,
+            "metadata": {
+                "timestamp": self.created,
+                "tools": [{"name": "rez-lsp-server", "version": env!("CARGO_PKG_VERSION")}],
+                "component": {
+                    "type": "application",
+                    "bom-ref": root,
+                    "name": self.package,
+                    "version": self.version,
+                },
+            },
+            "components": components,
+            "dependencies": dependencies,
+        })
+    }
+
+    fn component(&self, name: &str) -> Option<&ManifestComponent> {
+        self.components
+            .iter()
+            .find(|component| component.name == name)
+    }
+}
+
+/// Get the repository of a package installed at `family/version`.
+fn repository_of(path: &Path) -> PathBuf {
+    path.parent()
+        .and_then(Path::parent)
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+/// Get the SPDX element id of a package, which only allows letters, digits,
+/// `.` and `-`.
+fn spdx_id(name: &str, version: &str) -> String {
+    let id: String = format!("{}-{}", name, version)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("SPDXRef-Package-{}", id)
+}
+
+/// Get the CycloneDX reference of a package.
+fn bom_ref(name: &str, version: &str) -> String {
+    format!("{}@{}", name, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::VersionConstraint;
+
+    fn manifest() -> DependencyManifest {
+        let python = Package::builder()
+            .name("python")
+            .version("3.11.4")
+            .path("/repo/python/3.11.4")
+            .build();
+        let numpy = Package::builder()
+            .name("numpy")
+            .version("1.26.0")
+            .path("/repo/numpy/1.26.0")
+            .require(Requirement::new("python", VersionConstraint::Any))
+            .build();
+        let context = ResolvedContext::builder().packages([python, numpy]).build();
+        let requires = [
+            Requirement::new("numpy", VersionConstraint::Any),
+            Requirement::builder("python").conflict(true).build(),
+        ];
+        DependencyManifest::new("my_tool", "1.0.0", &requires, &context)
+    }
+
+    #[test]
+    fn test_manifest_closure() {
+        let manifest = manifest();
+        assert_eq!(manifest.depends_on, ["numpy"]);
+        assert_eq!(manifest.components[0].name, "numpy");
+        assert_eq!(manifest.components[0].depends_on, ["python"]);
+        assert_eq!(manifest.components[0].repository, PathBuf::from("/repo"));
+        assert_eq!(
+            ManifestFormat::parse("CycloneDX"),
+            Some(ManifestFormat::CycloneDx)
+        );
+        assert_eq!(ManifestFormat::parse("xml"), None);
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let manifest = manifest();
+
+        let spdx = manifest.render(ManifestFormat::Spdx);
+        assert_eq!(spdx["packages"].as_array().unwrap().len(), 3);
+        assert!(spdx["relationships"].as_array().unwrap().contains(&json!({
+            "spdxElementId": "SPDXRef-Package-numpy-1.26.0",
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": "SPDXRef-Package-python-3.11.4",
+        })));
+
+        let bom = manifest.render(ManifestFormat::CycloneDx);
+        assert_eq!(bom["metadata"]["component"]["bom-ref"], "my_tool@1.0.0");
+        assert_eq!(bom["dependencies"][0]["dependsOn"], json!(["numpy@1.26.0"]));
+        assert_eq!(bom["components"][1]["properties"][1]["value"], "/repo");
+    }
+}
@@ -0,0 +1,16 @@
+//! Index-wide analysis of discovered packages.
+
+mod freshness;
+mod graph;
+mod manifest;
+mod stale;
+mod usage;
+
+pub use freshness::{FreshnessError, FreshnessReport, OutdatedRequirement, ReportFormat};
+pub use graph::{DependedOnFamily, GraphStats, MOST_DEPENDED_ON_LIMIT};
+pub use manifest::{DependencyManifest, ManifestComponent, ManifestFormat};
+pub use stale::{
+    load_environment, load_environments, EnvironmentLoadError, PinnedEnvironment, StaleEnvironment,
+    StaleEnvironmentReport, StalePackage, StaleReason,
+};
+pub use usage::{FamilyUsage, PackageUsageStats, UsageIndex};
@@ -0,0 +1,367 @@
+//! Audit of resolved environments against the package index.
+//!
+//! Resolved contexts (`.rxt`) and lock files pin exact package versions. Over
+//! time those versions get removed from the repositories or fall behind
+//! releases their original requests exclude. The audit reports both cases so
+//! teams can re-resolve or widen requests before the environments break.
+
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::{PackageIndex, Requirement, Version};
+use crate::discovery::WorkspaceWalker;
+
+/// Resolved package names and versions in resolve order.
+type PinnedPackages = Vec<(String, Version)>;
+
+/// A resolved environment read from a context or lock file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinnedEnvironment {
+    /// File the environment was read from
+    pub path: PathBuf,
+    /// Requests the environment was resolved from (empty for lock files)
+    pub requests: Vec<Requirement>,
+    /// Resolved package names and versions
+    pub packages: PinnedPackages,
+}
+
+/// Why a resolved package is stale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StaleReason {
+    /// The resolved version no longer exists in any repository
+    Missing,
+    /// A newer version exists that the original request excludes
+    Superseded,
+}
+
+impl std::fmt::Display for StaleReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "missing"),
+            Self::Superseded => write!(f, "superseded"),
+        }
+    }
+}
+
+/// A stale package of a resolved environment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StalePackage {
+    /// Package family
+    pub name: String,
+    /// Resolved version
+    pub version: String,
+    /// Why the package is stale
+    pub reason: StaleReason,
+    /// Latest indexed version of the family
+    pub latest: Option<String>,
+    /// Request the family was resolved from
+    pub request: Option<String>,
+}
+
+/// A resolved environment with stale packages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleEnvironment {
+    /// Context or lock file
+    pub path: PathBuf,
+    /// Stale packages in resolve order
+    pub packages: Vec<StalePackage>,
+}
+
+/// A context or lock file that could not be read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentLoadError {
+    /// Context or lock file
+    pub path: PathBuf,
+    /// Reason the file was skipped
+    pub message: String,
+}
+
+/// Result of auditing a directory of resolved environments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleEnvironmentReport {
+    /// Number of audited environments
+    pub audited: usize,
+    /// Environments with at least one stale package
+    pub stale: Vec<StaleEnvironment>,
+    /// Files that could not be read
+    pub errors: Vec<EnvironmentLoadError>,
+}
+
+impl StaleEnvironmentReport {
+    /// Audit resolved environments against the indexed packages.
+    pub fn audit(environments: &[PinnedEnvironment], packages: &PackageIndex) -> Self {
+        let stale = environments
+            .iter()
+            .filter_map(|environment| {
+                let packages = stale_packages(environment, packages);
+                (!packages.is_empty()).then(|| StaleEnvironment {
+                    path: environment.path.clone(),
+                    packages,
+                })
+            })
+            .collect();
+
+        Self {
+            audited: environments.len(),
+            stale,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Check whether any audited environment is stale.
+    pub fn has_stale(&self) -> bool {
+        !self.stale.is_empty()
+    }
+}
+
+/// Find the stale packages of a resolved environment.
+fn stale_packages(environment: &PinnedEnvironment, packages: &PackageIndex) -> Vec<StalePackage> {
+    environment
+        .packages
+        .iter()
+        .filter_map(|(name, version)| {
+            let versions = packages.get(name).map(Vec::as_slice).unwrap_or_default();
+            let latest = versions.iter().map(|p| &p.version).max();
+            let request = environment
+                .requests
+                .iter()
+                .find(|r| &r.name == name && !r.weak && !r.conflict);
+
+            let exists = versions
+                .iter()
+                .any(|p| p.version.cmp(version) == Ordering::Equal);
+            let reason = if !exists {
+                StaleReason::Missing
+            } else {
+                let latest = latest?;
+                let request = request?;
+                if latest > version && !request.constraint.satisfies(latest) {
+                    StaleReason::Superseded
+                } else {
+                    return None;
+                }
+            };
+
+            Some(StalePackage {
+                name: name.clone(),
+                version: version.to_string(),
+                reason,
+                latest: latest.map(|v| v.to_string()),
+                request: request.map(|r| r.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Read every context (`.rxt`) and lock (`.lock`) file below a directory.
+///
+/// Paths ignored by `walker` are skipped. Files that cannot be read are
+/// returned as errors instead of failing the whole audit.
+pub fn load_environments(
+    dir: &Path,
+    walker: &WorkspaceWalker,
+) -> (Vec<PinnedEnvironment>, Vec<EnvironmentLoadError>) {
+    let result = walker.files(dir, |path| {
+        matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("rxt" | "lock")
+        )
+    });
+    let mut errors: Vec<EnvironmentLoadError> = result
+        .errors
+        .into_iter()
+        .map(|(path, message)| EnvironmentLoadError { path, message })
+        .collect();
+
+    let mut environments = Vec::new();
+    for path in result.files {
+        match load_environment(&path) {
+            Ok(environment) => environments.push(environment),
+            Err(message) => errors.push(EnvironmentLoadError { path, message }),
+        }
+    }
+
+    (environments, errors)
+}
+
+/// Read a single context or lock file.
+pub fn load_environment(path: &Path) -> Result<PinnedEnvironment, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let (requests, packages) = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("rxt") => parse_context(&content)?,
+        _ => (Vec::new(), parse_lock(&content)),
+    };
+
+    Ok(PinnedEnvironment {
+        path: path.to_path_buf(),
+        requests,
+        packages,
+    })
+}
+
+/// Parse the requests and resolved packages of a serialized rez context.
+///
+/// Resolved packages are variant handles whose `variables` carry the package
+/// name and version; older contexts store the variables inline.
+fn parse_context(content: &str) -> Result<(Vec<Requirement>, PinnedPackages), String> {
+    let context: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| format!("Invalid context: {}", e))?;
+
+    let requests = context["package_requests"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|request| request.as_str())
+        .filter_map(|request| Requirement::parse(request).ok())
+        .collect();
+
+    let Some(resolved) = context["resolved_packages"].as_array() else {
+        return Err("Context has no resolved_packages".to_string());
+    };
+    let packages = resolved
+        .iter()
+        .filter_map(|handle| {
+            let variables = [&handle["handle"]["variables"], &handle["variables"], handle]
+                .into_iter()
+                .find(|value| value["name"].is_string())?;
+            let name = variables["name"].as_str()?;
+            let version = variables["version"].as_str().unwrap_or_default();
+            Some((name.to_string(), Version::new(version)))
+        })
+        .collect();
+
+    Ok((requests, packages))
+}
+
+/// Parse a lock file listing one pinned `name-version` or `name==version`
+/// per line.
+fn parse_lock(content: &str) -> PinnedPackages {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (name, version) = line
+                .split_once("==")
+                .or_else(|| line.split_once('-'))
+                .unwrap_or((line, ""));
+            (name.trim().to_string(), Version::new(version.trim()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Package;
+    use std::sync::Arc;
+
+    fn index(packages: &[(&str, &str)]) -> PackageIndex {
+        let mut index = PackageIndex::new();
+        for (name, version) in packages {
+            index.entry(name.to_string()).or_default().push(Arc::new(
+                Package::builder().name(*name).version(*version).build(),
+            ));
+        }
+        index
+    }
+
+    #[test]
+    fn test_parse_context() {
+        let content = r#"{
+            "package_requests": ["python-3.9+<3.10", "~maya"],
+            "resolved_packages": [
+                {"cls": "variant", "handle": {"variables": {"name": "python", "version": "3.9.7"}}},
+                {"variables": {"name": "maya", "version": "2024.1"}}
+            ]
+        }"#;
+
+        let (requests, packages) = parse_context(content).unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            packages,
+            vec![
+                ("python".to_string(), Version::new("3.9.7")),
+                ("maya".to_string(), Version::new("2024.1")),
+            ]
+        );
+        assert!(parse_context("{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_lock() {
+        let packages = parse_lock("# pinned\npython-3.9.7\nnumpy==1.24\n\nscratch\n");
+        assert_eq!(
+            packages,
+            vec![
+                ("python".to_string(), Version::new("3.9.7")),
+                ("numpy".to_string(), Version::new("1.24")),
+                ("scratch".to_string(), Version::new("")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audit_reports_missing_and_superseded() {
+        let environment = PinnedEnvironment {
+            path: PathBuf::from("/contexts/shot.rxt"),
+            requests: vec![
+                Requirement::parse("python-3.9+<3.10").unwrap(),
+                Requirement::parse("numpy").unwrap(),
+            ],
+            packages: vec![
+                ("python".to_string(), Version::new("3.9.7")),
+                ("numpy".to_string(), Version::new("1.24")),
+                ("maya".to_string(), Version::new("2022")),
+            ],
+        };
+        let packages = index(&[
+            ("python", "3.9.7"),
+            ("python", "3.11.4"),
+            ("numpy", "1.24"),
+            ("numpy", "1.26"),
+            ("maya", "2024"),
+        ]);
+
+        let report = StaleEnvironmentReport::audit(&[environment], &packages);
+        assert_eq!(report.audited, 1);
+        assert!(report.has_stale());
+
+        let stale = &report.stale[0].packages;
+        assert_eq!(stale.len(), 2);
+        assert_eq!(stale[0].name, "python");
+        assert_eq!(stale[0].reason, StaleReason::Superseded);
+        assert_eq!(stale[0].latest.as_deref(), Some("3.11.4"));
+        assert_eq!(stale[1].name, "maya");
+        assert_eq!(stale[1].reason, StaleReason::Missing);
+        assert!(stale[1].request.is_none());
+    }
+
+    #[test]
+    fn test_load_environments() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let nested = temp_dir.path().join("shots");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("env.lock"), "python-3.9.7\n").unwrap();
+        fs::write(temp_dir.path().join("broken.rxt"), "not json").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "python-3.9.7\n").unwrap();
+        let ignored = temp_dir.path().join("build");
+        fs::create_dir_all(&ignored).unwrap();
+        fs::write(ignored.join("env.lock"), "python-3.9.7\n").unwrap();
+        fs::write(temp_dir.path().join(".rezignore"), "build/\n").unwrap();
+
+        let (environments, errors) =
+            load_environments(temp_dir.path(), &WorkspaceWalker::default());
+        assert_eq!(environments.len(), 1);
+        assert_eq!(environments[0].path, nested.join("env.lock"));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, temp_dir.path().join("broken.rxt"));
+    }
+}
@@ -0,0 +1,252 @@
+//! Requirement usage analytics across the package index.
+//!
+//! Tracks how many packages depend on each family and which constraints they
+//! request. The index is updated one family at a time so rescans only pay for
+//! the families that changed.
+
+use serde::Serialize;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::core::Package;
+
+/// Usage statistics for a single package family.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FamilyUsage {
+    /// The required package family
+    pub family: String,
+    /// Number of indexed package versions that require this family
+    pub dependent_count: usize,
+    /// Number of package versions per dependent family
+    pub dependents: BTreeMap<String, usize>,
+    /// Distribution of requested constraints (`*` means any version)
+    pub constraints: BTreeMap<String, usize>,
+}
+
+/// Usage statistics across the whole index.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageUsageStats {
+    /// Number of families that contributed requirements
+    pub analyzed_families: usize,
+    /// Usage per required family, most depended-upon first
+    pub families: Vec<FamilyUsage>,
+}
+
+/// A single requirement contributed by an indexed package.
+#[derive(Debug, Clone)]
+struct RequirementUse {
+    family: String,
+    constraint: String,
+}
+
+/// Incrementally maintained requirement usage index.
+#[derive(Debug, Default)]
+pub struct UsageIndex {
+    /// Requirements contributed by each dependent family
+    contributions: HashMap<String, Vec<RequirementUse>>,
+    /// Aggregated usage per required family
+    usage: HashMap<String, FamilyUsage>,
+}
+
+impl UsageIndex {
+    /// Create an empty usage index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the requirements contributed by all versions of a family.
+    pub fn update_family<P: Borrow<Package>>(&mut self, family: &str, packages: &[P]) {
+        self.remove_family(family);
+
+        let uses: Vec<RequirementUse> = packages
+            .iter()
+            .flat_map(|package| package.borrow().requires.iter())
+            .filter(|req| !req.conflict && !req.is_ephemeral())
+            .map(|req| RequirementUse {
+                family: req.name.clone(),
+                constraint: req.constraint.to_string(),
+            })
+            .collect();
+
+        for requirement in &uses {
+            let usage = self
+                .usage
+                .entry(requirement.family.clone())
+                .or_insert_with(|| FamilyUsage {
+                    family: requirement.family.clone(),
+                    ..Default::default()
+                });
+            usage.dependent_count += 1;
+            *usage.dependents.entry(family.to_string()).or_default() += 1;
+            *usage
+                .constraints
+                .entry(requirement.constraint.clone())
+                .or_default() += 1;
+        }
+
+        if !uses.is_empty() {
+            self.contributions.insert(family.to_string(), uses);
+        }
+    }
+
+    /// Remove all requirements contributed by a family.
+    pub fn remove_family(&mut self, family: &str) {
+        let Some(uses) = self.contributions.remove(family) else {
+            return;
+        };
+
+        for requirement in uses {
+            let Some(usage) = self.usage.get_mut(&requirement.family) else {
+                continue;
+            };
+
+            usage.dependent_count -= 1;
+            decrement(&mut usage.dependents, family);
+            decrement(&mut usage.constraints, &requirement.constraint);
+
+            if usage.dependent_count == 0 {
+                self.usage.remove(&requirement.family);
+            }
+        }
+    }
+
+    /// Remove everything from the index.
+    pub fn clear(&mut self) {
+        self.contributions.clear();
+        self.usage.clear();
+    }
+
+    /// Get the requirement graph, mapping each family to the families its
+    /// versions require.
+    pub fn requirement_graph(&self) -> BTreeMap<String, BTreeSet<String>> {
+        self.contributions
+            .iter()
+            .map(|(family, uses)| {
+                let required = uses.iter().map(|r| r.family.clone()).collect();
+                (family.clone(), required)
+            })
+            .collect()
+    }
+
+    /// Get the number of families that contributed requirements.
+    pub fn analyzed_families(&self) -> usize {
+        self.contributions.len()
+    }
+
+    /// Get usage statistics for a single family.
+    pub fn family_usage(&self, family: &str) -> FamilyUsage {
+        self.usage
+            .get(family)
+            .cloned()
+            .unwrap_or_else(|| FamilyUsage {
+                family: family.to_string(),
+                ..Default::default()
+            })
+    }
+
+    /// Get usage statistics for all families, most depended-upon first.
+    pub fn stats(&self, limit: Option<usize>) -> PackageUsageStats {
+        let mut families: Vec<FamilyUsage> = self.usage.values().cloned().collect();
+        families.sort_by(|a, b| {
+            b.dependent_count
+                .cmp(&a.dependent_count)
+                .then_with(|| a.family.cmp(&b.family))
+        });
+        if let Some(limit) = limit {
+            families.truncate(limit);
+        }
+
+        PackageUsageStats {
+            analyzed_families: self.analyzed_families(),
+            families,
+        }
+    }
+}
+
+/// Decrement a counter, removing it once it reaches zero.
+fn decrement(counts: &mut BTreeMap<String, usize>, key: &str) {
+    if let Some(count) = counts.get_mut(key) {
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Requirement;
+
+    fn create_test_package(name: &str, version: &str, requires: &[&str]) -> Package {
+        Package::builder()
+            .name(name)
+            .version(version)
+            .requires(requires.iter().map(|r| Requirement::parse(r).unwrap()))
+            .path("/test")
+            .build()
+    }
+
+    #[test]
+    fn test_usage_aggregation() {
+        let mut index = UsageIndex::new();
+        index.update_family(
+            "maya_tools",
+            &[
+                create_test_package("maya_tools", "1.0", &["python-3.7+", "maya"]),
+                create_test_package("maya_tools", "2.0", &["python-3.9+", "!houdini"]),
+            ],
+        );
+        index.update_family(
+            "nuke_tools",
+            &[create_test_package("nuke_tools", "1.0", &["python-3.7+"])],
+        );
+
+        let python = index.family_usage("python");
+        assert_eq!(python.dependent_count, 3);
+        assert_eq!(python.dependents.get("maya_tools"), Some(&2));
+        assert_eq!(python.constraints.get("3.7+"), Some(&2));
+        assert_eq!(python.constraints.get("3.9+"), Some(&1));
+
+        // Conflict requirements are not dependencies
+        assert_eq!(index.family_usage("houdini").dependent_count, 0);
+
+        let stats = index.stats(Some(1));
+        assert_eq!(stats.analyzed_families, 2);
+        assert_eq!(stats.families.len(), 1);
+        assert_eq!(stats.families[0].family, "python");
+
+        let graph = index.requirement_graph();
+        assert_eq!(
+            graph["maya_tools"],
+            BTreeSet::from(["maya".to_string(), "python".to_string()])
+        );
+        assert_eq!(graph["nuke_tools"].len(), 1);
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        let mut index = UsageIndex::new();
+        index.update_family(
+            "maya_tools",
+            &[create_test_package(
+                "maya_tools",
+                "1.0",
+                &["python", "maya"],
+            )],
+        );
+
+        // Rescanning the family replaces its previous contributions
+        index.update_family(
+            "maya_tools",
+            &[create_test_package("maya_tools", "1.1", &["python"])],
+        );
+        assert_eq!(index.family_usage("python").dependent_count, 1);
+        assert_eq!(index.family_usage("maya").dependent_count, 0);
+
+        index.remove_family("maya_tools");
+        assert!(index.stats(None).families.is_empty());
+    }
+}
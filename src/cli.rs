@@ -0,0 +1,418 @@
+//! Output shared by the command-line subcommands.
+//!
+//! Every subcommand reports through an [`Output`], so they share the same
+//! switches and conventions:
+//!
+//! - `--json` prints the machine-readable report only; without it the report
+//!   is printed for people, whether or not stdout is a terminal
+//! - `--text` asks for the report for people explicitly, overriding
+//!   `--json`, for callers such as git hooks that must not depend on defaults
+//! - `--quiet` (`-q`) hides progress and informational lines, keeping
+//!   findings and errors
+//! - `--no-color` (or `NO_COLOR`) disables colors, which are otherwise used
+//!   when stdout is a terminal
+//! - exit codes are [`EXIT_OK`], [`EXIT_FAILURE`] and [`EXIT_FINDINGS`]
+//!
+//! Progress is drawn on stderr, and only when it is a terminal.
+
+use serde::Serialize;
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::task::AbortHandle;
+
+/// Exit code of a subcommand that completed without findings.
+pub const EXIT_OK: i32 = 0;
+
+/// Exit code of a subcommand that failed to run.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit code of a subcommand that found something to act on, e.g. outdated
+/// requirements or files to fix.
+pub const EXIT_FINDINGS: i32 = 2;
+
+/// Width of progress bars in characters.
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// Interval between redraws of spinners.
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Frames of spinners.
+const SPINNER_FRAMES: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Style of colored text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Successful outcome
+    Success,
+    /// Outcome needing attention
+    Warning,
+    /// Failed outcome
+    Error,
+    /// Headings and table headers
+    Heading,
+}
+
+impl Style {
+    fn ansi_code(self) -> &'static str {
+        match self {
+            Self::Success => "32",
+            Self::Warning => "33",
+            Self::Error => "31",
+            Self::Heading => "1",
+        }
+    }
+}
+
+/// Reporter of a subcommand's output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Output {
+    json: bool,
+    quiet: bool,
+    color: bool,
+    progress: bool,
+}
+
+impl Output {
+    /// Take the output switches out of the command-line arguments.
+    pub fn from_args(args: &mut Vec<String>) -> Self {
+        let mut take = |names: &[&str]| {
+            let before = args.len();
+            args.retain(|arg| !names.contains(&arg.as_str()));
+            args.len() != before
+        };
+        let json = take(&["--json"]);
+        let text = take(&["--text"]);
+        let quiet = take(&["--quiet", "-q"]);
+        let no_color = take(&["--no-color"]);
+
+        let stdout_terminal = std::io::stdout().is_terminal();
+        Self {
+            json: json && !text,
+            quiet,
+            color: stdout_terminal && !no_color && std::env::var_os("NO_COLOR").is_none(),
+            progress: !quiet && std::io::stderr().is_terminal(),
+        }
+    }
+
+    /// Check whether the report is printed as JSON.
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+
+    /// Color text in `style`, if colors are enabled.
+    pub fn paint(&self, text: &str, style: Style) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style.ansi_code(), text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Print an informational line, unless quiet or printing JSON.
+    pub fn info(&self, text: &str) {
+        if !self.quiet && !self.json {
+            println!("{}", text);
+        }
+    }
+
+    /// Print an outcome marked with `style`, unless quiet or printing JSON.
+    pub fn status(&self, style: Style, text: &str) {
+        self.info(&self.marked(style, text));
+    }
+
+    /// Print a finding marked with `style`, unless printing JSON.
+    ///
+    /// Findings are what the subcommand reports on, e.g. issues or files
+    /// that failed to read, so unlike [`status`](Self::status) lines they
+    /// are kept by `--quiet`.
+    pub fn finding(&self, style: Style, text: &str) {
+        if let Some(line) = self.finding_line(style, text) {
+            println!("{}", line);
+        }
+    }
+
+    fn finding_line(&self, style: Style, text: &str) -> Option<String> {
+        (!self.json).then(|| self.marked(style, text))
+    }
+
+    fn marked(&self, style: Style, text: &str) -> String {
+        let marker = match style {
+            Style::Success => "✔",
+            Style::Warning => "⚠",
+            Style::Error => "✖",
+            Style::Heading => "•",
+        };
+        format!("{} {}", self.paint(marker, style), text)
+    }
+
+    /// Print an error on stderr and get [`EXIT_FAILURE`].
+    pub fn error(&self, text: &str) -> i32 {
+        if self.color && std::io::stderr().is_terminal() {
+            eprintln!("{} {}", self.paint("error:", Style::Error), text);
+        } else {
+            eprintln!("error: {}", text);
+        }
+        EXIT_FAILURE
+    }
+
+    /// Print a report as pretty JSON, getting [`EXIT_FAILURE`] if it cannot
+    /// be serialized.
+    pub fn print_json(&self, report: &impl Serialize) -> Result<(), i32> {
+        match serde_json::to_string_pretty(report) {
+            Ok(json) => {
+                println!("{}", json);
+                Ok(())
+            }
+            Err(e) => Err(self.error(&format!("Failed to serialize report: {}", e))),
+        }
+    }
+
+    /// Print a table with aligned columns, unless printing JSON.
+    pub fn table(&self, headers: &[&str], rows: &[Vec<String>]) {
+        if self.json {
+            return;
+        }
+        print!("{}", self.render_table(headers, rows));
+    }
+
+    fn render_table(&self, headers: &[&str], rows: &[Vec<String>]) -> String {
+        let mut widths: Vec<usize> = headers
+            .iter()
+            .map(|header| header.chars().count())
+            .collect();
+        for row in rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let line = |cells: Vec<String>, style: Option<Style>| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            let text = padded.join("  ").trim_end().to_string();
+            match style {
+                Some(style) => format!("{}\n", self.paint(&text, style)),
+                None => format!("{}\n", text),
+            }
+        };
+
+        let mut table = line(
+            headers.iter().map(|header| header.to_string()).collect(),
+            Some(Style::Heading),
+        );
+        for row in rows {
+            table.push_str(&line(row.clone(), None));
+        }
+        table
+    }
+
+    /// Start a progress bar of `total` steps, or a spinner when the total is
+    /// unknown.
+    ///
+    /// Nothing is drawn when quiet or when stderr is not a terminal.
+    pub fn progress(&self, label: &str, total: Option<usize>) -> Progress {
+        let progress = Progress {
+            state: Arc::new(Mutex::new(ProgressState {
+                label: label.to_string(),
+                total,
+                done: 0,
+                frame: 0,
+                started: Instant::now(),
+                finished: false,
+                spinner: None,
+            })),
+            visible: self.progress,
+        };
+        if progress.visible && total.is_none() {
+            // The spinner stops when the progress is finished or dropped
+            let state = Arc::downgrade(&progress.state);
+            let spinner = tokio::spawn(spin(state)).abort_handle();
+            progress.lock().spinner = Some(spinner);
+        }
+        progress
+    }
+}
+
+/// Redraw a spinner until its progress is finished or dropped.
+async fn spin(state: Weak<Mutex<ProgressState>>) {
+    let mut interval = tokio::time::interval(SPINNER_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(state) = state.upgrade() else {
+            break;
+        };
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.finished {
+            break;
+        }
+        state.frame += 1;
+        state.draw();
+    }
+}
+
+/// Progress of a long step, drawn on stderr.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    state: Arc<Mutex<ProgressState>>,
+    visible: bool,
+}
+
+#[derive(Debug)]
+struct ProgressState {
+    label: String,
+    total: Option<usize>,
+    done: usize,
+    frame: usize,
+    started: Instant,
+    finished: bool,
+    spinner: Option<AbortHandle>,
+}
+
+impl Drop for ProgressState {
+    fn drop(&mut self) {
+        if let Some(spinner) = self.spinner.take() {
+            spinner.abort();
+        }
+    }
+}
+
+impl ProgressState {
+    fn line(&self) -> String {
+        match self.total {
+            Some(total) => {
+                let filled = (self.done * PROGRESS_BAR_WIDTH)
+                    .checked_div(total)
+                    .unwrap_or(PROGRESS_BAR_WIDTH)
+                    .min(PROGRESS_BAR_WIDTH);
+                format!(
+                    "{} [{}{}] {}/{}",
+                    self.label,
+                    "#".repeat(filled),
+                    "-".repeat(PROGRESS_BAR_WIDTH - filled),
+                    self.done,
+                    total
+                )
+            }
+            None => format!(
+                "{} {} ({:.1}s)",
+                SPINNER_FRAMES[self.frame % SPINNER_FRAMES.len()],
+                self.label,
+                self.started.elapsed().as_secs_f32()
+            ),
+        }
+    }
+
+    fn draw(&self) {
+        let mut stderr = std::io::stderr();
+        let _ = write!(stderr, "\r\x1b[2K{}", self.line());
+        let _ = stderr.flush();
+    }
+}
+
+impl Progress {
+    fn lock(&self) -> std::sync::MutexGuard<'_, ProgressState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Advance the progress by one step.
+    pub fn inc(&self) {
+        let mut state = self.lock();
+        state.done += 1;
+        if self.visible {
+            state.draw();
+        }
+    }
+
+    /// Stop the progress and clear its line.
+    pub fn finish(&self) {
+        let mut state = self.lock();
+        state.finished = true;
+        if let Some(spinner) = state.spinner.take() {
+            spinner.abort();
+        }
+        if self.visible {
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(color: bool) -> Output {
+        Output {
+            json: false,
+            quiet: false,
+            color,
+            progress: false,
+        }
+    }
+
+    #[test]
+    fn test_switches_are_taken_from_args() {
+        let mut args: Vec<String> = ["--repo", "/repo", "-q", "--json", "--no-color"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let output = Output::from_args(&mut args);
+        assert_eq!(args, ["--repo", "/repo"]);
+        assert!(output.is_json() && output.quiet && !output.color && !output.progress);
+
+        // JSON is opt-in, even when stdout is not a terminal
+        let output = Output::from_args(&mut Vec::new());
+        assert!(!output.is_json());
+        let mut args = vec!["--json".to_string(), "--text".to_string()];
+        assert!(!Output::from_args(&mut args).is_json());
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn test_quiet_keeps_findings() {
+        let quiet = Output {
+            quiet: true,
+            ..output(false)
+        };
+        assert_eq!(
+            quiet.finding_line(Style::Error, "package.py:1:1: E001 Syntax error"),
+            Some("✖ package.py:1:1: E001 Syntax error".to_string())
+        );
+
+        let json = Output {
+            json: true,
+            ..output(false)
+        };
+        assert_eq!(json.finding_line(Style::Error, "package.py"), None);
+    }
+
+    #[test]
+    fn test_render_table() {
+        let rows = vec![
+            vec!["maya_tools".to_string(), "python-3.9".to_string()],
+            vec!["nuke".to_string(), "maya".to_string()],
+        ];
+        assert_eq!(
+            output(false).render_table(&["PACKAGE", "REQUIREMENT"], &rows),
+            "PACKAGE     REQUIREMENT\nmaya_tools  python-3.9\nnuke        maya\n"
+        );
+        assert!(output(true)
+            .render_table(&["PACKAGE"], &[])
+            .starts_with("\x1b[1mPACKAGE"));
+    }
+
+    #[test]
+    fn test_progress_line() {
+        let progress = output(false).progress("Fixing", Some(4));
+        progress.inc();
+        let line = progress.lock().line();
+        assert_eq!(
+            line,
+            format!("Fixing [{}{}] 1/4", "#".repeat(7), "-".repeat(23))
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Expansion of environment variables in configured paths.
+//!
+//! Package path entries may reference variables as `${STUDIO_ROOT}` or
+//! `$STUDIO_ROOT`, and start with `~` for the home directory. They are
+//! expanded when the configuration is loaded, so a studio can share one
+//! setting across sites.
+
+use crate::core::{ConfigError, Result};
+use std::path::PathBuf;
+
+/// Expand the variables of a path `entry` read from `source`, e.g.
+/// `REZ_PACKAGES_PATH`.
+///
+/// Fails with [`ConfigError::UnexpandedVariable`] naming the variable and
+/// the entry when a variable is not set.
+pub fn expand_path(entry: &str, source: &str) -> Result<PathBuf> {
+    let home = dirs::home_dir();
+    let expanded =
+        expand_with(entry, |name| std::env::var(name).ok()).map_err(|problem| match problem {
+            Expansion::Unset(variable) => ConfigError::UnexpandedVariable {
+                variable,
+                entry: entry.to_string(),
+                source: source.to_string(),
+            },
+            Expansion::Unterminated => ConfigError::InvalidPath(format!(
+                "unterminated '${{' in {} entry '{}'",
+                source, entry
+            )),
+        })?;
+
+    match (expanded.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            Ok(home.join(rest.trim_start_matches(['/', '\\'])))
+        }
+        _ => Ok(PathBuf::from(expanded)),
+    }
+}
+
+/// Why an entry could not be expanded.
+#[derive(Debug, PartialEq, Eq)]
+enum Expansion {
+    /// The named variable is not set
+    Unset(String),
+    /// A `${` has no closing brace
+    Unterminated,
+}
+
+/// Expand `${NAME}` and `$NAME` in `entry` with the values of `lookup`.
+///
+/// A `$` not followed by a name or a brace is kept as is.
+fn expand_with(
+    entry: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> std::result::Result<String, Expansion> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::with_capacity(entry.len());
+    let mut rest = entry;
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, remainder) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}').ok_or(Expansion::Unterminated)?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+
+        if name.is_empty() {
+            expanded.push('$');
+            rest = after;
+            continue;
+        }
+        expanded.push_str(&lookup(name).ok_or_else(|| Expansion::Unset(name.to_string()))?);
+        rest = remainder;
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        (name == "STUDIO_ROOT").then(|| "/studio".to_string())
+    }
+
+    #[test]
+    fn test_expand_variables() {
+        assert_eq!(
+            expand_with("${STUDIO_ROOT}/packages", lookup).unwrap(),
+            "/studio/packages"
+        );
+        assert_eq!(
+            expand_with("$STUDIO_ROOT/ext:$", lookup).unwrap(),
+            "/studio/ext:$"
+        );
+        assert_eq!(
+            expand_with("${SITE}/packages", lookup),
+            Err(Expansion::Unset("SITE".to_string()))
+        );
+        assert_eq!(
+            expand_with("${STUDIO_ROOT/packages", lookup),
+            Err(Expansion::Unterminated)
+        );
+    }
+
+    #[test]
+    fn test_expand_path_reports_variable_and_entry() {
+        let error = expand_path("${REZ_LSP_TEST_UNSET_ROOT}/packages", "rezLsp.packagePaths")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("REZ_LSP_TEST_UNSET_ROOT"));
+        assert!(error.contains("'${REZ_LSP_TEST_UNSET_ROOT}/packages'"));
+        assert!(error.contains("rezLsp.packagePaths"));
+    }
+}
@@ -1,12 +1,115 @@
 //! Configuration management for the Rez LSP server.
 
+mod expand;
 mod provider;
 
+pub use expand::expand_path;
+
 pub use provider::RezConfigProvider;
 
-use crate::core::{ConfigError, Result};
+use crate::core::{
+    ConfigError, KnownFamilies, Locale, Package, PackageCategory, Result, VersionConstraint,
+    VersionPolicy,
+};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+/// Characters triggering completion when no others are configured.
+pub const DEFAULT_COMPLETION_TRIGGER_CHARACTERS: &[&str] = &["\"", "'", "-", ".", "{", "/"];
+
+/// Solver used to resolve requirements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResolverBackend {
+    /// Built-in solver working on the package index
+    #[default]
+    Native,
+    /// The `rez-env` binary, for exact parity with production rez
+    RezBinary,
+}
+
+/// Order in which the versions satisfying a requirement are picked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResolveStrategy {
+    /// Pick the highest satisfying version, as rez does
+    #[default]
+    Latest,
+    /// Pick the lowest satisfying version, to check that the lower bounds of
+    /// requirements still hold
+    Earliest,
+}
+
+impl ResolveStrategy {
+    /// Pick a version among the candidates satisfying a requirement.
+    ///
+    /// Candidates matching `preferred` win over the others, whatever the
+    /// strategy; the strategy then orders the remaining choice.
+    pub fn pick<'a>(
+        self,
+        candidates: impl IntoIterator<Item = &'a Package>,
+        preferred: Option<&VersionConstraint>,
+    ) -> Option<&'a Package> {
+        let rank = |package: &Package| {
+            preferred.is_some_and(|preferred| preferred.satisfies(&package.version))
+        };
+        candidates.into_iter().max_by(|a, b| {
+            let order = match self {
+                Self::Latest => a.version.cmp(&b.version),
+                Self::Earliest => b.version.cmp(&a.version),
+            };
+            rank(a).cmp(&rank(b)).then(order)
+        })
+    }
+}
+
+/// How package.py files are read during discovery.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PackageEvaluation {
+    /// Parse attributes statically without running any Python
+    #[default]
+    Static,
+    /// Execute package.py in a restricted Python worker (requires the
+    /// `python-eval` feature), falling back to static parsing on failure.
+    /// The restrictions are not a security boundary.
+    Sandbox,
+}
+
+/// Where on-type formatting puts a comma after the last entry of a
+/// requirement list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailingComma {
+    /// Always end lists with a comma
+    Always,
+    /// End lists spanning several lines with a comma
+    #[default]
+    Multiline,
+    /// Never end lists with a comma
+    Never,
+}
+
+/// A directory seen under different paths by the server and the client, e.g.
+/// a package repository mounted into a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathMapping {
+    /// Directory on the filesystem of the server
+    pub server: PathBuf,
+    /// The same directory as the client sees it, e.g. `C:\studio\packages`
+    pub client: String,
+}
+
+/// Placeholders of [`Config::completion_detail`] templates.
+pub const COMPLETION_DETAIL_PLACEHOLDERS: &[&str] = &["latest", "count", "repo", "description"];
+
+/// Get the `{placeholder}` names of a template that are not known
+/// completion detail placeholders.
+pub fn unknown_detail_placeholders(template: &str) -> Vec<&str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+        .filter(|name| !COMPLETION_DETAIL_PLACEHOLDERS.contains(name))
+        .collect()
+}
+
 /// Configuration for the Rez LSP server.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -22,6 +125,95 @@ pub struct Config {
     pub cache_expiration_secs: u64,
     /// Enable debug logging
     pub debug_logging: bool,
+    /// Map Python imports to packages of the server's rez-resolved environment
+    pub resolve_introspection: bool,
+    /// Record profiling sessions that can be exported as Chrome traces
+    pub enable_profiling: bool,
+    /// Seconds to wait for the initial package scan before running degraded
+    pub scan_timeout_secs: u64,
+    /// Seconds between background retries of a failed package scan
+    pub scan_retry_interval_secs: u64,
+    /// Keep only the newest N versions of each package family in the index
+    pub max_versions_per_family: Option<usize>,
+    /// Renamed package families, mapping old names to their canonical name
+    pub package_aliases: HashMap<String, String>,
+    /// Build or runtime category of families, overriding the heuristics that
+    /// rank package completions
+    pub package_categories: HashMap<String, PackageCategory>,
+    /// Variants the native resolver tries in one resolve before giving up
+    pub max_variants: usize,
+    /// Variants of a single package the native resolver tries, in order
+    pub max_variant_fanout: usize,
+    /// Program running package tests, invoked as `<runner> <package> <test>`
+    pub test_runner: String,
+    /// Solver used to resolve requirements
+    pub resolver_backend: ResolverBackend,
+    /// Path of the `rez-env` binary used by [`ResolverBackend::RezBinary`]
+    pub rez_env_path: PathBuf,
+    /// Order in which the native resolver, inlay hints and hovers pick
+    /// satisfying versions
+    pub resolve_strategy: ResolveStrategy,
+    /// Preferred version range of families, picked over newer (or older)
+    /// satisfying versions
+    pub preferred_versions: HashMap<String, VersionConstraint>,
+    /// Version schemes enforced per package repository
+    pub version_policy: VersionPolicy,
+    /// How package.py files are read during discovery
+    pub package_evaluation: PackageEvaluation,
+    /// Python interpreter used by [`PackageEvaluation::Sandbox`]
+    pub python_path: PathBuf,
+    /// Allow the language server features executing or evaluating package
+    /// content, such as [`PackageEvaluation::Sandbox`], the
+    /// [`ResolverBackend::RezBinary`] backend and running package tests; the
+    /// server keeps the workspace in restricted mode otherwise
+    pub trust_workspace: bool,
+    /// Seconds a sandboxed package evaluation may take
+    pub evaluation_timeout_secs: u64,
+    /// Gitignore-style patterns excluded from workspace traversals, on top of
+    /// `.gitignore` and `.rezignore` files
+    pub scan_exclude: Vec<String>,
+    /// Gitignore-style filename patterns of package definitions, on top of
+    /// `package.py`, e.g. `package.py.in` templates
+    pub package_file_patterns: Vec<String>,
+    /// Show the installed payload size of required packages on hover
+    pub hover_payload_stats: bool,
+    /// Explain the constraint of hovered requirements in plain language
+    pub hover_explain: bool,
+    /// Language of generated explanations
+    pub locale: Locale,
+    /// Maximum number of files counted for a payload size
+    pub payload_max_files: usize,
+    /// Template of the detail line of package name completions, with
+    /// `{latest}`, `{count}`, `{repo}` and `{description}` placeholders
+    pub completion_detail: Option<String>,
+    /// Leave pre-release versions out of the latest version shown by
+    /// completion and hover, like a rez package filter
+    pub exclude_prereleases: bool,
+    /// Comma after the last entry of requirement lists formatted on type
+    pub trailing_comma: TrailingComma,
+    /// Directory of the package index shared between server instances
+    pub shared_index_dir: Option<PathBuf>,
+    /// Seconds a shared package index is reused before it is rebuilt
+    pub shared_index_max_age_secs: u64,
+    /// Directories mapped between server paths and client URIs
+    pub path_mappings: Vec<PathMapping>,
+    /// File or `http://` URL of the advisory list of deprecated or banned
+    /// package versions
+    pub advisories: Option<String>,
+    /// Prometheus textfile the metrics are periodically written to
+    pub metrics_textfile: Option<PathBuf>,
+    /// Seconds between two writes of the metrics textfile
+    pub metrics_textfile_interval_secs: u64,
+    /// Characters that trigger completion as they are typed
+    pub completion_trigger_characters: Vec<String>,
+    /// Point definition targets at read-only `rezpkg://` virtual documents
+    /// served by the server instead of repository files
+    pub virtual_documents: bool,
+    /// Restore the diagnostics of documents reopened after a server restart
+    pub restore_session: bool,
+    /// Descriptions of well-known families shown when package definitions
+    /// carry little metadata
+    pub known_families: KnownFamilies,
 }
 
 impl Config {
@@ -34,6 +226,47 @@ impl Config {
             max_cache_size: 10000,
             cache_expiration_secs: 3600, // 1 hour
             debug_logging: false,
+            resolve_introspection: false,
+            enable_profiling: false,
+            scan_timeout_secs: 30,
+            scan_retry_interval_secs: 60,
+            max_versions_per_family: None,
+            package_aliases: HashMap::new(),
+            package_categories: HashMap::new(),
+            max_variants: 1000,
+            max_variant_fanout: 16,
+            test_runner: "rez-test".to_string(),
+            resolver_backend: ResolverBackend::Native,
+            rez_env_path: PathBuf::from("rez-env"),
+            resolve_strategy: ResolveStrategy::Latest,
+            preferred_versions: HashMap::new(),
+            version_policy: VersionPolicy::default(),
+            package_evaluation: PackageEvaluation::Static,
+            trust_workspace: false,
+            python_path: PathBuf::from("python3"),
+            evaluation_timeout_secs: 5,
+            scan_exclude: Vec::new(),
+            package_file_patterns: Vec::new(),
+            hover_payload_stats: false,
+            hover_explain: false,
+            locale: Locale::English,
+            payload_max_files: 10000,
+            completion_detail: None,
+            exclude_prereleases: false,
+            trailing_comma: TrailingComma::Multiline,
+            shared_index_dir: None,
+            shared_index_max_age_secs: 300,
+            path_mappings: Vec::new(),
+            advisories: None,
+            metrics_textfile: None,
+            metrics_textfile_interval_secs: 15,
+            completion_trigger_characters: DEFAULT_COMPLETION_TRIGGER_CHARACTERS
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+            virtual_documents: false,
+            restore_session: true,
+            known_families: KnownFamilies::bundled(),
         }
     }
 
@@ -57,6 +290,14 @@ impl Config {
         Ok(())
     }
 
+    /// Get the canonical family name of a possibly aliased package name.
+    pub fn canonical_package_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.package_aliases
+            .get(name)
+            .map(String::as_str)
+            .unwrap_or(name)
+    }
+
     /// Get all package search paths in priority order.
     pub fn get_all_package_paths(&self) -> Vec<PathBuf> {
         let mut all_paths = Vec::new();
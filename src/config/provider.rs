@@ -1,17 +1,25 @@
 //! Configuration provider implementation.
 
 use async_trait::async_trait;
+use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
 use tracing::{debug, info, warn};
 
-use super::Config;
-use crate::core::{ConfigError, ConfigProvider, Result};
+use super::{
+    expand_path, unknown_detail_placeholders, Config, PackageEvaluation, PathMapping,
+    ResolveStrategy, ResolverBackend, TrailingComma, DEFAULT_COMPLETION_TRIGGER_CHARACTERS,
+};
+use crate::core::{
+    ConfigError, ConfigProvider, KnownFamilies, Locale, PackageCategory, Result, VersionConstraint,
+    VersionPolicy, VersionScheme,
+};
 
 /// Implementation of ConfigProvider that reads from environment variables.
 #[derive(Debug)]
 pub struct RezConfigProvider {
     config: Config,
+    package_paths_override: Option<Vec<String>>,
 }
 
 impl RezConfigProvider {
@@ -19,16 +27,106 @@ impl RezConfigProvider {
     pub fn new() -> Self {
         Self {
             config: Config::new(),
+            package_paths_override: None,
         }
     }
 
+    /// Use the `rezLsp.packagePaths` setting instead of
+    /// `REZ_LSP_PACKAGE_PATHS`, once the client sent it.
+    pub fn set_package_paths(&mut self, paths: Vec<String>) {
+        self.package_paths_override = Some(paths);
+    }
+
     /// Load configuration from environment variables.
     ///
     /// This method reads Rez configuration from standard environment variables:
     /// - `REZ_PACKAGES_PATH`: Colon/semicolon-separated list of package directories
+    /// - `REZ_LSP_PACKAGE_PATHS`: Additional package directories, separated
+    ///   like `REZ_PACKAGES_PATH`
     /// - `REZ_LOCAL_PACKAGES_PATH`: Local packages directory (highest priority)
     /// - `REZ_RELEASE_PACKAGES_PATH`: Release packages directory (lowest priority)
     /// - `REZ_LSP_DEBUG`: Enable debug logging (true/1)
+    /// - `REZ_LSP_RESOLVE_INTROSPECTION`: Map imports to packages of the
+    ///   resolved environment the server runs in (true/1)
+    /// - `REZ_LSP_PROFILE`: Record profiling sessions for `rez/exportTrace` (true/1)
+    /// - `REZ_LSP_MAX_VERSIONS_PER_FAMILY`: Index only the newest N versions of
+    ///   each package family (unlimited when unset or 0)
+    /// - `REZ_LSP_PACKAGE_ALIASES`: Comma-separated `old=new` renames of
+    ///   package families, e.g. `usd=openusd`
+    /// - `REZ_LSP_PACKAGE_CATEGORIES`: Comma-separated `family=build` or
+    ///   `family=runtime` categories ranking package completions, e.g.
+    ///   `sphinx=build`
+    /// - `REZ_LSP_MAX_VARIANTS`: Variants the native resolver tries in one
+    ///   resolve before giving up (default 1000)
+    /// - `REZ_LSP_MAX_VARIANT_FANOUT`: Variants of a single package the
+    ///   native resolver tries (default 16)
+    /// - `REZ_LSP_TEST_RUNNER`: Program running package tests as
+    ///   `<runner> <package> <test>` (default `rez-test`)
+    /// - `REZ_LSP_RESOLVER`: Resolver backend, `native` (default) or `rez` to
+    ///   shell out to `rez-env`
+    /// - `REZ_LSP_REZ_ENV`: Path of the `rez-env` binary (default `rez-env`)
+    /// - `REZ_LSP_RESOLVE_STRATEGY`: Satisfying version picked by the native
+    ///   resolver, inlay hints and hovers, `latest` (default) or `earliest`
+    /// - `REZ_LSP_PREFERRED_VERSIONS`: Comma-separated `family=range`
+    ///   versions picked over the others when they satisfy a requirement,
+    ///   e.g. `maya=2023,python=3.9`
+    /// - `REZ_LSP_VERSION_SCHEME`: Default version scheme, `rez` (default),
+    ///   `semver` or `calver`
+    /// - `REZ_LSP_VERSION_SCHEMES`: Comma-separated `repository=scheme`
+    ///   overrides, e.g. `/studio/config=calver`
+    /// - `REZ_LSP_EVALUATION`: How package.py files are read, `static`
+    ///   (default) or `sandbox` to execute them in a restricted Python worker
+    ///   (requires the `python-eval` feature; `rezLsp.evaluation`)
+    /// - `REZ_LSP_TRUST_WORKSPACE`: Trust the workspace to evaluate package
+    ///   definitions and run package tests (true/1, default restricted)
+    /// - `REZ_LSP_PYTHON`: Python interpreter of the sandbox (default `python3`)
+    /// - `REZ_LSP_EVALUATION_TIMEOUT`: Seconds a sandboxed evaluation may take
+    ///   (default 5)
+    /// - `REZ_LSP_SCAN_EXCLUDE`: Comma-separated gitignore-style patterns
+    ///   excluded from workspace traversals, e.g. `build/,*.venv`
+    /// - `REZ_LSP_PACKAGE_FILE_PATTERNS`: Comma-separated filename patterns of
+    ///   package definitions besides `package.py`, e.g. `package.py.in`
+    /// - `REZ_LSP_HOVER_PAYLOAD`: Show the installed payload size and file
+    ///   count of required packages on hover (true/1)
+    /// - `REZ_LSP_PAYLOAD_MAX_FILES`: Files counted before a payload size is
+    ///   reported as a lower bound (default 10000)
+    /// - `REZ_LSP_HOVER_EXPLAIN`: Explain the constraint of hovered
+    ///   requirements in plain language (true/1)
+    /// - `REZ_LSP_LOCALE`: Language tag of explanations, e.g. `zh-cn`
+    ///   (default English)
+    /// - `REZ_LSP_COMPLETION_DETAIL`: Template of the detail line of package
+    ///   name completions, e.g. `{count} versions · {repo}`
+    /// - `REZ_LSP_EXCLUDE_PRERELEASES`: Leave alpha/beta/rc versions out of the
+    ///   latest version shown by completion and hover (default false)
+    /// - `REZ_LSP_TRAILING_COMMA`: Comma after the last entry of requirement
+    ///   lists formatted on type, `always`, `multiline` (default) or `never`
+    /// - `REZ_LSP_SHARED_INDEX`: Share the package index with other server
+    ///   instances, `true`/`1` for the user cache directory or a directory path
+    /// - `REZ_LSP_SHARED_INDEX_MAX_AGE`: Seconds a shared index is reused
+    ///   before it is rebuilt (default 300)
+    /// - `REZ_LSP_PATH_MAPPINGS`: Comma-separated `server=client` directories
+    ///   mapped between server paths and client URIs in remote setups, e.g.
+    ///   `/packages=C:\studio\packages`
+    /// - `REZ_LSP_ADVISORIES`: File or `http://` URL of a JSON list of
+    ///   deprecated or banned package versions flagged in requirements
+    /// - `REZ_LSP_METRICS_TEXTFILE`: Prometheus textfile the metrics are
+    ///   periodically written to, for the node_exporter textfile collector
+    /// - `REZ_LSP_METRICS_TEXTFILE_INTERVAL`: Seconds between two writes of
+    ///   the metrics textfile (default 15)
+    /// - `REZ_LSP_COMPLETION_TRIGGER_CHARACTERS`: Characters triggering
+    ///   completion, e.g. `"'-.[,` to also complete unquoted requirements
+    ///   after `[` and `,` (default `"'-.{/`)
+    /// - `REZ_LSP_VIRTUAL_DOCUMENTS`: Open definition targets as read-only
+    ///   `rezpkg://family/version/package.py` documents served by the server
+    ///   (true/1)
+    /// - `REZ_LSP_RESTORE_SESSION`: Save the diagnostics of open documents to
+    ///   the user cache directory and restore them after a restart (default
+    ///   true)
+    /// - `REZ_LSP_KNOWN_FAMILIES`: JSON object describing package families,
+    ///   replacing or extending the bundled descriptions of well-known ones
+    ///
+    /// Package directories may reference environment variables as `${VAR}`
+    /// or `$VAR`; loading fails when one of them is not set.
     ///
     /// # Errors
     ///
@@ -40,6 +138,67 @@ impl RezConfigProvider {
         self.config.local_packages_path = self.get_local_packages_path_from_env().await?;
         self.config.release_packages_path = self.get_release_packages_path_from_env().await?;
         self.config.debug_logging = self.get_debug_logging_from_env().await;
+        self.config.resolve_introspection = self.get_resolve_introspection_from_env().await;
+        self.config.enable_profiling = self.get_enable_profiling_from_env().await;
+        self.config.max_versions_per_family = self.get_max_versions_per_family_from_env().await;
+        self.config.package_aliases = self.get_package_aliases_from_env().await;
+        self.config.package_categories = self.get_package_categories_from_env().await;
+        if let Some(max) = self
+            .get_variant_limit_from_env("REZ_LSP_MAX_VARIANTS")
+            .await
+        {
+            self.config.max_variants = max;
+        }
+        if let Some(max) = self
+            .get_variant_limit_from_env("REZ_LSP_MAX_VARIANT_FANOUT")
+            .await
+        {
+            self.config.max_variant_fanout = max;
+        }
+        if let Some(runner) = self.get_test_runner_from_env().await {
+            self.config.test_runner = runner;
+        }
+        self.config.resolver_backend = self.get_resolver_backend_from_env().await;
+        if let Some(path) = self.get_rez_env_path_from_env().await {
+            self.config.rez_env_path = path;
+        }
+        self.config.resolve_strategy = self.get_resolve_strategy_from_env().await;
+        self.config.preferred_versions = self.get_preferred_versions_from_env().await;
+        self.config.version_policy = self.get_version_policy_from_env().await;
+        self.config.package_evaluation = self.get_package_evaluation_from_env().await;
+        self.config.trust_workspace = self.get_trust_workspace_from_env().await;
+        if let Some(path) = self.get_python_path_from_env().await {
+            self.config.python_path = path;
+        }
+        if let Some(timeout) = self.get_evaluation_timeout_from_env().await {
+            self.config.evaluation_timeout_secs = timeout;
+        }
+        self.config.scan_exclude = self.get_scan_exclude_from_env().await;
+        self.config.package_file_patterns = self.get_package_file_patterns_from_env().await;
+        self.config.hover_payload_stats = self.get_hover_payload_stats_from_env().await;
+        self.config.hover_explain = self.get_hover_explain_from_env().await;
+        self.config.locale = self.get_locale_from_env().await;
+        if let Some(max_files) = self.get_payload_max_files_from_env().await {
+            self.config.payload_max_files = max_files;
+        }
+        self.config.completion_detail = self.get_completion_detail_from_env().await;
+        self.config.exclude_prereleases = self.get_exclude_prereleases_from_env().await;
+        self.config.trailing_comma = self.get_trailing_comma_from_env().await;
+        self.config.shared_index_dir = self.get_shared_index_dir_from_env().await;
+        if let Some(max_age) = self.get_shared_index_max_age_from_env().await {
+            self.config.shared_index_max_age_secs = max_age;
+        }
+        self.config.path_mappings = self.get_path_mappings_from_env().await;
+        self.config.advisories = self.get_advisories_from_env().await;
+        self.config.metrics_textfile = self.get_metrics_textfile_from_env().await;
+        if let Some(interval) = self.get_metrics_textfile_interval_from_env().await {
+            self.config.metrics_textfile_interval_secs = interval;
+        }
+        self.config.completion_trigger_characters =
+            self.get_completion_trigger_characters_from_env().await;
+        self.config.virtual_documents = self.get_virtual_documents_from_env().await;
+        self.config.restore_session = self.get_restore_session_from_env().await;
+        self.config.known_families = self.get_known_families_from_env().await;
 
         debug!("Packages path: {:?}", self.config.packages_path);
         debug!("Local packages path: {:?}", self.config.local_packages_path);
@@ -58,27 +217,46 @@ impl RezConfigProvider {
 
     /// Get REZ_PACKAGES_PATH from environment.
     async fn get_packages_path_from_env(&self) -> Result<Vec<PathBuf>> {
-        match env::var("REZ_PACKAGES_PATH") {
+        let mut paths = match env::var("REZ_PACKAGES_PATH") {
             Ok(path_str) => {
-                let paths: Vec<PathBuf> = path_str
-                    .split(if cfg!(windows) { ';' } else { ':' })
-                    .filter(|s| !s.is_empty())
-                    .map(PathBuf::from)
-                    .collect();
+                let paths = split_path_list(&path_str)
+                    .map(|entry| expand_path(entry, "REZ_PACKAGES_PATH"))
+                    .collect::<Result<Vec<PathBuf>>>()?;
 
                 if paths.is_empty() {
                     warn!("REZ_PACKAGES_PATH is empty, using default paths");
-                    Ok(self.get_default_packages_path())
+                    self.get_default_packages_path()
                 } else {
                     info!("Found {} package paths in REZ_PACKAGES_PATH", paths.len());
-                    Ok(paths)
+                    paths
                 }
             }
             Err(_) => {
                 warn!("REZ_PACKAGES_PATH not set, using default paths");
-                Ok(self.get_default_packages_path())
+                self.get_default_packages_path()
+            }
+        };
+
+        let extra = match &self.package_paths_override {
+            Some(entries) => entries
+                .iter()
+                .map(|entry| entry.trim())
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| expand_path(entry, "rezLsp.packagePaths"))
+                .collect::<Result<Vec<PathBuf>>>()?,
+            None => match env::var("REZ_LSP_PACKAGE_PATHS") {
+                Ok(path_str) => split_path_list(&path_str)
+                    .map(|entry| expand_path(entry, "REZ_LSP_PACKAGE_PATHS"))
+                    .collect::<Result<Vec<PathBuf>>>()?,
+                Err(_) => Vec::new(),
+            },
+        };
+        for path in extra {
+            if !paths.contains(&path) {
+                paths.push(path);
             }
         }
+        Ok(paths)
     }
 
     /// Get default package paths when REZ_PACKAGES_PATH is not set.
@@ -103,14 +281,522 @@ impl RezConfigProvider {
 
     /// Get REZ_LOCAL_PACKAGES_PATH from environment.
     async fn get_local_packages_path_from_env(&self) -> Result<Option<PathBuf>> {
-        Ok(env::var("REZ_LOCAL_PACKAGES_PATH").ok().map(PathBuf::from))
+        env::var("REZ_LOCAL_PACKAGES_PATH")
+            .ok()
+            .map(|path| expand_path(&path, "REZ_LOCAL_PACKAGES_PATH"))
+            .transpose()
     }
 
     /// Get REZ_RELEASE_PACKAGES_PATH from environment.
     async fn get_release_packages_path_from_env(&self) -> Result<Option<PathBuf>> {
-        Ok(env::var("REZ_RELEASE_PACKAGES_PATH")
+        env::var("REZ_RELEASE_PACKAGES_PATH")
             .ok()
-            .map(PathBuf::from))
+            .map(|path| expand_path(&path, "REZ_RELEASE_PACKAGES_PATH"))
+            .transpose()
+    }
+
+    /// Get resolve introspection setting from environment.
+    async fn get_resolve_introspection_from_env(&self) -> bool {
+        env::var("REZ_LSP_RESOLVE_INTROSPECTION")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false)
+    }
+
+    /// Get profiling setting from environment.
+    async fn get_enable_profiling_from_env(&self) -> bool {
+        env::var("REZ_LSP_PROFILE")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false)
+    }
+
+    /// Get the per-family version cap from environment.
+    async fn get_max_versions_per_family_from_env(&self) -> Option<usize> {
+        let value = env::var("REZ_LSP_MAX_VERSIONS_PER_FAMILY").ok()?;
+        match value.trim().parse::<usize>() {
+            Ok(max) => (max > 0).then_some(max),
+            Err(_) => {
+                warn!(
+                    "Ignoring invalid REZ_LSP_MAX_VERSIONS_PER_FAMILY '{}', keeping all versions",
+                    value
+                );
+                None
+            }
+        }
+    }
+
+    /// Get a positive variant expansion limit from environment.
+    async fn get_variant_limit_from_env(&self, var: &str) -> Option<usize> {
+        let value = env::var(var).ok()?;
+        match value.trim().parse::<usize>() {
+            Ok(max) if max > 0 => Some(max),
+            _ => {
+                warn!("Ignoring invalid {} '{}'", var, value);
+                None
+            }
+        }
+    }
+
+    /// Get package family aliases from environment.
+    async fn get_package_aliases_from_env(&self) -> HashMap<String, String> {
+        let Ok(value) = env::var("REZ_LSP_PACKAGE_ALIASES") else {
+            return HashMap::new();
+        };
+
+        let mut aliases = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((alias, canonical))
+                    if !alias.trim().is_empty()
+                        && !canonical.trim().is_empty()
+                        && alias.trim() != canonical.trim() =>
+                {
+                    aliases.insert(alias.trim().to_string(), canonical.trim().to_string());
+                }
+                _ => warn!("Ignoring invalid REZ_LSP_PACKAGE_ALIASES entry '{}'", entry),
+            }
+        }
+
+        if !aliases.is_empty() {
+            info!("Loaded {} package family aliases", aliases.len());
+        }
+        aliases
+    }
+
+    /// Get the build and runtime categories of package families from
+    /// environment.
+    async fn get_package_categories_from_env(&self) -> HashMap<String, PackageCategory> {
+        let Ok(value) = env::var("REZ_LSP_PACKAGE_CATEGORIES") else {
+            return HashMap::new();
+        };
+
+        let mut categories = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=').and_then(|(family, category)| {
+                Some((family.trim(), PackageCategory::parse(category)?))
+            }) {
+                Some((family, category)) if !family.is_empty() => {
+                    categories.insert(family.to_string(), category);
+                }
+                _ => warn!(
+                    "Ignoring invalid REZ_LSP_PACKAGE_CATEGORIES entry '{}'",
+                    entry
+                ),
+            }
+        }
+        categories
+    }
+
+    /// Get the package test runner from environment.
+    async fn get_test_runner_from_env(&self) -> Option<String> {
+        env::var("REZ_LSP_TEST_RUNNER")
+            .ok()
+            .map(|runner| runner.trim().to_string())
+            .filter(|runner| !runner.is_empty())
+    }
+
+    /// Get the resolver backend from environment.
+    async fn get_resolver_backend_from_env(&self) -> ResolverBackend {
+        let Ok(value) = env::var("REZ_LSP_RESOLVER") else {
+            return ResolverBackend::Native;
+        };
+        match value.trim().to_lowercase().as_str() {
+            "" | "native" => ResolverBackend::Native,
+            "rez" | "rez-env" => ResolverBackend::RezBinary,
+            _ => {
+                warn!(
+                    "Ignoring unknown REZ_LSP_RESOLVER '{}', using the native resolver",
+                    value
+                );
+                ResolverBackend::Native
+            }
+        }
+    }
+
+    /// Get the version resolve strategy from environment.
+    async fn get_resolve_strategy_from_env(&self) -> ResolveStrategy {
+        let Ok(value) = env::var("REZ_LSP_RESOLVE_STRATEGY") else {
+            return ResolveStrategy::Latest;
+        };
+        match value.trim().to_lowercase().as_str() {
+            "" | "latest" => ResolveStrategy::Latest,
+            "earliest" => ResolveStrategy::Earliest,
+            _ => {
+                warn!(
+                    "Ignoring unknown REZ_LSP_RESOLVE_STRATEGY '{}', using latest",
+                    value
+                );
+                ResolveStrategy::Latest
+            }
+        }
+    }
+
+    /// Get the preferred version ranges of package families from environment.
+    async fn get_preferred_versions_from_env(&self) -> HashMap<String, VersionConstraint> {
+        let Ok(value) = env::var("REZ_LSP_PREFERRED_VERSIONS") else {
+            return HashMap::new();
+        };
+
+        let mut preferred = HashMap::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=').and_then(|(family, range)| {
+                let family = family.trim();
+                let (_, constraint) =
+                    VersionConstraint::parse(&format!("{}-{}", family, range.trim())).ok()?;
+                (!family.is_empty() && constraint != VersionConstraint::Any)
+                    .then(|| (family.to_string(), constraint))
+            }) {
+                Some((family, constraint)) => {
+                    preferred.insert(family, constraint);
+                }
+                None => warn!(
+                    "Ignoring invalid REZ_LSP_PREFERRED_VERSIONS entry '{}'",
+                    entry
+                ),
+            }
+        }
+        preferred
+    }
+
+    /// Get the Prometheus textfile of the metrics from environment.
+    async fn get_metrics_textfile_from_env(&self) -> Option<PathBuf> {
+        env::var("REZ_LSP_METRICS_TEXTFILE")
+            .ok()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Get the interval of metrics textfile writes from environment.
+    async fn get_metrics_textfile_interval_from_env(&self) -> Option<u64> {
+        let value = env::var("REZ_LSP_METRICS_TEXTFILE_INTERVAL").ok()?;
+        match value.trim().parse::<u64>() {
+            Ok(interval) if interval > 0 => Some(interval),
+            _ => {
+                warn!(
+                    "Ignoring invalid REZ_LSP_METRICS_TEXTFILE_INTERVAL '{}'",
+                    value
+                );
+                None
+            }
+        }
+    }
+
+    /// Get the characters triggering completion from environment.
+    ///
+    /// Every character of the value is a trigger; whitespace is ignored. Public
+    /// because the triggers are announced on `initialize`, before the rest of
+    /// the configuration is loaded.
+    pub async fn get_completion_trigger_characters_from_env(&self) -> Vec<String> {
+        let mut characters: Vec<String> = Vec::new();
+        if let Ok(value) = env::var("REZ_LSP_COMPLETION_TRIGGER_CHARACTERS") {
+            for c in value.chars().filter(|c| !c.is_whitespace()) {
+                if !characters.contains(&c.to_string()) {
+                    characters.push(c.to_string());
+                }
+            }
+        }
+        if characters.is_empty() {
+            characters = DEFAULT_COMPLETION_TRIGGER_CHARACTERS
+                .iter()
+                .map(|c| c.to_string())
+                .collect();
+        }
+        characters
+    }
+
+    /// Check if definition targets are virtual documents from environment.
+    async fn get_virtual_documents_from_env(&self) -> bool {
+        env::var("REZ_LSP_VIRTUAL_DOCUMENTS")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false)
+    }
+
+    /// Check if the session is restored after a restart from environment.
+    pub async fn get_restore_session_from_env(&self) -> bool {
+        env::var("REZ_LSP_RESTORE_SESSION")
+            .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0"))
+            .unwrap_or(true)
+    }
+
+    /// Get the well-known families from environment, merging the JSON object
+    /// of `REZ_LSP_KNOWN_FAMILIES` over the bundled ones.
+    async fn get_known_families_from_env(&self) -> KnownFamilies {
+        let mut families = KnownFamilies::bundled();
+        let Ok(value) = env::var("REZ_LSP_KNOWN_FAMILIES") else {
+            return families;
+        };
+        if value.trim().is_empty() {
+            return families;
+        }
+        match families.merge_json(&value) {
+            Ok(count) => info!("Loaded {} known family overrides", count),
+            Err(e) => warn!("Ignoring invalid REZ_LSP_KNOWN_FAMILIES: {}", e),
+        }
+        families
+    }
+
+    /// Get the location of the package advisory list from environment.
+    async fn get_advisories_from_env(&self) -> Option<String> {
+        env::var("REZ_LSP_ADVISORIES")
+            .ok()
+            .map(|location| location.trim().to_string())
+            .filter(|location| !location.is_empty())
+    }
+
+    /// Get the directories mapped between server and client from environment.
+    async fn get_path_mappings_from_env(&self) -> Vec<PathMapping> {
+        let Ok(value) = env::var("REZ_LSP_PATH_MAPPINGS") else {
+            return Vec::new();
+        };
+
+        let mut mappings = Vec::new();
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry
+                .split_once('=')
+                .map(|(server, client)| (server.trim(), client.trim()))
+            {
+                Some((server, client)) if !server.is_empty() && !client.is_empty() => {
+                    mappings.push(PathMapping {
+                        server: PathBuf::from(server),
+                        client: client.to_string(),
+                    });
+                }
+                _ => warn!("Ignoring invalid REZ_LSP_PATH_MAPPINGS entry '{}'", entry),
+            }
+        }
+        mappings
+    }
+
+    /// Get the trailing comma style of on-type formatting from environment.
+    async fn get_trailing_comma_from_env(&self) -> TrailingComma {
+        let Ok(value) = env::var("REZ_LSP_TRAILING_COMMA") else {
+            return TrailingComma::Multiline;
+        };
+        match value.trim().to_lowercase().as_str() {
+            "" | "multiline" => TrailingComma::Multiline,
+            "always" => TrailingComma::Always,
+            "never" => TrailingComma::Never,
+            _ => {
+                warn!(
+                    "Ignoring unknown REZ_LSP_TRAILING_COMMA '{}', using multiline",
+                    value
+                );
+                TrailingComma::Multiline
+            }
+        }
+    }
+
+    /// Get the path of the `rez-env` binary from environment.
+    async fn get_rez_env_path_from_env(&self) -> Option<PathBuf> {
+        env::var("REZ_LSP_REZ_ENV")
+            .ok()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Get the package evaluation mode from environment.
+    async fn get_package_evaluation_from_env(&self) -> PackageEvaluation {
+        let Ok(value) = env::var("REZ_LSP_EVALUATION") else {
+            return PackageEvaluation::Static;
+        };
+        match value.trim().to_lowercase().as_str() {
+            "" | "static" => PackageEvaluation::Static,
+            "sandbox" => {
+                if !cfg!(feature = "python-eval") {
+                    warn!(
+                        "REZ_LSP_EVALUATION=sandbox requires the python-eval feature, \
+                         using static parsing"
+                    );
+                    return PackageEvaluation::Static;
+                }
+                PackageEvaluation::Sandbox
+            }
+            _ => {
+                warn!(
+                    "Ignoring unknown REZ_LSP_EVALUATION '{}', using static parsing",
+                    value
+                );
+                PackageEvaluation::Static
+            }
+        }
+    }
+
+    /// Check if the workspace is trusted from environment.
+    async fn get_trust_workspace_from_env(&self) -> bool {
+        env::var("REZ_LSP_TRUST_WORKSPACE")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false)
+    }
+
+    /// Get the Python interpreter of the evaluation sandbox from environment.
+    async fn get_python_path_from_env(&self) -> Option<PathBuf> {
+        env::var("REZ_LSP_PYTHON")
+            .ok()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Get the sandboxed evaluation timeout from environment.
+    async fn get_evaluation_timeout_from_env(&self) -> Option<u64> {
+        let value = env::var("REZ_LSP_EVALUATION_TIMEOUT").ok()?;
+        match value.trim().parse::<u64>() {
+            Ok(seconds) if seconds > 0 => Some(seconds),
+            _ => {
+                warn!("Ignoring invalid REZ_LSP_EVALUATION_TIMEOUT '{}'", value);
+                None
+            }
+        }
+    }
+
+    /// Get the workspace traversal exclude patterns from environment.
+    async fn get_scan_exclude_from_env(&self) -> Vec<String> {
+        Self::patterns_from_env("REZ_LSP_SCAN_EXCLUDE")
+    }
+
+    /// Get the filename patterns of package definitions from environment.
+    async fn get_package_file_patterns_from_env(&self) -> Vec<String> {
+        Self::patterns_from_env("REZ_LSP_PACKAGE_FILE_PATTERNS")
+    }
+
+    /// Get the comma-separated patterns of environment variable `var`.
+    fn patterns_from_env(var: &str) -> Vec<String> {
+        env::var(var)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Check if payload sizes are shown on hover from environment.
+    async fn get_hover_payload_stats_from_env(&self) -> bool {
+        env::var("REZ_LSP_HOVER_PAYLOAD")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false)
+    }
+
+    /// Check if hovered requirements are explained in plain language from
+    /// environment.
+    async fn get_hover_explain_from_env(&self) -> bool {
+        env::var("REZ_LSP_HOVER_EXPLAIN")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false)
+    }
+
+    /// Get the language of explanations from environment.
+    async fn get_locale_from_env(&self) -> Locale {
+        env::var("REZ_LSP_LOCALE")
+            .map(|tag| Locale::from_tag(&tag))
+            .unwrap_or_default()
+    }
+
+    /// Check if pre-release versions are left out of the latest version from
+    /// environment.
+    async fn get_exclude_prereleases_from_env(&self) -> bool {
+        env::var("REZ_LSP_EXCLUDE_PRERELEASES")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false)
+    }
+
+    /// Get the payload file limit from environment.
+    async fn get_payload_max_files_from_env(&self) -> Option<usize> {
+        let value = env::var("REZ_LSP_PAYLOAD_MAX_FILES").ok()?;
+        match value.trim().parse::<usize>() {
+            Ok(max_files) if max_files > 0 => Some(max_files),
+            _ => {
+                warn!("Ignoring invalid REZ_LSP_PAYLOAD_MAX_FILES '{}'", value);
+                None
+            }
+        }
+    }
+
+    /// Get the completion detail template from environment.
+    ///
+    /// Unknown placeholders are kept verbatim and reported once.
+    async fn get_completion_detail_from_env(&self) -> Option<String> {
+        let template = env::var("REZ_LSP_COMPLETION_DETAIL").ok()?;
+        if template.trim().is_empty() {
+            return None;
+        }
+        for placeholder in unknown_detail_placeholders(&template) {
+            warn!(
+                "Unknown placeholder {{{}}} in REZ_LSP_COMPLETION_DETAIL",
+                placeholder
+            );
+        }
+        Some(template)
+    }
+
+    /// Get the shared package index directory from environment.
+    async fn get_shared_index_dir_from_env(&self) -> Option<PathBuf> {
+        let value = env::var("REZ_LSP_SHARED_INDEX").ok()?;
+        match value.trim().to_lowercase().as_str() {
+            "" | "0" | "false" => None,
+            "1" | "true" => {
+                let dir = dirs::cache_dir().map(|dir| dir.join("rez-lsp").join("index"));
+                if dir.is_none() {
+                    warn!(
+                        "No user cache directory for REZ_LSP_SHARED_INDEX, not sharing the index"
+                    );
+                }
+                dir
+            }
+            _ => Some(PathBuf::from(value.trim())),
+        }
+    }
+
+    /// Get the shared package index maximum age from environment.
+    async fn get_shared_index_max_age_from_env(&self) -> Option<u64> {
+        let value = env::var("REZ_LSP_SHARED_INDEX_MAX_AGE").ok()?;
+        match value.trim().parse::<u64>() {
+            Ok(max_age) => Some(max_age),
+            Err(_) => {
+                warn!("Ignoring invalid REZ_LSP_SHARED_INDEX_MAX_AGE '{}'", value);
+                None
+            }
+        }
+    }
+
+    /// Get the version scheme policy from environment.
+    async fn get_version_policy_from_env(&self) -> VersionPolicy {
+        let mut policy = VersionPolicy::default();
+
+        if let Ok(value) = env::var("REZ_LSP_VERSION_SCHEME") {
+            match VersionScheme::parse(&value) {
+                Some(scheme) => policy.default_scheme = scheme,
+                None if value.trim().is_empty() => {}
+                None => warn!("Ignoring unknown REZ_LSP_VERSION_SCHEME '{}'", value),
+            }
+        }
+
+        let Ok(value) = env::var("REZ_LSP_VERSION_SCHEMES") else {
+            return policy;
+        };
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry
+                .rsplit_once('=')
+                .and_then(|(path, scheme)| Some((path.trim(), VersionScheme::parse(scheme)?)))
+            {
+                Some((path, scheme)) if !path.is_empty() => {
+                    policy.repositories.push((PathBuf::from(path), scheme));
+                }
+                _ => warn!("Ignoring invalid REZ_LSP_VERSION_SCHEMES entry '{}'", entry),
+            }
+        }
+
+        if !policy.repositories.is_empty() {
+            info!(
+                "Loaded version schemes for {} repositories",
+                policy.repositories.len()
+            );
+        }
+        policy
     }
 
     /// Get debug logging setting from environment.
@@ -167,6 +853,13 @@ impl ConfigProvider for RezConfigProvider {
     }
 }
 
+/// Split a list of paths separated like `PATH`.
+fn split_path_list(paths: &str) -> impl Iterator<Item = &str> {
+    paths
+        .split(if cfg!(windows) { ';' } else { ':' })
+        .filter(|s| !s.is_empty())
+}
+
 impl Default for RezConfigProvider {
     fn default() -> Self {
         Self::new()
@@ -207,4 +900,429 @@ mod tests {
 
         env::remove_var("REZ_PACKAGES_PATH");
     }
+
+    #[tokio::test]
+    async fn test_package_paths_setting_is_expanded() {
+        let mut provider = RezConfigProvider::new();
+        env::set_var("REZ_LSP_TEST_STUDIO_ROOT", "/studio");
+        provider.set_package_paths(vec!["${REZ_LSP_TEST_STUDIO_ROOT}/packages".to_string()]);
+        provider.load_from_environment().await.unwrap();
+        assert!(provider
+            .config
+            .packages_path
+            .contains(&PathBuf::from("/studio/packages")));
+
+        provider.set_package_paths(vec!["$REZ_LSP_TEST_UNSET_SITE/packages".to_string()]);
+        let error = provider.load_from_environment().await.unwrap_err();
+        assert_eq!(error.code(), "RZ105");
+        env::remove_var("REZ_LSP_TEST_STUDIO_ROOT");
+    }
+
+    #[tokio::test]
+    async fn test_max_versions_per_family_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_MAX_VERSIONS_PER_FAMILY", "50");
+        assert_eq!(
+            provider.get_max_versions_per_family_from_env().await,
+            Some(50)
+        );
+
+        env::set_var("REZ_LSP_MAX_VERSIONS_PER_FAMILY", "0");
+        assert_eq!(provider.get_max_versions_per_family_from_env().await, None);
+
+        env::set_var("REZ_LSP_MAX_VERSIONS_PER_FAMILY", "lots");
+        assert_eq!(provider.get_max_versions_per_family_from_env().await, None);
+
+        env::remove_var("REZ_LSP_MAX_VERSIONS_PER_FAMILY");
+        assert_eq!(provider.get_max_versions_per_family_from_env().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_variant_limit_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_MAX_VARIANT_FANOUT", "8");
+        assert_eq!(
+            provider
+                .get_variant_limit_from_env("REZ_LSP_MAX_VARIANT_FANOUT")
+                .await,
+            Some(8)
+        );
+        env::set_var("REZ_LSP_MAX_VARIANT_FANOUT", "0");
+        assert_eq!(
+            provider
+                .get_variant_limit_from_env("REZ_LSP_MAX_VARIANT_FANOUT")
+                .await,
+            None
+        );
+        env::remove_var("REZ_LSP_MAX_VARIANT_FANOUT");
+        assert_eq!(
+            provider
+                .get_variant_limit_from_env("REZ_LSP_MAX_VARIANT_FANOUT")
+                .await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_package_aliases_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var(
+            "REZ_LSP_PACKAGE_ALIASES",
+            "usd=openusd, pyside = pyside2,bad,same=same",
+        );
+        let aliases = provider.get_package_aliases_from_env().await;
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases["usd"], "openusd");
+        assert_eq!(aliases["pyside"], "pyside2");
+
+        env::remove_var("REZ_LSP_PACKAGE_ALIASES");
+        assert!(provider.get_package_aliases_from_env().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_package_categories_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var(
+            "REZ_LSP_PACKAGE_CATEGORIES",
+            "sphinx=build, devtoolset = runtime,bad,docs=test",
+        );
+        let categories = provider.get_package_categories_from_env().await;
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories["sphinx"], PackageCategory::Build);
+        assert_eq!(categories["devtoolset"], PackageCategory::Runtime);
+
+        env::remove_var("REZ_LSP_PACKAGE_CATEGORIES");
+        assert!(provider.get_package_categories_from_env().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_exclude_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_SCAN_EXCLUDE", "build/, *.venv,,");
+        assert_eq!(
+            provider.get_scan_exclude_from_env().await,
+            vec!["build/", "*.venv"]
+        );
+
+        env::remove_var("REZ_LSP_SCAN_EXCLUDE");
+        assert!(provider.get_scan_exclude_from_env().await.is_empty());
+
+        env::set_var(
+            "REZ_LSP_PACKAGE_FILE_PATTERNS",
+            "package.py.in, templates/*.py",
+        );
+        assert_eq!(
+            provider.get_package_file_patterns_from_env().await,
+            vec!["package.py.in", "templates/*.py"]
+        );
+        env::remove_var("REZ_LSP_PACKAGE_FILE_PATTERNS");
+        assert!(provider
+            .get_package_file_patterns_from_env()
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_completion_detail_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_COMPLETION_DETAIL", "{count} versions · {repo}");
+        assert_eq!(
+            provider.get_completion_detail_from_env().await.as_deref(),
+            Some("{count} versions · {repo}")
+        );
+
+        env::set_var("REZ_LSP_COMPLETION_DETAIL", " ");
+        assert!(provider.get_completion_detail_from_env().await.is_none());
+
+        env::remove_var("REZ_LSP_COMPLETION_DETAIL");
+        assert!(provider.get_completion_detail_from_env().await.is_none());
+        assert_eq!(
+            unknown_detail_placeholders("{latest} {owner} {count}"),
+            vec!["owner"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hover_explain_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_HOVER_EXPLAIN", "1");
+        assert!(provider.get_hover_explain_from_env().await);
+        env::remove_var("REZ_LSP_HOVER_EXPLAIN");
+        assert!(!provider.get_hover_explain_from_env().await);
+
+        env::set_var("REZ_LSP_LOCALE", "zh-cn");
+        assert_eq!(provider.get_locale_from_env().await, Locale::Chinese);
+        env::remove_var("REZ_LSP_LOCALE");
+        assert_eq!(provider.get_locale_from_env().await, Locale::English);
+    }
+
+    #[tokio::test]
+    async fn test_shared_index_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_SHARED_INDEX", "/tmp/rez-index");
+        assert_eq!(
+            provider.get_shared_index_dir_from_env().await,
+            Some(PathBuf::from("/tmp/rez-index"))
+        );
+        env::set_var("REZ_LSP_SHARED_INDEX", "false");
+        assert!(provider.get_shared_index_dir_from_env().await.is_none());
+        env::remove_var("REZ_LSP_SHARED_INDEX");
+        assert!(provider.get_shared_index_dir_from_env().await.is_none());
+
+        env::set_var("REZ_LSP_SHARED_INDEX_MAX_AGE", "soon");
+        assert!(provider.get_shared_index_max_age_from_env().await.is_none());
+        env::set_var("REZ_LSP_SHARED_INDEX_MAX_AGE", "60");
+        assert_eq!(provider.get_shared_index_max_age_from_env().await, Some(60));
+        env::remove_var("REZ_LSP_SHARED_INDEX_MAX_AGE");
+    }
+
+    #[tokio::test]
+    async fn test_version_policy_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_VERSION_SCHEME", "semver");
+        env::set_var(
+            "REZ_LSP_VERSION_SCHEMES",
+            "/studio/config=calver, /studio/libs = rez,bad,/x=unknown",
+        );
+        let policy = provider.get_version_policy_from_env().await;
+        assert_eq!(policy.default_scheme, VersionScheme::Semver);
+        assert_eq!(
+            policy.repositories,
+            vec![
+                (PathBuf::from("/studio/config"), VersionScheme::CalVer),
+                (PathBuf::from("/studio/libs"), VersionScheme::Rez),
+            ]
+        );
+
+        env::remove_var("REZ_LSP_VERSION_SCHEME");
+        env::remove_var("REZ_LSP_VERSION_SCHEMES");
+        assert_eq!(
+            provider.get_version_policy_from_env().await,
+            VersionPolicy::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_package_evaluation_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_EVALUATION", "sandbox");
+        let expected = if cfg!(feature = "python-eval") {
+            PackageEvaluation::Sandbox
+        } else {
+            PackageEvaluation::Static
+        };
+        assert_eq!(provider.get_package_evaluation_from_env().await, expected);
+
+        env::set_var("REZ_LSP_EVALUATION", "exec");
+        assert_eq!(
+            provider.get_package_evaluation_from_env().await,
+            PackageEvaluation::Static
+        );
+        env::remove_var("REZ_LSP_EVALUATION");
+
+        env::set_var("REZ_LSP_EVALUATION_TIMEOUT", "0");
+        assert_eq!(provider.get_evaluation_timeout_from_env().await, None);
+        env::set_var("REZ_LSP_EVALUATION_TIMEOUT", "12");
+        assert_eq!(provider.get_evaluation_timeout_from_env().await, Some(12));
+        env::remove_var("REZ_LSP_EVALUATION_TIMEOUT");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_strategy_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_RESOLVE_STRATEGY", "Earliest");
+        assert_eq!(
+            provider.get_resolve_strategy_from_env().await,
+            ResolveStrategy::Earliest
+        );
+        env::set_var("REZ_LSP_RESOLVE_STRATEGY", "oldest");
+        assert_eq!(
+            provider.get_resolve_strategy_from_env().await,
+            ResolveStrategy::Latest
+        );
+        env::remove_var("REZ_LSP_RESOLVE_STRATEGY");
+
+        env::set_var(
+            "REZ_LSP_PREFERRED_VERSIONS",
+            "maya=2023, python = 3.9+<3.11,bad,usd=",
+        );
+        let preferred = provider.get_preferred_versions_from_env().await;
+        assert_eq!(preferred.len(), 2);
+        assert!(preferred["maya"].satisfies(&crate::core::Version::new("2023.1")));
+        assert!(!preferred["python"].satisfies(&crate::core::Version::new("3.11")));
+
+        env::remove_var("REZ_LSP_PREFERRED_VERSIONS");
+        assert!(provider.get_preferred_versions_from_env().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trailing_comma_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_TRAILING_COMMA", "Never");
+        assert_eq!(
+            provider.get_trailing_comma_from_env().await,
+            TrailingComma::Never
+        );
+
+        env::set_var("REZ_LSP_TRAILING_COMMA", "sometimes");
+        assert_eq!(
+            provider.get_trailing_comma_from_env().await,
+            TrailingComma::Multiline
+        );
+
+        env::remove_var("REZ_LSP_TRAILING_COMMA");
+        assert_eq!(
+            provider.get_trailing_comma_from_env().await,
+            TrailingComma::Multiline
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics_textfile_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var(
+            "REZ_LSP_METRICS_TEXTFILE",
+            " /var/lib/node_exporter/rez_lsp.prom ",
+        );
+        assert_eq!(
+            provider.get_metrics_textfile_from_env().await,
+            Some(PathBuf::from("/var/lib/node_exporter/rez_lsp.prom"))
+        );
+        env::remove_var("REZ_LSP_METRICS_TEXTFILE");
+        assert_eq!(provider.get_metrics_textfile_from_env().await, None);
+
+        env::set_var("REZ_LSP_METRICS_TEXTFILE_INTERVAL", "0");
+        assert_eq!(
+            provider.get_metrics_textfile_interval_from_env().await,
+            None
+        );
+        env::set_var("REZ_LSP_METRICS_TEXTFILE_INTERVAL", "30");
+        assert_eq!(
+            provider.get_metrics_textfile_interval_from_env().await,
+            Some(30)
+        );
+        env::remove_var("REZ_LSP_METRICS_TEXTFILE_INTERVAL");
+    }
+
+    #[tokio::test]
+    async fn test_completion_trigger_characters_parsing() {
+        let provider = RezConfigProvider::new();
+        let defaults = Config::new().completion_trigger_characters;
+
+        env::remove_var("REZ_LSP_COMPLETION_TRIGGER_CHARACTERS");
+        assert_eq!(
+            provider.get_completion_trigger_characters_from_env().await,
+            defaults
+        );
+
+        env::set_var("REZ_LSP_COMPLETION_TRIGGER_CHARACTERS", "\"' [,[");
+        assert_eq!(
+            provider.get_completion_trigger_characters_from_env().await,
+            vec!["\"", "'", "[", ","]
+        );
+
+        env::set_var("REZ_LSP_COMPLETION_TRIGGER_CHARACTERS", "  ");
+        assert_eq!(
+            provider.get_completion_trigger_characters_from_env().await,
+            defaults
+        );
+        env::remove_var("REZ_LSP_COMPLETION_TRIGGER_CHARACTERS");
+    }
+
+    #[tokio::test]
+    async fn test_virtual_documents_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_VIRTUAL_DOCUMENTS", "true");
+        assert!(provider.get_virtual_documents_from_env().await);
+        env::remove_var("REZ_LSP_VIRTUAL_DOCUMENTS");
+        assert!(!provider.get_virtual_documents_from_env().await);
+    }
+
+    #[tokio::test]
+    async fn test_restore_session_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_RESTORE_SESSION", "false");
+        assert!(!provider.get_restore_session_from_env().await);
+        env::set_var("REZ_LSP_RESTORE_SESSION", "1");
+        assert!(provider.get_restore_session_from_env().await);
+        env::remove_var("REZ_LSP_RESTORE_SESSION");
+        assert!(provider.get_restore_session_from_env().await);
+    }
+
+    #[tokio::test]
+    async fn test_known_families_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var(
+            "REZ_LSP_KNOWN_FAMILIES",
+            r#"{"studio_tools": {"description": "In-house tools"}}"#,
+        );
+        let families = provider.get_known_families_from_env().await;
+        assert!(families.get("studio_tools").is_some());
+        assert!(families.get("maya").is_some());
+        env::set_var("REZ_LSP_KNOWN_FAMILIES", "not json");
+        assert_eq!(
+            provider.get_known_families_from_env().await,
+            KnownFamilies::bundled()
+        );
+        env::remove_var("REZ_LSP_KNOWN_FAMILIES");
+    }
+
+    #[tokio::test]
+    async fn test_path_mappings_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var(
+            "REZ_LSP_PATH_MAPPINGS",
+            "/packages = C:\\studio\\packages, broken, /home/dev=",
+        );
+        assert_eq!(
+            provider.get_path_mappings_from_env().await,
+            vec![PathMapping {
+                server: PathBuf::from("/packages"),
+                client: "C:\\studio\\packages".to_string(),
+            }]
+        );
+
+        env::remove_var("REZ_LSP_PATH_MAPPINGS");
+        assert!(provider.get_path_mappings_from_env().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolver_backend_parsing() {
+        let provider = RezConfigProvider::new();
+
+        env::set_var("REZ_LSP_RESOLVER", "rez");
+        assert_eq!(
+            provider.get_resolver_backend_from_env().await,
+            ResolverBackend::RezBinary
+        );
+
+        env::set_var("REZ_LSP_RESOLVER", "sat");
+        assert_eq!(
+            provider.get_resolver_backend_from_env().await,
+            ResolverBackend::Native
+        );
+
+        env::remove_var("REZ_LSP_RESOLVER");
+        assert_eq!(
+            provider.get_resolver_backend_from_env().await,
+            ResolverBackend::Native
+        );
+    }
 }
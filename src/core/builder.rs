@@ -0,0 +1,375 @@
+//! Builders for the core data types.
+//!
+//! [`Package`], [`Requirement`] and [`ResolvedContext`] are `#[non_exhaustive]`
+//! so fields can be added without breaking library consumers. Construct them
+//! through these builders instead of struct literals.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::types::{
+    ContextMetadata, Package, PlatformInfo, Requirement, ResolutionStats, ResolvedContext, Variant,
+    Version, VersionConstraint,
+};
+
+/// Type state of a [`PackageBuilder`] whose name has not been set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoName;
+
+/// Type state of a [`PackageBuilder`] whose version has not been set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoVersion;
+
+/// Builder for [`Package`].
+///
+/// The name and version are required; [`PackageBuilder::build`] is only
+/// available once both have been set.
+///
+/// # Examples
+///
+/// ```rust
+/// use rez_lsp_server::core::Package;
+///
+/// let package = Package::builder()
+///     .name("python")
+///     .version("3.9.0")
+///     .description("Python interpreter")
+///     .tools(["python", "pip"])
+///     .path("/packages/python/3.9.0")
+///     .build();
+///
+/// assert_eq!(&*package.name, "python");
+/// assert_eq!(package.tools.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct PackageBuilder<N = NoName, V = NoVersion> {
+    name: N,
+    version: V,
+    description: Option<String>,
+    authors: Vec<Arc<str>>,
+    requires: Vec<Requirement>,
+    tools: Vec<Arc<str>>,
+    variants: Vec<Variant>,
+    path: PathBuf,
+    metadata: HashMap<String, String>,
+}
+
+impl PackageBuilder {
+    /// Create a builder without name and version.
+    pub fn new() -> Self {
+        Self {
+            name: NoName,
+            version: NoVersion,
+            description: None,
+            authors: Vec::new(),
+            requires: Vec::new(),
+            tools: Vec::new(),
+            variants: Vec::new(),
+            path: PathBuf::new(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+impl Default for PackageBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> PackageBuilder<NoName, V> {
+    /// Set the package name, e.g. an interned family name shared by its
+    /// versions.
+    pub fn name(self, name: impl Into<Arc<str>>) -> PackageBuilder<Arc<str>, V> {
+        PackageBuilder {
+            name: name.into(),
+            version: self.version,
+            description: self.description,
+            authors: self.authors,
+            requires: self.requires,
+            tools: self.tools,
+            variants: self.variants,
+            path: self.path,
+            metadata: self.metadata,
+        }
+    }
+}
+
+impl<N> PackageBuilder<N, NoVersion> {
+    /// Set the package version.
+    pub fn version(self, version: impl Into<Version>) -> PackageBuilder<N, Version> {
+        PackageBuilder {
+            name: self.name,
+            version: version.into(),
+            description: self.description,
+            authors: self.authors,
+            requires: self.requires,
+            tools: self.tools,
+            variants: self.variants,
+            path: self.path,
+            metadata: self.metadata,
+        }
+    }
+}
+
+impl<N, V> PackageBuilder<N, V> {
+    /// Set the package description.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Add a package author.
+    pub fn author(mut self, author: impl Into<Arc<str>>) -> Self {
+        self.authors.push(author.into());
+        self
+    }
+
+    /// Replace the package authors.
+    pub fn authors<I, S>(mut self, authors: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Arc<str>>,
+    {
+        self.authors = authors.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add a package requirement.
+    pub fn require(mut self, requirement: Requirement) -> Self {
+        self.requires.push(requirement);
+        self
+    }
+
+    /// Replace the package requirements.
+    pub fn requires(mut self, requires: impl IntoIterator<Item = Requirement>) -> Self {
+        self.requires = requires.into_iter().collect();
+        self
+    }
+
+    /// Add a package tool.
+    pub fn tool(mut self, tool: impl Into<Arc<str>>) -> Self {
+        self.tools.push(tool.into());
+        self
+    }
+
+    /// Replace the package tools.
+    pub fn tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Arc<str>>,
+    {
+        self.tools = tools.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Replace the package variants.
+    pub fn variants(mut self, variants: impl IntoIterator<Item = Variant>) -> Self {
+        self.variants = variants.into_iter().collect();
+        self
+    }
+
+    /// Set the package installation path.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Add a metadata entry.
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl PackageBuilder<Arc<str>, Version> {
+    /// Build the package.
+    pub fn build(self) -> Package {
+        Package {
+            name: self.name,
+            version: self.version,
+            description: self.description,
+            authors: self.authors,
+            requires: self.requires,
+            tools: self.tools,
+            variants: self.variants,
+            path: self.path,
+            metadata: self.metadata,
+        }
+    }
+}
+
+/// Builder for [`Requirement`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rez_lsp_server::core::{Requirement, Version, VersionConstraint};
+///
+/// let requirement = Requirement::builder("python")
+///     .constraint(VersionConstraint::GreaterEqual(Version::new("3.7")))
+///     .weak(true)
+///     .build();
+///
+/// assert_eq!(requirement.to_string(), "~python-3.7+");
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct RequirementBuilder {
+    name: String,
+    constraint: VersionConstraint,
+    weak: bool,
+    conflict: bool,
+}
+
+impl RequirementBuilder {
+    /// Create a builder for a requirement on any version of `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            constraint: VersionConstraint::Any,
+            weak: false,
+            conflict: false,
+        }
+    }
+
+    /// Set the version constraint.
+    pub fn constraint(mut self, constraint: VersionConstraint) -> Self {
+        self.constraint = constraint;
+        self
+    }
+
+    /// Mark the requirement as weak (`~name`).
+    pub fn weak(mut self, weak: bool) -> Self {
+        self.weak = weak;
+        self
+    }
+
+    /// Mark the requirement as a conflict (`!name`).
+    pub fn conflict(mut self, conflict: bool) -> Self {
+        self.conflict = conflict;
+        self
+    }
+
+    /// Build the requirement.
+    pub fn build(self) -> Requirement {
+        Requirement {
+            name: self.name,
+            constraint: self.constraint,
+            weak: self.weak,
+            conflict: self.conflict,
+        }
+    }
+}
+
+/// Builder for [`ResolvedContext`].
+///
+/// Without explicit metadata, the context is stamped with the current time,
+/// this crate's version and the host platform.
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct ResolvedContextBuilder {
+    packages: Vec<Package>,
+    metadata: Option<ContextMetadata>,
+    stats: Option<ResolutionStats>,
+}
+
+impl ResolvedContextBuilder {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a resolved package.
+    pub fn package(mut self, package: Package) -> Self {
+        self.packages.push(package);
+        self
+    }
+
+    /// Replace the resolved packages.
+    pub fn packages(mut self, packages: impl IntoIterator<Item = Package>) -> Self {
+        self.packages = packages.into_iter().collect();
+        self
+    }
+
+    /// Set the complete resolution metadata.
+    pub fn metadata(mut self, metadata: ContextMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Set the resolution statistics of the default metadata.
+    pub fn stats(mut self, stats: ResolutionStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Build the resolved context.
+    pub fn build(self) -> ResolvedContext {
+        let packages_resolved = self.packages.len();
+        let metadata = self.metadata.unwrap_or_else(|| ContextMetadata {
+            timestamp: chrono::Utc::now(),
+            resolver_version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: PlatformInfo::current(),
+            stats: self.stats.unwrap_or(ResolutionStats {
+                packages_resolved,
+                ..Default::default()
+            }),
+        });
+
+        ResolvedContext {
+            packages: self.packages,
+            metadata,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_builder() {
+        let package = Package::builder()
+            .version("1.2.0")
+            .name("maya_tools")
+            .author("Jane")
+            .require(Requirement::builder("maya").build())
+            .tool("mt")
+            .metadata("uuid", "abc")
+            .path("/packages/maya_tools/1.2.0")
+            .build();
+
+        assert_eq!(&*package.name, "maya_tools");
+        assert_eq!(package.version, Version::new("1.2.0"));
+        assert_eq!(package.authors, vec!["Jane".into()]);
+        assert_eq!(package.requires[0].name, "maya");
+        assert_eq!(package.tools, vec!["mt".into()]);
+        assert_eq!(package.metadata["uuid"], "abc");
+        assert_eq!(package.path, PathBuf::from("/packages/maya_tools/1.2.0"));
+        assert!(package.description.is_none());
+    }
+
+    #[test]
+    fn test_requirement_builder() {
+        let requirement = Requirement::builder("python").conflict(true).build();
+        assert_eq!(
+            requirement,
+            Requirement::conflict("python", VersionConstraint::Any)
+        );
+    }
+
+    #[test]
+    fn test_resolved_context_builder_defaults() {
+        let context = ResolvedContext::builder()
+            .package(Package::builder().name("python").version("3.9").build())
+            .build();
+
+        assert_eq!(context.packages.len(), 1);
+        assert_eq!(context.metadata.stats.packages_resolved, 1);
+        assert_eq!(context.metadata.platform.os, std::env::consts::OS);
+        assert_eq!(context.metadata.resolver_version, env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -0,0 +1,160 @@
+//! Build and runtime categories of package families.
+//!
+//! Large indexes mix compilers and build systems with the libraries packages
+//! run against. Knowing which is which lets completion rank `cmake` first in
+//! `private_build_requires` and last in `requires`.
+
+use std::collections::HashMap;
+
+use super::types::Package;
+
+/// Families and tools of common compilers and build systems.
+pub const BUILD_TOOLS: &[&str] = &[
+    "autoconf",
+    "automake",
+    "bazel",
+    "bison",
+    "clang",
+    "cl",
+    "cmake",
+    "flex",
+    "g++",
+    "gcc",
+    "llvm",
+    "make",
+    "meson",
+    "msvc",
+    "nasm",
+    "ninja",
+    "pkg-config",
+    "pkgconfig",
+    "scons",
+    "swig",
+    "yasm",
+];
+
+/// Whether a package family is needed to build packages or at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageCategory {
+    /// Compilers, build systems and other build-time tools
+    Build,
+    /// Libraries and applications packages run against
+    Runtime,
+}
+
+impl PackageCategory {
+    /// Get the category preferred in the requirement list of `attribute`.
+    pub fn for_attribute(attribute: &str) -> Option<Self> {
+        match attribute {
+            "build_requires" | "private_build_requires" => Some(Self::Build),
+            "requires" | "variants" => Some(Self::Runtime),
+            _ => None,
+        }
+    }
+
+    /// Parse a category name, `build` or `runtime`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "build" => Some(Self::Build),
+            "runtime" => Some(Self::Runtime),
+            _ => None,
+        }
+    }
+}
+
+/// Get the category of a package family.
+///
+/// Families listed in `categories` keep their configured category. Otherwise
+/// families named after a build tool, e.g. `gcc` or `msvc_2022`, and families
+/// providing one are build tools and everything else is a runtime package.
+pub fn package_category(
+    package: &Package,
+    categories: &HashMap<String, PackageCategory>,
+) -> PackageCategory {
+    if let Some(category) = categories.get(&*package.name) {
+        return *category;
+    }
+    let is_build_tool = |name: &str| BUILD_TOOLS.contains(&name.to_lowercase().as_str());
+    // Versioned family names such as `gcc_11` or `msvc_2022`
+    let versioned_build_tool = package
+        .name
+        .split_once(['_', '-'])
+        .is_some_and(|(stem, suffix)| {
+            is_build_tool(stem) && suffix.chars().all(|c| c.is_ascii_digit() || c == '.')
+        });
+    if is_build_tool(&package.name)
+        || versioned_build_tool
+        || package.tools.iter().any(|tool| is_build_tool(tool))
+    {
+        PackageCategory::Build
+    } else {
+        PackageCategory::Runtime
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, tools: &[&str]) -> Package {
+        let mut package = Package::builder().name(name).version("1.0").build();
+        package.tools = tools.iter().map(|tool| (*tool).into()).collect();
+        package
+    }
+
+    #[test]
+    fn test_package_category() {
+        let categories = HashMap::new();
+        assert_eq!(
+            package_category(&package("cmake", &[]), &categories),
+            PackageCategory::Build
+        );
+        assert_eq!(
+            package_category(&package("msvc_2022", &[]), &categories),
+            PackageCategory::Build
+        );
+        assert_eq!(
+            package_category(&package("devtoolset", &["gcc", "g++"]), &categories),
+            PackageCategory::Build
+        );
+        assert_eq!(
+            package_category(&package("openexr", &["exrinfo"]), &categories),
+            PackageCategory::Runtime
+        );
+        assert_eq!(
+            package_category(&package("make_utils", &[]), &categories),
+            PackageCategory::Runtime
+        );
+
+        let categories = HashMap::from([
+            ("devtoolset".to_string(), PackageCategory::Runtime),
+            ("sphinx".to_string(), PackageCategory::Build),
+        ]);
+        assert_eq!(
+            package_category(&package("devtoolset", &["gcc"]), &categories),
+            PackageCategory::Runtime
+        );
+        assert_eq!(
+            package_category(&package("sphinx", &[]), &categories),
+            PackageCategory::Build
+        );
+    }
+
+    #[test]
+    fn test_category_for_attribute() {
+        assert_eq!(
+            PackageCategory::for_attribute("private_build_requires"),
+            Some(PackageCategory::Build)
+        );
+        assert_eq!(
+            PackageCategory::for_attribute("requires"),
+            Some(PackageCategory::Runtime)
+        );
+        assert_eq!(PackageCategory::for_attribute("tools"), None);
+        assert_eq!(
+            PackageCategory::parse(" Build"),
+            Some(PackageCategory::Build)
+        );
+        assert_eq!(PackageCategory::parse("test"), None);
+    }
+}
@@ -51,6 +51,15 @@ pub enum ConfigError {
     NoValidPaths,
     /// Configuration validation failed
     ValidationFailed(String),
+    /// A variable referenced by a configured path is not set
+    UnexpandedVariable {
+        /// Name of the variable
+        variable: String,
+        /// Path entry referencing it
+        entry: String,
+        /// Setting or environment variable the entry was read from
+        source: String,
+    },
 }
 
 /// Package discovery error types
@@ -90,6 +99,8 @@ pub enum ResolverError {
     UnsatisfiableConstraint(String),
     /// Package not found during resolution
     PackageNotFound(String),
+    /// Too many variants were expanded without finding a resolve
+    VariantLimit(String),
 }
 
 /// LSP error types
@@ -103,6 +114,42 @@ pub enum LspError {
     Internal(String),
 }
 
+impl Error {
+    /// Get the stable code identifying the kind of error, e.g. `RZ201`.
+    ///
+    /// Codes are grouped by category: `RZ1xx` configuration, `RZ2xx`
+    /// discovery, `RZ3xx` parsing, `RZ4xx` resolution, `RZ5xx` protocol and
+    /// `RZ6xx` file system errors.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Config(ConfigError::EnvVarNotFound(_)) => "RZ101",
+            Error::Config(ConfigError::InvalidPath(_)) => "RZ102",
+            Error::Config(ConfigError::NoValidPaths) => "RZ103",
+            Error::Config(ConfigError::ValidationFailed(_)) => "RZ104",
+            Error::Config(ConfigError::UnexpandedVariable { .. }) => "RZ105",
+            Error::Discovery(DiscoveryError::ScanFailed(_)) => "RZ201",
+            Error::Discovery(DiscoveryError::PackageNotFound(_)) => "RZ202",
+            Error::Discovery(DiscoveryError::InvalidStructure(_)) => "RZ203",
+            Error::Discovery(DiscoveryError::CacheFailed(_)) => "RZ204",
+            Error::Parser(ParserError::ReadFailed(_)) => "RZ301",
+            Error::Parser(ParserError::InvalidSyntax(_)) => "RZ302",
+            Error::Parser(ParserError::MissingField(_)) => "RZ303",
+            Error::Parser(ParserError::InvalidValue(_)) => "RZ304",
+            Error::Resolver(ResolverError::Conflict(_)) => "RZ401",
+            Error::Resolver(ResolverError::CircularDependency(_)) => "RZ402",
+            Error::Resolver(ResolverError::UnsatisfiableConstraint(_)) => "RZ403",
+            Error::Resolver(ResolverError::PackageNotFound(_)) => "RZ404",
+            Error::Resolver(ResolverError::VariantLimit(_)) => "RZ405",
+            Error::Lsp(LspError::InvalidRequest(_)) => "RZ501",
+            Error::Lsp(LspError::NotInitialized) => "RZ502",
+            Error::Lsp(LspError::Internal(_)) => "RZ503",
+            Error::InvalidPath(_) => "RZ601",
+            Error::Io(_) => "RZ602",
+            Error::Other(_) => "RZ999",
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -127,6 +174,15 @@ impl fmt::Display for ConfigError {
             ConfigError::InvalidPath(path) => write!(f, "Invalid path: {}", path),
             ConfigError::NoValidPaths => write!(f, "No valid package paths found"),
             ConfigError::ValidationFailed(msg) => write!(f, "Validation failed: {}", msg),
+            ConfigError::UnexpandedVariable {
+                variable,
+                entry,
+                source,
+            } => write!(
+                f,
+                "Environment variable {} in {} entry '{}' is not set",
+                variable, source, entry
+            ),
         }
     }
 }
@@ -166,6 +222,7 @@ impl fmt::Display for ResolverError {
             ResolverError::PackageNotFound(name) => {
                 write!(f, "Package not found during resolution: {}", name)
             }
+            ResolverError::VariantLimit(msg) => write!(f, "Variant limit reached: {}", msg),
         }
     }
 }
@@ -0,0 +1,179 @@
+//! Plain-language explanations of requirements.
+//!
+//! Rez range syntax is terse: `python-3.7+<4`, `~maya-2024` and `!nuke-13`
+//! read very differently to someone new to it. [`explain_requirement`] turns
+//! a parsed requirement into a sentence such as "Requires any 3.x version at
+//! or above 3.7 and below 4 of `python`." in the locale of the editor.
+
+use serde::{Deserialize, Serialize};
+
+use super::types::{Requirement, Version, VersionConstraint, VersionToken};
+
+/// Language of generated explanations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Locale {
+    /// English
+    #[default]
+    English,
+    /// Simplified Chinese
+    Chinese,
+}
+
+impl Locale {
+    /// Get the locale of a language tag such as `en-US` or `zh-cn`.
+    ///
+    /// Unsupported languages fall back to English.
+    pub fn from_tag(tag: &str) -> Self {
+        let language = tag.trim().to_lowercase();
+        if language == "zh" || language.starts_with("zh-") || language.starts_with("zh_") {
+            Locale::Chinese
+        } else {
+            Locale::English
+        }
+    }
+
+    /// Get the heading of the explanation section in hovers.
+    pub fn explanation_heading(self) -> &'static str {
+        match self {
+            Locale::English => "What this means",
+            Locale::Chinese => "含义",
+        }
+    }
+}
+
+/// Explain what a requirement asks of the resolved environment.
+pub fn explain_requirement(requirement: &Requirement, locale: Locale) -> String {
+    let range = describe_constraint(&requirement.constraint, locale);
+    let name = &requirement.name;
+    match locale {
+        Locale::English if requirement.conflict => format!("Forbids {} of `{}`.", range, name),
+        Locale::English if requirement.weak => format!(
+            "Does not pull in `{}`, but if the environment contains it, it must be {}.",
+            name, range
+        ),
+        Locale::English => format!("Requires {} of `{}`.", range, name),
+        Locale::Chinese if requirement.conflict => format!("禁止 `{}` 的{}。", name, range),
+        Locale::Chinese if requirement.weak => format!(
+            "不会引入 `{}`，但若环境中已有该包，其版本必须是{}。",
+            name, range
+        ),
+        Locale::Chinese => format!("需要 `{}` 的{}。", name, range),
+    }
+}
+
+/// Describe the versions accepted by a constraint, e.g. "any version below 4".
+pub fn describe_constraint(constraint: &VersionConstraint, locale: Locale) -> String {
+    match (constraint, locale) {
+        (VersionConstraint::Any, Locale::English) => "any version".to_string(),
+        (VersionConstraint::Any, Locale::Chinese) => "任意版本".to_string(),
+        (VersionConstraint::Exact(v), Locale::English) => format!("exactly version {}", v),
+        (VersionConstraint::Exact(v), Locale::Chinese) => format!("恰好为 {} 的版本", v),
+        (VersionConstraint::Family(v), Locale::English) => {
+            format!("any {} or {}.x version", v, v)
+        }
+        (VersionConstraint::Family(v), Locale::Chinese) => {
+            format!("任意 {} 或 {}.x 版本", v, v)
+        }
+        (VersionConstraint::Wildcard(v), Locale::English) => {
+            format!("any version matching {}", v)
+        }
+        (VersionConstraint::Wildcard(v), Locale::Chinese) => format!("匹配 {} 的任意版本", v),
+        (VersionConstraint::GreaterEqual(v), Locale::English) => {
+            format!("version {} or any later version", v)
+        }
+        (VersionConstraint::GreaterEqual(v), Locale::Chinese) => {
+            format!("{} 及以上的任意版本", v)
+        }
+        (VersionConstraint::Less(v), Locale::English) => format!("any version below {}", v),
+        (VersionConstraint::Less(v), Locale::Chinese) => format!("低于 {} 的任意版本", v),
+        (VersionConstraint::Range { min, max }, Locale::English) => match shared_family(min, max) {
+            Some(family) => format!(
+                "any {}.x version at or above {} and below {}",
+                family, min, max
+            ),
+            None => format!("any version at or above {} and below {}", min, max),
+        },
+        (VersionConstraint::Range { min, max }, Locale::Chinese) => match shared_family(min, max) {
+            Some(family) => format!("不低于 {} 且低于 {} 的任意 {}.x 版本", min, max, family),
+            None => format!("不低于 {} 且低于 {} 的任意版本", min, max),
+        },
+        (VersionConstraint::Or(alternatives), locale) => {
+            let alternatives: Vec<String> = alternatives
+                .iter()
+                .map(|alternative| describe_constraint(alternative, locale))
+                .collect();
+            match locale {
+                Locale::English => format!("either {}", alternatives.join(", or ")),
+                Locale::Chinese => format!("以下任一：{}", alternatives.join("，或")),
+            }
+        }
+    }
+}
+
+/// Get the major version shared by a range such as `3.7+<4`, whose upper
+/// bound is the next major version.
+fn shared_family(min: &Version, max: &Version) -> Option<u64> {
+    match (min.tokens.first(), max.tokens.as_slice()) {
+        (Some(VersionToken::Number(major)), [VersionToken::Number(next)])
+            if major.checked_add(1) == Some(*next) =>
+        {
+            Some(*major)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn explain(requirement: &str, locale: Locale) -> String {
+        explain_requirement(&Requirement::parse(requirement).unwrap(), locale)
+    }
+
+    #[test]
+    fn test_explain_requirement() {
+        assert_eq!(
+            explain("python-3.7+<4", Locale::English),
+            "Requires any 3.x version at or above 3.7 and below 4 of `python`."
+        );
+        assert_eq!(
+            explain("python-3.7+<3.11", Locale::English),
+            "Requires any version at or above 3.7 and below 3.11 of `python`."
+        );
+        assert_eq!(
+            explain("maya-2023|2025+", Locale::English),
+            "Requires either any 2023 or 2023.x version, or version 2025 or any later version of `maya`."
+        );
+        assert_eq!(
+            explain("~numpy==1.26", Locale::English),
+            "Does not pull in `numpy`, but if the environment contains it, it must be exactly version 1.26."
+        );
+        assert_eq!(
+            explain("!nuke<13", Locale::English),
+            "Forbids any version below 13 of `nuke`."
+        );
+        assert_eq!(
+            explain("houdini", Locale::English),
+            "Requires any version of `houdini`."
+        );
+    }
+
+    #[test]
+    fn test_localized_explanations() {
+        assert_eq!(Locale::from_tag("zh-cn"), Locale::Chinese);
+        assert_eq!(Locale::from_tag("zh_TW"), Locale::Chinese);
+        assert_eq!(Locale::from_tag("en-US"), Locale::English);
+        assert_eq!(Locale::from_tag("fr"), Locale::English);
+
+        assert_eq!(
+            explain("python-3.7+<4", Locale::Chinese),
+            "需要 `python` 的不低于 3.7 且低于 4 的任意 3.x 版本。"
+        );
+        assert_eq!(
+            explain("!nuke-13+", Locale::Chinese),
+            "禁止 `nuke` 的13 及以上的任意版本。"
+        );
+    }
+}
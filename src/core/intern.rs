@@ -0,0 +1,59 @@
+//! String interning for package fields.
+//!
+//! Large repositories repeat the same family names, authors and tool names
+//! across thousands of package versions. Interning them keeps a single allocation
+//! per distinct string, shared by every package referring to it.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// Shared pool of interned strings.
+///
+/// Clones share the same pool, so concurrent scans intern into one set.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    strings: Arc<Mutex<HashSet<Arc<str>>>>,
+}
+
+impl Interner {
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the shared copy of `value`, adding it to the pool if needed.
+    pub fn intern(&self, value: &str) -> Arc<str> {
+        let mut strings = self.strings.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(interned) = strings.get(value) {
+            return Arc::clone(interned);
+        }
+        let interned: Arc<str> = Arc::from(value);
+        strings.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// Number of distinct strings in the pool.
+    pub fn len(&self) -> usize {
+        self.strings.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Check whether the pool is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_shares_strings() {
+        let interner = Interner::new();
+        let first = interner.intern("mayapy");
+        let second = interner.clone().intern("mayapy");
+        assert!(Arc::ptr_eq(&first, &second));
+        interner.intern("mt");
+        assert_eq!(interner.len(), 2);
+    }
+}
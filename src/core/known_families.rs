@@ -0,0 +1,118 @@
+//! Descriptions of well-known package families.
+//!
+//! A small knowledge base of common DCC families is bundled at compile time,
+//! so hovers and completions can describe packages whose definitions carry
+//! little metadata. Entries of the `rezLsp.knownFamilies` setting override or
+//! extend it.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Knowledge base bundled with the server.
+const BUNDLED: &str = include_str!("known_families.json");
+
+/// What is known about a package family.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct KnownFamily {
+    /// What the family provides
+    pub description: Option<String>,
+    /// Company or project publishing it
+    pub vendor: Option<String>,
+    /// Tools it typically provides
+    pub tools: Vec<String>,
+    /// URL of its documentation
+    pub docs: Option<String>,
+}
+
+impl KnownFamily {
+    /// Summarize the family in plain text for completion documentation, e.g.
+    /// `Node-based compositing application (Foundry)`.
+    pub fn summary(&self) -> Option<String> {
+        let description = self.description.as_ref()?;
+        Some(match &self.vendor {
+            Some(vendor) => format!("{} ({})", description, vendor),
+            None => description.clone(),
+        })
+    }
+
+    /// Render the family as Markdown for hovers.
+    pub fn markdown(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(description) = &self.description {
+            lines.push(description.clone());
+        }
+        if let Some(vendor) = &self.vendor {
+            lines.push(format!("**Vendor:** {}", vendor));
+        }
+        if !self.tools.is_empty() {
+            lines.push(format!("**Typical tools:** {}", self.tools.join(", ")));
+        }
+        if let Some(docs) = &self.docs {
+            lines.push(format!("[Documentation]({})", docs));
+        }
+        lines.join("\n\n")
+    }
+}
+
+/// Well-known package families by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KnownFamilies {
+    families: HashMap<String, KnownFamily>,
+}
+
+impl KnownFamilies {
+    /// Get the families bundled with the server.
+    pub fn bundled() -> Self {
+        Self {
+            families: serde_json::from_str(BUNDLED).expect("bundled known families are valid JSON"),
+        }
+    }
+
+    /// Replace or add families from a JSON object of the bundled format, e.g.
+    /// `{"studio_tools": {"description": "In-house tools"}}`.
+    ///
+    /// Families are replaced as a whole.
+    pub fn merge_json(&mut self, json: &str) -> Result<usize, serde_json::Error> {
+        let families: HashMap<String, KnownFamily> = serde_json::from_str(json)?;
+        let count = families.len();
+        self.families.extend(families);
+        Ok(count)
+    }
+
+    /// Look up a family by name.
+    pub fn get(&self, family: &str) -> Option<&KnownFamily> {
+        self.families.get(family)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_and_overrides() {
+        let mut families = KnownFamilies::bundled();
+        for family in ["maya", "houdini", "nuke", "python", "usd"] {
+            assert!(families.get(family).is_some(), "{} is not bundled", family);
+        }
+
+        let merged = families
+            .merge_json(r#"{"maya": {"vendor": "Studio"}, "studio_tools": {"description": "In-house tools"}}"#)
+            .unwrap();
+        assert_eq!(merged, 2);
+        assert_eq!(
+            families.get("maya").unwrap().markdown(),
+            "**Vendor:** Studio"
+        );
+        assert_eq!(
+            families.get("studio_tools").unwrap().description.as_deref(),
+            Some("In-house tools")
+        );
+        assert_eq!(
+            families.get("nuke").unwrap().summary().as_deref(),
+            Some("Node-based compositing application (Foundry)")
+        );
+        assert!(families.merge_json("[]").is_err());
+    }
+}
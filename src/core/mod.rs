@@ -1,9 +1,23 @@
 //! Core types and traits for the Rez LSP server.
 
+pub mod builder;
+pub mod categories;
 pub mod error;
+pub mod explain;
+pub mod intern;
+pub mod known_families;
 pub mod traits;
 pub mod types;
+pub mod version_range;
+pub mod version_scheme;
 
+pub use builder::{NoName, NoVersion, PackageBuilder, RequirementBuilder, ResolvedContextBuilder};
+pub use categories::{package_category, PackageCategory};
 pub use error::*;
+pub use explain::{describe_constraint, explain_requirement, Locale};
+pub use intern::Interner;
+pub use known_families::{KnownFamilies, KnownFamily};
 pub use traits::*;
 pub use types::*;
+pub use version_range::VersionRange;
+pub use version_scheme::{BumpPart, VersionPolicy, VersionScheme};
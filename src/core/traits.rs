@@ -2,9 +2,11 @@
 
 use async_trait::async_trait;
 use std::path::Path;
+use std::sync::Arc;
 
 use super::{
     DependencyConflict, Package, Requirement, ResolvedContext, Result, Version, VersionConstraint,
+    VersionOrder,
 };
 
 /// Trait for Rez configuration management.
@@ -33,16 +35,37 @@ pub trait PackageDiscovery: Send + Sync {
     async fn scan_packages(&mut self) -> Result<()>;
 
     /// Find packages by name pattern.
-    async fn find_packages(&self, pattern: &str) -> Result<Vec<Package>>;
+    async fn find_packages(&self, pattern: &str) -> Result<Vec<Arc<Package>>>;
 
     /// Get all versions of a specific package.
-    async fn get_package_versions(&self, name: &str) -> Result<Vec<Package>>;
+    async fn get_package_versions(&self, name: &str) -> Result<Vec<Arc<Package>>>;
+
+    /// Get the versions of a family satisfying a constraint, sorted in
+    /// `order` and cut to the first `limit`.
+    ///
+    /// The default filters [`get_package_versions`](Self::get_package_versions);
+    /// implementations holding the index can avoid copying the rest of the
+    /// family.
+    async fn find_matching(
+        &self,
+        family: &str,
+        constraint: &VersionConstraint,
+        limit: Option<usize>,
+        order: VersionOrder,
+    ) -> Result<Vec<Arc<Package>>> {
+        let versions = self.get_package_versions(family).await?;
+        Ok(constraint
+            .select(&versions, limit, order)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
 
     /// Get all package names.
     async fn get_all_package_names(&self) -> Result<Vec<String>>;
 
     /// Get package by exact name and version.
-    async fn get_package(&self, name: &str, version: &Version) -> Result<Option<Package>>;
+    async fn get_package(&self, name: &str, version: &Version) -> Result<Option<Arc<Package>>>;
 
     /// Get discovery statistics.
     async fn get_stats(&self) -> Result<(usize, usize)>; // (families, total_packages)
@@ -94,6 +117,19 @@ pub trait CompletionProvider: Send + Sync {
     /// Provide package name completions.
     async fn complete_package_names(&self, prefix: &str) -> Result<Vec<CompletionItem>>;
 
+    /// Provide package name completions inside the requirement list of
+    /// `attribute`, e.g. `private_build_requires`.
+    ///
+    /// Defaults to [`CompletionProvider::complete_package_names`].
+    async fn complete_package_names_in(
+        &self,
+        prefix: &str,
+        attribute: Option<&str>,
+    ) -> Result<Vec<CompletionItem>> {
+        let _ = attribute;
+        self.complete_package_names(prefix).await
+    }
+
     /// Provide version completions for a package.
     async fn complete_versions(
         &self,
@@ -118,6 +154,20 @@ pub trait HoverProvider: Send + Sync {
         version: Option<&Version>,
     ) -> Result<Option<HoverInfo>>;
 
+    /// Provide hover information for the working copy of a package at
+    /// `location`, comparing it against the other indexed versions.
+    ///
+    /// Defaults to [`HoverProvider::hover_package`] for the version.
+    async fn hover_working_copy(
+        &self,
+        name: &str,
+        version: &Version,
+        location: &Path,
+    ) -> Result<Option<HoverInfo>> {
+        let _ = location;
+        self.hover_package(name, Some(version)).await
+    }
+
     /// Provide hover information for a requirement.
     async fn hover_requirement(&self, requirement: &str) -> Result<Option<HoverInfo>>;
 
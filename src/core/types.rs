@@ -1,8 +1,12 @@
 //! Core data types for the Rez LSP server.
 
 use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::builder::{PackageBuilder, RequirementBuilder, ResolvedContextBuilder};
 
 /// Represents a Rez package with all its metadata.
 ///
@@ -10,39 +14,37 @@ use std::path::PathBuf;
 /// has a name, version, and various metadata that describes its dependencies,
 /// tools, and environment configuration.
 ///
+/// The struct is non-exhaustive; construct it with [`Package::builder`].
+///
 /// # Examples
 ///
 /// ```rust
-/// use rez_lsp_server::core::{Package, Version};
-/// use std::path::PathBuf;
-/// use std::collections::HashMap;
+/// use rez_lsp_server::core::Package;
 ///
-/// let package = Package {
-///     name: "python".to_string(),
-///     version: Version::new("3.9.0"),
-///     description: Some("Python interpreter".to_string()),
-///     authors: vec!["Python Software Foundation".to_string()],
-///     requires: vec![],
-///     tools: vec!["python".to_string(), "pip".to_string()],
-///     variants: vec![],
-///     path: PathBuf::from("/packages/python/3.9.0"),
-///     metadata: HashMap::new(),
-/// };
+/// let package = Package::builder()
+///     .name("python")
+///     .version("3.9.0")
+///     .description("Python interpreter")
+///     .author("Python Software Foundation")
+///     .tools(["python", "pip"])
+///     .path("/packages/python/3.9.0")
+///     .build();
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Package {
-    /// Package name
-    pub name: String,
+    /// Package name, interned across the versions of a family
+    pub name: Arc<str>,
     /// Package version
     pub version: Version,
     /// Package description
     pub description: Option<String>,
-    /// Package authors
-    pub authors: Vec<String>,
+    /// Package authors, interned across packages
+    pub authors: Vec<Arc<str>>,
     /// Package requirements
     pub requires: Vec<Requirement>,
-    /// Package tools
-    pub tools: Vec<String>,
+    /// Package tools, interned across packages
+    pub tools: Vec<Arc<str>>,
     /// Package variants
     pub variants: Vec<Variant>,
     /// Package installation path
@@ -51,6 +53,12 @@ pub struct Package {
     pub metadata: HashMap<String, String>,
 }
 
+/// Package versions by family name.
+///
+/// Packages are shared, so copies of the index and of families handed to
+/// completion, hover or the resolvers do not copy their fields.
+pub type PackageIndex = HashMap<String, Vec<Arc<Package>>>;
+
 /// Represents a package version with semantic comparison support.
 ///
 /// Rez versions follow a flexible versioning scheme that supports
@@ -89,6 +97,16 @@ pub struct Version {
     pub tokens: Vec<VersionToken>,
 }
 
+/// Version string used for packages that have no version directory.
+///
+/// Unversioned packages live directly at `<family>/package.py` and are common
+/// for bleeding-edge local development packages.
+pub const NO_VERSION: &str = "_NO_VERSION";
+
+/// Version tokens marking a pre-release, compared case-insensitively once
+/// digits are stripped from the token.
+pub const PRERELEASE_TAGS: &[&str] = &["alpha", "beta", "rc", "pre", "preview", "dev"];
+
 /// A single token in a version string.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum VersionToken {
@@ -100,8 +118,18 @@ pub enum VersionToken {
     Underscore,
 }
 
+/// Prefix of ephemeral requirement names, e.g. `.foo.bar-1`.
+///
+/// Ephemerals are pure constraints shared by a resolve; no package provides
+/// them, so they are never looked up or resolved.
+pub const EPHEMERAL_PREFIX: char = '.';
+
 /// Represents a package requirement/dependency.
+///
+/// The struct is non-exhaustive; construct it with [`Requirement::new`],
+/// [`Requirement::parse`] or [`Requirement::builder`].
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Requirement {
     /// Package name
     pub name: String,
@@ -118,6 +146,7 @@ pub struct Requirement {
 /// Supports Rez's version constraint syntax:
 /// - `python` - Any version
 /// - `python==3.9` - Exact version
+/// - `python-3.9` - Version family (`3.9`, `3.9.1`, ...)
 /// - `python-3.7+` - Minimum version (inclusive)
 /// - `python<4` - Maximum version (exclusive)
 /// - `python-3.7+<4` - Range constraint
@@ -127,10 +156,15 @@ pub enum VersionConstraint {
     Any,
     /// Exact version match
     Exact(Version),
+    /// The version or any version below it, e.g. `3.9` matches `3.9.1`
+    Family(Version),
     /// Minimum version (inclusive)
     GreaterEqual(Version),
     /// Maximum version (exclusive)
     Less(Version),
+    /// Versions matching a pattern in which `*` stands for one token and
+    /// `**` for any number of tokens, e.g. `1.*` matches `1.2` and `1.2.3`
+    Wildcard(Version),
     /// Range constraint
     Range {
         /// Minimum version (inclusive)
@@ -144,10 +178,26 @@ pub enum VersionConstraint {
 
 impl VersionConstraint {
     /// Check if a version satisfies this constraint.
+    ///
+    /// Unversioned packages only satisfy constraints that accept any version.
     pub fn satisfies(&self, version: &Version) -> bool {
+        if version.is_unversioned() {
+            return match self {
+                VersionConstraint::Any => true,
+                VersionConstraint::Or(constraints) => {
+                    constraints.iter().any(|c| c.satisfies(version))
+                }
+                _ => false,
+            };
+        }
+
         match self {
             VersionConstraint::Any => true,
             VersionConstraint::Exact(v) => version == v,
+            VersionConstraint::Family(v) => version.tokens.starts_with(&v.tokens),
+            VersionConstraint::Wildcard(pattern) => {
+                matches_pattern(&pattern.tokens, &version.tokens)
+            }
             VersionConstraint::GreaterEqual(v) => version >= v,
             VersionConstraint::Less(v) => version < v,
             VersionConstraint::Range { min, max } => version >= min && version < max,
@@ -156,49 +206,85 @@ impl VersionConstraint {
     }
 
     /// Parse a Rez requirement string into a constraint.
+    ///
+    /// Alternatives separated by `|` parse into [`VersionConstraint::Or`],
+    /// e.g. `maya-2023|2025+` or `python==3.9|3.11`.
     pub fn parse(requirement: &str) -> Result<(String, Self), String> {
         let requirement = requirement.trim();
 
-        // Parse version constraints
-        if let Some(eq_pos) = requirement.find("==") {
-            let name = requirement[..eq_pos].to_string();
-            let version_str = &requirement[eq_pos + 2..];
-            let version = Version::new(version_str);
-            return Ok((name, VersionConstraint::Exact(version)));
+        // The family name ends where its version constraint starts
+        let name_end = requirement
+            .find(['-', '<', '='])
+            .unwrap_or(requirement.len());
+        let name = requirement[..name_end].to_string();
+        let range = &requirement[name_end..];
+        let range = range.strip_prefix('-').unwrap_or(range);
+        if range.is_empty() {
+            return Ok((name, VersionConstraint::Any));
         }
 
-        if let Some(range_start) = requirement.find('-') {
-            if let Some(plus_pos) = requirement[range_start..].find('+') {
-                let name = requirement[..range_start].to_string();
-                let version_str = &requirement[range_start + 1..range_start + plus_pos];
-                let version = Version::new(version_str);
-
-                // Check for upper bound
-                let remaining = &requirement[range_start + plus_pos + 1..];
-                if let Some(stripped) = remaining.strip_prefix('<') {
-                    let max_version = Version::new(stripped);
-                    return Ok((
-                        name,
-                        VersionConstraint::Range {
-                            min: version,
-                            max: max_version,
-                        },
-                    ));
-                } else {
-                    return Ok((name, VersionConstraint::GreaterEqual(version)));
-                }
-            }
-        }
+        let mut alternatives = range
+            .split('|')
+            .map(|alternative| {
+                Self::parse_alternative(alternative)
+                    .ok_or_else(|| format!("Empty version alternative in '{}'", requirement))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let constraint = if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            VersionConstraint::Or(alternatives)
+        };
+        Ok((name, constraint))
+    }
 
-        if let Some(lt_pos) = requirement.find('<') {
-            let name = requirement[..lt_pos].to_string();
-            let version_str = &requirement[lt_pos + 1..];
-            let version = Version::new(version_str);
-            return Ok((name, VersionConstraint::Less(version)));
+    /// Parse a single alternative of a version range, e.g. `3.7+<4`.
+    fn parse_alternative(alternative: &str) -> Option<Self> {
+        let alternative = alternative.trim();
+        if alternative.is_empty() {
+            return None;
+        }
+        if let Some(version) = alternative.strip_prefix("==") {
+            return Some(VersionConstraint::Exact(Version::new(version)));
+        }
+        if let Some(version) = alternative.strip_prefix('<') {
+            return Some(VersionConstraint::Less(Version::new(version)));
+        }
+        if let Some((min, max)) = alternative.split_once('+') {
+            let min = Version::new(min);
+            return Some(match max.strip_prefix('<') {
+                Some(max) => VersionConstraint::Range {
+                    min,
+                    max: Version::new(max),
+                },
+                None => VersionConstraint::GreaterEqual(min),
+            });
+        }
+        if alternative.contains('*') {
+            // A lone wildcard accepts every version
+            return Some(if alternative.trim_matches('*').is_empty() {
+                VersionConstraint::Any
+            } else {
+                VersionConstraint::Wildcard(Version::new(alternative))
+            });
         }
+        Some(VersionConstraint::Family(Version::new(alternative)))
+    }
+}
 
-        // No version constraint - any version
-        Ok((requirement.to_string(), VersionConstraint::Any))
+/// Check whether version tokens start with the tokens of a wildcard pattern.
+fn matches_pattern(pattern: &[VersionToken], tokens: &[VersionToken]) -> bool {
+    let Some((first, pattern)) = pattern.split_first() else {
+        return true;
+    };
+    match first {
+        VersionToken::String(wildcard) if wildcard == "**" => {
+            (0..=tokens.len()).any(|skip| matches_pattern(pattern, &tokens[skip..]))
+        }
+        _ => tokens.split_first().is_some_and(|(token, tokens)| {
+            (matches!(first, VersionToken::String(wildcard) if wildcard == "*") || first == token)
+                && matches_pattern(pattern, tokens)
+        }),
     }
 }
 
@@ -207,6 +293,7 @@ impl std::fmt::Display for VersionConstraint {
         match self {
             VersionConstraint::Any => write!(f, "*"),
             VersionConstraint::Exact(v) => write!(f, "=={}", v),
+            VersionConstraint::Family(v) | VersionConstraint::Wildcard(v) => write!(f, "{}", v),
             VersionConstraint::GreaterEqual(v) => write!(f, "{}+", v),
             VersionConstraint::Less(v) => write!(f, "<{}", v),
             VersionConstraint::Range { min, max } => write!(f, "{}+<{}", min, max),
@@ -231,7 +318,10 @@ pub struct Variant {
 }
 
 /// Represents a resolved package context.
+///
+/// The struct is non-exhaustive; construct it with [`ResolvedContext::builder`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ResolvedContext {
     /// List of resolved packages
     pub packages: Vec<Package>,
@@ -264,7 +354,7 @@ pub struct PlatformInfo {
 }
 
 /// Resolution statistics.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResolutionStats {
     /// Number of packages considered
     pub packages_considered: usize,
@@ -274,6 +364,15 @@ pub struct ResolutionStats {
     pub resolution_time_ms: u64,
     /// Number of conflicts encountered
     pub conflicts: usize,
+    /// Number of package variants tried
+    #[serde(default)]
+    pub variants_considered: usize,
+    /// Variants never tried because their package exceeded the fan-out limit
+    #[serde(default)]
+    pub variants_skipped: usize,
+    /// Largest number of variants tried for a single package
+    #[serde(default)]
+    pub largest_fanout: usize,
 }
 
 /// Represents a dependency conflict.
@@ -287,6 +386,70 @@ pub struct DependencyConflict {
     pub description: String,
 }
 
+impl Package {
+    /// Create a builder for a package.
+    pub fn builder() -> PackageBuilder {
+        PackageBuilder::new()
+    }
+}
+
+/// Order of the versions returned by constraint queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VersionOrder {
+    /// Highest version first
+    #[default]
+    Descending,
+    /// Lowest version first
+    Ascending,
+}
+
+impl VersionConstraint {
+    /// Get the versions of a family satisfying the constraint, sorted in
+    /// `order` and cut to the first `limit`.
+    ///
+    /// Works on owned and shared packages alike, e.g. `&[Arc<Package>]`.
+    pub fn select<'a, P: Borrow<Package>>(
+        &self,
+        versions: &'a [P],
+        limit: Option<usize>,
+        order: VersionOrder,
+    ) -> Vec<&'a P> {
+        let mut matching: Vec<&P> = versions
+            .iter()
+            .filter(|package| self.satisfies(&(*package).borrow().version))
+            .collect();
+        matching.sort_by(|a, b| {
+            let (a, b) = (&(*a).borrow().version, &(*b).borrow().version);
+            match order {
+                VersionOrder::Descending => b.cmp(a),
+                VersionOrder::Ascending => a.cmp(b),
+            }
+        });
+        if let Some(limit) = limit {
+            matching.truncate(limit);
+        }
+        matching
+    }
+}
+
+impl ResolvedContext {
+    /// Create a builder for a resolved context.
+    pub fn builder() -> ResolvedContextBuilder {
+        ResolvedContextBuilder::new()
+    }
+}
+
+impl PlatformInfo {
+    /// Describe the platform the server runs on.
+    pub fn current() -> Self {
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+        }
+    }
+}
+
 impl Version {
     /// Create a new version from a string.
     pub fn new(value: impl Into<String>) -> Self {
@@ -295,6 +458,33 @@ impl Version {
         Self { value, tokens }
     }
 
+    /// Create the placeholder version used by unversioned packages.
+    pub fn unversioned() -> Self {
+        Self::new(NO_VERSION)
+    }
+
+    /// Check if this is the placeholder version of an unversioned package.
+    pub fn is_unversioned(&self) -> bool {
+        self.value == NO_VERSION
+    }
+
+    /// Check if this is a pre-release, i.e. a token names an alpha, beta,
+    /// release candidate or development build, e.g. `2.0.0-beta.1` or
+    /// `3.1rc2`.
+    pub fn is_prerelease(&self) -> bool {
+        self.tokens.iter().any(|token| match token {
+            VersionToken::String(token) => {
+                let tag: String = token
+                    .chars()
+                    .filter(char::is_ascii_alphabetic)
+                    .collect::<String>()
+                    .to_ascii_lowercase();
+                PRERELEASE_TAGS.contains(&tag.as_str())
+            }
+            _ => false,
+        })
+    }
+
     /// Parse version tokens from a version string.
     fn parse_tokens(version: &str) -> Vec<VersionToken> {
         let mut tokens = Vec::new();
@@ -345,6 +535,14 @@ impl Ord for Version {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         use std::cmp::Ordering;
 
+        // Unversioned packages sort before any real version
+        match (self.is_unversioned(), other.is_unversioned()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            (false, false) => {}
+        }
+
         // Compare tokens one by one
         let max_len = self.tokens.len().max(other.tokens.len());
 
@@ -389,6 +587,11 @@ impl Ord for Version {
 }
 
 impl Requirement {
+    /// Create a builder for a requirement on `name`.
+    pub fn builder(name: impl Into<String>) -> RequirementBuilder {
+        RequirementBuilder::new(name)
+    }
+
     /// Create a new requirement.
     pub fn new(name: impl Into<String>, constraint: VersionConstraint) -> Self {
         Self {
@@ -419,6 +622,11 @@ impl Requirement {
         }
     }
 
+    /// Check whether the requirement is on an ephemeral, e.g. `.foo.bar-1`.
+    pub fn is_ephemeral(&self) -> bool {
+        self.name.starts_with(EPHEMERAL_PREFIX)
+    }
+
     /// Parse a Rez requirement string.
     pub fn parse(requirement_str: &str) -> Result<Self, String> {
         let requirement_str = requirement_str.trim();
@@ -455,14 +663,14 @@ impl std::fmt::Display for Requirement {
 
         match &self.constraint {
             VersionConstraint::Any => Ok(()),
-            VersionConstraint::Exact(v) => write!(f, "=={}", v),
-            VersionConstraint::GreaterEqual(v) => write!(f, "-{}+", v),
-            VersionConstraint::Less(v) => write!(f, "<{}", v),
-            VersionConstraint::Range { min, max } => write!(f, "-{}+<{}", min, max),
-            VersionConstraint::Or(constraints) => {
-                let constraint_strs: Vec<String> =
-                    constraints.iter().map(|c| format!("{:?}", c)).collect();
-                write!(f, "|{}", constraint_strs.join("|"))
+            // `==` and `<` follow the name directly, everything else after `-`
+            constraint => {
+                let range = constraint.to_string();
+                if range.starts_with(['<', '=']) {
+                    write!(f, "{}", range)
+                } else {
+                    write!(f, "-{}", range)
+                }
             }
         }
     }
@@ -590,6 +798,15 @@ mod tests {
             VersionConstraint::GreaterEqual(Version::new("3.7"))
         );
 
+        // Test version family
+        let req = Requirement::parse("maya-2024").unwrap();
+        assert_eq!(req.name, "maya");
+        assert_eq!(
+            req.constraint,
+            VersionConstraint::Family(Version::new("2024"))
+        );
+        assert_eq!(req.to_string(), "maya-2024");
+
         // Test conflict requirement
         let req = Requirement::parse("!python-4+").unwrap();
         assert_eq!(req.name, "python");
@@ -607,6 +824,47 @@ mod tests {
             req.constraint,
             VersionConstraint::GreaterEqual(Version::new("3.8"))
         );
+
+        // Test ephemeral requirement
+        let req = Requirement::parse("!.foo.bar-1").unwrap();
+        assert_eq!(req.name, ".foo.bar");
+        assert!(req.is_ephemeral());
+        assert!(req.conflict);
+        assert_eq!(req.constraint, VersionConstraint::Family(Version::new("1")));
+        assert!(!Requirement::parse("foo-1").unwrap().is_ephemeral());
+    }
+
+    #[test]
+    fn test_or_constraints() {
+        let req = Requirement::parse("~maya-2023|2025+").unwrap();
+        assert_eq!(req.name, "maya");
+        assert!(req.weak);
+        assert_eq!(
+            req.constraint,
+            VersionConstraint::Or(vec![
+                VersionConstraint::Family(Version::new("2023")),
+                VersionConstraint::GreaterEqual(Version::new("2025")),
+            ])
+        );
+        assert!(req.constraint.satisfies(&Version::new("2023.1")));
+        assert!(!req.constraint.satisfies(&Version::new("2024")));
+        assert!(req.constraint.satisfies(&Version::new("2026")));
+
+        // Requirements print back the way they were written
+        for requirement in [
+            "maya-2023|2025+",
+            "python==3.9|3.11",
+            "python<3|3.7+<4",
+            "!houdini-19.5|20.0",
+        ] {
+            assert_eq!(
+                Requirement::parse(requirement).unwrap().to_string(),
+                requirement
+            );
+        }
+
+        assert!(Requirement::parse("maya-2023|").is_err());
+        assert!(Requirement::parse("maya-|2023").is_err());
     }
 
     #[test]
@@ -620,5 +878,81 @@ mod tests {
         assert!(!VersionConstraint::GreaterEqual(Version::new("3.10")).satisfies(&version));
         assert!(VersionConstraint::Less(Version::new("4.0")).satisfies(&version));
         assert!(!VersionConstraint::Less(Version::new("3.9")).satisfies(&version));
+        assert!(VersionConstraint::Family(Version::new("3.9")).satisfies(&version));
+        assert!(!VersionConstraint::Family(Version::new("3.90")).satisfies(&version));
+        assert!(!VersionConstraint::Family(Version::new("3.9.0.1")).satisfies(&version));
+    }
+
+    #[test]
+    fn test_wildcard_constraints() {
+        let accepts = |requirement: &str, version: &str| {
+            Requirement::parse(requirement)
+                .unwrap()
+                .constraint
+                .satisfies(&Version::new(version))
+        };
+        assert_eq!(
+            Requirement::parse("foo-1.*").unwrap().constraint,
+            VersionConstraint::Wildcard(Version::new("1.*"))
+        );
+        assert_eq!(
+            Requirement::parse("foo-**").unwrap().constraint,
+            VersionConstraint::Any
+        );
+
+        // Partial versions accept their whole family
+        assert!(accepts("foo-1", "1.4.2"));
+        assert!(!accepts("foo-1", "10.0"));
+
+        assert!(accepts("foo-1.*", "1.2"));
+        assert!(accepts("foo-1.*", "1.2.3"));
+        assert!(!accepts("foo-1.*", "1"));
+        assert!(!accepts("foo-1.*", "11.2"));
+        assert!(accepts("foo-1.*.3", "1.2.3"));
+        assert!(!accepts("foo-1.*.3", "1.2.4"));
+        assert!(accepts("foo-1.**.3", "1.3"));
+        assert!(accepts("foo-1.**.3", "1.2.5.3"));
+        assert!(accepts("foo-2.*|1.0", "1.0.1"));
+        assert!(!accepts("foo-1.*", "_NO_VERSION"));
+        assert_eq!(
+            Requirement::parse("foo-1.*").unwrap().to_string(),
+            "foo-1.*"
+        );
+    }
+
+    #[test]
+    fn test_prerelease_versions() {
+        for version in [
+            "2.0.0-beta.1",
+            "1.0-alpha",
+            "3.1rc2",
+            "4.0.0-RC1",
+            "1.2.dev3",
+        ] {
+            assert!(Version::new(version).is_prerelease(), "{}", version);
+        }
+        for version in ["2.0.0", "1.0-develop", "2024.1-linux", "1.0a"] {
+            assert!(!Version::new(version).is_prerelease(), "{}", version);
+        }
+        assert!(!Version::unversioned().is_prerelease());
+    }
+
+    #[test]
+    fn test_unversioned_package_version() {
+        let unversioned = Version::unversioned();
+        assert!(unversioned.is_unversioned());
+        assert!(!Version::new("1.0.0").is_unversioned());
+
+        // Unversioned sorts before every real version
+        assert!(unversioned < Version::new("0"));
+        assert!(unversioned < Version::new("alpha"));
+        assert_eq!(
+            unversioned.cmp(&Version::unversioned()),
+            std::cmp::Ordering::Equal
+        );
+
+        assert!(VersionConstraint::Any.satisfies(&unversioned));
+        assert!(!VersionConstraint::GreaterEqual(Version::new("0")).satisfies(&unversioned));
+        assert!(!VersionConstraint::Less(Version::new("99")).satisfies(&unversioned));
     }
 }
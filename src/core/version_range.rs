@@ -0,0 +1,326 @@
+//! Sets of versions and their intersection.
+//!
+//! A [`VersionConstraint`] describes the versions accepted by one requirement.
+//! When a package requires the same family several times, e.g. in `requires`
+//! and in a variant, only versions accepted by all of them can be resolved.
+//! [`VersionRange`] represents such sets as unions of intervals so their
+//! intersection can be computed, displayed and checked for emptiness without
+//! a package index.
+
+use super::types::{Version, VersionConstraint, VersionToken};
+
+/// Check whether a version belongs to the family of `family`, e.g. `3.9.4`
+/// to the family of `3.9`.
+fn in_family(version: &Version, family: &Version) -> bool {
+    version.tokens.starts_with(&family.tokens)
+}
+
+/// Get the first version after the family of `family`, e.g. `3.10` for `3.9`.
+///
+/// Only families ending in a number have one.
+fn family_end(family: &Version) -> Option<Version> {
+    let Some(VersionToken::Number(last)) = family.tokens.last() else {
+        return None;
+    };
+    let head = family
+        .value
+        .rfind(['.', '-'])
+        .map_or("", |index| &family.value[..=index]);
+    Some(Version::new(format!("{}{}", head, last + 1)))
+}
+
+/// Upper end of a version interval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum UpperBound {
+    /// Versions below the version
+    Before(Version),
+    /// Versions up to and including the version
+    Through(Version),
+    /// Versions up to and including the family of the version
+    Family(Version),
+}
+
+impl UpperBound {
+    /// Check whether a version lies below the bound.
+    fn admits(&self, version: &Version) -> bool {
+        match self {
+            UpperBound::Before(bound) => version < bound,
+            UpperBound::Through(bound) => version <= bound,
+            UpperBound::Family(family) => version < family || in_family(version, family),
+        }
+    }
+
+    /// Check whether every version below this bound lies below `other`.
+    fn within(&self, other: &UpperBound) -> bool {
+        match (self, other) {
+            (UpperBound::Before(a), UpperBound::Before(b))
+            | (UpperBound::Through(a), UpperBound::Through(b))
+            | (UpperBound::Before(a), UpperBound::Through(b)) => a <= b,
+            (UpperBound::Through(a), UpperBound::Before(b)) => a < b,
+            (UpperBound::Before(a) | UpperBound::Through(a), UpperBound::Family(_)) => {
+                other.admits(a)
+            }
+            (UpperBound::Family(a), UpperBound::Family(b)) => {
+                in_family(a, b) || (a < b && !in_family(b, a))
+            }
+            (UpperBound::Family(_), UpperBound::Before(b) | UpperBound::Through(b)) => {
+                !self.admits(b)
+            }
+        }
+    }
+}
+
+/// Versions from an inclusive lower bound up to an upper bound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Interval {
+    lower: Option<Version>,
+    upper: Option<UpperBound>,
+}
+
+impl Interval {
+    fn is_empty(&self) -> bool {
+        match (&self.lower, &self.upper) {
+            (Some(lower), Some(upper)) => !upper.admits(lower),
+            _ => false,
+        }
+    }
+
+    fn contains(&self, version: &Version) -> bool {
+        self.lower.as_ref().is_none_or(|lower| version >= lower)
+            && self
+                .upper
+                .as_ref()
+                .is_none_or(|upper| upper.admits(version))
+    }
+
+    fn intersect(&self, other: &Interval) -> Interval {
+        let lower = match (&self.lower, &other.lower) {
+            (Some(a), Some(b)) => Some(a.max(b).clone()),
+            (a, b) => a.clone().or_else(|| b.clone()),
+        };
+        let upper = match (&self.upper, &other.upper) {
+            (Some(a), Some(b)) if b.within(a) => Some(b.clone()),
+            (a, b) => a.clone().or_else(|| b.clone()),
+        };
+        Interval { lower, upper }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.lower, &self.upper) {
+            (None, None) => write!(f, "*"),
+            (Some(lower), None) => write!(f, "{}+", lower),
+            (None, Some(UpperBound::Before(upper))) => write!(f, "<{}", upper),
+            (None, Some(UpperBound::Through(upper))) => write!(f, "<={}", upper),
+            (None, Some(UpperBound::Family(family))) => match family_end(family) {
+                Some(end) => write!(f, "<{}", end),
+                None => write!(f, "<={}", family),
+            },
+            (Some(lower), Some(UpperBound::Through(upper))) if lower == upper => {
+                write!(f, "=={}", lower)
+            }
+            (Some(lower), Some(UpperBound::Through(upper))) => write!(f, "{}+<={}", lower, upper),
+            (Some(lower), Some(UpperBound::Family(family))) if lower == family => {
+                write!(f, "{}", family)
+            }
+            // A family is the range up to the next version, e.g. `3.9+<3.10`
+            (Some(lower), Some(UpperBound::Before(upper))) => {
+                if family_end(lower).as_ref() == Some(upper) {
+                    write!(f, "{}", lower)
+                } else {
+                    write!(f, "{}+<{}", lower, upper)
+                }
+            }
+            (Some(lower), Some(UpperBound::Family(family))) => match family_end(family) {
+                Some(end) => write!(f, "{}+<{}", lower, end),
+                None => write!(f, "{}+<={}", lower, family),
+            },
+        }
+    }
+}
+
+/// A set of versions, such as the versions accepted by several requirements
+/// on the same family.
+///
+/// Displayed in constraint syntax, e.g. `3.9+<3.11` or `3.10|3.12`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionRange {
+    intervals: Vec<Interval>,
+}
+
+impl VersionRange {
+    /// Get the range of every version.
+    pub fn any() -> Self {
+        Self {
+            intervals: vec![Interval {
+                lower: None,
+                upper: None,
+            }],
+        }
+    }
+
+    /// Get the versions in both ranges.
+    pub fn intersect(&self, other: &VersionRange) -> VersionRange {
+        let intervals = self
+            .intervals
+            .iter()
+            .flat_map(|a| other.intervals.iter().map(move |b| a.intersect(b)))
+            .filter(|interval| !interval.is_empty())
+            .collect();
+        VersionRange { intervals }
+    }
+
+    /// Check whether no version lies in the range.
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Check whether a version lies in the range.
+    pub fn contains(&self, version: &Version) -> bool {
+        self.intervals
+            .iter()
+            .any(|interval| interval.contains(version))
+    }
+
+    /// Format a requirement on `name` accepting the versions of the range,
+    /// e.g. `python-3.10` or `python<4`.
+    pub fn to_requirement(&self, name: &str) -> String {
+        let constraint = self.to_string();
+        if constraint == "*" {
+            name.to_string()
+        } else if constraint.starts_with(['<', '=']) {
+            format!("{}{}", name, constraint)
+        } else {
+            format!("{}-{}", name, constraint)
+        }
+    }
+}
+
+impl From<&VersionConstraint> for VersionRange {
+    fn from(constraint: &VersionConstraint) -> Self {
+        let interval = |lower: Option<&Version>, upper: Option<UpperBound>| Interval {
+            lower: lower.cloned(),
+            upper,
+        };
+        let intervals = match constraint {
+            VersionConstraint::Any => vec![interval(None, None)],
+            VersionConstraint::Exact(version) => {
+                vec![interval(
+                    Some(version),
+                    Some(UpperBound::Through(version.clone())),
+                )]
+            }
+            VersionConstraint::Family(version) => vec![interval(
+                Some(version),
+                Some(UpperBound::Family(version.clone())),
+            )],
+            // Patterns are approximated by the family of their leading
+            // literal tokens, e.g. `1.*.3` by `1`
+            VersionConstraint::Wildcard(pattern) => {
+                let literal = pattern.value[..pattern.value.find('*').unwrap_or(0)]
+                    .trim_end_matches(['.', '-']);
+                if literal.is_empty() {
+                    vec![interval(None, None)]
+                } else {
+                    let family = Version::new(literal);
+                    vec![interval(
+                        Some(&family),
+                        Some(UpperBound::Family(family.clone())),
+                    )]
+                }
+            }
+            VersionConstraint::GreaterEqual(version) => vec![interval(Some(version), None)],
+            VersionConstraint::Less(version) => {
+                vec![interval(None, Some(UpperBound::Before(version.clone())))]
+            }
+            VersionConstraint::Range { min, max } => {
+                vec![interval(Some(min), Some(UpperBound::Before(max.clone())))]
+            }
+            VersionConstraint::Or(constraints) => constraints
+                .iter()
+                .flat_map(|constraint| VersionRange::from(constraint).intervals)
+                .collect(),
+        };
+        VersionRange {
+            intervals: intervals
+                .into_iter()
+                .filter(|interval| !interval.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let intervals: Vec<String> = self.intervals.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", intervals.join("|"))
+    }
+}
+
+impl VersionConstraint {
+    /// Get the versions accepted by both constraints.
+    pub fn intersect(&self, other: &VersionConstraint) -> VersionRange {
+        VersionRange::from(self).intersect(&VersionRange::from(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(constraint: &str) -> VersionRange {
+        let (_, constraint) = VersionConstraint::parse(&format!("pkg{}", constraint)).unwrap();
+        VersionRange::from(&constraint)
+    }
+
+    #[test]
+    fn test_intersection() {
+        let supported = range("-3.9+<3.11");
+        assert_eq!(supported.intersect(&range("-3.10")).to_string(), "3.10");
+        assert_eq!(
+            supported.intersect(&range("<3.10.2")).to_string(),
+            "3.9+<3.10.2"
+        );
+        assert_eq!(supported.intersect(&range("<3.10")).to_string(), "3.9");
+        assert_eq!(supported.intersect(&range("")).to_string(), "3.9+<3.11");
+        assert_eq!(
+            supported.intersect(&range("==3.9.7")).to_string(),
+            "==3.9.7"
+        );
+        assert_eq!(
+            range("-3.9").intersect(&range("-3.9.4+")).to_string(),
+            "3.9.4+<3.10"
+        );
+        assert_eq!(
+            range("-3.9").intersect(&range("-3.9.4")).to_string(),
+            "3.9.4"
+        );
+
+        assert!(supported.intersect(&range("-2.7")).is_empty());
+        assert!(supported.intersect(&range("-3.11")).is_empty());
+        assert!(range("==3.9").intersect(&range("==3.10")).is_empty());
+        assert!(range("-3.9").intersect(&range("-3.10")).is_empty());
+
+        // Alternatives intersect one by one
+        let alternatives = range("-3.9|3.11+");
+        assert_eq!(alternatives.intersect(&supported).to_string(), "3.9");
+        assert_eq!(
+            alternatives.intersect(&range("-3.7+<3.12")).to_string(),
+            "3.9|3.11"
+        );
+        assert!(alternatives.intersect(&range("-3.10")).is_empty());
+    }
+
+    #[test]
+    fn test_contains() {
+        let combined = range("-2024").intersect(&range("-2023+"));
+        assert_eq!(combined.to_requirement("maya"), "maya-2024");
+        assert!(combined.contains(&Version::new("2024.1")));
+        assert!(!combined.contains(&Version::new("2025")));
+
+        assert_eq!(range("").to_requirement("maya"), "maya");
+        assert_eq!(range("<2025").to_requirement("maya"), "maya<2025");
+        assert_eq!(range("==2024.2").to_requirement("maya"), "maya==2024.2");
+    }
+}
@@ -0,0 +1,368 @@
+//! Version scheme policies for package repositories.
+//!
+//! Rez accepts almost any version string, but studios often enforce stricter
+//! schemes per repository, such as strict semantic versioning for libraries or
+//! calendar versioning for pipeline configuration. A [`VersionPolicy`] maps
+//! repositories to the [`VersionScheme`] their packages must follow.
+
+use chrono::{Datelike, NaiveDate};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+use super::types::{Version, VersionToken};
+
+/// Format that package versions of a repository must follow.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum VersionScheme {
+    /// Rez versions, recommending numeric `major.minor.patch` releases
+    #[default]
+    Rez,
+    /// Strict semantic versioning: `MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]`
+    Semver,
+    /// Calendar versioning: `YYYY.MM[.MICRO]`
+    CalVer,
+}
+
+/// Component of a version to increment when bumping it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BumpPart {
+    /// Increment the major version and reset the rest
+    Major,
+    /// Increment the minor version and reset the patch version
+    Minor,
+    /// Increment the patch version
+    #[default]
+    Patch,
+}
+
+impl BumpPart {
+    /// Parse a bump part name (`major`, `minor` or `patch`).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "major" => Some(Self::Major),
+            "minor" => Some(Self::Minor),
+            "patch" => Some(Self::Patch),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::Major => 0,
+            Self::Minor => 1,
+            Self::Patch => 2,
+        }
+    }
+}
+
+impl VersionScheme {
+    /// Parse a scheme name (`rez`, `semver` or `calver`).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "rez" => Some(Self::Rez),
+            "semver" => Some(Self::Semver),
+            "calver" => Some(Self::CalVer),
+            _ => None,
+        }
+    }
+
+    /// Get the name of the scheme.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rez => "rez",
+            Self::Semver => "semver",
+            Self::CalVer => "calver",
+        }
+    }
+
+    /// Get an example version following the scheme.
+    pub fn example(&self) -> &'static str {
+        match self {
+            Self::Rez | Self::Semver => "1.0.0",
+            Self::CalVer => "2024.06.0",
+        }
+    }
+
+    /// Check whether a version string follows the scheme.
+    pub fn conforms(&self, version: &str) -> bool {
+        match self {
+            Self::Rez => is_rez_release(version),
+            Self::Semver => is_strict_semver(version),
+            Self::CalVer => calver_parts(version).is_some(),
+        }
+    }
+
+    /// Describe why a version does not follow the scheme.
+    ///
+    /// Returns `None` for conforming versions.
+    pub fn violation(&self, version: &str) -> Option<String> {
+        if self.conforms(version) {
+            return None;
+        }
+        Some(match self {
+            Self::Rez => "Version format doesn't follow semantic versioning".to_string(),
+            Self::Semver => format!(
+                "Version '{}' is not a strict semantic version (MAJOR.MINOR.PATCH)",
+                version
+            ),
+            Self::CalVer => format!(
+                "Version '{}' is not a calendar version (YYYY.MM[.MICRO])",
+                version
+            ),
+        })
+    }
+
+    /// Compare two versions for ordering under the scheme.
+    ///
+    /// Versions following the scheme order after those that do not, so the
+    /// newest conforming version is the greatest. Strict semver additionally
+    /// orders pre-releases before their release.
+    pub fn compare(&self, a: &Version, b: &Version) -> Ordering {
+        let conforming = self.conforms(&a.value).cmp(&self.conforms(&b.value));
+        conforming.then_with(|| match self {
+            Self::Semver => compare_semver(&a.value, &b.value),
+            _ => a.cmp(b),
+        })
+    }
+
+    /// Get the next version after `version`.
+    ///
+    /// Calendar versions move to the month of `today`, incrementing the micro
+    /// version within the same month; `part` only applies to the other
+    /// schemes. Pre-release and build suffixes are dropped.
+    pub fn bump(&self, version: &Version, part: BumpPart, today: NaiveDate) -> String {
+        match self {
+            Self::CalVer => {
+                let micro = match calver_parts(&version.value) {
+                    Some((year, month, micro))
+                        if year == today.year() as u64 && month == today.month() as u64 =>
+                    {
+                        micro.map_or(1, |micro| micro + 1)
+                    }
+                    _ => 0,
+                };
+                format!("{}.{:02}.{}", today.year(), today.month(), micro)
+            }
+            Self::Rez | Self::Semver => {
+                let mut numbers: Vec<u64> = version
+                    .tokens
+                    .iter()
+                    .map_while(|token| match token {
+                        VersionToken::Number(n) => Some(*n),
+                        _ => None,
+                    })
+                    .collect();
+
+                let len = match self {
+                    Self::Semver => 3,
+                    _ => numbers.len().max(part.index() + 1),
+                };
+                numbers.resize(len, 0);
+                numbers[part.index()] += 1;
+                for number in &mut numbers[part.index() + 1..] {
+                    *number = 0;
+                }
+
+                numbers
+                    .iter()
+                    .map(u64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(".")
+            }
+        }
+    }
+}
+
+/// Check for numeric releases with an optional alphabetic suffix, such as
+/// `1.2.3` or `2.0rc1`.
+fn is_rez_release(version: &str) -> bool {
+    let suffix_start = version
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(version.len());
+    let (numbers, suffix) = version.split_at(suffix_start);
+
+    numbers
+        .split('.')
+        .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+        && suffix.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// Check for `MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]` without leading zeros.
+fn is_strict_semver(version: &str) -> bool {
+    let (rest, build) = match version.split_once('+') {
+        Some((rest, build)) => (rest, Some(build)),
+        None => (version, None),
+    };
+    let (core, prerelease) = match rest.split_once('-') {
+        Some((core, prerelease)) => (core, Some(prerelease)),
+        None => (rest, None),
+    };
+
+    let identifiers_valid = |identifiers: &str| {
+        identifiers
+            .split('.')
+            .all(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'))
+    };
+    let numbers: Vec<&str> = core.split('.').collect();
+
+    numbers.len() == 3
+        && numbers.iter().all(|n| {
+            !n.is_empty()
+                && n.bytes().all(|b| b.is_ascii_digit())
+                && (n.len() == 1 || !n.starts_with('0'))
+        })
+        && prerelease.is_none_or(identifiers_valid)
+        && build.is_none_or(identifiers_valid)
+}
+
+/// Split a `YYYY.MM[.MICRO]` version into its year, month and micro version.
+fn calver_parts(version: &str) -> Option<(u64, u64, Option<u64>)> {
+    let mut parts = version.split('.');
+    let year = parts.next().filter(|year| year.len() == 4)?;
+    let month = parts.next().filter(|month| matches!(month.len(), 1 | 2))?;
+    let micro = parts.next();
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let number = |part: &str| {
+        part.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| part.parse::<u64>().ok())
+            .flatten()
+    };
+    let month_number = number(month).filter(|m| (1..=12).contains(m))?;
+    let micro = match micro {
+        Some(micro) => Some(number(micro)?),
+        None => None,
+    };
+
+    Some((number(year)?, month_number, micro))
+}
+
+/// Compare semantic versions, ordering pre-releases before their release.
+fn compare_semver(a: &str, b: &str) -> Ordering {
+    let split = |version: &str| {
+        let version = version.split('+').next().unwrap_or(version);
+        match version.split_once('-') {
+            Some((core, prerelease)) => (Version::new(core), Some(Version::new(prerelease))),
+            None => (Version::new(version), None),
+        }
+    };
+    let (a_core, a_pre) = split(a);
+    let (b_core, b_pre) = split(b);
+
+    a_core.cmp(&b_core).then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => a.cmp(&b),
+    })
+}
+
+/// Version schemes of the package repositories.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionPolicy {
+    /// Scheme of packages outside every configured repository
+    pub default_scheme: VersionScheme,
+    /// Schemes of individual repositories
+    pub repositories: Vec<(PathBuf, VersionScheme)>,
+}
+
+impl VersionPolicy {
+    /// Get the scheme that applies to a package file or directory.
+    ///
+    /// The most specific repository containing the path wins.
+    pub fn scheme_for(&self, path: &Path) -> VersionScheme {
+        self.repositories
+            .iter()
+            .filter(|(repository, _)| path.starts_with(repository))
+            .max_by_key(|(repository, _)| repository.components().count())
+            .map(|(_, scheme)| *scheme)
+            .unwrap_or(self.default_scheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, 15).unwrap()
+    }
+
+    #[test]
+    fn test_conforms() {
+        assert!(VersionScheme::Rez.conforms("1.2.3"));
+        assert!(VersionScheme::Rez.conforms("2.0rc1"));
+        assert!(!VersionScheme::Rez.conforms("v1-final"));
+
+        assert!(VersionScheme::Semver.conforms("1.2.3"));
+        assert!(VersionScheme::Semver.conforms("1.2.3-beta.1+build.5"));
+        assert!(!VersionScheme::Semver.conforms("1.2"));
+        assert!(!VersionScheme::Semver.conforms("01.2.3"));
+
+        assert!(VersionScheme::CalVer.conforms("2024.06"));
+        assert!(VersionScheme::CalVer.conforms("2024.6.2"));
+        assert!(!VersionScheme::CalVer.conforms("2024.13.0"));
+        assert!(!VersionScheme::CalVer.conforms("1.2.3"));
+    }
+
+    #[test]
+    fn test_compare_semver_prereleases() {
+        let scheme = VersionScheme::Semver;
+        let release = Version::new("1.0.0");
+        let beta = Version::new("1.0.0-beta");
+        let loose = Version::new("2.0");
+
+        assert_eq!(scheme.compare(&beta, &release), Ordering::Less);
+        assert_eq!(scheme.compare(&loose, &beta), Ordering::Less);
+    }
+
+    #[test]
+    fn test_bump() {
+        let today = date(2024, 6);
+        let version = Version::new("1.2.3-beta");
+
+        assert_eq!(
+            VersionScheme::Semver.bump(&version, BumpPart::Minor, today),
+            "1.3.0"
+        );
+        assert_eq!(
+            VersionScheme::Rez.bump(&Version::new("1.2"), BumpPart::Patch, today),
+            "1.2.1"
+        );
+        assert_eq!(
+            VersionScheme::CalVer.bump(&Version::new("2024.06.1"), BumpPart::Major, today),
+            "2024.06.2"
+        );
+        assert_eq!(
+            VersionScheme::CalVer.bump(&Version::new("2024.05.3"), BumpPart::Patch, today),
+            "2024.06.0"
+        );
+    }
+
+    #[test]
+    fn test_policy_prefers_most_specific_repository() {
+        let policy = VersionPolicy {
+            default_scheme: VersionScheme::Rez,
+            repositories: vec![
+                (PathBuf::from("/studio"), VersionScheme::Semver),
+                (PathBuf::from("/studio/config"), VersionScheme::CalVer),
+            ],
+        };
+
+        assert_eq!(
+            policy.scheme_for(Path::new("/studio/config/shows/2024.06.0/package.py")),
+            VersionScheme::CalVer
+        );
+        assert_eq!(
+            policy.scheme_for(Path::new("/studio/libs/foo/1.0.0/package.py")),
+            VersionScheme::Semver
+        );
+        assert_eq!(
+            policy.scheme_for(Path::new("/home/me/packages/foo/package.py")),
+            VersionScheme::Rez
+        );
+    }
+}
@@ -4,28 +4,690 @@ use async_trait::async_trait;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
+use super::metrics::{DiscoveryMetrics, RepositoryScanMetrics};
+use super::pick_cache::PickCache;
+use super::resolve_cache::{PendingResolve, ResolveCache};
+use super::shared_index::{IndexSnapshot, SharedIndex};
+use super::walk::WorkspaceWalker;
+use crate::analysis::{
+    load_environments, FreshnessReport, GraphStats, PinnedEnvironment, StaleEnvironmentReport,
+    UsageIndex,
+};
 use crate::config::Config;
-use crate::core::{DiscoveryError, Package, PackageDiscovery, Result, Version};
+use crate::core::{
+    DiscoveryError, Interner, Package, PackageDiscovery, PackageIndex, Requirement,
+    ResolvedContext, Result, Variant, Version, VersionConstraint, VersionOrder, NO_VERSION,
+};
+use crate::parser::encoding::read_source;
+use crate::parser::{find_requirements, VARIANTS_ATTRIBUTE};
+use crate::performance::{CacheManager, RepositoryClass};
+use crate::resolver::create_resolver;
+
+/// A package family indexed by a running scan.
+#[derive(Debug, Clone)]
+pub struct ScannedFamily {
+    /// Family name
+    pub name: String,
+    /// Indexed versions of the family, oldest first
+    pub versions: Vec<Arc<Package>>,
+}
+
+/// Stream of the families indexed by a scan, ending when the scan finishes.
+pub type FamilyStream = mpsc::UnboundedReceiver<ScannedFamily>;
 
 /// Implementation of PackageDiscovery trait.
 pub struct PackageDiscoveryImpl {
     config: Config,
-    package_cache: HashMap<String, Vec<Package>>,
+    package_cache: PackageIndex,
+    usage_index: UsageIndex,
+    metrics: DiscoveryMetrics,
+    elided_versions: HashMap<String, usize>,
+    shared_generation: Option<u64>,
+    family_sender: Option<mpsc::UnboundedSender<ScannedFamily>>,
+    /// Incremented whenever the indexed versions change
+    generation: u64,
+    pick_cache: PickCache,
+    resolve_cache: Arc<ResolveCache>,
+    /// Authors and tool names shared by the indexed packages
+    interner: Interner,
+    /// Scanned families reused by later scans until they expire
+    family_cache: Option<Arc<CacheManager>>,
+    /// Worker evaluating package definitions, shared by the scans
+    #[cfg(feature = "python-eval")]
+    sandbox: super::PythonSandbox,
 }
 
 impl PackageDiscoveryImpl {
     /// Create a new package discovery instance.
     pub fn new(config: Config) -> Self {
         Self {
-            config,
             package_cache: HashMap::new(),
+            usage_index: UsageIndex::new(),
+            metrics: DiscoveryMetrics::default(),
+            elided_versions: HashMap::new(),
+            shared_generation: None,
+            family_sender: None,
+            generation: 0,
+            pick_cache: PickCache::new(),
+            resolve_cache: Arc::new(ResolveCache::new()),
+            interner: Interner::new(),
+            family_cache: None,
+            #[cfg(feature = "python-eval")]
+            sandbox: super::PythonSandbox::from_config(&config),
+            config,
+        }
+    }
+
+    /// Reuse the families scanned into `cache` on later scans.
+    ///
+    /// Families are cached per directory and expire after the TTL of their
+    /// repository class, or when [`CacheManager::invalidate_changed_paths`]
+    /// reports a change below them.
+    pub fn with_family_cache(mut self, cache: Arc<CacheManager>) -> Self {
+        self.family_cache = Some(cache);
+        self
+    }
+
+    /// Stream the families indexed by the next scan as soon as each one is,
+    /// e.g. to complete package names before the scan finishes.
+    ///
+    /// Families restored from a shared index are not streamed, as the whole
+    /// index is available at once.
+    pub fn stream_families(&mut self) -> FamilyStream {
+        let (sender, stream) = mpsc::unbounded_channel();
+        self.family_sender = Some(sender);
+        stream
+    }
+
+    /// Replace the indexed versions of a family.
+    pub fn insert_family<P: Into<Arc<Package>>>(&mut self, name: String, versions: Vec<P>) {
+        let versions: Vec<Arc<Package>> = versions.into_iter().map(Into::into).collect();
+        self.usage_index.update_family(&name, &versions);
+        self.package_cache.insert(name, versions);
+        self.generation += 1;
+    }
+
+    /// Get the configuration used for discovery.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Replace the configuration, e.g. after the settings changed.
+    ///
+    /// Starts a new generation, so versions picked and contexts resolved
+    /// with the previous resolve strategy or preferred versions are not
+    /// reused. The indexed packages are kept; settings changing what is
+    /// scanned need a rescan.
+    pub fn set_config(&mut self, config: Config) {
+        #[cfg(feature = "python-eval")]
+        {
+            self.sandbox = super::PythonSandbox::from_config(&config);
+        }
+        self.config = config;
+        self.generation += 1;
+    }
+
+    /// Get the requirement usage index of the discovered packages.
+    pub fn usage_index(&self) -> &UsageIndex {
+        &self.usage_index
+    }
+
+    /// Get the metrics of the last package scan.
+    pub fn metrics(&self) -> &DiscoveryMetrics {
+        &self.metrics
+    }
+
+    /// Get all discovered packages indexed by family name.
+    pub fn packages(&self) -> &PackageIndex {
+        &self.package_cache
+    }
+
+    /// Get the generation of the index, incremented whenever the indexed
+    /// versions change.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Get the number of [`Self::pick_version`] calls answered from the pick
+    /// cache and computed.
+    pub fn pick_cache_stats(&self) -> (u64, u64) {
+        self.pick_cache.stats()
+    }
+
+    /// Get the context resolved for `requirements` at the current generation
+    /// of the index, `None` when resolving them failed.
+    ///
+    /// When the requirements were not resolved yet, a [`PendingResolve`]
+    /// over a copy of the index is returned instead; run it after releasing
+    /// the index lock and [`PendingResolve::finish`] it to cache the result.
+    pub fn cached_resolve(
+        &self,
+        requirements: &[Requirement],
+    ) -> std::result::Result<Option<ResolvedContext>, PendingResolve> {
+        let key = ResolveCache::key(requirements);
+        match self.resolve_cache.get(self.generation, &key) {
+            Some(context) => Ok(context),
+            None => Err(PendingResolve::new(
+                create_resolver(&self.config, self.package_cache.clone()),
+                self.resolve_cache.clone(),
+                self.generation,
+                key,
+            )),
+        }
+    }
+
+    /// Get the number of older versions of a family left out of the index by
+    /// [`Config::max_versions_per_family`].
+    pub fn elided_versions(&self, name: &str) -> usize {
+        self.elided_versions.get(name).copied().unwrap_or(0)
+    }
+
+    /// Get the version of a family picked for a constraint.
+    ///
+    /// A satisfying version of [`Config::preferred_versions`] is picked
+    /// first, otherwise the highest or lowest satisfying version following
+    /// [`Config::resolve_strategy`]. With [`Config::exclude_prereleases`] set,
+    /// pre-releases are only picked when no other version satisfies the
+    /// constraint, e.g. for an explicit `foo==2.0.0-beta1`.
+    ///
+    /// Picks are cached per family and constraint until the index changes.
+    pub fn pick_version(&self, name: &str, constraint: &VersionConstraint) -> Option<&Package> {
+        let name = self.config.canonical_package_name(name);
+        let versions = self.package_cache.get(name)?;
+        let position = self
+            .pick_cache
+            .get_or_pick(self.generation, name, constraint, || {
+                let candidates = self.pick_candidates(name, constraint)?;
+                let picked = self
+                    .config
+                    .resolve_strategy
+                    .pick(candidates, self.config.preferred_versions.get(name))?;
+                versions
+                    .iter()
+                    .position(|package| std::ptr::eq(&**package, picked))
+            })?;
+        versions.get(position).map(|package| &**package)
+    }
+
+    /// Get the latest version of a family, whatever the resolve strategy.
+    ///
+    /// Pre-releases are left out as by [`Self::pick_version`].
+    pub fn latest_version(&self, name: &str) -> Option<&Package> {
+        let name = self.config.canonical_package_name(name);
+        self.pick_candidates(name, &VersionConstraint::Any)?
+            .into_iter()
+            .max_by(|a, b| a.version.cmp(&b.version))
+    }
+
+    /// Get the versions of a family satisfying a constraint, sorted in
+    /// `order` and cut to the first `limit`.
+    pub fn matching_versions(
+        &self,
+        name: &str,
+        constraint: &VersionConstraint,
+        limit: Option<usize>,
+        order: VersionOrder,
+    ) -> Vec<&Package> {
+        let name = self.config.canonical_package_name(name);
+        self.package_cache
+            .get(name)
+            .map(|versions| {
+                constraint
+                    .select(versions, limit, order)
+                    .into_iter()
+                    .map(|package| &**package)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the versions of a family [`Self::pick_version`] chooses from.
+    fn pick_candidates(&self, name: &str, constraint: &VersionConstraint) -> Option<Vec<&Package>> {
+        let matching = constraint.select(
+            self.package_cache.get(name)?,
+            None,
+            VersionOrder::Descending,
+        );
+        let (prereleases, releases): (Vec<&Package>, Vec<&Package>) = matching
+            .into_iter()
+            .map(|package| &**package)
+            .partition(|package| {
+                self.config.exclude_prereleases && package.version.is_prerelease()
+            });
+        Some(if releases.is_empty() {
+            prereleases
+        } else {
+            releases
+        })
+    }
+
+    /// Get the generation of the shared index the packages were loaded from
+    /// or published to, if the index is shared.
+    pub fn shared_generation(&self) -> Option<u64> {
+        self.shared_generation
+    }
+
+    /// Build the index, reusing the index shared by other server instances
+    /// when [`Config::shared_index_dir`] is set.
+    ///
+    /// A fresh shared index is loaded instead of scanning. Otherwise the
+    /// repositories are scanned and, if no other instance is scanning them,
+    /// the result is published for the others. Repositories that fail to scan
+    /// keep the result from being published.
+    pub async fn scan_shared(&mut self) -> Result<()> {
+        let Some(shared) = SharedIndex::from_config(&self.config) else {
+            return self.scan_packages().await;
+        };
+        if let Some(snapshot) = shared.load_fresh() {
+            self.restore(snapshot);
+            return Ok(());
+        }
+
+        let lock = match shared.try_lock() {
+            Ok(Some(lock)) => lock,
+            Ok(None) => {
+                info!("Shared package index is being rebuilt by another instance, scanning in-process");
+                return self.scan_packages().await;
+            }
+            Err(e) => {
+                warn!("Failed to lock the shared package index: {}", e);
+                return self.scan_packages().await;
+            }
+        };
+
+        // Another instance may have published since the index was checked
+        let previous = shared.load();
+        if let Some(snapshot) = previous.clone().filter(|s| shared.is_fresh(s)) {
+            self.restore(snapshot);
+            return Ok(());
+        }
+
+        self.scan_packages().await?;
+        if self
+            .metrics
+            .repositories
+            .iter()
+            .any(|repo| repo.error.is_some())
+        {
+            warn!("Not publishing the shared package index, some repositories failed to scan");
+            return Ok(());
+        }
+        match shared.publish(
+            &lock,
+            previous.as_ref(),
+            &self.package_cache,
+            &self.elided_versions,
+            &self.metrics,
+        ) {
+            Ok(generation) => {
+                info!(
+                    "Published shared package index generation {} to {:?}",
+                    generation,
+                    shared.index_path()
+                );
+                self.shared_generation = Some(generation);
+            }
+            Err(e) => warn!("Failed to publish the shared package index: {}", e),
+        }
+        Ok(())
+    }
+
+    /// Replace the index with a snapshot published by another instance.
+    fn restore(&mut self, mut snapshot: IndexSnapshot) {
+        info!(
+            "Loaded shared package index generation {} ({} package families)",
+            snapshot.generation,
+            snapshot.packages.len()
+        );
+        self.usage_index.clear();
+        self.interner = Interner::new();
+        for (family, versions) in &mut snapshot.packages {
+            // Deserialized strings are not shared yet
+            for package in versions.iter_mut().filter_map(Arc::get_mut) {
+                package.name = self.interner.intern(&package.name);
+                for value in package.authors.iter_mut().chain(package.tools.iter_mut()) {
+                    *value = self.interner.intern(value);
+                }
+            }
+            self.usage_index.update_family(family, versions);
+        }
+        self.package_cache = snapshot.packages;
+        self.elided_versions = snapshot.elided_versions;
+        self.metrics = snapshot.metrics;
+        self.shared_generation = Some(snapshot.generation);
+        self.family_sender = None;
+        self.generation += 1;
+    }
+
+    /// Add a package to the index, replacing the indexed package of the same
+    /// version, e.g. after copying a release into the local repository.
+    pub fn insert_package(&mut self, package: impl Into<Arc<Package>>) {
+        let package: Arc<Package> = package.into();
+        let name = package.name.to_string();
+        let versions = self.package_cache.entry(name.clone()).or_default();
+        versions.retain(|indexed| indexed.version != package.version);
+        let position = versions.partition_point(|indexed| indexed.version < package.version);
+        versions.insert(position, package);
+        self.usage_index.update_family(&name, versions);
+        self.generation += 1;
+    }
+
+    /// Remove a version of a family from the index.
+    ///
+    /// Returns whether the version was indexed. A family left without
+    /// versions is removed as well.
+    pub fn remove_package(&mut self, name: &str, version: &Version) -> bool {
+        let name = self.config.canonical_package_name(name).to_string();
+        let Some(versions) = self.package_cache.get_mut(&name) else {
+            return false;
+        };
+        let before = versions.len();
+        versions.retain(|indexed| &indexed.version != version);
+        let removed = versions.len() != before;
+        if versions.is_empty() {
+            self.package_cache.remove(&name);
+            self.usage_index.remove_family(&name);
+        } else if removed {
+            self.usage_index.update_family(&name, versions);
+        }
+        if removed {
+            self.generation += 1;
+        }
+        removed
+    }
+
+    /// Remove every version of a family from the index.
+    ///
+    /// Returns whether the family was indexed.
+    pub fn remove_family(&mut self, name: &str) -> bool {
+        let name = self.config.canonical_package_name(name).to_string();
+        if self.package_cache.remove(&name).is_none() {
+            return false;
+        }
+        self.usage_index.remove_family(&name);
+        self.elided_versions.remove(&name);
+        self.generation += 1;
+        true
+    }
+
+    /// Read a single version of a family from the repositories into the
+    /// index, without scanning the rest of the family.
+    ///
+    /// As in a scan, the last repository containing the version wins.
+    /// Returns `None` when no repository contains the version.
+    pub async fn load_version(
+        &mut self,
+        name: &str,
+        version: &str,
+    ) -> Result<Option<Arc<Package>>> {
+        let found = self.read_version(name, version).await?.map(Arc::new);
+        if let Some(package) = &found {
+            self.insert_package(Arc::clone(package));
+        }
+        Ok(found)
+    }
+
+    /// Read a single version of a family from the repositories without
+    /// adding it to the index.
+    ///
+    /// Lets callers read the file system while the index stays readable, and
+    /// only lock it to [`insert_package`](Self::insert_package) the result.
+    pub async fn read_version(&self, name: &str, version: &str) -> Result<Option<Package>> {
+        let name = self.config.canonical_package_name(name).to_string();
+        let mut found = None;
+        for repo_path in self.config.get_all_package_paths() {
+            let package_py_path = repo_path.join(&name).join(version).join("package.py");
+            if package_py_path.is_file() {
+                found = Some(
+                    self.parse_package_file(&package_py_path, &name, Some(version))
+                        .await?,
+                );
+            }
+        }
+        Ok(found)
+    }
+
+    /// Scan every version of a family, ignoring the per-family version cap.
+    ///
+    /// The full list replaces the capped one in the index, so subsequent
+    /// lookups see all versions until the next scan.
+    pub async fn load_all_versions(&mut self, name: &str) -> Result<Vec<Arc<Package>>> {
+        let versions: Vec<Arc<Package>> = self
+            .read_all_versions(name)
+            .await?
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+        self.insert_all_versions(name, versions.clone());
+        Ok(versions)
+    }
+
+    /// Scan every version of a family without adding them to the index.
+    ///
+    /// As in [`read_version`](Self::read_version), the index stays readable
+    /// while the file system is read; the result is added with
+    /// [`insert_all_versions`](Self::insert_all_versions).
+    pub async fn read_all_versions(&self, name: &str) -> Result<Vec<Package>> {
+        let name = self.config.canonical_package_name(name);
+        let mut versions = Vec::new();
+        for repo_path in self.config.get_all_package_paths() {
+            let family_path = repo_path.join(name);
+            if !family_path.is_dir() {
+                continue;
+            }
+
+            let mut metrics = RepositoryScanMetrics::default();
+            let found = self
+                .scan_package_versions(&family_path, name, &mut metrics)
+                .await?;
+            if !found.is_empty() {
+                versions = found;
+            }
+        }
+        Ok(versions)
+    }
+
+    /// Replace the indexed versions of a family by every version read with
+    /// [`read_all_versions`](Self::read_all_versions).
+    ///
+    /// Nothing changes when `versions` is empty.
+    pub fn insert_all_versions(&mut self, name: &str, versions: Vec<Arc<Package>>) {
+        if versions.is_empty() {
+            return;
+        }
+        let name = self.config.canonical_package_name(name).to_string();
+        self.elided_versions.remove(&name);
+        self.insert_family(name, versions);
+    }
+
+    /// Audit the context and lock files below `dir` against the index.
+    ///
+    /// Families with versions left out by the per-family version cap are
+    /// loaded in full first, so old pins are not reported as missing.
+    pub async fn audit_stale_environments(&mut self, dir: &Path) -> StaleEnvironmentReport {
+        let walker = WorkspaceWalker::from_config(&self.config);
+        let (environments, errors) = load_environments(dir, &walker);
+
+        for family in self.capped_families(&environments) {
+            if let Err(e) = self.load_all_versions(&family).await {
+                warn!("Failed to load all versions of {}: {}", family, e);
+            }
+        }
+
+        let mut report = StaleEnvironmentReport::audit(&environments, &self.package_cache);
+        report.errors = errors;
+        report
+    }
+
+    /// Get the families pinned by `environments` with versions left out by
+    /// the per-family version cap, sorted by name.
+    pub fn capped_families(&self, environments: &[PinnedEnvironment]) -> Vec<String> {
+        let mut capped: Vec<String> = environments
+            .iter()
+            .flat_map(|environment| environment.packages.iter().map(|(name, _)| name))
+            .filter(|name| self.elided_versions(name) > 0)
+            .cloned()
+            .collect();
+        capped.sort();
+        capped.dedup();
+        capped
+    }
+
+    /// Audit how far the requirements of the packages in `repository` lag
+    /// behind the latest indexed versions.
+    ///
+    /// The repository is scanned on its own, so every version of its
+    /// packages is audited, and the packages are resolved against this index
+    /// with the configured resolver.
+    pub async fn audit_freshness(
+        &self,
+        repository: &Path,
+        threshold: usize,
+    ) -> Result<FreshnessReport> {
+        let mut config = self.config.clone();
+        config.packages_path = vec![repository.to_path_buf()];
+        config.local_packages_path = None;
+        config.release_packages_path = None;
+        config.max_versions_per_family = None;
+        let mut repository_discovery = PackageDiscoveryImpl::new(config);
+        repository_discovery.scan_packages().await?;
+        let packages = repository_discovery
+            .package_cache
+            .into_values()
+            .flatten()
+            .collect();
+
+        let resolver = Arc::from(create_resolver(&self.config, self.package_cache.clone()));
+        let concurrency = std::thread::available_parallelism().map_or(4, |n| n.get());
+        let mut report = FreshnessReport::audit(
+            repository.to_path_buf(),
+            packages,
+            Arc::new(self.package_cache.clone()),
+            resolver,
+            threshold,
+            concurrency,
+        )
+        .await;
+        report.graph = GraphStats::analyze(&self.usage_index.requirement_graph());
+        Ok(report)
+    }
+
+    /// Drop the oldest versions beyond the configured per-family cap.
+    ///
+    /// `versions` must be sorted oldest first.
+    fn apply_version_cap(&mut self, name: &str, mut versions: Vec<Package>) -> Vec<Package> {
+        match self.config.max_versions_per_family {
+            Some(max) if versions.len() > max => {
+                let elided = versions.len() - max;
+                versions.drain(..elided);
+                self.elided_versions.insert(name.to_string(), elided);
+            }
+            _ => {
+                self.elided_versions.remove(name);
+            }
+        }
+        versions
+    }
+
+    /// Parse the `requires` list of package.py content.
+    pub fn parse_requires(&self, content: &str) -> Vec<Requirement> {
+        self.logical_lines(content)
+            .iter()
+            .map(|line| line.trim())
+            .find(|line| line.starts_with("requires") && line.contains('='))
+            .map(|line| {
+                self.extract_list_values(line)
+                    .iter()
+                    .filter_map(|requirement| Requirement::parse(requirement).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Parse the `variants` list of package.py content.
+    ///
+    /// Empty trailing variants are not listed.
+    pub fn parse_variants(&self, content: &str) -> Vec<Variant> {
+        let mut variants: Vec<Variant> = Vec::new();
+        for span in find_requirements(content) {
+            let Some(index) = span
+                .variant
+                .filter(|_| span.attribute == VARIANTS_ATTRIBUTE)
+            else {
+                continue;
+            };
+            while variants.len() <= index {
+                variants.push(Variant {
+                    index: variants.len(),
+                    requires: Vec::new(),
+                    metadata: HashMap::new(),
+                });
+            }
+            if let Ok(requirement) = Requirement::parse(&span.text) {
+                variants[index].requires.push(requirement);
+            }
         }
+        variants
+    }
+
+    /// Scan every repository into the index, which must be empty.
+    async fn scan_repositories(&mut self) {
+        let start_time = std::time::Instant::now();
+        info!("Starting package discovery scan");
+
+        let all_paths = self.config.get_all_package_paths();
+        let mut total_packages = 0;
+
+        for path in all_paths {
+            if !path.exists() {
+                warn!("Package path does not exist: {:?}", path);
+                continue;
+            }
+
+            let repo_start = std::time::Instant::now();
+            let mut repo_metrics = RepositoryScanMetrics {
+                path: path.clone(),
+                ..Default::default()
+            };
+
+            match self.scan_package_repository(&path, &mut repo_metrics).await {
+                Ok(count) => {
+                    total_packages += count;
+                    info!("Scanned {} packages from {:?}", count, path);
+                }
+                Err(e) => {
+                    error!("Failed to scan package repository {:?}: {}", path, e);
+                    repo_metrics.error = Some(e.to_string());
+                }
+            }
+
+            repo_metrics.duration_ms = repo_start.elapsed().as_millis() as u64;
+            self.metrics.repositories.push(repo_metrics);
+        }
+
+        // End the family stream
+        self.family_sender = None;
+
+        let elapsed = start_time.elapsed();
+        self.metrics.total_duration_ms = elapsed.as_millis() as u64;
+        info!(
+            "Package discovery completed: {} packages found across {} package families in {:?}",
+            total_packages,
+            self.package_cache.len(),
+            elapsed
+        );
     }
 
     /// Scan a single package repository directory.
-    async fn scan_package_repository(&mut self, repo_path: &Path) -> Result<usize> {
+    async fn scan_package_repository(
+        &mut self,
+        repo_path: &Path,
+        metrics: &mut RepositoryScanMetrics,
+    ) -> Result<usize> {
         debug!("Scanning package repository: {:?}", repo_path);
         let mut package_count = 0;
 
@@ -45,31 +707,86 @@ impl PackageDiscoveryImpl {
                     .to_string();
 
                 if package_name.starts_with('.') {
+                    metrics.skipped_entries += 1;
                     continue; // Skip hidden directories
                 }
 
-                match self.scan_package_versions(&path, &package_name).await {
+                match self
+                    .scan_cached_package_versions(repo_path, &path, &package_name, metrics)
+                    .await
+                {
                     Ok(versions) => {
                         if !versions.is_empty() {
+                            let versions: Vec<Arc<Package>> = self
+                                .apply_version_cap(&package_name, versions)
+                                .into_iter()
+                                .map(Arc::new)
+                                .collect();
                             package_count += versions.len();
-                            self.package_cache.insert(package_name, versions);
+                            metrics.families += 1;
+                            if let Some(sender) = &self.family_sender {
+                                let _ = sender.send(ScannedFamily {
+                                    name: package_name.clone(),
+                                    versions: versions.clone(),
+                                });
+                            }
+                            self.insert_family(package_name, versions);
                         }
                     }
                     Err(e) => {
+                        metrics.skipped_entries += 1;
                         debug!("Failed to scan package {}: {}", package_name, e);
                     }
                 }
+            } else {
+                metrics.skipped_entries += 1;
             }
         }
 
+        metrics.packages = package_count;
+
         Ok(package_count)
     }
 
+    /// Scan all versions of a specific package, reusing the versions found
+    /// by a previous scan while they are cached.
+    async fn scan_cached_package_versions(
+        &self,
+        repo_path: &Path,
+        package_path: &Path,
+        package_name: &str,
+        metrics: &mut RepositoryScanMetrics,
+    ) -> Result<Vec<Package>> {
+        let Some(cache) = &self.family_cache else {
+            return self
+                .scan_package_versions(package_path, package_name, metrics)
+                .await;
+        };
+        let key = package_path.to_string_lossy();
+        if let Some(versions) = cache.get_packages(&key).await {
+            debug!("Reusing cached versions of {}", package_name);
+            return Ok(versions);
+        }
+
+        let versions = self
+            .scan_package_versions(package_path, package_name, metrics)
+            .await?;
+        cache
+            .put_packages(
+                key.into_owned(),
+                RepositoryClass::of(repo_path, &self.config),
+                versions.clone(),
+            )
+            .await;
+        Ok(versions)
+    }
+
     /// Scan all versions of a specific package.
     async fn scan_package_versions(
         &self,
         package_path: &Path,
         package_name: &str,
+        metrics: &mut RepositoryScanMetrics,
     ) -> Result<Vec<Package>> {
         debug!("Scanning package versions for: {}", package_name);
         let mut versions = Vec::new();
@@ -100,16 +817,43 @@ impl PackageDiscoveryImpl {
                 let package_py_path = version_path.join("package.py");
                 if package_py_path.exists() {
                     match self
-                        .parse_package_file(&package_py_path, package_name, &version)
+                        .parse_package_file(&package_py_path, package_name, Some(&version))
                         .await
                     {
                         Ok(package) => {
                             versions.push(package);
                         }
                         Err(e) => {
+                            metrics.parse_failures += 1;
                             debug!("Failed to parse package file {:?}: {}", package_py_path, e);
                         }
                     }
+                } else {
+                    metrics.skipped_entries += 1;
+                }
+            }
+        }
+
+        // Unversioned packages keep their package.py directly in the family directory
+        let family_package_py = package_path.join("package.py");
+        if family_package_py.is_file() {
+            match self
+                .parse_package_file(&family_package_py, package_name, None)
+                .await
+            {
+                Ok(package) => {
+                    debug!(
+                        "Found unversioned package {} (version: {})",
+                        package_name, package.version
+                    );
+                    versions.push(package);
+                }
+                Err(e) => {
+                    metrics.parse_failures += 1;
+                    debug!(
+                        "Failed to parse package file {:?}: {}",
+                        family_package_py, e
+                    );
                 }
             }
         }
@@ -126,7 +870,9 @@ impl PackageDiscoveryImpl {
     ///
     /// * `package_py_path` - Path to the package.py file
     /// * `expected_name` - Expected package name from directory structure
-    /// * `expected_version` - Expected version from directory structure
+    /// * `expected_version` - Expected version from directory structure, or `None`
+    ///   for unversioned packages whose version is read from the file itself
+    ///   (falling back to [`NO_VERSION`])
     ///
     /// # Returns
     ///
@@ -142,32 +888,38 @@ impl PackageDiscoveryImpl {
         &self,
         package_py_path: &Path,
         expected_name: &str,
-        expected_version: &str,
+        expected_version: Option<&str>,
     ) -> Result<Package> {
         debug!("Parsing package file: {:?}", package_py_path);
 
         // For now, we'll do basic parsing. In the future, we could use a Python AST parser
-        let content = fs::read_to_string(package_py_path).map_err(|e| {
+        let content = read_source(package_py_path).map_err(|e| {
             DiscoveryError::ScanFailed(format!(
                 "Failed to read package file {:?}: {}",
                 package_py_path, e
             ))
         })?;
 
-        let mut package = Package {
-            name: expected_name.to_string(),
-            version: Version::new(expected_version),
-            description: None,
-            authors: Vec::new(),
-            requires: Vec::new(),
-            tools: Vec::new(),
-            variants: Vec::new(),
-            path: package_py_path.parent().unwrap().to_path_buf(),
-            metadata: HashMap::new(),
+        let version = match expected_version {
+            Some(version) => Version::new(version),
+            None => content
+                .lines()
+                .map(str::trim)
+                .find(|line| line.starts_with("version") && line.contains('='))
+                .and_then(|line| self.extract_string_value(line))
+                .filter(|version| !version.is_empty())
+                .map(Version::new)
+                .unwrap_or_else(|| Version::new(NO_VERSION)),
         };
 
+        let mut package = Package::builder()
+            .name(self.interner.intern(expected_name))
+            .version(version)
+            .path(package_py_path.parent().unwrap())
+            .build();
+
         // Simple regex-based parsing (could be improved with proper Python parsing)
-        for line in content.lines() {
+        for line in self.logical_lines(&content) {
             let line = line.trim();
 
             if line.starts_with("description") && line.contains('=') {
@@ -175,16 +927,72 @@ impl PackageDiscoveryImpl {
                     package.description = Some(desc);
                 }
             } else if line.starts_with("authors") && line.contains('=') {
-                package.authors = self.extract_list_values(line);
+                package.authors = self.intern_list_values(line);
             } else if line.starts_with("tools") && line.contains('=') {
-                package.tools = self.extract_list_values(line);
+                package.tools = self.intern_list_values(line);
+            }
+        }
+        package.requires = self.parse_requires(&content);
+        package.variants = self.parse_variants(&content);
+
+        #[cfg(feature = "python-eval")]
+        if self.config.package_evaluation == crate::config::PackageEvaluation::Sandbox {
+            match self.sandbox.evaluate(package_py_path).await {
+                Ok(evaluated) => {
+                    evaluated.apply(&mut package, expected_version.is_none(), &self.interner)
+                }
+                Err(e) => warn!(
+                    "Sandboxed evaluation of {:?} failed, using static parsing: {}",
+                    package_py_path, e
+                ),
             }
-            // TODO: Parse requires and variants
         }
 
         Ok(package)
     }
 
+    /// Join physical lines into logical lines so that bracketed values
+    /// spanning multiple lines can be parsed as a single assignment.
+    fn logical_lines(&self, content: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut current = String::new();
+        let mut depth: i32 = 0;
+
+        for line in content.lines() {
+            if depth > 0 {
+                current.push(' ');
+                current.push_str(line.trim());
+            } else {
+                current = line.to_string();
+            }
+
+            // Count brackets outside of string literals and comments
+            let mut quote: Option<char> = None;
+            for ch in line.chars() {
+                match (quote, ch) {
+                    (Some(q), c) if c == q => quote = None,
+                    (Some(_), _) => {}
+                    (None, '"' | '\'') => quote = Some(ch),
+                    (None, '#') => break,
+                    (None, '[' | '(' | '{') => depth += 1,
+                    (None, ']' | ')' | '}') => depth -= 1,
+                    (None, _) => {}
+                }
+            }
+
+            if depth <= 0 {
+                depth = 0;
+                lines.push(std::mem::take(&mut current));
+            }
+        }
+
+        if !current.is_empty() {
+            lines.push(current);
+        }
+
+        lines
+    }
+
     /// Extract string value from a Python assignment line.
     fn extract_string_value(&self, line: &str) -> Option<String> {
         if let Some(eq_pos) = line.find('=') {
@@ -198,6 +1006,14 @@ impl PackageDiscoveryImpl {
         None
     }
 
+    /// Extract the values of a list assignment as interned strings.
+    fn intern_list_values(&self, line: &str) -> Vec<Arc<str>> {
+        self.extract_list_values(line)
+            .iter()
+            .map(|value| self.interner.intern(value))
+            .collect()
+    }
+
     /// Extract list values from a Python assignment line.
     fn extract_list_values(&self, line: &str) -> Vec<String> {
         let mut values = Vec::new();
@@ -223,42 +1039,30 @@ impl PackageDiscoveryImpl {
 
 #[async_trait]
 impl PackageDiscovery for PackageDiscoveryImpl {
+    /// Scan the repositories and replace the index.
+    ///
+    /// The new index is built on the side and swapped in once complete, so
+    /// the index is never seen half cleared or half filled.
     async fn scan_packages(&mut self) -> Result<()> {
-        let start_time = std::time::Instant::now();
-        info!("Starting package discovery scan");
-        self.package_cache.clear();
-
-        let all_paths = self.config.get_all_package_paths();
-        let mut total_packages = 0;
-
-        for path in all_paths {
-            if !path.exists() {
-                warn!("Package path does not exist: {:?}", path);
-                continue;
-            }
-
-            match self.scan_package_repository(&path).await {
-                Ok(count) => {
-                    total_packages += count;
-                    info!("Scanned {} packages from {:?}", count, path);
-                }
-                Err(e) => {
-                    error!("Failed to scan package repository {:?}: {}", path, e);
-                }
-            }
+        let mut next = Self::new(self.config.clone());
+        next.family_sender = self.family_sender.take();
+        next.family_cache = self.family_cache.clone();
+        #[cfg(feature = "python-eval")]
+        {
+            next.sandbox = self.sandbox.clone();
         }
+        next.scan_repositories().await;
 
-        let elapsed = start_time.elapsed();
-        info!(
-            "Package discovery completed: {} packages found across {} package families in {:?}",
-            total_packages,
-            self.package_cache.len(),
-            elapsed
-        );
+        self.package_cache = next.package_cache;
+        self.usage_index = next.usage_index;
+        self.metrics = next.metrics;
+        self.elided_versions = next.elided_versions;
+        self.interner = next.interner;
+        self.generation += 1;
         Ok(())
     }
 
-    async fn find_packages(&self, pattern: &str) -> Result<Vec<Package>> {
+    async fn find_packages(&self, pattern: &str) -> Result<Vec<Arc<Package>>> {
         let mut results = Vec::new();
 
         for (package_name, versions) in &self.package_cache {
@@ -270,15 +1074,35 @@ impl PackageDiscovery for PackageDiscoveryImpl {
         Ok(results)
     }
 
-    async fn get_package_versions(&self, name: &str) -> Result<Vec<Package>> {
+    async fn get_package_versions(&self, name: &str) -> Result<Vec<Arc<Package>>> {
+        let name = self.config.canonical_package_name(name);
         Ok(self.package_cache.get(name).cloned().unwrap_or_default())
     }
 
+    async fn find_matching(
+        &self,
+        family: &str,
+        constraint: &VersionConstraint,
+        limit: Option<usize>,
+        order: VersionOrder,
+    ) -> Result<Vec<Arc<Package>>> {
+        let family = self.config.canonical_package_name(family);
+        let Some(versions) = self.package_cache.get(family) else {
+            return Ok(Vec::new());
+        };
+        Ok(constraint
+            .select(versions, limit, order)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
     async fn get_all_package_names(&self) -> Result<Vec<String>> {
         Ok(self.package_cache.keys().cloned().collect())
     }
 
-    async fn get_package(&self, name: &str, version: &Version) -> Result<Option<Package>> {
+    async fn get_package(&self, name: &str, version: &Version) -> Result<Option<Arc<Package>>> {
+        let name = self.config.canonical_package_name(name);
         if let Some(versions) = self.package_cache.get(name) {
             for package in versions {
                 if package.version == *version {
@@ -297,6 +1121,10 @@ impl PackageDiscovery for PackageDiscoveryImpl {
 
     async fn clear_cache(&mut self) -> Result<()> {
         self.package_cache.clear();
+        self.usage_index.clear();
+        self.metrics = DiscoveryMetrics::default();
+        self.elided_versions.clear();
+        self.generation += 1;
         Ok(())
     }
 }
@@ -304,6 +1132,8 @@ impl PackageDiscovery for PackageDiscoveryImpl {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ResolveStrategy;
+    use crate::discovery::test_support::{repository_config, scan_repositories, write_package};
 
     #[test]
     fn test_extract_string_value() {
@@ -339,6 +1169,26 @@ mod tests {
         assert!(result.is_empty());
     }
 
+    #[test]
+    fn test_parse_variants() {
+        let discovery = PackageDiscoveryImpl::new(Config::new());
+        let content = "requires = ['python']\nvariants = [\n    ['maya-2024', 'python-3.10'],\n    ['maya-2025'],\n]\n";
+
+        let variants = discovery.parse_variants(content);
+        let requires: Vec<Vec<String>> = variants
+            .iter()
+            .map(|variant| variant.requires.iter().map(|r| r.to_string()).collect())
+            .collect();
+        assert_eq!(
+            requires,
+            vec![vec!["maya-2024", "python-3.10"], vec!["maya-2025"]]
+        );
+        assert_eq!(variants[1].index, 1);
+        assert!(discovery
+            .parse_variants("requires = ['python']\n")
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn test_package_discovery_stats() {
         let discovery = PackageDiscoveryImpl::new(Config::new());
@@ -356,10 +1206,439 @@ mod tests {
         assert!(names.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_scan_unversioned_packages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+
+        // Unversioned package with a version attribute in the file
+        write_package(
+            repo,
+            "dev_tool",
+            "",
+            "name = \"dev_tool\"\nversion = \"0.3.0\"\n",
+        );
+
+        // Unversioned package without any version attribute
+        write_package(repo, "scratch", "", "name = \"scratch\"\n");
+
+        let discovery = scan_repositories(&[repo]).await;
+
+        let dev_tool = discovery.get_package_versions("dev_tool").await.unwrap();
+        assert_eq!(dev_tool.len(), 1);
+        assert_eq!(dev_tool[0].version, Version::new("0.3.0"));
+        assert_eq!(dev_tool[0].path, repo.join("dev_tool"));
+
+        let scratch = discovery.get_package_versions("scratch").await.unwrap();
+        assert_eq!(scratch.len(), 1);
+        assert!(scratch[0].version.is_unversioned());
+    }
+
+    #[tokio::test]
+    async fn test_scan_records_repository_metrics() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+
+        write_package(repo, "maya_tools", "1.0.0", "name = \"maya_tools\"\n");
+        // An unreadable package.py counts as a parse failure
+        fs::create_dir_all(repo.join("broken").join("1.0").join("package.py")).unwrap();
+        fs::create_dir_all(repo.join("maya_tools").join("docs")).unwrap();
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::write(repo.join("README.txt"), "").unwrap();
+
+        let discovery = scan_repositories(&[repo]).await;
+
+        let metrics = discovery.metrics();
+        assert_eq!(metrics.repositories.len(), 1);
+
+        let repo_metrics = &metrics.repositories[0];
+        assert_eq!(repo_metrics.path, repo);
+        assert_eq!(repo_metrics.families, 1);
+        assert_eq!(repo_metrics.packages, 1);
+        assert_eq!(repo_metrics.parse_failures, 1);
+        assert_eq!(repo_metrics.skipped_entries, 3);
+        assert!(repo_metrics.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rescan_replaces_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+        for family in ["maya", "houdini"] {
+            write_package(repo, family, "1.0.0", "requires = ['python']\n");
+        }
+
+        let mut discovery = scan_repositories(&[repo]).await;
+        assert_eq!(discovery.packages().len(), 2);
+
+        fs::remove_dir_all(repo.join("houdini")).unwrap();
+        let mut families = discovery.stream_families();
+        discovery.scan_packages().await.unwrap();
+
+        let mut names: Vec<&String> = discovery.packages().keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["maya"]);
+        assert_eq!(discovery.metrics().repositories.len(), 1);
+        assert_eq!(discovery.usage_index().analyzed_families(), 1);
+        // The stream saw the new index only and ended with the scan
+        assert_eq!(families.recv().await.unwrap().name, "maya");
+        assert!(families.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rescan_reuses_cached_families() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+        let version_dir = write_package(repo, "maya", "1.0.0", "requires = ['python']\n");
+
+        let config = repository_config(&[repo]);
+        let cache = Arc::new(CacheManager::new(&Default::default()));
+        let mut discovery = PackageDiscoveryImpl::new(config).with_family_cache(cache.clone());
+        discovery.scan_packages().await.unwrap();
+
+        // Unchanged families are not read again until invalidated
+        fs::write(version_dir.join("package.py"), "requires = ['usd']\n").unwrap();
+        discovery.scan_packages().await.unwrap();
+        let maya = discovery.get_package_versions("maya").await.unwrap();
+        assert_eq!(maya[0].requires[0].name, "python");
+
+        let changed = vec![version_dir.join("package.py")];
+        let invalidated = cache
+            .invalidate_changed_paths(&changed, &[repo.to_path_buf()])
+            .await;
+        assert_eq!(invalidated, 1);
+        discovery.scan_packages().await.unwrap();
+        let maya = discovery.get_package_versions("maya").await.unwrap();
+        assert_eq!(maya[0].requires[0].name, "usd");
+    }
+
+    #[tokio::test]
+    async fn test_scan_indexes_requirement_usage() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+
+        write_package(
+            repo,
+            "maya_tools",
+            "1.0.0",
+            "name = \"maya_tools\"\nrequires = [\n    \"python-3.7+\",\n    \"maya\",  # host\n]\n",
+        );
+
+        let discovery = scan_repositories(&[repo]).await;
+
+        let packages = discovery.get_package_versions("maya_tools").await.unwrap();
+        assert_eq!(packages[0].requires.len(), 2);
+
+        let python = discovery.usage_index().family_usage("python");
+        assert_eq!(python.dependent_count, 1);
+        assert_eq!(python.constraints.get("3.7+"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_scan_shares_packages_and_strings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+
+        for version in ["1.0.0", "1.1.0"] {
+            write_package(
+                repo,
+                "maya_tools",
+                version,
+                "name = \"maya_tools\"\nauthors = [\"Jane\"]\ntools = [\"mt\"]\n",
+            );
+        }
+
+        let discovery = scan_repositories(&[repo]).await;
+
+        // Versions handed out are the indexed ones, not copies
+        let packages = discovery.get_package_versions("maya_tools").await.unwrap();
+        assert_eq!(packages.len(), 2);
+        assert!(Arc::ptr_eq(
+            &packages[0],
+            &discovery.packages()["maya_tools"][0]
+        ));
+
+        // Names, authors and tools are stored once for every version
+        assert!(Arc::ptr_eq(&packages[0].name, &packages[1].name));
+        assert!(Arc::ptr_eq(&packages[0].tools[0], &packages[1].tools[0]));
+        assert!(Arc::ptr_eq(
+            &packages[0].authors[0],
+            &packages[1].authors[0]
+        ));
+        assert_eq!(discovery.interner.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_shared_index_is_reused_across_instances() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path().join("packages");
+        let version_dir = write_package(
+            &repo,
+            "maya_tools",
+            "1.0.0",
+            "name = \"maya_tools\"\nrequires = [\"maya\"]\n",
+        );
+
+        let mut config = repository_config(&[&repo]);
+        config.shared_index_dir = Some(temp_dir.path().join("index"));
+
+        let mut first = PackageDiscoveryImpl::new(config.clone());
+        first.scan_shared().await.unwrap();
+        assert_eq!(first.shared_generation(), Some(1));
+
+        // A second instance loads the published index instead of scanning
+        fs::remove_dir_all(&version_dir).unwrap();
+        let mut second = PackageDiscoveryImpl::new(config.clone());
+        second.scan_shared().await.unwrap();
+        assert_eq!(second.shared_generation(), Some(1));
+        assert_eq!(second.get_stats().await.unwrap(), (1, 1));
+        assert_eq!(second.usage_index().family_usage("maya").dependent_count, 1);
+
+        // While another instance holds the lock, a stale index is rebuilt in-process
+        config.shared_index_max_age_secs = 0;
+        let shared = SharedIndex::from_config(&config).unwrap();
+        let mut snapshot = shared.load().unwrap();
+        snapshot.created_at -= 10;
+        fs::write(shared.index_path(), serde_json::to_vec(&snapshot).unwrap()).unwrap();
+        let lock = shared.try_lock().unwrap().unwrap();
+        let mut third = PackageDiscoveryImpl::new(config.clone());
+        third.scan_shared().await.unwrap();
+        assert_eq!(third.shared_generation(), None);
+        assert_eq!(third.get_stats().await.unwrap(), (0, 0));
+
+        // Once the lock is free, the rebuilt index is published as the next generation
+        drop(lock);
+        let mut fourth = PackageDiscoveryImpl::new(config);
+        fourth.scan_shared().await.unwrap();
+        assert_eq!(fourth.shared_generation(), Some(2));
+    }
+
+    #[test]
+    fn test_insert_package() {
+        use std::path::PathBuf;
+
+        let mut discovery = PackageDiscoveryImpl::new(Config::new());
+        for (version, path) in [("1.0", "/release/maya/1.0"), ("2.0", "/release/maya/2.0")] {
+            discovery.insert_package(
+                Package::builder()
+                    .name("maya")
+                    .version(version)
+                    .path(path)
+                    .build(),
+            );
+        }
+        discovery.insert_package(
+            Package::builder()
+                .name("maya")
+                .version("1.0")
+                .path("/local/maya/1.0")
+                .build(),
+        );
+
+        let versions = &discovery.packages()["maya"];
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].path, PathBuf::from("/local/maya/1.0"));
+        assert_eq!(versions[1].version, Version::new("2.0"));
+    }
+
+    #[tokio::test]
+    async fn test_load_and_remove_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let write_version = |version: &str| {
+            let content = "name = 'maya'\nrequires = ['python-3']\n";
+            write_package(temp_dir.path(), "maya", version, content);
+        };
+        write_version("2024.1");
+        let mut discovery = scan_repositories(&[temp_dir.path()]).await;
+
+        // A release the scan did not see is indexed on its own
+        write_version("2025.0");
+        let package = discovery.load_version("maya", "2025.0").await.unwrap();
+        assert_eq!(package.unwrap().requires.len(), 1);
+        assert_eq!(discovery.packages()["maya"].len(), 2);
+        assert!(discovery
+            .load_version("maya", "2026.0")
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(discovery.remove_package("maya", &Version::new("2024.1")));
+        assert!(!discovery.remove_package("maya", &Version::new("2024.1")));
+        assert!(discovery.remove_package("maya", &Version::new("2025.0")));
+        assert!(!discovery.packages().contains_key("maya"));
+    }
+
+    #[tokio::test]
+    async fn test_find_matching() {
+        let mut discovery = PackageDiscoveryImpl::new(Config::new());
+        let versions = ["1.0.0", "2.1.0", "1.2.0", "2.0.0"]
+            .into_iter()
+            .map(|version| Arc::new(Package::builder().name("maya").version(version).build()))
+            .collect();
+        discovery.package_cache.insert("maya".to_string(), versions);
+        let found = |packages: Vec<Arc<Package>>| {
+            packages
+                .iter()
+                .map(|package| package.version.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let constraint = VersionConstraint::GreaterEqual(Version::new("1.2"));
+        let matching = discovery
+            .find_matching("maya", &constraint, None, VersionOrder::Descending)
+            .await
+            .unwrap();
+        assert_eq!(found(matching), ["2.1.0", "2.0.0", "1.2.0"]);
+        let matching = discovery
+            .find_matching("maya", &constraint, Some(2), VersionOrder::Ascending)
+            .await
+            .unwrap();
+        assert_eq!(found(matching), ["1.2.0", "2.0.0"]);
+        let matching = discovery
+            .find_matching("nuke", &constraint, None, VersionOrder::default())
+            .await
+            .unwrap();
+        assert!(matching.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolves_are_cached_until_the_index_changes() {
+        let mut discovery = PackageDiscoveryImpl::new(Config::new());
+        discovery.insert_package(Package::builder().name("maya").version("1.0.0").build());
+        let requirements = vec![Requirement::parse("maya").unwrap()];
+
+        let Err(pending) = discovery.cached_resolve(&requirements) else {
+            panic!("expected a pending resolve");
+        };
+        let context = pending.resolver().resolve(&requirements).await.ok();
+        assert!(pending.finish(context).is_some());
+        let Ok(Some(context)) = discovery.cached_resolve(&requirements) else {
+            panic!("expected the cached context");
+        };
+        assert_eq!(context.packages[0].version.to_string(), "1.0.0");
+
+        discovery.insert_package(Package::builder().name("maya").version("2.0.0").build());
+        assert!(discovery.cached_resolve(&requirements).is_err());
+    }
+
+    #[test]
+    fn test_pick_version() {
+        let mut discovery = PackageDiscoveryImpl::new(Config::new());
+        let versions = ["1.0.0", "1.1.0", "2.0.0-beta.1"]
+            .into_iter()
+            .map(|version| Arc::new(Package::builder().name("maya").version(version).build()))
+            .collect();
+        discovery.package_cache.insert("maya".to_string(), versions);
+        let picked = |discovery: &PackageDiscoveryImpl, constraint: &VersionConstraint| {
+            discovery
+                .pick_version("maya", constraint)
+                .map(|package| package.version.to_string())
+        };
+
+        assert_eq!(
+            picked(&discovery, &VersionConstraint::Any).as_deref(),
+            Some("2.0.0-beta.1")
+        );
+
+        // Picks are cached until the configuration changes
+        let mut config = discovery.config().clone();
+        config.exclude_prereleases = true;
+        discovery.set_config(config);
+        assert_eq!(
+            picked(&discovery, &VersionConstraint::Any).as_deref(),
+            Some("1.1.0")
+        );
+        // Pre-releases are still picked when nothing else satisfies
+        assert_eq!(
+            picked(
+                &discovery,
+                &VersionConstraint::GreaterEqual(Version::new("2"))
+            )
+            .as_deref(),
+            Some("2.0.0-beta.1")
+        );
+        assert_eq!(
+            picked(&discovery, &VersionConstraint::Less(Version::new("1"))),
+            None
+        );
+
+        // Preferred versions win over the strategy, which orders the rest
+        let mut config = discovery.config().clone();
+        config.resolve_strategy = ResolveStrategy::Earliest;
+        discovery.set_config(config);
+        assert_eq!(
+            picked(&discovery, &VersionConstraint::Any).as_deref(),
+            Some("1.0.0")
+        );
+        let mut config = discovery.config().clone();
+        config.preferred_versions.insert(
+            "maya".to_string(),
+            VersionConstraint::Family(Version::new("1.1")),
+        );
+        discovery.set_config(config);
+        assert_eq!(
+            picked(&discovery, &VersionConstraint::Any).as_deref(),
+            Some("1.1.0")
+        );
+        assert_eq!(
+            picked(&discovery, &VersionConstraint::Less(Version::new("1.1"))).as_deref(),
+            Some("1.0.0")
+        );
+        assert_eq!(
+            picked(&discovery, &VersionConstraint::Less(Version::new("1.1"))).as_deref(),
+            Some("1.0.0")
+        );
+        assert_eq!(discovery.pick_cache_stats().0, 1);
+
+        // Indexing a version invalidates the cached picks
+        discovery.insert_package(Package::builder().name("maya").version("0.9.0").build());
+        assert_eq!(
+            picked(&discovery, &VersionConstraint::Less(Version::new("1.1"))).as_deref(),
+            Some("0.9.0")
+        );
+        assert_eq!(
+            discovery
+                .latest_version("maya")
+                .map(|package| package.version.to_string())
+                .as_deref(),
+            Some("1.1.0")
+        );
+    }
+
     #[tokio::test]
     async fn test_find_packages_empty() {
         let discovery = PackageDiscoveryImpl::new(Config::new());
         let packages = discovery.find_packages("nonexistent").await.unwrap();
         assert!(packages.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_max_versions_per_family() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+
+        for version in ["1.0.0", "1.2.0", "2.0.0", "10.0.0"] {
+            write_package(repo, "maya_tools", version, "name = \"maya_tools\"\n");
+        }
+
+        let mut config = repository_config(&[repo]);
+        config.max_versions_per_family = Some(2);
+        let mut discovery = PackageDiscoveryImpl::new(config);
+        discovery.scan_packages().await.unwrap();
+
+        let versions: Vec<String> = discovery
+            .get_package_versions("maya_tools")
+            .await
+            .unwrap()
+            .iter()
+            .map(|p| p.version.to_string())
+            .collect();
+        assert_eq!(versions, vec!["2.0.0", "10.0.0"]);
+        assert_eq!(discovery.elided_versions("maya_tools"), 2);
+
+        let all = discovery.load_all_versions("maya_tools").await.unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(discovery.elided_versions("maya_tools"), 0);
+        assert_eq!(discovery.get_stats().await.unwrap(), (1, 4));
+    }
 }
@@ -0,0 +1,107 @@
+//! Metrics collected while scanning package repositories.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Scan statistics of a single package repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryScanMetrics {
+    /// Repository path
+    pub path: PathBuf,
+    /// Time spent scanning the repository in milliseconds
+    pub duration_ms: u64,
+    /// Number of package families found
+    pub families: usize,
+    /// Number of packages found
+    pub packages: usize,
+    /// Number of package.py files that could not be parsed
+    pub parse_failures: usize,
+    /// Number of directory entries that are not packages
+    pub skipped_entries: usize,
+    /// Error that aborted the scan of this repository, if any
+    pub error: Option<String>,
+}
+
+/// Scan statistics of all package repositories.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveryMetrics {
+    /// Per-repository statistics in scan order
+    pub repositories: Vec<RepositoryScanMetrics>,
+    /// Total scan time in milliseconds
+    pub total_duration_ms: u64,
+}
+
+impl DiscoveryMetrics {
+    /// Get the repository that took the longest to scan.
+    pub fn slowest_repository(&self) -> Option<&RepositoryScanMetrics> {
+        self.repositories.iter().max_by_key(|repo| repo.duration_ms)
+    }
+
+    /// Get the total number of package.py files that could not be parsed.
+    pub fn total_parse_failures(&self) -> usize {
+        self.repositories
+            .iter()
+            .map(|repo| repo.parse_failures)
+            .sum()
+    }
+
+    /// Get the total number of skipped directory entries.
+    pub fn total_skipped_entries(&self) -> usize {
+        self.repositories
+            .iter()
+            .map(|repo| repo.skipped_entries)
+            .sum()
+    }
+
+    /// Summarize the metrics in a single line for log messages.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} repositories scanned in {}ms, {} parse failures, {} skipped entries",
+            self.repositories.len(),
+            self.total_duration_ms,
+            self.total_parse_failures(),
+            self.total_skipped_entries()
+        );
+        if let Some(slowest) = self.slowest_repository() {
+            summary.push_str(&format!(
+                "; slowest: {} ({}ms)",
+                slowest.path.display(),
+                slowest.duration_ms
+            ));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_metrics_summary() {
+        let metrics = DiscoveryMetrics {
+            repositories: vec![
+                RepositoryScanMetrics {
+                    path: PathBuf::from("/fast"),
+                    duration_ms: 5,
+                    parse_failures: 1,
+                    ..Default::default()
+                },
+                RepositoryScanMetrics {
+                    path: PathBuf::from("/mnt/slow"),
+                    duration_ms: 900,
+                    skipped_entries: 3,
+                    ..Default::default()
+                },
+            ],
+            total_duration_ms: 905,
+        };
+
+        assert_eq!(metrics.slowest_repository().unwrap().duration_ms, 900);
+        assert_eq!(metrics.total_parse_failures(), 1);
+        assert_eq!(metrics.total_skipped_entries(), 3);
+        assert!(metrics.summary().contains("slowest: /mnt/slow (900ms)"));
+    }
+}
@@ -2,5 +2,27 @@
 
 mod cache;
 mod discovery_impl;
+mod metrics;
+mod payload;
+mod pick_cache;
+mod resolve_cache;
+mod resolved_env;
+#[cfg(feature = "python-eval")]
+mod sandbox;
+mod shared_index;
+#[cfg(test)]
+pub(crate) mod test_support;
+mod walk;
 
-pub use discovery_impl::PackageDiscoveryImpl;
+pub use discovery_impl::{FamilyStream, PackageDiscoveryImpl, ScannedFamily};
+pub use metrics::{DiscoveryMetrics, RepositoryScanMetrics};
+pub use payload::{PayloadCache, PayloadStats};
+pub use pick_cache::PickCache;
+pub use resolve_cache::{PendingResolve, ResolveCache};
+pub use resolved_env::{imported_module, ResolvedEnvironment, ResolvedPackage};
+#[cfg(feature = "python-eval")]
+pub use sandbox::{EvaluatedPackage, PythonSandbox};
+pub use shared_index::{IndexSnapshot, SharedIndex};
+#[cfg(feature = "lsp")]
+pub(crate) use walk::glob_to_regex;
+pub use walk::{WalkResult, WorkspaceWalker, IGNORE_FILES};
@@ -0,0 +1,149 @@
+//! Installed payload size of packages.
+//!
+//! Statting a package payload walks its whole directory tree, which is slow
+//! on network repositories. [`PayloadCache`] computes the statistics only when
+//! first requested, stops after a maximum number of files and remembers the
+//! result, since released payloads do not change.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+/// Maximum number of payloads whose statistics are cached.
+const MAX_CACHED_PAYLOADS: usize = 1024;
+
+/// Size and file count of a package payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PayloadStats {
+    /// Total size of the counted files in bytes
+    pub bytes: u64,
+    /// Number of counted files
+    pub files: usize,
+    /// Whether counting stopped at the file limit
+    pub truncated: bool,
+}
+
+impl PayloadStats {
+    /// Count the files below `root`, stopping after `max_files`.
+    ///
+    /// Symbolic links are counted as files and never followed.
+    pub fn compute(root: &Path, max_files: usize) -> Self {
+        let mut stats = Self::default();
+        let mut pending = vec![root.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                    continue;
+                }
+                if stats.files == max_files {
+                    stats.truncated = true;
+                    return stats;
+                }
+                stats.files += 1;
+                stats.bytes += metadata.len();
+            }
+        }
+        stats
+    }
+
+    /// Describe the statistics, e.g. `1.5 MiB in 42 files`.
+    pub fn summary(&self) -> String {
+        let at_least = if self.truncated { "at least " } else { "" };
+        format!(
+            "{}{} in {}{} file(s)",
+            at_least,
+            format_bytes(self.bytes),
+            at_least,
+            self.files
+        )
+    }
+}
+
+/// Format a byte count with a binary unit.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Lazily computed payload statistics, keyed by package directory.
+#[derive(Debug, Default)]
+pub struct PayloadCache {
+    stats: RwLock<HashMap<PathBuf, PayloadStats>>,
+}
+
+impl PayloadCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the statistics of the payload at `root`, counting at most
+    /// `max_files` files on first use.
+    pub async fn get(&self, root: &Path, max_files: usize) -> PayloadStats {
+        if let Some(stats) = self.stats.read().await.get(root) {
+            return *stats;
+        }
+
+        let dir = root.to_path_buf();
+        let stats = tokio::task::spawn_blocking(move || PayloadStats::compute(&dir, max_files))
+            .await
+            .unwrap_or_default();
+
+        let mut cached = self.stats.write().await;
+        if cached.len() >= MAX_CACHED_PAYLOADS {
+            cached.clear();
+        }
+        cached.insert(root.to_path_buf(), stats);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::write_package;
+
+    #[tokio::test]
+    async fn test_payload_stats() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = &write_package(temp_dir.path(), "tool", "1.0", "name = 'tool'\n");
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::write(root.join("bin/tool"), vec![0u8; 2048]).unwrap();
+
+        let stats = PayloadStats::compute(root, 100);
+        assert_eq!(stats.files, 2);
+        assert_eq!(stats.bytes, 2048 + 14);
+        assert!(!stats.truncated);
+        assert_eq!(stats.summary(), "2.0 KiB in 2 file(s)");
+
+        let limited = PayloadStats::compute(root, 1);
+        assert_eq!(limited.files, 1);
+        assert!(limited.truncated);
+        assert!(limited.summary().starts_with("at least "));
+
+        // Cached statistics are not recomputed
+        let cache = PayloadCache::new();
+        assert_eq!(cache.get(root, 100).await, stats);
+        fs::write(root.join("extra"), "x").unwrap();
+        assert_eq!(cache.get(root, 100).await, stats);
+    }
+}
@@ -0,0 +1,110 @@
+//! Cache of the versions picked for requirements.
+//!
+//! Inlay hints, hovers, completions and diagnostics all ask which version a
+//! requirement picks, again on every edit. [`PickCache`] remembers the answer
+//! per family and constraint until the index changes, so most of those
+//! questions are a single lookup instead of a scan of the family.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::core::VersionConstraint;
+
+/// Maximum number of picks cached before the cache starts over.
+const MAX_CACHED_PICKS: usize = 10_000;
+
+/// Picks of one index generation, keyed by family and constraint.
+#[derive(Debug, Default)]
+struct Picks {
+    generation: u64,
+    /// Position of the picked version in the family's version list
+    positions: HashMap<(String, String), Option<usize>>,
+}
+
+/// Versions picked for `(family, constraint)` pairs at an index generation.
+///
+/// Entries of an older generation are dropped on the first lookup at a newer
+/// one, as the versions of any family may have changed.
+#[derive(Debug, Default)]
+pub struct PickCache {
+    picks: Mutex<Picks>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl PickCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the cached pick of a constraint at `generation`, or compute it
+    /// with `pick` and remember it.
+    ///
+    /// Picks are positions in the family's version list, `None` when no
+    /// version satisfies the constraint.
+    pub fn get_or_pick(
+        &self,
+        generation: u64,
+        family: &str,
+        constraint: &VersionConstraint,
+        pick: impl FnOnce() -> Option<usize>,
+    ) -> Option<usize> {
+        let key = (family.to_string(), constraint.to_string());
+        let mut picks = self.picks.lock().unwrap_or_else(|e| e.into_inner());
+        if picks.generation != generation {
+            picks.positions.clear();
+            picks.generation = generation;
+        }
+        if let Some(&position) = picks.positions.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return position;
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let position = pick();
+        if picks.positions.len() >= MAX_CACHED_PICKS {
+            picks.positions.clear();
+        }
+        picks.positions.insert(key, position);
+        position
+    }
+
+    /// Get the number of lookups answered from the cache and computed.
+    pub fn stats(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Version;
+
+    #[test]
+    fn test_picks_are_cached_per_generation() {
+        let cache = PickCache::new();
+        let constraint = VersionConstraint::GreaterEqual(Version::new("3.9"));
+        assert_eq!(
+            cache.get_or_pick(1, "python", &constraint, || Some(2)),
+            Some(2)
+        );
+        assert_eq!(
+            cache.get_or_pick(1, "python", &constraint, || unreachable!()),
+            Some(2)
+        );
+        assert_eq!(cache.get_or_pick(1, "maya", &constraint, || None), None);
+        assert_eq!(cache.stats(), (1, 2));
+
+        // A new generation computes the pick again
+        assert_eq!(
+            cache.get_or_pick(2, "python", &constraint, || Some(3)),
+            Some(3)
+        );
+        assert_eq!(cache.stats(), (1, 3));
+    }
+}
@@ -0,0 +1,135 @@
+//! Cache of the contexts resolved for requirement lists.
+//!
+//! Code actions and diagnostics resolve the `requires` of the document being
+//! edited, again on every request. [`ResolveCache`] remembers the context
+//! per requirement list until the index changes, so only edits changing the
+//! requirements resolve again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::core::{DependencyResolver, Requirement, ResolvedContext};
+
+/// Maximum number of contexts cached before the cache starts over.
+const MAX_CACHED_CONTEXTS: usize = 256;
+
+/// Contexts of one index generation, keyed by requirement list.
+#[derive(Debug, Default)]
+struct Contexts {
+    generation: u64,
+    /// Resolved context, `None` when resolving failed
+    contexts: HashMap<String, Option<ResolvedContext>>,
+}
+
+/// Contexts resolved for requirement lists at an index generation.
+///
+/// Entries of an older generation are dropped on the first lookup at a newer
+/// one, as the versions of any family may have changed.
+#[derive(Debug, Default)]
+pub struct ResolveCache {
+    contexts: Mutex<Contexts>,
+}
+
+impl ResolveCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the cached context of a requirement list at `generation`.
+    ///
+    /// Returns `Some(None)` when resolving the list failed.
+    pub fn get(&self, generation: u64, key: &str) -> Option<Option<ResolvedContext>> {
+        let mut contexts = self.contexts.lock().unwrap_or_else(|e| e.into_inner());
+        if contexts.generation != generation {
+            contexts.contexts.clear();
+            contexts.generation = generation;
+        }
+        contexts.contexts.get(key).cloned()
+    }
+
+    /// Remember the context of a requirement list resolved at `generation`.
+    ///
+    /// Contexts resolved at another generation than the current one are not
+    /// kept.
+    pub fn insert(&self, generation: u64, key: String, context: Option<ResolvedContext>) {
+        let mut contexts = self.contexts.lock().unwrap_or_else(|e| e.into_inner());
+        if contexts.generation != generation {
+            return;
+        }
+        if contexts.contexts.len() >= MAX_CACHED_CONTEXTS {
+            contexts.contexts.clear();
+        }
+        contexts.contexts.insert(key, context);
+    }
+
+    /// Get the key of a requirement list.
+    pub fn key(requirements: &[Requirement]) -> String {
+        requirements
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// A resolve missing from the [`ResolveCache`], to run without holding the
+/// index lock.
+pub struct PendingResolve {
+    resolver: Box<dyn DependencyResolver>,
+    cache: Arc<ResolveCache>,
+    generation: u64,
+    key: String,
+}
+
+impl PendingResolve {
+    pub(super) fn new(
+        resolver: Box<dyn DependencyResolver>,
+        cache: Arc<ResolveCache>,
+        generation: u64,
+        key: String,
+    ) -> Self {
+        Self {
+            resolver,
+            cache,
+            generation,
+            key,
+        }
+    }
+
+    /// Get the resolver over a copy of the index.
+    pub fn resolver(&self) -> &dyn DependencyResolver {
+        self.resolver.as_ref()
+    }
+
+    /// Cache the result of the resolve and return it.
+    pub fn finish(self, context: Option<ResolvedContext>) -> Option<ResolvedContext> {
+        self.cache
+            .insert(self.generation, self.key, context.clone());
+        context
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contexts_are_cached_per_generation() {
+        let cache = ResolveCache::new();
+        let requirements = vec![Requirement::parse("python-3").unwrap()];
+        let key = ResolveCache::key(&requirements);
+        assert!(cache.get(1, &key).is_none());
+        cache.insert(1, key.clone(), None);
+        assert_eq!(
+            cache.get(1, &key).map(|context| context.is_none()),
+            Some(true)
+        );
+
+        // A new generation resolves again, and late results of the previous
+        // one are not kept
+        assert!(cache.get(2, &key).is_none());
+        cache.insert(1, key.clone(), None);
+        assert!(cache.get(2, &key).is_none());
+    }
+}
@@ -0,0 +1,203 @@
+//! Introspection of the rez-resolved environment the server runs in.
+//!
+//! When the server is started from a `rez env` shell, `REZ_USED_RESOLVE`
+//! lists the resolved packages and `REZ_<PKG>_ROOT` points at each package's
+//! installation root. This lets us map Python modules back to the rez package
+//! that provides them.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::core::Version;
+
+/// A package from the resolved environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPackage {
+    /// Package name
+    pub name: String,
+    /// Resolved version (empty for unversioned packages)
+    pub version: Version,
+    /// Package root from `REZ_<PKG>_ROOT`, if set
+    pub root: Option<PathBuf>,
+}
+
+/// Packages and search paths of a rez-resolved environment.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedEnvironment {
+    /// Resolved packages in resolve order
+    packages: Vec<ResolvedPackage>,
+    /// Entries of `PYTHONPATH`
+    python_paths: Vec<PathBuf>,
+}
+
+/// Subdirectories of a package root that commonly hold Python modules.
+const MODULE_SUBDIRS: &[&str] = &["", "python", "lib", "site-packages", "src"];
+
+impl ResolvedEnvironment {
+    /// Read the resolved environment from the server process environment.
+    ///
+    /// Returns `None` when the server was not started from a resolved context.
+    pub fn from_env() -> Option<Self> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Build a resolved environment from a set of environment variables.
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Option<Self> {
+        let vars: HashMap<String, String> = vars.into_iter().collect();
+        let resolve = vars.get("REZ_USED_RESOLVE")?;
+
+        let packages = resolve
+            .split_whitespace()
+            .map(|request| {
+                let request = request.trim_start_matches('~');
+                let (name, version) = match request.split_once('-') {
+                    Some((name, version)) => (name, version),
+                    None => (request, ""),
+                };
+                let root_var = format!("REZ_{}_ROOT", name.to_uppercase());
+
+                ResolvedPackage {
+                    name: name.to_string(),
+                    version: Version::new(version),
+                    root: vars.get(&root_var).map(PathBuf::from),
+                }
+            })
+            .collect();
+
+        let python_paths = vars
+            .get("PYTHONPATH")
+            .map(|paths| std::env::split_paths(paths).collect())
+            .unwrap_or_default();
+
+        Some(Self {
+            packages,
+            python_paths,
+        })
+    }
+
+    /// Get the resolved packages.
+    pub fn packages(&self) -> &[ResolvedPackage] {
+        &self.packages
+    }
+
+    /// Find the package that provides a Python module.
+    ///
+    /// Only the top-level module name is considered, so `foo.bar.baz`
+    /// resolves through `foo`.
+    pub fn find_module_provider(&self, module: &str) -> Option<&ResolvedPackage> {
+        let top_level = module.split('.').next()?.trim();
+        if top_level.is_empty() {
+            return None;
+        }
+
+        self.packages.iter().find(|package| {
+            let Some(root) = &package.root else {
+                return false;
+            };
+
+            let search_dirs = MODULE_SUBDIRS.iter().map(|subdir| root.join(subdir)).chain(
+                self.python_paths
+                    .iter()
+                    .filter(|path| path.starts_with(root))
+                    .cloned(),
+            );
+
+            search_dirs
+                .into_iter()
+                .any(|dir| provides_module(&dir, top_level))
+        })
+    }
+}
+
+/// Check whether a directory contains a Python module or package.
+fn provides_module(dir: &Path, module: &str) -> bool {
+    dir.join(module).join("__init__.py").is_file()
+        || dir.join(format!("{}.py", module)).is_file()
+        || dir.join(module).is_dir()
+}
+
+/// Extract the module imported by a Python `import` or `from ... import` line.
+pub fn imported_module(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let rest = line
+        .strip_prefix("from ")
+        .or_else(|| line.strip_prefix("import "))?;
+
+    rest.split(|c: char| c.is_whitespace() || c == ',')
+        .find(|part| !part.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn vars(pairs: &[(&str, String)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_not_in_resolved_environment() {
+        assert!(ResolvedEnvironment::from_vars(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_parse_used_resolve() {
+        let env = ResolvedEnvironment::from_vars(vars(&[
+            (
+                "REZ_USED_RESOLVE",
+                "python-3.9.7 maya_tools-1.2 scratch".to_string(),
+            ),
+            ("REZ_PYTHON_ROOT", "/packages/python/3.9.7".to_string()),
+        ]))
+        .unwrap();
+
+        let packages = env.packages();
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0].name, "python");
+        assert_eq!(packages[0].version, Version::new("3.9.7"));
+        assert_eq!(
+            packages[0].root,
+            Some(PathBuf::from("/packages/python/3.9.7"))
+        );
+        assert_eq!(packages[1].name, "maya_tools");
+        assert!(packages[1].root.is_none());
+        assert_eq!(packages[2].name, "scratch");
+    }
+
+    #[test]
+    fn test_find_module_provider() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("maya_tools").join("1.2");
+        fs::create_dir_all(root.join("python").join("maya_tools")).unwrap();
+        fs::write(
+            root.join("python").join("maya_tools").join("__init__.py"),
+            "",
+        )
+        .unwrap();
+
+        let env = ResolvedEnvironment::from_vars(vars(&[
+            ("REZ_USED_RESOLVE", "maya_tools-1.2".to_string()),
+            ("REZ_MAYA_TOOLS_ROOT", root.to_string_lossy().to_string()),
+        ]))
+        .unwrap();
+
+        let provider = env.find_module_provider("maya_tools.ui.dialogs").unwrap();
+        assert_eq!(provider.name, "maya_tools");
+        assert!(env.find_module_provider("numpy").is_none());
+    }
+
+    #[test]
+    fn test_imported_module() {
+        assert_eq!(imported_module("import os.path"), Some("os.path"));
+        assert_eq!(
+            imported_module("from maya_tools import ui"),
+            Some("maya_tools")
+        );
+        assert_eq!(imported_module("  import a, b"), Some("a"));
+        assert_eq!(imported_module("x = 1"), None);
+    }
+}
@@ -0,0 +1,433 @@
+//! Sandboxed evaluation of package definitions.
+//!
+//! Static parsing only understands literal attribute values. Packages that
+//! compute attributes with Python, such as `@early()` bound functions, are
+//! executed by a separate CPython worker instead. One worker process is
+//! started per sandbox and evaluates package.py files one at a time; it runs
+//! in isolated mode with a memory limit, restricted builtins and an import
+//! allow-list, and is killed and restarted once an evaluation exceeds the
+//! configured timeout.
+//!
+//! The restrictions keep well-meaning packages from touching the machine by
+//! accident. They are not a security boundary: Python code can reach the
+//! unrestricted builtins through object introspection, so evaluation runs
+//! package code with the privileges of the server and is only enabled in
+//! trusted workspaces.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::core::{Error, Interner, Package, Requirement, Result, Variant, Version};
+
+/// Script reading the paths of package.py files from stdin, one per line,
+/// and writing one JSON line of evaluated attributes or error per path.
+const EVALUATE_SCRIPT: &str = r#"
+import builtins, json, os, sys
+
+try:
+    import resource
+    resource.setrlimit(resource.RLIMIT_AS, (512 << 20, 512 << 20))
+except Exception:
+    pass
+
+ALLOWED_MODULES = {
+    "collections", "datetime", "functools", "itertools", "json", "math",
+    "platform", "re", "string",
+}
+BLOCKED_BUILTINS = {
+    "__import__", "breakpoint", "compile", "eval", "exec", "exit", "globals",
+    "help", "input", "locals", "open", "quit", "vars",
+}
+# Attributes read into the index, only these are evaluated
+ATTRIBUTES = (
+    "name", "version", "description", "authors", "requires", "tools", "variants",
+)
+
+def restricted_import(name, globals=None, locals=None, fromlist=(), level=0):
+    if level or name.split(".")[0] not in ALLOWED_MODULES:
+        raise ImportError("import of '%s' is not allowed in the sandbox" % name)
+    return builtins.__import__(name, globals, locals, fromlist, level)
+
+def bind(marker):
+    def decorator():
+        def mark(function):
+            setattr(function, marker, True)
+            return function
+        return mark
+    return decorator
+
+class This(object):
+    def __init__(self, namespace):
+        self._namespace = namespace
+
+    def __getattr__(self, name):
+        value = self._namespace.get(name)
+        if getattr(value, "_rez_early", False):
+            value = value()
+        if value is None:
+            raise AttributeError(name)
+        return value
+
+safe_builtins = {
+    name: getattr(builtins, name)
+    for name in dir(builtins)
+    if name not in BLOCKED_BUILTINS
+}
+safe_builtins["__import__"] = restricted_import
+
+def evaluate(path):
+    with open(path) as f:
+        source = f.read()
+    os.chdir(os.path.dirname(path) or ".")
+
+    namespace = {
+        "__builtins__": safe_builtins,
+        "__name__": "__rez_package__",
+        "early": bind("_rez_early"),
+        "late": bind("_rez_late"),
+    }
+    namespace["this"] = This(namespace)
+    exec(compile(source, path, "exec"), namespace)
+
+    result = {}
+    for attribute in ATTRIBUTES:
+        value = namespace.get(attribute)
+        if getattr(value, "_rez_early", False):
+            value = value()
+        if value is not None and not callable(value):
+            result[attribute] = value
+    return result
+
+# Output printed by packages must not corrupt the responses
+responses = sys.stdout
+sys.stdout = sys.stderr
+
+for line in sys.stdin:
+    try:
+        response = {"attributes": evaluate(line.rstrip("\n"))}
+    except BaseException as e:
+        response = {"error": "%s: %s" % (type(e).__name__, e)}
+    responses.write(json.dumps(response, default=str) + "\n")
+    responses.flush()
+"#;
+
+/// Response of the worker to one evaluation.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum WorkerResponse {
+    Attributes(EvaluatedPackage),
+    Error(String),
+}
+
+/// Attributes of a package definition evaluated by Python.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct EvaluatedPackage {
+    /// Package name
+    pub name: Option<String>,
+    /// Package version
+    pub version: Option<String>,
+    /// Package description
+    pub description: Option<String>,
+    /// Package authors
+    pub authors: Vec<String>,
+    /// Runtime requirements
+    pub requires: Vec<String>,
+    /// Tools provided by the package
+    pub tools: Vec<String>,
+    /// Requirements of each variant
+    pub variants: Vec<Vec<String>>,
+}
+
+impl EvaluatedPackage {
+    /// Overwrite the statically parsed attributes of a package with the
+    /// evaluated ones.
+    ///
+    /// The version is only replaced when `replace_version` is set, since the
+    /// version directory of a repository takes precedence. Authors and tools
+    /// are interned with `interner`.
+    pub fn apply(self, package: &mut Package, replace_version: bool, interner: &Interner) {
+        let parse = |requirements: Vec<String>| -> Vec<Requirement> {
+            requirements
+                .iter()
+                .filter_map(|requirement| Requirement::parse(requirement).ok())
+                .collect()
+        };
+
+        if replace_version {
+            if let Some(version) = self.version.filter(|version| !version.is_empty()) {
+                package.version = Version::new(&version);
+            }
+        }
+        if self.description.is_some() {
+            package.description = self.description;
+        }
+        if !self.authors.is_empty() {
+            package.authors = self.authors.iter().map(|a| interner.intern(a)).collect();
+        }
+        if !self.tools.is_empty() {
+            package.tools = self.tools.iter().map(|t| interner.intern(t)).collect();
+        }
+        if !self.requires.is_empty() {
+            package.requires = parse(self.requires);
+        }
+        if !self.variants.is_empty() {
+            package.variants = self
+                .variants
+                .into_iter()
+                .enumerate()
+                .map(|(index, requires)| Variant {
+                    index,
+                    requires: parse(requires),
+                    metadata: HashMap::new(),
+                })
+                .collect();
+        }
+    }
+}
+
+/// A running evaluation worker.
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    /// Response lines, read by a background thread so reads can time out
+    responses: Receiver<String>,
+}
+
+impl Worker {
+    fn spawn(python: &Path) -> Result<Self> {
+        let mut child = Command::new(python)
+            .arg("-I")
+            .arg("-c")
+            .arg(EVALUATE_SCRIPT)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Error::Other(format!("Failed to start {}: {}", python.display(), e)))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let (sender, responses) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            responses,
+        })
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Evaluates package definitions in a restricted Python worker process.
+///
+/// Clones share the worker, which is started on the first evaluation and
+/// stopped when the last clone is dropped.
+#[derive(Clone)]
+pub struct PythonSandbox {
+    /// Python interpreter
+    python: PathBuf,
+    /// Maximum duration of an evaluation
+    timeout: Duration,
+    /// Worker process, if running
+    worker: Arc<Mutex<Option<Worker>>>,
+}
+
+impl std::fmt::Debug for PythonSandbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PythonSandbox")
+            .field("python", &self.python)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl PythonSandbox {
+    /// Create a sandbox running the interpreter at `python`.
+    pub fn new(python: impl Into<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            python: python.into(),
+            timeout,
+            worker: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Create the sandbox configured by [`Config::python_path`] and
+    /// [`Config::evaluation_timeout_secs`].
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            &config.python_path,
+            Duration::from_secs(config.evaluation_timeout_secs),
+        )
+    }
+
+    /// Execute a package.py and return its evaluated attributes.
+    pub async fn evaluate(&self, package_py: &Path) -> Result<EvaluatedPackage> {
+        let sandbox = self.clone();
+        let package_py = package_py.to_path_buf();
+        tokio::task::spawn_blocking(move || sandbox.evaluate_blocking(&package_py))
+            .await
+            .map_err(|e| Error::Other(format!("Sandbox task failed: {}", e)))?
+    }
+
+    fn evaluate_blocking(&self, package_py: &Path) -> Result<EvaluatedPackage> {
+        let path = package_py.to_str().filter(|path| !path.contains('\n'));
+        let Some(path) = path else {
+            return Err(Error::Other(format!(
+                "Cannot evaluate {}: unsupported path",
+                package_py.display()
+            )));
+        };
+
+        let mut guard = self.worker.lock().unwrap_or_else(|e| e.into_inner());
+        let worker = match guard.as_mut() {
+            Some(worker) => worker,
+            None => guard.insert(Worker::spawn(&self.python)?),
+        };
+
+        let sent = writeln!(worker.stdin, "{}", path).and_then(|()| worker.stdin.flush());
+        let response = match sent {
+            Ok(()) => worker.responses.recv_timeout(self.timeout),
+            Err(_) => Err(RecvTimeoutError::Disconnected),
+        };
+        let line = match response {
+            Ok(line) => line,
+            Err(e) => {
+                // The worker is stuck or gone, start a fresh one next time
+                guard.take();
+                return Err(Error::Other(match e {
+                    RecvTimeoutError::Timeout => format!(
+                        "Evaluation of {} did not finish within {}s",
+                        package_py.display(),
+                        self.timeout.as_secs_f32()
+                    ),
+                    RecvTimeoutError::Disconnected => format!(
+                        "{} exited while evaluating {}",
+                        self.python.display(),
+                        package_py.display()
+                    ),
+                }));
+            }
+        };
+
+        match serde_json::from_str(&line) {
+            Ok(WorkerResponse::Attributes(evaluated)) => Ok(evaluated),
+            Ok(WorkerResponse::Error(message)) => Err(Error::Other(format!(
+                "Evaluation of {} failed: {}",
+                package_py.display(),
+                message
+            ))),
+            Err(e) => Err(Error::Other(format!(
+                "Invalid sandbox output for {}: {}",
+                package_py.display(),
+                e
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::write_package;
+
+    fn sandbox() -> PythonSandbox {
+        PythonSandbox::new("python3", Duration::from_secs(10))
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_early_bound_attributes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package_py = temp_dir.path().join("package.py");
+        std::fs::write(
+            &package_py,
+            r#"name = "viewer"
+version = ".".join(str(n) for n in (1, 4, 0))
+
+@early()
+def requires():
+    return ["python-3.%d" % minor for minor in (10,)] + ["ocio"]
+
+variants = [["maya-%d" % year] for year in (2024, 2025)]
+
+def commands():
+    env.PATH.append("{root}/bin")
+"#,
+        )
+        .unwrap();
+
+        let evaluated = sandbox().evaluate(&package_py).await.unwrap();
+        assert_eq!(evaluated.name.as_deref(), Some("viewer"));
+        assert_eq!(evaluated.version.as_deref(), Some("1.4.0"));
+        assert_eq!(evaluated.requires, vec!["python-3.10", "ocio"]);
+        assert_eq!(evaluated.variants.len(), 2);
+
+        let mut package = Package::builder().name("viewer").version("0").build();
+        evaluated.apply(&mut package, true, &Interner::new());
+        assert_eq!(package.version, Version::new("1.4.0"));
+        assert_eq!(package.requires.len(), 2);
+        assert_eq!(package.variants[1].requires[0].name, "maya");
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_restrictions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package_py = temp_dir.path().join("package.py");
+
+        std::fs::write(&package_py, "import os\nname = os.getcwd()\n").unwrap();
+        assert!(sandbox().evaluate(&package_py).await.is_err());
+
+        std::fs::write(&package_py, "name = open('/etc/hostname').read()\n").unwrap();
+        assert!(sandbox().evaluate(&package_py).await.is_err());
+
+        std::fs::write(&package_py, "while True:\n    pass\n").unwrap();
+        let quick = PythonSandbox::new("python3", Duration::from_millis(300));
+        assert!(quick.evaluate(&package_py).await.is_err());
+
+        // A fresh worker replaces the one killed by the timeout
+        std::fs::write(&package_py, "name = 'viewer'\n").unwrap();
+        let evaluated = quick.evaluate(&package_py).await.unwrap();
+        assert_eq!(evaluated.name.as_deref(), Some("viewer"));
+    }
+
+    #[tokio::test]
+    async fn test_worker_is_reused() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let sandbox = sandbox();
+        for name in ["maya", "houdini"] {
+            let content = format!("print('noise')\nname = '{}'\nrequires = ['python']\n", name);
+            let package_py = write_package(temp_dir.path(), name, "", &content).join("package.py");
+
+            let evaluated = sandbox.evaluate(&package_py).await.unwrap();
+            assert_eq!(evaluated.name.as_deref(), Some(name));
+            assert_eq!(evaluated.requires, vec!["python"]);
+        }
+
+        // Packages do not see the attributes of the previous one
+        let package_py = temp_dir.path().join("package.py");
+        std::fs::write(&package_py, "name = 'scratch'\n").unwrap();
+        let evaluated = sandbox.evaluate(&package_py).await.unwrap();
+        assert!(evaluated.requires.is_empty());
+    }
+}
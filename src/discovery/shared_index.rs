@@ -0,0 +1,278 @@
+//! Package index shared between server instances.
+//!
+//! Every editor window starts its own server, and each would rescan the same
+//! repositories. With [`Config::shared_index_dir`] set, the instance that
+//! scans publishes its index to that directory while holding an exclusive
+//! lock on a sibling lock file, and other instances load the published index
+//! while it is fresh. Each publication bumps a generation number, so an
+//! instance that obtains the lock can tell whether another one published
+//! since it last looked. While the lock is held by a scanning instance, the
+//! others scan in-process instead of waiting.
+//!
+//! Indexes are keyed by the settings that shape them, so instances with
+//! different package paths never share an index.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::metrics::DiscoveryMetrics;
+use crate::config::Config;
+use crate::core::PackageIndex;
+
+/// A published package index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexSnapshot {
+    /// Number of times the index was published
+    pub generation: u64,
+    /// Publication time in seconds since the Unix epoch
+    pub created_at: u64,
+    /// Indexed packages by family name
+    pub packages: PackageIndex,
+    /// Number of versions left out per family by the version cap
+    pub elided_versions: HashMap<String, usize>,
+    /// Metrics of the scan that built the index
+    pub metrics: DiscoveryMetrics,
+}
+
+/// Exclusive right to rebuild and publish a shared index.
+///
+/// The lock is released when the guard is dropped.
+#[derive(Debug)]
+pub struct IndexLock {
+    _file: File,
+}
+
+/// Location and freshness policy of a shared package index.
+#[derive(Debug, Clone)]
+pub struct SharedIndex {
+    dir: PathBuf,
+    key: String,
+    max_age: Duration,
+}
+
+impl SharedIndex {
+    /// Get the shared index of a configuration, if sharing is enabled.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let dir = config.shared_index_dir.clone()?;
+        Some(Self {
+            dir,
+            key: index_key(config),
+            max_age: Duration::from_secs(config.shared_index_max_age_secs),
+        })
+    }
+
+    /// Get the path of the published index.
+    pub fn index_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.json", self.key))
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.lock", self.key))
+    }
+
+    /// Load the published index, if any.
+    pub fn load(&self) -> Option<IndexSnapshot> {
+        let content = fs::read(self.index_path()).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    /// Check whether a published index can be reused.
+    pub fn is_fresh(&self, snapshot: &IndexSnapshot) -> bool {
+        let age = unix_now().saturating_sub(snapshot.created_at);
+        Duration::from_secs(age) <= self.max_age
+    }
+
+    /// Load the published index if it is fresh.
+    pub fn load_fresh(&self) -> Option<IndexSnapshot> {
+        self.load().filter(|snapshot| self.is_fresh(snapshot))
+    }
+
+    /// Try to take the lock without waiting.
+    ///
+    /// Returns `None` when another instance holds it.
+    pub fn try_lock(&self) -> io::Result<Option<IndexLock>> {
+        fs::create_dir_all(&self.dir)?;
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.lock_path())?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(IndexLock { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    /// Publish an index as the next generation after `previous`.
+    ///
+    /// The index is written to a temporary file and renamed into place, so
+    /// readers never see a partial index.
+    pub fn publish(
+        &self,
+        _lock: &IndexLock,
+        previous: Option<&IndexSnapshot>,
+        packages: &PackageIndex,
+        elided_versions: &HashMap<String, usize>,
+        metrics: &DiscoveryMetrics,
+    ) -> io::Result<u64> {
+        let snapshot = IndexSnapshot {
+            generation: previous.map_or(0, |previous| previous.generation) + 1,
+            created_at: unix_now(),
+            packages: packages.clone(),
+            elided_versions: elided_versions.clone(),
+            metrics: metrics.clone(),
+        };
+        let content = serde_json::to_vec(&snapshot).map_err(io::Error::other)?;
+
+        let temp_path = self
+            .dir
+            .join(format!("{}.{}.tmp", self.key, std::process::id()));
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, self.index_path())?;
+        Ok(snapshot.generation)
+    }
+}
+
+/// Get the current time in seconds since the Unix epoch.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Get the file name key of the index built for a configuration.
+///
+/// Uses FNV-1a, which is stable across builds unlike the standard hasher.
+fn index_key(config: &Config) -> String {
+    let paths: Vec<String> = config
+        .get_all_package_paths()
+        .iter()
+        .map(|path| path_key(path))
+        .collect();
+    let settings = format!(
+        "{}\n{:?}\n{:?}\n{:?}",
+        paths.join("\n"),
+        config.max_versions_per_family,
+        config.package_evaluation,
+        config.scan_exclude
+    );
+
+    let hash = settings.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("index-{:016x}", hash)
+}
+
+/// Get a path as used in index keys, absolute where possible.
+fn path_key(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Package;
+    use std::sync::Arc;
+
+    fn shared_index(dir: &Path, packages_path: &str) -> SharedIndex {
+        let mut config = Config::new();
+        config.packages_path = vec![PathBuf::from(packages_path)];
+        config.shared_index_dir = Some(dir.to_path_buf());
+        SharedIndex::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_publish_and_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let shared = shared_index(temp_dir.path(), "/studio/packages");
+        assert!(shared.load().is_none());
+
+        let packages = HashMap::from([(
+            "maya".to_string(),
+            vec![Arc::new(
+                Package::builder()
+                    .name("maya")
+                    .version("2024.1")
+                    .path("/studio/packages/maya/2024.1")
+                    .build(),
+            )],
+        )]);
+        let lock = shared.try_lock().unwrap().unwrap();
+        let generation = shared
+            .publish(
+                &lock,
+                None,
+                &packages,
+                &HashMap::new(),
+                &DiscoveryMetrics::default(),
+            )
+            .unwrap();
+        assert_eq!(generation, 1);
+
+        let snapshot = shared.load_fresh().unwrap();
+        assert_eq!(snapshot.packages, packages);
+        let generation = shared
+            .publish(
+                &lock,
+                Some(&snapshot),
+                &packages,
+                &HashMap::new(),
+                &DiscoveryMetrics::default(),
+            )
+            .unwrap();
+        assert_eq!(generation, 2);
+
+        // Other package paths use another index
+        assert!(shared_index(temp_dir.path(), "/home/dev/packages")
+            .load()
+            .is_none());
+    }
+
+    #[test]
+    fn test_index_key_covers_excludes() {
+        let mut config = Config::new();
+        config.packages_path = vec![PathBuf::from("/studio/packages")];
+        let key = index_key(&config);
+
+        config.scan_exclude = vec!["build/".to_string()];
+        assert_ne!(index_key(&config), key);
+    }
+
+    #[test]
+    fn test_freshness() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let shared = shared_index(temp_dir.path(), "/studio/packages");
+        let mut snapshot = IndexSnapshot {
+            generation: 1,
+            created_at: unix_now(),
+            packages: HashMap::new(),
+            elided_versions: HashMap::new(),
+            metrics: DiscoveryMetrics::default(),
+        };
+        assert!(shared.is_fresh(&snapshot));
+        snapshot.created_at -= 301;
+        assert!(!shared.is_fresh(&snapshot));
+    }
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let shared = shared_index(temp_dir.path(), "/studio/packages");
+
+        let lock = shared.try_lock().unwrap();
+        assert!(lock.is_some());
+        assert!(shared.try_lock().unwrap().is_none());
+
+        drop(lock);
+        assert!(shared.try_lock().unwrap().is_some());
+    }
+}
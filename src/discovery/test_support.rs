@@ -0,0 +1,34 @@
+//! Package repositories of unit tests.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+use super::PackageDiscoveryImpl;
+
+/// Write a `package.py` with `content` to the `family`/`version` directory
+/// of the repository at `root`, returning the package directory.
+///
+/// An empty `version` writes an unversioned package.
+pub fn write_package(root: &Path, family: &str, version: &str, content: &str) -> PathBuf {
+    let dir = root.join(family).join(version);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("package.py"), content).unwrap();
+    dir
+}
+
+/// Get a configuration searching the repositories `roots` only.
+pub fn repository_config(roots: &[&Path]) -> Config {
+    let mut config = Config::new();
+    config.packages_path = roots.iter().map(|root| root.to_path_buf()).collect();
+    config.local_packages_path = None;
+    config.release_packages_path = None;
+    config
+}
+
+/// Scan the repositories `roots` into a new package index.
+pub async fn scan_repositories(roots: &[&Path]) -> PackageDiscoveryImpl {
+    let mut discovery = PackageDiscoveryImpl::new(repository_config(roots));
+    discovery.scan_shared().await.unwrap();
+    discovery
+}
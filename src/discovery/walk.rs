@@ -0,0 +1,347 @@
+//! Workspace traversal honoring ignore files.
+//!
+//! Workspaces contain build artifacts and virtual environments that must not
+//! be scanned for package definitions or resolved contexts. [`WorkspaceWalker`]
+//! skips paths matched by `.gitignore` and `.rezignore` files, using gitignore
+//! semantics, and by the configured [`Config::scan_exclude`] patterns.
+
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::config::Config;
+
+/// Ignore files read in every traversed directory.
+pub const IGNORE_FILES: [&str; 2] = [".gitignore", ".rezignore"];
+
+/// Directories never traversed.
+const ALWAYS_SKIPPED: [&str; 3] = [".git", ".hg", ".svn"];
+
+/// A single gitignore pattern.
+#[derive(Debug, Clone)]
+struct IgnorePattern {
+    /// Matches the path relative to the directory of the pattern
+    regex: Regex,
+    /// `!pattern`, re-including previously ignored paths
+    negated: bool,
+    /// `pattern/`, only matching directories
+    directory_only: bool,
+}
+
+impl IgnorePattern {
+    /// Parse a line of an ignore file, skipping blank lines and comments.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (directory_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        if pattern.is_empty() {
+            return None;
+        }
+
+        // Patterns with a separator other than a trailing one are relative to
+        // the ignore file, the others match at any depth.
+        let anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+        let prefix = if anchored { "^" } else { "^(?:.*/)?" };
+        let regex = match Regex::new(&format!("{}{}$", prefix, glob_to_regex(pattern))) {
+            Ok(regex) => regex,
+            Err(e) => {
+                warn!("Ignoring invalid ignore pattern '{}': {}", line, e);
+                return None;
+            }
+        };
+
+        Some(Self {
+            regex,
+            negated,
+            directory_only,
+        })
+    }
+}
+
+/// Translate a gitignore glob to a regular expression.
+pub(crate) fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let class = match class.strip_prefix('!') {
+                    Some(rest) => format!("^{}", rest),
+                    None => class,
+                };
+                regex.push('[');
+                regex.push_str(&class.replace('\\', "\\\\").replace('[', "\\["));
+                regex.push(']');
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex
+}
+
+/// Patterns of one ignore file, relative to its directory.
+#[derive(Debug, Clone)]
+struct IgnoreRules {
+    base: PathBuf,
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreRules {
+    fn new<'a>(base: &Path, lines: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            base: base.to_path_buf(),
+            patterns: lines.into_iter().filter_map(IgnorePattern::parse).collect(),
+        }
+    }
+
+    /// Read the ignore files of a directory.
+    fn read(dir: &Path) -> Option<Self> {
+        let content: String = IGNORE_FILES
+            .iter()
+            .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let rules = Self::new(dir, content.lines());
+        (!rules.patterns.is_empty()).then_some(rules)
+    }
+
+    /// Get whether the last matching pattern ignores or re-includes a path.
+    fn decision(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.base).ok()?;
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| {
+                (is_dir || !pattern.directory_only) && pattern.regex.is_match(&relative)
+            })
+            .map(|pattern| !pattern.negated)
+    }
+}
+
+/// Files found by a workspace traversal.
+#[derive(Debug, Clone, Default)]
+pub struct WalkResult {
+    /// Matching files, sorted
+    pub files: Vec<PathBuf>,
+    /// Directories that could not be read, with the reason
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+/// Recursive directory traversal skipping ignored paths.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceWalker {
+    /// Configured exclude patterns, overriding ignore files
+    excludes: Vec<String>,
+}
+
+impl WorkspaceWalker {
+    /// Create a walker excluding `patterns` in addition to ignore files.
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            excludes: patterns.to_vec(),
+        }
+    }
+
+    /// Create a walker excluding [`Config::scan_exclude`].
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(&config.scan_exclude)
+    }
+
+    /// Collect the files below `root` accepted by `filter`.
+    pub fn files(&self, root: &Path, filter: impl Fn(&Path) -> bool) -> WalkResult {
+        let excludes = IgnoreRules::new(root, self.excludes.iter().map(String::as_str));
+        let mut result = WalkResult::default();
+        let mut rules = Vec::new();
+        let mut visited = HashSet::new();
+        walk_dir(
+            root,
+            &excludes,
+            &mut rules,
+            &mut visited,
+            &filter,
+            &mut result,
+        );
+        result.files.sort();
+        result
+    }
+}
+
+/// Traverse `dir`, following symlinks but entering every directory once so
+/// that symlink cycles end.
+fn walk_dir(
+    dir: &Path,
+    excludes: &IgnoreRules,
+    rules: &mut Vec<IgnoreRules>,
+    visited: &mut HashSet<PathBuf>,
+    filter: &dyn Fn(&Path) -> bool,
+    result: &mut WalkResult,
+) {
+    let canonical = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    if !visited.insert(canonical) {
+        return;
+    }
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            result.errors.push((dir.to_path_buf(), e.to_string()));
+            return;
+        }
+    };
+
+    let pushed = match IgnoreRules::read(dir) {
+        Some(dir_rules) => {
+            rules.push(dir_rules);
+            true
+        }
+        None => false,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        if is_dir
+            && ALWAYS_SKIPPED
+                .iter()
+                .any(|name| entry.file_name() == std::ffi::OsStr::new(name))
+        {
+            continue;
+        }
+
+        // Deeper ignore files override shallower ones, and configured
+        // excludes override both.
+        let ignored = std::iter::once(excludes)
+            .chain(rules.iter().rev())
+            .find_map(|rules| rules.decision(&path, is_dir))
+            .unwrap_or(false);
+        if ignored {
+            continue;
+        }
+
+        if is_dir {
+            walk_dir(&path, excludes, rules, visited, filter, result);
+        } else if filter(&path) {
+            result.files.push(path);
+        }
+    }
+
+    if pushed {
+        rules.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(root: &Path, relative: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn test_ignore_pattern_semantics() {
+        let rules = IgnoreRules::new(
+            Path::new("/ws"),
+            [
+                "# comment",
+                "*.pyc",
+                "/dist",
+                "build/",
+                "docs/**/*.rxt",
+                "!keep.pyc",
+            ],
+        );
+        let ignored = |path: &str, is_dir: bool| rules.decision(Path::new(path), is_dir);
+
+        assert_eq!(ignored("/ws/a/b/module.pyc", false), Some(true));
+        assert_eq!(ignored("/ws/a/keep.pyc", false), Some(false));
+        assert_eq!(ignored("/ws/dist", true), Some(true));
+        assert_eq!(ignored("/ws/a/dist", true), None);
+        assert_eq!(ignored("/ws/a/build", true), Some(true));
+        assert_eq!(ignored("/ws/a/build", false), None);
+        assert_eq!(ignored("/ws/docs/x/y/env.rxt", false), Some(true));
+        assert_eq!(ignored("/ws/docs/env.rxt", false), Some(true));
+        assert_eq!(ignored("/other/module.pyc", false), None);
+    }
+
+    #[test]
+    fn test_walker_honors_ignore_files_and_excludes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        for file in [
+            "env.rxt",
+            "build/env.rxt",
+            "venv/lib/env.rxt",
+            "shots/a/env.rxt",
+            "shots/a/scratch/env.rxt",
+            "shots/b/env.rxt",
+            ".git/env.rxt",
+        ] {
+            touch(root, file);
+        }
+        fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        fs::write(root.join("shots/a/.rezignore"), "scratch\n").unwrap();
+
+        let walker = WorkspaceWalker::new(&["venv/".to_string(), "shots/b".to_string()]);
+        let result = walker.files(root, |path| path.extension().is_some_and(|e| e == "rxt"));
+        let files: Vec<_> = result
+            .files
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            files,
+            vec![PathBuf::from("env.rxt"), PathBuf::from("shots/a/env.rxt")]
+        );
+        assert!(result.errors.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walker_ends_symlink_cycles() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        touch(root, "shots/a/env.rxt");
+        std::os::unix::fs::symlink(root, root.join("shots/a/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("shots/a"), root.join("alias")).unwrap();
+
+        let result = WorkspaceWalker::default()
+            .files(root, |path| path.extension().is_some_and(|e| e == "rxt"));
+        // The file is found once, through whichever path is walked first
+        assert_eq!(result.files.len(), 1);
+        assert!(result.files[0].ends_with("env.rxt"));
+    }
+}
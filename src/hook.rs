@@ -0,0 +1,422 @@
+//! Commit-time validation hooks.
+//!
+//! The `install-hook` subcommand runs the `fix` engine and the validator
+//! against the staged package.py files of a git repository, either as a
+//! plain git pre-commit hook or as an entry of a
+//! [pre-commit](https://pre-commit.com) framework configuration.
+//!
+//! Git hooks check the staged content rather than the working tree, and
+//! with `--fix` stage the fixed content without the unstaged changes of the
+//! files.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Marker line of generated hooks, so they may be replaced without `--force`.
+pub const HOOK_MARKER: &str = "# Generated by rez-lsp-server install-hook";
+
+/// Name of the pre-commit framework configuration file.
+pub const PRE_COMMIT_CONFIG: &str = ".pre-commit-config.yaml";
+
+/// Output switches of the subcommands run by hooks, so their reports stay
+/// plain text whatever the defaults.
+const OUTPUT_ARGS: &str = "--text --no-color";
+
+/// Arguments of the subcommands run by hooks: the `fix` subcommand, then
+/// the validator.
+fn hook_commands(fix: bool) -> [&'static str; 2] {
+    [if fix { "fix" } else { "fix --check" }, "validate"]
+}
+
+/// Generate a git pre-commit hook validating the staged package.py files.
+///
+/// The hook checks the files by default; with `fix` it fixes the staged
+/// content and stages the result. `rez-lsp-server` is looked up on `PATH`
+/// when the hook runs, or taken from `REZ_LSP_SERVER`, so the hook keeps
+/// working when the binary moves.
+pub fn pre_commit_script(fix: bool) -> String {
+    let staged =
+        "git diff --cached --name-only --diff-filter=ACMR -z -- 'package.py' '*/package.py'";
+    let mut script = format!(
+        "#!/bin/sh\n\
+         {marker}\n\
+         # Validates staged package.py files; skip with `git commit --no-verify`.\n\
+         \n\
+         if [ -z \"$({staged} | tr -d '\\0')\" ]; then\n    exit 0\nfi\n\
+         \n\
+         rez_lsp_server=\"${{REZ_LSP_SERVER:-rez-lsp-server}}\"\n\
+         if ! command -v \"$rez_lsp_server\" >/dev/null 2>&1; then\n\
+         \x20   echo \"rez-lsp-server not found; add it to PATH or set REZ_LSP_SERVER\" >&2\n\
+         \x20   exit 1\n\
+         fi\n\
+         \n",
+        marker = HOOK_MARKER,
+        staged = staged,
+    );
+    for command in hook_commands(fix) {
+        script.push_str(&format!(
+            "\"$rez_lsp_server\" {} --staged {} || exit 1\n",
+            command, OUTPUT_ARGS
+        ));
+    }
+    script
+}
+
+/// Generate a pre-commit framework configuration declaring local hooks that
+/// run `rez-lsp-server` against package.py files.
+///
+/// The framework stashes unstaged changes itself, so the hooks check the
+/// files it passes.
+pub fn pre_commit_config(fix: bool) -> String {
+    let [fix_command, validate_command] = hook_commands(fix);
+    let mut config = "repos:\n  - repo: local\n    hooks:\n".to_string();
+    for (id, name, command) in [
+        (
+            if fix { "fix" } else { "check" },
+            "rez package definitions",
+            fix_command,
+        ),
+        ("validate", "rez package validation", validate_command),
+    ] {
+        config.push_str(&format!(
+            "\x20     - id: rez-lsp-{id}\n\
+             \x20       name: {name}\n\
+             \x20       entry: rez-lsp-server {command} {output}\n\
+             \x20       language: system\n\
+             \x20       files: (^|/)package\\.py$\n",
+            id = id,
+            name = name,
+            command = command,
+            output = OUTPUT_ARGS,
+        ));
+    }
+    config
+}
+
+/// A package.py file staged in the git index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagedFile {
+    /// Path relative to the repository root
+    pub path: PathBuf,
+    /// Mode of the index entry, e.g. `100644`
+    mode: String,
+    /// Object name of the staged blob
+    blob: String,
+}
+
+/// Run git in `dir`, feeding it `input`, and get its output.
+fn git(dir: &Path, args: &[&str], input: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input)
+            .map_err(|e| format!("Failed to write to git: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Find the root of the git repository containing `dir`.
+pub fn repository_root(dir: &Path) -> Result<PathBuf, String> {
+    let stdout = git(dir, &["rev-parse", "--show-toplevel"], None)
+        .map_err(|_| format!("{} is not a git repository", dir.display()))?;
+    Ok(PathBuf::from(String::from_utf8_lossy(&stdout).trim()))
+}
+
+/// List the package.py files added, copied, modified or renamed in the
+/// index of the repository at `root`.
+pub fn staged_package_files(root: &Path) -> Result<Vec<StagedFile>, String> {
+    let stdout = git(
+        root,
+        &[
+            "diff",
+            "--cached",
+            "--raw",
+            "-z",
+            "--no-abbrev",
+            "--diff-filter=ACMR",
+            "--",
+            "package.py",
+            "*/package.py",
+        ],
+        None,
+    )?;
+    Ok(parse_raw_diff(&stdout))
+}
+
+/// Parse the records of `git diff --raw -z` into the staged files.
+fn parse_raw_diff(output: &[u8]) -> Vec<StagedFile> {
+    let mut fields = output
+        .split(|byte| *byte == 0)
+        .filter(|field| !field.is_empty())
+        .map(|field| String::from_utf8_lossy(field).into_owned());
+    let mut files = Vec::new();
+    // `:<old mode> <new mode> <old blob> <new blob> <status>`, then the path
+    while let Some(record) = fields.next() {
+        let parts: Vec<&str> = record.trim_start_matches(':').split(' ').collect();
+        let [_, mode, _, blob, status] = parts[..] else {
+            break;
+        };
+        // Renames and copies name their source first
+        if status.starts_with(['R', 'C']) {
+            fields.next();
+        }
+        let Some(path) = fields.next() else {
+            break;
+        };
+        files.push(StagedFile {
+            path: PathBuf::from(path),
+            mode: mode.to_string(),
+            blob: blob.to_string(),
+        });
+    }
+    files
+}
+
+/// Read the staged content of `file`.
+pub fn read_staged(root: &Path, file: &StagedFile) -> Result<Vec<u8>, String> {
+    git(root, &["cat-file", "blob", &file.blob], None)
+}
+
+/// Stage `content` as the content of `file`.
+///
+/// Only the index entry is replaced, so changes of the working tree file
+/// that were not staged stay unstaged.
+pub fn stage_content(root: &Path, file: &StagedFile, content: &[u8]) -> Result<(), String> {
+    let blob = git(root, &["hash-object", "-w", "--stdin"], Some(content))?;
+    let blob = String::from_utf8_lossy(&blob).trim().to_string();
+    let path = file.path.to_string_lossy();
+    git(
+        root,
+        &["update-index", "--cacheinfo", &file.mode, &blob, &path],
+        None,
+    )?;
+    Ok(())
+}
+
+/// Outcome of an installation.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Installed {
+    /// The file was written
+    Written(PathBuf),
+    /// The file already declares the hook and was left untouched
+    Unchanged(PathBuf),
+}
+
+/// Write `script` as the pre-commit hook of `hooks_dir`.
+///
+/// An existing hook is only replaced if it was generated, or with `force`.
+pub fn install_pre_commit(
+    hooks_dir: &Path,
+    script: &str,
+    force: bool,
+) -> Result<Installed, String> {
+    let path = hooks_dir.join("pre-commit");
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        if existing == script {
+            return Ok(Installed::Unchanged(path));
+        }
+        if !force && !existing.contains(HOOK_MARKER) {
+            return Err(format!(
+                "{} already exists; use --force to replace it",
+                path.display()
+            ));
+        }
+    }
+    std::fs::create_dir_all(hooks_dir)
+        .and_then(|_| std::fs::write(&path, script))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+    }
+    Ok(Installed::Written(path))
+}
+
+/// Add the hook of [`pre_commit_config`] to the configuration of `repo`.
+///
+/// A missing configuration is created. An existing one is left untouched
+/// when it already runs `rez-lsp-server`, and otherwise refused, since its
+/// `repos` list cannot be extended safely without a YAML editor.
+pub fn install_pre_commit_config(repo: &Path, fix: bool) -> Result<Installed, String> {
+    let path = repo.join(PRE_COMMIT_CONFIG);
+    match std::fs::read_to_string(&path) {
+        Ok(existing) if existing.contains("rez-lsp-server") => Ok(Installed::Unchanged(path)),
+        Ok(_) => Err(format!(
+            "{} already exists; add this entry to its repos:\n{}",
+            path.display(),
+            pre_commit_config(fix)
+                .lines()
+                .skip(1)
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+        Err(_) => std::fs::write(&path, pre_commit_config(fix))
+            .map(|_| Installed::Written(path.clone()))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e)),
+    }
+}
+
+/// Find the hooks directory of the git repository at `repo`.
+///
+/// Asks git, so worktrees and `core.hooksPath` are honored, and falls back
+/// to `.git/hooks` when git is not available.
+pub fn hooks_dir(repo: &Path) -> Result<PathBuf, String> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            let dir = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+            Ok(if dir.is_absolute() {
+                dir
+            } else {
+                repo.join(dir)
+            })
+        }
+        Ok(_) => Err(format!("{} is not a git repository", repo.display())),
+        Err(_) if repo.join(".git").is_dir() => Ok(repo.join(".git").join("hooks")),
+        Err(_) => Err(format!("{} is not a git repository", repo.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pre_commit_script() {
+        let script = pre_commit_script(false);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains(HOOK_MARKER));
+        assert!(script.contains("rez_lsp_server=\"${REZ_LSP_SERVER:-rez-lsp-server}\""));
+        assert!(script.contains(
+            "\"$rez_lsp_server\" fix --check --staged --text --no-color || exit 1\n\
+             \"$rez_lsp_server\" validate --staged --text --no-color || exit 1\n"
+        ));
+        assert!(!script.contains("git add"));
+
+        let script = pre_commit_script(true);
+        assert!(script.contains("\"$rez_lsp_server\" fix --staged --text --no-color || exit 1"));
+        assert!(script.contains("\"$rez_lsp_server\" validate --staged"));
+    }
+
+    #[test]
+    fn test_parse_raw_diff() {
+        let blob = "a".repeat(40);
+        let output = format!(
+            ":000000 100644 {zero} {blob} A\0package.py\0\
+             :100644 100755 {blob} {blob} M\0tools/package.py\0\
+             :100644 100644 {blob} {blob} R087\0old/package.py\0new/package.py\0",
+            zero = "0".repeat(40),
+            blob = blob,
+        );
+        let files = parse_raw_diff(output.as_bytes());
+        let paths: Vec<&Path> = files.iter().map(|file| file.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("package.py"),
+                Path::new("tools/package.py"),
+                Path::new("new/package.py")
+            ]
+        );
+        assert_eq!(files[1].mode, "100755");
+        assert_eq!(files[0].blob, blob);
+    }
+
+    #[test]
+    fn test_stage_content_keeps_unstaged_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let run = |args: &[&str]| git(root, args, None).unwrap();
+        run(&["init", "-q"]);
+        std::fs::write(root.join("package.py"), "name = 'tool'\n").unwrap();
+        run(&["add", "package.py"]);
+        std::fs::write(root.join("package.py"), "name = 'tool'\n# wip\n").unwrap();
+
+        let files = staged_package_files(root).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(read_staged(root, &files[0]).unwrap(), b"name = 'tool'\n");
+
+        stage_content(root, &files[0], b"name = \"tool\"\n").unwrap();
+        let files = staged_package_files(root).unwrap();
+        assert_eq!(read_staged(root, &files[0]).unwrap(), b"name = \"tool\"\n");
+        assert_eq!(
+            std::fs::read_to_string(root.join("package.py")).unwrap(),
+            "name = 'tool'\n# wip\n"
+        );
+    }
+
+    #[test]
+    fn test_install_pre_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        let script = pre_commit_script(false);
+        assert_eq!(
+            install_pre_commit(&hooks, &script, false),
+            Ok(Installed::Written(hooks.join("pre-commit")))
+        );
+        assert_eq!(
+            install_pre_commit(&hooks, &script, false),
+            Ok(Installed::Unchanged(hooks.join("pre-commit")))
+        );
+        // Generated hooks are replaced, others need --force
+        let fix = pre_commit_script(true);
+        assert!(install_pre_commit(&hooks, &fix, false).is_ok());
+        std::fs::write(hooks.join("pre-commit"), "#!/bin/sh\nmake lint\n").unwrap();
+        assert!(install_pre_commit(&hooks, &script, false).is_err());
+        assert!(install_pre_commit(&hooks, &script, true).is_ok());
+        assert_eq!(
+            std::fs::read_to_string(hooks.join("pre-commit")).unwrap(),
+            script
+        );
+    }
+
+    #[test]
+    fn test_install_pre_commit_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PRE_COMMIT_CONFIG);
+        assert_eq!(
+            install_pre_commit_config(dir.path(), false),
+            Ok(Installed::Written(path.clone()))
+        );
+        let config = std::fs::read_to_string(&path).unwrap();
+        assert!(config.contains("entry: rez-lsp-server fix --check --text --no-color"));
+        assert!(config.contains("entry: rez-lsp-server validate --text --no-color"));
+        assert_eq!(
+            install_pre_commit_config(dir.path(), true),
+            Ok(Installed::Unchanged(path.clone()))
+        );
+
+        std::fs::write(&path, "repos: []\n").unwrap();
+        let error = install_pre_commit_config(dir.path(), true).unwrap_err();
+        assert!(error.contains("- id: rez-lsp-fix"));
+    }
+}
@@ -23,37 +23,54 @@
 //!
 //! The server is built with a modular architecture:
 //! - **Core**: Fundamental types and traits
+//! - **Analysis**: Index-wide analytics such as requirement usage
 //! - **Config**: Rez configuration management
 //! - **Discovery**: Package discovery and caching
 //! - **Parser**: Rez package.py file parsing
 //! - **Resolver**: Dependency resolution engine
-//! - **LSP**: Language Server Protocol implementation
+//! - **LSP**: Language Server Protocol implementation (behind the `lsp` feature)
+//!
+//! ## Cargo Features
+//!
+//! - `lsp` (default): the language server and the `rez-lsp-server` binary.
+//!   Disable default features to embed the core modules in other Rust tools
+//!   without pulling in `tower-lsp` or a full `tokio` runtime.
+//! - `python-eval`: evaluate package.py files in a restricted CPython
+//!   worker, for packages whose attributes are computed by Python code.
 //!
 //! ## Example
 //!
 //! ```rust,no_run
+//! # #[cfg(feature = "lsp")]
 //! use rez_lsp_server::server::RezLanguageServer;
-//! use tower_lsp::{LspService, Server};
+//! # #[cfg(feature = "lsp")]
+//! use tower_lsp::Server;
 //!
+//! # #[cfg(feature = "lsp")]
 //! #[tokio::main]
 //! async fn main() {
 //!     let stdin = tokio::io::stdin();
 //!     let stdout = tokio::io::stdout();
 //!
-//!     let (service, socket) = LspService::new(RezLanguageServer::new);
+//!     let (service, socket) = RezLanguageServer::build_service();
 //!     Server::new(stdin, stdout, socket).serve(service).await;
 //! }
+//! # #[cfg(not(feature = "lsp"))]
+//! # fn main() {}
 //! ```
 
+pub mod analysis;
 pub mod config;
 pub mod core;
 pub mod discovery;
 pub mod parser;
 pub mod performance;
 pub mod resolver;
+#[cfg(feature = "lsp")]
 pub mod server;
 pub mod validation;
 
 // Re-export commonly used types
 pub use core::{Error, Result};
+#[cfg(feature = "lsp")]
 pub use server::RezLanguageServer;
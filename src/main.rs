@@ -10,9 +10,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use rez_lsp_server::server::RezLanguageServer;
+mod cli;
+mod hook;
+
+use cli::{Output, Style, EXIT_FINDINGS, EXIT_OK};
+use rez_lsp_server::analysis::{DependencyManifest, ManifestFormat, ReportFormat};
+use rez_lsp_server::config::RezConfigProvider;
+use rez_lsp_server::core::PackageDiscovery;
+use rez_lsp_server::discovery::{PackageDiscoveryImpl, WorkspaceWalker};
+use rez_lsp_server::parser::encoding::{
+    decode, encode, read_source, read_source_with_encoding, write_source, SourceEncoding,
+};
+use rez_lsp_server::parser::string_attribute;
+use rez_lsp_server::resolver::create_resolver;
+use rez_lsp_server::server::{ProtocolTrace, RezLanguageServer, TracedReader, TracedWriter};
+use rez_lsp_server::validation::{
+    apply_fixes, find_fixes, Severity as ValidationSeverity, Suppressions, ValidationEngine,
+};
 use std::env;
-use tower_lsp::{LspService, Server};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tower_lsp::Server;
 
 #[tokio::main]
 async fn main() {
@@ -26,11 +44,18 @@ async fn main() {
         .compact() // Use compact format
         .init();
 
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let output = Output::from_args(&mut args);
+    let mut trace_output = None;
+    let mut stdio_trace = None;
+    let mut parent_pid = None;
+    #[cfg(feature = "http")]
+    let mut http_addr: Option<std::net::SocketAddr> = None;
 
     // Handle command line arguments
-    if args.len() > 1 {
-        match args[1].as_str() {
+    let mut index = 1;
+    while index < args.len() {
+        match args[index].as_str() {
             "--help" | "-h" => {
                 print_help();
                 return;
@@ -43,12 +68,74 @@ async fn main() {
                 // This is the default mode for LSP, just continue
                 tracing::info!("Starting in stdio mode (LSP)");
             }
+            "--profile-out" => {
+                index += 1;
+                let Some(path) = args.get(index) else {
+                    eprintln!("Missing value for --profile-out");
+                    print_help();
+                    std::process::exit(1);
+                };
+                trace_output = Some(PathBuf::from(path));
+            }
+            "--stdio-trace" => {
+                index += 1;
+                let Some(path) = args.get(index) else {
+                    eprintln!("Missing value for --stdio-trace");
+                    print_help();
+                    std::process::exit(1);
+                };
+                stdio_trace = Some(PathBuf::from(path));
+            }
+            "--parent-pid" => {
+                index += 1;
+                let Some(pid) = args.get(index).and_then(|pid| pid.parse().ok()) else {
+                    eprintln!("Missing or invalid value for --parent-pid");
+                    print_help();
+                    std::process::exit(1);
+                };
+                parent_pid = Some(pid);
+            }
+            "audit" => {
+                std::process::exit(audit_freshness(&args[index + 1..], &output).await);
+            }
+            "manifest" => {
+                std::process::exit(dependency_manifest(&args[index + 1..], &output).await);
+            }
+            "fix" => {
+                std::process::exit(fix_packages(&args[index + 1..], &output).await);
+            }
+            "validate" => {
+                std::process::exit(validate_packages(&args[index + 1..], &output).await);
+            }
+            "install-hook" => {
+                std::process::exit(install_hook(&args[index + 1..], &output));
+            }
+            "--stale-environments" => {
+                index += 1;
+                let Some(dir) = args.get(index) else {
+                    eprintln!("Missing value for --stale-environments");
+                    print_help();
+                    std::process::exit(1);
+                };
+                std::process::exit(audit_stale_environments(PathBuf::from(dir), &output).await);
+            }
+            #[cfg(feature = "http")]
+            "--http-addr" => {
+                index += 1;
+                let Some(addr) = args.get(index).and_then(|addr| addr.parse().ok()) else {
+                    eprintln!("Missing or invalid value for --http-addr");
+                    print_help();
+                    std::process::exit(1);
+                };
+                http_addr = Some(addr);
+            }
             _ => {
-                eprintln!("Unknown argument: {}", args[1]);
+                eprintln!("Unknown argument: {}", args[index]);
                 print_help();
                 std::process::exit(1);
             }
         }
+        index += 1;
     }
 
     // Start LSP server (default mode)
@@ -57,11 +144,632 @@ async fn main() {
         env!("CARGO_PKG_VERSION")
     );
 
+    let protocol_trace = match stdio_trace {
+        Some(path) => match ProtocolTrace::to_file(&path) {
+            Ok(protocol_trace) => {
+                tracing::info!("Tracing JSON-RPC traffic to {}", path.display());
+                Some(Arc::new(protocol_trace))
+            }
+            Err(e) => {
+                eprintln!("Failed to open {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let server_trace = protocol_trace.clone();
+    let (service, socket) = RezLanguageServer::build_service_with(trace_output, |server| {
+        let server = match server_trace {
+            Some(protocol_trace) => server.with_protocol_trace(protocol_trace),
+            None => server,
+        };
+        match parent_pid {
+            Some(pid) => server.with_parent_pid(pid),
+            None => server,
+        }
+    });
+
+    #[cfg(feature = "http")]
+    if let Some(addr) = http_addr {
+        use rez_lsp_server::server::http;
+
+        match http::bind(addr).await {
+            Ok(listener) => {
+                tokio::spawn(http::serve(listener, service.inner().introspection_state()));
+            }
+            Err(e) => tracing::warn!("Failed to start HTTP endpoint on {}: {}", addr, e),
+        }
+    }
+
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
+    match protocol_trace {
+        Some(trace) => {
+            let stdin = TracedReader::new(stdin, trace.clone());
+            let stdout = TracedWriter::new(stdout, trace);
+            Server::new(stdin, stdout, socket).serve(service).await;
+        }
+        None => Server::new(stdin, stdout, socket).serve(service).await,
+    }
+}
+
+/// Load the configuration and scan the package repositories, with a spinner.
+async fn scan_index(output: &Output) -> Result<PackageDiscoveryImpl, i32> {
+    let mut config_provider = RezConfigProvider::new();
+    if let Err(e) = config_provider.load_from_environment().await {
+        return Err(output.error(&format!("Failed to load configuration: {}", e)));
+    }
+
+    let progress = output.progress("Scanning package repositories", None);
+    let mut discovery = PackageDiscoveryImpl::new(config_provider.config().clone());
+    let scanned = discovery.scan_packages().await;
+    progress.finish();
+    match scanned {
+        Ok(()) => Ok(discovery),
+        Err(e) => Err(output.error(&format!("Failed to scan packages: {}", e))),
+    }
+}
+
+/// Audit the contexts and lock files in `dir` against the package index and
+/// print the report.
+///
+/// Returns the exit code: 0 when every environment is current, 1 on failure
+/// and 2 when stale environments were found.
+async fn audit_stale_environments(dir: PathBuf, output: &Output) -> i32 {
+    let mut discovery = match scan_index(output).await {
+        Ok(discovery) => discovery,
+        Err(code) => return code,
+    };
+
+    let report = discovery.audit_stale_environments(&dir).await;
+    if output.is_json() {
+        if let Err(code) = output.print_json(&report) {
+            return code;
+        }
+    } else {
+        let rows: Vec<Vec<String>> = report
+            .stale
+            .iter()
+            .flat_map(|environment| {
+                environment.packages.iter().map(|package| {
+                    vec![
+                        environment.path.display().to_string(),
+                        format!("{}-{}", package.name, package.version),
+                        package.reason.to_string(),
+                        package.latest.clone().unwrap_or_default(),
+                    ]
+                })
+            })
+            .collect();
+        output.table(&["ENVIRONMENT", "PACKAGE", "REASON", "LATEST"], &rows);
+        for error in &report.errors {
+            output.finding(
+                Style::Error,
+                &format!("{}: {}", error.path.display(), error.message),
+            );
+        }
+        let summary = format!(
+            "{} of {} environments are stale",
+            report.stale.len(),
+            report.audited
+        );
+        let style = if report.has_stale() {
+            Style::Warning
+        } else {
+            Style::Success
+        };
+        output.status(style, &summary);
+    }
+
+    if report.has_stale() {
+        EXIT_FINDINGS
+    } else {
+        EXIT_OK
+    }
+}
+
+/// Run the `audit` subcommand: report requirements of the packages in a
+/// repository that lag behind the latest indexed versions.
+///
+/// Returns the exit code: 0 when every requirement is fresh, 1 on failure
+/// and 2 when outdated requirements were found.
+async fn audit_freshness(args: &[String], output: &Output) -> i32 {
+    let mut repository = None;
+    let mut format = None;
+    let mut threshold = 0;
+    let mut index = 0;
+    while index < args.len() {
+        let value = args.get(index + 1);
+        match (args[index].as_str(), value) {
+            ("--repo", Some(path)) => repository = Some(PathBuf::from(path)),
+            ("--format", Some(name)) if name.trim().eq_ignore_ascii_case("table") => format = None,
+            ("--format", Some(name)) => match ReportFormat::parse(name) {
+                Some(parsed) => format = Some(parsed),
+                None => {
+                    return output.error(&format!(
+                        "Invalid value for --format: {} (expected table, json or csv)",
+                        name
+                    ));
+                }
+            },
+            ("--threshold", Some(count)) => match count.parse() {
+                Ok(count) => threshold = count,
+                Err(_) => {
+                    return output.error(&format!("Invalid value for --threshold: {}", count));
+                }
+            },
+            (option, _) => {
+                print_help();
+                return output.error(&format!("Unknown or incomplete audit option: {}", option));
+            }
+        }
+        index += 2;
+    }
+    let Some(repository) = repository.filter(|path| path.is_dir()) else {
+        return output.error("audit needs --repo <DIR> naming an existing package repository");
+    };
+
+    let discovery = match scan_index(output).await {
+        Ok(discovery) => discovery,
+        Err(code) => return code,
+    };
+
+    let progress = output.progress("Auditing requirements", None);
+    let report = discovery.audit_freshness(&repository, threshold).await;
+    progress.finish();
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            return output.error(&format!("Failed to audit {}: {}", repository.display(), e));
+        }
+    };
+    match format {
+        Some(ReportFormat::Csv) => print!("{}", report.to_csv()),
+        Some(ReportFormat::Json) => {
+            if let Err(code) = output.print_json(&report) {
+                return code;
+            }
+        }
+        None if output.is_json() => {
+            if let Err(code) = output.print_json(&report) {
+                return code;
+            }
+        }
+        None => {
+            let rows: Vec<Vec<String>> = report
+                .outdated
+                .iter()
+                .map(|outdated| {
+                    vec![
+                        format!("{}-{}", outdated.package, outdated.version),
+                        outdated.requirement.clone(),
+                        outdated.resolved.clone(),
+                        outdated.latest.clone(),
+                        outdated.versions_behind.to_string(),
+                    ]
+                })
+                .collect();
+            output.table(
+                &["PACKAGE", "REQUIREMENT", "RESOLVED", "LATEST", "BEHIND"],
+                &rows,
+            );
+            for error in &report.errors {
+                output.finding(
+                    Style::Error,
+                    &format!("{}: {}", error.package, error.message),
+                );
+            }
+            let summary = format!(
+                "{} outdated requirements in {} packages",
+                report.outdated.len(),
+                report.audited
+            );
+            let style = if report.has_outdated() {
+                Style::Warning
+            } else {
+                Style::Success
+            };
+            output.status(style, &summary);
+        }
+    }
 
-    let (service, socket) = LspService::new(RezLanguageServer::new);
-    Server::new(stdin, stdout, socket).serve(service).await;
+    if report.has_outdated() {
+        EXIT_FINDINGS
+    } else {
+        EXIT_OK
+    }
+}
+
+/// Run the `manifest` subcommand: print the dependency manifest of a package
+/// definition, describing its resolved dependency closure.
+///
+/// The manifest is always printed as JSON. Returns the exit code: 0 when done
+/// and 1 on failure.
+async fn dependency_manifest(args: &[String], output: &Output) -> i32 {
+    let mut path = None;
+    let mut format = ManifestFormat::default();
+    let mut index = 0;
+    while index < args.len() {
+        match (args[index].as_str(), args.get(index + 1)) {
+            ("--format", Some(name)) => match ManifestFormat::parse(name) {
+                Some(parsed) => format = parsed,
+                None => {
+                    return output.error(&format!(
+                        "Invalid value for --format: {} (expected spdx or cyclonedx)",
+                        name
+                    ));
+                }
+            },
+            (option, _) if option.starts_with("--") => {
+                print_help();
+                return output.error(&format!(
+                    "Unknown or incomplete manifest option: {}",
+                    option
+                ));
+            }
+            (file, _) if path.is_none() => {
+                path = Some(PathBuf::from(file));
+                index += 1;
+                continue;
+            }
+            (extra, _) => {
+                return output.error(&format!("Unexpected manifest argument: {}", extra));
+            }
+        }
+        index += 2;
+    }
+    let Some(path) = path else {
+        return output.error("manifest needs the path of a package.py");
+    };
+    let content = match read_source(&path) {
+        Ok(content) => content,
+        Err(e) => return output.error(&format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    let discovery = match scan_index(output).await {
+        Ok(discovery) => discovery,
+        Err(code) => return code,
+    };
+
+    let requirements = discovery.parse_requires(&content);
+    let resolver = create_resolver(discovery.config(), discovery.packages().clone());
+    let context = match resolver.resolve(&requirements).await {
+        Ok(context) => context,
+        Err(e) => return output.error(&format!("Failed to resolve requirements: {}", e)),
+    };
+    let attribute = |name: &str| string_attribute(&content, name).unwrap_or_default();
+    let manifest = DependencyManifest::new(
+        &attribute("name"),
+        &attribute("version"),
+        &requirements,
+        &context,
+    );
+    match output.print_json(&manifest.render(format)) {
+        Ok(()) => EXIT_OK,
+        Err(code) => code,
+    }
+}
+
+/// A package definition read by the `fix` and `validate` subcommands.
+struct Definition {
+    /// Path of the file, relative to the repository root when staged
+    path: PathBuf,
+    /// Content of the file, or of its staged blob
+    content: String,
+    /// Encoding the content was read in
+    encoding: SourceEncoding,
+    /// Repository root and index entry of a staged definition
+    staged: Option<(PathBuf, hook::StagedFile)>,
+}
+
+impl Definition {
+    /// Write `content` back where the definition was read from.
+    ///
+    /// Staged definitions are fixed in the index; the working tree file is
+    /// only updated too when it has no unstaged changes, which would
+    /// otherwise be lost or staged.
+    fn write(&self, content: &str) -> Result<(), String> {
+        let Some((root, file)) = &self.staged else {
+            return write_source(&self.path, content, self.encoding).map_err(|e| e.to_string());
+        };
+        let bytes = encode(content, self.encoding)
+            .ok_or_else(|| "the text cannot be encoded as Latin-1".to_string())?;
+        let working_tree = root.join(&file.path);
+        let unchanged = std::fs::read(&working_tree)
+            .is_ok_and(|bytes| encode(&self.content, self.encoding).as_ref() == Some(&bytes));
+        hook::stage_content(root, file, &bytes)?;
+        if unchanged {
+            std::fs::write(&working_tree, &bytes).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the package definitions at `paths`, or the staged ones with
+/// `staged`.
+///
+/// Directories are searched for package.py files. Returns the exit code of
+/// the failure if a definition cannot be read.
+async fn read_definitions(
+    paths: &[&String],
+    staged: bool,
+    output: &Output,
+) -> Result<Vec<Definition>, i32> {
+    if staged {
+        let root = hook::repository_root(Path::new(".")).map_err(|e| output.error(&e))?;
+        let files = hook::staged_package_files(&root).map_err(|e| output.error(&e))?;
+        return files
+            .into_iter()
+            .map(|file| {
+                let bytes = hook::read_staged(&root, &file).map_err(|e| output.error(&e))?;
+                let (content, encoding) = decode(bytes);
+                Ok(Definition {
+                    path: file.path.clone(),
+                    content,
+                    encoding,
+                    staged: Some((root.clone(), file)),
+                })
+            })
+            .collect();
+    }
+
+    let mut config_provider = RezConfigProvider::new();
+    if let Err(e) = config_provider.load_from_environment().await {
+        return Err(output.error(&format!("Failed to load configuration: {}", e)));
+    }
+    let walker = WorkspaceWalker::from_config(config_provider.config());
+    let is_definition = |path: &Path| path.file_name().is_some_and(|name| name == "package.py");
+
+    let mut files = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if path.is_dir() {
+            files.extend(walker.files(&path, is_definition).files);
+        } else if path.is_file() {
+            files.push(path);
+        } else {
+            return Err(output.error(&format!("No such file or directory: {}", path.display())));
+        }
+    }
+    files
+        .into_iter()
+        .map(|path| match read_source_with_encoding(&path) {
+            Ok((content, encoding)) => Ok(Definition {
+                path,
+                content,
+                encoding,
+                staged: None,
+            }),
+            Err(e) => Err(output.error(&format!("Failed to read {}: {}", path.display(), e))),
+        })
+        .collect()
+}
+
+/// Split the arguments of the `fix` and `validate` subcommands into the
+/// paths and whether `--staged` is set, rejecting options other than
+/// `flags`.
+fn definition_args<'a>(
+    subcommand: &str,
+    args: &'a [String],
+    flags: &[&str],
+    output: &Output,
+) -> Result<(Vec<&'a String>, bool), i32> {
+    let staged = args.iter().any(|arg| arg == "--staged");
+    let paths: Vec<&String> = args
+        .iter()
+        .filter(|arg| *arg != "--staged" && !flags.contains(&arg.as_str()))
+        .collect();
+    if let Some(option) = paths.iter().find(|arg| arg.starts_with("--")) {
+        print_help();
+        return Err(output.error(&format!("Unknown {} option: {}", subcommand, option)));
+    }
+    if paths.is_empty() && !staged {
+        return Err(output.error(&format!(
+            "{} needs at least one package.py file or directory, or --staged",
+            subcommand
+        )));
+    }
+    Ok((paths, staged))
+}
+
+/// Run the `fix` subcommand: apply every auto-fixable issue of the package
+/// definitions at the given paths in place.
+///
+/// Directories are searched for package.py files; with `--staged` the
+/// package.py files staged in the git repository are fixed in the index
+/// instead. With `--check` nothing is written. Returns the exit code: 0
+/// when done (or nothing needs fixing with `--check`), 1 on failure and 2
+/// when `--check` found issues to fix.
+async fn fix_packages(args: &[String], output: &Output) -> i32 {
+    let check = args.iter().any(|arg| arg == "--check");
+    let (paths, staged) = match definition_args("fix", args, &["--check"], output) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+    let definitions = match read_definitions(&paths, staged, output).await {
+        Ok(definitions) => definitions,
+        Err(code) => return code,
+    };
+
+    let mut status = EXIT_OK;
+    let mut fixed = Vec::new();
+    let progress = output.progress("Fixing package definitions", Some(definitions.len()));
+    for definition in definitions {
+        progress.inc();
+        let fixes = find_fixes(&definition.content);
+        if fixes.is_empty() {
+            continue;
+        }
+        if check {
+            status = EXIT_FINDINGS;
+        } else if let Err(e) = definition.write(&apply_fixes(&definition.content, &fixes)) {
+            progress.finish();
+            return output.error(&format!(
+                "Failed to write {}: {}",
+                definition.path.display(),
+                e
+            ));
+        }
+        fixed.push((definition.path, fixes.len()));
+    }
+    progress.finish();
+
+    if output.is_json() {
+        let files: Vec<serde_json::Value> = fixed
+            .iter()
+            .map(|(file, issues)| serde_json::json!({ "file": file, "issues": issues }))
+            .collect();
+        let report = serde_json::json!({ "check": check, "files": files });
+        if let Err(code) = output.print_json(&report) {
+            return code;
+        }
+    } else {
+        for (file, issues) in &fixed {
+            let (style, verb) = if check {
+                (Style::Warning, "fixable")
+            } else {
+                (Style::Success, "fixed")
+            };
+            output.finding(
+                style,
+                &format!("{}: {} {} issue(s)", file.display(), verb, issues),
+            );
+        }
+    }
+    status
+}
+
+/// Run the `validate` subcommand: report the validation issues of the
+/// package definitions at the given paths.
+///
+/// Directories are searched for package.py files; with `--staged` the
+/// staged content of the package.py files of the git repository is
+/// validated. Issues silenced by `# rez-lsp: disable=` directives are not
+/// reported. Returns the exit code: 0 when no definition has errors, 1 on
+/// failure and 2 when errors were found.
+async fn validate_packages(args: &[String], output: &Output) -> i32 {
+    let (paths, staged) = match definition_args("validate", args, &[], output) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+    let definitions = match read_definitions(&paths, staged, output).await {
+        Ok(definitions) => definitions,
+        Err(code) => return code,
+    };
+    let engine = match ValidationEngine::new() {
+        Ok(engine) => engine,
+        Err(e) => return output.error(&format!("Failed to create validator: {}", e)),
+    };
+
+    let mut status = EXIT_OK;
+    let mut reports = Vec::new();
+    for definition in &definitions {
+        let path = definition.path.to_string_lossy();
+        let mut result = match engine.validate_file(&definition.content, &path) {
+            Ok(result) => result,
+            Err(e) => return output.error(&format!("Failed to validate {}: {}", path, e)),
+        };
+        let suppressions = Suppressions::parse(&definition.content);
+        result
+            .issues
+            .retain(|issue| !suppressions.is_suppressed(&issue.code, issue.line));
+        if result
+            .issues
+            .iter()
+            .any(|issue| issue.severity >= ValidationSeverity::Error)
+        {
+            status = EXIT_FINDINGS;
+        }
+        if !result.issues.is_empty() {
+            reports.push((&definition.path, result.issues));
+        }
+    }
+
+    if output.is_json() {
+        let files: Vec<serde_json::Value> = reports
+            .iter()
+            .map(|(file, issues)| serde_json::json!({ "file": file, "issues": issues }))
+            .collect();
+        if let Err(code) = output.print_json(&serde_json::json!({ "files": files })) {
+            return code;
+        }
+    } else {
+        for (file, issues) in &reports {
+            for issue in issues {
+                let style = if issue.severity >= ValidationSeverity::Error {
+                    Style::Error
+                } else {
+                    Style::Warning
+                };
+                output.finding(
+                    style,
+                    &format!(
+                        "{}:{}:{}: {} {}",
+                        file.display(),
+                        issue.line,
+                        issue.column,
+                        issue.code,
+                        issue.message
+                    ),
+                );
+            }
+        }
+    }
+    status
+}
+
+/// Run the `install-hook` subcommand: validate the staged package.py files
+/// of a git repository at commit time with the `fix` and `validate`
+/// subcommands.
+///
+/// Writes a git pre-commit hook, or with `--pre-commit-config` a pre-commit
+/// framework configuration. With `--fix` the hook fixes the staged content
+/// instead of rejecting commits with fixable issues. Returns the exit code: 0 when installed
+/// and 1 on failure.
+fn install_hook(args: &[String], output: &Output) -> i32 {
+    let mut fix = false;
+    let mut force = false;
+    let mut framework = false;
+    let mut repo = PathBuf::from(".");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fix" => fix = true,
+            "--force" => force = true,
+            "--pre-commit-config" => framework = true,
+            "--repo" => match args.next() {
+                Some(dir) => repo = PathBuf::from(dir),
+                None => return output.error("--repo requires a directory argument"),
+            },
+            other => {
+                print_help();
+                return output.error(&format!("Unknown install-hook option: {}", other));
+            }
+        }
+    }
+
+    let installed = if framework {
+        hook::install_pre_commit_config(&repo, fix)
+    } else {
+        hook::hooks_dir(&repo)
+            .and_then(|dir| hook::install_pre_commit(&dir, &hook::pre_commit_script(fix), force))
+    };
+    let (path, written) = match installed {
+        Ok(hook::Installed::Written(path)) => (path, true),
+        Ok(hook::Installed::Unchanged(path)) => (path, false),
+        Err(e) => return output.error(&e),
+    };
+    if output.is_json() {
+        let report = serde_json::json!({ "file": path, "written": written });
+        if let Err(code) = output.print_json(&report) {
+            return code;
+        }
+    } else if written {
+        output.status(Style::Success, &format!("Installed {}", path.display()));
+    } else {
+        output.status(Style::Success, &format!("{} is up to date", path.display()));
+    }
+    EXIT_OK
 }
 
 fn print_help() {
@@ -70,11 +778,83 @@ fn print_help() {
     println!();
     println!("USAGE:");
     println!("    rez-lsp-server [OPTIONS]");
+    println!("    rez-lsp-server audit --repo <DIR> [--format json|csv] [--threshold <N>]");
+    println!("    rez-lsp-server manifest <PACKAGE.PY> [--format spdx|cyclonedx]");
+    println!("    rez-lsp-server fix [--check] [--staged] <PATH>...");
+    println!("    rez-lsp-server validate [--staged] <PATH>...");
+    println!(
+        "    rez-lsp-server install-hook [--fix] [--force] [--pre-commit-config] [--repo <DIR>]"
+    );
     println!();
     println!("OPTIONS:");
     println!("    -h, --help       Print this help message and exit");
     println!("    -V, --version    Print version information and exit");
     println!("        --stdio      Start LSP server (default mode)");
+    println!("        --json       Print subcommand reports as JSON");
+    println!("        --text       Print subcommand reports as text, even with --json");
+    println!("    -q, --quiet      Hide progress and informational subcommand output");
+    println!("        --no-color   Print subcommand output without colors");
+    println!("        --profile-out <FILE>");
+    println!(
+        "                     Profile the server and write a Chrome trace to FILE on shutdown"
+    );
+    println!("        --stdio-trace <FILE>");
+    println!(
+        "                     Append the JSON-RPC traffic, with document contents redacted, to FILE"
+    );
+    println!("        --parent-pid <PID>");
+    println!("                     Exit when process PID is gone, unless initialize names another");
+    println!("        --stale-environments <DIR>");
+    println!(
+        "                     Report packages of the .rxt and .lock files in DIR that are missing"
+    );
+    println!("                     or superseded, then exit (status 2 when any are stale)");
+    #[cfg(feature = "http")]
+    {
+        println!("        --http-addr <ADDR>");
+        println!(
+            "                     Serve /health, /metrics, /packages and /resolve over HTTP on ADDR"
+        );
+    }
+    println!();
+    println!("AUDIT:");
+    println!("        --repo <DIR>       Repository whose packages' requirements are audited");
+    println!("        --format <FORMAT>  Report format, table (default), json or csv");
+    println!("        --threshold <N>    Newer versions a requirement may lag behind (default 0)");
+    println!("                           Exits with status 2 when requirements are outdated");
+    println!("                           JSON reports include requirement graph metrics");
+    println!();
+    println!("MANIFEST:");
+    println!("        <PACKAGE.PY>       Package definition whose requirements are resolved");
+    println!("        --format <FORMAT>  Manifest format, spdx (default) or cyclonedx");
+    println!(
+        "                           Lists the resolved closure with versions and repositories"
+    );
+    println!();
+    println!("FIX:");
+    println!("        <PATH>...          package.py files, or directories searched for them,");
+    println!("                           whose auto-fixable issues are fixed in place");
+    println!("        --check            Only list the files to fix; exits with status 2 if any");
+    println!("        --staged           Fix the staged package.py files of the git repository");
+    println!("                           in the index, keeping their unstaged changes");
+    println!();
+    println!("VALIDATE:");
+    println!("        <PATH>...          package.py files, or directories searched for them,");
+    println!("                           to validate; exits with status 2 on errors");
+    println!(
+        "        --staged           Validate the staged package.py files of the git repository"
+    );
+    println!();
+    println!("INSTALL-HOOK:");
+    println!(
+        "        --repo <DIR>       Git repository to install into (default: current directory)"
+    );
+    println!("        --fix              Fix the staged package.py files instead of rejecting");
+    println!("                           commits with fixable issues");
+    println!("                           Hooks run rez-lsp-server from PATH, or REZ_LSP_SERVER");
+    println!("        --force            Replace an existing pre-commit hook");
+    println!("        --pre-commit-config");
+    println!("                           Declare the hook in .pre-commit-config.yaml instead");
     println!();
     println!("DESCRIPTION:");
     println!("    When run without arguments, starts the LSP server and communicates");
@@ -0,0 +1,482 @@
+//! Parsing of the package `commands` attribute.
+//!
+//! Rez accepts `commands` either as a function using the rex API or, in older
+//! packages, as a string of shell-like statements such as
+//! `export PATH=$PATH:{root}/bin`. Both forms are normalized into the same
+//! list of environment operations.
+
+use regex::Regex;
+
+/// How the `commands` attribute is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandsForm {
+    /// `def commands():` using the rex API
+    Function,
+    /// `commands = "..."` with shell-like statements
+    String,
+}
+
+/// Kind of change applied to the environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvAction {
+    /// Set a variable, replacing its value
+    Set,
+    /// Append to a path-like variable
+    Append,
+    /// Prepend to a path-like variable
+    Prepend,
+    /// Remove a variable
+    Unset,
+    /// Define a shell alias
+    Alias,
+}
+
+impl EnvAction {
+    /// Get the rex function name for this action.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnvAction::Set => "setenv",
+            EnvAction::Append => "appendenv",
+            EnvAction::Prepend => "prependenv",
+            EnvAction::Unset => "unsetenv",
+            EnvAction::Alias => "alias",
+        }
+    }
+}
+
+/// A single environment change made by `commands`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvOperation {
+    /// Kind of change
+    pub action: EnvAction,
+    /// Variable (or alias) name
+    pub variable: String,
+    /// Value assigned, appended or prepended
+    pub value: Option<String>,
+    /// Line of the statement (0-based)
+    pub line: u32,
+}
+
+/// A statement of string-form commands that could not be interpreted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnrecognizedStatement {
+    /// The statement text
+    pub text: String,
+    /// Line of the statement (0-based)
+    pub line: u32,
+}
+
+/// The parsed `commands` attribute of a package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandsBlock {
+    /// How the attribute is written
+    pub form: CommandsForm,
+    /// First line of the attribute (0-based)
+    pub line: u32,
+    /// Last line of the attribute (0-based)
+    pub end_line: u32,
+    /// Environment changes in statement order
+    pub operations: Vec<EnvOperation>,
+    /// Statements of string-form commands that were not understood
+    pub unrecognized: Vec<UnrecognizedStatement>,
+}
+
+/// Find and parse the `commands` attribute in package.py content.
+pub fn find_commands(content: &str) -> Option<CommandsBlock> {
+    let lines: Vec<&str> = content.lines().collect();
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.len() != line.len() {
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("def commands") {
+            if rest.trim_start().starts_with('(') {
+                return Some(parse_function_commands(&lines, index));
+            }
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("commands") {
+            if let Some(value) = rest.trim_start().strip_prefix('=') {
+                if !value.trim_start().starts_with('=') {
+                    return parse_string_commands(&lines, index, value.trim_start());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Check whether a name is a valid environment variable name.
+pub fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Find malformed `$VAR` / `${VAR}` references in a value.
+///
+/// Returns the offending reference text for each problem found.
+pub fn malformed_env_references(value: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut rest = value;
+
+    while let Some(pos) = rest.find('$') {
+        let after = &rest[pos + 1..];
+        if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => {
+                    let name = &braced[..end];
+                    if !is_valid_env_name(name) {
+                        problems.push(format!("${{{}}}", name));
+                    }
+                    rest = &braced[end + 1..];
+                }
+                None => {
+                    problems.push(format!("${{{}", braced));
+                    break;
+                }
+            }
+        } else {
+            let name_len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            let name = &after[..name_len];
+            if !name.is_empty() && !is_valid_env_name(name) {
+                problems.push(format!("${}", name));
+            }
+            rest = &after[name_len..];
+        }
+    }
+
+    problems
+}
+
+/// Find the packages `commands` refers to through the variables rez defines
+/// for every resolved package, such as `{env.REZ_OCIO_ROOT}`.
+///
+/// Returns the upper-case package part of each variable (`OCIO`) with the
+/// line it appears on.
+pub fn rez_package_variables(content: &str, block: &CommandsBlock) -> Vec<(String, u32)> {
+    let variable_regex = Regex::new(
+        r"REZ_([A-Z0-9_]+?)_(ROOT|BASE|VERSION|MAJOR_VERSION|MINOR_VERSION|PATCH_VERSION)\b",
+    )
+    .unwrap();
+
+    content
+        .lines()
+        .enumerate()
+        .skip(block.line as usize)
+        .take((block.end_line - block.line + 1) as usize)
+        .flat_map(|(index, line)| {
+            variable_regex
+                .captures_iter(line)
+                .map(move |captures| (captures[1].to_string(), index as u32))
+        })
+        .collect()
+}
+
+/// Find the programs invoked by the aliases `commands` defines.
+///
+/// Returns the program of each alias with the line of the alias.
+pub fn alias_programs(block: &CommandsBlock) -> Vec<(String, u32)> {
+    block
+        .operations
+        .iter()
+        .filter(|operation| operation.action == EnvAction::Alias)
+        .filter_map(|operation| {
+            let program = operation.value.as_deref()?.split_whitespace().next()?;
+            let program = program.rsplit(['/', '\\']).next().unwrap_or(program);
+            Some((program.to_string(), operation.line))
+        })
+        .collect()
+}
+
+/// Parse a `def commands():` function body.
+fn parse_function_commands(lines: &[&str], start: usize) -> CommandsBlock {
+    let env_regex = Regex::new(r#"^env\.(\w+)\.(append|prepend|set)\((.*)\)\s*$"#).unwrap();
+    let env_assign_regex = Regex::new(r#"^env\.(\w+)\s*=\s*(.+)$"#).unwrap();
+    let call_regex = Regex::new(
+        r#"^(setenv|appendenv|prependenv|unsetenv|alias)\(\s*["']([^"']*)["']\s*(?:,\s*(.*))?\)\s*$"#,
+    )
+    .unwrap();
+
+    let mut operations = Vec::new();
+    let mut end_line = start;
+
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        if !line.trim().is_empty() && !line.starts_with([' ', '\t']) {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        end_line = index;
+
+        let statement = line.trim();
+        let line = index as u32;
+        if let Some(captures) = env_regex.captures(statement) {
+            let action = match &captures[2] {
+                "append" => EnvAction::Append,
+                "prepend" => EnvAction::Prepend,
+                _ => EnvAction::Set,
+            };
+            operations.push(EnvOperation {
+                action,
+                variable: captures[1].to_string(),
+                value: Some(clean_value(&captures[3])),
+                line,
+            });
+        } else if let Some(captures) = env_assign_regex.captures(statement) {
+            operations.push(EnvOperation {
+                action: EnvAction::Set,
+                variable: captures[1].to_string(),
+                value: Some(clean_value(&captures[2])),
+                line,
+            });
+        } else if let Some(captures) = call_regex.captures(statement) {
+            let action = match &captures[1] {
+                "setenv" => EnvAction::Set,
+                "appendenv" => EnvAction::Append,
+                "prependenv" => EnvAction::Prepend,
+                "unsetenv" => EnvAction::Unset,
+                _ => EnvAction::Alias,
+            };
+            operations.push(EnvOperation {
+                action,
+                variable: captures[2].to_string(),
+                value: captures.get(3).map(|value| clean_value(value.as_str())),
+                line,
+            });
+        }
+    }
+
+    CommandsBlock {
+        form: CommandsForm::Function,
+        line: start as u32,
+        end_line: end_line as u32,
+        operations,
+        unrecognized: Vec::new(),
+    }
+}
+
+/// Parse a `commands = "..."` string assignment.
+fn parse_string_commands(lines: &[&str], start: usize, value: &str) -> Option<CommandsBlock> {
+    let quote = ["\"\"\"", "'''", "\"", "'"]
+        .into_iter()
+        .find(|quote| value.starts_with(quote))?;
+    let opened = &value[quote.len()..];
+
+    // Collect (line, text) pairs of the string body
+    let mut body = Vec::new();
+    let mut end_line = start;
+    if let Some(end) = opened.find(quote) {
+        body.push((start, &opened[..end]));
+    } else if quote.len() == 3 {
+        body.push((start, opened));
+        for (index, line) in lines.iter().enumerate().skip(start + 1) {
+            end_line = index;
+            if let Some(end) = line.find(quote) {
+                body.push((index, &line[..end]));
+                break;
+            }
+            body.push((index, line));
+        }
+    } else {
+        return None;
+    }
+
+    let mut operations = Vec::new();
+    let mut unrecognized = Vec::new();
+    for (index, text) in body {
+        // Single-line strings may separate statements with `\n` escapes
+        for statement in text.split("\\n").flat_map(|s| s.split(';')) {
+            let statement = statement.trim();
+            if statement.is_empty() || statement.starts_with('#') {
+                continue;
+            }
+            match parse_shell_statement(statement, index as u32) {
+                Some(operation) => operations.push(operation),
+                None => unrecognized.push(UnrecognizedStatement {
+                    text: statement.to_string(),
+                    line: index as u32,
+                }),
+            }
+        }
+    }
+
+    Some(CommandsBlock {
+        form: CommandsForm::String,
+        line: start as u32,
+        end_line: end_line as u32,
+        operations,
+        unrecognized,
+    })
+}
+
+/// Parse a single shell-like statement of string-form commands.
+fn parse_shell_statement(statement: &str, line: u32) -> Option<EnvOperation> {
+    let (keyword, rest) = statement.split_once(char::is_whitespace)?;
+    let rest = rest.trim();
+
+    let (action, variable, value) = match keyword {
+        "export" => {
+            let (variable, value) = rest.split_once('=')?;
+            let variable = variable.trim();
+            let value = clean_value(value);
+            let own_reference = [format!("${}", variable), format!("${{{}}}", variable)];
+
+            let mut operation = (EnvAction::Set, variable, Some(value.clone()));
+            for reference in &own_reference {
+                let separators = [':', ';'];
+                if let Some(appended) = value.strip_prefix(reference.as_str()) {
+                    if let Some(appended) = appended.strip_prefix(separators) {
+                        operation = (EnvAction::Append, variable, Some(appended.to_string()));
+                    }
+                } else if let Some(prepended) = value.strip_suffix(reference.as_str()) {
+                    if let Some(prepended) = prepended.strip_suffix(separators) {
+                        operation = (EnvAction::Prepend, variable, Some(prepended.to_string()));
+                    }
+                }
+            }
+            operation
+        }
+        "setenv" | "appendenv" | "prependenv" | "alias" => {
+            let (variable, value) = rest.split_once(char::is_whitespace)?;
+            let action = match keyword {
+                "setenv" => EnvAction::Set,
+                "appendenv" => EnvAction::Append,
+                "prependenv" => EnvAction::Prepend,
+                _ => EnvAction::Alias,
+            };
+            (action, variable, Some(clean_value(value)))
+        }
+        "unsetenv" | "unset" => (EnvAction::Unset, rest, None),
+        _ => return None,
+    };
+
+    Some(EnvOperation {
+        action,
+        variable: variable.to_string(),
+        value,
+        line,
+    })
+}
+
+/// Strip surrounding whitespace and quotes from a value.
+fn clean_value(value: &str) -> String {
+    let value = value.trim();
+    let unquoted = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')));
+    unquoted.unwrap_or(value).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_commands() {
+        let content = r#"name = "tools"
+
+def commands():
+    env.PATH.append("{root}/bin")
+    env.TOOLS_ROOT = "{root}"
+    setenv("TOOLS_MODE", "prod")
+
+build_command = False
+"#;
+
+        let block = find_commands(content).unwrap();
+        assert_eq!(block.form, CommandsForm::Function);
+        assert_eq!(block.line, 2);
+        assert_eq!(block.end_line, 5);
+        assert_eq!(block.operations.len(), 3);
+        assert_eq!(block.operations[0].action, EnvAction::Append);
+        assert_eq!(block.operations[0].variable, "PATH");
+        assert_eq!(block.operations[0].value.as_deref(), Some("{root}/bin"));
+        assert_eq!(block.operations[1].action, EnvAction::Set);
+        assert_eq!(block.operations[2].variable, "TOOLS_MODE");
+    }
+
+    #[test]
+    fn test_string_commands() {
+        let content = r#"name = "legacy"
+commands = """
+export PATH=$PATH:{root}/bin
+export PYTHONPATH={root}/python:$PYTHONPATH
+setenv LEGACY_ROOT {root}
+echo hello
+"""
+"#;
+
+        let block = find_commands(content).unwrap();
+        assert_eq!(block.form, CommandsForm::String);
+        assert_eq!(block.line, 1);
+        assert_eq!(block.end_line, 6);
+        assert_eq!(block.operations.len(), 3);
+        assert_eq!(block.operations[0].action, EnvAction::Append);
+        assert_eq!(block.operations[0].value.as_deref(), Some("{root}/bin"));
+        assert_eq!(block.operations[1].action, EnvAction::Prepend);
+        assert_eq!(block.operations[1].value.as_deref(), Some("{root}/python"));
+        assert_eq!(block.operations[2].action, EnvAction::Set);
+        assert_eq!(block.unrecognized.len(), 1);
+        assert_eq!(block.unrecognized[0].line, 5);
+    }
+
+    #[test]
+    fn test_single_line_string_commands() {
+        let block = find_commands("commands = 'export FOO=bar\\nunsetenv BAZ'").unwrap();
+        assert_eq!(block.form, CommandsForm::String);
+        assert_eq!(block.end_line, 0);
+        assert_eq!(block.operations.len(), 2);
+        assert_eq!(block.operations[1].action, EnvAction::Unset);
+        assert_eq!(block.operations[1].variable, "BAZ");
+    }
+
+    #[test]
+    fn test_no_commands() {
+        assert!(find_commands("name = \"tools\"\nversion = \"1.0\"").is_none());
+        assert!(find_commands("    commands = 'nested'").is_none());
+    }
+
+    #[test]
+    fn test_malformed_env_references() {
+        assert!(malformed_env_references("$PATH:${HOME}/bin").is_empty());
+        assert_eq!(malformed_env_references("${1BAD}"), vec!["${1BAD}"]);
+        assert_eq!(malformed_env_references("$1x/bin"), vec!["$1x"]);
+        assert_eq!(malformed_env_references("${PATH"), vec!["${PATH"]);
+    }
+
+    #[test]
+    fn test_commands_package_usage() {
+        let content = r#"name = "viewer"
+def commands():
+    env.OCIO = "{env.REZ_OCIO_CONFIGS_ROOT}/aces/config.ocio"
+    env.PATH.append("$REZ_MAYA_TOOLS_MAJOR_VERSION")
+    alias("check", "ociocheck --verbose")
+    alias("view", "{root}/bin/usdview")
+"#;
+        let block = find_commands(content).unwrap();
+
+        assert_eq!(
+            rez_package_variables(content, &block),
+            vec![
+                ("OCIO_CONFIGS".to_string(), 2),
+                ("MAYA_TOOLS".to_string(), 3)
+            ]
+        );
+        assert_eq!(
+            alias_programs(&block),
+            vec![("ociocheck".to_string(), 4), ("usdview".to_string(), 5)]
+        );
+    }
+}
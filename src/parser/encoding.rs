@@ -0,0 +1,137 @@
+//! Text encoding normalization.
+//!
+//! Package definitions written by old Windows tooling may start with a UTF-8
+//! byte order mark or be encoded as Latin-1. Editors hide the byte order mark
+//! and count positions in code units, while the parsers count characters.
+//! These helpers normalize text before parsing.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use tracing::warn;
+
+/// Byte order mark as decoded from UTF-8.
+pub const BOM: char = '\u{feff}';
+
+/// Character substituted by decoders for undecodable bytes.
+pub const REPLACEMENT_CHARACTER: char = '\u{fffd}';
+
+/// Encoding a source file was decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceEncoding {
+    /// UTF-8 without a byte order mark
+    Utf8,
+    /// UTF-8 with a byte order mark, which was removed
+    Utf8Bom,
+    /// Invalid UTF-8, decoded as Latin-1
+    Latin1,
+}
+
+/// Remove a leading byte order mark from an owned string.
+///
+/// Returns whether one was removed.
+pub fn strip_bom_in_place(text: &mut String) -> bool {
+    if text.starts_with(BOM) {
+        text.drain(..BOM.len_utf8());
+        true
+    } else {
+        false
+    }
+}
+
+/// Decode the bytes of a source file.
+///
+/// UTF-8 is tried first, with its byte order mark removed. Anything else is
+/// decoded as Latin-1, which maps every byte to a character and never fails.
+pub fn decode(bytes: Vec<u8>) -> (String, SourceEncoding) {
+    match String::from_utf8(bytes) {
+        Ok(mut text) => {
+            let encoding = if strip_bom_in_place(&mut text) {
+                SourceEncoding::Utf8Bom
+            } else {
+                SourceEncoding::Utf8
+            };
+            (text, encoding)
+        }
+        Err(e) => {
+            let text = e.into_bytes().into_iter().map(char::from).collect();
+            (text, SourceEncoding::Latin1)
+        }
+    }
+}
+
+/// Encode text back into the encoding it was decoded from.
+///
+/// Returns `None` when Latin-1 cannot represent a character of the text.
+pub fn encode(text: &str, encoding: SourceEncoding) -> Option<Vec<u8>> {
+    match encoding {
+        SourceEncoding::Utf8 => Some(text.as_bytes().to_vec()),
+        SourceEncoding::Utf8Bom => {
+            let mut bytes = BOM.to_string().into_bytes();
+            bytes.extend_from_slice(text.as_bytes());
+            Some(bytes)
+        }
+        SourceEncoding::Latin1 => text.chars().map(|c| u8::try_from(c).ok()).collect(),
+    }
+}
+
+/// Read a source file, tolerating a byte order mark and Latin-1 content.
+pub fn read_source(path: &Path) -> io::Result<String> {
+    read_source_with_encoding(path).map(|(text, _)| text)
+}
+
+/// Read a source file as [`read_source`] does, with the encoding it was
+/// decoded from, e.g. to write it back with [`write_source`].
+pub fn read_source_with_encoding(path: &Path) -> io::Result<(String, SourceEncoding)> {
+    let (text, encoding) = decode(fs::read(path)?);
+    if encoding == SourceEncoding::Latin1 {
+        warn!(
+            "{} is not valid UTF-8, decoded it as Latin-1",
+            path.display()
+        );
+    }
+    Ok((text, encoding))
+}
+
+/// Write a source file in the encoding it was read in.
+pub fn write_source(path: &Path, text: &str, encoding: SourceEncoding) -> io::Result<()> {
+    let bytes = encode(text, encoding).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the text cannot be encoded as Latin-1",
+        )
+    })?;
+    fs::write(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let (text, encoding) = decode(b"\xef\xbb\xbfname = 'tool'".to_vec());
+        assert_eq!(text, "name = 'tool'");
+        assert_eq!(encoding, SourceEncoding::Utf8Bom);
+
+        let (text, encoding) = decode(b"description = 'Caf\xe9'".to_vec());
+        assert_eq!(text, "description = 'Café'");
+        assert_eq!(encoding, SourceEncoding::Latin1);
+
+        let (_, encoding) = decode("name = '工具'".as_bytes().to_vec());
+        assert_eq!(encoding, SourceEncoding::Utf8);
+    }
+
+    #[test]
+    fn test_encode_round_trips() {
+        for bytes in [
+            b"\xef\xbb\xbfname = 'tool'".to_vec(),
+            b"description = 'Caf\xe9'".to_vec(),
+            "name = '工具'".as_bytes().to_vec(),
+        ] {
+            let (text, encoding) = decode(bytes.clone());
+            assert_eq!(encode(&text, encoding), Some(bytes));
+        }
+        assert_eq!(encode("name = '工具'", SourceEncoding::Latin1), None);
+    }
+}
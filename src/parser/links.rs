@@ -0,0 +1,326 @@
+//! Links to documentation, build scripts and files in package definitions.
+//!
+//! Three kinds of targets are recognized:
+//!
+//! ```python
+//! help = "https://docs.example.com/my_tool"
+//! build_command = "python {root}/build.py"
+//!
+//! def commands():
+//!     env.PATH.append("/opt/my_tool/bin")
+//! ```
+//!
+//! URLs are found anywhere, build scripts in the `build_command` string, and
+//! absolute paths in the string literals of `commands`.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use super::commands::find_commands;
+use super::SourceRange;
+
+/// Placeholders expanded by rez in `build_command`, with their documentation.
+pub const BUILD_COMMAND_PLACEHOLDERS: &[(&str, &str)] = &[
+    (
+        "root",
+        "Source directory of the package, containing package.py",
+    ),
+    (
+        "install",
+        "`install` if the package is being installed, empty otherwise",
+    ),
+    ("build_path", "Build directory of the current variant"),
+    (
+        "install_path",
+        "Installation directory of the current variant",
+    ),
+    ("name", "Name of the package"),
+    ("version", "Version of the package"),
+    (
+        "variant_index",
+        "Index of the variant being built, empty for packages without variants",
+    ),
+];
+
+/// Extensions of files treated as build scripts.
+pub const SCRIPT_EXTENSIONS: &[&str] = &["py", "sh", "bash", "bat", "cmd", "ps1"];
+
+/// What a link points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// A web page, e.g. the `help` documentation
+    Url,
+    /// A script run by `build_command`, relative to the package directory
+    BuildScript,
+    /// An absolute file system path used by `commands`
+    Path,
+}
+
+/// A link target in package.py content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkSpan {
+    /// What the link points to
+    pub kind: LinkKind,
+    /// Link text as written, e.g. `{root}/build.py`
+    pub target: String,
+    /// Range of the link text
+    pub range: SourceRange,
+}
+
+/// URLs written anywhere in a package definition.
+static URL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"https?://[^\s'"<>()\[\]]+"#).unwrap());
+
+/// Single or double quoted string literals.
+static STRING_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""([^"]*)"|'([^']*)'"#).unwrap());
+
+/// Find the links of package.py content in source order.
+pub fn find_links(content: &str) -> Vec<LinkSpan> {
+    let commands_lines = find_commands(content).map(|block| block.line..=block.end_line);
+
+    let mut links = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line_number = line_number as u32;
+        let span = |kind, start: usize, end: usize| LinkSpan {
+            kind,
+            target: line[start..end].to_string(),
+            range: SourceRange {
+                line: line_number,
+                start: line[..start].chars().count() as u32,
+                end: line[..end].chars().count() as u32,
+            },
+        };
+
+        for url in URL_REGEX.find_iter(line) {
+            let end = url.start() + url.as_str().trim_end_matches(['.', ',']).len();
+            links.push(span(LinkKind::Url, url.start(), end));
+        }
+
+        if let Some(value) = build_command_value(line) {
+            for string in STRING_REGEX.captures_iter(value) {
+                let literal = string.get(1).or_else(|| string.get(2)).unwrap();
+                let offset = line.len() - value.len() + literal.start();
+                for (start, word) in words(literal.as_str()) {
+                    if is_script(word) {
+                        let start = offset + start;
+                        links.push(span(LinkKind::BuildScript, start, start + word.len()));
+                    }
+                }
+            }
+        }
+
+        if commands_lines
+            .as_ref()
+            .is_some_and(|lines| lines.contains(&line_number))
+        {
+            for string in STRING_REGEX.captures_iter(line) {
+                let literal = string.get(1).or_else(|| string.get(2)).unwrap();
+                if let Some(len) = absolute_path_len(literal.as_str()) {
+                    links.push(span(LinkKind::Path, literal.start(), literal.start() + len));
+                }
+            }
+        }
+    }
+
+    links
+}
+
+/// A word being typed in a `build_command` string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildCommandWord {
+    /// Character column where the word starts
+    pub start: u32,
+    /// Text of the word up to the cursor
+    pub text: String,
+}
+
+/// Get the word before `character` if it is inside a `build_command` string.
+///
+/// The string may still be unterminated, as it is while the user types it.
+pub fn build_command_word_at(content: &str, line: u32, character: u32) -> Option<BuildCommandWord> {
+    let text = content.lines().nth(line as usize)?;
+    let cursor = text
+        .char_indices()
+        .nth(character as usize)
+        .map_or(text.len(), |(byte, _)| byte);
+    let value = build_command_value(text)?;
+    let value_start = text.len() - value.len();
+    if cursor < value_start {
+        return None;
+    }
+
+    let mut quote = None;
+    let mut literal_start = 0;
+    for (byte, c) in text[value_start..cursor].char_indices() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                literal_start = value_start + byte + 1;
+            }
+            _ => {}
+        }
+    }
+    quote?;
+
+    let literal = &text[literal_start..cursor];
+    let word_start = literal
+        .rfind(char::is_whitespace)
+        .map_or(literal_start, |space| literal_start + space + 1);
+    Some(BuildCommandWord {
+        start: text[..word_start].chars().count() as u32,
+        text: text[word_start..cursor].to_string(),
+    })
+}
+
+/// Get the path of a build script relative to the package directory.
+///
+/// `{root}` is the package directory. Absolute scripts and scripts under any
+/// other placeholder, e.g. `{install_path}/bin/setup.sh`, are not relative to
+/// the package and give `None`.
+pub fn package_relative_path(script: &str) -> Option<&str> {
+    let relative = match script.strip_prefix("{root}") {
+        Some(rest) => rest.trim_start_matches(['/', '\\']),
+        None => script,
+    };
+    let bytes = relative.as_bytes();
+    let absolute = relative.starts_with(['/', '\\']) || bytes.get(1) == Some(&b':');
+    (!absolute && !relative.contains('{')).then_some(relative)
+}
+
+/// Get the value of a top-level `build_command = ...` assignment line.
+fn build_command_value(line: &str) -> Option<&str> {
+    let value = line.strip_prefix("build_command")?.trim_start();
+    let value = value.strip_prefix('=')?;
+    (!value.starts_with('=')).then_some(value)
+}
+
+/// Split text into whitespace-separated words with their byte offsets.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_whitespace()
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+/// Check whether a `build_command` word names a script file.
+fn is_script(word: &str) -> bool {
+    let name = word.rsplit(['/', '\\']).next().unwrap_or(word);
+    match name.rsplit_once('.') {
+        Some((stem, extension)) => !stem.is_empty() && SCRIPT_EXTENSIONS.contains(&extension),
+        None => false,
+    }
+}
+
+/// Get the byte length of the absolute path starting a string, if any.
+///
+/// The path ends at whitespace or at the start of a variable reference or
+/// path list separator, e.g. `/opt/bin` in `/opt/bin:$PATH`.
+fn absolute_path_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let windows = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/');
+    if !windows && !text.starts_with('/') {
+        return None;
+    }
+
+    let separators: &[char] = if windows {
+        &[';', '$', '{']
+    } else {
+        &[':', ';', '$', '{']
+    };
+    let len = text
+        .find(|c: char| c.is_whitespace() || separators.contains(&c))
+        .unwrap_or(text.len());
+    // A lone `/` is not worth a link
+    (len > 1).then_some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_links() {
+        let content = r#"name = "my_tool"
+help = [["Docs", "https://docs.example.com/my_tool"]]
+build_command = "python {root}/build.py --install"
+
+def commands():
+    env.PATH.append("/opt/my_tool/bin:$PATH")
+    env.MY_TOOL_CONFIG = 'C:\studio\my_tool.ini'
+    env.MY_TOOL_ROOT = "{root}"
+
+description = "See https://example.com/faq."
+"#;
+        let links = find_links(content);
+        let found: Vec<(LinkKind, &str, u32, u32)> = links
+            .iter()
+            .map(|link| {
+                (
+                    link.kind,
+                    link.target.as_str(),
+                    link.range.line,
+                    link.range.start,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (LinkKind::Url, "https://docs.example.com/my_tool", 1, 18),
+                (LinkKind::BuildScript, "{root}/build.py", 2, 24),
+                (LinkKind::Path, "/opt/my_tool/bin", 5, 21),
+                (LinkKind::Path, "C:\\studio\\my_tool.ini", 6, 26),
+                (LinkKind::Url, "https://example.com/faq", 9, 19),
+            ]
+        );
+        assert_eq!(links[0].range.end, 50);
+    }
+
+    #[test]
+    fn test_build_scripts() {
+        assert!(is_script("build.sh"));
+        assert!(is_script("./scripts/build.py"));
+        assert!(!is_script("python"));
+        assert!(!is_script("--install"));
+        assert!(!is_script(".py"));
+        assert!(find_links("build_command = False\n").is_empty());
+    }
+
+    #[test]
+    fn test_build_command_word_at() {
+        let content = "build_command = \"python {root}/scr\nversion = \"1.0\"\n";
+        assert_eq!(
+            build_command_word_at(content, 0, 34),
+            Some(BuildCommandWord {
+                start: 24,
+                text: "{root}/scr".to_string(),
+            })
+        );
+        assert_eq!(
+            build_command_word_at(content, 0, 17).map(|word| word.text),
+            Some(String::new())
+        );
+        // Outside the string and outside build_command
+        assert_eq!(build_command_word_at(content, 0, 10), None);
+        assert_eq!(build_command_word_at(content, 1, 12), None);
+        assert_eq!(
+            build_command_word_at("build_command = 'a' + 'b c'\n", 0, 20),
+            None
+        );
+    }
+
+    #[test]
+    fn test_package_relative_path() {
+        assert_eq!(package_relative_path("{root}/build.py"), Some("build.py"));
+        assert_eq!(
+            package_relative_path("scripts/build.sh"),
+            Some("scripts/build.sh")
+        );
+        assert_eq!(package_relative_path("{install_path}/setup.sh"), None);
+        assert_eq!(package_relative_path("/opt/build.sh"), None);
+        assert_eq!(package_relative_path("C:\\build.bat"), None);
+    }
+}
@@ -0,0 +1,261 @@
+//! Tokenizing and parsing of the Python literals used in package definitions.
+//!
+//! Package attributes such as `tests` and `requires` are plain dictionary,
+//! list and string literals. The tokenizer tracks 0-based lines and columns
+//! so parsed values can be mapped back to their source.
+//!
+//! While a definition is being typed it is rarely valid Python. The tokenizer
+//! recovers at statement boundaries: a value whose brackets are still open
+//! ends before the next top-level statement, so a half-typed list does not
+//! swallow the attributes following it.
+
+/// Find the byte offset of the `open` bracket starting the value of a
+/// top-level `name = ...` assignment.
+pub(super) fn attribute_value_offset(content: &str, name: &str, open: char) -> Option<usize> {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if let Some(rest) = line.strip_prefix(name) {
+            let value = rest.trim_start();
+            if let Some(value) = value.strip_prefix('=') {
+                let value_trimmed = value.trim_start();
+                if value_trimmed.starts_with(open) {
+                    return Some(offset + line.len() - value_trimmed.len());
+                }
+            }
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Find the top-level statement containing a 0-based line.
+///
+/// Returns the first line of the statement with its source, up to the next
+/// top-level statement, so a position can be looked up without scanning the
+/// whole definition.
+pub fn statement_at(content: &str, line: u32) -> (u32, &str) {
+    let mut start = (0, 0);
+    let mut offset = 0;
+    for (index, text) in content.split_inclusive('\n').enumerate() {
+        let index = index as u32;
+        if starts_statement(text.trim_end_matches(['\r', '\n'])) {
+            if index > line {
+                return (start.0, &content[start.1..offset]);
+            }
+            start = (index, offset);
+        }
+        offset += text.len();
+    }
+    (start.0, &content[start.1..])
+}
+
+/// Keywords starting top-level statements of package definitions.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "class", "def", "for", "from", "if", "import", "try", "while", "with",
+];
+
+/// Check whether a line, starting at column 0, begins a new statement.
+///
+/// Decorators, compound statements, imports and assignments such as
+/// `variants = [` or `build_command += ...` start statements; closing
+/// brackets and list entries written at column 0 do not.
+fn starts_statement(line: &str) -> bool {
+    if line.starts_with('@') {
+        return true;
+    }
+    let word_len = line
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(line.len());
+    let (word, rest) = line.split_at(word_len);
+    if word.is_empty() || word.starts_with(|c: char| c.is_ascii_digit()) {
+        return false;
+    }
+    if STATEMENT_KEYWORDS.contains(&word) {
+        return rest.is_empty() || rest.starts_with([' ', '\t', ':', '(']);
+    }
+    let rest = rest.trim_start();
+    let rest = rest
+        .strip_prefix(['+', '-', '*', '/', '|', '&'])
+        .unwrap_or(rest);
+    rest.starts_with('=') && !rest.starts_with("==")
+}
+
+/// A Python literal of the subset used in package definitions.
+#[derive(Debug)]
+pub(super) enum Literal {
+    Str(String),
+    List(Vec<Literal>),
+    Dict(Vec<DictEntry>),
+    Other,
+}
+
+/// A string-keyed dictionary entry and the position of its key.
+#[derive(Debug)]
+pub(super) struct DictEntry {
+    pub(super) key: String,
+    pub(super) value: Literal,
+    pub(super) line: u32,
+    pub(super) column: u32,
+}
+
+/// Token of a Python literal.
+#[derive(Debug, PartialEq)]
+pub(super) enum Token {
+    Str(String),
+    Open(char),
+    Close(char),
+    Colon,
+    Comma,
+    Other,
+}
+
+/// Tokenizer over Python literal source tracking 0-based line and column.
+pub(super) struct Tokenizer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: u32,
+    column: u32,
+    /// Line the tokenized value starts on
+    start_line: u32,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub(super) fn new(content: &'a str, start: usize) -> Self {
+        let before = &content[..start];
+        let line = before.matches('\n').count() as u32;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() as u32;
+        Self {
+            chars: content[start..].chars().peekable(),
+            line,
+            column,
+            start_line: line,
+        }
+    }
+
+    /// Get the current 0-based line and column.
+    pub(super) fn position(&self) -> (u32, u32) {
+        (self.line, self.column)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Get the next token with its starting line and column.
+    ///
+    /// Returns `None` at the end of the content and at the start of the next
+    /// top-level statement.
+    pub(super) fn next_token(&mut self) -> Option<(Token, u32, u32)> {
+        loop {
+            match self.chars.peek()? {
+                c if c.is_whitespace() => {
+                    self.bump();
+                }
+                '#' => {
+                    while self.chars.peek().is_some_and(|c| *c != '\n') {
+                        self.bump();
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        if self.column == 0 && self.line > self.start_line {
+            let line: String = self.chars.clone().take_while(|c| *c != '\n').collect();
+            if starts_statement(&line) {
+                return None;
+            }
+        }
+
+        let (line, column) = (self.line, self.column);
+        let c = self.bump()?;
+        let token = match c {
+            '"' | '\'' => {
+                let mut value = String::new();
+                while let Some(next) = self.bump() {
+                    match next {
+                        '\\' => {
+                            if let Some(escaped) = self.bump() {
+                                value.push(escaped);
+                            }
+                        }
+                        '\n' => break,
+                        next if next == c => break,
+                        next => value.push(next),
+                    }
+                }
+                Token::Str(value)
+            }
+            '{' | '[' | '(' => Token::Open(c),
+            '}' | ']' | ')' => Token::Close(c),
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            _ => {
+                while self
+                    .chars
+                    .peek()
+                    .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '.'))
+                {
+                    self.bump();
+                }
+                Token::Other
+            }
+        };
+        Some((token, line, column))
+    }
+}
+
+/// Parse a literal, returning `None` on unbalanced or truncated input.
+pub(super) fn parse_literal(tokens: &mut Tokenizer) -> Option<Literal> {
+    let (token, _, _) = tokens.next_token()?;
+    match token {
+        Token::Str(value) => Some(Literal::Str(value)),
+        Token::Open('{') => parse_dict(tokens),
+        Token::Open(open) => {
+            let close = if open == '[' { ']' } else { ')' };
+            let mut items = Vec::new();
+            loop {
+                match tokens.next_token()? {
+                    (Token::Close(c), _, _) if c == close => return Some(Literal::List(items)),
+                    (Token::Comma, _, _) => {}
+                    (Token::Str(value), _, _) => items.push(Literal::Str(value)),
+                    (Token::Open('{'), _, _) => items.push(parse_dict(tokens)?),
+                    (Token::Open(_), _, _) | (Token::Close(_), _, _) => return None,
+                    _ => items.push(Literal::Other),
+                }
+            }
+        }
+        _ => Some(Literal::Other),
+    }
+}
+
+/// Parse the entries of a dictionary after its opening brace.
+fn parse_dict(tokens: &mut Tokenizer) -> Option<Literal> {
+    let mut entries = Vec::new();
+    loop {
+        let (token, line, column) = tokens.next_token()?;
+        let key = match token {
+            Token::Close('}') => return Some(Literal::Dict(entries)),
+            Token::Comma => continue,
+            Token::Str(key) => key,
+            _ => return None,
+        };
+
+        if tokens.next_token()?.0 != Token::Colon {
+            return None;
+        }
+        let value = parse_literal(tokens)?;
+        entries.push(DictEntry {
+            key,
+            value,
+            line,
+            column,
+        });
+    }
+}
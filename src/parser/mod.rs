@@ -2,9 +2,56 @@
 
 // TODO: Implement proper Python AST parsing for package.py files
 
+pub mod commands;
+pub mod encoding;
+pub mod links;
+mod literal;
+pub mod package_tests;
+pub mod requirements;
+
+pub use commands::{find_commands, CommandsBlock, CommandsForm, EnvAction, EnvOperation};
+pub use links::{
+    build_command_word_at, find_links, package_relative_path, BuildCommandWord, LinkKind, LinkSpan,
+    BUILD_COMMAND_PLACEHOLDERS,
+};
+pub use literal::statement_at;
+pub use package_tests::{find_tests, PackageTest};
+pub use requirements::{
+    find_requirements, requirement_at, requirement_insertion, requirement_list_at,
+    requirement_part_at, RequirementList,
+    RequirementPart, RequirementSpan, SourceRange, REQUIREMENT_ATTRIBUTES, VARIANTS_ATTRIBUTE,
+};
+
 use crate::core::{Package, Result};
 use std::path::Path;
 
+/// Get the value of a top-level string attribute of package.py content.
+pub fn string_attribute(content: &str, name: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix(name)?.trim_start().strip_prefix('=')?;
+        let value = value.trim();
+        let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let value = &value[1..];
+        Some(value[..value.find(quote)?].to_string())
+    })
+}
+
+/// Get the line and byte range of the quoted value of a top-level string
+/// attribute of package.py content.
+pub fn string_attribute_range(content: &str, name: &str) -> Option<(u32, std::ops::Range<usize>)> {
+    content.lines().enumerate().find_map(|(line_num, line)| {
+        let value = line.strip_prefix(name)?.trim_start().strip_prefix('=')?;
+        let quote_start = line.len() - value.trim_start().len();
+        let quote = line[quote_start..].chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let start = quote_start + 1;
+        let len = line[start..].find(quote)?;
+        Some((line_num as u32, start..start + len))
+    })
+}
+
 /// Parse a package.py file.
 pub async fn parse_package_file(_path: &Path) -> Result<Package> {
     todo!("Implement package parsing")
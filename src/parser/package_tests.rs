@@ -0,0 +1,140 @@
+//! Parsing of the package `tests` attribute.
+//!
+//! Each entry of the `tests` dictionary is either a command string (or list
+//! of arguments) or a dictionary with a `command` key and optional
+//! `requires`:
+//!
+//! ```python
+//! tests = {
+//!     "unit": "python -m pytest {root}/tests",
+//!     "lint": {
+//!         "command": ["pylint", "mymodule"],
+//!         "requires": ["pylint"],
+//!     },
+//! }
+//! ```
+
+use super::literal::{attribute_value_offset, parse_literal, Literal, Tokenizer};
+
+/// A test declared in the `tests` attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageTest {
+    /// Name of the test (the dictionary key)
+    pub name: String,
+    /// Command run by the test, with list arguments joined by spaces
+    pub command: Option<String>,
+    /// Extra packages the test requires
+    pub requires: Vec<String>,
+    /// Line of the test name (0-based)
+    pub line: u32,
+    /// Column of the opening quote of the test name (0-based)
+    pub column: u32,
+}
+
+/// Find and parse the entries of the `tests` attribute in package.py content.
+pub fn find_tests(content: &str) -> Vec<PackageTest> {
+    let Some(start) = attribute_value_offset(content, "tests", '{') else {
+        return Vec::new();
+    };
+
+    let mut tokens = Tokenizer::new(content, start);
+    let Some(Literal::Dict(entries)) = parse_literal(&mut tokens) else {
+        return Vec::new();
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let (command, requires) = match entry.value {
+                Literal::Dict(fields) => {
+                    let field = |key: &str| {
+                        fields
+                            .iter()
+                            .find(|field| field.key == key)
+                            .map(|field| &field.value)
+                    };
+                    let requires = match field("requires") {
+                        Some(Literal::List(items)) => items
+                            .iter()
+                            .filter_map(|item| match item {
+                                Literal::Str(s) => Some(s.clone()),
+                                _ => None,
+                            })
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    (field("command").and_then(command_text), requires)
+                }
+                value => (command_text(&value), Vec::new()),
+            };
+
+            PackageTest {
+                name: entry.key,
+                command,
+                requires,
+                line: entry.line,
+                column: entry.column,
+            }
+        })
+        .collect()
+}
+
+/// Get the text of a command given as a string or a list of arguments.
+fn command_text(value: &Literal) -> Option<String> {
+    match value {
+        Literal::Str(command) => Some(command.clone()),
+        Literal::List(args) => {
+            let args: Vec<&str> = args
+                .iter()
+                .filter_map(|arg| match arg {
+                    Literal::Str(arg) => Some(arg.as_str()),
+                    _ => None,
+                })
+                .collect();
+            (!args.is_empty()).then(|| args.join(" "))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_tests() {
+        let content = r#"name = "my_tool"
+
+tests = {
+    # Fast checks
+    "unit": "python -m pytest {root}/tests",
+    'lint': {
+        "command": ["pylint", "my_tool"],
+        "requires": ["pylint-2+"],
+        "run_on": ["default"],
+    },
+}
+"#;
+        let tests = find_tests(content);
+        assert_eq!(tests.len(), 2);
+
+        assert_eq!(tests[0].name, "unit");
+        assert_eq!(
+            tests[0].command.as_deref(),
+            Some("python -m pytest {root}/tests")
+        );
+        assert_eq!((tests[0].line, tests[0].column), (4, 4));
+
+        assert_eq!(tests[1].name, "lint");
+        assert_eq!(tests[1].command.as_deref(), Some("pylint my_tool"));
+        assert_eq!(tests[1].requires, vec!["pylint-2+"]);
+        assert_eq!((tests[1].line, tests[1].column), (5, 4));
+    }
+
+    #[test]
+    fn test_find_tests_missing_or_malformed() {
+        assert!(find_tests("name = \"my_tool\"\n").is_empty());
+        assert!(find_tests("tests = {\n    \"unit\": \"pytest\",\n").is_empty());
+        assert!(find_tests("def f():\n    tests = {\"unit\": \"pytest\"}\n").is_empty());
+    }
+}
@@ -0,0 +1,671 @@
+//! Source positions of the requirement strings of package definitions.
+//!
+//! Diagnostics about a requirement should underline that requirement, not the
+//! whole `requires` line. Each requirement string is split into its weak or
+//! conflict prefix, family name and version constraint so checks can target
+//! the exact token at fault:
+//!
+//! ```python
+//! requires = [
+//!     "python-3.9+<3.11",
+//!     "~maya-2024",
+//! ]
+//! variants = [
+//!     ["maya-2024", "python-3.10"],
+//! ]
+//! ```
+
+use super::literal::{attribute_value_offset, Token, Tokenizer};
+use crate::core::EPHEMERAL_PREFIX;
+
+/// Attributes whose values are lists of requirements.
+pub const REQUIREMENT_ATTRIBUTES: &[&str] =
+    &["requires", "build_requires", "private_build_requires"];
+
+/// Attribute whose value is a list of requirement lists, one per variant.
+pub const VARIANTS_ATTRIBUTE: &str = "variants";
+
+/// A single-line range of source text.
+///
+/// Lines and columns are 0-based and count characters; `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceRange {
+    /// Line of the range
+    pub line: u32,
+    /// First column of the range
+    pub start: u32,
+    /// Column after the last character of the range
+    pub end: u32,
+}
+
+impl SourceRange {
+    /// Get the number of characters covered by the range.
+    pub fn len(&self) -> u32 {
+        self.end - self.start
+    }
+
+    /// Check whether the range is empty.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Part of a requirement string under a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequirementPart {
+    /// The `~` (weak) or `!` (conflict) prefix
+    Prefix,
+    /// The package family name
+    Name,
+    /// The version constraint, including the `-` separating it from the name
+    Constraint,
+}
+
+/// A requirement string of a requirement list attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequirementSpan {
+    /// Attribute listing the requirement, e.g. `requires`
+    pub attribute: &'static str,
+    /// Index of the variant listing the requirement, for `variants` entries
+    pub variant: Option<usize>,
+    /// Requirement text without quotes
+    pub text: String,
+    /// Range of the text inside the quotes
+    pub range: SourceRange,
+    /// Range of the `~` (weak) or `!` (conflict) prefix
+    pub prefix: Option<SourceRange>,
+    /// Range of the package family name, or of the ephemeral name including
+    /// its leading `.`
+    pub name: SourceRange,
+    /// Range of the version constraint following the name
+    pub constraint: Option<SourceRange>,
+}
+
+impl RequirementSpan {
+    /// Get the package family name.
+    pub fn name_text(&self) -> &str {
+        self.slice(self.name)
+    }
+
+    /// Check whether the requirement is on an ephemeral, e.g. `.foo.bar-1`.
+    pub fn is_ephemeral(&self) -> bool {
+        self.name_text().starts_with(EPHEMERAL_PREFIX)
+    }
+
+    /// Get the part of the requirement at a 0-based character column of its
+    /// line.
+    ///
+    /// The column right after the name still belongs to the name, so a cursor
+    /// in `maya|-2024` navigates and completes the family.
+    pub fn part_at(&self, character: u32) -> RequirementPart {
+        match self.prefix {
+            Some(prefix) if character <= prefix.start => RequirementPart::Prefix,
+            _ if character <= self.name.end => RequirementPart::Name,
+            _ => RequirementPart::Constraint,
+        }
+    }
+
+    /// Get the range of a part of the requirement, or of the whole
+    /// requirement when it has no such part.
+    pub fn part_range(&self, part: RequirementPart) -> SourceRange {
+        match part {
+            RequirementPart::Prefix => self.prefix,
+            RequirementPart::Name => Some(self.name),
+            RequirementPart::Constraint => self.constraint,
+        }
+        .unwrap_or(self.range)
+    }
+
+    /// Get the text between the start of the name and a 0-based character
+    /// column, e.g. `maya-20` for a cursor typing `"~maya-20|"`.
+    pub fn text_before(&self, character: u32) -> &str {
+        let end = SourceRange {
+            end: character.clamp(self.name.start, self.range.end),
+            ..self.name
+        };
+        self.slice(end)
+    }
+
+    /// Get the version constraint text, if any.
+    pub fn constraint_text(&self) -> Option<&str> {
+        self.constraint.map(|range| self.slice(range))
+    }
+
+    fn slice(&self, range: SourceRange) -> &str {
+        let offset = |column: u32| {
+            self.text
+                .char_indices()
+                .nth((column - self.range.start) as usize)
+                .map_or(self.text.len(), |(index, _)| index)
+        };
+        &self.text[offset(range.start)..offset(range.end)]
+    }
+}
+
+/// Find the requirement strings of every requirement list attribute and of
+/// every variant, in source order.
+pub fn find_requirements(content: &str) -> Vec<RequirementSpan> {
+    let mut requirements: Vec<RequirementSpan> = REQUIREMENT_ATTRIBUTES
+        .iter()
+        .map(|attribute| (*attribute, 1))
+        .chain([(VARIANTS_ATTRIBUTE, 2)])
+        .filter_map(|(attribute, depth)| {
+            let start = attribute_value_offset(content, attribute, '[')?;
+            Some(list_requirements(content, start, attribute, depth))
+        })
+        .flatten()
+        .collect();
+
+    requirements.sort_by_key(|requirement| (requirement.range.line, requirement.range.start));
+    requirements
+}
+
+/// Find the requirement string under a 0-based line and character position.
+///
+/// The position may be anywhere between the quotes, including right after
+/// the last character.
+pub fn requirement_at(content: &str, line: u32, character: u32) -> Option<RequirementSpan> {
+    find_requirements(content).into_iter().find(|requirement| {
+        requirement.range.line == line
+            && (requirement.range.start..=requirement.range.end).contains(&character)
+    })
+}
+
+/// Find the requirement string under a 0-based line and character position,
+/// with the part of it under the position.
+///
+/// Hover, navigation and completion all look requirements up this way, so a
+/// cursor in `maya|-2024` means the family to each of them.
+pub fn requirement_part_at(
+    content: &str,
+    line: u32,
+    character: u32,
+) -> Option<(RequirementSpan, RequirementPart)> {
+    let span = requirement_at(content, line, character)?;
+    let part = span.part_at(character);
+    Some((span, part))
+}
+
+/// Layout of a requirement list, or of `variants` and its variant lists.
+///
+/// Positions are 0-based `(line, column)` pairs counting characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequirementList {
+    /// Attribute the list belongs to, e.g. `requires`
+    pub attribute: &'static str,
+    /// Position of the opening bracket
+    pub open: (u32, u32),
+    /// Position of the closing bracket, unless the list is still open
+    pub close: Option<(u32, u32)>,
+    /// Start and end of each entry, a string or a nested list
+    pub entries: Vec<((u32, u32), (u32, u32))>,
+    /// Position of a comma following the last entry
+    pub trailing_comma: Option<(u32, u32)>,
+}
+
+impl RequirementList {
+    /// Check whether the list spans several lines.
+    pub fn is_multiline(&self) -> bool {
+        self.close.is_some_and(|(line, _)| line != self.open.0)
+    }
+}
+
+/// Find the innermost requirement list containing a 0-based line and
+/// character position, between its brackets.
+pub fn requirement_list_at(content: &str, line: u32, character: u32) -> Option<RequirementList> {
+    let position = (line, character);
+    REQUIREMENT_ATTRIBUTES
+        .iter()
+        .chain([&VARIANTS_ATTRIBUTE])
+        .filter_map(|attribute| {
+            let start = attribute_value_offset(content, attribute, '[')?;
+            list_at(content, start, attribute, position)
+        })
+        .next()
+}
+
+/// Find the innermost list containing `position` in the list value starting
+/// at `start`.
+fn list_at(
+    content: &str,
+    start: usize,
+    attribute: &'static str,
+    position: (u32, u32),
+) -> Option<RequirementList> {
+    let mut tokens = Tokenizer::new(content, start);
+    let mut open: Vec<RequirementList> = Vec::new();
+    while let Some((token, line, column)) = tokens.next_token() {
+        match token {
+            Token::Open(_) => open.push(RequirementList {
+                attribute,
+                open: (line, column),
+                close: None,
+                entries: Vec::new(),
+                trailing_comma: None,
+            }),
+            Token::Close(_) => {
+                let mut list = open.pop()?;
+                list.close = Some((line, column));
+                if list.open < position && position <= (line, column) {
+                    return Some(list);
+                }
+                let end = tokens.position();
+                match open.last_mut() {
+                    Some(parent) => {
+                        parent.entries.push((list.open, end));
+                        parent.trailing_comma = None;
+                    }
+                    None => return None,
+                }
+            }
+            Token::Str(_) => {
+                let end = tokens.position();
+                if let Some(list) = open.last_mut() {
+                    list.entries.push(((line, column), end));
+                    list.trailing_comma = None;
+                }
+            }
+            Token::Comma => {
+                if let Some(list) = open.last_mut() {
+                    list.trailing_comma = Some((line, column));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // An unterminated list contains everything after its opening bracket, up
+    // to the statement following it
+    let end = tokens.position();
+    open.pop()
+        .filter(|list| list.open < position && position <= end)
+}
+
+/// Build the insertions adding a requirement to the `requires` list.
+///
+/// The layout of an existing list is preserved: multi-line lists get a new
+/// line indented like their entries, single-line lists a new trailing entry,
+/// and the quote style of the first entry is reused. Without a `requires`
+/// attribute, one is added after the `version` (or `name`) attribute.
+///
+/// Each insertion is an empty range with the text to insert there.
+pub fn requirement_insertion(content: &str, requirement: &str) -> Vec<(SourceRange, String)> {
+    let at = |line: u32, column: u32| SourceRange {
+        line,
+        start: column,
+        end: column,
+    };
+    let lines: Vec<&str> = content.lines().collect();
+
+    let Some(start) = attribute_value_offset(content, "requires", '[') else {
+        let line = ["version", "name"]
+            .iter()
+            .find_map(|name| {
+                lines.iter().position(|line| {
+                    line.strip_prefix(name)
+                        .is_some_and(|rest| rest.trim_start().starts_with('='))
+                })
+            })
+            .map_or(0, |index| index + 1);
+        return vec![(
+            at(line as u32, 0),
+            format!("requires = [\"{}\"]\n", requirement),
+        )];
+    };
+
+    // Locate the entries, a trailing comma and the closing bracket
+    let mut tokens = Tokenizer::new(content, start);
+    let Some((_, open_line, _)) = tokens.next_token() else {
+        return Vec::new();
+    };
+    let mut depth = 1;
+    let mut first_item: Option<(u32, u32)> = None;
+    let mut last_end: Option<(u32, u32)> = None;
+    let mut trailing_comma = false;
+    let close = loop {
+        let Some((token, line, column)) = tokens.next_token() else {
+            return Vec::new();
+        };
+        match token {
+            Token::Open(_) => depth += 1,
+            Token::Close(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    break (line, column);
+                }
+            }
+            Token::Str(_) if depth == 1 => {
+                first_item.get_or_insert((line, column));
+                last_end = Some(tokens.position());
+                trailing_comma = false;
+            }
+            Token::Comma if depth == 1 => trailing_comma = true,
+            _ => {}
+        }
+    };
+
+    let line_text = |line: u32| lines.get(line as usize).copied().unwrap_or("");
+    let quote = first_item
+        .and_then(|(line, column)| line_text(line).chars().nth(column as usize))
+        .unwrap_or('"');
+    let quoted = format!("{}{}{}", quote, requirement, quote);
+    let indent_of = |line: u32| {
+        let text = line_text(line);
+        text[..text.len() - text.trim_start().len()].to_string()
+    };
+    let close_on_own_line = line_text(close.0)
+        .chars()
+        .take(close.1 as usize)
+        .all(char::is_whitespace);
+
+    match last_end {
+        Some((end_line, end_column)) if close.0 != open_line && close_on_own_line => {
+            let mut insertions = Vec::new();
+            if !trailing_comma {
+                insertions.push((at(end_line, end_column), ",".to_string()));
+            }
+            insertions.push((
+                at(close.0, 0),
+                format!("{}{},\n", indent_of(end_line), quoted),
+            ));
+            insertions
+        }
+        None if close.0 != open_line && close_on_own_line => vec![(
+            at(close.0, 0),
+            format!("{}    {},\n", indent_of(close.0), quoted),
+        )],
+        Some(_) => {
+            let separator = if trailing_comma { " " } else { ", " };
+            vec![(at(close.0, close.1), format!("{}{}", separator, quoted))]
+        }
+        None => vec![(at(close.0, close.1), quoted)],
+    }
+}
+
+/// Collect the strings nested `item_depth` lists deep inside the list
+/// starting at `start`.
+fn list_requirements(
+    content: &str,
+    start: usize,
+    attribute: &'static str,
+    item_depth: usize,
+) -> Vec<RequirementSpan> {
+    let mut tokens = Tokenizer::new(content, start);
+    let mut requirements = Vec::new();
+    let mut depth = 0;
+    // Index of the current variant, counting the nested lists of `variants`
+    let mut variant = None;
+    let mut variants = 0;
+
+    while let Some((token, line, column)) = tokens.next_token() {
+        match token {
+            Token::Open(_) => {
+                depth += 1;
+                if item_depth > 1 && depth == 2 {
+                    variant = Some(variants);
+                    variants += 1;
+                }
+            }
+            Token::Close(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Token::Str(text) if depth == item_depth => {
+                let start = column + 1;
+                let (end_line, end_column) = tokens.position();
+                let end = if end_line == line {
+                    end_column - 1
+                } else {
+                    start + text.chars().count() as u32
+                };
+                requirements.push(split_requirement(
+                    attribute,
+                    variant,
+                    text,
+                    SourceRange { line, start, end },
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    requirements
+}
+
+/// Split a requirement string into its prefix, name and constraint ranges.
+fn split_requirement(
+    attribute: &'static str,
+    variant: Option<usize>,
+    text: String,
+    range: SourceRange,
+) -> RequirementSpan {
+    let at = |offset: usize| SourceRange {
+        line: range.line,
+        start: range.start + offset as u32,
+        end: range.start + offset as u32,
+    };
+
+    let chars: Vec<char> = text.chars().collect();
+    let leading = chars.iter().take_while(|c| c.is_whitespace()).count();
+    let prefix_len = usize::from(matches!(chars.get(leading), Some('~' | '!')));
+    let name_start = leading + prefix_len;
+    // Ephemeral names start with a dot and may contain more of them
+    let ephemeral = chars.get(name_start) == Some(&EPHEMERAL_PREFIX);
+    let name_len = chars[name_start..]
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || **c == '_' || (ephemeral && **c == '.'))
+        .count();
+    let name_end = name_start + name_len;
+    let trailing = chars.iter().rev().take_while(|c| c.is_whitespace()).count();
+    let constraint_end = (chars.len() - trailing).max(name_end);
+
+    RequirementSpan {
+        attribute,
+        variant,
+        prefix: (prefix_len > 0).then(|| SourceRange {
+            end: at(leading).start + 1,
+            ..at(leading)
+        }),
+        name: SourceRange {
+            end: at(name_end).start,
+            ..at(name_start)
+        },
+        constraint: (constraint_end > name_end).then(|| SourceRange {
+            end: at(constraint_end).start,
+            ..at(name_end)
+        }),
+        text,
+        range,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_requirements() {
+        let content = "name = 'tools'\nrequires = [\n    \"python-3.9+<3.11\",  # interpreter\n    '~maya',\n]\nbuild_requires = [\"!cmake-3\"]\n";
+
+        let requirements = find_requirements(content);
+        assert_eq!(requirements.len(), 3);
+
+        let python = &requirements[0];
+        assert_eq!(python.attribute, "requires");
+        assert_eq!(python.variant, None);
+        assert_eq!(python.text, "python-3.9+<3.11");
+        assert_eq!(
+            python.range,
+            SourceRange {
+                line: 2,
+                start: 5,
+                end: 21
+            }
+        );
+        assert_eq!(python.name_text(), "python");
+        assert_eq!(python.name.end, 11);
+        assert_eq!(python.constraint_text(), Some("-3.9+<3.11"));
+        assert!(python.prefix.is_none());
+
+        let maya = &requirements[1];
+        assert_eq!(maya.prefix.map(|p| (p.start, p.end)), Some((5, 6)));
+        assert_eq!(maya.name_text(), "maya");
+        assert!(maya.constraint.is_none());
+
+        let cmake = &requirements[2];
+        assert_eq!(cmake.attribute, "build_requires");
+        assert_eq!(cmake.range.line, 5);
+        assert_eq!(cmake.name_text(), "cmake");
+        assert_eq!(cmake.constraint_text(), Some("-3"));
+    }
+
+    #[test]
+    fn test_find_ephemeral_requirements() {
+        let content = "requires = ['.foo.bar-1', '!.beta']\nvariants = [['.platform-linux']]\n";
+
+        let requirements = find_requirements(content);
+        assert!(requirements.iter().all(RequirementSpan::is_ephemeral));
+        assert_eq!(requirements[0].name_text(), ".foo.bar");
+        assert_eq!(requirements[0].constraint_text(), Some("-1"));
+        assert_eq!(requirements[1].name_text(), ".beta");
+        assert_eq!(requirements[2].name_text(), ".platform");
+        assert_eq!(requirements[2].variant, Some(0));
+    }
+
+    #[test]
+    fn test_find_requirements_ignores_nested_and_indented_lists() {
+        let content =
+            "requires = [[\"nested\"], \"python\"]\ndef f():\n    requires = [\"local\"]\n";
+
+        let requirements = find_requirements(content);
+        assert_eq!(requirements.len(), 1);
+        assert_eq!(requirements[0].name_text(), "python");
+    }
+
+    #[test]
+    fn test_find_variant_requirements() {
+        let content = "variants = [\n    [\"maya-2024\", \"python-3.10\"],\n    [\n        \"houdini-20\",\n        \"python-3.11\",\n    ],\n]\n";
+
+        let requirements = find_requirements(content);
+        let names: Vec<&str> = requirements.iter().map(|r| r.name_text()).collect();
+        assert_eq!(names, vec!["maya", "python", "houdini", "python"]);
+        assert!(requirements.iter().all(|r| r.attribute == VARIANTS_ATTRIBUTE));
+        let variants: Vec<Option<usize>> = requirements.iter().map(|r| r.variant).collect();
+        assert_eq!(variants, vec![Some(0), Some(0), Some(1), Some(1)]);
+
+        let houdini = requirement_at(content, 3, 12).unwrap();
+        assert_eq!(houdini.text, "houdini-20");
+        assert_eq!(requirement_at(content, 1, 26).unwrap().text, "python-3.10");
+        assert!(requirement_at(content, 1, 4).is_none());
+    }
+
+    #[test]
+    fn test_requirement_part_at() {
+        // Columns:      0123456789012345678901234567
+        let content = "requires = [' ~maya-2024+', 'python']\n";
+        let maya = requirement_at(content, 0, 16).unwrap();
+        let parts: Vec<RequirementPart> = [13, 14, 15, 19, 20, 25]
+            .iter()
+            .map(|&character| maya.part_at(character))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                RequirementPart::Prefix,
+                RequirementPart::Prefix,
+                RequirementPart::Name,
+                RequirementPart::Name,
+                RequirementPart::Constraint,
+                RequirementPart::Constraint,
+            ]
+        );
+        assert_eq!(maya.text_before(22), "maya-20");
+        assert_eq!(maya.text_before(0), "");
+        assert_eq!(maya.part_range(RequirementPart::Constraint).start, 19);
+
+        let python = requirement_at(content, 0, 35).unwrap();
+        assert_eq!(python.part_at(35), RequirementPart::Name);
+        assert_eq!(python.part_range(RequirementPart::Constraint), python.range);
+    }
+
+    #[test]
+    fn test_half_typed_list_recovers_at_next_statement() {
+        let content = "name = 'tools'\nrequires = [\n    \"python-3.9\",\n    \"ma\ndescription = \"A tool\"\nvariants = [[\"maya-2024\"]]\n";
+
+        let requirements = find_requirements(content);
+        let found: Vec<(&str, &str)> = requirements
+            .iter()
+            .map(|r| (r.attribute, r.name_text()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("requires", "python"),
+                ("requires", "ma"),
+                ("variants", "maya")
+            ]
+        );
+
+        assert_eq!(
+            requirement_list_at(content, 3, 7).unwrap().attribute,
+            "requires"
+        );
+        let variant = requirement_list_at(content, 5, 13).unwrap();
+        assert_eq!(variant.attribute, VARIANTS_ATTRIBUTE);
+        assert_eq!(variant.open, (5, 12));
+        assert!(requirement_list_at(content, 4, 16).is_none());
+    }
+
+    /// Apply insertions to content, last first.
+    fn insert(content: &str, mut insertions: Vec<(SourceRange, String)>) -> String {
+        let mut lines: Vec<String> = content.split('\n').map(str::to_string).collect();
+        insertions.sort_by_key(|(range, _)| std::cmp::Reverse((range.line, range.start)));
+        for (range, text) in insertions {
+            let line = &mut lines[range.line as usize];
+            let offset = line
+                .char_indices()
+                .nth(range.start as usize)
+                .map_or(line.len(), |(index, _)| index);
+            line.insert_str(offset, &text);
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_requirement_insertion() {
+        let multi_line = "requires = [\n    'python-3',\n    'maya',  # host\n]\n";
+        assert_eq!(
+            insert(multi_line, requirement_insertion(multi_line, "ocio")),
+            "requires = [\n    'python-3',\n    'maya',  # host\n    'ocio',\n]\n"
+        );
+
+        let no_trailing_comma = "requires = [\n    \"python-3\"\n]\n";
+        assert_eq!(
+            insert(
+                no_trailing_comma,
+                requirement_insertion(no_trailing_comma, "ocio")
+            ),
+            "requires = [\n    \"python-3\",\n    \"ocio\",\n]\n"
+        );
+
+        let single_line = "requires = [\"python-3\"]\n";
+        assert_eq!(
+            insert(single_line, requirement_insertion(single_line, "ocio")),
+            "requires = [\"python-3\", \"ocio\"]\n"
+        );
+
+        let empty = "requires = []\n";
+        assert_eq!(
+            insert(empty, requirement_insertion(empty, "ocio")),
+            "requires = [\"ocio\"]\n"
+        );
+
+        let missing = "name = 'viewer'\nversion = '1.0'\ndef commands():\n    pass\n";
+        assert_eq!(
+            insert(missing, requirement_insertion(missing, "ocio")),
+            "name = 'viewer'\nversion = '1.0'\nrequires = [\"ocio\"]\ndef commands():\n    pass\n"
+        );
+    }
+}
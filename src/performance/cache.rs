@@ -1,12 +1,22 @@
 //! Caching system for performance optimization.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+use super::metrics::MetricsCollector;
+use super::tuning::{
+    plan_adjustments, CacheAdjustment, CacheKind, CacheTuningReport, CacheUsage, TuningPolicy,
+};
+
+/// Maximum number of cache adjustments kept for reports.
+const MAX_ADJUSTMENTS: usize = 50;
+
 /// A cache entry with expiration time.
 #[derive(Debug, Clone)]
 struct CacheEntry<T> {
@@ -33,7 +43,9 @@ impl<T> CacheEntry<T> {
 pub struct Cache<K, V> {
     data: Arc<RwLock<HashMap<K, CacheEntry<V>>>>,
     default_ttl: Duration,
-    max_size: usize,
+    max_size: AtomicUsize,
+    hits: AtomicU64,
+    misses: AtomicU64,
 }
 
 impl<K, V> Cache<K, V>
@@ -46,19 +58,55 @@ where
         Self {
             data: Arc::new(RwLock::new(HashMap::new())),
             default_ttl,
-            max_size,
+            max_size: AtomicUsize::new(max_size),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
         }
     }
 
     /// Get a value from the cache.
     pub async fn get(&self, key: &K) -> Option<V> {
         let data = self.data.read().await;
-        if let Some(entry) = data.get(key) {
-            if !entry.is_expired() {
-                return Some(entry.value.clone());
-            }
+        let value = data
+            .get(key)
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| entry.value.clone());
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    /// Get the number of hits and misses of all lookups so far.
+    pub fn lookups(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Get the maximum number of entries.
+    pub fn max_size(&self) -> usize {
+        self.max_size.load(Ordering::Relaxed)
+    }
+
+    /// Change the maximum number of entries, evicting expired and then the
+    /// oldest entries when shrinking below the current size.
+    pub async fn set_max_size(&self, max_size: usize) {
+        self.max_size.store(max_size, Ordering::Relaxed);
+        let mut data = self.data.write().await;
+        if data.len() > max_size {
+            self.cleanup_expired(&mut data);
+        }
+        while data.len() > max_size {
+            let Some(oldest_key) = self.find_oldest_key(&data) else {
+                break;
+            };
+            data.remove(&oldest_key);
         }
-        None
     }
 
     /// Put a value into the cache with default TTL.
@@ -71,11 +119,12 @@ where
         let mut data = self.data.write().await;
 
         // Remove expired entries if we're at capacity
-        if data.len() >= self.max_size {
+        let max_size = self.max_size();
+        if data.len() >= max_size {
             self.cleanup_expired(&mut data);
 
             // If still at capacity, remove oldest entry
-            if data.len() >= self.max_size {
+            if data.len() >= max_size {
                 if let Some(oldest_key) = self.find_oldest_key(&data) {
                     data.remove(&oldest_key);
                 }
@@ -103,13 +152,14 @@ where
         let total_entries = data.len();
         let expired_entries = data.values().filter(|entry| entry.is_expired()).count();
         let active_entries = total_entries - expired_entries;
+        let (hits, misses) = self.lookups();
 
         CacheStats {
             total_entries,
             active_entries,
             expired_entries,
-            max_size: self.max_size,
-            hit_ratio: 0.0, // This would need to be tracked separately
+            max_size: self.max_size(),
+            hit_ratio: hit_ratio(hits, misses),
         }
     }
 
@@ -126,6 +176,16 @@ where
     }
 }
 
+/// Get the share of lookups that were hits, 0 without lookups.
+pub(crate) fn hit_ratio(hits: u64, misses: u64) -> f64 {
+    let total = hits + misses;
+    if total > 0 {
+        hits as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
 /// Cache statistics.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheStats {
@@ -141,16 +201,57 @@ pub struct CacheStats {
     pub hit_ratio: f64,
 }
 
+/// Volatility class of a package repository.
+///
+/// Local packages are rebuilt constantly while released packages are
+/// practically immutable, so each class gets its own package cache TTL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RepositoryClass {
+    /// The local packages path, where packages are built during development
+    Local,
+    /// Other repositories of the package search path
+    Workspace,
+    /// The release packages path
+    Release,
+}
+
+impl RepositoryClass {
+    /// Classify the repository containing `path`.
+    pub fn of(path: &Path, config: &crate::config::Config) -> Self {
+        let contains = |root: &Option<PathBuf>| root.as_ref().is_some_and(|r| path.starts_with(r));
+        if contains(&config.local_packages_path) {
+            Self::Local
+        } else if contains(&config.release_packages_path) {
+            Self::Release
+        } else {
+            Self::Workspace
+        }
+    }
+}
+
 /// Cache manager that handles multiple caches.
 pub struct CacheManager {
-    /// Cache for package discovery results
+    /// Cache for package discovery results, keyed by family
     package_cache: Cache<String, Vec<crate::core::types::Package>>,
+    /// Package cache TTLs of the repository classes
+    package_ttls: HashMap<RepositoryClass, Duration>,
     /// Cache for validation results
     validation_cache: Cache<String, crate::validation::ValidationResult>,
     /// Cache for completion results
     completion_cache: Cache<String, Vec<String>>,
     /// Cache statistics
     stats: Arc<RwLock<CacheManagerStats>>,
+    /// Entries shared by the sub-caches
+    budget: usize,
+    /// Lookups seen by the previous tuning round and recent adjustments
+    tuning: RwLock<TuningState>,
+}
+
+#[derive(Debug, Default)]
+struct TuningState {
+    lookups: HashMap<CacheKind, (u64, u64)>,
+    adjustments: VecDeque<CacheAdjustment>,
 }
 
 #[derive(Debug, Clone)]
@@ -168,32 +269,194 @@ impl CacheManager {
         let ttl = Duration::from_secs(config.cache_ttl_seconds);
         let max_size = config.cache_size_mb * 1024 / 10; // Rough estimate of entries per MB
 
+        let package_ttls = [
+            RepositoryClass::Local,
+            RepositoryClass::Workspace,
+            RepositoryClass::Release,
+        ]
+        .into_iter()
+        .map(|class| (class, config.package_cache_ttl(class)))
+        .collect();
+
+        let package_cache = Cache::new(ttl, max_size);
+        let validation_cache = Cache::new(ttl, max_size / 2);
+        let completion_cache = Cache::new(Duration::from_secs(60), max_size / 4); // Shorter TTL for completions
+        let budget =
+            package_cache.max_size() + validation_cache.max_size() + completion_cache.max_size();
+
         Self {
-            package_cache: Cache::new(ttl, max_size),
-            validation_cache: Cache::new(ttl, max_size / 2),
-            completion_cache: Cache::new(Duration::from_secs(60), max_size / 4), // Shorter TTL for completions
+            package_cache,
+            package_ttls,
+            validation_cache,
+            completion_cache,
             stats: Arc::new(RwLock::new(CacheManagerStats {
                 hits: 0,
                 misses: 0,
                 puts: 0,
                 evictions: 0,
             })),
+            budget,
+            tuning: RwLock::new(TuningState::default()),
+        }
+    }
+
+    /// Resize the sub-caches within the shared budget from their lookups
+    /// since the previous round and the latencies in `metrics`.
+    ///
+    /// Returns the adjustments made, which are also kept for
+    /// [`CacheManager::tuning_report`].
+    pub async fn auto_tune(
+        &self,
+        metrics: &MetricsCollector,
+        policy: &TuningPolicy,
+    ) -> Vec<CacheAdjustment> {
+        let mut tuning = self.tuning.write().await;
+
+        let mut usage = Vec::new();
+        for cache in CacheKind::ALL {
+            let (hits, misses) = self.lookups(cache);
+            let (previous_hits, previous_misses) = tuning
+                .lookups
+                .insert(cache, (hits, misses))
+                .unwrap_or((0, 0));
+            usage.push(CacheUsage {
+                cache,
+                max_size: self.max_size(cache),
+                active_entries: self.active_entries(cache).await,
+                hits: hits - previous_hits,
+                misses: misses - previous_misses,
+                p95_ms: metrics
+                    .get_metrics(cache.operation())
+                    .await
+                    .map(|metrics| metrics.p95_duration_ms),
+            });
+        }
+
+        let adjustments = plan_adjustments(&usage, self.budget, policy);
+        for adjustment in &adjustments {
+            match adjustment.cache {
+                CacheKind::Package => self.package_cache.set_max_size(adjustment.new_size).await,
+                CacheKind::Validation => {
+                    self.validation_cache
+                        .set_max_size(adjustment.new_size)
+                        .await
+                }
+                CacheKind::Completion => {
+                    self.completion_cache
+                        .set_max_size(adjustment.new_size)
+                        .await
+                }
+            }
+            tracing::debug!(
+                "Resized {:?} cache from {} to {} entries: {}",
+                adjustment.cache,
+                adjustment.previous_size,
+                adjustment.new_size,
+                adjustment.reason
+            );
+            tuning.adjustments.push_back(adjustment.clone());
+        }
+        while tuning.adjustments.len() > MAX_ADJUSTMENTS {
+            tuning.adjustments.pop_front();
+        }
+
+        adjustments
+    }
+
+    /// Get the current sub-cache sizes and the most recent adjustments.
+    pub async fn tuning_report(&self) -> CacheTuningReport {
+        CacheTuningReport {
+            budget: self.budget,
+            sizes: CacheKind::ALL
+                .into_iter()
+                .map(|cache| (cache, self.max_size(cache)))
+                .collect(),
+            adjustments: self
+                .tuning
+                .read()
+                .await
+                .adjustments
+                .iter()
+                .cloned()
+                .collect(),
+        }
+    }
+
+    fn lookups(&self, cache: CacheKind) -> (u64, u64) {
+        match cache {
+            CacheKind::Package => self.package_cache.lookups(),
+            CacheKind::Validation => self.validation_cache.lookups(),
+            CacheKind::Completion => self.completion_cache.lookups(),
+        }
+    }
+
+    fn max_size(&self, cache: CacheKind) -> usize {
+        match cache {
+            CacheKind::Package => self.package_cache.max_size(),
+            CacheKind::Validation => self.validation_cache.max_size(),
+            CacheKind::Completion => self.completion_cache.max_size(),
+        }
+    }
+
+    async fn active_entries(&self, cache: CacheKind) -> usize {
+        match cache {
+            CacheKind::Package => self.package_cache.stats().await.active_entries,
+            CacheKind::Validation => self.validation_cache.stats().await.active_entries,
+            CacheKind::Completion => self.completion_cache.stats().await.active_entries,
         }
     }
 
-    /// Get packages from cache.
+    /// Get the cached packages of a family directory.
     pub async fn get_packages(&self, key: &str) -> Option<Vec<crate::core::types::Package>> {
         let result = self.package_cache.get(&key.to_string()).await;
         self.update_stats(result.is_some()).await;
         result
     }
 
-    /// Put packages into cache.
-    pub async fn put_packages(&self, key: String, packages: Vec<crate::core::types::Package>) {
-        self.package_cache.put(key, packages).await;
+    /// Put the packages of a family directory into cache, expiring after the
+    /// TTL of the repository class they were found in.
+    pub async fn put_packages(
+        &self,
+        key: String,
+        class: RepositoryClass,
+        packages: Vec<crate::core::types::Package>,
+    ) {
+        let ttl = self.package_ttls[&class];
+        self.package_cache.put_with_ttl(key, packages, ttl).await;
         self.increment_puts().await;
     }
 
+    /// Drop the cached packages of the families affected by file changes.
+    ///
+    /// Watcher events invalidate families immediately instead of waiting for
+    /// their TTL. The family of a path is the first directory below the
+    /// repository containing it. Returns the number of invalidated families.
+    pub async fn invalidate_changed_paths(
+        &self,
+        paths: &[PathBuf],
+        repositories: &[PathBuf],
+    ) -> usize {
+        let mut families: Vec<String> = paths
+            .iter()
+            .filter_map(|path| {
+                repositories.iter().find_map(|repository| {
+                    let family = path.strip_prefix(repository).ok()?.components().next()?;
+                    Some(repository.join(family).to_string_lossy().into_owned())
+                })
+            })
+            .collect();
+        families.sort();
+        families.dedup();
+
+        let mut invalidated = 0;
+        for family in families {
+            if self.package_cache.remove(&family).await.is_some() {
+                invalidated += 1;
+            }
+        }
+        invalidated
+    }
+
     /// Get validation result from cache.
     pub async fn get_validation(&self, key: &str) -> Option<crate::validation::ValidationResult> {
         let result = self.validation_cache.get(&key.to_string()).await;
@@ -364,4 +627,113 @@ mod tests {
         let stats = manager.get_stats().await;
         assert!(stats.hit_ratio > 0.0);
     }
+
+    #[tokio::test]
+    async fn test_auto_tune_moves_budget_to_missing_cache() {
+        let config = super::super::PerformanceConfig {
+            cache_size_mb: 1,
+            ..Default::default()
+        };
+        let manager = CacheManager::new(&config);
+        let metrics = MetricsCollector::new(100);
+        metrics
+            .record_duration("completion", Duration::from_millis(120))
+            .await;
+
+        // Fill the completion cache and miss it more often than not
+        let completion_size = manager.max_size(CacheKind::Completion);
+        for i in 0..completion_size {
+            manager.put_completions(i.to_string(), Vec::new()).await;
+        }
+        for i in 0..40 {
+            manager.get_completions(&format!("missing{}", i)).await;
+        }
+        manager.get_packages("maya").await;
+
+        let adjustments = manager.auto_tune(&metrics, &TuningPolicy::default()).await;
+        let resized: Vec<CacheKind> = adjustments.iter().map(|a| a.cache).collect();
+        assert_eq!(
+            resized,
+            vec![
+                CacheKind::Package,
+                CacheKind::Validation,
+                CacheKind::Completion
+            ]
+        );
+        assert!(manager.max_size(CacheKind::Completion) > completion_size);
+
+        let report = manager.tuning_report().await;
+        assert_eq!(report.adjustments, adjustments);
+        assert!(report.sizes.values().sum::<usize>() <= report.budget);
+
+        // Lookups are counted per round, and entries in use are kept
+        let adjustments = manager.auto_tune(&metrics, &TuningPolicy::default()).await;
+        let completion = adjustments
+            .iter()
+            .find(|adjustment| adjustment.cache == CacheKind::Completion)
+            .unwrap();
+        assert_eq!(completion.reason, "unused since the last tuning round");
+        assert_eq!(completion.new_size, completion_size);
+    }
+
+    #[test]
+    fn test_repository_class() {
+        let mut config = crate::config::Config::new();
+        config.local_packages_path = Some(PathBuf::from("/home/me/packages"));
+        config.release_packages_path = Some(PathBuf::from("/studio/release"));
+
+        let class = |path: &str| RepositoryClass::of(Path::new(path), &config);
+        assert_eq!(class("/home/me/packages/tools/1.0"), RepositoryClass::Local);
+        assert_eq!(class("/studio/release/maya/2024"), RepositoryClass::Release);
+        assert_eq!(
+            class("/studio/shared/maya/2024"),
+            RepositoryClass::Workspace
+        );
+    }
+
+    #[tokio::test]
+    async fn test_package_ttl_per_repository_class() {
+        let config = super::super::PerformanceConfig {
+            local_cache_ttl_seconds: 0,
+            ..Default::default()
+        };
+        let manager = CacheManager::new(&config);
+
+        manager
+            .put_packages("tools".to_string(), RepositoryClass::Local, Vec::new())
+            .await;
+        manager
+            .put_packages("maya".to_string(), RepositoryClass::Release, Vec::new())
+            .await;
+        sleep(Duration::from_millis(5)).await;
+
+        assert!(manager.get_packages("tools").await.is_none());
+        assert!(manager.get_packages("maya").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_watcher_changes_bypass_ttl() {
+        let manager = CacheManager::new(&super::super::PerformanceConfig::default());
+        let release = PathBuf::from("/studio/release");
+        let key = |family: &str| release.join(family).to_string_lossy().into_owned();
+        for family in ["maya", "houdini"] {
+            manager
+                .put_packages(key(family), RepositoryClass::Release, Vec::new())
+                .await;
+        }
+
+        let repositories = vec![release.clone()];
+        let changed = vec![
+            release.join("maya").join("2024").join("package.py"),
+            release.join("maya").join("2025").join("package.py"),
+            PathBuf::from("/elsewhere/houdini/package.py"),
+        ];
+        let invalidated = manager
+            .invalidate_changed_paths(&changed, &repositories)
+            .await;
+
+        assert_eq!(invalidated, 1);
+        assert!(manager.get_packages(&key("maya")).await.is_none());
+        assert!(manager.get_packages(&key("houdini")).await.is_some());
+    }
 }
@@ -126,10 +126,21 @@ impl PerformanceMetrics {
     }
 }
 
+/// Lifetime counts of an operation, kept beyond the bounded history.
+#[derive(Debug, Clone, Copy, Default)]
+struct OperationCounts {
+    /// Measurements recorded
+    total: u64,
+    /// Failures recorded
+    errors: u64,
+}
+
 /// Collects and analyzes performance metrics.
 pub struct MetricsCollector {
     /// Raw metrics data
     metrics: Arc<RwLock<HashMap<String, VecDeque<Metric>>>>,
+    /// Lifetime counts of each operation, exported as Prometheus counters
+    counts: Arc<RwLock<HashMap<String, OperationCounts>>>,
     /// Maximum number of metrics to keep per operation
     max_metrics_per_operation: usize,
     /// Start time for calculating rates
@@ -141,6 +152,7 @@ impl MetricsCollector {
     pub fn new(max_metrics_per_operation: usize) -> Self {
         Self {
             metrics: Arc::new(RwLock::new(HashMap::new())),
+            counts: Arc::new(RwLock::new(HashMap::new())),
             max_metrics_per_operation,
             start_time: Instant::now(),
         }
@@ -148,6 +160,13 @@ impl MetricsCollector {
 
     /// Record a metric.
     pub async fn record(&self, metric: Metric) {
+        self.counts
+            .write()
+            .await
+            .entry(metric.name.clone())
+            .or_default()
+            .total += 1;
+
         let mut metrics = self.metrics.write().await;
         let operation_metrics = metrics
             .entry(metric.name.clone())
@@ -167,6 +186,19 @@ impl MetricsCollector {
         self.record(metric).await;
     }
 
+    /// Record a failure of an operation.
+    ///
+    /// Failures are counted separately from durations, so a failed operation
+    /// is usually recorded with both.
+    pub async fn record_error(&self, operation: impl Into<String>) {
+        self.counts
+            .write()
+            .await
+            .entry(operation.into())
+            .or_default()
+            .errors += 1;
+    }
+
     /// Record a duration with metadata.
     pub async fn record_duration_with_metadata(
         &self,
@@ -250,6 +282,7 @@ impl MetricsCollector {
     pub async fn clear(&self) {
         let mut metrics = self.metrics.write().await;
         metrics.clear();
+        self.counts.write().await.clear();
     }
 
     /// Get the total number of recorded metrics.
@@ -263,6 +296,75 @@ impl MetricsCollector {
         self.start_time.elapsed().as_secs()
     }
 
+    /// Render all operations in the Prometheus text exposition format.
+    ///
+    /// Every operation becomes a series of the `rez_lsp_operation_duration_ms`
+    /// summary, labelled with the operation name. The summary covers the
+    /// bounded history; `rez_lsp_operations_total` and
+    /// `rez_lsp_operation_errors_total` count over the server's lifetime.
+    pub async fn render_prometheus(&self) -> String {
+        let all_metrics = self.get_all_metrics().await;
+        let mut operations: Vec<&PerformanceMetrics> = all_metrics.values().collect();
+        operations.sort_by(|a, b| a.operation.cmp(&b.operation));
+
+        let mut output = String::new();
+        output.push_str("# HELP rez_lsp_uptime_seconds Time since the server started.\n");
+        output.push_str("# TYPE rez_lsp_uptime_seconds gauge\n");
+        output.push_str(&format!(
+            "rez_lsp_uptime_seconds {}\n",
+            self.uptime_seconds()
+        ));
+
+        output.push_str(
+            "# HELP rez_lsp_operation_duration_ms Duration of server operations in milliseconds.\n",
+        );
+        output.push_str("# TYPE rez_lsp_operation_duration_ms summary\n");
+        for metrics in operations {
+            let operation = escape_label_value(&metrics.operation);
+            for (quantile, value) in [
+                ("0.95", metrics.p95_duration_ms),
+                ("0.99", metrics.p99_duration_ms),
+            ] {
+                output.push_str(&format!(
+                    "rez_lsp_operation_duration_ms{{operation=\"{}\",quantile=\"{}\"}} {}\n",
+                    operation, quantile, value
+                ));
+            }
+            output.push_str(&format!(
+                "rez_lsp_operation_duration_ms_sum{{operation=\"{}\"}} {}\n",
+                operation, metrics.total_duration_ms
+            ));
+            output.push_str(&format!(
+                "rez_lsp_operation_duration_ms_count{{operation=\"{}\"}} {}\n",
+                operation, metrics.count
+            ));
+        }
+
+        let counts = self.counts.read().await;
+        let mut counts: Vec<(&String, &OperationCounts)> = counts.iter().collect();
+        counts.sort_by(|a, b| a.0.cmp(b.0));
+        output.push_str("# HELP rez_lsp_operations_total Server operations performed.\n");
+        output.push_str("# TYPE rez_lsp_operations_total counter\n");
+        for (operation, count) in &counts {
+            output.push_str(&format!(
+                "rez_lsp_operations_total{{operation=\"{}\"}} {}\n",
+                escape_label_value(operation),
+                count.total
+            ));
+        }
+        output.push_str("# HELP rez_lsp_operation_errors_total Server operations that failed.\n");
+        output.push_str("# TYPE rez_lsp_operation_errors_total counter\n");
+        for (operation, count) in &counts {
+            output.push_str(&format!(
+                "rez_lsp_operation_errors_total{{operation=\"{}\"}} {}\n",
+                escape_label_value(operation),
+                count.errors
+            ));
+        }
+
+        output
+    }
+
     /// Get recent metrics for an operation (last N measurements).
     pub async fn get_recent_metrics(&self, operation: &str, count: usize) -> Vec<Metric> {
         let metrics = self.metrics.read().await;
@@ -319,6 +421,14 @@ impl MetricsCollector {
     }
 }
 
+/// Escape a Prometheus label value.
+pub(crate) fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
 /// Summary of all metrics.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsSummary {
@@ -444,4 +554,45 @@ mod tests {
         assert_eq!(slowest[0].operation, "slow_op");
         assert_eq!(slowest[1].operation, "medium_op");
     }
+
+    #[tokio::test]
+    async fn test_render_prometheus() {
+        let collector = MetricsCollector::new(100);
+        collector
+            .record_duration("hover", Duration::from_millis(10))
+            .await;
+        collector
+            .record_duration("hover", Duration::from_millis(30))
+            .await;
+
+        let output = collector.render_prometheus().await;
+        assert!(output.contains("# TYPE rez_lsp_operation_duration_ms summary"));
+        assert!(output.contains("rez_lsp_operation_duration_ms_sum{operation=\"hover\"} 40"));
+        assert!(output.contains("rez_lsp_operation_duration_ms_count{operation=\"hover\"} 2"));
+        assert!(output
+            .contains("rez_lsp_operation_duration_ms{operation=\"hover\",quantile=\"0.99\"} 30"));
+        assert!(output.contains("rez_lsp_operations_total{operation=\"hover\"} 2"));
+        assert!(output.contains("rez_lsp_operation_errors_total{operation=\"hover\"} 0"));
+    }
+
+    #[tokio::test]
+    async fn test_operation_counts_outlive_history() {
+        let collector = MetricsCollector::new(2);
+        for _ in 0..3 {
+            collector
+                .record_duration("resolve", Duration::from_millis(1))
+                .await;
+        }
+        collector.record_error("resolve").await;
+
+        let output = collector.render_prometheus().await;
+        assert!(output.contains("rez_lsp_operation_duration_ms_count{operation=\"resolve\"} 2"));
+        assert!(output.contains("rez_lsp_operations_total{operation=\"resolve\"} 3"));
+        assert!(output.contains("rez_lsp_operation_errors_total{operation=\"resolve\"} 1"));
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape_label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
 }
@@ -3,10 +3,16 @@
 pub mod cache;
 pub mod metrics;
 pub mod profiler;
+pub mod textfile;
+pub mod trace;
+pub mod tuning;
 
-pub use cache::{CacheManager, CacheStats};
+pub use cache::{CacheManager, CacheStats, RepositoryClass};
 pub use metrics::{MetricsCollector, PerformanceMetrics};
 pub use profiler::{Profiler, ProfilerGuard};
+pub use textfile::TextfileExporter;
+pub use trace::{ChromeTrace, TraceEvent};
+pub use tuning::{CacheAdjustment, CacheKind, CacheTuningReport, TuningPolicy};
 
 use std::time::{Duration, Instant};
 
@@ -21,6 +27,12 @@ pub struct PerformanceConfig {
     pub cache_size_mb: usize,
     /// Cache TTL in seconds
     pub cache_ttl_seconds: u64,
+    /// Package cache TTL in seconds for local (development) repositories
+    pub local_cache_ttl_seconds: u64,
+    /// Package cache TTL in seconds for other configured repositories
+    pub workspace_cache_ttl_seconds: u64,
+    /// Package cache TTL in seconds for the release repository
+    pub release_cache_ttl_seconds: u64,
     /// Enable profiling
     pub enable_profiling: bool,
     /// Maximum number of metrics to keep in memory
@@ -33,13 +45,28 @@ impl Default for PerformanceConfig {
             enable_monitoring: true,
             enable_caching: true,
             cache_size_mb: 100,
-            cache_ttl_seconds: 300,  // 5 minutes
+            cache_ttl_seconds: 300, // 5 minutes
+            local_cache_ttl_seconds: 30,
+            workspace_cache_ttl_seconds: 300,
+            release_cache_ttl_seconds: 3600,
             enable_profiling: false, // Disabled by default for production
             max_metrics_history: 1000,
         }
     }
 }
 
+impl PerformanceConfig {
+    /// Get the package cache TTL of a repository class.
+    pub fn package_cache_ttl(&self, class: RepositoryClass) -> Duration {
+        let seconds = match class {
+            RepositoryClass::Local => self.local_cache_ttl_seconds,
+            RepositoryClass::Workspace => self.workspace_cache_ttl_seconds,
+            RepositoryClass::Release => self.release_cache_ttl_seconds,
+        };
+        Duration::from_secs(seconds)
+    }
+}
+
 /// A simple timer for measuring operation duration.
 pub struct Timer {
     start: Instant,
@@ -209,6 +236,10 @@ mod tests {
         assert!(config.enable_caching);
         assert_eq!(config.cache_size_mb, 100);
         assert_eq!(config.cache_ttl_seconds, 300);
+        assert!(
+            config.package_cache_ttl(RepositoryClass::Local)
+                < config.package_cache_ttl(RepositoryClass::Release)
+        );
         assert!(!config.enable_profiling);
         assert_eq!(config.max_metrics_history, 1000);
     }
@@ -2,9 +2,10 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, oneshot, RwLock};
 
 /// A profiling session that tracks nested operations.
 #[derive(Debug)]
@@ -58,11 +59,7 @@ impl Drop for ProfilerGuard {
             session_id: self.session_id,
         };
 
-        // Record the profile entry asynchronously
-        let profiler = self.profiler.clone();
-        tokio::spawn(async move {
-            profiler.record_entry(profile_entry).await;
-        });
+        self.profiler.record_entry(profile_entry);
     }
 }
 
@@ -175,23 +172,131 @@ impl CallTreeNode {
     /// Find the most expensive child operations.
     pub fn most_expensive_children(&self, limit: usize) -> Vec<&CallTreeNode> {
         let mut children: Vec<&CallTreeNode> = self.children.iter().collect();
-        children.sort_by(|a, b| b.duration_ms.cmp(&a.duration_ms));
+        children.sort_by_key(|c| std::cmp::Reverse(c.duration_ms));
         children.truncate(limit);
         children
     }
 }
 
-/// Main profiler that manages profiling sessions.
+/// Messages processed by the profiler's recording task.
 #[derive(Debug)]
-pub struct Profiler {
+enum ProfilerMessage {
+    /// Record a finished operation
+    Record(ProfileEntry),
+    /// Complete a session and acknowledge once it is stored
+    Complete {
+        root_session_id: u64,
+        root_operation: String,
+        done: oneshot::Sender<()>,
+    },
+    /// Acknowledge once all previously sent messages are processed
+    Flush(oneshot::Sender<()>),
+}
+
+/// Storage shared between the profiler handles and the recording task.
+#[derive(Debug, Clone)]
+struct ProfilerStore {
     /// Active profiling sessions
     sessions: Arc<RwLock<HashMap<u64, Vec<ProfileEntry>>>>,
     /// Completed sessions
     completed_sessions: Arc<RwLock<Vec<ProfileSession>>>,
-    /// Next session ID
-    next_id: Arc<RwLock<u64>>,
     /// Maximum number of completed sessions to keep
     max_sessions: usize,
+}
+
+impl ProfilerStore {
+    /// Process messages until every profiler handle has been dropped.
+    async fn run(self, mut receiver: mpsc::UnboundedReceiver<ProfilerMessage>) {
+        while let Some(message) = receiver.recv().await {
+            match message {
+                ProfilerMessage::Record(entry) => self.record_entry(entry).await,
+                ProfilerMessage::Complete {
+                    root_session_id,
+                    root_operation,
+                    done,
+                } => {
+                    self.complete_session(root_session_id, root_operation).await;
+                    let _ = done.send(());
+                }
+                ProfilerMessage::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+
+    /// Record a profile entry.
+    async fn record_entry(&self, entry: ProfileEntry) {
+        let mut sessions = self.sessions.write().await;
+        let session_entries = sessions.entry(entry.session_id).or_insert_with(Vec::new);
+        session_entries.push(entry);
+    }
+
+    /// Move a session and all of its nested operations to the completed list.
+    async fn complete_session(&self, root_session_id: u64, root_operation: String) {
+        let mut sessions = self.sessions.write().await;
+
+        // Collect the root entry together with all of its nested operations
+        let mut session_ids = vec![root_session_id];
+        let mut index = 0;
+        while index < session_ids.len() {
+            let parent_id = session_ids[index];
+            session_ids.extend(
+                sessions
+                    .values()
+                    .flatten()
+                    .filter(|e| e.parent_id == Some(parent_id))
+                    .map(|e| e.session_id),
+            );
+            index += 1;
+        }
+
+        let entries: Vec<ProfileEntry> = session_ids
+            .iter()
+            .filter_map(|id| sessions.remove(id))
+            .flatten()
+            .collect();
+
+        if entries.is_empty() {
+            return;
+        }
+
+        let total_duration_ms = entries.iter().map(|e| e.duration_ms).max().unwrap_or(0);
+        let start_timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let session = ProfileSession {
+            root_operation,
+            entries,
+            total_duration_ms,
+            start_timestamp,
+        };
+
+        let mut completed = self.completed_sessions.write().await;
+        completed.push(session);
+
+        // Keep only the most recent sessions
+        while completed.len() > self.max_sessions {
+            completed.remove(0);
+        }
+    }
+}
+
+/// Main profiler that manages profiling sessions.
+///
+/// Finished operations are sent over a channel to a single background task,
+/// so dropping a [`ProfilerGuard`] never blocks or spawns. The profiler must
+/// be created inside a Tokio runtime for entries to be recorded.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    /// Storage for active and completed sessions
+    store: ProfilerStore,
+    /// Channel to the recording task
+    sender: mpsc::UnboundedSender<ProfilerMessage>,
+    /// Next session ID
+    next_id: Arc<AtomicU64>,
     /// Whether profiling is enabled
     enabled: bool,
 }
@@ -199,11 +304,26 @@ pub struct Profiler {
 impl Profiler {
     /// Create a new profiler.
     pub fn new(max_sessions: usize, enabled: bool) -> Self {
-        Self {
+        let store = ProfilerStore {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             completed_sessions: Arc::new(RwLock::new(Vec::new())),
-            next_id: Arc::new(RwLock::new(1)),
             max_sessions,
+        };
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(store.clone().run(receiver));
+            }
+            Err(_) => tracing::warn!(
+                "Profiler created outside a Tokio runtime; entries will not be recorded"
+            ),
+        }
+
+        Self {
+            store,
+            sender,
+            next_id: Arc::new(AtomicU64::new(1)),
             enabled,
         }
     }
@@ -223,71 +343,61 @@ impl Profiler {
 
     /// Get the next session ID.
     fn next_session_id(&self) -> u64 {
-        let mut next_id = futures::executor::block_on(self.next_id.write());
-        let id = *next_id;
-        *next_id += 1;
-        id
+        self.next_id.fetch_add(1, Ordering::Relaxed)
     }
 
-    /// Record a profile entry.
-    async fn record_entry(&self, entry: ProfileEntry) {
+    /// Queue a profile entry for recording.
+    fn record_entry(&self, entry: ProfileEntry) {
         if !self.enabled {
             return;
         }
 
-        let mut sessions = self.sessions.write().await;
-        let session_entries = sessions.entry(entry.session_id).or_insert_with(Vec::new);
-        session_entries.push(entry);
+        // The recording task only stops once every profiler handle is gone
+        let _ = self.sender.send(ProfilerMessage::Record(entry));
+    }
+
+    /// Wait until all previously queued entries have been recorded.
+    async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.sender.send(ProfilerMessage::Flush(done)).is_ok() {
+            let _ = wait.await;
+        }
     }
 
     /// Complete a profiling session.
+    ///
+    /// Entries of guards dropped before this call are always included.
     pub async fn complete_session(&self, root_session_id: u64, root_operation: String) {
         if !self.enabled {
             return;
         }
 
-        let mut sessions = self.sessions.write().await;
-        if let Some(entries) = sessions.remove(&root_session_id) {
-            if !entries.is_empty() {
-                let total_duration_ms = entries.iter().map(|e| e.duration_ms).max().unwrap_or(0);
-                let start_timestamp = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs();
-
-                let session = ProfileSession {
-                    root_operation,
-                    entries,
-                    total_duration_ms,
-                    start_timestamp,
-                };
-
-                let mut completed = self.completed_sessions.write().await;
-                completed.push(session);
-
-                // Keep only the most recent sessions
-                while completed.len() > self.max_sessions {
-                    completed.remove(0);
-                }
-            }
+        let (done, wait) = oneshot::channel();
+        let message = ProfilerMessage::Complete {
+            root_session_id,
+            root_operation,
+            done,
+        };
+        if self.sender.send(message).is_ok() {
+            let _ = wait.await;
         }
     }
 
     /// Get all completed sessions.
     pub async fn get_completed_sessions(&self) -> Vec<ProfileSession> {
-        let completed = self.completed_sessions.read().await;
+        let completed = self.store.completed_sessions.read().await;
         completed.clone()
     }
 
     /// Get the most recent session.
     pub async fn get_latest_session(&self) -> Option<ProfileSession> {
-        let completed = self.completed_sessions.read().await;
+        let completed = self.store.completed_sessions.read().await;
         completed.last().cloned()
     }
 
     /// Get sessions by operation name.
     pub async fn get_sessions_by_operation(&self, operation: &str) -> Vec<ProfileSession> {
-        let completed = self.completed_sessions.read().await;
+        let completed = self.store.completed_sessions.read().await;
         completed
             .iter()
             .filter(|s| s.root_operation == operation)
@@ -297,16 +407,20 @@ impl Profiler {
 
     /// Clear all sessions.
     pub async fn clear(&self) {
-        let mut sessions = self.sessions.write().await;
-        let mut completed = self.completed_sessions.write().await;
+        self.flush().await;
+
+        let mut sessions = self.store.sessions.write().await;
+        let mut completed = self.store.completed_sessions.write().await;
         sessions.clear();
         completed.clear();
     }
 
     /// Get profiling statistics.
     pub async fn get_stats(&self) -> ProfilerStats {
-        let sessions = self.sessions.read().await;
-        let completed = self.completed_sessions.read().await;
+        self.flush().await;
+
+        let sessions = self.store.sessions.read().await;
+        let completed = self.store.completed_sessions.read().await;
 
         let active_sessions = sessions.len();
         let completed_sessions = completed.len();
@@ -335,18 +449,6 @@ impl Profiler {
     }
 }
 
-impl Clone for Profiler {
-    fn clone(&self) -> Self {
-        Self {
-            sessions: self.sessions.clone(),
-            completed_sessions: self.completed_sessions.clone(),
-            next_id: self.next_id.clone(),
-            max_sessions: self.max_sessions,
-            enabled: self.enabled,
-        }
-    }
-}
-
 /// Profiler statistics.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProfilerStats {
@@ -411,6 +513,44 @@ mod tests {
         assert!(stats.total_entries >= 2);
     }
 
+    #[tokio::test]
+    async fn test_complete_session_includes_children() {
+        let profiler = Profiler::new(10, true);
+
+        let session_id = {
+            let parent_guard = profiler.profile("parent_operation").unwrap();
+            let _child_guard = parent_guard.child("child_operation");
+            parent_guard.session_id()
+        };
+
+        // Wait a bit for async recording to complete
+        sleep(Duration::from_millis(50)).await;
+
+        profiler
+            .complete_session(session_id, "parent_operation".to_string())
+            .await;
+
+        let session = profiler.get_latest_session().await.unwrap();
+        assert_eq!(session.entries.len(), 2);
+        assert_eq!(session.call_tree().children[0].name, "child_operation");
+    }
+
+    #[tokio::test]
+    async fn test_guard_dropped_outside_runtime() {
+        let profiler = Profiler::new(10, true);
+
+        // Dropping a guard on a plain thread must neither panic nor block
+        let thread_profiler = profiler.clone();
+        std::thread::spawn(move || {
+            let _guard = thread_profiler.profile("blocking_operation");
+        })
+        .join()
+        .unwrap();
+
+        let stats = profiler.get_stats().await;
+        assert_eq!(stats.total_entries, 1);
+    }
+
     #[test]
     fn test_call_tree_node() {
         let node = CallTreeNode {
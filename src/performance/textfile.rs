@@ -0,0 +1,110 @@
+//! Prometheus textfile exporter for daemon deployments.
+//!
+//! Long-lived servers without the `http` feature can still be monitored:
+//! metrics snapshots are written periodically to a `.prom` file read by the
+//! node_exporter textfile collector. Each snapshot is written to a temporary
+//! file next to the target and renamed over it, so the collector never reads
+//! a partial file.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use super::MetricsCollector;
+
+/// Periodically writes the metrics of a collector to a Prometheus textfile.
+pub struct TextfileExporter {
+    collector: Arc<MetricsCollector>,
+    path: PathBuf,
+    interval: Duration,
+}
+
+impl TextfileExporter {
+    /// Create an exporter writing `collector` to `path` every `interval`.
+    pub fn new(
+        collector: Arc<MetricsCollector>,
+        path: impl Into<PathBuf>,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            collector,
+            path: path.into(),
+            interval,
+        }
+    }
+
+    /// Get the path of the textfile.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write a snapshot of the metrics.
+    pub async fn export(&self) -> std::io::Result<()> {
+        let snapshot = self.collector.render_prometheus().await;
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || write_atomically(&path, &snapshot))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// Write snapshots in the background until the returned task is aborted.
+    ///
+    /// Failed writes are logged and retried at the next interval.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(self.run())
+    }
+
+    /// Write snapshots until the future is dropped.
+    ///
+    /// Failed writes are logged and retried at the next interval.
+    pub async fn run(self) {
+        let mut ticks = tokio::time::interval(self.interval);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = self.export().await {
+                tracing::warn!("Failed to write metrics to {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+/// Write `content` to a temporary file next to `path` and rename it over
+/// `path`.
+fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_textfile_export() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("rez_lsp.prom");
+        let collector = Arc::new(MetricsCollector::new(100));
+        collector
+            .record_duration("resolve", Duration::from_millis(3))
+            .await;
+        collector.record_error("resolve").await;
+
+        let exporter = TextfileExporter::new(collector.clone(), &path, Duration::from_secs(60));
+        exporter.export().await.unwrap();
+        let snapshot = std::fs::read_to_string(&path).unwrap();
+        assert!(snapshot.contains("rez_lsp_operation_errors_total{operation=\"resolve\"} 1"));
+
+        // Snapshots replace each other without leaving temporary files behind
+        collector.record_error("resolve").await;
+        exporter.export().await.unwrap();
+        let snapshot = std::fs::read_to_string(&path).unwrap();
+        assert!(snapshot.contains("rez_lsp_operation_errors_total{operation=\"resolve\"} 2"));
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,188 @@
+//! Export of profiler sessions in the Chrome trace-event format.
+//!
+//! The output can be loaded in `chrome://tracing` or Perfetto to visualize
+//! where time is spent. Each completed session is shown as its own track.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::profiler::ProfileSession;
+use crate::core::Result;
+
+/// Process ID used for all exported events.
+const TRACE_PID: u32 = 1;
+
+/// A single Chrome trace event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEvent {
+    /// Event name
+    pub name: String,
+    /// Event category
+    pub cat: String,
+    /// Event phase (`X` for complete events, `M` for metadata)
+    pub ph: String,
+    /// Timestamp in microseconds
+    pub ts: u64,
+    /// Duration in microseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dur: Option<u64>,
+    /// Process ID
+    pub pid: u32,
+    /// Thread ID (one per profiling session)
+    pub tid: u64,
+    /// Additional event arguments
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty", default)]
+    pub args: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A Chrome trace document in the JSON object format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChromeTrace {
+    /// All trace events
+    pub trace_events: Vec<TraceEvent>,
+    /// Unit used by the viewer to display timestamps
+    pub display_time_unit: String,
+}
+
+impl ChromeTrace {
+    /// Build a trace from completed profiling sessions.
+    pub fn from_sessions(sessions: &[ProfileSession]) -> Self {
+        let mut trace_events = Vec::new();
+
+        for (index, session) in sessions.iter().enumerate() {
+            let tid = index as u64 + 1;
+            let session_start_us = session.start_timestamp * 1_000_000;
+
+            let mut args = serde_json::Map::new();
+            args.insert(
+                "name".to_string(),
+                serde_json::Value::String(session.root_operation.clone()),
+            );
+            trace_events.push(TraceEvent {
+                name: "thread_name".to_string(),
+                cat: "__metadata".to_string(),
+                ph: "M".to_string(),
+                ts: 0,
+                dur: None,
+                pid: TRACE_PID,
+                tid,
+                args,
+            });
+
+            // Entries only carry monotonic start times, so place them relative
+            // to the earliest entry of the session
+            let Some(origin) = session.entries.iter().map(|e| e.start_time).min() else {
+                continue;
+            };
+
+            let mut entries: Vec<_> = session.entries.iter().collect();
+            entries.sort_by_key(|e| e.start_time);
+
+            for entry in entries {
+                let offset_us = entry.start_time.duration_since(origin).as_micros() as u64;
+                trace_events.push(TraceEvent {
+                    name: entry.name.clone(),
+                    cat: session.root_operation.clone(),
+                    ph: "X".to_string(),
+                    ts: session_start_us + offset_us,
+                    dur: Some(entry.duration_ms * 1000),
+                    pid: TRACE_PID,
+                    tid,
+                    args: serde_json::Map::new(),
+                });
+            }
+        }
+
+        Self {
+            trace_events,
+            display_time_unit: "ms".to_string(),
+        }
+    }
+
+    /// Serialize the trace to a JSON value.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    /// Write the trace as JSON to a file.
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer(std::io::BufWriter::new(file), self)
+            .map_err(|e| crate::core::Error::Other(format!("Failed to write trace: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::profiler::ProfileEntry;
+    use std::time::{Duration, Instant};
+
+    fn create_test_session() -> ProfileSession {
+        let origin = Instant::now();
+        ProfileSession {
+            root_operation: "initialize".to_string(),
+            entries: vec![
+                ProfileEntry {
+                    name: "scan_packages".to_string(),
+                    duration_ms: 20,
+                    start_time: origin + Duration::from_millis(5),
+                    parent_id: Some(1),
+                    session_id: 2,
+                },
+                ProfileEntry {
+                    name: "initialize".to_string(),
+                    duration_ms: 30,
+                    start_time: origin,
+                    parent_id: None,
+                    session_id: 1,
+                },
+            ],
+            total_duration_ms: 30,
+            start_timestamp: 10,
+        }
+    }
+
+    #[test]
+    fn test_chrome_trace_events() {
+        let trace = ChromeTrace::from_sessions(&[create_test_session()]);
+        assert_eq!(trace.trace_events.len(), 3);
+
+        let metadata = &trace.trace_events[0];
+        assert_eq!(metadata.ph, "M");
+        assert_eq!(metadata.args["name"], "initialize");
+
+        let root = &trace.trace_events[1];
+        assert_eq!(root.name, "initialize");
+        assert_eq!(root.ts, 10_000_000);
+        assert_eq!(root.dur, Some(30_000));
+
+        let child = &trace.trace_events[2];
+        assert_eq!(child.name, "scan_packages");
+        assert_eq!(child.ts, 10_005_000);
+        assert_eq!(child.tid, root.tid);
+    }
+
+    #[test]
+    fn test_chrome_trace_json_format() {
+        let json = ChromeTrace::from_sessions(&[create_test_session()]).to_json();
+        assert!(json["traceEvents"].is_array());
+        assert_eq!(json["displayTimeUnit"], "ms");
+        assert!(json["traceEvents"][0].get("dur").is_none());
+    }
+
+    #[test]
+    fn test_write_trace_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("trace.json");
+
+        ChromeTrace::from_sessions(&[create_test_session()])
+            .write_to_file(&path)
+            .unwrap();
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["traceEvents"].as_array().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,283 @@
+//! Metrics-driven sizing of the sub-caches of [`CacheManager`].
+//!
+//! The sub-caches share the entry budget derived from
+//! [`PerformanceConfig::cache_size_mb`]. Each tuning round compares the
+//! lookups since the previous round with the latency of the operation a
+//! cache serves: caches that are full yet miss often, or back slow
+//! operations, grow into the budget freed by caches that sit unused.
+//!
+//! [`CacheManager`]: super::CacheManager
+//! [`PerformanceConfig::cache_size_mb`]: super::PerformanceConfig::cache_size_mb
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::cache::hit_ratio;
+
+/// A sub-cache of the cache manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheKind {
+    /// Package discovery results
+    Package,
+    /// Validation results
+    Validation,
+    /// Completion results
+    Completion,
+}
+
+impl CacheKind {
+    /// All sub-caches.
+    pub const ALL: [CacheKind; 3] = [
+        CacheKind::Package,
+        CacheKind::Validation,
+        CacheKind::Completion,
+    ];
+
+    /// Get the name of the metrics operation the cache speeds up.
+    pub fn operation(self) -> &'static str {
+        match self {
+            CacheKind::Package => "hover",
+            CacheKind::Validation => "diagnostic",
+            CacheKind::Completion => "completion",
+        }
+    }
+}
+
+/// Thresholds of the tuning rounds.
+#[derive(Debug, Clone)]
+pub struct TuningPolicy {
+    /// Lookups needed in a round before a hit ratio is trusted
+    pub min_lookups: u64,
+    /// Hit ratio below which a full cache grows
+    pub low_hit_ratio: f64,
+    /// p95 latency in milliseconds above which a full cache grows
+    pub slow_p95_ms: u64,
+    /// Share of the size in use from which a cache counts as full
+    pub full_utilization: f64,
+    /// Share of the size in use below which a cache shrinks
+    pub idle_utilization: f64,
+    /// Share of the size added or removed per adjustment
+    pub step: f64,
+    /// Size no cache shrinks below
+    pub min_size: usize,
+}
+
+impl Default for TuningPolicy {
+    fn default() -> Self {
+        Self {
+            min_lookups: 20,
+            low_hit_ratio: 0.5,
+            slow_p95_ms: 50,
+            full_utilization: 0.9,
+            idle_utilization: 0.25,
+            step: 0.5,
+            min_size: 16,
+        }
+    }
+}
+
+/// Usage of a sub-cache during a tuning round.
+#[derive(Debug, Clone)]
+pub struct CacheUsage {
+    /// The sub-cache
+    pub cache: CacheKind,
+    /// Current maximum number of entries
+    pub max_size: usize,
+    /// Entries currently held
+    pub active_entries: usize,
+    /// Hits since the previous round
+    pub hits: u64,
+    /// Misses since the previous round
+    pub misses: u64,
+    /// p95 latency of the operation the cache serves, if measured
+    pub p95_ms: Option<u64>,
+}
+
+impl CacheUsage {
+    fn utilization(&self) -> f64 {
+        if self.max_size == 0 {
+            1.0
+        } else {
+            self.active_entries as f64 / self.max_size as f64
+        }
+    }
+}
+
+/// A size change made by a tuning round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheAdjustment {
+    /// The resized sub-cache
+    pub cache: CacheKind,
+    /// Maximum number of entries before the change
+    pub previous_size: usize,
+    /// Maximum number of entries after the change
+    pub new_size: usize,
+    /// Why the size changed
+    pub reason: String,
+    /// Time of the change in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+/// Sizes and recent adjustments of the sub-caches, reported by
+/// `rez/performanceReport`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheTuningReport {
+    /// Entry budget shared by the sub-caches
+    pub budget: usize,
+    /// Current maximum number of entries per sub-cache
+    pub sizes: BTreeMap<CacheKind, usize>,
+    /// Most recent adjustments, oldest first
+    pub adjustments: Vec<CacheAdjustment>,
+}
+
+/// Plan the size changes of a tuning round.
+///
+/// Idle caches shrink first; the budget they free, plus any budget not yet
+/// allocated, goes to the full caches that need to grow, slowest operation
+/// first.
+pub fn plan_adjustments(
+    usage: &[CacheUsage],
+    budget: usize,
+    policy: &TuningPolicy,
+) -> Vec<CacheAdjustment> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let adjustment = |usage: &CacheUsage, new_size: usize, reason: String| CacheAdjustment {
+        cache: usage.cache,
+        previous_size: usage.max_size,
+        new_size,
+        reason,
+        timestamp,
+    };
+
+    let mut adjustments = Vec::new();
+    let mut allocated: usize = usage.iter().map(|usage| usage.max_size).sum();
+
+    for usage in usage {
+        let lookups = usage.hits + usage.misses;
+        let reason = if lookups == 0 {
+            "unused since the last tuning round".to_string()
+        } else if usage.utilization() < policy.idle_utilization {
+            format!("only {:.0}% full", usage.utilization() * 100.0)
+        } else {
+            continue;
+        };
+        let shrunk = ((usage.max_size as f64) * (1.0 - policy.step)) as usize;
+        let new_size = shrunk.max(usage.active_entries).max(policy.min_size);
+        if new_size < usage.max_size {
+            allocated -= usage.max_size - new_size;
+            adjustments.push(adjustment(usage, new_size, reason));
+        }
+    }
+
+    let mut growing: Vec<(&CacheUsage, String)> = usage
+        .iter()
+        .filter(|usage| usage.hits + usage.misses >= policy.min_lookups)
+        .filter(|usage| usage.utilization() >= policy.full_utilization)
+        .filter_map(|usage| {
+            let ratio = hit_ratio(usage.hits, usage.misses);
+            let slow = usage.p95_ms.filter(|p95| *p95 >= policy.slow_p95_ms);
+            let reason = match slow {
+                Some(p95) => format!(
+                    "full with {:.0}% hits and {} p95 at {}ms",
+                    ratio * 100.0,
+                    usage.cache.operation(),
+                    p95
+                ),
+                None if ratio < policy.low_hit_ratio => {
+                    format!("full with {:.0}% hits", ratio * 100.0)
+                }
+                None => return None,
+            };
+            Some((usage, reason))
+        })
+        .collect();
+    growing.sort_by_key(|(usage, _)| std::cmp::Reverse(usage.p95_ms.unwrap_or(0)));
+
+    for (usage, reason) in growing {
+        let available = budget.saturating_sub(allocated);
+        let growth = (((usage.max_size as f64) * policy.step) as usize)
+            .max(1)
+            .min(available);
+        if growth > 0 {
+            allocated += growth;
+            adjustments.push(adjustment(usage, usage.max_size + growth, reason));
+        }
+    }
+
+    adjustments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(
+        cache: CacheKind,
+        max_size: usize,
+        active_entries: usize,
+        (hits, misses): (u64, u64),
+        p95_ms: Option<u64>,
+    ) -> CacheUsage {
+        CacheUsage {
+            cache,
+            max_size,
+            active_entries,
+            hits,
+            misses,
+            p95_ms,
+        }
+    }
+
+    #[test]
+    fn test_unused_cache_funds_missing_cache() {
+        let usage = [
+            usage(CacheKind::Package, 100, 100, (40, 60), Some(20)),
+            usage(CacheKind::Validation, 100, 5, (0, 0), None),
+            usage(CacheKind::Completion, 100, 60, (90, 10), Some(5)),
+        ];
+        let adjustments = plan_adjustments(&usage, 300, &TuningPolicy::default());
+
+        let sizes: Vec<(CacheKind, usize, usize)> = adjustments
+            .iter()
+            .map(|a| (a.cache, a.previous_size, a.new_size))
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![
+                (CacheKind::Validation, 100, 50),
+                (CacheKind::Package, 100, 150),
+            ]
+        );
+        assert_eq!(adjustments[1].reason, "full with 40% hits");
+    }
+
+    #[test]
+    fn test_growth_stays_within_budget() {
+        let usage = [
+            usage(CacheKind::Package, 100, 100, (90, 10), Some(80)),
+            usage(CacheKind::Completion, 100, 95, (10, 90), Some(200)),
+        ];
+        let adjustments = plan_adjustments(&usage, 230, &TuningPolicy::default());
+
+        // The slowest operation gets the remaining budget first
+        assert_eq!(adjustments.len(), 1);
+        assert_eq!(adjustments[0].cache, CacheKind::Completion);
+        assert_eq!(adjustments[0].new_size, 130);
+        assert!(adjustments[0].reason.contains("completion p95 at 200ms"));
+    }
+
+    #[test]
+    fn test_healthy_caches_are_left_alone() {
+        let usage = [
+            usage(CacheKind::Package, 100, 95, (95, 5), Some(10)),
+            usage(CacheKind::Completion, 100, 50, (5, 5), Some(10)),
+        ];
+        assert!(plan_adjustments(&usage, 1000, &TuningPolicy::default()).is_empty());
+    }
+}
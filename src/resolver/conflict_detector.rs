@@ -1,14 +1,18 @@
 //! Conflict detection for dependency resolution.
 
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
 use std::collections::HashMap;
 use tracing::debug;
 
-use crate::core::{DependencyConflict, Package, Requirement, Version, VersionConstraint};
+use crate::core::{
+    DependencyConflict, Package, PackageIndex, Requirement, Version, VersionConstraint,
+};
 
 /// Detects conflicts in package requirements.
 pub struct ConflictDetector {
     /// Available packages indexed by name
-    packages: HashMap<String, Vec<Package>>,
+    packages: PackageIndex,
 }
 
 impl ConflictDetector {
@@ -20,7 +24,7 @@ impl ConflictDetector {
     }
 
     /// Set the available packages for conflict detection.
-    pub fn set_packages(&mut self, packages: HashMap<String, Vec<Package>>) {
+    pub fn set_packages(&mut self, packages: PackageIndex) {
         self.packages = packages;
     }
 
@@ -28,9 +32,9 @@ impl ConflictDetector {
     pub fn detect_conflicts(&self, requirements: &[Requirement]) -> Vec<DependencyConflict> {
         let mut conflicts = Vec::new();
 
-        // Group requirements by package name
+        // Group requirements by package name; ephemerals have no packages
         let mut package_requirements: HashMap<String, Vec<&Requirement>> = HashMap::new();
-        for req in requirements {
+        for req in requirements.iter().filter(|req| !req.is_ephemeral()) {
             package_requirements
                 .entry(req.name.clone())
                 .or_default()
@@ -79,23 +83,17 @@ impl ConflictDetector {
         // Check if any version can satisfy all requirements
         let satisfying_versions: Vec<&Package> = available_versions
             .iter()
+            .map(|pkg| &**pkg)
             .filter(|pkg| self.version_satisfies_all_requirements(&pkg.version, requirements))
             .collect();
 
         if satisfying_versions.is_empty() {
-            // No version satisfies all requirements - this is a conflict
+            // No version satisfies all requirements - report only the clashing ones
+            let clashing = minimal_conflicting_subset(available_versions, requirements);
             conflicts.push(DependencyConflict {
                 package: package_name.to_string(),
-                requirements: requirements.iter().map(|&r| r.clone()).collect(),
-                description: format!(
-                    "No version of '{}' satisfies all requirements: {}",
-                    package_name,
-                    requirements
-                        .iter()
-                        .map(|r| r.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ),
+                requirements: clashing.iter().map(|&r| r.clone()).collect(),
+                description: describe_conflict(package_name, &clashing, requirements.len()),
             });
             // If no version satisfies all requirements, don't check for other conflicts
             return conflicts;
@@ -107,6 +105,7 @@ impl ConflictDetector {
                 // This is a conflict requirement - check if it would exclude valid versions
                 let excluded_versions: Vec<&Package> = available_versions
                     .iter()
+                    .map(|pkg| &**pkg)
                     .filter(|pkg| req.constraint.satisfies(&pkg.version))
                     .collect();
 
@@ -133,20 +132,7 @@ impl ConflictDetector {
         version: &Version,
         requirements: &[&Requirement],
     ) -> bool {
-        for req in requirements {
-            if req.conflict {
-                // Conflict requirements should NOT be satisfied
-                if req.constraint.satisfies(version) {
-                    return false;
-                }
-            } else {
-                // Normal requirements should be satisfied
-                if !req.constraint.satisfies(version) {
-                    return false;
-                }
-            }
-        }
-        true
+        satisfies_all(version, requirements)
     }
 
     /// Check if two constraints are mutually exclusive.
@@ -193,6 +179,93 @@ impl ConflictDetector {
     }
 }
 
+/// Check if a version satisfies all requirements of one package family.
+fn satisfies_all(version: &Version, requirements: &[&Requirement]) -> bool {
+    requirements.iter().all(|req| {
+        if req.conflict {
+            // Conflict requirements should NOT be satisfied
+            !req.constraint.satisfies(version)
+        } else {
+            req.constraint.satisfies(version)
+        }
+    })
+}
+
+/// Reduce requirements that no available version satisfies to a minimal
+/// conflicting subset.
+///
+/// Uses delta debugging: chunks are dropped while the remaining requirements
+/// stay unsatisfiable, refining the chunk size down to single requirements.
+/// The result is 1-minimal, so dropping any one of the returned requirements
+/// leaves a satisfiable set. Requirements that can be satisfied together are
+/// returned unchanged.
+pub fn minimal_conflicting_subset<'a, P: Borrow<Package>>(
+    available_versions: &[P],
+    requirements: &[&'a Requirement],
+) -> Vec<&'a Requirement> {
+    let unsatisfiable = |subset: &[&Requirement]| {
+        !available_versions
+            .iter()
+            .any(|pkg| satisfies_all(&pkg.borrow().version, subset))
+    };
+
+    let mut current = requirements.to_vec();
+    if !unsatisfiable(&current) {
+        return current;
+    }
+
+    let mut granularity = 2;
+    while current.len() >= 2 {
+        let chunk_size = current.len().div_ceil(granularity);
+        let complement = (0..current.len()).step_by(chunk_size).find_map(|start| {
+            let end = (start + chunk_size).min(current.len());
+            let complement: Vec<&Requirement> = current[..start]
+                .iter()
+                .chain(&current[end..])
+                .copied()
+                .collect();
+            unsatisfiable(&complement).then_some(complement)
+        });
+
+        match complement {
+            Some(complement) => {
+                current = complement;
+                granularity = (granularity - 1).max(2);
+            }
+            None if granularity >= current.len() => break,
+            None => granularity = (granularity * 2).min(current.len()),
+        }
+    }
+
+    current
+}
+
+/// Describe a conflict between the clashing requirements of a package.
+pub(crate) fn describe_conflict(
+    package_name: &str,
+    clashing: &[&Requirement],
+    total: usize,
+) -> String {
+    let listed = clashing
+        .iter()
+        .map(|r| r.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let compatible = total.saturating_sub(clashing.len());
+
+    if compatible == 0 {
+        format!(
+            "No version of '{}' satisfies all requirements: {}",
+            package_name, listed
+        )
+    } else {
+        format!(
+            "No version of '{}' satisfies the conflicting requirements: {} ({} other requirement(s) are compatible)",
+            package_name, listed, compatible
+        )
+    }
+}
+
 impl Default for ConflictDetector {
     fn default() -> Self {
         Self::new()
@@ -215,9 +288,11 @@ pub struct ConflictAnalysis {
 }
 
 /// Severity levels for conflicts.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum ConflictSeverity {
     /// No conflicts
+    #[serde(rename = "ok")]
     None,
     /// Minor conflicts (1 conflict)
     Minor,
@@ -232,20 +307,18 @@ mod tests {
     use super::*;
     use crate::core::{Package, Version, VersionConstraint};
     use std::collections::HashMap;
-    use std::path::PathBuf;
-
-    fn create_test_package(name: &str, version: &str) -> Package {
-        Package {
-            name: name.to_string(),
-            version: Version::new(version),
-            description: Some(format!("Test package {}", name)),
-            authors: vec!["Test Author".to_string()],
-            requires: vec![],
-            tools: vec![],
-            variants: vec![],
-            path: PathBuf::from("/test"),
-            metadata: HashMap::new(),
-        }
+    use std::sync::Arc;
+
+    fn create_test_package(name: &str, version: &str) -> Arc<Package> {
+        Arc::new(
+            Package::builder()
+                .name(name)
+                .version(version)
+                .description(format!("Test package {}", name))
+                .author("Test Author")
+                .path("/test")
+                .build(),
+        )
     }
 
     #[test]
@@ -319,4 +392,57 @@ mod tests {
         assert_eq!(analysis.total_requirements, 2);
         assert_eq!(analysis.total_packages, 1);
     }
+
+    #[test]
+    fn test_minimal_conflicting_subset() {
+        let versions: Vec<Arc<Package>> = ["3.7.0", "3.9.0", "3.11.0"]
+            .iter()
+            .map(|v| create_test_package("python", v))
+            .collect();
+        let requirements: Vec<Requirement> = [
+            "python-3+",
+            "python<4",
+            "python-3.9+",
+            "python-3.7+",
+            "!python==3.11.0",
+            "python<3.8",
+            "python",
+        ]
+        .iter()
+        .map(|r| Requirement::parse(r).unwrap())
+        .collect();
+        let refs: Vec<&Requirement> = requirements.iter().collect();
+
+        let clashing = minimal_conflicting_subset(&versions, &refs);
+        let clashing: Vec<String> = clashing.iter().map(|r| r.to_string()).collect();
+        assert_eq!(clashing, vec!["python-3.9+", "python<3.8"]);
+    }
+
+    #[test]
+    fn test_conflict_reports_only_clashing_requirements() {
+        let mut detector = ConflictDetector::new();
+        let mut packages = HashMap::new();
+        packages.insert(
+            "python".to_string(),
+            vec![
+                create_test_package("python", "3.7.0"),
+                create_test_package("python", "3.9.0"),
+            ],
+        );
+        detector.set_packages(packages);
+
+        let requirements = vec![
+            Requirement::parse("python-3+").unwrap(),
+            Requirement::parse("python-3.9+").unwrap(),
+            Requirement::parse("python<4").unwrap(),
+            Requirement::parse("python<3.8").unwrap(),
+        ];
+
+        let conflicts = detector.detect_conflicts(&requirements);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].requirements.len(), 2);
+        assert!(conflicts[0]
+            .description
+            .contains("python-3.9+, python<3.8 (2 other requirement(s) are compatible)"));
+    }
 }
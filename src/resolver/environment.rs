@@ -0,0 +1,307 @@
+//! Static preview of the environment a resolved context produces.
+//!
+//! Instead of running rex, the `commands` of every resolved package are
+//! parsed into environment operations and applied in dependency order. Values
+//! that are Python expressions rather than string literals are kept verbatim.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::core::{Package, ResolvedContext};
+use crate::parser::commands::{find_commands, EnvAction, EnvOperation};
+use crate::parser::encoding::read_source;
+
+/// Separator used when appending to or prepending path-like variables.
+const PATH_SEPARATOR: &str = if cfg!(windows) { ";" } else { ":" };
+
+/// The environment a resolved context would produce.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentPreview {
+    /// Resolved packages as `name-version`, in the order commands were applied
+    pub packages: Vec<String>,
+    /// Final value of every variable set by the context
+    pub variables: BTreeMap<String, String>,
+    /// Shell aliases defined by the context
+    pub aliases: BTreeMap<String, String>,
+    /// Packages whose package.py could not be read
+    pub skipped: Vec<String>,
+}
+
+impl EnvironmentPreview {
+    /// Build a preview by reading the package.py of each resolved package.
+    pub fn from_context(context: &ResolvedContext) -> Self {
+        Self::from_context_with(context, |package| {
+            read_source(&package.path.join("package.py")).ok()
+        })
+    }
+
+    /// Build a preview, loading package definitions through `load`.
+    pub fn from_context_with(
+        context: &ResolvedContext,
+        load: impl Fn(&Package) -> Option<String>,
+    ) -> Self {
+        let mut preview = Self::default();
+        let ordered = dependency_order(&context.packages);
+
+        preview.packages = ordered
+            .iter()
+            .map(|package| format!("{}-{}", package.name, package.version))
+            .collect();
+        preview
+            .variables
+            .insert("REZ_USED_RESOLVE".to_string(), preview.packages.join(" "));
+
+        for package in ordered {
+            let prefix = format!("REZ_{}", package.name.to_uppercase());
+            preview.variables.insert(
+                format!("{}_ROOT", prefix),
+                package.path.display().to_string(),
+            );
+            preview
+                .variables
+                .insert(format!("{}_VERSION", prefix), package.version.to_string());
+
+            let Some(content) = load(package) else {
+                preview
+                    .skipped
+                    .push(format!("{}-{}", package.name, package.version));
+                continue;
+            };
+
+            if let Some(block) = find_commands(&content) {
+                for operation in &block.operations {
+                    preview.apply(package, operation);
+                }
+            }
+        }
+
+        preview
+    }
+
+    /// Apply a single environment operation of a package.
+    fn apply(&mut self, package: &Package, operation: &EnvOperation) {
+        let value = operation
+            .value
+            .as_deref()
+            .map(|value| self.expand(package, value))
+            .unwrap_or_default();
+        let variable = operation.variable.clone();
+
+        match operation.action {
+            EnvAction::Set => {
+                self.variables.insert(variable, value);
+            }
+            EnvAction::Append => {
+                let entry = self.variables.entry(variable).or_default();
+                if !entry.is_empty() {
+                    entry.push_str(PATH_SEPARATOR);
+                }
+                entry.push_str(&value);
+            }
+            EnvAction::Prepend => {
+                let entry = self.variables.entry(variable).or_default();
+                if !entry.is_empty() {
+                    entry.insert_str(0, PATH_SEPARATOR);
+                }
+                entry.insert_str(0, &value);
+            }
+            EnvAction::Unset => {
+                self.variables.remove(&variable);
+            }
+            EnvAction::Alias => {
+                self.aliases.insert(variable, value);
+            }
+        }
+    }
+
+    /// Expand package placeholders and references to known variables.
+    fn expand(&self, package: &Package, value: &str) -> String {
+        let root = package.path.display().to_string();
+        let version = package.version.to_string();
+        let mut expanded = value
+            .replace("{root}", &root)
+            .replace("{this.root}", &root)
+            .replace("{version}", &version)
+            .replace("{this.version}", &version)
+            .replace("{name}", &package.name)
+            .replace("{this.name}", &package.name);
+
+        for (name, current) in &self.variables {
+            expanded = expanded
+                .replace(&format!("${{{}}}", name), current)
+                .replace(&format!("{{env.{}}}", name), current);
+            // Avoid replacing prefixes of longer names, e.g. $PATH in $PATHEXT
+            let reference = format!("${}", name);
+            let mut result = String::with_capacity(expanded.len());
+            let mut rest = expanded.as_str();
+            while let Some(pos) = rest.find(&reference) {
+                let after = &rest[pos + reference.len()..];
+                result.push_str(&rest[..pos]);
+                if after.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+                    result.push_str(&reference);
+                } else {
+                    result.push_str(current);
+                }
+                rest = after;
+            }
+            result.push_str(rest);
+            expanded = result;
+        }
+
+        expanded
+    }
+}
+
+/// Order the resolved packages by the precedence of their `PATH` entries,
+/// highest first.
+///
+/// Commands apply in dependency order, so a package prepending to `PATH`
+/// shadows the packages applied before it and one appending yields to them.
+/// Packages that cannot be loaded or whose commands leave `PATH` alone are
+/// assumed to prepend, as rez packages conventionally do.
+pub fn path_precedence(
+    context: &ResolvedContext,
+    load: impl Fn(&Package) -> Option<String>,
+) -> Vec<&Package> {
+    let mut precedence = VecDeque::new();
+    for package in dependency_order(&context.packages) {
+        let action = load(package)
+            .and_then(|content| find_commands(&content))
+            .and_then(|block| {
+                block
+                    .operations
+                    .iter()
+                    .rev()
+                    .find(|operation| operation.variable == "PATH")
+                    .map(|operation| operation.action)
+            });
+        match action {
+            Some(EnvAction::Append) => precedence.push_back(package),
+            Some(EnvAction::Set) => {
+                precedence.clear();
+                precedence.push_front(package);
+            }
+            Some(EnvAction::Unset) => precedence.clear(),
+            _ => precedence.push_front(package),
+        }
+    }
+    precedence.into()
+}
+
+/// Order packages so that every package comes after its dependencies.
+fn dependency_order(packages: &[Package]) -> Vec<&Package> {
+    let by_name: HashMap<&str, &Package> = packages
+        .iter()
+        .map(|package| (&*package.name, package))
+        .collect();
+    let mut names: Vec<&str> = by_name.keys().copied().collect();
+    names.sort_unstable();
+
+    let mut ordered = Vec::new();
+    let mut visited = HashSet::new();
+    for name in names {
+        visit(name, &by_name, &mut visited, &mut ordered);
+    }
+    ordered
+}
+
+/// Depth-first visit appending `name` after its dependencies.
+fn visit<'a>(
+    name: &str,
+    by_name: &HashMap<&str, &'a Package>,
+    visited: &mut HashSet<String>,
+    ordered: &mut Vec<&'a Package>,
+) {
+    let Some(package) = by_name.get(name) else {
+        return;
+    };
+    if !visited.insert(name.to_string()) {
+        return;
+    }
+
+    for requirement in &package.requires {
+        if !requirement.conflict {
+            visit(&requirement.name, by_name, visited, ordered);
+        }
+    }
+    ordered.push(package);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Requirement;
+
+    fn create_test_package(name: &str, version: &str, requires: &[&str]) -> Package {
+        Package::builder()
+            .name(name)
+            .version(version)
+            .requires(requires.iter().map(|r| Requirement::parse(r).unwrap()))
+            .path(format!("/packages/{}/{}", name, version))
+            .build()
+    }
+
+    fn create_test_context(packages: Vec<Package>) -> ResolvedContext {
+        ResolvedContext::builder().packages(packages).build()
+    }
+
+    #[test]
+    fn test_preview_applies_commands_in_dependency_order() {
+        let context = create_test_context(vec![
+            create_test_package("maya_tools", "1.2", &["python"]),
+            create_test_package("python", "3.9", &[]),
+        ]);
+
+        let preview = EnvironmentPreview::from_context_with(&context, |package| {
+            Some(match &*package.name {
+                "python" => "def commands():\n    env.PATH.append('{root}/bin')\n".to_string(),
+                _ => "commands = \"\"\"\nexport PATH=$PATH:{root}/bin\nexport TOOLS_HOME=${REZ_PYTHON_ROOT}/tools\nalias mt {root}/bin/mt\n\"\"\"\n".to_string(),
+            })
+        });
+
+        assert_eq!(preview.packages, vec!["python-3.9", "maya_tools-1.2"]);
+        assert_eq!(
+            preview.variables["PATH"],
+            format!(
+                "/packages/python/3.9/bin{}/packages/maya_tools/1.2/bin",
+                PATH_SEPARATOR
+            )
+        );
+        assert_eq!(
+            preview.variables["TOOLS_HOME"],
+            "/packages/python/3.9/tools"
+        );
+        assert_eq!(
+            preview.variables["REZ_USED_RESOLVE"],
+            "python-3.9 maya_tools-1.2"
+        );
+        assert_eq!(preview.aliases["mt"], "/packages/maya_tools/1.2/bin/mt");
+    }
+
+    #[test]
+    fn test_path_precedence() {
+        let context = create_test_context(vec![
+            create_test_package("maya", "2024", &["python"]),
+            create_test_package("python", "3.9", &[]),
+            create_test_package("legacy", "1.0", &["maya"]),
+        ]);
+
+        let precedence = path_precedence(&context, |package| match &*package.name {
+            "legacy" => Some("def commands():\n    env.PATH.append('{root}/bin')\n".to_string()),
+            "maya" => Some("def commands():\n    env.PATH.prepend('{root}/bin')\n".to_string()),
+            _ => None,
+        });
+        let names: Vec<_> = precedence.iter().map(|p| &*p.name).collect();
+        assert_eq!(names, vec!["maya", "python", "legacy"]);
+    }
+
+    #[test]
+    fn test_preview_reports_unreadable_packages() {
+        let context = create_test_context(vec![create_test_package("ghost", "1.0", &[])]);
+
+        let preview = EnvironmentPreview::from_context_with(&context, |_| None);
+        assert_eq!(preview.skipped, vec!["ghost-1.0"]);
+        assert_eq!(preview.variables["REZ_GHOST_ROOT"], "/packages/ghost/1.0");
+    }
+}
@@ -1,12 +1,52 @@
 //! Dependency resolution implementation for Rez.
 
 mod conflict_detector;
+mod environment;
 mod resolver_impl;
+mod rez_binary;
 
-pub use conflict_detector::ConflictDetector;
-pub use resolver_impl::DependencyResolverImpl;
+pub use conflict_detector::{
+    minimal_conflicting_subset, ConflictAnalysis, ConflictDetector, ConflictSeverity,
+};
+pub use environment::{path_precedence, EnvironmentPreview};
+pub use resolver_impl::{DependencyResolverImpl, VariantLimits};
+pub use rez_binary::RezBinaryResolver;
 
-use crate::core::{DependencyResolver, Requirement, ResolvedContext, Result};
+use crate::config::{Config, ResolverBackend};
+use crate::core::{DependencyResolver, PackageIndex, Requirement, ResolvedContext, Result};
+
+/// Create the resolver selected by [`Config::resolver_backend`] over the
+/// given package index.
+///
+/// [`Config::resolve_strategy`] and [`Config::preferred_versions`] only
+/// apply to the native resolver; `rez-env` orders versions itself.
+pub fn create_resolver(config: &Config, packages: PackageIndex) -> Box<dyn DependencyResolver> {
+    match config.resolver_backend {
+        ResolverBackend::Native => Box::new(native_resolver(config, packages)),
+        ResolverBackend::RezBinary => {
+            let mut resolver = RezBinaryResolver::new(&config.rez_env_path);
+            resolver.set_packages(packages);
+            resolver.set_aliases(config.package_aliases.clone());
+            Box::new(resolver)
+        }
+    }
+}
+
+/// Create the native resolver over the given package index, whatever the
+/// configured backend.
+///
+/// Used where resolving must stay in-process, e.g. on every validation.
+pub fn native_resolver(config: &Config, packages: PackageIndex) -> DependencyResolverImpl {
+    let mut resolver = DependencyResolverImpl::new();
+    resolver.set_packages(packages);
+    resolver.set_aliases(config.package_aliases.clone());
+    resolver.set_variant_limits(VariantLimits {
+        max_variants: config.max_variants,
+        max_fanout: config.max_variant_fanout,
+    });
+    resolver.set_strategy(config.resolve_strategy, config.preferred_versions.clone());
+    resolver
+}
 
 /// Resolve a list of package requirements.
 pub async fn resolve_requirements(requirements: &[Requirement]) -> Result<ResolvedContext> {
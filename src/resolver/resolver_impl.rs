@@ -4,17 +4,62 @@ use async_trait::async_trait;
 use std::collections::{HashMap, HashSet};
 use tracing::{debug, info};
 
+use super::conflict_detector::{describe_conflict, minimal_conflicting_subset};
+
+use crate::config::ResolveStrategy;
 use crate::core::{
-    ContextMetadata, DependencyResolver, Package, PlatformInfo, Requirement, ResolutionStats,
-    ResolvedContext, ResolverError, Result, Version, VersionConstraint,
+    DependencyResolver, Error, Package, PackageIndex, Requirement, ResolutionStats,
+    ResolvedContext, ResolverError, Result, Version, VersionConstraint, VersionOrder,
 };
 
+/// Bounds on the variants tried while resolving.
+///
+/// Every package with variants multiplies the combinations a failing resolve
+/// backtracks through, so both the total and the per-package count are
+/// capped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VariantLimits {
+    /// Variants tried in one resolve before giving up
+    pub max_variants: usize,
+    /// Variants of a single package tried, in order
+    pub max_fanout: usize,
+}
+
+impl Default for VariantLimits {
+    fn default() -> Self {
+        Self {
+            max_variants: 1000,
+            max_fanout: 16,
+        }
+    }
+}
+
+/// Families rez provides implicitly from the host, satisfied when they are
+/// not indexed as packages.
+const IMPLICIT_FAMILIES: [&str; 3] = ["platform", "arch", "os"];
+
+/// Variant expansion of a resolve in progress.
+#[derive(Debug, Default)]
+struct VariantExpansion {
+    considered: usize,
+    skipped: usize,
+    largest_fanout: usize,
+}
+
 /// Implementation of the dependency resolver.
 pub struct DependencyResolverImpl {
     /// Available packages indexed by name
-    packages: HashMap<String, Vec<Package>>,
+    packages: PackageIndex,
     /// Resolution cache for performance
     resolution_cache: HashMap<Vec<Requirement>, Option<ResolvedContext>>,
+    /// Renamed package families, mapping old names to their canonical name
+    aliases: HashMap<String, String>,
+    /// Bounds on the variants tried
+    variant_limits: VariantLimits,
+    /// Order in which satisfying versions are picked
+    strategy: ResolveStrategy,
+    /// Preferred version ranges of families
+    preferred_versions: HashMap<String, VersionConstraint>,
 }
 
 impl DependencyResolverImpl {
@@ -23,33 +68,62 @@ impl DependencyResolverImpl {
         Self {
             packages: HashMap::new(),
             resolution_cache: HashMap::new(),
+            aliases: HashMap::new(),
+            variant_limits: VariantLimits::default(),
+            strategy: ResolveStrategy::Latest,
+            preferred_versions: HashMap::new(),
         }
     }
 
     /// Set the available packages for resolution.
-    pub fn set_packages(&mut self, packages: HashMap<String, Vec<Package>>) {
+    pub fn set_packages(&mut self, packages: PackageIndex) {
         self.packages = packages;
         self.resolution_cache.clear(); // Clear cache when packages change
     }
 
-    /// Find the best version of a package that satisfies the constraint.
-    fn find_best_version(&self, name: &str, constraint: &VersionConstraint) -> Option<&Package> {
-        let versions = self.packages.get(name)?;
+    /// Set the package family aliases, mapping old names to canonical names.
+    pub fn set_aliases(&mut self, aliases: HashMap<String, String>) {
+        self.aliases = aliases;
+        self.resolution_cache.clear();
+    }
 
-        // Filter versions that satisfy the constraint
-        let mut candidates: Vec<&Package> = versions
-            .iter()
-            .filter(|pkg| constraint.satisfies(&pkg.version))
-            .collect();
+    /// Set the bounds on the variants tried while resolving.
+    pub fn set_variant_limits(&mut self, limits: VariantLimits) {
+        self.variant_limits = limits;
+        self.resolution_cache.clear();
+    }
 
-        if candidates.is_empty() {
-            return None;
-        }
+    /// Set the order in which satisfying versions are picked, and the
+    /// preferred version ranges of families picked first.
+    pub fn set_strategy(
+        &mut self,
+        strategy: ResolveStrategy,
+        preferred_versions: HashMap<String, VersionConstraint>,
+    ) {
+        self.strategy = strategy;
+        self.preferred_versions = preferred_versions;
+        self.resolution_cache.clear();
+    }
 
-        // Sort by version (highest first)
-        candidates.sort_by(|a, b| b.version.cmp(&a.version));
+    /// Rename requirements on aliased families to their canonical family.
+    fn canonicalize(&self, requirements: &[Requirement]) -> Vec<Requirement> {
+        canonicalize_requirements(requirements, &self.aliases)
+    }
 
-        candidates.first().copied()
+    /// Find the best version of a package that satisfies the constraint.
+    ///
+    /// A preferred version is picked first, otherwise the highest or lowest
+    /// satisfying version depending on the strategy.
+    fn find_best_version(&self, name: &str, constraint: &VersionConstraint) -> Option<&Package> {
+        let name = self.aliases.get(name).map(String::as_str).unwrap_or(name);
+        let versions = self.packages.get(name)?;
+
+        let candidates = constraint
+            .select(versions, None, VersionOrder::Descending)
+            .into_iter()
+            .map(|package| &**package);
+        self.strategy
+            .pick(candidates, self.preferred_versions.get(name))
     }
 
     /// Check for conflicts between requirements.
@@ -57,8 +131,8 @@ impl DependencyResolverImpl {
         let mut conflicts = Vec::new();
         let mut package_constraints: HashMap<String, Vec<&Requirement>> = HashMap::new();
 
-        // Group requirements by package name
-        for req in requirements {
+        // Group requirements by package name; ephemerals have no packages
+        for req in requirements.iter().filter(|req| !req.is_ephemeral()) {
             package_constraints
                 .entry(req.name.clone())
                 .or_default()
@@ -72,6 +146,7 @@ impl DependencyResolverImpl {
                 if let Some(versions) = self.packages.get(&package_name) {
                     let satisfying_versions: Vec<&Package> = versions
                         .iter()
+                        .map(|pkg| &**pkg)
                         .filter(|pkg| {
                             reqs.iter().all(|req| {
                                 if req.conflict {
@@ -84,14 +159,8 @@ impl DependencyResolverImpl {
                         .collect();
 
                     if satisfying_versions.is_empty() {
-                        conflicts.push(format!(
-                            "No version of '{}' satisfies all constraints: {}",
-                            package_name,
-                            reqs.iter()
-                                .map(|r| r.to_string())
-                                .collect::<Vec<_>>()
-                                .join(", ")
-                        ));
+                        let clashing = minimal_conflicting_subset(versions, &reqs);
+                        conflicts.push(describe_conflict(&package_name, &clashing, reqs.len()));
                     }
                 }
             }
@@ -123,10 +192,17 @@ impl DependencyResolverImpl {
         requirements: &[Requirement],
         resolved: &mut HashMap<String, Package>,
         visited: &mut HashSet<String>,
+        expansion: &mut VariantExpansion,
     ) -> Result<()> {
         for req in requirements {
-            if req.conflict {
-                // Skip conflict requirements in resolution
+            if req.conflict || req.is_ephemeral() {
+                // Skip conflict requirements and ephemerals in resolution
+                continue;
+            }
+            if IMPLICIT_FAMILIES.contains(&req.name.as_str())
+                && !self.packages.contains_key(&req.name)
+            {
+                debug!("Treating implicit requirement '{}' as satisfied", req.name);
                 continue;
             }
 
@@ -167,7 +243,15 @@ impl DependencyResolverImpl {
             visited.insert(req.name.clone());
 
             // Recursively resolve dependencies of this package
-            self.resolve_recursive(&package.requires, resolved, visited)?;
+            self.resolve_recursive(
+                &self.canonicalize(&package.requires),
+                resolved,
+                visited,
+                expansion,
+            )?;
+            if !package.variants.is_empty() {
+                self.resolve_variants(package, resolved, visited, expansion)?;
+            }
 
             // Remove from visited set
             visited.remove(&req.name);
@@ -178,6 +262,89 @@ impl DependencyResolverImpl {
 
         Ok(())
     }
+
+    /// Resolve the requirements of the first variant of `package` that
+    /// resolves, trying at most [`VariantLimits::max_fanout`] variants.
+    fn resolve_variants(
+        &self,
+        package: &Package,
+        resolved: &mut HashMap<String, Package>,
+        visited: &mut HashSet<String>,
+        expansion: &mut VariantExpansion,
+    ) -> Result<()> {
+        let fanout = package.variants.len().min(self.variant_limits.max_fanout);
+        expansion.skipped += package.variants.len() - fanout;
+        expansion.largest_fanout = expansion.largest_fanout.max(fanout);
+
+        let mut last_error = None;
+        for variant in &package.variants[..fanout] {
+            if expansion.considered >= self.variant_limits.max_variants {
+                return Err(ResolverError::VariantLimit(format!(
+                    "tried {} variants (limit {}) before reaching variant {} of '{}-{}'",
+                    expansion.considered,
+                    self.variant_limits.max_variants,
+                    variant.index,
+                    package.name,
+                    package.version
+                ))
+                .into());
+            }
+            expansion.considered += 1;
+
+            // Variants that fail are rolled back before trying the next one
+            let (resolved_before, visited_before) = (resolved.clone(), visited.clone());
+            match self.resolve_recursive(
+                &self.canonicalize(&variant.requires),
+                resolved,
+                visited,
+                expansion,
+            ) {
+                Ok(()) => {
+                    debug!(
+                        "Resolved '{}-{}' with variant {}",
+                        package.name, package.version, variant.index
+                    );
+                    return Ok(());
+                }
+                Err(e @ Error::Resolver(ResolverError::VariantLimit(_))) => return Err(e),
+                Err(e) => {
+                    *resolved = resolved_before;
+                    *visited = visited_before;
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(ResolverError::UnsatisfiableConstraint(format!(
+            "none of the {} variant(s) of '{}-{}' tried can be resolved{}",
+            fanout,
+            package.name,
+            package.version,
+            last_error
+                .map(|e| format!(" (last: {})", e))
+                .unwrap_or_default()
+        ))
+        .into())
+    }
+}
+
+/// Rename requirements on aliased families to their canonical family.
+pub(crate) fn canonicalize_requirements(
+    requirements: &[Requirement],
+    aliases: &HashMap<String, String>,
+) -> Vec<Requirement> {
+    requirements
+        .iter()
+        .map(|req| match aliases.get(&req.name) {
+            Some(canonical) => {
+                debug!("Resolving alias '{}' as '{}'", req.name, canonical);
+                let mut req = req.clone();
+                req.name = canonical.clone();
+                req
+            }
+            None => req.clone(),
+        })
+        .collect()
 }
 
 impl Default for DependencyResolverImpl {
@@ -194,6 +361,7 @@ impl DependencyResolver for DependencyResolverImpl {
             "Starting dependency resolution for {} requirements",
             requirements.len()
         );
+        let requirements = &self.canonicalize(requirements);
 
         // Check for obvious conflicts first
         let conflicts = self.check_conflicts(requirements);
@@ -203,37 +371,32 @@ impl DependencyResolver for DependencyResolverImpl {
 
         let mut resolved = HashMap::new();
         let mut visited = HashSet::new();
+        let mut expansion = VariantExpansion::default();
 
         // Resolve all requirements
-        self.resolve_recursive(requirements, &mut resolved, &mut visited)?;
+        self.resolve_recursive(requirements, &mut resolved, &mut visited, &mut expansion)?;
 
         let resolution_time = start_time.elapsed();
         let packages: Vec<Package> = resolved.into_values().collect();
         let packages_count = packages.len();
 
         info!(
-            "Dependency resolution completed: {} packages resolved in {:?}",
-            packages_count, resolution_time
+            "Dependency resolution completed: {} packages resolved in {:?} ({} variants tried)",
+            packages_count, resolution_time, expansion.considered
         );
 
-        Ok(ResolvedContext {
-            packages,
-            metadata: ContextMetadata {
-                timestamp: chrono::Utc::now(),
-                resolver_version: env!("CARGO_PKG_VERSION").to_string(),
-                platform: PlatformInfo {
-                    os: std::env::consts::OS.to_string(),
-                    arch: std::env::consts::ARCH.to_string(),
-                    platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
-                },
-                stats: ResolutionStats {
-                    packages_considered: self.packages.values().map(|v| v.len()).sum(),
-                    packages_resolved: packages_count,
-                    resolution_time_ms: resolution_time.as_millis() as u64,
-                    conflicts: conflicts.len(),
-                },
-            },
-        })
+        Ok(ResolvedContext::builder()
+            .packages(packages)
+            .stats(ResolutionStats {
+                packages_considered: self.packages.values().map(|v| v.len()).sum(),
+                packages_resolved: packages_count,
+                resolution_time_ms: resolution_time.as_millis() as u64,
+                conflicts: conflicts.len(),
+                variants_considered: expansion.considered,
+                variants_skipped: expansion.skipped,
+                largest_fanout: expansion.largest_fanout,
+            })
+            .build())
     }
 
     async fn can_resolve(&self, requirements: &[Requirement]) -> Result<bool> {
@@ -247,7 +410,7 @@ impl DependencyResolver for DependencyResolverImpl {
         &self,
         requirements: &[Requirement],
     ) -> Result<Vec<crate::core::DependencyConflict>> {
-        let conflicts = self.check_conflicts(requirements);
+        let conflicts = self.check_conflicts(&self.canonicalize(requirements));
         Ok(conflicts
             .into_iter()
             .map(|description| crate::core::DependencyConflict {
@@ -274,20 +437,19 @@ mod tests {
     use super::*;
     use crate::core::{Package, Requirement, Version, VersionConstraint};
     use std::collections::HashMap;
-    use std::path::PathBuf;
-
-    fn create_test_package(name: &str, version: &str, requires: Vec<Requirement>) -> Package {
-        Package {
-            name: name.to_string(),
-            version: Version::new(version),
-            description: Some(format!("Test package {}", name)),
-            authors: vec!["Test Author".to_string()],
-            requires,
-            tools: vec![],
-            variants: vec![],
-            path: PathBuf::from("/test"),
-            metadata: HashMap::new(),
-        }
+    use std::sync::Arc;
+
+    fn create_test_package(name: &str, version: &str, requires: Vec<Requirement>) -> Arc<Package> {
+        Arc::new(
+            Package::builder()
+                .name(name)
+                .version(version)
+                .description(format!("Test package {}", name))
+                .author("Test Author")
+                .requires(requires)
+                .path("/test")
+                .build(),
+        )
     }
 
     #[tokio::test]
@@ -310,7 +472,7 @@ mod tests {
         assert!(result.is_ok());
         let context = result.unwrap();
         assert_eq!(context.packages.len(), 1);
-        assert_eq!(context.packages[0].name, "python");
+        assert_eq!(&*context.packages[0].name, "python");
     }
 
     #[tokio::test]
@@ -343,6 +505,73 @@ mod tests {
         assert_eq!(context.packages[0].version, Version::new("3.9.0")); // Should pick the latest
     }
 
+    #[tokio::test]
+    async fn test_resolve_strategy() {
+        let mut resolver = DependencyResolverImpl::new();
+        let mut packages = HashMap::new();
+        packages.insert(
+            "python".to_string(),
+            vec![
+                create_test_package("python", "3.7.0", vec![]),
+                create_test_package("python", "3.8.0", vec![]),
+                create_test_package("python", "3.9.0", vec![]),
+            ],
+        );
+        resolver.set_packages(packages);
+        let requirements = vec![Requirement::new(
+            "python",
+            VersionConstraint::GreaterEqual(Version::new("3.8")),
+        )];
+        let resolved = |context: ResolvedContext| context.packages[0].version.to_string();
+
+        resolver.set_strategy(ResolveStrategy::Earliest, HashMap::new());
+        let context = resolver.resolve(&requirements).await.unwrap();
+        assert_eq!(resolved(context), "3.8.0");
+
+        // A preferred version is picked when it satisfies the requirement
+        let preferred = HashMap::from([(
+            "python".to_string(),
+            VersionConstraint::Family(Version::new("3.7")),
+        )]);
+        resolver.set_strategy(ResolveStrategy::Latest, preferred);
+        let context = resolver.resolve(&requirements).await.unwrap();
+        assert_eq!(resolved(context), "3.9.0");
+        let context = resolver
+            .resolve(&[Requirement::new("python", VersionConstraint::Any)])
+            .await
+            .unwrap();
+        assert_eq!(resolved(context), "3.7.0");
+    }
+
+    #[tokio::test]
+    async fn test_unversioned_package_resolution() {
+        let mut resolver = DependencyResolverImpl::new();
+
+        let mut packages = HashMap::new();
+        packages.insert(
+            "dev_tool".to_string(),
+            vec![create_test_package(
+                "dev_tool",
+                crate::core::NO_VERSION,
+                vec![],
+            )],
+        );
+        resolver.set_packages(packages);
+
+        // Unconstrained requests resolve to the unversioned package
+        let requirements = vec![Requirement::new("dev_tool", VersionConstraint::Any)];
+        let context = resolver.resolve(&requirements).await.unwrap();
+        assert_eq!(context.packages.len(), 1);
+        assert!(context.packages[0].version.is_unversioned());
+
+        // Versioned constraints cannot be satisfied by it
+        let requirements = vec![Requirement::new(
+            "dev_tool",
+            VersionConstraint::GreaterEqual(Version::new("1.0")),
+        )];
+        assert!(resolver.resolve(&requirements).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_conflict_detection() {
         let mut resolver = DependencyResolverImpl::new();
@@ -365,4 +594,163 @@ mod tests {
         let result = resolver.resolve(&requirements).await;
         assert!(result.is_err());
     }
+
+    fn with_variants(package: Arc<Package>, variants: &[&[&str]]) -> Arc<Package> {
+        let mut package = Arc::unwrap_or_clone(package);
+        package.variants = variants
+            .iter()
+            .enumerate()
+            .map(|(index, requires)| crate::core::Variant {
+                index,
+                requires: requires
+                    .iter()
+                    .map(|r| Requirement::parse(r).unwrap())
+                    .collect(),
+                metadata: HashMap::new(),
+            })
+            .collect();
+        Arc::new(package)
+    }
+
+    #[tokio::test]
+    async fn test_variant_resolution() {
+        let mut resolver = DependencyResolverImpl::new();
+
+        let mut packages = HashMap::new();
+        packages.insert(
+            "maya".to_string(),
+            vec![create_test_package("maya", "2025", vec![])],
+        );
+        packages.insert(
+            "mtoa".to_string(),
+            vec![with_variants(
+                create_test_package("mtoa", "5.4", vec![]),
+                &[&["maya-2024"], &["houdini"], &["maya-2025"]],
+            )],
+        );
+        resolver.set_packages(packages);
+
+        // The first variants cannot be resolved, the third one is picked
+        let requirements = vec![Requirement::parse("mtoa").unwrap()];
+        let context = resolver.resolve(&requirements).await.unwrap();
+        let mut names: Vec<_> = context.packages.iter().map(|p| &*p.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["maya", "mtoa"]);
+        let stats = &context.metadata.stats;
+        assert_eq!(
+            (
+                stats.variants_considered,
+                stats.variants_skipped,
+                stats.largest_fanout
+            ),
+            (3, 0, 3)
+        );
+
+        // Variants beyond the fan-out limit are never tried
+        resolver.set_variant_limits(VariantLimits {
+            max_variants: 1000,
+            max_fanout: 2,
+        });
+        let error = resolver.resolve(&requirements).await.unwrap_err();
+        assert_eq!(error.code(), "RZ403");
+        assert!(error
+            .to_string()
+            .contains("none of the 2 variant(s) of 'mtoa-5.4' tried can be resolved"));
+
+        // The total limit stops the resolve with its own error
+        resolver.set_variant_limits(VariantLimits {
+            max_variants: 1,
+            max_fanout: 16,
+        });
+        let error = resolver.resolve(&requirements).await.unwrap_err();
+        assert_eq!(error.code(), "RZ405");
+        assert!(error
+            .to_string()
+            .contains("tried 1 variants (limit 1) before reaching variant 1 of 'mtoa-5.4'"));
+    }
+
+    #[tokio::test]
+    async fn test_unindexed_implicit_packages_are_satisfied() {
+        let mut resolver = DependencyResolverImpl::new();
+
+        let mut packages = HashMap::new();
+        packages.insert(
+            "python".to_string(),
+            vec![create_test_package("python", "3.11", vec![])],
+        );
+        packages.insert(
+            "viewer".to_string(),
+            vec![with_variants(
+                create_test_package("viewer", "1.0", vec![]),
+                &[&[
+                    "platform-linux",
+                    "arch-x86_64",
+                    "os-Ubuntu-22.04",
+                    "python-3",
+                ]],
+            )],
+        );
+        resolver.set_packages(packages);
+
+        let requirements = vec![Requirement::parse("viewer").unwrap()];
+        let context = resolver.resolve(&requirements).await.unwrap();
+        let mut names: Vec<_> = context.packages.iter().map(|p| &*p.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["python", "viewer"]);
+    }
+
+    #[tokio::test]
+    async fn test_alias_resolution() {
+        let mut resolver = DependencyResolverImpl::new();
+
+        let mut packages = HashMap::new();
+        packages.insert(
+            "openusd".to_string(),
+            vec![create_test_package("openusd", "24.0", vec![])],
+        );
+        packages.insert(
+            "usd_view".to_string(),
+            vec![create_test_package(
+                "usd_view",
+                "1.0",
+                vec![Requirement::new("usd", VersionConstraint::Any)],
+            )],
+        );
+        resolver.set_packages(packages);
+        resolver.set_aliases(HashMap::from([("usd".to_string(), "openusd".to_string())]));
+
+        let requirements = vec![
+            Requirement::new("usd", VersionConstraint::Any),
+            Requirement::new("usd_view", VersionConstraint::Any),
+        ];
+        let context = resolver.resolve(&requirements).await.unwrap();
+        let mut names: Vec<_> = context.packages.iter().map(|p| &*p.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["openusd", "usd_view"]);
+    }
+
+    #[tokio::test]
+    async fn test_ephemerals_are_not_resolved() {
+        let mut resolver = DependencyResolverImpl::new();
+
+        let mut packages = HashMap::new();
+        packages.insert(
+            "maya".to_string(),
+            vec![create_test_package(
+                "maya",
+                "2024.0",
+                vec![Requirement::parse(".platform-linux").unwrap()],
+            )],
+        );
+        resolver.set_packages(packages);
+
+        let requirements = vec![
+            Requirement::parse("maya").unwrap(),
+            Requirement::parse(".foo.bar-1").unwrap(),
+            Requirement::parse(".foo.bar-1+").unwrap(),
+        ];
+        let context = resolver.resolve(&requirements).await.unwrap();
+        assert_eq!(context.packages.len(), 1);
+        assert_eq!(&*context.packages[0].name, "maya");
+    }
 }
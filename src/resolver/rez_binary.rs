@@ -0,0 +1,372 @@
+//! Resolver backend delegating to the `rez-env` binary.
+//!
+//! Runs `rez-env --output - <requests...>`, which writes the resolved context
+//! as rxt JSON to stdout instead of starting a shell, and adapts it into a
+//! [`ResolvedContext`]. Slower than the native solver but matches production
+//! rez exactly.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use super::resolver_impl::canonicalize_requirements;
+use crate::core::{
+    DependencyConflict, DependencyResolver, Error, Package, PackageIndex, Requirement,
+    ResolutionStats, ResolvedContext, ResolverError, Result, Version, VersionConstraint,
+};
+
+/// Default time a single `rez-env` invocation may take.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Interval between checks whether `rez-env` has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Resolver running the real `rez-env` binary.
+#[derive(Debug, Clone)]
+pub struct RezBinaryResolver {
+    /// Path of the `rez-env` binary
+    rez_env: PathBuf,
+    /// Maximum duration of a resolve
+    timeout: Duration,
+    /// Indexed packages used to fill in details missing from rez output
+    packages: PackageIndex,
+    /// Renamed package families, mapping old names to their canonical name
+    aliases: HashMap<String, String>,
+}
+
+impl RezBinaryResolver {
+    /// Create a resolver running the `rez-env` binary at `rez_env`.
+    pub fn new(rez_env: impl Into<PathBuf>) -> Self {
+        Self {
+            rez_env: rez_env.into(),
+            timeout: DEFAULT_TIMEOUT,
+            packages: HashMap::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Set the maximum duration of a resolve.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Set the indexed packages used to complete resolved package details.
+    pub fn set_packages(&mut self, packages: PackageIndex) {
+        self.packages = packages;
+    }
+
+    /// Set the package family aliases, mapping old names to canonical names.
+    pub fn set_aliases(&mut self, aliases: HashMap<String, String>) {
+        self.aliases = aliases;
+    }
+
+    /// Run `rez-env` and return its stdout.
+    async fn run_rez_env(&self, requirements: &[Requirement]) -> Result<String> {
+        let mut command = Command::new(&self.rez_env);
+        command
+            .arg("--output")
+            .arg("-")
+            .args(requirements.iter().map(|req| req.to_string()))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let rez_env = self.rez_env.clone();
+        let timeout = self.timeout;
+        tokio::task::spawn_blocking(move || run_with_timeout(command, &rez_env, timeout))
+            .await
+            .map_err(|e| Error::Other(format!("rez-env task failed: {}", e)))?
+    }
+}
+
+/// Run a command to completion, killing it once `timeout` has elapsed.
+pub(crate) fn run_with_timeout(
+    mut command: Command,
+    program: &Path,
+    timeout: Duration,
+) -> Result<String> {
+    let mut child = command
+        .spawn()
+        .map_err(|e| Error::Other(format!("Failed to start {}: {}", program.display(), e)))?;
+
+    let stdout = child.stdout.take().map(read_to_string_in_background);
+    let stderr = child.stderr.take().map(read_to_string_in_background);
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(Error::Other(format!(
+                "{} did not finish within {}s",
+                program.display(),
+                timeout.as_secs()
+            )));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let join = |handle: Option<std::thread::JoinHandle<String>>| {
+        handle.and_then(|h| h.join().ok()).unwrap_or_default()
+    };
+    let stdout = join(stdout);
+    let stderr = join(stderr);
+
+    // A failed resolve still writes the context, so prefer parsing stdout
+    if !status.success() && !stdout.trim_start().starts_with('{') {
+        return Err(Error::Other(format!(
+            "{} exited with {}: {}",
+            program.display(),
+            status,
+            stderr.trim()
+        )));
+    }
+
+    Ok(stdout)
+}
+
+/// Read a stream to the end on a separate thread so pipes never fill up.
+fn read_to_string_in_background(
+    mut stream: impl Read + Send + 'static,
+) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stream.read_to_string(&mut output);
+        output
+    })
+}
+
+/// The parts of a serialized rez context (`.rxt`) the adapter needs.
+#[derive(Debug, Deserialize)]
+struct RxtContext {
+    status: String,
+    #[serde(default)]
+    failure_description: Option<String>,
+    #[serde(default)]
+    resolved_packages: Option<Vec<RxtHandle>>,
+    #[serde(default)]
+    solve_time: Option<f64>,
+    #[serde(default)]
+    num_loaded_packages: Option<usize>,
+}
+
+/// Handle of a resolved variant.
+#[derive(Debug, Deserialize)]
+struct RxtHandle {
+    variables: RxtVariables,
+}
+
+/// Variables identifying a resolved variant.
+#[derive(Debug, Deserialize)]
+struct RxtVariables {
+    name: String,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    location: Option<String>,
+}
+
+/// Adapt rxt JSON written by `rez-env` into a resolved context.
+///
+/// Package details rez does not serialize (requirements, tools, ...) are
+/// taken from `packages` when the resolved version is indexed.
+fn context_from_rxt(json: &str, packages: &PackageIndex) -> Result<ResolvedContext> {
+    let rxt: RxtContext = serde_json::from_str(json)
+        .map_err(|e| Error::Other(format!("Invalid rez-env output: {}", e)))?;
+
+    if rxt.status != "solved" {
+        let description = rxt
+            .failure_description
+            .unwrap_or_else(|| format!("resolve {}", rxt.status));
+        return Err(ResolverError::Conflict(description).into());
+    }
+
+    let resolved: Vec<Package> = rxt
+        .resolved_packages
+        .unwrap_or_default()
+        .into_iter()
+        .map(|handle| {
+            let vars = handle.variables;
+            let version = match vars.version.filter(|version| !version.is_empty()) {
+                Some(version) => Version::new(version),
+                None => Version::unversioned(),
+            };
+            if let Some(indexed) = packages
+                .get(&vars.name)
+                .and_then(|versions| versions.iter().find(|p| p.version == version))
+            {
+                return (**indexed).clone();
+            }
+
+            let mut path = vars.location.map(PathBuf::from).unwrap_or_default();
+            path.push(&vars.name);
+            if !version.is_unversioned() {
+                path.push(version.to_string());
+            }
+            Package::builder()
+                .name(vars.name)
+                .version(version)
+                .path(path)
+                .build()
+        })
+        .collect();
+
+    let packages_resolved = resolved.len();
+    Ok(ResolvedContext::builder()
+        .packages(resolved)
+        .stats(ResolutionStats {
+            packages_considered: rxt.num_loaded_packages.unwrap_or(0),
+            packages_resolved,
+            resolution_time_ms: rxt.solve_time.map_or(0, |secs| (secs * 1000.0) as u64),
+            conflicts: 0,
+            ..Default::default()
+        })
+        .build())
+}
+
+#[async_trait]
+impl DependencyResolver for RezBinaryResolver {
+    async fn resolve(&self, requirements: &[Requirement]) -> Result<ResolvedContext> {
+        let requirements = canonicalize_requirements(requirements, &self.aliases);
+        info!(
+            "Resolving {} requirements with {}",
+            requirements.len(),
+            self.rez_env.display()
+        );
+
+        let output = self.run_rez_env(&requirements).await?;
+        let context = context_from_rxt(&output, &self.packages)?;
+        debug!("rez-env resolved {} packages", context.packages.len());
+        Ok(context)
+    }
+
+    async fn can_resolve(&self, requirements: &[Requirement]) -> Result<bool> {
+        match self.resolve(requirements).await {
+            Ok(_) => Ok(true),
+            Err(Error::Resolver(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn find_conflicts(
+        &self,
+        requirements: &[Requirement],
+    ) -> Result<Vec<DependencyConflict>> {
+        match self.resolve(requirements).await {
+            Ok(_) => Ok(Vec::new()),
+            Err(Error::Resolver(ResolverError::Conflict(description))) => {
+                Ok(vec![DependencyConflict {
+                    package: "unknown".to_string(),
+                    requirements: requirements.to_vec(),
+                    description,
+                }])
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn get_latest_version(
+        &self,
+        name: &str,
+        constraint: &VersionConstraint,
+    ) -> Result<Option<Version>> {
+        let requirement = Requirement::new(name, constraint.clone());
+        let context = match self.resolve(std::slice::from_ref(&requirement)).await {
+            Ok(context) => context,
+            Err(Error::Resolver(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let name = requirement.name;
+        Ok(context
+            .packages
+            .into_iter()
+            .find(|package| *package.name == name)
+            .map(|package| package.version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    const SOLVED: &str = r#"{
+        "serialize_version": "4.9",
+        "status": "solved",
+        "solve_time": 0.25,
+        "num_loaded_packages": 12,
+        "resolved_packages": [
+            {"key": "filesystem.variant", "variables": {
+                "repository_type": "filesystem", "location": "/packages",
+                "name": "python", "version": "3.9.0", "index": 0}},
+            {"key": "filesystem.variant", "variables": {
+                "repository_type": "filesystem", "location": "/packages",
+                "name": "my_tool", "version": "1.0.0", "index": null}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_context_from_rxt() {
+        let indexed = Package::builder()
+            .name("my_tool")
+            .version("1.0.0")
+            .tool("mt")
+            .build();
+        let packages = HashMap::from([("my_tool".to_string(), vec![Arc::new(indexed)])]);
+
+        let context = context_from_rxt(SOLVED, &packages).unwrap();
+        assert_eq!(context.packages.len(), 2);
+        assert_eq!(&*context.packages[0].name, "python");
+        assert_eq!(
+            context.packages[0].path,
+            PathBuf::from("/packages/python/3.9.0")
+        );
+        assert_eq!(context.packages[1].tools, vec!["mt".into()]);
+        assert_eq!(context.metadata.stats.packages_considered, 12);
+        assert_eq!(context.metadata.stats.resolution_time_ms, 250);
+    }
+
+    #[test]
+    fn test_context_from_failed_rxt() {
+        let failed = r#"{"status": "failed", "failure_description": "python-2 vs python-3"}"#;
+        let error = context_from_rxt(failed, &HashMap::new()).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Resolver(ResolverError::Conflict(ref description)) if description == "python-2 vs python-3"
+        ));
+
+        assert!(context_from_rxt("not json", &HashMap::new()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_resolve_with_rez_env_binary() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("rez-env");
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\ncat <<'EOF'\n{}\nEOF\n", SOLVED),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let resolver = RezBinaryResolver::new(&script);
+        let requirements = vec![Requirement::new("my_tool", VersionConstraint::Any)];
+        let context = resolver.resolve(&requirements).await.unwrap();
+        assert_eq!(context.packages.len(), 2);
+        assert!(resolver.can_resolve(&requirements).await.unwrap());
+
+        let missing = RezBinaryResolver::new(dir.path().join("missing"));
+        assert!(missing.resolve(&requirements).await.is_err());
+    }
+}
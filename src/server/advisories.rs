@@ -0,0 +1,336 @@
+//! Package advisories: studio-provided lists of deprecated or banned package
+//! versions, used for license compliance and security advisories.
+//!
+//! A list is a JSON document of requirements matching the flagged versions,
+//! each with its own diagnostic severity:
+//!
+//! ```json
+//! {
+//!   "advisories": [
+//!     {
+//!       "requirement": "openssl-<1.1.1n",
+//!       "severity": "error",
+//!       "reason": "CVE-2022-0778",
+//!       "url": "https://nvd.nist.gov/vuln/detail/CVE-2022-0778"
+//!     },
+//!     { "requirement": "maya-2022", "reason": "License expired" }
+//!   ]
+//! }
+//! ```
+//!
+//! Lists are read by an [`AdvisorySource`]: a file, a plain `http://` URL or
+//! a source provided by an embedder, e.g. a studio database.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tower_lsp::lsp_types::{DiagnosticSeverity, Url};
+use tracing::warn;
+
+use crate::core::{Error, Requirement, Result, Version};
+
+/// Time allowed to fetch an advisory list over HTTP.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Severity of the diagnostics of an advisory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdvisorySeverity {
+    /// Shown as a hint
+    Hint,
+    /// Shown as information
+    #[serde(alias = "info")]
+    Information,
+    /// Shown as a warning
+    #[default]
+    Warning,
+    /// Shown as an error, e.g. for banned versions
+    Error,
+}
+
+impl From<AdvisorySeverity> for DiagnosticSeverity {
+    fn from(severity: AdvisorySeverity) -> Self {
+        match severity {
+            AdvisorySeverity::Hint => DiagnosticSeverity::HINT,
+            AdvisorySeverity::Information => DiagnosticSeverity::INFORMATION,
+            AdvisorySeverity::Warning => DiagnosticSeverity::WARNING,
+            AdvisorySeverity::Error => DiagnosticSeverity::ERROR,
+        }
+    }
+}
+
+/// A deprecated or banned range of versions of a package family.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Advisory {
+    /// Requirement matching the flagged versions, e.g. `openssl-<1.1.1n`
+    pub requirement: String,
+    /// Severity of the diagnostics of requirements on flagged versions
+    #[serde(default)]
+    pub severity: AdvisorySeverity,
+    /// Why the versions are flagged, e.g. an advisory ID
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Link to the advisory
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Advisories of a studio, with their parsed requirements.
+#[derive(Debug, Clone, Default)]
+pub struct AdvisoryList {
+    entries: Vec<(Requirement, Advisory)>,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryDocument {
+    advisories: Vec<Advisory>,
+}
+
+impl AdvisoryList {
+    /// Parse an advisory list document.
+    ///
+    /// Advisories whose requirement cannot be parsed are skipped with a
+    /// warning.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let document: AdvisoryDocument = serde_json::from_str(json)
+            .map_err(|e| Error::Other(format!("Invalid advisory list: {}", e)))?;
+        Ok(Self::new(document.advisories))
+    }
+
+    /// Create a list from advisories, skipping those whose requirement cannot
+    /// be parsed.
+    pub fn new(advisories: Vec<Advisory>) -> Self {
+        let entries = advisories
+            .into_iter()
+            .filter_map(|advisory| match Requirement::parse(&advisory.requirement) {
+                Ok(requirement) if !requirement.conflict && !requirement.weak => {
+                    Some((requirement, advisory))
+                }
+                _ => {
+                    warn!("Ignoring invalid advisory '{}'", advisory.requirement);
+                    None
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Get the number of advisories.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the list has no advisories.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the most severe advisory flagging a version of a family.
+    pub fn flagging(&self, family: &str, version: &Version) -> Option<&Advisory> {
+        self.entries
+            .iter()
+            .filter(|(requirement, _)| {
+                requirement.name == family && requirement.constraint.satisfies(version)
+            })
+            .map(|(_, advisory)| advisory)
+            .max_by_key(|advisory| advisory.severity)
+    }
+}
+
+/// Where an advisory list is read from.
+///
+/// Implement this to consult a studio service the built-in sources cannot
+/// reach, and hand the loaded list to
+/// [`DiagnosticsManager::set_advisories`](crate::server::DiagnosticsManager::set_advisories).
+#[async_trait]
+pub trait AdvisorySource: Send + Sync {
+    /// Load the current advisories.
+    async fn load(&self) -> Result<AdvisoryList>;
+
+    /// Describe the source for log messages.
+    fn describe(&self) -> String;
+}
+
+/// Advisory list stored in a JSON file.
+pub struct FileAdvisorySource {
+    path: PathBuf,
+}
+
+impl FileAdvisorySource {
+    /// Create a source reading `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl AdvisorySource for FileAdvisorySource {
+    async fn load(&self) -> Result<AdvisoryList> {
+        let json = tokio::fs::read_to_string(&self.path).await?;
+        AdvisoryList::from_json(&json)
+    }
+
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// Advisory list served over plain HTTP.
+///
+/// Only `http://` URLs are supported; lists behind TLS can be synced to a
+/// file and read with [`FileAdvisorySource`].
+pub struct HttpAdvisorySource {
+    url: String,
+}
+
+impl HttpAdvisorySource {
+    /// Create a source fetching `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    /// Fetch the body of the list with a minimal HTTP/1.0 `GET` request.
+    async fn fetch(&self) -> Result<String> {
+        let rest = self.url.strip_prefix("http://").ok_or_else(|| {
+            Error::Other(format!(
+                "Unsupported advisory URL '{}', only http:// is supported",
+                self.url
+            ))
+        })?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        let address = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{}:80", authority)
+        };
+
+        let mut stream = TcpStream::connect(&address).await?;
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n\r\n",
+            path, authority
+        );
+        stream.write_all(request.as_bytes()).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| Error::Other(format!("Malformed response from {}", self.url)))?;
+        let status = head.split_whitespace().nth(1).unwrap_or_default();
+        if status != "200" {
+            return Err(Error::Other(format!(
+                "Fetching {} failed with status {}",
+                self.url, status
+            )));
+        }
+        Ok(body.to_string())
+    }
+}
+
+#[async_trait]
+impl AdvisorySource for HttpAdvisorySource {
+    async fn load(&self) -> Result<AdvisoryList> {
+        let body = tokio::time::timeout(FETCH_TIMEOUT, self.fetch())
+            .await
+            .map_err(|_| Error::Other(format!("Fetching {} timed out", self.url)))??;
+        AdvisoryList::from_json(&body)
+    }
+
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+}
+
+/// Get the source of an advisory list location, a URL or a file path.
+///
+/// `file://` URLs are read from disk like plain paths.
+pub fn advisory_source(location: &str) -> Box<dyn AdvisorySource> {
+    let file = Url::parse(location)
+        .ok()
+        .filter(|url| url.scheme() == "file")
+        .and_then(|url| url.to_file_path().ok());
+    if let Some(path) = file {
+        Box::new(FileAdvisorySource::new(path))
+    } else if location.contains("://") {
+        Box::new(HttpAdvisorySource::new(location))
+    } else {
+        Box::new(FileAdvisorySource::new(location))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    const LIST: &str = r#"{
+        "advisories": [
+            {"requirement": "openssl-<1.1.1n", "severity": "error", "reason": "CVE-2022-0778"},
+            {"requirement": "openssl-1.0", "reason": "Deprecated"},
+            {"requirement": "maya-2022", "severity": "info"},
+            {"requirement": "!broken"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_advisory_list_flagging() {
+        let list = AdvisoryList::from_json(LIST).unwrap();
+        assert_eq!(list.len(), 3);
+
+        let flagged = list.flagging("openssl", &Version::new("1.0.2")).unwrap();
+        assert_eq!(flagged.severity, AdvisorySeverity::Error);
+        assert_eq!(flagged.reason.as_deref(), Some("CVE-2022-0778"));
+        assert_eq!(
+            list.flagging("maya", &Version::new("2022.4"))
+                .unwrap()
+                .severity,
+            AdvisorySeverity::Information
+        );
+        assert!(list.flagging("openssl", &Version::new("3.0")).is_none());
+        assert!(list.flagging("houdini", &Version::new("20")).is_none());
+
+        assert!(AdvisoryList::from_json("[]").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_advisory_sources() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("advisories.json");
+        std::fs::write(&path, LIST).unwrap();
+        let list = advisory_source(path.to_str().unwrap())
+            .load()
+            .await
+            .unwrap();
+        assert_eq!(list.len(), 3);
+        let url = Url::from_file_path(&path).unwrap();
+        let list = advisory_source(url.as_str()).load().await.unwrap();
+        assert_eq!(list.len(), 3);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            let response = format!(
+                "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
+                LIST
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+        let source = advisory_source(&format!("http://{}/advisories.json", address));
+        assert_eq!(source.load().await.unwrap().len(), 3);
+
+        assert!(advisory_source("https://example.com/advisories.json")
+            .load()
+            .await
+            .is_err());
+    }
+}
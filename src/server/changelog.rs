@@ -0,0 +1,286 @@
+//! Changes between a pinned requirement and the latest version of its family.
+//!
+//! Requirements whose family has versions newer than the one they resolve
+//! to get a "3 newer versions — view changes" code lens executing
+//! [`VIEW_CHANGES_COMMAND`]. The client then asks for the changes with the
+//! `rez/changelog` request, which collects the changelog excerpts of the
+//! newer versions into one markdown document.
+
+use std::fs;
+use std::path::Path;
+use tower_lsp::lsp_types::{CodeLens, Command};
+
+use crate::core::{Package, Requirement, Version, VersionConstraint};
+use crate::discovery::PackageDiscoveryImpl;
+use crate::parser::find_requirements;
+use crate::server::positions::{LineIndex, PositionEncoding};
+
+/// Client command executed by the "view changes" code lenses, with the
+/// family and pinned version as arguments.
+pub const VIEW_CHANGES_COMMAND: &str = "rezLsp.viewChanges";
+
+/// Changelog file names looked up in package payloads, in order.
+const CHANGELOG_FILES: &[&str] = &[
+    "CHANGELOG.md",
+    "CHANGELOG.rst",
+    "CHANGELOG.txt",
+    "CHANGELOG",
+    "CHANGES.md",
+    "CHANGES",
+    "HISTORY.md",
+];
+
+/// Maximum number of lines taken from the changelog of one version.
+const MAX_EXCERPT_LINES: usize = 40;
+
+/// Parameters for the `rez/changelog` request.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangelogParams {
+    /// Package family
+    pub family: String,
+    /// Pinned version; the changes of the newer versions are returned
+    pub since: String,
+}
+
+/// Response of the `rez/changelog` request.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangelogDocument {
+    /// Title of the document, e.g. `maya changes since 2023.1`
+    pub title: String,
+    /// Markdown content with one section per newer version
+    pub content: String,
+}
+
+/// Get the indexed versions of a family newer than `version`, newest first.
+///
+/// Pre-releases are left out unless the pinned version is one.
+fn newer_versions<'a>(
+    discovery: &'a PackageDiscoveryImpl,
+    family: &str,
+    version: &Version,
+) -> Vec<&'a Package> {
+    let name = discovery.config().canonical_package_name(family);
+    let mut newer: Vec<&Package> = discovery
+        .packages()
+        .get(name)
+        .into_iter()
+        .flatten()
+        .map(|package| &**package)
+        .filter(|package| {
+            package.version > *version
+                && (version.is_prerelease() || !package.version.is_prerelease())
+        })
+        .collect();
+    newer.sort_by(|a, b| b.version.cmp(&a.version));
+    newer
+}
+
+/// Get the "view changes" code lenses of the pinned requirements.
+///
+/// A requirement is pinned when it has a version constraint; the lens
+/// counts the versions newer than the one the requirement resolves to.
+pub fn changelog_code_lenses(
+    content: &str,
+    discovery: &PackageDiscoveryImpl,
+    encoding: PositionEncoding,
+) -> Vec<CodeLens> {
+    let index = LineIndex::new(content, encoding);
+    find_requirements(content)
+        .into_iter()
+        .filter(|span| !span.is_ephemeral())
+        .filter_map(|span| {
+            let requirement = Requirement::parse(&span.text)
+                .ok()
+                .filter(|r| !r.conflict && r.constraint != VersionConstraint::Any)?;
+            let pinned = discovery.pick_version(&requirement.name, &requirement.constraint)?;
+            if pinned.version.is_unversioned() {
+                return None;
+            }
+            let count = newer_versions(discovery, &requirement.name, &pinned.version).len();
+            if count == 0 {
+                return None;
+            }
+
+            let title = if count == 1 {
+                "1 newer version — view changes".to_string()
+            } else {
+                format!("{} newer versions — view changes", count)
+            };
+            Some(CodeLens {
+                range: index.range(span.range),
+                command: Some(Command {
+                    title,
+                    command: VIEW_CHANGES_COMMAND.to_string(),
+                    arguments: Some(vec![
+                        requirement.name.into(),
+                        pinned.version.to_string().into(),
+                    ]),
+                }),
+                data: None,
+            })
+        })
+        .collect()
+}
+
+/// Collect the changelog excerpts of the versions newer than `since`.
+///
+/// Versions without a changelog file in their payload are listed with a
+/// note, so the document still shows every version that would be picked up.
+pub fn changelog_document(
+    discovery: &PackageDiscoveryImpl,
+    family: &str,
+    since: &Version,
+) -> ChangelogDocument {
+    let title = format!("{} changes since {}", family, since);
+    let mut content = format!("# {}\n", title);
+
+    let newer = newer_versions(discovery, family, since);
+    if newer.is_empty() {
+        content.push_str(&format!(
+            "\nNo versions newer than {} are indexed.\n",
+            since
+        ));
+    }
+    for package in newer {
+        content.push_str(&format!("\n## {}\n\n", package.version));
+        match read_changelog(&package.path) {
+            Some(changelog) => {
+                content.push_str(&changelog_excerpt(&changelog, &package.version.to_string()));
+                content.push('\n');
+            }
+            None => content.push_str("_No changelog in the package payload._\n"),
+        }
+    }
+
+    ChangelogDocument { title, content }
+}
+
+/// Read the first changelog file of a package payload.
+fn read_changelog(payload: &Path) -> Option<String> {
+    CHANGELOG_FILES
+        .iter()
+        .find_map(|name| fs::read_to_string(payload.join(name)).ok())
+}
+
+/// Get the part of a changelog describing `version`.
+///
+/// The excerpt starts at the first heading naming the version and ends
+/// before the next heading of the same or a higher level. Changelogs without
+/// such a heading contribute their first lines.
+fn changelog_excerpt(changelog: &str, version: &str) -> String {
+    let lines: Vec<&str> = changelog.lines().collect();
+    let heading_level = |line: &str| {
+        let level = line.chars().take_while(|&c| c == '#').count();
+        (level > 0).then_some(level)
+    };
+
+    let start = lines.iter().position(|line| {
+        heading_level(line).is_some()
+            && line
+                .split(|c: char| c.is_whitespace() || c == '[' || c == ']')
+                .any(|word| word.trim_start_matches('v') == version)
+    });
+    let section = match start {
+        Some(start) => {
+            let level = heading_level(lines[start]).unwrap_or(1);
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| heading_level(line).is_some_and(|l| l <= level))
+                .map_or(lines.len(), |offset| start + 1 + offset);
+            &lines[start + 1..end]
+        }
+        None => &lines[..],
+    };
+
+    let truncated = section.len() > MAX_EXCERPT_LINES;
+    let mut excerpt = section
+        .iter()
+        .take(MAX_EXCERPT_LINES)
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string();
+    if truncated {
+        excerpt.push_str("\n\n…");
+    }
+    excerpt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::{scan_repositories, write_package};
+
+    async fn discovery(root: &Path) -> PackageDiscoveryImpl {
+        for (version, changelog) in [
+            ("2023.1", None),
+            (
+                "2024.1",
+                Some("# Changelog\n\n## 2024.1\n\n- USD 23 support\n\n## 2023.1\n\n- Old\n"),
+            ),
+            ("2025.0", None),
+            ("2025.1", Some("Faster startup\n")),
+            ("2026.0-beta1", None),
+        ] {
+            let dir = write_package(root, "maya", version, "name = 'maya'\n");
+            if let Some(changelog) = changelog {
+                fs::write(dir.join("CHANGELOG.md"), changelog).unwrap();
+            }
+        }
+        scan_repositories(&[root]).await
+    }
+
+    #[tokio::test]
+    async fn test_changelog_code_lenses() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let discovery = discovery(temp_dir.path()).await;
+
+        let content = "requires = [\n    'maya-2023',\n    'maya',\n    'maya-2025.1',\n]\n";
+        let lenses = changelog_code_lenses(content, &discovery, PositionEncoding::Utf16);
+        assert_eq!(lenses.len(), 1);
+        let command = lenses[0].command.as_ref().unwrap();
+        assert_eq!(command.title, "3 newer versions — view changes");
+        assert_eq!(command.command, VIEW_CHANGES_COMMAND);
+        assert_eq!(
+            command.arguments,
+            Some(vec!["maya".into(), "2023.1".into()])
+        );
+        assert_eq!(lenses[0].range.start.line, 1);
+    }
+
+    #[tokio::test]
+    async fn test_changelog_document() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let discovery = discovery(temp_dir.path()).await;
+
+        let document = changelog_document(&discovery, "maya", &Version::new("2023.1"));
+        assert_eq!(document.title, "maya changes since 2023.1");
+        assert_eq!(
+            document.content,
+            "# maya changes since 2023.1\n\n\
+             ## 2025.1\n\nFaster startup\n\n\
+             ## 2025.0\n\n_No changelog in the package payload._\n\n\
+             ## 2024.1\n\n- USD 23 support\n"
+        );
+
+        let document = changelog_document(&discovery, "maya", &Version::new("2025.1"));
+        assert!(document.content.contains("No versions newer than 2025.1"));
+    }
+
+    #[test]
+    fn test_changelog_excerpt() {
+        let changelog = "# Changelog\n\n## [v1.2.0] - 2024-05-01\n\n### Fixed\n\n- Crash\n\n## [1.1.0]\n\n- Feature\n";
+        assert_eq!(
+            changelog_excerpt(changelog, "1.2.0"),
+            "### Fixed\n\n- Crash"
+        );
+        assert_eq!(changelog_excerpt(changelog, "1.1.0"), "- Feature");
+        assert_eq!(
+            changelog_excerpt("Line\n".repeat(50).as_str(), "1.0"),
+            format!("{}\n\n…", vec!["Line"; MAX_EXCERPT_LINES].join("\n"))
+        );
+    }
+}
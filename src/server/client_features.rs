@@ -0,0 +1,318 @@
+//! Response features gated on the capabilities of the client.
+//!
+//! Handlers build responses with the richest features: nested document
+//! symbols, markdown hovers and completion documentation, and snippets. The
+//! responses are then adapted to what the client announced at `initialize`.
+
+use tower_lsp::lsp_types::*;
+
+/// Response features supported by the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientFeatures {
+    /// Document symbols may be nested instead of a flat list
+    pub hierarchical_symbols: bool,
+    /// Hover contents may be markdown
+    pub markdown_hover: bool,
+    /// Completion documentation may be markdown
+    pub markdown_completion: bool,
+    /// Completion items may insert snippets
+    pub snippets: bool,
+}
+
+impl Default for ClientFeatures {
+    /// All features, until the client capabilities are known.
+    fn default() -> Self {
+        Self {
+            hierarchical_symbols: true,
+            markdown_hover: true,
+            markdown_completion: true,
+            snippets: true,
+        }
+    }
+}
+
+impl ClientFeatures {
+    /// Read the features from the capabilities sent at `initialize`.
+    ///
+    /// Nested symbols and snippets need an explicit opt-in. Markdown is
+    /// assumed unless the client lists its formats without it.
+    pub fn from_capabilities(capabilities: &ClientCapabilities) -> Self {
+        let text_document = capabilities.text_document.as_ref();
+        let completion_item = text_document
+            .and_then(|text_document| text_document.completion.as_ref())
+            .and_then(|completion| completion.completion_item.as_ref());
+        Self {
+            hierarchical_symbols: text_document
+                .and_then(|text_document| text_document.document_symbol.as_ref())
+                .and_then(|symbol| symbol.hierarchical_document_symbol_support)
+                .unwrap_or(false),
+            markdown_hover: accepts_markdown(
+                text_document
+                    .and_then(|text_document| text_document.hover.as_ref())
+                    .and_then(|hover| hover.content_format.as_deref()),
+            ),
+            markdown_completion: accepts_markdown(
+                completion_item.and_then(|item| item.documentation_format.as_deref()),
+            ),
+            snippets: completion_item
+                .and_then(|item| item.snippet_support)
+                .unwrap_or(false),
+        }
+    }
+
+    /// Adapt a document symbol response, flattening nested symbols into
+    /// symbols of `uri` named after their container.
+    pub fn document_symbols(
+        self,
+        uri: &Url,
+        response: DocumentSymbolResponse,
+    ) -> DocumentSymbolResponse {
+        match response {
+            DocumentSymbolResponse::Nested(symbols) if !self.hierarchical_symbols => {
+                let mut flat = Vec::new();
+                flatten_symbols(uri, symbols, None, &mut flat);
+                DocumentSymbolResponse::Flat(flat)
+            }
+            response => response,
+        }
+    }
+
+    /// Adapt the contents of a hover to plain text if markdown is not
+    /// supported.
+    pub fn hover(self, mut hover: Hover) -> Hover {
+        if self.markdown_hover {
+            return hover;
+        }
+        let markdown = match hover.contents {
+            HoverContents::Markup(markup) => markup.value,
+            HoverContents::Scalar(marked) => marked_string_text(marked),
+            HoverContents::Array(marked) => marked
+                .into_iter()
+                .map(marked_string_text)
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        };
+        hover.contents = HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::PlainText,
+            value: plain_text(&markdown),
+        });
+        hover
+    }
+
+    /// Adapt completion items to the documentation formats and insert text
+    /// formats of the client.
+    pub fn completion(self, response: CompletionResponse) -> CompletionResponse {
+        match response {
+            CompletionResponse::Array(items) => {
+                CompletionResponse::Array(self.completion_items(items))
+            }
+            CompletionResponse::List(list) => CompletionResponse::List(CompletionList {
+                items: self.completion_items(list.items),
+                ..list
+            }),
+        }
+    }
+
+    fn completion_items(self, items: Vec<CompletionItem>) -> Vec<CompletionItem> {
+        items
+            .into_iter()
+            .map(|mut item| {
+                if !self.markdown_completion {
+                    if let Some(Documentation::MarkupContent(markup)) = &item.documentation {
+                        if markup.kind == MarkupKind::Markdown {
+                            item.documentation =
+                                Some(Documentation::String(plain_text(&markup.value)));
+                        }
+                    }
+                }
+                if !self.snippets && item.insert_text_format == Some(InsertTextFormat::SNIPPET) {
+                    item.insert_text = item.insert_text.as_deref().map(snippet_text);
+                    if let Some(CompletionTextEdit::Edit(edit)) = &mut item.text_edit {
+                        edit.new_text = snippet_text(&edit.new_text);
+                    }
+                    item.insert_text_format = Some(InsertTextFormat::PLAIN_TEXT);
+                }
+                item
+            })
+            .collect()
+    }
+}
+
+/// Check whether a list of content formats allows markdown.
+fn accepts_markdown(formats: Option<&[MarkupKind]>) -> bool {
+    formats.is_none_or(|formats| formats.contains(&MarkupKind::Markdown))
+}
+
+fn flatten_symbols(
+    uri: &Url,
+    symbols: Vec<DocumentSymbol>,
+    container: Option<&str>,
+    flat: &mut Vec<SymbolInformation>,
+) {
+    for symbol in symbols {
+        #[allow(deprecated)]
+        flat.push(SymbolInformation {
+            name: symbol.name.clone(),
+            kind: symbol.kind,
+            tags: symbol.tags,
+            deprecated: None,
+            location: Location::new(uri.clone(), symbol.range),
+            container_name: container.map(str::to_string),
+        });
+        if let Some(children) = symbol.children {
+            flatten_symbols(uri, children, Some(&symbol.name), flat);
+        }
+    }
+}
+
+fn marked_string_text(marked: MarkedString) -> String {
+    match marked {
+        MarkedString::String(text) => text,
+        MarkedString::LanguageString(code) => format!("```\n{}\n```", code.value),
+    }
+}
+
+/// Strip the markdown markup of hover and completion documentation.
+fn plain_text(markdown: &str) -> String {
+    let lines: Vec<String> = markdown
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .map(|line| {
+            let line = match line.strip_prefix('#') {
+                Some(heading) => heading.trim_start_matches('#').trim_start(),
+                None => line,
+            };
+            line.replace("**", "").replace('`', "")
+        })
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// Remove the tab stops and escapes of a snippet.
+fn snippet_text(snippet: &str) -> String {
+    let mut text = String::with_capacity(snippet.len());
+    let mut chars = snippet.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            '$' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+            }
+            _ => text.push(c),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nested_symbols() -> DocumentSymbolResponse {
+        #[allow(deprecated)]
+        let symbol = |name: &str, children: Option<Vec<DocumentSymbol>>| DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind: SymbolKind::PROPERTY,
+            tags: None,
+            deprecated: None,
+            range: Range::default(),
+            selection_range: Range::default(),
+            children,
+        };
+        DocumentSymbolResponse::Nested(vec![symbol(
+            "requires",
+            Some(vec![symbol("python-3.9", None)]),
+        )])
+    }
+
+    #[test]
+    fn test_from_capabilities() {
+        let features = ClientFeatures::from_capabilities(&ClientCapabilities::default());
+        assert!(!features.hierarchical_symbols);
+        assert!(!features.snippets);
+        assert!(features.markdown_hover);
+
+        let capabilities: ClientCapabilities = serde_json::from_value(serde_json::json!({
+            "textDocument": {
+                "documentSymbol": { "hierarchicalDocumentSymbolSupport": true },
+                "hover": { "contentFormat": ["plaintext"] },
+                "completion": { "completionItem": { "snippetSupport": true } }
+            }
+        }))
+        .unwrap();
+        let features = ClientFeatures::from_capabilities(&capabilities);
+        assert!(features.hierarchical_symbols);
+        assert!(features.snippets);
+        assert!(!features.markdown_hover);
+        assert!(features.markdown_completion);
+    }
+
+    #[test]
+    fn test_adapt_responses() {
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let flat = ClientFeatures {
+            hierarchical_symbols: false,
+            markdown_hover: false,
+            markdown_completion: false,
+            snippets: false,
+        };
+
+        let DocumentSymbolResponse::Flat(symbols) = flat.document_symbols(&uri, nested_symbols())
+        else {
+            panic!("expected flat symbols");
+        };
+        let names: Vec<_> = symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.container_name.as_deref()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("requires", None), ("python-3.9", Some("requires"))]
+        );
+        assert!(matches!(
+            ClientFeatures::default().document_symbols(&uri, nested_symbols()),
+            DocumentSymbolResponse::Nested(_)
+        ));
+
+        let hover = flat.hover(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: "**maya** `2024.1`\n\n```python\nrequires = []\n```".to_string(),
+            }),
+            range: None,
+        });
+        assert_eq!(
+            hover.contents,
+            HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::PlainText,
+                value: "maya 2024.1\n\nrequires = []".to_string(),
+            })
+        );
+
+        let CompletionResponse::Array(items) =
+            flat.completion(CompletionResponse::Array(vec![CompletionItem {
+                label: "maya".to_string(),
+                insert_text: Some("\"maya$0\"".to_string()),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: "**Maya**".to_string(),
+                })),
+                ..Default::default()
+            }]))
+        else {
+            panic!("expected an array");
+        };
+        assert_eq!(items[0].insert_text.as_deref(), Some("\"maya\""));
+        assert_eq!(
+            items[0].insert_text_format,
+            Some(InsertTextFormat::PLAIN_TEXT)
+        );
+        assert_eq!(
+            items[0].documentation,
+            Some(Documentation::String("Maya".to_string()))
+        );
+        assert_eq!(snippet_text("a\\$1${}$12b"), "a$1${}b");
+    }
+}
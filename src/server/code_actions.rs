@@ -0,0 +1,657 @@
+//! Quick fixes for diagnostics reported by the server.
+
+use std::collections::{BTreeMap, HashMap};
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Diagnostic, DocumentChangeOperation,
+    DocumentChanges, NumberOrString, OneOf, OptionalVersionedTextDocumentIdentifier, Range,
+    RenameFile, ResourceOp, TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+
+use super::diagnostics::{ALIAS_CODE, OUTSIDE_UPDATE_CODE};
+use super::path_mapping::{client_uri, server_path};
+use super::positions::{LineIndex, PositionEncoding};
+use super::test_runner::{package_name, string_attribute};
+use crate::config::PathMapping;
+use crate::core::{PackageIndex, Version, VersionToken};
+use crate::parser::commands::{alias_programs, find_commands, rez_package_variables};
+use crate::parser::{
+    find_requirements, requirement_insertion, RequirementSpan, VARIANTS_ATTRIBUTE,
+};
+use crate::validation::find_fixes;
+
+/// Code action kind applying every auto-fixable issue of a document, e.g.
+/// from `editor.codeActionsOnSave`.
+pub const FIX_ALL_KIND: &str = "source.fixAll.rezLsp";
+
+/// Build the quick fixes for the diagnostics of a code action request.
+pub fn quick_fixes(uri: &Url, diagnostics: &[Diagnostic]) -> Vec<CodeActionOrCommand> {
+    diagnostics
+        .iter()
+        .filter_map(|diagnostic| match &diagnostic.code {
+            Some(NumberOrString::String(code)) if code == ALIAS_CODE => {
+                canonical_name_fix(uri, diagnostic)
+            }
+            Some(NumberOrString::String(code)) if code == OUTSIDE_UPDATE_CODE => {
+                widen_constraint_fix(uri, diagnostic)
+            }
+            _ => None,
+        })
+        .map(CodeActionOrCommand::CodeAction)
+        .collect()
+}
+
+/// Replace an aliased package name with its canonical family name.
+fn canonical_name_fix(uri: &Url, diagnostic: &Diagnostic) -> Option<CodeAction> {
+    let canonical = diagnostic.data.as_ref()?.get("canonical")?.as_str()?;
+    let edit = TextEdit {
+        range: diagnostic.range,
+        new_text: canonical.to_string(),
+    };
+
+    Some(CodeAction {
+        title: format!("Replace with '{}'", canonical),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diagnostic.clone()]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+            ..Default::default()
+        }),
+        is_preferred: Some(true),
+        ..Default::default()
+    })
+}
+
+/// Replace a requirement with its widened form, accepting a newer version
+/// outside its constraint.
+fn widen_constraint_fix(uri: &Url, diagnostic: &Diagnostic) -> Option<CodeAction> {
+    let widened = diagnostic.data.as_ref()?.get("widened")?.as_str()?;
+    let edit = TextEdit {
+        range: diagnostic.range,
+        new_text: widened.to_string(),
+    };
+
+    Some(CodeAction {
+        title: format!("Widen constraint to '{}'", widened),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diagnostic.clone()]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+/// Fix every auto-fixable issue of a package definition in one edit.
+///
+/// The action is offered when the request accepts [`FIX_ALL_KIND`] actions,
+/// e.g. for `only: ["source.fixAll"]`, and there is something to fix. Callers
+/// only ask for package definitions.
+pub fn fix_all_action(
+    uri: &Url,
+    content: &str,
+    only: Option<&[CodeActionKind]>,
+    encoding: PositionEncoding,
+) -> Option<CodeActionOrCommand> {
+    let requested = only.is_none_or(|kinds| {
+        kinds.iter().any(|kind| {
+            FIX_ALL_KIND == kind.as_str()
+                || FIX_ALL_KIND.starts_with(&format!("{}.", kind.as_str()))
+        })
+    });
+    if !requested {
+        return None;
+    }
+    let fixes = find_fixes(content);
+    if fixes.is_empty() {
+        return None;
+    }
+
+    let index = LineIndex::new(content, encoding);
+    let edits = fixes
+        .into_iter()
+        .map(|fix| TextEdit {
+            range: Range::new(
+                index.position(fix.start.0, fix.start.1),
+                index.position(fix.end.0, fix.end.1),
+            ),
+            new_text: fix.new_text,
+        })
+        .collect();
+    Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: "Fix all auto-fixable problems".to_string(),
+        kind: Some(CodeActionKind::new(FIX_ALL_KIND)),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), edits)])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }))
+}
+
+/// Offer adding the packages `commands` uses but does not require.
+///
+/// A package is used when `commands` reads one of its `REZ_<NAME>_*`
+/// variables or aliases one of its tools. Actions are offered for usages on
+/// the lines of `range`.
+pub fn missing_requirement_actions(
+    uri: &Url,
+    content: &str,
+    range: Range,
+    packages: &PackageIndex,
+    aliases: &HashMap<String, String>,
+    encoding: PositionEncoding,
+) -> Vec<CodeActionOrCommand> {
+    let Some(block) = find_commands(content) else {
+        return Vec::new();
+    };
+    let canonical = |name: &str| {
+        aliases
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    };
+
+    let mut provided: Vec<String> = find_requirements(content)
+        .iter()
+        .map(|requirement| canonical(requirement.name_text()))
+        .collect();
+    provided.extend(package_name(content));
+
+    let families_by_variable: HashMap<String, &str> = packages
+        .keys()
+        .map(|family| (family.to_uppercase(), family.as_str()))
+        .collect();
+    let tools: HashMap<&str, &str> = packages
+        .values()
+        .flatten()
+        .flat_map(|package| {
+            package
+                .tools
+                .iter()
+                .map(move |tool| (&**tool, &*package.name))
+        })
+        .collect();
+
+    let usages = rez_package_variables(content, &block)
+        .into_iter()
+        .filter_map(|(variable, line)| Some((families_by_variable.get(&variable)?, line)))
+        .chain(
+            alias_programs(&block)
+                .into_iter()
+                .filter_map(|(program, line)| Some((tools.get(program.as_str())?, line))),
+        );
+
+    // One action per family, in name order
+    let mut missing = BTreeMap::new();
+    for (family, line) in usages {
+        if (range.start.line..=range.end.line).contains(&line)
+            && !provided.iter().any(|name| name == family)
+        {
+            missing.entry(family.to_string()).or_insert(line);
+        }
+    }
+
+    let index = LineIndex::new(content, encoding);
+    missing
+        .into_keys()
+        .map(|family| {
+            let edits = requirement_insertion(content, &family)
+                .into_iter()
+                .map(|(range, new_text)| TextEdit {
+                    range: index.range(range),
+                    new_text,
+                })
+                .collect();
+
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Add '{}' to requires", family),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), edits)])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Get the requirements on the lines of `range` that name a family without
+/// any version, e.g. `"python"`.
+///
+/// Weak, conflict and ephemeral requirements and variants are left out.
+pub fn unpinned_requirements(content: &str, range: Range) -> Vec<RequirementSpan> {
+    find_requirements(content)
+        .into_iter()
+        .filter(|span| (range.start.line..=range.end.line).contains(&span.range.line))
+        .filter(|span| {
+            span.constraint.is_none()
+                && span.prefix.is_none()
+                && !span.is_ephemeral()
+                && span.attribute != VARIANTS_ATTRIBUTE
+        })
+        .collect()
+}
+
+/// Offer pinning the unpinned requirements on the lines of `range`, e.g.
+/// before a release freeze.
+///
+/// Each requirement can be pinned to the version it resolves to in
+/// `resolved`, keyed by canonical family name, or to the range of that
+/// version's major version, e.g. `python-3.11.4` or `python-3+<4`.
+pub fn pin_requirement_actions(
+    uri: &Url,
+    content: &str,
+    range: Range,
+    resolved: &HashMap<String, Version>,
+    aliases: &HashMap<String, String>,
+    encoding: PositionEncoding,
+) -> Vec<CodeActionOrCommand> {
+    let index = LineIndex::new(content, encoding);
+    let mut actions = Vec::new();
+    for span in unpinned_requirements(content, range) {
+        let name = span.name_text();
+        let family = aliases.get(name).map_or(name, String::as_str);
+        let Some(version) = resolved.get(family).filter(|v| !v.is_unversioned()) else {
+            continue;
+        };
+
+        let mut pins = vec![(
+            format!("{}-{}", name, version),
+            "resolved version".to_string(),
+        )];
+        if let Some(VersionToken::Number(major)) = version.tokens.first() {
+            if let Some(next) = major.checked_add(1) {
+                pins.push((
+                    format!("{}-{}+<{}", name, major, next),
+                    format!("major version {}", major),
+                ));
+            }
+        }
+        for (pinned, description) in pins {
+            let edit = TextEdit {
+                range: index.range(span.range),
+                new_text: pinned.clone(),
+            };
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Pin '{}' to {} '{}'", name, description, pinned),
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }));
+        }
+    }
+    actions
+}
+
+/// Offer renaming the version directory of a package to its edited version.
+///
+/// Applies to package.py files laid out as `<family>/<version>/package.py`
+/// whose `version` no longer matches the directory. Requirements of the
+/// given documents pinning the old version (`family-1.2.0` or
+/// `family==1.2.0`) are updated along with the rename. The directories are
+/// renamed on the server; `mappings` translate between its paths and the
+/// client's URIs.
+pub fn version_directory_rename(
+    uri: &Url,
+    content: &str,
+    documents: &[(Url, String)],
+    encoding: PositionEncoding,
+    mappings: &[PathMapping],
+) -> Option<CodeActionOrCommand> {
+    let path = server_path(uri, mappings)?;
+    let version_dir = path.parent()?;
+    let family_dir = version_dir.parent()?;
+    let old_version = version_dir.file_name()?.to_str()?;
+
+    let name = package_name(content)?;
+    let new_version = string_attribute(content, "version")?;
+    if family_dir.file_name()?.to_str()? != name
+        || new_version.is_empty()
+        || new_version == old_version
+    {
+        return None;
+    }
+    let new_dir = family_dir.join(&new_version);
+    if new_dir.exists() {
+        return None;
+    }
+
+    let mut operations: Vec<DocumentChangeOperation> = documents
+        .iter()
+        .filter_map(|(document_uri, document)| {
+            let index = LineIndex::new(document, encoding);
+            let edits: Vec<OneOf<TextEdit, _>> = find_requirements(document)
+                .into_iter()
+                .filter(|requirement| requirement.name_text() == name)
+                .filter_map(|requirement| {
+                    let constraint = requirement.constraint?;
+                    let pinned = requirement.constraint_text()?;
+                    let operator = ["==", "-"]
+                        .into_iter()
+                        .find(|op| pinned.strip_prefix(op) == Some(old_version))?;
+                    Some(OneOf::Left(TextEdit {
+                        range: index.range(constraint),
+                        new_text: format!("{}{}", operator, new_version),
+                    }))
+                })
+                .collect();
+
+            (!edits.is_empty()).then(|| {
+                DocumentChangeOperation::Edit(TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: document_uri.clone(),
+                        version: None,
+                    },
+                    edits,
+                })
+            })
+        })
+        .collect();
+
+    // Edit documents before the rename moves any of them
+    operations.push(DocumentChangeOperation::Op(ResourceOp::Rename(
+        RenameFile {
+            old_uri: client_uri(version_dir, mappings)?,
+            new_uri: client_uri(&new_dir, mappings)?,
+            options: None,
+            annotation_id: None,
+        },
+    )));
+
+    Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: format!(
+            "Rename version directory '{}' to '{}'",
+            old_version, new_version
+        ),
+        kind: Some(CodeActionKind::REFACTOR),
+        edit: Some(WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Package;
+    use std::sync::Arc;
+    use tower_lsp::lsp_types::Position;
+
+    #[test]
+    fn test_canonical_name_quick_fix() {
+        let uri = Url::parse("file:///packages/usd_view/package.py").unwrap();
+        let alias = Diagnostic {
+            range: Range::new(Position::new(3, 6), Position::new(3, 9)),
+            code: Some(NumberOrString::String(ALIAS_CODE.to_string())),
+            data: Some(serde_json::json!({ "canonical": "openusd" })),
+            ..Default::default()
+        };
+        let other = Diagnostic {
+            code: Some(NumberOrString::String("R104".to_string())),
+            ..Default::default()
+        };
+
+        let fixes = quick_fixes(&uri, &[alias.clone(), other]);
+        assert_eq!(fixes.len(), 1);
+
+        let CodeActionOrCommand::CodeAction(action) = &fixes[0] else {
+            panic!("expected a code action");
+        };
+        assert_eq!(action.kind, Some(CodeActionKind::QUICKFIX));
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(edits[0].range, alias.range);
+        assert_eq!(edits[0].new_text, "openusd");
+    }
+
+    #[test]
+    fn test_widen_constraint_quick_fix() {
+        let uri = Url::parse("file:///packages/viewer/package.py").unwrap();
+        let update = Diagnostic {
+            range: Range::new(Position::new(0, 12), Position::new(0, 20)),
+            code: Some(NumberOrString::String(OUTSIDE_UPDATE_CODE.to_string())),
+            data: Some(serde_json::json!({ "widened": "pyside-2+<4" })),
+            ..Default::default()
+        };
+
+        let fixes = quick_fixes(&uri, std::slice::from_ref(&update));
+        let CodeActionOrCommand::CodeAction(action) = &fixes[0] else {
+            panic!("expected a code action");
+        };
+        assert_eq!(action.title, "Widen constraint to 'pyside-2+<4'");
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(edits[0].range, update.range);
+        assert_eq!(edits[0].new_text, "pyside-2+<4");
+    }
+
+    #[test]
+    fn test_fix_all_action() {
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let content = "name = 'tool'\nrequires = ['maya', 'my-lib-1', 'maya']\n";
+
+        let Some(CodeActionOrCommand::CodeAction(action)) =
+            fix_all_action(&uri, content, None, PositionEncoding::Utf16)
+        else {
+            panic!("expected a fix-all action");
+        };
+        assert_eq!(action.kind, Some(CodeActionKind::new(FIX_ALL_KIND)));
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        let texts: Vec<&str> = edits.iter().map(|edit| edit.new_text.as_str()).collect();
+        assert_eq!(texts, vec!["my_lib-1", ""]);
+        assert_eq!(
+            edits[1].range,
+            Range::new(Position::new(1, 30), Position::new(1, 38))
+        );
+
+        let only = [CodeActionKind::SOURCE_FIX_ALL];
+        assert!(fix_all_action(&uri, content, Some(&only), PositionEncoding::Utf16).is_some());
+        let only = [CodeActionKind::QUICKFIX];
+        assert!(fix_all_action(&uri, content, Some(&only), PositionEncoding::Utf16).is_none());
+        assert!(fix_all_action(&uri, "name = 'tool'\n", None, PositionEncoding::Utf16).is_none());
+    }
+
+    #[test]
+    fn test_missing_requirement_actions() {
+        let uri = Url::parse("file:///packages/viewer/package.py").unwrap();
+        let content = "name = 'viewer'\nrequires = [\n    'python-3',\n]\ndef commands():\n    env.OCIO = '{env.REZ_OCIO_ROOT}/config.ocio'\n    env.PATH.append('{env.REZ_PYTHON_ROOT}/bin')\n    alias('usd', 'usdview')\n";
+        let mut packages = HashMap::new();
+        for name in ["ocio", "python", "openusd"] {
+            let mut package = Package::builder().name(name).version("1.0").build();
+            if name == "openusd" {
+                package.tools = vec!["usdview".into()];
+            }
+            packages.insert(name.to_string(), vec![Arc::new(package)]);
+        }
+        let aliases = HashMap::new();
+        let lines = |start, end| Range::new(Position::new(start, 0), Position::new(end, 0));
+
+        let actions = missing_requirement_actions(
+            &uri,
+            content,
+            lines(4, 7),
+            &packages,
+            &aliases,
+            PositionEncoding::Utf16,
+        );
+        let titles: Vec<&str> = actions
+            .iter()
+            .map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => action.title.as_str(),
+                CodeActionOrCommand::Command(command) => command.title.as_str(),
+            })
+            .collect();
+        assert_eq!(
+            titles,
+            vec!["Add 'ocio' to requires", "Add 'openusd' to requires"]
+        );
+
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range.start, Position::new(3, 0));
+        assert_eq!(edits[0].new_text, "    'ocio',\n");
+
+        // Only usages on the requested lines are offered
+        assert!(missing_requirement_actions(
+            &uri,
+            content,
+            lines(6, 6),
+            &packages,
+            &aliases,
+            PositionEncoding::Utf16,
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_pin_requirement_actions() {
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let content = "name = 'tool'\nrequires = [\n    'python',\n    'maya-2024',\n    '~usd',\n    'pyside',\n]\nvariants = [['nuke']]\n";
+        let resolved = HashMap::from([
+            ("python".to_string(), Version::new("3.11.4")),
+            ("maya".to_string(), Version::new("2024.2")),
+            ("usd".to_string(), Version::new("24.05")),
+            ("pyside2".to_string(), Version::new("5.15")),
+            ("nuke".to_string(), Version::new("15.1")),
+        ]);
+        let aliases = HashMap::from([("pyside".to_string(), "pyside2".to_string())]);
+        let lines = |start, end| Range::new(Position::new(start, 0), Position::new(end, 0));
+
+        let actions = pin_requirement_actions(
+            &uri,
+            content,
+            lines(0, 7),
+            &resolved,
+            &aliases,
+            PositionEncoding::Utf16,
+        );
+        let edits: Vec<(String, &TextEdit)> = actions
+            .iter()
+            .map(|action| {
+                let CodeActionOrCommand::CodeAction(action) = action else {
+                    panic!("expected a code action");
+                };
+                let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+                (action.title.clone(), &edits[0])
+            })
+            .collect();
+        let titles: Vec<&str> = edits.iter().map(|(title, _)| title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Pin 'python' to resolved version 'python-3.11.4'",
+                "Pin 'python' to major version 3 'python-3+<4'",
+                "Pin 'pyside' to resolved version 'pyside-5.15'",
+                "Pin 'pyside' to major version 5 'pyside-5+<6'",
+            ]
+        );
+        let (_, edit) = &edits[1];
+        assert_eq!(edit.range.start, Position::new(2, 5));
+        assert_eq!(edit.range.end, Position::new(2, 11));
+        assert_eq!(edit.new_text, "python-3+<4");
+
+        // Only requirements on the requested lines are offered
+        assert_eq!(unpinned_requirements(content, lines(5, 5)).len(), 1);
+    }
+
+    #[test]
+    fn test_version_directory_rename() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let version_dir = temp_dir.path().join("viewer").join("1.2.0");
+        std::fs::create_dir_all(&version_dir).unwrap();
+        let uri = Url::from_file_path(version_dir.join("package.py")).unwrap();
+        let content = "name = 'viewer'\nversion = '1.3.0'\n";
+
+        let dependent = Url::parse("file:///workspace/shot/package.py").unwrap();
+        let documents = vec![(
+            dependent.clone(),
+            "requires = [\n    'viewer-1.2.0',\n    'viewer-1.2+',\n]\n".to_string(),
+        )];
+
+        let Some(CodeActionOrCommand::CodeAction(action)) =
+            version_directory_rename(&uri, content, &documents, PositionEncoding::Utf16, &[])
+        else {
+            panic!("expected a code action");
+        };
+        assert_eq!(action.title, "Rename version directory '1.2.0' to '1.3.0'");
+
+        let Some(DocumentChanges::Operations(operations)) = action.edit.unwrap().document_changes
+        else {
+            panic!("expected document change operations");
+        };
+        assert_eq!(operations.len(), 2);
+        let DocumentChangeOperation::Edit(edit) = &operations[0] else {
+            panic!("expected a text document edit");
+        };
+        assert_eq!(edit.text_document.uri, dependent);
+        assert_eq!(edit.edits.len(), 1);
+        let OneOf::Left(text_edit) = &edit.edits[0] else {
+            panic!("expected a plain text edit");
+        };
+        assert_eq!(text_edit.new_text, "-1.3.0");
+        assert_eq!(text_edit.range.start, Position::new(1, 11));
+
+        let DocumentChangeOperation::Op(ResourceOp::Rename(rename)) = &operations[1] else {
+            panic!("expected a rename");
+        };
+        assert!(rename.new_uri.path().ends_with("viewer/1.3.0"));
+
+        // Matching versions and unversioned layouts get no action
+        assert!(version_directory_rename(
+            &uri,
+            "name = 'viewer'\nversion = '1.2.0'\n",
+            &[],
+            PositionEncoding::Utf16,
+            &[]
+        )
+        .is_none());
+        assert!(version_directory_rename(
+            &uri,
+            "name = 'other'\nversion = '1.3.0'\n",
+            &[],
+            PositionEncoding::Utf16,
+            &[]
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_version_directory_rename_utf16_columns() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let version_dir = temp_dir.path().join("viewer").join("1.2.0");
+        std::fs::create_dir_all(&version_dir).unwrap();
+        let uri = Url::from_file_path(version_dir.join("package.py")).unwrap();
+        let content = "name = 'viewer'\nversion = '1.3.0'\n";
+        let dependent = Url::parse("file:///workspace/shot/package.py").unwrap();
+        let documents = vec![(
+            dependent,
+            "requires = [\n    '🎬', 'viewer-1.2.0',\n]\n".to_string(),
+        )];
+
+        let Some(CodeActionOrCommand::CodeAction(action)) =
+            version_directory_rename(&uri, content, &documents, PositionEncoding::Utf16, &[])
+        else {
+            panic!("expected a code action");
+        };
+        let Some(DocumentChanges::Operations(operations)) = action.edit.unwrap().document_changes
+        else {
+            panic!("expected document change operations");
+        };
+        let DocumentChangeOperation::Edit(edit) = &operations[0] else {
+            panic!("expected a text document edit");
+        };
+        let OneOf::Left(text_edit) = &edit.edits[0] else {
+            panic!("expected a plain text edit");
+        };
+        // The emoji before the edit takes two UTF-16 code units
+        assert_eq!(text_edit.range.start, Position::new(1, 17));
+    }
+}
@@ -1,74 +1,516 @@
 //! Completion handling for the LSP server.
 
-use std::sync::Arc;
+use std::path::Path;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tracing::info;
 
-use crate::core::PackageDiscovery;
-use crate::discovery::PackageDiscoveryImpl;
+use crate::config::PathMapping;
+use crate::core::{self, CompletionProvider};
+use crate::parser::links::SCRIPT_EXTENSIONS;
+use crate::parser::{
+    build_command_word_at, package_relative_path, requirement_list_at, requirement_part_at,
+    statement_at, RequirementList, RequirementPart, SourceRange, BUILD_COMMAND_PLACEHOLDERS,
+};
+use crate::server::documents::DocumentStore;
+use crate::server::path_mapping::server_path;
+use crate::server::positions::{LineIndex, PositionEncoding};
+use crate::validation::validation_engine::LARGE_FILE_THRESHOLD;
 
 /// Handle completion requests.
+///
+/// In documents larger than [`LARGE_FILE_THRESHOLD`] the items are filtered
+/// server-side to those starting with the word before the cursor, and the
+/// list is marked incomplete so the client asks again as the user types.
+/// The requirement list around the cursor is not looked up there.
+///
+/// Inside the `build_command` string, placeholders and files of the package
+/// directory are completed instead of packages; `mappings` translate the
+/// client URI of the definition to the package directory on the server.
+///
+/// After the family name of a requirement string, e.g. `"maya-20|"`, the
+/// versions of that family are completed instead of packages.
 pub async fn handle_completion(
     params: &CompletionParams,
-    package_discovery: &Arc<tokio::sync::RwLock<Option<PackageDiscoveryImpl>>>,
+    document_map: &tokio::sync::RwLock<DocumentStore>,
+    completion_provider: &dyn CompletionProvider,
+    encoding: PositionEncoding,
+    mappings: &[PathMapping],
 ) -> Result<Option<CompletionResponse>> {
     info!(
         "Completion requested at {:?}",
         params.text_document_position
     );
 
-    let package_discovery_guard = package_discovery.read().await;
-
-    let completions = if let Some(ref discovery) = *package_discovery_guard {
-        // Use real package discovery
-        match discovery.get_all_package_names().await {
-            Ok(package_names) => {
-                let mut completions = Vec::new();
-
-                for package_name in package_names {
-                    match discovery.get_package_versions(&package_name).await {
-                        Ok(versions) => {
-                            if let Some(latest_version) = versions.last() {
-                                completions.push(CompletionItem {
-                                    label: package_name.clone(),
-                                    kind: Some(CompletionItemKind::MODULE),
-                                    detail: Some(format!(
-                                        "Rez package (latest: {})",
-                                        latest_version.version
-                                    )),
-                                    documentation: latest_version
-                                        .description
-                                        .as_ref()
-                                        .map(|desc| Documentation::String(desc.clone())),
-                                    insert_text: Some(package_name),
-                                    ..Default::default()
-                                });
-                            }
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                "Failed to get versions for package {}: {}",
-                                package_name,
-                                e
-                            );
-                        }
-                    }
+    let position = &params.text_document_position;
+    let versioned = document_map
+        .read()
+        .await
+        .get(&position.text_document.uri)
+        .and_then(|content| constraint_word(&content, position.position, encoding));
+    if let Some((typed, range)) = versioned {
+        let items = match completion_provider.complete_requirements(&typed).await {
+            Ok(items) => items,
+            Err(e) => {
+                tracing::debug!("Version completions unavailable: {}", e);
+                Vec::new()
+            }
+        };
+        let items = items
+            .into_iter()
+            .map(|item| {
+                let label = item.label.clone();
+                CompletionItem {
+                    filter_text: Some(label.clone()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, label))),
+                    ..to_lsp_completion_item(item)
                 }
+            })
+            .collect();
+        return Ok(Some(CompletionResponse::Array(items)));
+    }
 
-                completions
-            }
-            Err(e) => {
-                tracing::warn!("Failed to get package names: {}", e);
-                get_fallback_completions()
+    let uri = &position.text_document.uri;
+    let content = document_map.read().await.get(uri);
+    if let Some(content) = &content {
+        let directory =
+            server_path(uri, mappings).and_then(|path| path.parent().map(Path::to_path_buf));
+        if let Some(items) =
+            build_command_completions(content, position.position, encoding, directory.as_deref())
+                .await
+        {
+            return Ok(Some(CompletionResponse::Array(items)));
+        }
+    }
+
+    let (prefix, attribute, unquoted) = {
+        let documents = document_map.read().await;
+        let prefix = match documents.size(uri) {
+            Some(size) if size > LARGE_FILE_THRESHOLD => {
+                documents.line(uri, position.position.line).map(|line| {
+                    let character = encoding.char_column(&line, position.position.character);
+                    word_before(&line, character).to_string()
+                })
             }
+            _ => None,
+        };
+        let (attribute, unquoted) = content
+            .as_deref()
+            .filter(|_| prefix.is_none())
+            .and_then(|content| requirement_context(content, position.position, encoding))
+            .unwrap_or_default();
+        (prefix, attribute, unquoted)
+    };
+
+    let completions = match completion_provider
+        .complete_package_names_in(prefix.as_deref().unwrap_or_default(), attribute)
+        .await
+    {
+        Ok(items) => items.into_iter().map(to_lsp_completion_item).collect(),
+        Err(e) => {
+            // Fall back to static completions while no package index is available
+            tracing::debug!("Package completions unavailable: {}", e);
+            get_fallback_completions()
         }
+    };
+    let mut completions = merge_implicit_family_completions(completions);
+    if let Some(word) = &unquoted {
+        completions = quote_completions(completions, word);
+    }
+
+    match prefix {
+        Some(prefix) => Ok(Some(CompletionResponse::List(CompletionList {
+            is_incomplete: true,
+            items: filter_by_prefix(completions, &prefix),
+        }))),
+        None => Ok(Some(CompletionResponse::Array(completions))),
+    }
+}
+
+/// Find the requirement typed up to a position on the version constraint of
+/// a requirement string.
+///
+/// Returns the text from the start of the family name to the position, with
+/// the range it spans, e.g. `maya-20` for `"~maya-20|"`.
+fn constraint_word(
+    content: &str,
+    position: Position,
+    encoding: PositionEncoding,
+) -> Option<(String, Range)> {
+    let index = LineIndex::new(content, encoding);
+    let character = index.char_column(&position);
+    let (span, _) = requirement_part_at(content, position.line, character)
+        .filter(|(span, part)| !span.is_ephemeral() && *part == RequirementPart::Constraint)?;
+    let range = SourceRange {
+        end: character,
+        ..span.name
+    };
+    Some((span.text_before(character).to_string(), index.range(range)))
+}
+
+/// Find the requirement list attribute at a position, and the unquoted word
+/// typed there.
+///
+/// Only the top-level statement containing the position is searched.
+fn requirement_context(
+    content: &str,
+    position: Position,
+    encoding: PositionEncoding,
+) -> Option<(Option<&'static str>, Option<UnquotedWord>)> {
+    let line = content.lines().nth(position.line as usize)?;
+    let character = encoding.char_column(line, position.character);
+    let (first_line, statement) = statement_at(content, position.line);
+    let line = position.line - first_line;
+    let list = requirement_list_at(statement, line, character)?;
+    let unquoted = unquoted_word(statement, &list, line, character, encoding).map(|mut word| {
+        word.range.start.line += first_line;
+        word.range.end.line += first_line;
+        word
+    });
+    Some((Some(list.attribute), unquoted))
+}
+
+/// A requirement typed without quotes in a requirement list.
+struct UnquotedWord {
+    /// Range of the word, replaced by the quoted requirement
+    range: Range,
+    /// Quote used by the other entries of the list
+    quote: char,
+}
+
+/// Check whether a character can be part of a requirement.
+fn is_requirement_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '+' | '~' | '!' | '<' | '>' | '=' | '|')
+}
+
+/// Find the unquoted word before the 0-based character column `character`
+/// in a requirement list.
+///
+/// Returns `None` inside string entries, so only words typed before their
+/// quotes, or empty positions such as right after `[`, are found.
+fn unquoted_word(
+    content: &str,
+    list: &RequirementList,
+    line: u32,
+    character: u32,
+    encoding: PositionEncoding,
+) -> Option<UnquotedWord> {
+    let position = (line, character);
+    if list
+        .entries
+        .iter()
+        .any(|&(start, end)| start < position && position < end)
+    {
+        return None;
+    }
+
+    let before: Vec<char> = content
+        .lines()
+        .nth(line as usize)?
+        .chars()
+        .take(character as usize)
+        .collect();
+    let start = before
+        .iter()
+        .rposition(|&c| !is_requirement_char(c))
+        .map_or(0, |index| index + 1);
+    if start > 0 && matches!(before[start - 1], '"' | '\'') {
+        return None;
+    }
+
+    let quote = list
+        .entries
+        .iter()
+        .find_map(|&((line, column), _)| {
+            let c = content
+                .lines()
+                .nth(line as usize)?
+                .chars()
+                .nth(column as usize)?;
+            matches!(c, '"' | '\'').then_some(c)
+        })
+        .unwrap_or('"');
+    let range = LineIndex::new(content, encoding).range(SourceRange {
+        line,
+        start: start as u32,
+        end: character,
+    });
+    Some(UnquotedWord { range, quote })
+}
+
+/// Make completions replace an unquoted word with the quoted requirement.
+///
+/// The edit is a snippet leaving the cursor before the closing quote, so a
+/// version can be typed right away.
+fn quote_completions(completions: Vec<CompletionItem>, word: &UnquotedWord) -> Vec<CompletionItem> {
+    completions
+        .into_iter()
+        .map(|mut item| {
+            let text = item
+                .insert_text
+                .take()
+                .unwrap_or_else(|| item.label.clone());
+            let escaped = text
+                .replace('\\', "\\\\")
+                .replace('$', "\\$")
+                .replace('}', "\\}");
+            let quoted = format!("{}{}$0{}", word.quote, escaped, word.quote);
+            item.text_edit = Some(CompletionTextEdit::Edit(TextEdit::new(word.range, quoted)));
+            item.insert_text_format = Some(InsertTextFormat::SNIPPET);
+            item
+        })
+        .collect()
+}
+
+/// Complete the word under the cursor inside a `build_command` string.
+///
+/// An open `{` completes the rez placeholders. Other words complete the
+/// files and directories of the package directory, following `{root}` and
+/// any directories already typed. Returns `None` outside `build_command`.
+async fn build_command_completions(
+    content: &str,
+    position: Position,
+    encoding: PositionEncoding,
+    directory: Option<&Path>,
+) -> Option<Vec<CompletionItem>> {
+    let line = content.lines().nth(position.line as usize)?;
+    let character = encoding.char_column(line, position.character);
+    let word = build_command_word_at(content, position.line, character)?;
+    let index = LineIndex::new(content, encoding);
+    let edit_from = |offset: usize| {
+        index.range(SourceRange {
+            line: position.line,
+            start: word.start + word.text[..offset].chars().count() as u32,
+            end: character,
+        })
+    };
+
+    let placeholders = |range: Range| {
+        BUILD_COMMAND_PLACEHOLDERS
+            .iter()
+            .map(move |(name, documentation)| {
+                let text = format!("{{{}}}", name);
+                CompletionItem {
+                    label: text.clone(),
+                    kind: Some(CompletionItemKind::CONSTANT),
+                    detail: Some("build_command placeholder".to_string()),
+                    documentation: Some(Documentation::String(documentation.to_string())),
+                    sort_text: Some(format!("1{}", name)),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, text))),
+                    ..Default::default()
+                }
+            })
+    };
+    let open_brace = word
+        .text
+        .rfind('{')
+        .filter(|&brace| !word.text[brace..].contains('}'));
+    if let Some(brace) = open_brace {
+        return Some(placeholders(edit_from(brace)).collect());
+    }
+
+    let mut items: Vec<CompletionItem> = if word.text.is_empty() {
+        placeholders(edit_from(0)).collect()
     } else {
-        // Fallback to static completions if package discovery is not available
-        get_fallback_completions()
+        Vec::new()
+    };
+
+    let (Some(directory), Some(relative)) = (directory, package_relative_path(&word.text)) else {
+        return Some(items);
+    };
+    let (subdirectory, name_prefix) = relative.rsplit_once(['/', '\\']).unwrap_or(("", relative));
+    let range = edit_from(word.text.len() - name_prefix.len());
+    let Ok(mut entries) = tokio::fs::read_dir(directory.join(subdirectory)).await else {
+        return Some(items);
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with(name_prefix)
+            || (name.starts_with('.') && !name_prefix.starts_with('.'))
+        {
+            continue;
+        }
+        let is_dir = entry.file_type().await.is_ok_and(|kind| kind.is_dir());
+        let is_script = Path::new(&name)
+            .extension()
+            .is_some_and(|extension| SCRIPT_EXTENSIONS.iter().any(|e| extension == *e));
+        // Scripts first, then directories, then other files
+        let rank = match (is_script, is_dir) {
+            (true, _) => 0,
+            (_, true) => 1,
+            _ => 2,
+        };
+        let text = if is_dir { format!("{}/", name) } else { name };
+        items.push(CompletionItem {
+            label: text.clone(),
+            kind: Some(if is_dir {
+                CompletionItemKind::FOLDER
+            } else {
+                CompletionItemKind::FILE
+            }),
+            detail: is_script.then(|| "Build script".to_string()),
+            sort_text: Some(format!("0{}{}", rank, text)),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, text))),
+            ..Default::default()
+        });
+    }
+    Some(items)
+}
+
+/// Convert a provider completion item into an LSP completion item.
+fn to_lsp_completion_item(item: core::CompletionItem) -> CompletionItem {
+    let kind = match item.kind {
+        core::CompletionItemKind::Package | core::CompletionItemKind::Requirement => {
+            CompletionItemKind::MODULE
+        }
+        core::CompletionItemKind::Version => CompletionItemKind::VALUE,
+        core::CompletionItemKind::Tool => CompletionItemKind::FUNCTION,
+        core::CompletionItemKind::Keyword => CompletionItemKind::KEYWORD,
+        core::CompletionItemKind::Variable => CompletionItemKind::VARIABLE,
+        core::CompletionItemKind::Function => CompletionItemKind::FUNCTION,
     };
 
-    Ok(Some(CompletionResponse::Array(completions)))
+    CompletionItem {
+        label: item.label,
+        kind: Some(kind),
+        detail: item.detail,
+        documentation: item.documentation.map(Documentation::String),
+        insert_text: item.insert_text,
+        sort_text: item.sort_text,
+        ..Default::default()
+    }
+}
+
+/// Values of the placeholders of a package completion detail template.
+pub(crate) struct DetailFields<'a> {
+    /// Latest indexed version, `unversioned` for unversioned packages
+    pub latest: String,
+    /// Number of indexed versions
+    pub count: usize,
+    /// Repository of the latest version
+    pub repo: Option<&'a Path>,
+    /// Description of the latest version
+    pub description: Option<&'a str>,
+}
+
+/// Render the detail line of a package completion from a template.
+///
+/// Placeholders without a value render empty and unknown placeholders are
+/// kept verbatim.
+pub(crate) fn render_detail(template: &str, fields: &DetailFields<'_>) -> String {
+    let mut detail = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        detail.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..start + length];
+        match placeholder {
+            "latest" => detail.push_str(&fields.latest),
+            "count" => detail.push_str(&fields.count.to_string()),
+            "repo" => detail.push_str(
+                &fields
+                    .repo
+                    .map(|repo| repo.display().to_string())
+                    .unwrap_or_default(),
+            ),
+            "description" => detail.push_str(fields.description.unwrap_or_default()),
+            _ => detail.push_str(&rest[start..=start + length]),
+        }
+        rest = &rest[start + length + 1..];
+    }
+    detail.push_str(rest);
+    detail.trim().to_string()
+}
+
+/// Get the package name characters directly before the 0-based character
+/// column `character` of a line.
+fn word_before(line: &str, character: u32) -> &str {
+    let end = line
+        .char_indices()
+        .nth(character as usize)
+        .map_or(line.len(), |(index, _)| index);
+    let before = &line[..end];
+    let start = before
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |index| index + 1);
+    &before[start..]
+}
+
+/// Keep the completion items whose label starts with `prefix`.
+fn filter_by_prefix(completions: Vec<CompletionItem>, prefix: &str) -> Vec<CompletionItem> {
+    completions
+        .into_iter()
+        .filter(|item| item.label.starts_with(prefix))
+        .collect()
+}
+
+/// Implicit package families provided by Rez itself.
+///
+/// Each entry is `(family, version, description)`. These families are rarely
+/// present in studio repositories but are valid in `requires` and `variants`.
+const IMPLICIT_FAMILIES: &[(&str, &str, &str)] = &[
+    ("platform", "linux", "Linux platform"),
+    ("platform", "windows", "Windows platform"),
+    ("platform", "osx", "macOS platform"),
+    ("arch", "x86_64", "64-bit x86 architecture"),
+    ("arch", "arm64", "64-bit ARM architecture"),
+    ("arch", "AMD64", "64-bit x86 architecture (Windows naming)"),
+    ("os", "Ubuntu-22.04", "Ubuntu 22.04 operating system"),
+    ("os", "Ubuntu-20.04", "Ubuntu 20.04 operating system"),
+    ("os", "CentOS-7", "CentOS 7 operating system"),
+    ("os", "Rocky-9", "Rocky Linux 9 operating system"),
+    ("os", "windows-10", "Windows 10 operating system"),
+    ("os", "osx-13", "macOS 13 operating system"),
+];
+
+/// Get completion items for the implicit `platform-`, `arch-` and `os-` families.
+fn get_implicit_family_completions() -> Vec<CompletionItem> {
+    IMPLICIT_FAMILIES
+        .iter()
+        .map(|(family, version, description)| {
+            let label = format!("{}-{}", family, version);
+            CompletionItem {
+                label: label.clone(),
+                kind: Some(CompletionItemKind::MODULE),
+                detail: Some(format!("Implicit Rez package ({})", family)),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: format!(
+                        "{}.\n\nThe `{}` family is an implicit package provided by Rez to describe the host system and can be used in `requires` and `variants`.",
+                        description, family
+                    ),
+                })),
+                insert_text: Some(label),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Merge the implicit family completions with the indexed completions.
+///
+/// Indexed families take precedence; implicit items whose family is also
+/// indexed are annotated so users know a real package exists.
+fn merge_implicit_family_completions(mut completions: Vec<CompletionItem>) -> Vec<CompletionItem> {
+    let indexed: std::collections::HashSet<String> =
+        completions.iter().map(|item| item.label.clone()).collect();
+
+    for mut item in get_implicit_family_completions() {
+        if indexed.contains(&item.label) {
+            continue;
+        }
+
+        let family = item.label.split('-').next().unwrap_or_default();
+        if indexed.contains(family) {
+            item.detail = Some(format!("Implicit Rez package ({}, also indexed)", family));
+        }
+
+        completions.push(item);
+    }
+
+    completions
 }
 
 /// Get fallback completions when package discovery is not available.
@@ -106,3 +548,448 @@ fn get_fallback_completions() -> Vec<CompletionItem> {
         },
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::LspError;
+    use tokio::sync::RwLock;
+
+    /// Provider serving a fixed list of package names.
+    struct FakeProvider(Vec<&'static str>);
+
+    #[async_trait::async_trait]
+    impl CompletionProvider for FakeProvider {
+        async fn complete_package_names(
+            &self,
+            prefix: &str,
+        ) -> crate::core::Result<Vec<core::CompletionItem>> {
+            if self.0.is_empty() {
+                return Err(LspError::NotInitialized.into());
+            }
+            Ok(self
+                .0
+                .iter()
+                .filter(|name| name.starts_with(prefix))
+                .map(|name| core::CompletionItem {
+                    label: name.to_string(),
+                    kind: core::CompletionItemKind::Package,
+                    detail: None,
+                    documentation: Some(format!("{} package", name)),
+                    insert_text: Some(name.to_string()),
+                    sort_text: None,
+                })
+                .collect())
+        }
+
+        async fn complete_versions(
+            &self,
+            _: &str,
+            _: &str,
+        ) -> crate::core::Result<Vec<core::CompletionItem>> {
+            Ok(Vec::new())
+        }
+
+        async fn complete_requirements(
+            &self,
+            prefix: &str,
+        ) -> crate::core::Result<Vec<core::CompletionItem>> {
+            let Some((family, version)) = prefix.split_once('-') else {
+                return self.complete_package_names(prefix).await;
+            };
+            Ok(["2024.1", "2025.0"]
+                .iter()
+                .filter(|v| v.starts_with(version))
+                .map(|v| core::CompletionItem {
+                    label: format!("{}-{}", family, v),
+                    kind: core::CompletionItemKind::Requirement,
+                    detail: None,
+                    documentation: None,
+                    insert_text: None,
+                    sort_text: None,
+                })
+                .collect())
+        }
+
+        async fn complete_tools(&self, _: &str) -> crate::core::Result<Vec<core::CompletionItem>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn completion_params(uri: &Url, line: u32, character: u32) -> CompletionParams {
+        CompletionParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+                position: Position::new(line, character),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completion_uses_provider() {
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let documents = RwLock::new(DocumentStore::new(4));
+        let provider = FakeProvider(vec!["python", "maya"]);
+
+        let response = handle_completion(
+            &completion_params(&uri, 0, 0),
+            &documents,
+            &provider,
+            PositionEncoding::Utf16,
+            &[],
+        )
+        .await
+        .unwrap();
+        let Some(CompletionResponse::Array(items)) = response else {
+            panic!("expected a complete completion array");
+        };
+        let python = items.iter().find(|item| item.label == "python").unwrap();
+        assert_eq!(python.kind, Some(CompletionItemKind::MODULE));
+        assert_eq!(
+            python.documentation,
+            Some(Documentation::String("python package".to_string()))
+        );
+        assert!(items.iter().any(|item| item.label == "platform-linux"));
+    }
+
+    #[tokio::test]
+    async fn test_completion_falls_back_without_index() {
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let documents = RwLock::new(DocumentStore::new(4));
+
+        let response = handle_completion(
+            &completion_params(&uri, 0, 0),
+            &documents,
+            &FakeProvider(vec![]),
+            PositionEncoding::Utf16,
+            &[],
+        )
+        .await
+        .unwrap();
+        let Some(CompletionResponse::Array(items)) = response else {
+            panic!("expected a complete completion array");
+        };
+        assert!(items.iter().any(|item| item.label == "python"));
+    }
+
+    #[tokio::test]
+    async fn test_completion_in_large_document_is_prefix_filtered() {
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let documents = RwLock::new(DocumentStore::new(4));
+        let content = format!(
+            "requires = [\"工具\", \"py\", \"ma\"]\n{}",
+            "# generated\n".repeat(LARGE_FILE_THRESHOLD / 10)
+        );
+        documents.write().await.insert(uri.clone(), content);
+        let provider = FakeProvider(vec!["python", "pyside2", "maya"]);
+
+        // The cursor after "py" is at UTF-16 column 21 and UTF-8 column 25
+        for (character, encoding) in [(21, PositionEncoding::Utf16), (25, PositionEncoding::Utf8)] {
+            let response = handle_completion(
+                &completion_params(&uri, 0, character),
+                &documents,
+                &provider,
+                encoding,
+                &[],
+            )
+            .await
+            .unwrap();
+            let Some(CompletionResponse::List(list)) = response else {
+                panic!("expected an incomplete completion list");
+            };
+            assert!(list.is_incomplete);
+            let labels: Vec<&str> = list.items.iter().map(|item| item.label.as_str()).collect();
+            assert_eq!(labels, vec!["python", "pyside2"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completion_on_constraint_completes_versions() {
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let documents = RwLock::new(DocumentStore::new(4));
+        let content = "requires = ['~maya-202', 'maya']\n";
+        documents
+            .write()
+            .await
+            .insert(uri.clone(), content.to_string());
+        let provider = FakeProvider(vec!["maya", "mayapy"]);
+
+        let response = handle_completion(
+            &completion_params(&uri, 0, 22),
+            &documents,
+            &provider,
+            PositionEncoding::Utf16,
+            &[],
+        )
+        .await
+        .unwrap();
+        let Some(CompletionResponse::Array(items)) = response else {
+            panic!("expected a complete completion array");
+        };
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["maya-2024.1", "maya-2025.0"]);
+        let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else {
+            panic!("expected a text edit");
+        };
+        // The weak prefix is kept, the typed requirement is replaced
+        assert_eq!(
+            edit.range,
+            Range::new(Position::new(0, 14), Position::new(0, 22))
+        );
+
+        // On the family name, packages are completed
+        let response = handle_completion(
+            &completion_params(&uri, 0, 31),
+            &documents,
+            &provider,
+            PositionEncoding::Utf16,
+            &[],
+        )
+        .await
+        .unwrap();
+        let Some(CompletionResponse::Array(items)) = response else {
+            panic!("expected a complete completion array");
+        };
+        assert!(items.iter().any(|item| item.label == "mayapy"));
+    }
+
+    #[tokio::test]
+    async fn test_unquoted_completion_is_wrapped_in_quotes() {
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let documents = RwLock::new(DocumentStore::new(4));
+        let content = "requires = ['maya', py]\ntools = [py]\n";
+        documents
+            .write()
+            .await
+            .insert(uri.clone(), content.to_string());
+        let provider = FakeProvider(vec!["python"]);
+
+        let response = handle_completion(
+            &completion_params(&uri, 0, 22),
+            &documents,
+            &provider,
+            PositionEncoding::Utf16,
+            &[],
+        )
+        .await
+        .unwrap();
+        let Some(CompletionResponse::Array(items)) = response else {
+            panic!("expected a complete completion array");
+        };
+        let python = items.iter().find(|item| item.label == "python").unwrap();
+        assert_eq!(
+            python.text_edit,
+            Some(CompletionTextEdit::Edit(TextEdit::new(
+                Range::new(Position::new(0, 20), Position::new(0, 22)),
+                "'python$0'".to_string(),
+            )))
+        );
+        assert_eq!(python.insert_text_format, Some(InsertTextFormat::SNIPPET));
+
+        // Quoted entries and other lists are completed as usual
+        for (line, character) in [(0, 17), (1, 11)] {
+            let response = handle_completion(
+                &completion_params(&uri, line, character),
+                &documents,
+                &provider,
+                PositionEncoding::Utf16,
+                &[],
+            )
+            .await
+            .unwrap();
+            let Some(CompletionResponse::Array(items)) = response else {
+                panic!("expected a complete completion array");
+            };
+            assert!(items.iter().all(|item| item.text_edit.is_none()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unquoted_completion_in_later_statement() {
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let documents = RwLock::new(DocumentStore::new(4));
+        let content = "name = 'tool'\n\nrequires = [\n    'maya',\n    py\n]\n\ntools = []\n";
+        documents
+            .write()
+            .await
+            .insert(uri.clone(), content.to_string());
+        let provider = FakeProvider(vec!["python"]);
+
+        let response = handle_completion(
+            &completion_params(&uri, 4, 6),
+            &documents,
+            &provider,
+            PositionEncoding::Utf16,
+            &[],
+        )
+        .await
+        .unwrap();
+        let Some(CompletionResponse::Array(items)) = response else {
+            panic!("expected a complete completion array");
+        };
+        let python = items.iter().find(|item| item.label == "python").unwrap();
+        assert_eq!(
+            python.text_edit,
+            Some(CompletionTextEdit::Edit(TextEdit::new(
+                Range::new(Position::new(4, 4), Position::new(4, 6)),
+                "'python$0'".to_string(),
+            )))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_command_completions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("scripts")).unwrap();
+        std::fs::write(temp_dir.path().join("scripts/build.sh"), "").unwrap();
+        std::fs::write(temp_dir.path().join("scripts/notes.txt"), "").unwrap();
+        std::fs::write(temp_dir.path().join("build.py"), "").unwrap();
+        let uri = Url::from_file_path(temp_dir.path().join("package.py")).unwrap();
+        let documents = RwLock::new(DocumentStore::new(4));
+        let content = "build_command = \"python {root}/scripts/b\nvariants = [\"{bu\"]\n";
+        documents
+            .write()
+            .await
+            .insert(uri.clone(), content.to_string());
+        let complete = |line, character| {
+            let params = completion_params(&uri, line, character);
+            let documents = &documents;
+            async move {
+                let response = handle_completion(
+                    &params,
+                    documents,
+                    &FakeProvider(vec!["python"]),
+                    PositionEncoding::Utf16,
+                    &[],
+                )
+                .await
+                .unwrap();
+                let Some(CompletionResponse::Array(items)) = response else {
+                    panic!("expected a complete completion array");
+                };
+                items
+            }
+        };
+
+        let items = complete(0, 40).await;
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["build.sh"]);
+        let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.range.start, Position::new(0, 39));
+
+        // `{` opens a placeholder, replaced as a whole
+        let items = complete(0, 25).await;
+        let root = items.iter().find(|item| item.label == "{root}").unwrap();
+        assert!(root.documentation.is_some());
+        assert!(items.iter().any(|item| item.label == "{install_path}"));
+        assert!(items
+            .iter()
+            .all(|item| item.kind == Some(CompletionItemKind::CONSTANT)));
+
+        // A new word gets placeholders and the package directory
+        let content = "build_command = \"python \"\n";
+        documents
+            .write()
+            .await
+            .insert(uri.clone(), content.to_string());
+        let items = complete(0, 24).await;
+        assert!(items.iter().any(|item| item.label == "{build_path}"));
+        let scripts = items.iter().find(|item| item.label == "scripts/").unwrap();
+        assert_eq!(scripts.kind, Some(CompletionItemKind::FOLDER));
+        assert!(items.iter().any(|item| item.label == "build.py"));
+
+        // Outside build_command packages are completed
+        let items = complete(1, 0).await;
+        assert!(items.iter().any(|item| item.label == "python"));
+    }
+
+    #[test]
+    fn test_implicit_family_completions() {
+        let completions = get_implicit_family_completions();
+        for label in [
+            "platform-linux",
+            "platform-windows",
+            "platform-osx",
+            "arch-x86_64",
+        ] {
+            assert!(completions.iter().any(|item| item.label == label));
+        }
+        assert!(completions.iter().any(|item| item.label.starts_with("os-")));
+    }
+
+    #[test]
+    fn test_merge_implicit_family_completions() {
+        let indexed = vec![
+            CompletionItem {
+                label: "platform".to_string(),
+                ..Default::default()
+            },
+            CompletionItem {
+                label: "arch-x86_64".to_string(),
+                detail: Some("Rez package (latest: 1)".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let merged = merge_implicit_family_completions(indexed);
+
+        // Indexed items are kept and not duplicated
+        let arch: Vec<_> = merged
+            .iter()
+            .filter(|item| item.label == "arch-x86_64")
+            .collect();
+        assert_eq!(arch.len(), 1);
+        assert_eq!(arch[0].detail.as_deref(), Some("Rez package (latest: 1)"));
+
+        // Implicit items of an indexed family are annotated
+        let linux = merged
+            .iter()
+            .find(|item| item.label == "platform-linux")
+            .unwrap();
+        assert!(linux.detail.as_ref().unwrap().contains("also indexed"));
+    }
+
+    #[test]
+    fn test_render_detail() {
+        let fields = DetailFields {
+            latest: "2.1.0".to_string(),
+            count: 3,
+            repo: Some(Path::new("/studio/packages")),
+            description: None,
+        };
+        assert_eq!(
+            render_detail("{count} versions · {repo}", &fields),
+            "3 versions · /studio/packages"
+        );
+        assert_eq!(
+            render_detail("latest: {latest} {description}", &fields),
+            "latest: 2.1.0"
+        );
+        assert_eq!(render_detail("{owner} {latest", &fields), "{owner} {latest");
+    }
+
+    #[test]
+    fn test_prefix_only_matching() {
+        assert_eq!(word_before("requires = [\"py", 15), "py");
+        assert_eq!(word_before("requires = [\"py", 12), "");
+        assert_eq!(word_before("maya_", 99), "maya_");
+
+        let items = ["python", "pyside2", "maya"]
+            .into_iter()
+            .map(|label| CompletionItem {
+                label: label.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let labels: Vec<String> = filter_by_prefix(items, "py")
+            .into_iter()
+            .map(|item| item.label)
+            .collect();
+        assert_eq!(labels, vec!["python", "pyside2"]);
+    }
+}
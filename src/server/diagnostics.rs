@@ -1,18 +1,64 @@
 //! Diagnostic management for the LSP server.
 
-use crate::core::Result;
-use crate::validation::{Severity as ValidationSeverity, ValidationEngine, ValidationResult};
-use std::collections::HashMap;
+use crate::config::{PackageEvaluation, PathMapping};
+use crate::core::{
+    LspError, Package, Requirement, ResolvedContext, Result, Version, VersionConstraint,
+    VersionOrder, VersionPolicy, VersionToken,
+};
+use crate::discovery::PackageDiscoveryImpl;
+use crate::parser::encoding::read_source;
+use crate::parser::{find_links, find_requirements, package_relative_path, LinkKind, SourceRange};
+use crate::resolver::path_precedence;
+use crate::server::advisories::AdvisoryList;
+use crate::server::documents::document_path;
+use crate::server::path_mapping::{client_uri, server_path};
+use crate::server::positions::{LineIndex, PositionEncoding};
+use crate::server::trust::{restricted_mode_diagnostic, WorkspaceTrust};
+use crate::validation::validation_engine::ValidationConfig;
+use crate::validation::{
+    Severity as ValidationSeverity, Suppressions, ValidationEngine, ValidationEvent,
+    ValidationResult,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, Url};
+use tokio::sync::{mpsc, RwLock};
+use tower_lsp::lsp_types::{
+    CodeDescription, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location,
+    NumberOrString, Range, Url,
+};
 
 /// Manages diagnostics for the LSP server.
 pub struct DiagnosticsManager {
     /// Validation engine for checking package.py files
-    validation_engine: Arc<ValidationEngine>,
+    validation_engine: RwLock<Arc<ValidationEngine>>,
     /// Current diagnostics for each file
     diagnostics: Arc<RwLock<HashMap<Url, Vec<Diagnostic>>>>,
+    /// Result IDs of the diagnostics of pulled documents
+    result_ids: RwLock<HashMap<Url, String>>,
+    /// Generation of the validation settings, part of every result ID
+    generation: AtomicU64,
+    /// Renamed package families, mapping old names to their canonical name
+    package_aliases: RwLock<HashMap<String, String>>,
+    /// Unit in which diagnostic ranges count columns
+    position_encoding: RwLock<PositionEncoding>,
+    /// Release repository whose package definitions get a
+    /// [`RELEASED_PACKAGE_CODE`] warning
+    release_packages_path: RwLock<Option<PathBuf>>,
+    /// Deprecated or banned versions reported by [`ADVISORY_CODE`] diagnostics
+    advisories: RwLock<AdvisoryList>,
+    /// Mappings locating the package directories checked for
+    /// [`MISSING_BUILD_SCRIPT_CODE`] diagnostics
+    path_mappings: RwLock<Vec<PathMapping>>,
+    /// Package index consulted by [`PRERELEASE_CODE`], [`VARIANT_LIMIT_CODE`]
+    /// and [`ADVISORY_CODE`] diagnostics
+    package_discovery: Option<Arc<RwLock<Option<PackageDiscoveryImpl>>>>,
+    /// Trust whose restricted mode is reported as [`RESTRICTED_MODE_CODE`]
+    workspace_trust: Option<WorkspaceTrust>,
+    /// Configured package evaluation, held back while the workspace is
+    /// restricted
+    package_evaluation: RwLock<PackageEvaluation>,
 }
 
 impl DiagnosticsManager {
@@ -22,30 +68,320 @@ impl DiagnosticsManager {
         let diagnostics = Arc::new(RwLock::new(HashMap::new()));
 
         Ok(Self {
-            validation_engine,
+            validation_engine: RwLock::new(validation_engine),
             diagnostics,
+            result_ids: RwLock::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+            package_aliases: RwLock::new(HashMap::new()),
+            position_encoding: RwLock::new(PositionEncoding::default()),
+            release_packages_path: RwLock::new(None),
+            advisories: RwLock::new(AdvisoryList::default()),
+            path_mappings: RwLock::new(Vec::new()),
+            package_discovery: None,
+            workspace_trust: None,
+            package_evaluation: RwLock::new(PackageEvaluation::Static),
         })
     }
 
-    /// Validate a file and update diagnostics.
-    pub async fn validate_file(&self, uri: &Url, content: &str) -> Result<Vec<Diagnostic>> {
-        let file_path = uri.path();
+    /// Report the features disabled while `workspace_trust` is restricted.
+    pub fn with_workspace_trust(mut self, workspace_trust: WorkspaceTrust) -> Self {
+        self.workspace_trust = Some(workspace_trust);
+        self
+    }
 
-        // Run validation
-        let validation_result = self.validation_engine.validate_file(content, file_path)?;
+    /// Report requirements resolving to pre-releases in the package index.
+    pub fn with_package_index(
+        mut self,
+        package_discovery: Arc<RwLock<Option<PackageDiscoveryImpl>>>,
+    ) -> Self {
+        self.package_discovery = Some(package_discovery);
+        self
+    }
+
+    /// Invalidate the result IDs of earlier pulled diagnostics after the
+    /// package index was rebuilt.
+    pub async fn package_index_changed(&self) {
+        self.invalidate_results().await;
+    }
+
+    /// Set the package family aliases reported by [`ALIAS_CODE`] diagnostics.
+    ///
+    /// Invalidates the result IDs of earlier pulled diagnostics.
+    pub async fn set_package_aliases(&self, aliases: HashMap<String, String>) {
+        *self.package_aliases.write().await = aliases;
+        self.invalidate_results().await;
+    }
+
+    /// Set the configured package evaluation, reported as disabled by
+    /// [`RESTRICTED_MODE_CODE`] diagnostics while the workspace is restricted.
+    ///
+    /// Invalidates the result IDs of earlier pulled diagnostics.
+    pub async fn set_package_evaluation(&self, evaluation: PackageEvaluation) {
+        *self.package_evaluation.write().await = evaluation;
+        self.invalidate_results().await;
+    }
+
+    /// Set the advisories flagging deprecated or banned package versions.
+    ///
+    /// Invalidates the result IDs of earlier pulled diagnostics.
+    pub async fn set_advisories(&self, advisories: AdvisoryList) {
+        *self.advisories.write().await = advisories;
+        self.invalidate_results().await;
+    }
+
+    /// Set the mappings between server paths and client URIs.
+    ///
+    /// Invalidates the result IDs of earlier pulled diagnostics.
+    pub async fn set_path_mappings(&self, mappings: Vec<PathMapping>) {
+        *self.path_mappings.write().await = mappings;
+        self.invalidate_results().await;
+    }
+
+    /// Set the position encoding negotiated with the client.
+    ///
+    /// Invalidates the result IDs of earlier pulled diagnostics.
+    pub async fn set_position_encoding(&self, encoding: PositionEncoding) {
+        *self.position_encoding.write().await = encoding;
+        self.invalidate_results().await;
+    }
+
+    /// Set the release repository whose package definitions are reported by
+    /// [`RELEASED_PACKAGE_CODE`] diagnostics.
+    ///
+    /// Invalidates the result IDs of earlier pulled diagnostics.
+    pub async fn set_release_packages_path(&self, path: Option<PathBuf>) {
+        *self.release_packages_path.write().await = path;
+        self.invalidate_results().await;
+    }
+
+    /// Check if a document is a package definition of the release repository.
+    pub async fn is_released_definition(&self, uri: &Url) -> bool {
+        is_released_definition(uri, self.release_packages_path.read().await.as_deref())
+    }
+
+    /// Enforce the version schemes of a policy in R003/R103 diagnostics.
+    ///
+    /// Invalidates the result IDs of earlier pulled diagnostics.
+    pub async fn set_version_policy(&self, version_policy: VersionPolicy) -> Result<()> {
+        let engine = ValidationEngine::with_config(ValidationConfig {
+            version_policy,
+            ..Default::default()
+        })?;
+        *self.validation_engine.write().await = Arc::new(engine);
+        self.invalidate_results().await;
+        Ok(())
+    }
+
+    /// Start a new generation of result IDs after the validation settings
+    /// changed.
+    async fn invalidate_results(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.result_ids.write().await.clear();
+    }
+
+    /// Get the result ID of the diagnostics of a document revision.
+    ///
+    /// The ID changes whenever the content or the validation settings change,
+    /// so a client holding the same ID has up-to-date diagnostics.
+    pub fn result_id(&self, revision: u64) -> String {
+        format!("{}-{}", self.generation.load(Ordering::Relaxed), revision)
+    }
 
-        // Convert validation issues to LSP diagnostics
-        let diagnostics = self.convert_validation_result(&validation_result);
+    /// Validate a document revision for a diagnostic pull request.
+    ///
+    /// Reuses the stored diagnostics when the revision was already validated.
+    /// Returns the result ID of the diagnostics with them.
+    pub async fn validate_revision(
+        &self,
+        uri: &Url,
+        content: &str,
+        revision: u64,
+    ) -> Result<(String, Vec<Diagnostic>)> {
+        let result_id = self.result_id(revision);
+        if self.result_ids.read().await.get(uri) == Some(&result_id) {
+            if let Some(diagnostics) = self.diagnostics.read().await.get(uri) {
+                return Ok((result_id, diagnostics.clone()));
+            }
+        }
+
+        let diagnostics = self.validate_file(uri, content).await?;
+        self.result_ids
+            .write()
+            .await
+            .insert(uri.clone(), result_id.clone());
+        Ok((result_id, diagnostics))
+    }
+
+    /// Validate a file and update diagnostics.
+    pub async fn validate_file(&self, uri: &Url, content: &str) -> Result<Vec<Diagnostic>> {
+        let diagnostics = self.check_file(uri, content).await?;
 
         // Store diagnostics
         {
             let mut diag_map = self.diagnostics.write().await;
             diag_map.insert(uri.clone(), diagnostics.clone());
         }
+        self.result_ids.write().await.remove(uri);
 
         Ok(diagnostics)
     }
 
+    /// Validate a file without storing its diagnostics, e.g. for a file that
+    /// is not open.
+    pub async fn check_file(&self, uri: &Url, content: &str) -> Result<Vec<Diagnostic>> {
+        let file_path = document_path(uri);
+
+        // Run validation
+        let engine = self.validation_engine.read().await.clone();
+        let validation_result = engine.validate_file(content, &file_path)?;
+
+        let index = LineIndex::new(content, *self.position_encoding.read().await);
+        Ok(self
+            .complete_diagnostics(uri, content, &index, &validation_result)
+            .await)
+    }
+
+    /// Validate a file and update diagnostics like
+    /// [`DiagnosticsManager::validate_file`], sending the diagnostics found
+    /// so far to `partial` as each validator finishes.
+    ///
+    /// Lets the diagnostics of very large files be published before every
+    /// validator ran.
+    pub async fn validate_file_progressively(
+        &self,
+        uri: &Url,
+        content: &str,
+        partial: mpsc::UnboundedSender<Vec<Diagnostic>>,
+    ) -> Result<Vec<Diagnostic>> {
+        let engine = self.validation_engine.read().await.clone();
+        let index = LineIndex::new(content, *self.position_encoding.read().await);
+
+        let file_path = document_path(uri);
+        let mut events = engine.validate_stream(content.to_string(), file_path.clone());
+        let mut found = Vec::new();
+        let mut validation_result = None;
+        while let Some(event) = events.recv().await {
+            match event {
+                ValidationEvent::Issues { issues, .. } => {
+                    found.extend(issues);
+                    let so_far = ValidationResult::new(&file_path, found.clone(), 0);
+                    let _ = partial.send(self.convert_validation_result(&index, &so_far));
+                }
+                ValidationEvent::Finished(result) => validation_result = Some(result),
+            }
+        }
+        drop(partial);
+        let Some(validation_result) = validation_result else {
+            return Err(LspError::Internal(format!("Validation of {} was aborted", uri)).into());
+        };
+
+        let diagnostics = self
+            .complete_diagnostics(uri, content, &index, &validation_result)
+            .await;
+        self.diagnostics
+            .write()
+            .await
+            .insert(uri.clone(), diagnostics.clone());
+        self.result_ids.write().await.remove(uri);
+        Ok(diagnostics)
+    }
+
+    /// Convert a validation result to LSP diagnostics and add the diagnostics
+    /// checking the document against the settings and the package index.
+    ///
+    /// Files the validation found not to be package definitions only get
+    /// its note.
+    async fn complete_diagnostics(
+        &self,
+        uri: &Url,
+        content: &str,
+        index: &LineIndex<'_>,
+        validation_result: &ValidationResult,
+    ) -> Vec<Diagnostic> {
+        let mut diagnostics = self.convert_validation_result(index, validation_result);
+        if validation_result
+            .issues
+            .iter()
+            .any(|issue| issue.code == "V004")
+        {
+            return diagnostics;
+        }
+        if self.is_released_definition(uri).await {
+            diagnostics.push(released_package_diagnostic(content, index));
+        }
+        let directory = server_path(uri, &self.path_mappings.read().await)
+            .and_then(|path| path.parent().map(Path::to_path_buf));
+        if let Some(directory) = directory {
+            diagnostics.extend(missing_build_script_diagnostics(content, index, &directory).await);
+        }
+        diagnostics.extend(alias_diagnostics(
+            content,
+            index,
+            &*self.package_aliases.read().await,
+        ));
+        if self
+            .workspace_trust
+            .as_ref()
+            .is_some_and(|trust| !trust.is_trusted())
+        {
+            let evaluation = *self.package_evaluation.read().await;
+            diagnostics.extend(restricted_mode_diagnostic(content, index, evaluation));
+        }
+        let mut resolve = None;
+        if let Some(package_discovery) = &self.package_discovery {
+            if let Some(discovery) = package_discovery.read().await.as_ref() {
+                diagnostics.extend(prerelease_diagnostics(content, index, discovery));
+                diagnostics.extend(variant_limit_diagnostics(content, index, discovery));
+                diagnostics.extend(update_diagnostics(content, index, discovery));
+                let requirements = discovery.parse_requires(content);
+                if !requirements.is_empty() {
+                    resolve = Some((discovery.cached_resolve(&requirements), requirements));
+                }
+                let advisories = self.advisories.read().await;
+                if !advisories.is_empty() {
+                    diagnostics.extend(advisory_diagnostics(
+                        content,
+                        index,
+                        discovery,
+                        &advisories,
+                    ));
+                }
+            }
+        }
+        // Contexts missing from the cache are resolved without holding the
+        // index lock
+        if let Some((cached, requirements)) = resolve {
+            let context = match cached {
+                Ok(context) => context,
+                Err(pending) => {
+                    let context = pending.resolver().resolve(&requirements).await.ok();
+                    pending.finish(context)
+                }
+            };
+            if let Some(context) = context {
+                let sources = provider_sources(&context).await;
+                diagnostics.extend(tool_conflict_diagnostics(
+                    content,
+                    index,
+                    &context,
+                    |package| sources.get(&*package.name).cloned(),
+                    &self.path_mappings.read().await,
+                ));
+            }
+        }
+
+        let suppressions = Suppressions::parse(content);
+        if !suppressions.is_empty() {
+            diagnostics.retain(|diagnostic| match &diagnostic.code {
+                Some(NumberOrString::String(code)) => {
+                    !suppressions.is_suppressed(code, diagnostic.range.start.line + 1)
+                }
+                _ => true,
+            });
+        }
+        diagnostics
+    }
+
     /// Get current diagnostics for a file.
     pub async fn get_diagnostics(&self, uri: &Url) -> Vec<Diagnostic> {
         let diag_map = self.diagnostics.read().await;
@@ -56,6 +392,7 @@ impl DiagnosticsManager {
     pub async fn clear_diagnostics(&self, uri: &Url) {
         let mut diag_map = self.diagnostics.write().await;
         diag_map.remove(uri);
+        self.result_ids.write().await.remove(uri);
     }
 
     /// Get all files with diagnostics.
@@ -65,7 +402,14 @@ impl DiagnosticsManager {
     }
 
     /// Convert validation result to LSP diagnostics.
-    fn convert_validation_result(&self, result: &ValidationResult) -> Vec<Diagnostic> {
+    ///
+    /// Issue columns count characters and are converted to the code units of
+    /// the negotiated position encoding.
+    fn convert_validation_result(
+        &self,
+        index: &LineIndex,
+        result: &ValidationResult,
+    ) -> Vec<Diagnostic> {
         result
             .issues
             .iter()
@@ -77,20 +421,11 @@ impl DiagnosticsManager {
                     ValidationSeverity::Info => DiagnosticSeverity::INFORMATION,
                 };
 
-                let start_pos = Position {
-                    line: issue.line - 1,        // Convert to 0-based
-                    character: issue.column - 1, // Convert to 0-based
-                };
-
-                let end_pos = Position {
-                    line: issue.line - 1,
-                    character: issue.column - 1 + issue.length,
-                };
-
-                let range = Range {
-                    start: start_pos,
-                    end: end_pos,
-                };
+                let range = index.range(SourceRange {
+                    line: issue.line - 1,    // Convert to 0-based
+                    start: issue.column - 1, // Convert to 0-based
+                    end: issue.column - 1 + issue.length,
+                });
 
                 let mut diagnostic = Diagnostic {
                     range,
@@ -170,6 +505,578 @@ impl DiagnosticsManager {
     }
 }
 
+/// Diagnostic code of requirements that use an aliased package family.
+pub const ALIAS_CODE: &str = "R107";
+
+/// Report requirements on aliased families, suggesting the canonical name.
+///
+/// The canonical name is stored in the diagnostic's `data` so the quick fix
+/// does not need the alias map.
+fn alias_diagnostics(
+    content: &str,
+    index: &LineIndex,
+    aliases: &HashMap<String, String>,
+) -> Vec<Diagnostic> {
+    if aliases.is_empty() {
+        return Vec::new();
+    }
+
+    find_requirements(content)
+        .into_iter()
+        .filter_map(|requirement| {
+            let name = requirement.name_text();
+            let canonical = aliases.get(name)?;
+            Some(Diagnostic {
+                range: index.range(requirement.name),
+                severity: Some(DiagnosticSeverity::INFORMATION),
+                code: Some(NumberOrString::String(ALIAS_CODE.to_string())),
+                source: Some("rez-lsp".to_string()),
+                message: format!(
+                    "Package '{}' has been renamed to '{}'\nSuggestion: Require '{}' instead",
+                    name, canonical, canonical
+                ),
+                data: Some(serde_json::json!({ "canonical": canonical })),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Diagnostic code of requirements resolving to a pre-release version.
+pub const PRERELEASE_CODE: &str = "R108";
+
+/// Report requirements whose picked version in the index is a pre-release.
+fn prerelease_diagnostics(
+    content: &str,
+    index: &LineIndex,
+    discovery: &PackageDiscoveryImpl,
+) -> Vec<Diagnostic> {
+    find_requirements(content)
+        .into_iter()
+        .filter(|span| !span.is_ephemeral())
+        .filter_map(|span| {
+            // Conflicts exclude versions rather than picking one
+            let requirement = Requirement::parse(&span.text)
+                .ok()
+                .filter(|r| !r.conflict)?;
+            let picked = discovery.pick_version(&requirement.name, &requirement.constraint)?;
+            if !picked.version.is_prerelease() {
+                return None;
+            }
+            Some(Diagnostic {
+                range: index.range(span.range),
+                severity: Some(DiagnosticSeverity::INFORMATION),
+                code: Some(NumberOrString::String(PRERELEASE_CODE.to_string())),
+                source: Some("rez-lsp".to_string()),
+                message: format!(
+                    "'{}' resolves to pre-release version {}",
+                    span.text, picked.version
+                ),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Diagnostic code of requirements that can resolve to a version flagged by
+/// the package advisories.
+pub const ADVISORY_CODE: &str = "R111";
+
+/// Report requirements that can resolve to an indexed version flagged by an
+/// advisory.
+///
+/// Each requirement is reported once, with the severity of the most severe
+/// advisory and the newest version it flags.
+fn advisory_diagnostics(
+    content: &str,
+    index: &LineIndex,
+    discovery: &PackageDiscoveryImpl,
+    advisories: &AdvisoryList,
+) -> Vec<Diagnostic> {
+    find_requirements(content)
+        .into_iter()
+        .filter(|span| !span.is_ephemeral())
+        .filter_map(|span| {
+            // Conflicts exclude versions rather than resolving to them
+            let requirement = Requirement::parse(&span.text)
+                .ok()
+                .filter(|r| !r.conflict)?;
+            let family = discovery.config().canonical_package_name(&requirement.name);
+            let (package, advisory) = discovery
+                .packages()
+                .get(family)?
+                .iter()
+                .filter(|package| requirement.constraint.satisfies(&package.version))
+                .filter_map(|package| {
+                    Some((package, advisories.flagging(family, &package.version)?))
+                })
+                .max_by_key(|(_, advisory)| advisory.severity)?;
+
+            let reason = advisory
+                .reason
+                .as_ref()
+                .map(|reason| format!(": {}", reason))
+                .unwrap_or_default();
+            Some(Diagnostic {
+                range: index.range(span.range),
+                severity: Some(advisory.severity.into()),
+                code: Some(NumberOrString::String(ADVISORY_CODE.to_string())),
+                code_description: advisory
+                    .url
+                    .as_deref()
+                    .and_then(|url| Url::parse(url).ok())
+                    .map(|href| CodeDescription { href }),
+                source: Some("rez-lsp".to_string()),
+                message: format!(
+                    "'{}' can resolve to flagged version {}-{}{}",
+                    span.text, family, package.version, reason
+                ),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Diagnostic code of tools provided by several packages of the resolved
+/// context.
+pub const TOOL_CONFLICT_CODE: &str = "R116";
+
+/// Get the tools provided by more than one package of a context, with their
+/// providers.
+fn tool_providers(context: &ResolvedContext) -> BTreeMap<&str, Vec<&Package>> {
+    let mut providers: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+    for package in &context.packages {
+        for tool in &package.tools {
+            providers.entry(&**tool).or_default().push(package);
+        }
+    }
+    providers.retain(|_, packages| packages.len() > 1);
+    providers
+}
+
+/// Read the definitions of the packages providing a tool provided by
+/// another package of the context too, by family.
+///
+/// The files are read on the blocking pool, for the `PATH` order of
+/// [`tool_conflict_diagnostics`].
+async fn provider_sources(context: &ResolvedContext) -> HashMap<String, String> {
+    let mut paths: Vec<(String, PathBuf)> = tool_providers(context)
+        .into_values()
+        .flatten()
+        .map(|package| (package.name.to_string(), package.path.join("package.py")))
+        .collect();
+    if paths.is_empty() {
+        return HashMap::new();
+    }
+    paths.sort();
+    paths.dedup();
+    tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .filter_map(|(name, path)| Some((name, read_source(&path).ok()?)))
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Report tools provided by more than one package of the context the
+/// requirements resolve to.
+///
+/// The message names the package whose tool comes first on `PATH`, as
+/// ordered by [`path_precedence`] from the definitions returned by `load`.
+/// The diagnostic is attached to the first requirement on one of the
+/// providers, or to the first requirement when they are all indirect.
+fn tool_conflict_diagnostics(
+    content: &str,
+    index: &LineIndex,
+    context: &ResolvedContext,
+    load: impl Fn(&Package) -> Option<String>,
+    mappings: &[PathMapping],
+) -> Vec<Diagnostic> {
+    let providers = tool_providers(context);
+    if providers.is_empty() {
+        return Vec::new();
+    }
+
+    let colliding: HashSet<&str> = providers
+        .values()
+        .flatten()
+        .map(|package| &*package.name)
+        .collect();
+    let precedence = path_precedence(context, |package| {
+        colliding
+            .contains(&*package.name)
+            .then(|| load(package))
+            .flatten()
+    });
+    let rank = |package: &Package| {
+        precedence
+            .iter()
+            .position(|ranked| ranked.name == package.name)
+            .unwrap_or(usize::MAX)
+    };
+
+    let spans: Vec<_> = find_requirements(content)
+        .into_iter()
+        .filter(|span| !span.is_ephemeral())
+        .collect();
+    let Some(first_span) = spans.first() else {
+        return Vec::new();
+    };
+
+    providers
+        .into_iter()
+        .map(|(tool, mut packages)| {
+            packages.sort_by_key(|package| rank(package));
+            let names: Vec<String> = packages
+                .iter()
+                .map(|package| format!("{}-{}", package.name, package.version))
+                .collect();
+            let span = spans
+                .iter()
+                .find(|span| {
+                    packages
+                        .iter()
+                        .any(|package| span.name_text() == &*package.name)
+                })
+                .unwrap_or(first_span);
+            let related_information = packages
+                .iter()
+                .zip(&names)
+                .filter_map(|(package, name)| {
+                    let uri = client_uri(&package.path.join("package.py"), mappings)?;
+                    let message = if name == &names[0] {
+                        format!("{} provides '{}' first on PATH", name, tool)
+                    } else {
+                        format!("{} provides '{}', shadowed by {}", name, tool, names[0])
+                    };
+                    Some(DiagnosticRelatedInformation {
+                        location: Location::new(uri, Range::default()),
+                        message,
+                    })
+                })
+                .collect();
+            Diagnostic {
+                range: index.range(span.range),
+                severity: Some(DiagnosticSeverity::INFORMATION),
+                code: Some(NumberOrString::String(TOOL_CONFLICT_CODE.to_string())),
+                source: Some("rez-lsp".to_string()),
+                message: format!(
+                    "Tool '{}' is provided by {}; {} comes first on PATH",
+                    tool,
+                    names.join(", "),
+                    names[0]
+                ),
+                related_information: Some(related_information),
+                data: Some(serde_json::json!({ "tool": tool, "providers": names })),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Diagnostic code of variants the resolver does not try within its limits.
+pub const VARIANT_LIMIT_CODE: &str = "R109";
+
+/// Report variant expansion the resolver limits would cut short.
+///
+/// Variants of the document past the fan-out limit are flagged, as is the
+/// requirement contributing most to a variant expansion, counted over the
+/// document and its direct requirements, that exceeds the total limit.
+fn variant_limit_diagnostics(
+    content: &str,
+    index: &LineIndex,
+    discovery: &PackageDiscoveryImpl,
+) -> Vec<Diagnostic> {
+    let config = discovery.config();
+    let warning = |range: SourceRange, message: String| Diagnostic {
+        range: index.range(range),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String(VARIANT_LIMIT_CODE.to_string())),
+        source: Some("rez-lsp".to_string()),
+        message,
+        ..Default::default()
+    };
+    let spans = find_requirements(content);
+    let mut diagnostics = Vec::new();
+
+    let variants = discovery.parse_variants(content).len();
+    if let Some(first_skipped) = spans
+        .iter()
+        .find(|span| span.variant >= Some(config.max_variant_fanout))
+    {
+        diagnostics.push(warning(
+            first_skipped.range,
+            format!(
+                "Only the first {} of {} variants are tried when resolving (REZ_LSP_MAX_VARIANT_FANOUT)",
+                config.max_variant_fanout, variants
+            ),
+        ));
+    }
+
+    // Variant counts of the picked versions of the direct requirements
+    let mut fanouts: Vec<(&str, SourceRange, usize)> = spans
+        .iter()
+        .filter(|span| span.attribute == "requires" && !span.is_ephemeral())
+        .filter_map(|span| {
+            let requirement = Requirement::parse(&span.text)
+                .ok()
+                .filter(|r| !r.conflict)?;
+            let picked = discovery.pick_version(&requirement.name, &requirement.constraint)?;
+            let fanout = picked.variants.len().min(config.max_variant_fanout);
+            (fanout > 1).then(|| (span.name_text(), span.range, fanout))
+        })
+        .collect();
+    fanouts.sort_by_key(|(_, _, fanout)| std::cmp::Reverse(*fanout));
+    let expansion = fanouts.iter().fold(
+        variants.clamp(1, config.max_variant_fanout),
+        |total, (_, _, fanout)| total.saturating_mul(*fanout),
+    );
+    if let Some((_, range, _)) = fanouts.first().filter(|_| expansion > config.max_variants) {
+        let contributors: Vec<String> = fanouts
+            .iter()
+            .map(|(name, _, fanout)| format!("'{}' has {}", name, fanout))
+            .collect();
+        diagnostics.push(warning(
+            *range,
+            format!(
+                "Resolving may try up to {} variant combinations, more than the limit of {} (REZ_LSP_MAX_VARIANTS): {} variants",
+                expansion,
+                config.max_variants,
+                contributors.join(", ")
+            ),
+        ));
+    }
+
+    diagnostics
+}
+
+/// Diagnostic code of requirements with a newer version within their
+/// constraint.
+pub const COMPATIBLE_UPDATE_CODE: &str = "R114";
+
+/// Diagnostic code of requirements with a newer version outside their
+/// constraint.
+pub const OUTSIDE_UPDATE_CODE: &str = "R115";
+
+/// Report requirements with newer versions in the index.
+///
+/// A newer version within the constraint resolves without an edit, so it is
+/// only reported, as information, when the lower bound names an indexed
+/// version. A newer version
+/// outside the constraint is reported with the widened constraint in the
+/// diagnostic's `data`, for the quick fix.
+fn update_diagnostics(
+    content: &str,
+    index: &LineIndex,
+    discovery: &PackageDiscoveryImpl,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for span in find_requirements(content) {
+        if span.is_ephemeral() {
+            continue;
+        }
+        // Conflicts exclude versions rather than resolving to them
+        let Some(requirement) = Requirement::parse(&span.text)
+            .ok()
+            .filter(|r| !r.conflict && r.constraint != VersionConstraint::Any)
+        else {
+            continue;
+        };
+        let Some(latest) = discovery.latest_version(&requirement.name) else {
+            continue;
+        };
+        let family = discovery.config().canonical_package_name(&requirement.name);
+        let newest_matching = discovery
+            .matching_versions(
+                family,
+                &requirement.constraint,
+                None,
+                VersionOrder::Descending,
+            )
+            .into_iter()
+            .find(|package| !package.version.is_prerelease());
+        let lower_bound = lower_bound(&requirement.constraint);
+
+        if let (Some(newest), Some(lower)) = (newest_matching, lower_bound) {
+            let indexed = discovery
+                .matching_versions(
+                    family,
+                    &VersionConstraint::Exact(lower.clone()),
+                    Some(1),
+                    VersionOrder::Descending,
+                )
+                .len()
+                == 1;
+            if indexed && newest.version > *lower {
+                diagnostics.push(Diagnostic {
+                    range: index.range(span.range),
+                    severity: Some(DiagnosticSeverity::INFORMATION),
+                    code: Some(NumberOrString::String(COMPATIBLE_UPDATE_CODE.to_string())),
+                    source: Some("rez-lsp".to_string()),
+                    message: format!(
+                        "Newer compatible version {}-{} is available; it resolves without an edit",
+                        family, newest.version
+                    ),
+                    ..Default::default()
+                });
+            }
+        }
+
+        if requirement.constraint.satisfies(&latest.version)
+            || newest_matching.is_some_and(|newest| newest.version >= latest.version)
+        {
+            continue;
+        }
+        let major = |version: &Version| version.tokens.first().cloned();
+        let new_major = newest_matching
+            .map(|newest| &newest.version)
+            .or(lower_bound)
+            .is_none_or(|current| major(current) != major(&latest.version));
+        // The prefix and name are kept, the constraint replaced
+        let widened =
+            widened_constraint(&requirement.constraint, &latest.version).and_then(|widened| {
+                let kept = span
+                    .text
+                    .strip_suffix(span.constraint_text().unwrap_or_default())?;
+                Some(format!("{}{}", kept, widened))
+            });
+        let suggestion = widened
+            .as_ref()
+            .map(|widened| format!("\nSuggestion: Widen the constraint to '{}'", widened))
+            .unwrap_or_default();
+        diagnostics.push(Diagnostic {
+            range: index.range(span.range),
+            severity: Some(DiagnosticSeverity::INFORMATION),
+            code: Some(NumberOrString::String(OUTSIDE_UPDATE_CODE.to_string())),
+            source: Some("rez-lsp".to_string()),
+            message: format!(
+                "Newer {}version {}-{} is available outside '{}'{}",
+                if new_major { "major " } else { "" },
+                family,
+                latest.version,
+                span.text,
+                suggestion
+            ),
+            data: widened.map(|widened| serde_json::json!({ "widened": widened })),
+            ..Default::default()
+        });
+    }
+    diagnostics
+}
+
+/// Get the lowest version a constraint names, if any.
+fn lower_bound(constraint: &VersionConstraint) -> Option<&Version> {
+    match constraint {
+        VersionConstraint::Exact(version)
+        | VersionConstraint::Family(version)
+        | VersionConstraint::GreaterEqual(version)
+        | VersionConstraint::Range { min: version, .. } => Some(version),
+        _ => None,
+    }
+}
+
+/// Build a constraint, including its leading `-`, keeping the lower bound
+/// of `constraint` and accepting every version of the major of `latest`.
+///
+/// Alternatives are left alone, as is a `latest` not starting with a number.
+fn widened_constraint(constraint: &VersionConstraint, latest: &Version) -> Option<String> {
+    let VersionToken::Number(major) = latest.tokens.first()? else {
+        return None;
+    };
+    let upper = major + 1;
+    match constraint {
+        VersionConstraint::Less(_) => Some(format!("-<{}", upper)),
+        VersionConstraint::Or(_) | VersionConstraint::Any => None,
+        _ => Some(format!("-{}+<{}", lower_bound(constraint)?, upper)),
+    }
+}
+
+/// Diagnostic code of package definitions opened from the release repository.
+pub const RELEASED_PACKAGE_CODE: &str = "R110";
+
+/// Check if `uri` is a package.py file under the release repository.
+fn is_released_definition(uri: &Url, release_path: Option<&Path>) -> bool {
+    let (Some(release_path), Ok(path)) = (release_path, uri.to_file_path()) else {
+        return false;
+    };
+    path.file_name().is_some_and(|name| name == "package.py") && path.starts_with(release_path)
+}
+
+/// Warn that edits of a released package definition have no effect until the
+/// package is released again.
+///
+/// The warning spans the first line so it is visible at the top of the file.
+fn released_package_diagnostic(content: &str, index: &LineIndex) -> Diagnostic {
+    let first_line = content.lines().next().unwrap_or("");
+    Diagnostic {
+        range: index.range(SourceRange {
+            line: 0,
+            start: 0,
+            end: first_line.chars().count() as u32,
+        }),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String(RELEASED_PACKAGE_CODE.to_string())),
+        source: Some("rez-lsp".to_string()),
+        message: "Editing a released package: changes won't take effect without a re-release\n\
+                  Suggestion: Edit the package's source and release a new version"
+            .to_string(),
+        ..Default::default()
+    }
+}
+
+/// Diagnostic code of build scripts missing from the package directory.
+pub const MISSING_BUILD_SCRIPT_CODE: &str = "R112";
+
+/// Report `build_command` scripts that do not exist in the package directory.
+///
+/// Only scripts relative to the package are checked; scripts under
+/// `{install_path}` or `{build_path}` are created by the build itself, and
+/// nothing is reported when the package directory is not on this machine.
+async fn missing_build_script_diagnostics(
+    content: &str,
+    index: &LineIndex<'_>,
+    directory: &Path,
+) -> Vec<Diagnostic> {
+    if !tokio::fs::metadata(directory)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
+    {
+        return Vec::new();
+    }
+    let mut missing = Vec::new();
+    for link in find_links(content) {
+        let Some(relative) = package_relative_path(&link.target) else {
+            continue;
+        };
+        if link.kind == LinkKind::BuildScript
+            && !tokio::fs::try_exists(directory.join(relative))
+                .await
+                .unwrap_or(false)
+        {
+            missing.push(link);
+        }
+    }
+    missing
+        .into_iter()
+        .map(|link| Diagnostic {
+            range: index.range(link.range),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String(
+                MISSING_BUILD_SCRIPT_CODE.to_string(),
+            )),
+            source: Some("rez-lsp".to_string()),
+            message: format!(
+                "Build script '{}' does not exist in the package directory\n\
+                 Suggestion: Create the script or fix its path",
+                link.target
+            ),
+            ..Default::default()
+        })
+        .collect()
+}
+
 /// Statistics about validation across all files.
 #[derive(Debug, Clone)]
 pub struct ValidationStats {
@@ -209,3 +1116,411 @@ impl ValidationStats {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::{repository_config, scan_repositories, write_package};
+    use tower_lsp::lsp_types::Position;
+
+    #[test]
+    fn test_alias_diagnostics() {
+        let aliases = HashMap::from([("usd".to_string(), "openusd".to_string())]);
+        let content = "name = \"usd_view\"\nrequires = [\n    \"python-3+\",\n    \"~usd-22+\",\n    # \"usd\"\n]\ntools = [\"usd\"]\n";
+
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+        let diagnostics = alias_diagnostics(content, &index, &aliases);
+        assert_eq!(diagnostics.len(), 1);
+
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(3, 6));
+        assert_eq!(diagnostic.range.end, Position::new(3, 9));
+        assert_eq!(
+            diagnostic.code,
+            Some(NumberOrString::String(ALIAS_CODE.to_string()))
+        );
+        assert_eq!(
+            diagnostic.data,
+            Some(serde_json::json!({ "canonical": "openusd" }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_progressive_validation() {
+        let manager = DiagnosticsManager::new().unwrap();
+        let uri = Url::parse("file:///packages/tool/package.py").unwrap();
+        let content = "name = \"tool\" \nversion = \"1.0.0\"\nrequires = [\"python-\"]\n";
+        let expected = manager.check_file(&uri, content).await.unwrap();
+
+        let (partial, mut batches) = mpsc::unbounded_channel();
+        let diagnostics = manager
+            .validate_file_progressively(&uri, content, partial)
+            .await
+            .unwrap();
+        assert_eq!(diagnostics, expected);
+        assert_eq!(manager.get_diagnostics(&uri).await, expected);
+
+        // One batch per validator, each holding the diagnostics found so far
+        let mut sizes = Vec::new();
+        while let Some(batch) = batches.recv().await {
+            sizes.push(batch.len());
+        }
+        assert_eq!(sizes.len(), 2);
+        assert!(sizes[0] <= sizes[1]);
+        assert!(sizes[1] > 0);
+    }
+
+    #[tokio::test]
+    async fn test_missing_build_script_diagnostics() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("build.py"), "").unwrap();
+        let content = "name = \"tool\"\n\
+                       build_command = \"python {root}/build.py && bash {root}/post.sh \
+                       {install_path}/setup.sh\"\n";
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+
+        let diagnostics = missing_build_script_diagnostics(content, &index, temp_dir.path()).await;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String(
+                MISSING_BUILD_SCRIPT_CODE.to_string()
+            ))
+        );
+        assert!(diagnostics[0].message.contains("{root}/post.sh"));
+        assert_eq!(diagnostics[0].range.start, Position::new(1, 48));
+
+        // Definitions outside an existing directory are not checked
+        let manager = DiagnosticsManager::new().unwrap();
+        let uri = Url::from_file_path(temp_dir.path().join("missing/package.py")).unwrap();
+        let diagnostics = manager.check_file(&uri, content).await.unwrap();
+        assert!(diagnostics.iter().all(|diagnostic| diagnostic.code
+            != Some(NumberOrString::String(
+                MISSING_BUILD_SCRIPT_CODE.to_string()
+            ))));
+    }
+
+    #[tokio::test]
+    async fn test_released_package_diagnostics() {
+        let release = std::env::temp_dir().join("release");
+        let manager = DiagnosticsManager::new().unwrap();
+        manager
+            .set_release_packages_path(Some(release.clone()))
+            .await;
+        let content = "name = \"maya_tools\"\nversion = \"1.0.0\"\n";
+
+        let released = Url::from_file_path(release.join("maya_tools/1.0.0/package.py")).unwrap();
+        assert!(manager.is_released_definition(&released).await);
+        let diagnostics = manager.check_file(&released, content).await.unwrap();
+        let warning = diagnostics
+            .iter()
+            .find(|d| d.code == Some(NumberOrString::String(RELEASED_PACKAGE_CODE.to_string())))
+            .unwrap();
+        assert_eq!(warning.severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(warning.range.end, Position::new(0, 19));
+
+        let source =
+            Url::from_file_path(std::env::temp_dir().join("src/maya_tools/package.py")).unwrap();
+        assert!(!manager.is_released_definition(&source).await);
+        let payload =
+            Url::from_file_path(release.join("maya_tools/1.0.0/python/setup.py")).unwrap();
+        assert!(!manager.is_released_definition(&payload).await);
+
+        // Files that are not package definitions only get the validation note
+        let module = "import os\nimport sys\nimport setuptools\n";
+        let diagnostics = manager.check_file(&released, module).await.unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("V004".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prerelease_diagnostics() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for version in ["2024.1", "2025.0-beta1"] {
+            write_package(temp_dir.path(), "maya", version, "name = 'maya'\n");
+        }
+        let discovery = scan_repositories(&[temp_dir.path()]).await;
+
+        let content = "requires = ['maya-2024', 'maya-2025', '!maya-2025', 'nuke']\n";
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+        let diagnostics = prerelease_diagnostics(content, &index, &discovery);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 26));
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String(PRERELEASE_CODE.to_string()))
+        );
+        assert!(diagnostics[0].message.contains("2025.0-beta1"));
+    }
+
+    #[tokio::test]
+    async fn test_update_diagnostics() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for version in ["2.1.0", "2.3.0", "3.0.0"] {
+            write_package(temp_dir.path(), "pyside", version, "name = 'pyside'\n");
+        }
+        let discovery = scan_repositories(&[temp_dir.path()]).await;
+
+        let content = "requires = ['pyside-2.1.0+<3', '~pyside-2', 'pyside-2+', '!pyside-3']\n";
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+        let diagnostics = update_diagnostics(content, &index, &discovery);
+        let codes: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.range.start.character, d.code.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                (
+                    13,
+                    NumberOrString::String(COMPATIBLE_UPDATE_CODE.to_string())
+                ),
+                (13, NumberOrString::String(OUTSIDE_UPDATE_CODE.to_string())),
+                (32, NumberOrString::String(OUTSIDE_UPDATE_CODE.to_string())),
+            ]
+        );
+        assert!(diagnostics[0].message.contains("pyside-2.3.0"));
+        // Reported at the severity documented for R114
+        assert_eq!(
+            diagnostics[0].severity,
+            Some(DiagnosticSeverity::INFORMATION)
+        );
+        assert!(diagnostics[1]
+            .message
+            .contains("major version pyside-3.0.0"));
+        assert_eq!(
+            diagnostics[1].data,
+            Some(serde_json::json!({ "widened": "pyside-2.1.0+<4" }))
+        );
+        assert_eq!(
+            diagnostics[2].data,
+            Some(serde_json::json!({ "widened": "~pyside-2+<4" }))
+        );
+    }
+
+    #[test]
+    fn test_tool_conflict_diagnostics() {
+        let package = |name: &str, version: &str, requires: &[&str], tools: &[&str]| {
+            Package::builder()
+                .name(name)
+                .version(version)
+                .requires(requires.iter().map(|r| Requirement::parse(r).unwrap()))
+                .tools(tools.iter().copied())
+                .path(format!("/packages/{}/{}", name, version))
+                .build()
+        };
+        let context = ResolvedContext::builder()
+            .package(package("maya", "2024.1", &["python"], &["maya", "python"]))
+            .package(package("python", "3.11", &[], &["python"]))
+            .package(package("nuke", "15.0", &[], &["nuke"]))
+            .build();
+
+        let content = "requires = ['nuke-15', 'python-3']\n";
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+        let diagnostics = tool_conflict_diagnostics(content, &index, &context, |_| None, &[]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 24));
+        assert_eq!(
+            diagnostics[0].message,
+            "Tool 'python' is provided by maya-2024.1, python-3.11; maya-2024.1 comes first on PATH"
+        );
+        assert_eq!(
+            diagnostics[0].related_information.as_ref().unwrap()[1].message,
+            "python-3.11 provides 'python', shadowed by maya-2024.1"
+        );
+
+        // Appending to PATH yields to the dependencies
+        let diagnostics = tool_conflict_diagnostics(
+            content,
+            &index,
+            &context,
+            |package| {
+                (&*package.name == "maya")
+                    .then(|| "def commands():\n    env.PATH.append('{root}/bin')\n".to_string())
+            },
+            &[],
+        );
+        assert_eq!(
+            diagnostics[0].data,
+            Some(serde_json::json!({
+                "tool": "python",
+                "providers": ["python-3.11", "maya-2024.1"]
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_conflicts_of_validated_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let packages = [
+            ("maya", "2024.1", "requires = ['python']\ntools = ['maya', 'python']\ndef commands():\n    env.PATH.append('{root}/bin')\n"),
+            ("python", "3.11", "tools = ['python']\n"),
+        ];
+        for (name, version, body) in packages {
+            let content = format!("name = '{}'\n{}", name, body);
+            write_package(temp_dir.path(), name, version, &content);
+        }
+        let discovery = scan_repositories(&[temp_dir.path()]).await;
+        let discovery = Arc::new(RwLock::new(Some(discovery)));
+        let manager = DiagnosticsManager::new()
+            .unwrap()
+            .with_package_index(discovery.clone());
+
+        let uri = Url::parse("file:///ws/shot/package.py").unwrap();
+        let content = "name = 'shot'\nrequires = ['maya-2024', 'python-3']\n";
+        let diagnostics = manager.check_file(&uri, content).await.unwrap();
+        let conflict = diagnostics
+            .iter()
+            .find(|d| d.code == Some(NumberOrString::String(TOOL_CONFLICT_CODE.to_string())))
+            .unwrap();
+        // The PATH order comes from the providers' definitions on disk
+        assert_eq!(
+            conflict.data,
+            Some(serde_json::json!({
+                "tool": "python",
+                "providers": ["python-3.11", "maya-2024.1"]
+            }))
+        );
+
+        // The context is kept for the next validation
+        let guard = discovery.read().await;
+        let requirements = guard.as_ref().unwrap().parse_requires(content);
+        assert!(matches!(
+            guard.as_ref().unwrap().cached_resolve(&requirements),
+            Ok(Some(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_advisory_diagnostics() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for version in ["1.0.2", "1.1.1", "3.0.8"] {
+            write_package(temp_dir.path(), "openssl", version, "name = 'openssl'\n");
+        }
+        let discovery = scan_repositories(&[temp_dir.path()]).await;
+
+        let advisories = AdvisoryList::from_json(
+            r#"{"advisories": [
+                {"requirement": "openssl-<1.1", "severity": "error", "reason": "CVE-2022-0778",
+                 "url": "https://example.com/CVE-2022-0778"},
+                {"requirement": "openssl-1", "severity": "hint", "reason": "Deprecated"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let content = "requires = ['openssl-1', 'openssl-1.1', 'openssl-3', '!openssl-1']\n";
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+        let diagnostics = advisory_diagnostics(content, &index, &discovery, &advisories);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 13));
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(
+            diagnostics[0].message,
+            "'openssl-1' can resolve to flagged version openssl-1.0.2: CVE-2022-0778"
+        );
+        assert!(diagnostics[0].code_description.is_some());
+        assert_eq!(diagnostics[1].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(
+            diagnostics[1].message,
+            "'openssl-1.1' can resolve to flagged version openssl-1.1.1: Deprecated"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_variant_limit_diagnostics() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for (name, variants) in [("mtoa", 6), ("usd", 5)] {
+            let variants: Vec<String> = (0..variants)
+                .map(|i| format!("['python-3.{}']", i))
+                .collect();
+            write_package(
+                temp_dir.path(),
+                name,
+                "1.0",
+                &format!("name = '{}'\nvariants = [{}]\n", name, variants.join(", ")),
+            );
+        }
+        let mut config = repository_config(&[temp_dir.path()]);
+        config.max_variants = 40;
+        config.max_variant_fanout = 2;
+        let mut discovery = PackageDiscoveryImpl::new(config);
+        discovery.scan_shared().await.unwrap();
+
+        let content = "requires = ['usd', 'mtoa']\nvariants = [['maya-2024'], ['maya-2025'], ['maya-2026']]\n";
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+        let diagnostics = variant_limit_diagnostics(content, &index, &discovery);
+        // 2 * 2 * 2 combinations stay within the total limit
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(1, 44));
+        assert!(diagnostics[0]
+            .message
+            .starts_with("Only the first 2 of 3 variants"));
+
+        let mut config = discovery.config().clone();
+        config.max_variants = 4;
+        config.max_variant_fanout = 16;
+        let mut discovery = PackageDiscoveryImpl::new(config);
+        discovery.scan_shared().await.unwrap();
+        let diagnostics = variant_limit_diagnostics(content, &index, &discovery);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 20));
+        assert!(diagnostics[0]
+            .message
+            .contains("up to 90 variant combinations, more than the limit of 4"));
+        assert!(diagnostics[0]
+            .message
+            .ends_with("'mtoa' has 6, 'usd' has 5 variants"));
+    }
+
+    #[tokio::test]
+    async fn test_diagnostic_columns_count_utf16_units() {
+        let manager = DiagnosticsManager::new().unwrap();
+        let uri = Url::parse("file:///packages/tools/1.0.0/package.py").unwrap();
+        let content = "name = 'tools'\ndescription = '🎬 工具'  \n";
+
+        let diagnostics = manager.validate_file(&uri, content).await.unwrap();
+        let trailing = diagnostics
+            .iter()
+            .find(|d| d.code == Some(NumberOrString::String("W291".to_string())))
+            .unwrap();
+        assert_eq!(trailing.range.start, Position::new(1, 21));
+        assert_eq!(trailing.range.end, Position::new(1, 23));
+
+        // Clients supporting UTF-8 positions count bytes
+        manager.set_position_encoding(PositionEncoding::Utf8).await;
+        let diagnostics = manager.validate_file(&uri, content).await.unwrap();
+        let trailing = diagnostics
+            .iter()
+            .find(|d| d.code == Some(NumberOrString::String("W291".to_string())))
+            .unwrap();
+        assert_eq!(trailing.range.start, Position::new(1, 27));
+        assert_eq!(trailing.range.end, Position::new(1, 29));
+    }
+
+    #[tokio::test]
+    async fn test_validate_revision_reuses_result() {
+        let manager = DiagnosticsManager::new().unwrap();
+        let uri = Url::parse("file:///packages/tools/1.0.0/package.py").unwrap();
+        let content = "name = 'tools'\nrequires = ['usd-22+']\n";
+
+        let (first_id, first) = manager.validate_revision(&uri, content, 1).await.unwrap();
+        let (second_id, second) = manager.validate_revision(&uri, "", 1).await.unwrap();
+        assert_eq!(first_id, second_id);
+        assert_eq!(first, second);
+
+        let (changed_id, _) = manager.validate_revision(&uri, content, 2).await.unwrap();
+        assert_ne!(changed_id, first_id);
+
+        manager
+            .set_package_aliases(HashMap::from([("usd".to_string(), "openusd".to_string())]))
+            .await;
+        let (aliased_id, aliased) = manager.validate_revision(&uri, content, 2).await.unwrap();
+        assert_ne!(aliased_id, changed_id);
+        assert!(aliased
+            .iter()
+            .any(|d| d.code == Some(NumberOrString::String(ALIAS_CODE.to_string()))));
+    }
+}
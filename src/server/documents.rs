@@ -0,0 +1,336 @@
+//! Bounded store of open document contents, and the kinds of documents the
+//! server handles.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tower_lsp::lsp_types::Url;
+use tracing::warn;
+
+use crate::discovery::glob_to_regex;
+use crate::server::virtual_documents::PACKAGE_SCHEME;
+
+/// URI schemes of unsaved scratch documents: untitled buffers and notebook
+/// cells, e.g. `untitled:Untitled-1`.
+pub const SCRATCH_SCHEMES: &[&str] = &["untitled", "vscode-notebook-cell"];
+
+/// Check whether `uri` is an unsaved scratch document.
+pub fn is_scratch_document(uri: &Url) -> bool {
+    SCRATCH_SCHEMES.contains(&uri.scheme())
+}
+
+/// Check whether the server handles documents with the scheme of `uri`:
+/// files, package virtual documents and scratch documents.
+pub fn is_supported_document(uri: &Url) -> bool {
+    matches!(uri.scheme(), "file" | PACKAGE_SCHEME) || is_scratch_document(uri)
+}
+
+/// Language IDs clients register package definitions under, besides
+/// `python` for files named like one.
+pub const PACKAGE_LANGUAGE_IDS: &[&str] = &["rez", "rez-packagefile"];
+
+/// Check whether `uri` is a package definition by its name alone: a
+/// `package.py` file, or a scratch document the client sent as one.
+pub fn is_package_definition(uri: &Url) -> bool {
+    is_scratch_document(uri) || (is_supported_document(uri) && uri.path().ends_with("package.py"))
+}
+
+/// Language IDs of open documents and filename patterns of package
+/// definitions.
+#[derive(Debug, Default)]
+struct Kinds {
+    patterns: Vec<Regex>,
+    language_ids: HashMap<Url, String>,
+}
+
+/// Classifier of the documents receiving rez features, shared by the server
+/// components.
+///
+/// On top of [`is_package_definition`], documents the client opened with one
+/// of the [`PACKAGE_LANGUAGE_IDS`] and files matching the configured
+/// [`Config::package_file_patterns`] are package definitions, e.g.
+/// `package.py.in` templates. Clones observe the same state.
+///
+/// [`Config::package_file_patterns`]: crate::config::Config::package_file_patterns
+#[derive(Debug, Clone, Default)]
+pub struct DocumentKinds {
+    kinds: Arc<RwLock<Kinds>>,
+}
+
+impl DocumentKinds {
+    /// Replace the filename patterns of package definitions.
+    ///
+    /// Patterns are gitignore-style globs matched against the file name, or
+    /// against the end of the path when they contain a `/`.
+    pub fn set_patterns(&self, patterns: &[String]) {
+        let patterns = patterns
+            .iter()
+            .filter_map(|pattern| {
+                let regex = format!(
+                    "^(?:.*/)?{}$",
+                    glob_to_regex(pattern.trim_start_matches('/'))
+                );
+                Regex::new(&regex)
+                    .inspect_err(|e| {
+                        warn!("Ignoring invalid package file pattern '{}': {}", pattern, e)
+                    })
+                    .ok()
+            })
+            .collect();
+        self.kinds
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .patterns = patterns;
+    }
+
+    /// Record the language ID a document was opened with.
+    pub fn open(&self, uri: Url, language_id: &str) {
+        self.kinds
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .language_ids
+            .insert(uri, language_id.to_string());
+    }
+
+    /// Forget the language ID of a closed document.
+    pub fn close(&self, uri: &Url) {
+        self.kinds
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .language_ids
+            .remove(uri);
+    }
+
+    /// Check whether `uri` is a package definition.
+    pub fn is_package_definition(&self, uri: &Url) -> bool {
+        if is_package_definition(uri) {
+            return true;
+        }
+        if !is_supported_document(uri) {
+            return false;
+        }
+        let kinds = self.kinds.read().unwrap_or_else(|e| e.into_inner());
+        kinds
+            .language_ids
+            .get(uri)
+            .is_some_and(|id| PACKAGE_LANGUAGE_IDS.contains(&id.as_str()))
+            || kinds
+                .patterns
+                .iter()
+                .any(|pattern| pattern.is_match(uri.path()))
+    }
+}
+
+/// Get the path validators see for a document.
+///
+/// Scratch documents have no path of their own and get a synthetic
+/// `package.py` path named after their URI, e.g. `/untitled/Untitled-1/package.py`.
+pub fn document_path(uri: &Url) -> String {
+    if is_scratch_document(uri) {
+        format!(
+            "/{}/{}/package.py",
+            uri.scheme(),
+            uri.path().trim_matches('/')
+        )
+    } else {
+        uri.path().to_string()
+    }
+}
+
+/// A stored document and the tick of its last use.
+#[derive(Debug)]
+struct Entry {
+    content: String,
+    revision: u64,
+    last_used: AtomicU64,
+}
+
+/// Document contents keyed by URI, evicting the least recently used document
+/// once `capacity` is exceeded.
+///
+/// Reads bump recency through an atomic tick so lookups only need shared
+/// access to the store.
+#[derive(Debug)]
+pub struct DocumentStore {
+    entries: HashMap<Url, Entry>,
+    capacity: usize,
+    clock: AtomicU64,
+    next_revision: u64,
+}
+
+impl DocumentStore {
+    /// Create a store holding at most `capacity` documents.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: AtomicU64::new(0),
+            next_revision: 0,
+        }
+    }
+
+    /// Get the content of a document, marking it as recently used.
+    pub fn get(&self, uri: &Url) -> Option<String> {
+        let entry = self.entries.get(uri)?;
+        entry.last_used.store(self.tick(), Ordering::Relaxed);
+        Some(entry.content.clone())
+    }
+
+    /// Get the revision of a document's content.
+    ///
+    /// Every insert gets a new revision, so equal revisions mean the content
+    /// has not changed.
+    pub fn revision(&self, uri: &Url) -> Option<u64> {
+        self.entries.get(uri).map(|entry| entry.revision)
+    }
+
+    /// Get the size in bytes of a document.
+    pub fn size(&self, uri: &Url) -> Option<usize> {
+        self.entries.get(uri).map(|entry| entry.content.len())
+    }
+
+    /// Get a single line of a document, marking it as recently used.
+    ///
+    /// Avoids copying the whole content of large documents.
+    pub fn line(&self, uri: &Url, line: u32) -> Option<String> {
+        let entry = self.entries.get(uri)?;
+        entry.last_used.store(self.tick(), Ordering::Relaxed);
+        entry.content.lines().nth(line as usize).map(str::to_string)
+    }
+
+    /// Iterate over the stored documents without marking them as used.
+    pub fn iter(&self) -> impl Iterator<Item = (&Url, &str)> {
+        self.entries
+            .iter()
+            .map(|(uri, entry)| (uri, entry.content.as_str()))
+    }
+
+    /// Insert or replace the content of a document.
+    ///
+    /// Returns the URI of the document evicted to stay within capacity.
+    pub fn insert(&mut self, uri: Url, content: String) -> Option<Url> {
+        self.next_revision += 1;
+        let entry = Entry {
+            content,
+            revision: self.next_revision,
+            last_used: AtomicU64::new(self.tick()),
+        };
+        self.entries.insert(uri, entry);
+
+        if self.entries.len() <= self.capacity {
+            return None;
+        }
+
+        let evicted = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+            .map(|(uri, _)| uri.clone())?;
+        self.entries.remove(&evicted);
+        Some(evicted)
+    }
+
+    /// Remove a document, returning whether it was stored.
+    pub fn remove(&mut self, uri: &Url) -> bool {
+        self.entries.remove(uri).is_some()
+    }
+
+    /// Advance the recency clock.
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(name: &str) -> Url {
+        Url::parse(&format!("file:///packages/{}/package.py", name)).unwrap()
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut store = DocumentStore::new(2);
+        assert_eq!(store.insert(uri("a"), "a".to_string()), None);
+        assert_eq!(store.insert(uri("b"), "b".to_string()), None);
+
+        // Reading `a` makes `b` the least recently used document
+        assert_eq!(store.get(&uri("a")).as_deref(), Some("a"));
+        assert_eq!(store.insert(uri("c"), "c".to_string()), Some(uri("b")));
+
+        assert!(store.get(&uri("b")).is_none());
+        assert!(store.get(&uri("c")).is_some());
+    }
+
+    #[test]
+    fn test_replace_and_remove() {
+        let mut store = DocumentStore::new(2);
+        store.insert(uri("a"), "old".to_string());
+        let old_revision = store.revision(&uri("a")).unwrap();
+        assert_eq!(store.insert(uri("a"), "new".to_string()), None);
+        assert!(store.revision(&uri("a")).unwrap() > old_revision);
+        assert_eq!(store.get(&uri("a")).as_deref(), Some("new"));
+
+        assert_eq!(store.size(&uri("a")), Some(3));
+        assert_eq!(store.line(&uri("a"), 0).as_deref(), Some("new"));
+        assert!(store.line(&uri("a"), 1).is_none());
+
+        assert!(store.remove(&uri("a")));
+        assert!(!store.remove(&uri("a")));
+        assert!(store.get(&uri("a")).is_none());
+    }
+
+    #[test]
+    fn test_document_kinds() {
+        let untitled = Url::parse("untitled:Untitled-1").unwrap();
+        assert!(is_package_definition(&untitled));
+        assert_eq!(document_path(&untitled), "/untitled/Untitled-1/package.py");
+
+        let cell = Url::parse("vscode-notebook-cell:/work/scratch.ipynb#W0sZmlsZQ").unwrap();
+        assert!(is_package_definition(&cell));
+        assert_eq!(
+            document_path(&cell),
+            "/vscode-notebook-cell/work/scratch.ipynb/package.py"
+        );
+
+        assert!(is_package_definition(&uri("a")));
+        assert_eq!(document_path(&uri("a")), "/packages/a/package.py");
+        let readme = Url::parse("file:///packages/a/README.md").unwrap();
+        assert!(is_supported_document(&readme));
+        assert!(!is_package_definition(&readme));
+
+        let remote = Url::parse("vscode-vfs://github/studio/packages/a/package.py").unwrap();
+        assert!(!is_supported_document(&remote));
+        assert!(!is_package_definition(&remote));
+    }
+
+    #[test]
+    fn test_document_kinds_language_ids_and_patterns() {
+        let kinds = DocumentKinds::default();
+        let template = Url::parse("file:///packages/a/package.py.in").unwrap();
+        let custom = Url::parse("file:///packages/b/definition.rez").unwrap();
+        assert!(kinds.is_package_definition(&uri("a")));
+        assert!(!kinds.is_package_definition(&template));
+        assert!(!kinds.is_package_definition(&custom));
+
+        kinds.set_patterns(&["package.py.in".to_string(), "templates/*.py".to_string()]);
+        assert!(kinds.is_package_definition(&template));
+        let nested = Url::parse("file:///repo/templates/tool.py").unwrap();
+        assert!(kinds.is_package_definition(&nested));
+        let other = Url::parse("file:///repo/src/tool.py").unwrap();
+        assert!(!kinds.is_package_definition(&other));
+
+        kinds.open(custom.clone(), "rez");
+        assert!(kinds.is_package_definition(&custom));
+        kinds.close(&custom);
+        assert!(!kinds.is_package_definition(&custom));
+        kinds.open(custom.clone(), "python");
+        assert!(!kinds.is_package_definition(&custom));
+
+        let remote = Url::parse("vscode-vfs://github/studio/packages/a/package.py.in").unwrap();
+        kinds.open(remote.clone(), "rez-packagefile");
+        assert!(!kinds.is_package_definition(&remote));
+    }
+}
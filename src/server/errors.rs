@@ -0,0 +1,380 @@
+//! User-facing reporting of internal errors.
+//!
+//! Errors such as a failed repository scan carry raw OS messages that mean
+//! little to users. [`ErrorReport`] maps them to a stable code, a plain
+//! explanation and suggested next steps for `window/showMessage` and
+//! diagnostics. Recent reports are kept in an [`ErrorLog`] and returned by
+//! the `rez/lastErrors` request so they can be attached to bug reports.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use tokio::sync::RwLock;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, MessageType, NumberOrString, Range};
+
+use crate::core::{ConfigError, DiscoveryError, Error, LspError, ParserError, ResolverError};
+
+/// How serious a reported error is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorSeverity {
+    /// The server keeps working with reduced functionality
+    Warning,
+    /// The operation failed
+    Error,
+}
+
+/// An internal error described for users.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorReport {
+    /// Stable error code, see [`Error::code`]
+    pub code: String,
+    /// Operation that failed, e.g. `Package scan`
+    pub operation: String,
+    /// How serious the error is
+    pub severity: ErrorSeverity,
+    /// Plain explanation of what went wrong
+    pub message: String,
+    /// Suggested next steps
+    pub next_steps: Vec<String>,
+    /// Original error message
+    pub detail: String,
+    /// When the error occurred, in RFC 3339 format
+    pub timestamp: String,
+}
+
+impl ErrorReport {
+    /// Describe an error raised by `operation`.
+    pub fn new(operation: &str, error: &Error) -> Self {
+        let (message, next_steps) = describe(error);
+        Self {
+            code: error.code().to_string(),
+            operation: operation.to_string(),
+            severity: severity(error),
+            message,
+            next_steps,
+            detail: error.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Get the type of `window/showMessage` and `window/logMessage` messages.
+    pub fn message_type(&self) -> MessageType {
+        match self.severity {
+            ErrorSeverity::Warning => MessageType::WARNING,
+            ErrorSeverity::Error => MessageType::ERROR,
+        }
+    }
+
+    /// Get the text shown to users.
+    pub fn user_message(&self) -> String {
+        let mut text = format!(
+            "{} failed: {} [{}]",
+            self.operation, self.message, self.code
+        );
+        if let Some(step) = self.next_steps.first() {
+            text.push_str(&format!(" {}", step));
+        }
+        text
+    }
+
+    /// Get the text written to the client log, including the original error.
+    pub fn log_message(&self) -> String {
+        format!("{} ({})", self.user_message(), self.detail)
+    }
+
+    /// Build a diagnostic reporting the error at the start of a document.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            range: Range::default(),
+            severity: Some(match self.severity {
+                ErrorSeverity::Warning => DiagnosticSeverity::WARNING,
+                ErrorSeverity::Error => DiagnosticSeverity::ERROR,
+            }),
+            code: Some(NumberOrString::String(self.code.clone())),
+            source: Some("rez-lsp".to_string()),
+            message: std::iter::once(format!("{} failed: {}", self.operation, self.message))
+                .chain(self.next_steps.iter().cloned())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ..Default::default()
+        }
+    }
+}
+
+/// Bounded history of recent error reports.
+#[derive(Debug)]
+pub struct ErrorLog {
+    reports: RwLock<VecDeque<ErrorReport>>,
+    capacity: usize,
+}
+
+impl ErrorLog {
+    /// Create a log keeping the last `capacity` reports.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            reports: RwLock::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Record a report, dropping the oldest one when full.
+    pub async fn record(&self, report: ErrorReport) {
+        let mut reports = self.reports.write().await;
+        if reports.len() == self.capacity {
+            reports.pop_front();
+        }
+        reports.push_back(report);
+    }
+
+    /// Get up to `limit` recent reports, newest first.
+    pub async fn recent(&self, limit: Option<usize>) -> Vec<ErrorReport> {
+        self.reports
+            .read()
+            .await
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(self.capacity))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Get the severity of an error.
+fn severity(error: &Error) -> ErrorSeverity {
+    match error {
+        Error::Config(_) | Error::Discovery(_) | Error::Resolver(_) | Error::InvalidPath(_) => {
+            ErrorSeverity::Warning
+        }
+        Error::Parser(_) | Error::Lsp(_) | Error::Io(_) | Error::Other(_) => ErrorSeverity::Error,
+    }
+}
+
+/// Kind of an OS error embedded in an error message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OsProblem {
+    NotFound,
+    PermissionDenied,
+    Other,
+}
+
+impl OsProblem {
+    fn of_io(error: &std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => OsProblem::NotFound,
+            std::io::ErrorKind::PermissionDenied => OsProblem::PermissionDenied,
+            _ => OsProblem::Other,
+        }
+    }
+
+    /// Recognize the OS error of a message formatted from an I/O error.
+    fn of_message(message: &str) -> Self {
+        let message = message.to_lowercase();
+        if message.contains("permission denied") || message.contains("access is denied") {
+            OsProblem::PermissionDenied
+        } else if message.contains("no such file")
+            || message.contains("cannot find the path")
+            || message.contains("not found")
+        {
+            OsProblem::NotFound
+        } else {
+            OsProblem::Other
+        }
+    }
+
+    fn describe(self, subject: &str) -> (String, Vec<String>) {
+        match self {
+            OsProblem::NotFound => (
+                format!("{} does not exist.", subject),
+                vec![
+                    "Check REZ_PACKAGES_PATH and remove repositories that were moved or deleted."
+                        .to_string(),
+                ],
+            ),
+            OsProblem::PermissionDenied => (
+                format!("{} is not readable by the server.", subject),
+                vec![
+                    "Grant read access to the directory or exclude it from REZ_PACKAGES_PATH."
+                        .to_string(),
+                ],
+            ),
+            OsProblem::Other => (
+                format!("{} could not be read.", subject),
+                vec![
+                    "Check that network shares are mounted and retry with 'Reload Workspace'."
+                        .to_string(),
+                ],
+            ),
+        }
+    }
+}
+
+/// Explain an error and suggest next steps.
+fn describe(error: &Error) -> (String, Vec<String>) {
+    let steps = |steps: &[&str]| steps.iter().map(|step| step.to_string()).collect();
+
+    match error {
+        Error::Config(ConfigError::EnvVarNotFound(var)) => (
+            format!("The environment variable {} is not set.", var),
+            steps(&["Set it before starting the editor, or run the 'rez.setupWizard' command."]),
+        ),
+        Error::Config(ConfigError::InvalidPath(path)) => (
+            format!("The configured path {} is invalid.", path),
+            steps(&["Fix the path in REZ_PACKAGES_PATH or the rezLsp settings."]),
+        ),
+        Error::Config(ConfigError::NoValidPaths) => (
+            "None of the configured package repositories exist.".to_string(),
+            steps(&[
+                "Point REZ_PACKAGES_PATH at your package repositories.",
+                "Run the 'rez.setupWizard' command to create ~/packages.",
+            ]),
+        ),
+        Error::Config(ConfigError::ValidationFailed(message)) => (
+            format!("The configuration is invalid: {}.", message),
+            steps(&["Review the REZ_LSP_* environment variables."]),
+        ),
+        Error::Config(ConfigError::UnexpandedVariable {
+            variable,
+            entry,
+            source,
+        }) => (
+            format!(
+                "The {} entry '{}' uses the environment variable {}, which is not set.",
+                source, entry, variable
+            ),
+            steps(&[
+                "Set the variable before starting the editor, or remove the entry.",
+                "Reload the workspace once the setting is fixed.",
+            ]),
+        ),
+        Error::Discovery(DiscoveryError::ScanFailed(message)) => {
+            OsProblem::of_message(message).describe("A package repository")
+        }
+        Error::Discovery(DiscoveryError::PackageNotFound(name)) => (
+            format!("No package named {} was found.", name),
+            steps(&["Check the spelling or add its repository to REZ_PACKAGES_PATH."]),
+        ),
+        Error::Discovery(DiscoveryError::InvalidStructure(message)) => (
+            format!(
+                "A package repository has an unexpected layout: {}.",
+                message
+            ),
+            steps(&["Packages must be laid out as <family>/<version>/package.py."]),
+        ),
+        Error::Discovery(DiscoveryError::CacheFailed(_)) => (
+            "The package cache could not be updated.".to_string(),
+            steps(&["Run 'Reload Workspace' to rebuild the cache."]),
+        ),
+        Error::Parser(ParserError::ReadFailed(path)) => {
+            OsProblem::of_message(path).describe("A package definition")
+        }
+        Error::Parser(ParserError::InvalidSyntax(message)) => (
+            format!("A package definition could not be parsed: {}.", message),
+            steps(&["Fix the syntax error reported in the file."]),
+        ),
+        Error::Parser(ParserError::MissingField(field)) => (
+            format!("A package definition does not declare '{}'.", field),
+            steps(&["Add the attribute to package.py."]),
+        ),
+        Error::Parser(ParserError::InvalidValue(message)) => (
+            format!("A package attribute has an invalid value: {}.", message),
+            steps(&["Correct the value in package.py."]),
+        ),
+        Error::Resolver(ResolverError::Conflict(message))
+        | Error::Resolver(ResolverError::UnsatisfiableConstraint(message)) => (
+            format!("The requirements cannot be resolved together: {}.", message),
+            steps(&["Relax one of the conflicting version ranges."]),
+        ),
+        Error::Resolver(ResolverError::CircularDependency(message)) => (
+            format!("Packages require each other in a cycle: {}.", message),
+            steps(&["Remove one of the requirements forming the cycle."]),
+        ),
+        Error::Resolver(ResolverError::PackageNotFound(name)) => (
+            format!("The required package {} is not in any repository.", name),
+            steps(&["Release the package or add its repository to REZ_PACKAGES_PATH."]),
+        ),
+        Error::Resolver(ResolverError::VariantLimit(message)) => (
+            format!(
+                "The resolve stopped expanding package variants: {}.",
+                message
+            ),
+            steps(&[
+                "Narrow the version ranges of packages with many variants.",
+                "Raise REZ_LSP_MAX_VARIANTS if the resolve is expected to be this large.",
+            ]),
+        ),
+        Error::Lsp(LspError::NotInitialized) => (
+            "The server has not finished starting.".to_string(),
+            steps(&["Wait for the package scan to complete and retry."]),
+        ),
+        Error::Lsp(LspError::InvalidRequest(message)) => (
+            format!("The editor sent an invalid request: {}.", message),
+            steps(&["Update the editor extension."]),
+        ),
+        Error::InvalidPath(path) => (
+            format!("The path {} is invalid.", path),
+            steps(&["Check for typos and unsupported characters in the path."]),
+        ),
+        Error::Io(io_error) => OsProblem::of_io(io_error).describe("A file"),
+        Error::Lsp(LspError::Internal(_)) | Error::Other(_) => (
+            "An unexpected internal error occurred.".to_string(),
+            steps(&["Report it with the output of the 'rez/lastErrors' request."]),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_failure_report() {
+        let error: Error = DiscoveryError::ScanFailed(
+            "Failed to read directory \"/mnt/pkgs\": Permission denied (os error 13)".to_string(),
+        )
+        .into();
+        let report = ErrorReport::new("Package scan", &error);
+
+        assert_eq!(report.code, "RZ201");
+        assert_eq!(report.severity, ErrorSeverity::Warning);
+        assert_eq!(
+            report.message,
+            "A package repository is not readable by the server."
+        );
+        assert!(report
+            .user_message()
+            .starts_with("Package scan failed: A package"));
+        assert!(report.log_message().contains("os error 13"));
+
+        let diagnostic = report.to_diagnostic();
+        assert_eq!(
+            diagnostic.code,
+            Some(NumberOrString::String("RZ201".to_string()))
+        );
+        assert!(diagnostic.message.contains("Grant read access"));
+
+        let io_error: Error = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+        let report = ErrorReport::new("Validation", &io_error);
+        assert_eq!(report.code, "RZ602");
+        assert_eq!(report.message_type(), MessageType::ERROR);
+        assert_eq!(report.message, "A file does not exist.");
+    }
+
+    #[tokio::test]
+    async fn test_error_log_keeps_recent_reports() {
+        let log = ErrorLog::new(2);
+        for operation in ["first", "second", "third"] {
+            log.record(ErrorReport::new(
+                operation,
+                &Error::Other("boom".to_string()),
+            ))
+            .await;
+        }
+
+        let recent = log.recent(None).await;
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].operation, "third");
+        assert_eq!(recent[1].operation, "second");
+        assert_eq!(log.recent(Some(1)).await.len(), 1);
+    }
+}
@@ -0,0 +1,339 @@
+//! Edits following renamed package directories.
+//!
+//! Renaming a version directory (`maya/2024.1` to `maya/2024.2`) or a family
+//! directory in the editor's explorer leaves requirements pinning the old
+//! version, requirements naming the old family and lock files listing
+//! packages that no longer exist. `workspace/willRenameFiles` returns the
+//! edits updating them before the rename happens, and
+//! `workspace/didRenameFiles` then refreshes the index.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{
+    FileOperationFilter, FileOperationPattern, FileOperationPatternKind,
+    FileOperationRegistrationOptions, RenameFilesParams, TextEdit, Url,
+};
+
+use crate::config::PathMapping;
+use crate::parser::{find_requirements, string_attribute_range, RequirementSpan};
+use crate::server::path_mapping::server_path;
+use crate::server::positions::{LineIndex, PositionEncoding};
+
+/// Get the file operation filter of the directory renames the server
+/// follows.
+pub fn directory_rename_filter() -> FileOperationRegistrationOptions {
+    FileOperationRegistrationOptions {
+        filters: vec![FileOperationFilter {
+            scheme: Some("file".to_string()),
+            pattern: FileOperationPattern {
+                glob: "**/*".to_string(),
+                matches: Some(FileOperationPatternKind::Folder),
+                options: None,
+            },
+        }],
+    }
+}
+
+/// Get the package directory renames of a file operation.
+pub fn package_moves(params: &RenameFilesParams, mappings: &[PathMapping]) -> Vec<PackageMove> {
+    params
+        .files
+        .iter()
+        .filter_map(|file| {
+            let old = server_path(&Url::parse(&file.old_uri).ok()?, mappings)?;
+            let new = server_path(&Url::parse(&file.new_uri).ok()?, mappings)?;
+            PackageMove::classify(&old, &new)
+        })
+        .collect()
+}
+
+/// A renamed package directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageMove {
+    /// A version directory renamed within its family
+    Version {
+        /// Family of the version
+        family: String,
+        /// Previous version directory name
+        old: String,
+        /// New version directory name
+        new: String,
+        /// Previous version directory
+        old_dir: PathBuf,
+    },
+    /// A family directory renamed within its repository
+    Family {
+        /// Previous family name
+        old: String,
+        /// New family name
+        new: String,
+        /// Previous family directory
+        old_dir: PathBuf,
+    },
+}
+
+impl PackageMove {
+    /// Classify the rename of `old` to `new`, before or after it happened.
+    ///
+    /// A directory holding a package.py is a version directory, one whose
+    /// subdirectories do a family directory. Moves to another directory are
+    /// not package renames.
+    pub fn classify(old: &Path, new: &Path) -> Option<Self> {
+        if old.parent() != new.parent() {
+            return None;
+        }
+        let old_name = old.file_name()?.to_str()?;
+        let new_name = new.file_name()?.to_str()?;
+        if old_name == new_name {
+            return None;
+        }
+
+        let dir = if old.is_dir() { old } else { new };
+        if dir.join("package.py").is_file() {
+            let family = old.parent()?.file_name()?.to_str()?;
+            return Some(Self::Version {
+                family: family.to_string(),
+                old: old_name.to_string(),
+                new: new_name.to_string(),
+                old_dir: old.to_path_buf(),
+            });
+        }
+        let is_family = fs::read_dir(dir)
+            .ok()?
+            .flatten()
+            .any(|entry| entry.path().join("package.py").is_file());
+        is_family.then(|| Self::Family {
+            old: old_name.to_string(),
+            new: new_name.to_string(),
+            old_dir: old.to_path_buf(),
+        })
+    }
+
+    /// Get the family whose references the rename updates.
+    pub fn family(&self) -> &str {
+        match self {
+            Self::Version { family, .. } => family,
+            Self::Family { old, .. } => old,
+        }
+    }
+
+    /// Get the edits updating the references of a package.py or lock file
+    /// at `path` to the renamed package.
+    pub fn edits(&self, path: &Path, content: &str, encoding: PositionEncoding) -> Vec<TextEdit> {
+        let index = LineIndex::new(content, encoding);
+        if path.extension().is_some_and(|ext| ext == "lock") {
+            return self.lock_edits(content, &index);
+        }
+
+        let mut edits: Vec<TextEdit> = find_requirements(content)
+            .iter()
+            .filter(|span| !span.is_ephemeral())
+            .filter_map(|span| self.requirement_edit(span, &index))
+            .collect();
+        match self {
+            // The moved package takes the new version directory name
+            Self::Version { new, old_dir, .. } if path.starts_with(old_dir) => {
+                edits.extend(string_attribute_edit(content, "version", new, &index));
+            }
+            // Packages moving with the family directory take its new name
+            Self::Family { new, old_dir, .. } if path.starts_with(old_dir) => {
+                edits.extend(string_attribute_edit(content, "name", new, &index));
+            }
+            _ => {}
+        }
+        edits
+    }
+
+    /// Get the edit of a requirement naming the old family or pinning the
+    /// old version (`family-1.0` or `family==1.0`).
+    fn requirement_edit(&self, span: &RequirementSpan, index: &LineIndex) -> Option<TextEdit> {
+        match self {
+            Self::Version {
+                family, old, new, ..
+            } => {
+                if span.name_text() != family {
+                    return None;
+                }
+                let constraint = span.constraint?;
+                let pinned = span.constraint_text()?;
+                let operator = ["==", "-"]
+                    .into_iter()
+                    .find(|op| pinned.strip_prefix(op) == Some(old.as_str()))?;
+                Some(TextEdit {
+                    range: index.range(constraint),
+                    new_text: format!("{}{}", operator, new),
+                })
+            }
+            Self::Family { old, new, .. } => (span.name_text() == old).then(|| TextEdit {
+                range: index.range(span.name),
+                new_text: new.clone(),
+            }),
+        }
+    }
+
+    /// Get the edits of lock file entries (`name-version` or
+    /// `name==version`) of the renamed package.
+    fn lock_edits(&self, content: &str, index: &LineIndex) -> Vec<TextEdit> {
+        content
+            .lines()
+            .enumerate()
+            .filter_map(|(line_num, line)| {
+                let entry = line.trim();
+                if entry.is_empty() || entry.starts_with('#') {
+                    return None;
+                }
+                let (name, separator, version) = match entry.split_once("==") {
+                    Some((name, version)) => (name.trim(), "==", version.trim()),
+                    None => match entry.split_once('-') {
+                        Some((name, version)) => (name.trim(), "-", version.trim()),
+                        None => (entry, "", ""),
+                    },
+                };
+                let new_text = match self {
+                    Self::Version {
+                        family, old, new, ..
+                    } if name == family && version == old => {
+                        format!("{}{}{}", name, separator, new)
+                    }
+                    Self::Family { old, new, .. } if name == old => {
+                        format!("{}{}{}", new, separator, version)
+                    }
+                    _ => return None,
+                };
+                let start = line.len() - line.trim_start().len();
+                Some(TextEdit {
+                    range: index.byte_range(line_num as u32, start, start + entry.len()),
+                    new_text,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Check whether a file may reference packages: a package.py or lock file.
+pub fn is_reference_file(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == "package.py")
+        || path.extension().is_some_and(|ext| ext == "lock")
+}
+
+/// Get the edit replacing the quoted value of a top-level string attribute
+/// (`name` or `version`) of package.py content.
+fn string_attribute_edit(
+    content: &str,
+    attribute: &str,
+    value: &str,
+    index: &LineIndex,
+) -> Option<TextEdit> {
+    let (line, range) = string_attribute_range(content, attribute)?;
+    Some(TextEdit {
+        range: index.byte_range(line, range.start, range.end),
+        new_text: value.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::write_package;
+
+    fn apply(content: &str, edits: &[TextEdit]) -> Vec<String> {
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+        for edit in edits {
+            let line = &mut lines[edit.range.start.line as usize];
+            let start = edit.range.start.character as usize;
+            let end = edit.range.end.character as usize;
+            line.replace_range(start..end, &edit.new_text);
+        }
+        lines
+    }
+
+    #[test]
+    fn test_version_rename() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old = write_package(temp_dir.path(), "maya", "2024.1", "name = 'maya'\n");
+        let moved = PackageMove::classify(&old, &old.with_file_name("2024.2")).unwrap();
+        assert_eq!(
+            moved,
+            PackageMove::Version {
+                family: "maya".to_string(),
+                old: "2024.1".to_string(),
+                new: "2024.2".to_string(),
+                old_dir: old.clone(),
+            }
+        );
+
+        // The moved package takes the new version
+        let content = "name = 'maya'\nversion = \"2024.1\"\nversions_note = '2024.1'\n";
+        let edits = moved.edits(&old.join("package.py"), content, PositionEncoding::Utf16);
+        assert_eq!(
+            apply(content, &edits),
+            [
+                "name = 'maya'",
+                "version = \"2024.2\"",
+                "versions_note = '2024.1'"
+            ]
+        );
+
+        let content = "requires = ['maya-2024.1', 'maya==2024.1', 'maya-2024', 'nuke-2024.1']\n";
+        let edits = moved.edits(
+            Path::new("/ws/package.py"),
+            content,
+            PositionEncoding::Utf16,
+        );
+        assert_eq!(
+            apply(content, &edits),
+            ["requires = ['maya-2024.2', 'maya==2024.2', 'maya-2024', 'nuke-2024.1']"]
+        );
+
+        let lock = "# pinned\n  maya-2024.1\nmaya==2024.0\npython-3.9\n";
+        let edits = moved.edits(Path::new("/ws/env.lock"), lock, PositionEncoding::Utf16);
+        assert_eq!(
+            apply(lock, &edits),
+            ["# pinned", "  maya-2024.2", "maya==2024.0", "python-3.9"]
+        );
+    }
+
+    #[test]
+    fn test_family_rename() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let version_dir =
+            write_package(temp_dir.path(), "old_tools", "1.0", "name = 'old_tools'\n");
+        let old = temp_dir.path().join("old_tools");
+        let new = temp_dir.path().join("tools");
+        let moved = PackageMove::classify(&old, &new).unwrap();
+
+        // The moved package is renamed along with its requirements
+        let content = "name = 'old_tools'\nrequires = ['old_tools_extra', '~old_tools-1']\n";
+        let edits = moved.edits(
+            &version_dir.join("package.py"),
+            content,
+            PositionEncoding::Utf16,
+        );
+        assert_eq!(
+            apply(content, &edits),
+            [
+                "name = 'tools'",
+                "requires = ['old_tools_extra', '~tools-1']"
+            ]
+        );
+        let edits = moved.edits(
+            Path::new("/ws/env.lock"),
+            "old_tools==1.0\n",
+            PositionEncoding::Utf16,
+        );
+        assert_eq!(edits[0].new_text, "tools==1.0");
+
+        // Plain directories and moves are not package renames
+        fs::create_dir_all(temp_dir.path().join("docs")).unwrap();
+        assert!(PackageMove::classify(
+            &temp_dir.path().join("docs"),
+            &temp_dir.path().join("documentation")
+        )
+        .is_none());
+        assert!(PackageMove::classify(&old, &temp_dir.path().join("archive/old_tools")).is_none());
+        assert!(is_reference_file(Path::new("/ws/env.lock")));
+        assert!(!is_reference_file(Path::new("/ws/build.py")));
+    }
+}
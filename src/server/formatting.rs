@@ -0,0 +1,275 @@
+//! On-type formatting of requirement lists.
+//!
+//! Typing in `requires` and `variants` lists is where most syntax errors of
+//! package definitions start: an unterminated quote, an entry indented off
+//! the list, a missing comma before the next entry. Typing `"` closes the
+//! requirement string and typing `,` aligns the entries of a multi-line list
+//! and applies the trailing comma style of [`Config::trailing_comma`].
+//!
+//! [`Config::trailing_comma`]: crate::config::Config::trailing_comma
+
+use tower_lsp::lsp_types::TextEdit;
+
+use crate::config::TrailingComma;
+use crate::parser::{requirement_list_at, RequirementList, SourceRange};
+use crate::server::positions::{LineIndex, PositionEncoding};
+
+/// Characters triggering on-type formatting, the first one being the
+/// primary trigger.
+pub const TRIGGER_CHARACTERS: [&str; 2] = ["\"", ","];
+
+/// Build the edits formatting a requirement list after `typed` was typed
+/// right before the 0-based `line` and character column `character`.
+pub fn on_type_edits(
+    content: &str,
+    line: u32,
+    character: u32,
+    typed: &str,
+    style: TrailingComma,
+    encoding: PositionEncoding,
+) -> Vec<TextEdit> {
+    let Some(list) = requirement_list_at(content, line, character) else {
+        return Vec::new();
+    };
+    let index = LineIndex::new(content, encoding);
+    let lines: Vec<&str> = content.lines().collect();
+    let line_text = lines.get(line as usize).copied().unwrap_or("");
+
+    let edits = match typed {
+        "\"" | "'" => closing_quote(line_text, line, character, typed),
+        "," => {
+            let mut edits = align_entries(&list, &lines);
+            edits.extend(trailing_comma(&list, (line, character), style));
+            edits
+        }
+        _ => Vec::new(),
+    };
+    edits
+        .into_iter()
+        .map(|(range, new_text)| TextEdit {
+            range: index.range(range),
+            new_text,
+        })
+        .collect()
+}
+
+/// Close a requirement string opened by the quote before `character`.
+fn closing_quote(
+    line_text: &str,
+    line: u32,
+    character: u32,
+    quote: &str,
+) -> Vec<(SourceRange, String)> {
+    let chars: Vec<char> = line_text.chars().collect();
+    let Some(typed) = (character as usize).checked_sub(1) else {
+        return Vec::new();
+    };
+    let quote_char = quote.chars().next().unwrap_or('"');
+    // The quote opens a string when an even number of quotes precede it
+    let mut quotes = 0;
+    let mut escaped = false;
+    for c in &chars[..typed.min(chars.len())] {
+        match c {
+            '\\' if !escaped => {
+                escaped = true;
+                continue;
+            }
+            c if *c == quote_char && !escaped => quotes += 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    let next = chars.get(typed + 1);
+    let opens = quotes % 2 == 0;
+    let at_boundary = next.is_none_or(|c| c.is_whitespace() || matches!(c, ',' | ']'));
+    if opens && at_boundary {
+        vec![(
+            SourceRange {
+                line,
+                start: character,
+                end: character,
+            },
+            quote.to_string(),
+        )]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Indent the entries starting a line of a multi-line list like its first
+/// such entry.
+fn align_entries(list: &RequirementList, lines: &[&str]) -> Vec<(SourceRange, String)> {
+    if !list.is_multiline() {
+        return Vec::new();
+    }
+    let indent_of = |line: u32| {
+        let text = lines.get(line as usize).copied().unwrap_or("");
+        text[..text.len() - text.trim_start().len()].to_string()
+    };
+    let leading = |line: u32| indent_of(line).chars().count() as u32;
+
+    // Entries preceded by nothing but whitespace on their line
+    let mut own_line = list
+        .entries
+        .iter()
+        .map(|(start, _)| *start)
+        .filter(|(line, column)| *line != list.open.0 && leading(*line) == *column);
+    let Some((first_line, _)) = own_line.next() else {
+        return Vec::new();
+    };
+    let indent = indent_of(first_line);
+    own_line
+        .filter(|(line, _)| indent_of(*line) != indent)
+        .map(|(line, column)| {
+            (
+                SourceRange {
+                    line,
+                    start: 0,
+                    end: column,
+                },
+                indent.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Add or remove the comma after the last entry according to `style`.
+///
+/// The comma just typed before `cursor` is never removed, as the next entry
+/// is usually about to follow it.
+fn trailing_comma(
+    list: &RequirementList,
+    cursor: (u32, u32),
+    style: TrailingComma,
+) -> Vec<(SourceRange, String)> {
+    let Some(&(_, (end_line, end_column))) = list.entries.last() else {
+        return Vec::new();
+    };
+    if list.close.is_none() {
+        return Vec::new();
+    }
+    let wanted = match style {
+        TrailingComma::Always => true,
+        TrailingComma::Multiline => list.is_multiline(),
+        TrailingComma::Never => false,
+    };
+    match list.trailing_comma {
+        None if wanted => vec![(
+            SourceRange {
+                line: end_line,
+                start: end_column,
+                end: end_column,
+            },
+            ",".to_string(),
+        )],
+        Some((line, column)) if !wanted && (line, column + 1) != cursor => vec![(
+            SourceRange {
+                line,
+                start: column,
+                end: column + 1,
+            },
+            String::new(),
+        )],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(
+        content: &str,
+        line: u32,
+        character: u32,
+        typed: &str,
+        style: TrailingComma,
+    ) -> String {
+        let mut edits = on_type_edits(
+            content,
+            line,
+            character,
+            typed,
+            style,
+            PositionEncoding::Utf16,
+        );
+        // Apply from the end so earlier positions stay valid
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        for edit in edits {
+            let line = &mut lines[edit.range.start.line as usize];
+            let chars: Vec<char> = line.chars().collect();
+            let start = edit.range.start.character as usize;
+            let end = edit.range.end.character as usize;
+            *line = chars[..start]
+                .iter()
+                .chain(edit.new_text.chars().collect::<Vec<_>>().iter())
+                .chain(chars[end..].iter())
+                .collect();
+        }
+        lines.join("\n") + "\n"
+    }
+
+    #[test]
+    fn test_closing_quote() {
+        let style = TrailingComma::Multiline;
+        assert_eq!(
+            format("requires = [\"python\", \"]\n", 0, 23, "\"", style),
+            "requires = [\"python\", \"\"]\n"
+        );
+        // Closing quotes and quotes inserted by the editor are left alone
+        assert_eq!(
+            format("requires = [\"python\"]\n", 0, 20, "\"", style),
+            "requires = [\"python\"]\n"
+        );
+        assert_eq!(
+            format("requires = [\"\"]\n", 0, 13, "\"", style),
+            "requires = [\"\"]\n"
+        );
+        // Outside requirement lists nothing happens
+        assert_eq!(format("name = \"\n", 0, 8, "\"", style), "name = \"\n");
+    }
+
+    #[test]
+    fn test_align_and_trailing_comma() {
+        let content = "requires = [\n    \"python-3\",\n  \"maya-2024\",\n      \"nuke\"\n]\n";
+        assert_eq!(
+            format(content, 2, 14, ",", TrailingComma::Multiline),
+            "requires = [\n    \"python-3\",\n    \"maya-2024\",\n    \"nuke\",\n]\n"
+        );
+
+        // The comma just typed is kept, others follow the style
+        let single = "requires = [\"python-3\", \"maya\",]\n";
+        assert_eq!(
+            format(single, 0, 23, ",", TrailingComma::Multiline),
+            "requires = [\"python-3\", \"maya\"]\n"
+        );
+        assert_eq!(format(single, 0, 31, ",", TrailingComma::Never), single);
+        assert_eq!(
+            format(
+                "requires = [\"a\", \"b\"]\n",
+                0,
+                16,
+                ",",
+                TrailingComma::Always
+            ),
+            "requires = [\"a\", \"b\",]\n"
+        );
+    }
+
+    #[test]
+    fn test_variant_lists() {
+        let content =
+            "variants = [\n    [\"maya-2024\", \"python-3.10\",],\n    [\"maya-2025\"]\n]\n";
+        // Inside a variant the variant list is formatted
+        assert_eq!(
+            format(content, 1, 17, ",", TrailingComma::Multiline),
+            "variants = [\n    [\"maya-2024\", \"python-3.10\"],\n    [\"maya-2025\"]\n]\n"
+        );
+        // Between variants the `variants` list is
+        assert_eq!(
+            format(content, 1, 34, ",", TrailingComma::Multiline),
+            "variants = [\n    [\"maya-2024\", \"python-3.10\",],\n    [\"maya-2025\"],\n]\n"
+        );
+    }
+}
@@ -1,10 +1,28 @@
 //! Hover handling for the LSP server.
 
+use std::borrow::Borrow;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 
+use crate::core::{HoverProvider, Package, Requirement, Version, VersionConstraint, VersionRange};
+use crate::discovery::{imported_module, ResolvedEnvironment};
+use crate::parser::commands::{find_commands, CommandsBlock, CommandsForm};
+use crate::parser::{find_requirements, requirement_part_at, RequirementPart};
+use crate::server::documents::{DocumentKinds, DocumentStore};
+use crate::server::positions::{LineIndex, PositionEncoding};
+
 /// Handle hover requests.
-pub async fn handle_hover(params: &HoverParams) -> Result<Option<Hover>> {
+pub async fn handle_hover(
+    params: &HoverParams,
+    document_map: &RwLock<DocumentStore>,
+    hover_provider: &dyn HoverProvider,
+    resolved_environment: &Arc<RwLock<Option<ResolvedEnvironment>>>,
+    document_kinds: &DocumentKinds,
+    encoding: PositionEncoding,
+) -> Result<Option<Hover>> {
     let uri = &params.text_document_position_params.text_document.uri;
     let position = &params.text_document_position_params.position;
 
@@ -16,6 +34,31 @@ pub async fn handle_hover(params: &HoverParams) -> Result<Option<Hover>> {
         position.character + 1
     );
 
+    let content = document_map.read().await.get(uri);
+
+    // Outside package definitions, only resolved-environment imports are described
+    if !document_kinds.is_package_definition(uri) {
+        let environment = resolved_environment.read().await;
+        return Ok(content
+            .zip(environment.as_ref())
+            .and_then(|(content, env)| hover_import(position, &content, env)));
+    }
+
+    if let Some(content) = content {
+        let index = LineIndex::new(&content, encoding);
+        if let Some(hover) = hover_commands(position, &content, &index) {
+            return Ok(Some(hover));
+        }
+        if let Some(hover) = hover_requirement(position, &content, &index, hover_provider).await {
+            return Ok(Some(hover));
+        }
+        if let Some(hover) =
+            hover_version_attribute(uri, position, &content, &index, hover_provider).await
+        {
+            return Ok(Some(hover));
+        }
+    }
+
     // MVP: Basic hover information
     let hover_content = "Rez package definition file\n\nThis file defines a Rez package with its dependencies, version, and environment configuration.";
 
@@ -24,3 +67,593 @@ pub async fn handle_hover(params: &HoverParams) -> Result<Option<Hover>> {
         range: None,
     }))
 }
+
+/// Describe which resolved rez package provides the module imported on a line.
+fn hover_import(position: &Position, content: &str, env: &ResolvedEnvironment) -> Option<Hover> {
+    let line = content.lines().nth(position.line as usize)?;
+    let module = imported_module(line)?;
+    let package = env.find_module_provider(module)?;
+
+    let mut markdown = format!(
+        "`{}` is provided by rez package **{}**",
+        module, package.name
+    );
+    if !package.version.value.is_empty() {
+        markdown.push_str(&format!(" `{}`", package.version));
+    }
+    if let Some(root) = &package.root {
+        markdown.push_str(&format!("\n\nRoot: `{}`", root.display()));
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: markdown,
+        }),
+        range: None,
+    })
+}
+
+/// Provide hover information for the `commands` attribute in either form.
+fn hover_commands(position: &Position, content: &str, index: &LineIndex) -> Option<Hover> {
+    let block = find_commands(content)?;
+    if position.line != block.line {
+        return None;
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: commands_hover_markdown(&block),
+        }),
+        range: Some(Range::new(
+            Position::new(block.line, 0),
+            index.line_end(block.line),
+        )),
+    })
+}
+
+/// Build the markdown shown when hovering the `commands` attribute.
+fn commands_hover_markdown(block: &CommandsBlock) -> String {
+    let form = match block.form {
+        CommandsForm::Function => "function",
+        CommandsForm::String => "string",
+    };
+    let mut markdown = format!(
+        "**commands** ({} form)\n\nConfigures the environment when the package is resolved.\n",
+        form
+    );
+
+    if block.operations.is_empty() {
+        markdown.push_str("\nNo environment changes detected.");
+    } else {
+        markdown.push_str("\n**Environment changes:**\n");
+        for operation in &block.operations {
+            match &operation.value {
+                Some(value) => markdown.push_str(&format!(
+                    "- `{}` `{}` `{}`\n",
+                    operation.action.as_str(),
+                    operation.variable,
+                    value
+                )),
+                None => markdown.push_str(&format!(
+                    "- `{}` `{}`\n",
+                    operation.action.as_str(),
+                    operation.variable
+                )),
+            }
+        }
+    }
+
+    markdown
+}
+
+/// Provide hover information for a `requires` or `variants` entry.
+///
+/// Besides the indexed packages, the combined constraints on the family in
+/// each variant are shown on the family name when the package requires it
+/// more than once.
+async fn hover_requirement(
+    position: &Position,
+    content: &str,
+    index: &LineIndex<'_>,
+    hover_provider: &dyn HoverProvider,
+) -> Option<Hover> {
+    let (span, part) = requirement_part_at(content, position.line, index.char_column(position))?;
+    let info = if span.is_ephemeral() {
+        ephemeral_markdown(&span.text)
+    } else {
+        match hover_provider.hover_requirement(&span.text).await {
+            Ok(info) => info.map(|info| info.content),
+            Err(e) => {
+                tracing::debug!("Requirement hover unavailable: {}", e);
+                None
+            }
+        }
+    };
+    // The constraints combined across the package concern the family, so
+    // they are left out when hovering the version constraint
+    let combined = Requirement::parse(&span.text)
+        .ok()
+        .filter(|_| part != RequirementPart::Constraint)
+        .and_then(|requirement| combined_constraints_markdown(content, &requirement.name));
+
+    let value = match (info, combined) {
+        (Some(info), Some(combined)) => format!("{}\n\n{}", info, combined),
+        (Some(info), None) => info,
+        (None, Some(combined)) => combined,
+        (None, None) => return None,
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(index.range(span.range)),
+    })
+}
+
+/// Describe an ephemeral requirement, e.g. `.foo.bar-1`.
+///
+/// Ephemerals are not packages, so the package index is not consulted.
+fn ephemeral_markdown(text: &str) -> Option<String> {
+    let requirement = Requirement::parse(text).ok()?;
+    let range = match requirement.constraint {
+        VersionConstraint::Any => "any version".to_string(),
+        ref constraint => format!("`{}`", VersionRange::from(constraint)),
+    };
+    let kind = if requirement.conflict {
+        "Excluded range"
+    } else if requirement.weak {
+        "Weakly requested range"
+    } else {
+        "Requested range"
+    };
+    Some(format!(
+        "**{}** *(ephemeral)*\n\nA pure constraint on `{}` shared by the resolved environment; no package provides it.\n\n{}: {}",
+        requirement, requirement.name, kind, range
+    ))
+}
+
+/// Describe the versions of a family accepted by all requirements on it, in
+/// each variant or in `requires` when there are no variants.
+///
+/// Contexts where no version satisfies every requirement are flagged.
+/// Conflict (`!`) requirements are not combined. Returns `None` unless some
+/// context requires the family more than once.
+fn combined_constraints_markdown(content: &str, family: &str) -> Option<String> {
+    let mut shared = Vec::new();
+    let mut variants: Vec<Vec<Requirement>> = Vec::new();
+    for span in find_requirements(content) {
+        let Ok(requirement) = Requirement::parse(&span.text) else {
+            continue;
+        };
+        let applies = requirement.name == family && !requirement.conflict;
+        match span.variant {
+            Some(variant) => {
+                if variants.len() <= variant {
+                    variants.resize(variant + 1, Vec::new());
+                }
+                if applies {
+                    variants[variant].push(requirement);
+                }
+            }
+            None if applies && span.attribute == "requires" => shared.push(requirement),
+            None => {}
+        }
+    }
+
+    let contexts: Vec<(String, Vec<&Requirement>)> = if variants.is_empty() {
+        vec![("`requires`".to_string(), shared.iter().collect())]
+    } else {
+        variants
+            .iter()
+            .enumerate()
+            .map(|(index, variant)| {
+                (
+                    format!("Variant {}", index),
+                    shared.iter().chain(variant).collect(),
+                )
+            })
+            .collect()
+    };
+    if contexts
+        .iter()
+        .all(|(_, requirements)| requirements.len() < 2)
+    {
+        return None;
+    }
+
+    let mut markdown = format!("**Combined constraints on `{}`:**\n", family);
+    for (label, requirements) in contexts.iter().filter(|(_, r)| !r.is_empty()) {
+        let range = requirements
+            .iter()
+            .fold(VersionRange::any(), |range, requirement| {
+                range.intersect(&VersionRange::from(&requirement.constraint))
+            });
+        let listed: Vec<String> = requirements
+            .iter()
+            .map(|requirement| format!("`{}`", requirement))
+            .collect();
+        let result = if range.is_empty() {
+            "**no version satisfies all of them**".to_string()
+        } else {
+            format!("`{}`", range.to_requirement(family))
+        };
+        markdown.push_str(&format!(
+            "- {}: {} → {}\n",
+            label,
+            listed.join(", "),
+            result
+        ));
+    }
+    Some(markdown)
+}
+
+/// Provide hover information for the `version` attribute of a package.
+async fn hover_version_attribute(
+    uri: &Url,
+    position: &Position,
+    content: &str,
+    index: &LineIndex<'_>,
+    hover_provider: &dyn HoverProvider,
+) -> Option<Hover> {
+    let line = content.lines().nth(position.line as usize)?;
+    let version = Version::new(string_assignment(line, "version")?);
+    let name = content
+        .lines()
+        .find_map(|line| string_assignment(line, "name"))?;
+
+    let working_dir = uri
+        .to_file_path()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf));
+    let info = match working_dir {
+        Some(dir) => {
+            hover_provider
+                .hover_working_copy(&name, &version, &dir)
+                .await
+        }
+        None => hover_provider.hover_package(&name, Some(&version)).await,
+    };
+    let info = match info {
+        Ok(info) => info?,
+        Err(e) => {
+            tracing::debug!("Version hover unavailable: {}", e);
+            return None;
+        }
+    };
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: info.content,
+        }),
+        range: Some(Range::new(
+            Position::new(position.line, 0),
+            index.line_end(position.line),
+        )),
+    })
+}
+
+/// Build the markdown shown when hovering the `version` attribute.
+///
+/// Lists the indexed sibling versions of the family, highlights the latest
+/// one, and warns if the working copy's version already exists in the
+/// release repository.
+pub(crate) fn version_hover_markdown<P: Borrow<Package>>(
+    name: &str,
+    current: &Version,
+    siblings: &[P],
+    working_dir: Option<&Path>,
+    release_path: Option<&Path>,
+) -> String {
+    let mut markdown = format!("**{}** version `{}`\n", name, current);
+
+    // Ignore the working copy itself if it lives inside an indexed repository
+    let others: Vec<&Package> = siblings
+        .iter()
+        .map(Borrow::borrow)
+        .filter(|pkg| Some(pkg.path.as_path()) != working_dir)
+        .collect();
+
+    if others.is_empty() {
+        markdown.push_str("\nNo other versions of this package are indexed.");
+        return markdown;
+    }
+
+    let latest = others.iter().map(|pkg| &pkg.version).max();
+    if let Some(latest) = latest {
+        let hint = match current.cmp(latest) {
+            std::cmp::Ordering::Greater => {
+                format!("newer than the latest indexed version `{}`", latest)
+            }
+            std::cmp::Ordering::Equal => "same as the latest indexed version".to_string(),
+            std::cmp::Ordering::Less => {
+                let newer = others.iter().filter(|pkg| pkg.version > *current).count();
+                format!(
+                    "older than the latest indexed version `{}` ({} newer version(s))",
+                    latest, newer
+                )
+            }
+        };
+        markdown.push_str(&format!("\nThis version is {}.\n", hint));
+    }
+
+    markdown.push_str("\n**Indexed versions:**\n");
+    let mut versions: Vec<&Version> = others.iter().map(|pkg| &pkg.version).collect();
+    versions.sort_by(|a, b| b.cmp(a));
+    versions.dedup();
+    for version in versions {
+        if Some(version) == latest {
+            markdown.push_str(&format!("- `{}` (latest)\n", version));
+        } else {
+            markdown.push_str(&format!("- `{}`\n", version));
+        }
+    }
+
+    if let Some(release_path) = release_path {
+        let already_released = others
+            .iter()
+            .any(|pkg| pkg.version == *current && pkg.path.starts_with(release_path));
+        if already_released {
+            markdown.push_str(&format!(
+                "\n**Warning:** version `{}` already exists in the release repository `{}`. Bump the version before releasing.",
+                current,
+                release_path.display()
+            ));
+        }
+    }
+
+    markdown
+}
+
+/// Note that older versions were left out of the index by the version cap.
+pub(crate) fn elided_versions_note(elided: usize) -> String {
+    format!(
+        "\n\n_{} older version(s) not indexed (`REZ_LSP_MAX_VERSIONS_PER_FAMILY`). Use `rez/loadAllVersions` to load the full list._",
+        elided
+    )
+}
+
+/// Extract the string value of a top-level `field = "value"` assignment.
+fn string_assignment(line: &str, field: &str) -> Option<String> {
+    let rest = line.trim().strip_prefix(field)?.trim_start();
+    let value = rest.strip_prefix('=')?.trim();
+
+    if value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')))
+    {
+        Some(value[1..value.len() - 1].to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::HoverInfo;
+
+    /// Provider describing every working copy with a fixed text.
+    struct FakeProvider;
+
+    #[async_trait::async_trait]
+    impl HoverProvider for FakeProvider {
+        async fn hover_package(
+            &self,
+            name: &str,
+            version: Option<&Version>,
+        ) -> crate::core::Result<Option<HoverInfo>> {
+            Ok(Some(HoverInfo {
+                content: format!("{} {}", name, version.unwrap()),
+                range: None,
+            }))
+        }
+
+        async fn hover_requirement(&self, _: &str) -> crate::core::Result<Option<HoverInfo>> {
+            Ok(None)
+        }
+
+        async fn hover_tool(&self, _: &str) -> crate::core::Result<Option<HoverInfo>> {
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_version_hover_uses_provider() {
+        let uri = Url::parse("file:///packages/maya_tools/package.py").unwrap();
+        let documents = RwLock::new(DocumentStore::new(4));
+        documents.write().await.insert(
+            uri.clone(),
+            "name = \"maya_tools\"\nversion = \"2.1.0\"\n".to_string(),
+        );
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position::new(1, 3),
+            },
+            work_done_progress_params: Default::default(),
+        };
+
+        let hover = handle_hover(
+            &params,
+            &documents,
+            &FakeProvider,
+            &Arc::new(RwLock::new(None)),
+            &DocumentKinds::default(),
+            PositionEncoding::Utf16,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let HoverContents::Markup(markup) = hover.contents else {
+            panic!("Expected markdown hover");
+        };
+        assert_eq!(markup.value, "maya_tools 2.1.0");
+        assert_eq!(hover.range.unwrap().end, Position::new(1, 17));
+    }
+
+    fn create_test_package(version: &str, path: &str) -> Package {
+        Package::builder()
+            .name("maya_tools")
+            .version(version)
+            .path(path)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_requirement_hover_combines_variant_constraints() {
+        let uri = Url::parse("file:///packages/tools/package.py").unwrap();
+        let content = "name = 'tools'\nrequires = ['python-3.9+<3.11', '!python-3.9.0']\nvariants = [\n    ['maya-2024', 'python-3.10'],\n    ['python-2.7'],\n    ['houdini-20'],\n]\n";
+        let documents = RwLock::new(DocumentStore::new(4));
+        documents
+            .write()
+            .await
+            .insert(uri.clone(), content.to_string());
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position::new(4, 8),
+            },
+            work_done_progress_params: Default::default(),
+        };
+
+        let hover = handle_hover(
+            &params,
+            &documents,
+            &FakeProvider,
+            &Arc::new(RwLock::new(None)),
+            &DocumentKinds::default(),
+            PositionEncoding::Utf16,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let HoverContents::Markup(markup) = hover.contents else {
+            panic!("Expected markdown hover");
+        };
+        assert_eq!(
+            markup.value,
+            "**Combined constraints on `python`:**\n\
+             - Variant 0: `python-3.9+<3.11`, `python-3.10` → `python-3.10`\n\
+             - Variant 1: `python-3.9+<3.11`, `python-2.7` → **no version satisfies all of them**\n\
+             - Variant 2: `python-3.9+<3.11` → `python-3.9+<3.11`\n"
+        );
+        assert_eq!(
+            hover.range,
+            Some(Range::new(Position::new(4, 6), Position::new(4, 16)))
+        );
+
+        // On the version constraint, only the requirement itself is described
+        let params = HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                position: Position::new(4, 14),
+                ..params.text_document_position_params
+            },
+            ..params
+        };
+        let hover = handle_hover(
+            &params,
+            &documents,
+            &FakeProvider,
+            &Arc::new(RwLock::new(None)),
+            &DocumentKinds::default(),
+            PositionEncoding::Utf16,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(!format!("{:?}", hover.contents).contains("Combined constraints"));
+
+        // A single requirement on the family has nothing to combine
+        assert!(combined_constraints_markdown(content, "maya").is_none());
+    }
+
+    #[test]
+    fn test_ephemeral_hover() {
+        let markdown = ephemeral_markdown(".foo.bar-1").unwrap();
+        assert!(markdown.starts_with("**.foo.bar-1** *(ephemeral)*"));
+        assert!(markdown.ends_with("Requested range: `1`"));
+
+        let markdown = ephemeral_markdown("!.beta").unwrap();
+        assert!(markdown.ends_with("Excluded range: any version"));
+    }
+
+    #[test]
+    fn test_string_assignment() {
+        assert_eq!(
+            string_assignment("version = \"2.1.0\"", "version"),
+            Some("2.1.0".to_string())
+        );
+        assert_eq!(
+            string_assignment("  name='tools'", "name"),
+            Some("tools".to_string())
+        );
+        assert_eq!(string_assignment("versions = \"1\"", "version"), None);
+        assert_eq!(
+            string_assignment("version = get_version()", "version"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_commands_hover_both_forms() {
+        let function_form = "def commands():\n    env.PATH.append('{root}/bin')\n";
+        let string_form = "commands = 'export PATH=$PATH:{root}/bin'\n";
+
+        for content in [function_form, string_form] {
+            let index = LineIndex::new(content, PositionEncoding::Utf16);
+            let hover = hover_commands(
+                &Position {
+                    line: 0,
+                    character: 2,
+                },
+                content,
+                &index,
+            )
+            .unwrap();
+            let HoverContents::Markup(markup) = hover.contents else {
+                panic!("Expected markdown hover");
+            };
+            assert!(markup.value.contains("`appendenv` `PATH` `{root}/bin`"));
+        }
+    }
+
+    #[test]
+    fn test_version_hover_lists_siblings() {
+        let siblings = vec![
+            create_test_package("2.0.0", "/repo/maya_tools/2.0.0"),
+            create_test_package("2.3.0", "/repo/maya_tools/2.3.0"),
+        ];
+
+        let markdown =
+            version_hover_markdown("maya_tools", &Version::new("2.1.0"), &siblings, None, None);
+        assert!(markdown.contains("`2.3.0` (latest)"));
+        assert!(markdown.contains("- `2.0.0`"));
+        assert!(markdown.contains("1 newer version(s)"));
+        assert!(!markdown.contains("release repository"));
+    }
+
+    #[test]
+    fn test_version_hover_duplicate_release() {
+        let siblings = vec![create_test_package("2.1.0", "/release/maya_tools/2.1.0")];
+
+        let markdown = version_hover_markdown(
+            "maya_tools",
+            &Version::new("2.1.0"),
+            &siblings,
+            Some(Path::new("/home/dev/maya_tools")),
+            Some(Path::new("/release")),
+        );
+        assert!(markdown.contains("already exists in the release repository"));
+    }
+
+    #[test]
+    fn test_elided_versions_note() {
+        let note = elided_versions_note(412);
+        assert!(note.contains("412 older version(s) not indexed"));
+        assert!(note.contains("rez/loadAllVersions"));
+    }
+}
@@ -0,0 +1,433 @@
+//! Read-only HTTP introspection endpoint for long-lived server instances.
+//!
+//! Exposes the health, metrics and package index of a running server so it
+//! can be monitored with standard tooling:
+//!
+//! - `GET /health` - health of the package index as JSON
+//! - `GET /metrics` - operation timings and index gauges in Prometheus format
+//! - `GET /packages?prefix=` - indexed package families and their versions
+//! - `GET /resolve?req=` - resolve space or comma separated requirements
+//!
+//! The endpoint is a minimal HTTP/1.1 implementation on top of tokio and only
+//! serves `GET` requests, one per connection.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{RwLock, Semaphore};
+use tracing::{debug, info, warn};
+
+use crate::core::{PackageDiscovery, Requirement};
+use crate::discovery::PackageDiscoveryImpl;
+use crate::performance::metrics::escape_label_value;
+use crate::performance::MetricsCollector;
+use crate::resolver::create_resolver;
+use crate::server::ServerHealth;
+
+/// Maximum size of a request head in bytes.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// Time a client has to send its request head before getting a 408.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of connections served at once; further clients wait in the
+/// listen backlog.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Shared server state read by the introspection endpoint.
+#[derive(Clone)]
+pub struct IntrospectionState {
+    /// Health of the package index
+    pub health: Arc<RwLock<ServerHealth>>,
+    /// Package discovery service
+    pub package_discovery: Arc<RwLock<Option<PackageDiscoveryImpl>>>,
+    /// Operation timings of the server
+    pub metrics: Arc<MetricsCollector>,
+}
+
+/// An indexed package family returned by `/packages`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageFamily {
+    /// Family name
+    pub name: String,
+    /// Indexed versions, newest first
+    pub versions: Vec<String>,
+}
+
+/// An HTTP response produced by the endpoint.
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, value: &impl Serialize) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_string(value).unwrap_or_else(|_| "null".to_string()),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message.into() }))
+    }
+
+    fn text(body: String) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/plain; version=0.0.4",
+            body,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason_phrase(self.status),
+            self.content_type,
+            self.body.len(),
+            self.body
+        )
+        .into_bytes()
+    }
+}
+
+/// Bind the introspection endpoint to `addr`.
+pub async fn bind(addr: SocketAddr) -> std::io::Result<TcpListener> {
+    let listener = TcpListener::bind(addr).await?;
+    info!(
+        "HTTP introspection endpoint listening on {}",
+        listener.local_addr()?
+    );
+    Ok(listener)
+}
+
+/// Serve introspection requests until the listener fails.
+pub async fn serve(listener: TcpListener, state: IntrospectionState) {
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        let Ok(permit) = Arc::clone(&connections).acquire_owned().await else {
+            return;
+        };
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &state, REQUEST_TIMEOUT).await {
+                        debug!("HTTP connection from {} failed: {}", peer, e);
+                    }
+                    drop(permit);
+                });
+            }
+            Err(e) => {
+                warn!("HTTP introspection endpoint stopped: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+/// Read a single request from `stream` and write its response.
+///
+/// Clients not sending their request head within `timeout` get a 408.
+async fn handle_connection(
+    mut stream: TcpStream,
+    state: &IntrospectionState,
+    timeout: Duration,
+) -> std::io::Result<()> {
+    let buffer = match tokio::time::timeout(timeout, read_request_head(&mut stream)).await {
+        Ok(Ok(Some(buffer))) => buffer,
+        Ok(Ok(None)) => {
+            let response = Response::error(431, "Request header too large");
+            return stream.write_all(&response.to_bytes()).await;
+        }
+        Ok(Err(e)) => return Err(e),
+        Err(_) => {
+            let response = Response::error(408, "Request timed out");
+            return stream.write_all(&response.to_bytes()).await;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer);
+    let request_line = head.lines().next().unwrap_or_default();
+    let response = respond(request_line, state).await;
+    stream.write_all(&response.to_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Read the request head from `stream`, or `None` when it exceeds
+/// [`MAX_REQUEST_SIZE`].
+async fn read_request_head(stream: &mut TcpStream) -> std::io::Result<Option<Vec<u8>>> {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+        if buffer.len() > MAX_REQUEST_SIZE {
+            return Ok(None);
+        }
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    Ok(Some(buffer))
+}
+
+/// Route a request line such as `GET /health HTTP/1.1`.
+async fn respond(request_line: &str, state: &IntrospectionState) -> Response {
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Response::error(400, "Malformed request line");
+    };
+    if method != "GET" {
+        return Response::error(405, format!("Method not allowed: {}", method));
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = parse_query(query);
+
+    match path {
+        "/health" => Response::json(200, &*state.health.read().await),
+        "/metrics" => Response::text(render_metrics(state).await),
+        "/packages" => list_packages(state, query.get("prefix").map(String::as_str)).await,
+        "/resolve" => resolve(state, query.get("req").map(String::as_str)).await,
+        _ => Response::error(404, format!("Not found: {}", path)),
+    }
+}
+
+/// Render operation timings and index gauges in Prometheus format.
+async fn render_metrics(state: &IntrospectionState) -> String {
+    let mut output = state.metrics.render_prometheus().await;
+
+    let ready = matches!(*state.health.read().await, ServerHealth::Ready);
+    output.push_str("# HELP rez_lsp_ready Whether the package index is ready.\n");
+    output.push_str("# TYPE rez_lsp_ready gauge\n");
+    output.push_str(&format!("rez_lsp_ready {}\n", u8::from(ready)));
+
+    let discovery = state.package_discovery.read().await;
+    if let Some(discovery) = discovery.as_ref() {
+        let (families, packages) = discovery.get_stats().await.unwrap_or((0, 0));
+        output.push_str("# HELP rez_lsp_package_families Number of indexed package families.\n");
+        output.push_str("# TYPE rez_lsp_package_families gauge\n");
+        output.push_str(&format!("rez_lsp_package_families {}\n", families));
+        output.push_str("# HELP rez_lsp_packages Number of indexed packages.\n");
+        output.push_str("# TYPE rez_lsp_packages gauge\n");
+        output.push_str(&format!("rez_lsp_packages {}\n", packages));
+
+        output.push_str(
+            "# HELP rez_lsp_repository_scan_duration_ms Duration of the last scan of each repository.\n",
+        );
+        output.push_str("# TYPE rez_lsp_repository_scan_duration_ms gauge\n");
+        for repository in &discovery.metrics().repositories {
+            output.push_str(&format!(
+                "rez_lsp_repository_scan_duration_ms{{repository=\"{}\"}} {}\n",
+                escape_label_value(&repository.path.display().to_string()),
+                repository.duration_ms
+            ));
+        }
+    }
+
+    output
+}
+
+/// List indexed package families whose name starts with `prefix`.
+async fn list_packages(state: &IntrospectionState, prefix: Option<&str>) -> Response {
+    let discovery = state.package_discovery.read().await;
+    let Some(discovery) = discovery.as_ref() else {
+        return Response::error(503, "Package index is not available yet");
+    };
+
+    let prefix = prefix.unwrap_or_default();
+    let mut families: Vec<PackageFamily> = discovery
+        .packages()
+        .iter()
+        .filter(|(name, _)| name.starts_with(prefix))
+        .map(|(name, packages)| {
+            let mut versions: Vec<_> = packages.iter().map(|p| &p.version).collect();
+            versions.sort_unstable_by(|a, b| b.cmp(a));
+            PackageFamily {
+                name: name.clone(),
+                versions: versions.iter().map(|v| v.to_string()).collect(),
+            }
+        })
+        .collect();
+    families.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Response::json(200, &families)
+}
+
+/// Resolve the requirements in `req` against the package index.
+async fn resolve(state: &IntrospectionState, req: Option<&str>) -> Response {
+    let Some(req) = req.filter(|req| !req.trim().is_empty()) else {
+        return Response::error(400, "Missing query parameter: req");
+    };
+
+    let requirements: std::result::Result<Vec<Requirement>, String> = req
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(Requirement::parse)
+        .collect();
+    let requirements = match requirements {
+        Ok(requirements) => requirements,
+        Err(e) => return Response::error(400, format!("Invalid requirement: {}", e)),
+    };
+
+    let resolver = match state.package_discovery.read().await.as_ref() {
+        Some(discovery) => create_resolver(discovery.config(), discovery.packages().clone()),
+        None => return Response::error(503, "Package index is not available yet"),
+    };
+
+    match resolver.resolve(&requirements).await {
+        Ok(context) => Response::json(200, &context),
+        Err(e) => Response::error(422, format!("Failed to resolve requirements: {}", e)),
+    }
+}
+
+/// Parse a URL query string into decoded key/value pairs.
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` in a URL query component.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' if index + 2 < bytes.len() => {
+                let byte = std::str::from_utf8(&bytes[index + 1..index + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match byte {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        index += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Reason phrase of the status codes produced by the endpoint.
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_state() -> IntrospectionState {
+        IntrospectionState {
+            health: Arc::new(RwLock::new(ServerHealth::Initializing)),
+            package_discovery: Arc::new(RwLock::new(None)),
+            metrics: Arc::new(MetricsCollector::new(100)),
+        }
+    }
+
+    async fn get(addr: SocketAddr, target: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = parse_query("req=maya-2024%2B+python-3&prefix=");
+        assert_eq!(query["req"], "maya-2024+ python-3");
+        assert_eq!(query["prefix"], "");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    #[tokio::test]
+    async fn test_routes() {
+        let state = create_test_state();
+        state
+            .metrics
+            .record_duration("hover", std::time::Duration::from_millis(5))
+            .await;
+
+        let response = respond("GET /health HTTP/1.1", &state).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, r#"{"state":"initializing"}"#);
+
+        let response = respond("GET /metrics HTTP/1.1", &state).await;
+        assert!(response.body.contains("rez_lsp_ready 0"));
+        assert!(response
+            .body
+            .contains("rez_lsp_operation_duration_ms_count{operation=\"hover\"} 1"));
+
+        assert_eq!(respond("GET /packages HTTP/1.1", &state).await.status, 503);
+        assert_eq!(respond("GET /resolve HTTP/1.1", &state).await.status, 400);
+        assert_eq!(respond("GET /nope HTTP/1.1", &state).await.status, 404);
+        assert_eq!(respond("POST /health HTTP/1.1", &state).await.status, 405);
+    }
+
+    #[tokio::test]
+    async fn test_serve_over_tcp() {
+        let listener = bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, create_test_state()));
+
+        let response = get(addr, "/health").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: application/json"));
+        assert!(response.ends_with(r#"{"state":"initializing"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        client.write_all(b"GET /health HTTP/1.1\r\n").await.unwrap();
+
+        handle_connection(stream, &create_test_state(), Duration::from_millis(50))
+            .await
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+    }
+}
@@ -0,0 +1,112 @@
+//! Inlay hints showing the version each requirement resolves to.
+//!
+//! The version is the one picked from the package index by
+//! [`PackageDiscoveryImpl::pick_version`]; pre-releases are marked so they
+//! stand out next to the requirement.
+
+use tower_lsp::lsp_types::{InlayHint, InlayHintLabel, InlayHintTooltip, Range};
+
+use crate::core::Requirement;
+use crate::discovery::PackageDiscoveryImpl;
+use crate::parser::find_requirements;
+use crate::server::positions::{LineIndex, PositionEncoding};
+
+/// Get the resolved version hints of the requirements on the lines of
+/// `range`.
+///
+/// Conflicts and ephemerals do not pick a version and get no hint.
+pub fn requirement_inlay_hints(
+    content: &str,
+    range: Range,
+    discovery: &PackageDiscoveryImpl,
+    encoding: PositionEncoding,
+) -> Vec<InlayHint> {
+    let index = LineIndex::new(content, encoding);
+    find_requirements(content)
+        .into_iter()
+        .filter(|span| {
+            !span.is_ephemeral() && (range.start.line..=range.end.line).contains(&span.range.line)
+        })
+        .filter_map(|span| {
+            let requirement = Requirement::parse(&span.text)
+                .ok()
+                .filter(|r| !r.conflict)?;
+            let picked = discovery.pick_version(&requirement.name, &requirement.constraint)?;
+            if picked.version.is_unversioned() {
+                return None;
+            }
+
+            let prerelease = picked.version.is_prerelease();
+            let label = if prerelease {
+                format!("→ {} (pre-release)", picked.version)
+            } else {
+                format!("→ {}", picked.version)
+            };
+            Some(InlayHint {
+                // After the closing quote
+                position: index.position(span.range.line, span.range.end + 1),
+                label: InlayHintLabel::String(label),
+                kind: None,
+                text_edits: None,
+                tooltip: prerelease.then(|| {
+                    InlayHintTooltip::String(format!(
+                        "{} is a pre-release; set REZ_LSP_EXCLUDE_PRERELEASES to prefer releases",
+                        picked.version
+                    ))
+                }),
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::{scan_repositories, write_package};
+    use tower_lsp::lsp_types::Position;
+
+    fn label(hint: &InlayHint) -> &str {
+        match &hint.label {
+            InlayHintLabel::String(label) => label,
+            InlayHintLabel::LabelParts(_) => panic!("expected a string label"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requirement_inlay_hints() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for (family, version) in [
+            ("maya", "2024.1"),
+            ("maya", "2025.0-beta1"),
+            ("nuke", "15.0"),
+        ] {
+            write_package(
+                temp_dir.path(),
+                family,
+                version,
+                &format!("name = '{}'\n", family),
+            );
+        }
+        let discovery = scan_repositories(&[temp_dir.path()]).await;
+
+        let content =
+            "requires = [\n    'maya',\n    'nuke-15',\n    '!nuke-14',\n    'houdini',\n]\n";
+        let range = Range::new(Position::new(0, 0), Position::new(6, 0));
+        let hints = requirement_inlay_hints(content, range, &discovery, PositionEncoding::Utf16);
+        assert_eq!(hints.len(), 2);
+
+        assert_eq!(hints[0].position, Position::new(1, 10));
+        assert_eq!(label(&hints[0]), "→ 2025.0-beta1 (pre-release)");
+        assert!(hints[0].tooltip.is_some());
+        assert_eq!(label(&hints[1]), "→ 15.0");
+        assert!(hints[1].tooltip.is_none());
+
+        // Only the requested lines get hints
+        let range = Range::new(Position::new(2, 0), Position::new(2, 0));
+        let hints = requirement_inlay_hints(content, range, &discovery, PositionEncoding::Utf16);
+        assert_eq!(hints.len(), 1);
+    }
+}
@@ -0,0 +1,133 @@
+//! Document links to documentation, build scripts and paths.
+//!
+//! Links are returned without a target; the target is filled in by
+//! `documentLink/resolve` from the link's `data`, so file URIs are only built
+//! for links the user actually follows.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{DocumentLink, Url};
+
+use crate::config::PathMapping;
+use crate::parser::{find_links, LinkKind};
+use crate::server::path_mapping::{client_uri, server_path};
+use crate::server::positions::{LineIndex, PositionEncoding};
+
+/// Target of a link kept in its `data` until it is resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum LinkData {
+    Url { url: String },
+    BuildScript { directory: PathBuf, script: String },
+    Path { path: String },
+}
+
+/// Get the unresolved links of a package definition.
+///
+/// Build script directories are kept as server paths; `mappings` translate
+/// the client URI of the definition.
+pub fn document_links(
+    uri: &Url,
+    content: &str,
+    encoding: PositionEncoding,
+    mappings: &[PathMapping],
+) -> Vec<DocumentLink> {
+    let directory = server_path(uri, mappings).and_then(|path| path.parent().map(PathBuf::from));
+    let index = LineIndex::new(content, encoding);
+
+    find_links(content)
+        .into_iter()
+        .filter_map(|link| {
+            let (data, tooltip) = match link.kind {
+                LinkKind::Url => (LinkData::Url { url: link.target }, "Open documentation"),
+                LinkKind::BuildScript => (
+                    LinkData::BuildScript {
+                        directory: directory.clone()?,
+                        script: link.target,
+                    },
+                    "Open build script",
+                ),
+                LinkKind::Path => (LinkData::Path { path: link.target }, "Open path"),
+            };
+            Some(DocumentLink {
+                range: index.range(link.range),
+                target: None,
+                tooltip: Some(tooltip.to_string()),
+                data: serde_json::to_value(data).ok(),
+            })
+        })
+        .collect()
+}
+
+/// Fill in the target of a link from its `data`.
+///
+/// File targets are mapped to the client's view of the server paths. Links
+/// whose data cannot be turned into a URI are returned unchanged.
+pub fn resolve_link(mut link: DocumentLink, mappings: &[PathMapping]) -> DocumentLink {
+    let Some(data) = link
+        .data
+        .clone()
+        .and_then(|data| serde_json::from_value::<LinkData>(data).ok())
+    else {
+        return link;
+    };
+
+    link.target = match data {
+        LinkData::Url { url } => Url::parse(&url).ok(),
+        LinkData::BuildScript { directory, script } => {
+            // `{root}` in build commands is the package source directory
+            let relative = script
+                .strip_prefix("{root}")
+                .unwrap_or(&script)
+                .trim_start_matches(['/', '\\']);
+            client_uri(&directory.join(relative), mappings)
+        }
+        LinkData::Path { path } => client_uri(Path::new(&path), mappings),
+    };
+    link
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_links_resolve() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package_dir = temp_dir.path().join("my_tool");
+        let bin_dir = temp_dir.path().join("opt").join("bin");
+        let uri = Url::from_file_path(package_dir.join("package.py")).unwrap();
+        let content = format!(
+            "help = 'https://docs.example.com'\nbuild_command = 'bash {{root}}/build.sh'\n\ndef commands():\n    env.PATH.append('{}')\n",
+            bin_dir.display()
+        );
+
+        let links = document_links(&uri, &content, PositionEncoding::Utf16, &[]);
+        assert_eq!(links.len(), 3);
+        assert!(links.iter().all(|link| link.target.is_none()));
+
+        let targets: Vec<Url> = links
+            .into_iter()
+            .map(|link| resolve_link(link, &[]).target.unwrap())
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                Url::parse("https://docs.example.com/").unwrap(),
+                Url::from_file_path(package_dir.join("build.sh")).unwrap(),
+                Url::from_file_path(&bin_dir).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_link_without_data() {
+        let link = DocumentLink {
+            range: Default::default(),
+            target: None,
+            tooltip: None,
+            data: None,
+        };
+        assert_eq!(resolve_link(link.clone(), &[]), link);
+    }
+}
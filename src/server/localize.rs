@@ -0,0 +1,192 @@
+//! Copying released packages into the local package repository.
+//!
+//! Debugging a released package usually starts by copying it into
+//! `REZ_LOCAL_PACKAGES_PATH`, where it can be edited and rebuilt without
+//! touching the release. [`LOCALIZE_PACKAGE_COMMAND`] copies the version
+//! directory (definition and payload), adds the copy to the index and can pin
+//! the requirements of the current document to the copied version.
+
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::TextEdit;
+
+use crate::core::Requirement;
+use crate::parser::{find_requirements, SourceRange};
+use crate::server::positions::{LineIndex, PositionEncoding};
+
+/// Command copying a package version into the local package repository.
+///
+/// Arguments are the package name, its version and optionally the URI of a
+/// document whose requirements on the package are pinned to that version.
+pub const LOCALIZE_PACKAGE_COMMAND: &str = "rez.localizePackage";
+
+/// Result of [`LOCALIZE_PACKAGE_COMMAND`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalizedPackage {
+    /// Package name
+    pub package: String,
+    /// Copied version
+    pub version: String,
+    /// Directory the package was copied from
+    pub source: PathBuf,
+    /// Directory of the copy in the local repository
+    pub destination: PathBuf,
+    /// Number of files copied
+    pub files: usize,
+    /// Number of requirements pinned to the copied version
+    pub pinned: usize,
+}
+
+/// Copy a package version directory to `destination`, which must not exist.
+///
+/// Symbolic links are recreated rather than followed on Unix. The package is
+/// copied into a hidden sibling directory first and renamed into place, so a
+/// failed copy leaves nothing behind. Returns the number of files copied.
+pub fn copy_package(source: &Path, destination: &Path) -> io::Result<usize> {
+    if destination.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", destination.display()),
+        ));
+    }
+    let (Some(parent), Some(name)) = (destination.parent(), destination.file_name()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a package directory", destination.display()),
+        ));
+    };
+    fs::create_dir_all(parent)?;
+
+    let partial = parent.join(format!(
+        ".{}.partial-{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let copied = copy_dir(source, &partial).and_then(|files| {
+        fs::rename(&partial, destination)?;
+        Ok(files)
+    });
+    if copied.is_err() {
+        let _ = fs::remove_dir_all(&partial);
+    }
+    copied
+}
+
+fn copy_dir(source: &Path, destination: &Path) -> io::Result<usize> {
+    fs::create_dir(destination)?;
+    let mut files = 0;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = destination.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            files += copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_symlink() && cfg!(unix) {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+            files += 1;
+        } else {
+            fs::copy(entry.path(), &target)?;
+            files += 1;
+        }
+    }
+    Ok(files)
+}
+
+/// Build the edits pinning the requirements on `name` to `version`, e.g.
+/// `~maya-2024+` to `~maya==2024.1`.
+///
+/// Conflicts and ephemerals are left alone.
+pub fn pin_requirement_edits(
+    content: &str,
+    name: &str,
+    version: &str,
+    encoding: PositionEncoding,
+) -> Vec<TextEdit> {
+    let index = LineIndex::new(content, encoding);
+    find_requirements(content)
+        .into_iter()
+        .filter(|span| span.name_text() == name)
+        .filter(|span| Requirement::parse(&span.text).is_ok_and(|r| !r.conflict))
+        .map(|span| TextEdit {
+            range: index.range(SourceRange {
+                line: span.range.line,
+                start: span.name.start,
+                end: span.range.end,
+            }),
+            new_text: format!("{}=={}", name, version),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::write_package;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    #[test]
+    fn test_copy_package() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = write_package(
+            &temp_dir.path().join("release"),
+            "maya_tools",
+            "1.0.0",
+            "name = 'maya_tools'\n",
+        );
+        fs::create_dir_all(source.join("python").join("maya_tools")).unwrap();
+        fs::write(
+            source.join("python").join("maya_tools").join("__init__.py"),
+            "",
+        )
+        .unwrap();
+
+        let destination = temp_dir
+            .path()
+            .join("local")
+            .join("maya_tools")
+            .join("1.0.0");
+        assert_eq!(copy_package(&source, &destination).unwrap(), 2);
+        assert!(destination.join("package.py").is_file());
+        assert!(destination
+            .join("python")
+            .join("maya_tools")
+            .join("__init__.py")
+            .is_file());
+
+        // An existing copy is never overwritten
+        let error = copy_package(&source, &destination).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_failed_copy_leaves_nothing_behind() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let local = temp_dir.path().join("local").join("maya_tools");
+        let missing = temp_dir.path().join("release").join("maya_tools");
+
+        assert!(copy_package(&missing, &local.join("1.0.0")).is_err());
+        assert_eq!(fs::read_dir(&local).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_pin_requirement_edits() {
+        let content =
+            "requires = ['~maya-2024+', 'python-3']\nvariants = [['maya'], ['!maya-2023']]\n";
+        let edits = pin_requirement_edits(content, "maya", "2024.1", PositionEncoding::Utf16);
+
+        assert_eq!(edits.len(), 2);
+        assert_eq!(
+            edits[0].range,
+            Range::new(Position::new(0, 14), Position::new(0, 24))
+        );
+        assert_eq!(edits[0].new_text, "maya==2024.1");
+        assert_eq!(
+            edits[1].range,
+            Range::new(Position::new(1, 14), Position::new(1, 18))
+        );
+    }
+}
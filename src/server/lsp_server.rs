@@ -1,50 +1,800 @@
 //! Main LSP server implementation.
 
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
-use tower_lsp::{Client, LanguageServer};
-use tracing::info;
+use tower_lsp::{Client, ClientSocket, LanguageServer, LspService};
+use tracing::{info, warn};
 
-use crate::config::RezConfigProvider;
-use crate::core::{ConfigProvider, PackageDiscovery as PackageDiscoveryTrait};
-use crate::discovery::PackageDiscoveryImpl;
+use crate::analysis::{
+    load_environments, DependencyManifest, ManifestFormat, PackageUsageStats,
+    StaleEnvironmentReport,
+};
+use crate::config::{Config, PackageEvaluation, PathMapping, RezConfigProvider};
+use crate::core::{
+    BumpPart, CompletionProvider, ConfigProvider, DependencyResolver, Error, HoverProvider,
+    Package, PackageDiscovery as PackageDiscoveryTrait, Requirement, ResolvedContext, Version,
+    VersionConstraint,
+};
+use crate::discovery::{
+    DiscoveryMetrics, PackageDiscoveryImpl, ResolvedEnvironment, WorkspaceWalker,
+};
+use crate::parser::encoding::{read_source, strip_bom_in_place};
+use crate::performance::profiler::ProfilerStats;
+use crate::performance::{
+    CacheManager, ChromeTrace, MetricsCollector, PerformanceConfig, Profiler, TextfileExporter,
+};
+use crate::resolver::{create_resolver, EnvironmentPreview};
+use crate::server::advisories::advisory_source;
+use crate::server::changelog::{self, ChangelogDocument, ChangelogParams};
+use crate::server::client_features::ClientFeatures;
+use crate::server::code_actions;
+use crate::server::documents::{is_supported_document, DocumentKinds, DocumentStore};
+use crate::server::errors::{ErrorLog, ErrorReport};
+use crate::server::file_renames::{
+    directory_rename_filter, is_reference_file, package_moves, PackageMove,
+};
+use crate::server::formatting;
+use crate::server::inlay_hints;
+use crate::server::links;
+use crate::server::localize::{self, LocalizedPackage, LOCALIZE_PACKAGE_COMMAND};
+use crate::server::notifications::{send_index_stats, send_read_only_hint, send_resolution_health};
+use crate::server::package_browser::{self, FamilyPage, ListFamiliesParams};
+use crate::server::parent_process::{is_process_alive, wait_for_exit, PARENT_POLL_INTERVAL};
+use crate::server::path_mapping::{client_uri, server_path};
+use crate::server::positions::{LineIndex, PositionEncoding};
+use crate::server::protocol_trace::{ProtocolTrace, TracedMessage};
+use crate::server::providers::IndexProvider;
+use crate::server::reference_index::{IndexedFile, ReferenceIndex};
+use crate::server::release_check::{self, ReleaseReport, PRE_RELEASE_CHECK_COMMAND};
+use crate::server::session::{
+    content_hash, SessionDocument, SessionSaver, SessionState, SessionStore,
+};
+use crate::server::setup_wizard::{self, SetupEnvironment, SetupReport, SETUP_WIZARD_COMMAND};
+use crate::server::tasks::TaskRegistry;
+use crate::server::test_runner::{self, TestRunner, TestStatus, RUN_TEST_COMMAND};
+use crate::server::trust::{WorkspaceTrust, TRUST_WORKSPACE_COMMAND};
+use crate::server::version_bump::{bump_version_edit, BUMP_VERSION_COMMAND};
+use crate::server::virtual_documents::{self, PackageContent, PackageContentParams};
+use crate::server::watchdog::{self, ScanCaches, ScanOutcome, ServerHealth};
 use crate::server::{navigation::NavigationHandler, DiagnosticsManager};
+use crate::validation::validation_engine::LARGE_FILE_THRESHOLD;
+use crate::validation::{diagnostic_codes, DiagnosticCodeInfo};
+
+/// Parameters for the `rez/packageUsageStats` request.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageUsageParams {
+    /// Only report usage of this family
+    #[serde(default)]
+    pub family: Option<String>,
+    /// Maximum number of families to report
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Parameters for the `rez/lastErrors` request.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastErrorsParams {
+    /// Maximum number of errors to return, newest first
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Parameters for the `rez/dumpTrace` request.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpTraceParams {
+    /// Maximum number of messages to return, newest last
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Parameters for the `rez/exportTrace` request.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTraceParams {
+    /// Also write the trace to this file
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+/// Response of the `rez/exportTrace` request.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTraceResponse {
+    /// Number of exported profiling sessions
+    pub session_count: usize,
+    /// File the trace was written to, if requested
+    pub path: Option<PathBuf>,
+    /// Chrome trace-event JSON document
+    pub trace: serde_json::Value,
+}
+
+/// Parameters for the `rez/previewEnvironment` request.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewEnvironmentParams {
+    /// Package definition whose requirements are resolved
+    pub text_document: TextDocumentIdentifier,
+}
+
+/// Parameters for the `rez/loadAllVersions` request.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadAllVersionsParams {
+    /// Package family to load every version of
+    pub family: String,
+}
+
+/// Change of a package version reported by the `rez/indexUpdate` request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IndexChange {
+    /// The version was released or copied into a repository
+    Added,
+    /// The version was deleted from its repository
+    Removed,
+}
+
+/// Parameters for the `rez/indexUpdate` request.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexUpdateParams {
+    /// Package family of the changed version
+    pub family: String,
+    /// Changed version
+    pub version: String,
+    /// What happened to the version
+    pub change: IndexChange,
+}
+
+/// Response of the `rez/indexUpdate` request.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexUpdateResponse {
+    /// Whether the index changed
+    pub updated: bool,
+    /// Number of indexed versions of the family after the update
+    pub versions: usize,
+}
+
+/// Parameters for the `rez/staleEnvironments` request.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleEnvironmentsParams {
+    /// Directory of context (`.rxt`) and lock (`.lock`) files to audit
+    pub directory: PathBuf,
+}
+
+/// Response of the `rez/performanceReport` request.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceReport {
+    /// Health of the package index
+    pub health: ServerHealth,
+    /// Profiler statistics
+    pub profiler: ProfilerStats,
+    /// Scan metrics of the package index, once available
+    pub discovery: Option<DiscoveryMetrics>,
+}
+
+/// Maximum number of completed profiling sessions kept for export.
+const MAX_PROFILE_SESSIONS: usize = 100;
+
+/// Maximum number of document contents kept in memory.
+const MAX_OPEN_DOCUMENTS: usize = 1000;
+
+/// Maximum number of timings kept per operation.
+const MAX_METRICS_PER_OPERATION: usize = 1000;
+
+/// Maximum number of error reports kept for `rez/lastErrors`.
+const MAX_RECENT_ERRORS: usize = 100;
+
+/// Time background tasks get to stop on shutdown before they are aborted.
+const SHUTDOWN_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Interval at which changes of the session are written to disk.
+const SESSION_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Command producing the dependency manifest of a package definition.
+///
+/// The arguments are the URI of the package definition, which does not need
+/// to be open, and optionally the format, `spdx` (default) or `cyclonedx`.
+pub const DEPENDENCY_MANIFEST_COMMAND: &str = "rez.dependencyManifest";
 
 /// The main Rez Language Server.
 pub struct RezLanguageServer {
     /// LSP client for communication
     client: Client,
-    /// Document content cache
-    document_map: tokio::sync::RwLock<HashMap<Url, String>>,
+    /// Contents of open documents
+    document_map: Arc<tokio::sync::RwLock<DocumentStore>>,
     /// Configuration provider
     config_provider: Arc<tokio::sync::RwLock<RezConfigProvider>>,
     /// Package discovery service
     package_discovery: Arc<tokio::sync::RwLock<Option<PackageDiscoveryImpl>>>,
+    /// Families indexed so far while the initial scan runs, and families
+    /// reused by rescans
+    scan_caches: ScanCaches,
     /// Diagnostics manager
     diagnostics_manager: Arc<DiagnosticsManager>,
+    /// Whether the client pulls diagnostics instead of receiving them
+    pull_diagnostics: AtomicBool,
+    /// Whether the client applies workspace edits renaming files
+    rename_resources: AtomicBool,
+    /// Whether positions count UTF-8 code units instead of UTF-16 ones
+    utf8_positions: AtomicBool,
+    /// Response features supported by the client
+    client_features: tokio::sync::RwLock<ClientFeatures>,
     /// Navigation handler
     navigation_handler: Arc<NavigationHandler>,
+    /// Source of package completions
+    completion_provider: Arc<dyn CompletionProvider>,
+    /// Source of package hover information
+    hover_provider: Arc<dyn HoverProvider>,
+    /// Health of the package index, updated by the initialization watchdog
+    health: Arc<tokio::sync::RwLock<ServerHealth>>,
+    /// Rez-resolved environment of the server process (opt-in)
+    resolved_environment: Arc<tokio::sync::RwLock<Option<ResolvedEnvironment>>>,
+    /// Profiler for server operations
+    profiler: tokio::sync::RwLock<Profiler>,
+    /// Timings of request handlers and package scans
+    metrics: Arc<MetricsCollector>,
+    /// Runner of package tests started from code lenses
+    test_runner: Arc<TestRunner>,
+    /// Recent errors reported to the user
+    errors: Arc<ErrorLog>,
+    /// File the profiling trace is written to on shutdown
+    trace_output: Option<PathBuf>,
+    /// Store of the session of the workspace, if it is saved
+    session: tokio::sync::RwLock<Option<Arc<SessionSaver>>>,
+    /// Session saved before the last restart, until the index is ready
+    restored_session: tokio::sync::RwLock<Option<SessionState>>,
+    /// Background tasks, cancelled on shutdown
+    tasks: Arc<TaskRegistry>,
+    /// JSON-RPC traffic captured with `--stdio-trace`
+    protocol_trace: Option<Arc<ProtocolTrace>>,
+    /// Tokens of the workspace reference files, built on first use
+    reference_index: tokio::sync::RwLock<Option<ReferenceIndex>>,
+    /// Workspace folders searched for references to renamed packages
+    workspace_folders: tokio::sync::RwLock<Vec<Url>>,
+    /// Editor process to monitor when `initialize` names none
+    parent_pid: Option<u32>,
+    /// Whether package content may be executed or evaluated
+    workspace_trust: WorkspaceTrust,
+    /// Classifier of the documents receiving rez features
+    document_kinds: DocumentKinds,
 }
 
 impl RezLanguageServer {
     /// Create a new Rez Language Server instance.
     pub fn new(client: Client) -> Self {
-        let diagnostics_manager =
-            Arc::new(DiagnosticsManager::new().expect("Failed to create diagnostics manager"));
+        Self::with_trace_output(client, None)
+    }
 
+    /// Create a new server that writes a Chrome trace of its profiling
+    /// sessions to `trace_output` on shutdown.
+    ///
+    /// Profiling is always enabled when a trace output is set.
+    pub fn with_trace_output(client: Client, trace_output: Option<PathBuf>) -> Self {
         let package_discovery = Arc::new(tokio::sync::RwLock::new(None));
-        let navigation_handler = Arc::new(NavigationHandler::new(package_discovery.clone()));
+        let workspace_trust = WorkspaceTrust::default();
+        let diagnostics_manager = Arc::new(
+            DiagnosticsManager::new()
+                .expect("Failed to create diagnostics manager")
+                .with_package_index(package_discovery.clone())
+                .with_workspace_trust(workspace_trust.clone()),
+        );
+        let document_kinds = DocumentKinds::default();
+        let navigation_handler = Arc::new(
+            NavigationHandler::new(package_discovery.clone())
+                .with_document_kinds(document_kinds.clone()),
+        );
+        let scan_caches = ScanCaches::new(
+            package_discovery.clone(),
+            Arc::new(CacheManager::new(&PerformanceConfig::default())),
+        );
+        let index_provider = Arc::new(
+            IndexProvider::new(package_discovery.clone())
+                .with_warming_index(scan_caches.warming.clone()),
+        );
 
         Self {
             client,
-            document_map: tokio::sync::RwLock::new(HashMap::new()),
+            document_map: Arc::new(tokio::sync::RwLock::new(DocumentStore::new(
+                MAX_OPEN_DOCUMENTS,
+            ))),
             config_provider: Arc::new(tokio::sync::RwLock::new(RezConfigProvider::new())),
             package_discovery,
+            scan_caches,
             diagnostics_manager,
+            pull_diagnostics: AtomicBool::new(false),
+            rename_resources: AtomicBool::new(false),
+            utf8_positions: AtomicBool::new(false),
+            client_features: tokio::sync::RwLock::new(ClientFeatures::default()),
+            reference_index: tokio::sync::RwLock::new(None),
             navigation_handler,
+            completion_provider: index_provider.clone(),
+            hover_provider: index_provider,
+            health: Arc::new(tokio::sync::RwLock::new(ServerHealth::Initializing)),
+            resolved_environment: Arc::new(tokio::sync::RwLock::new(None)),
+            profiler: tokio::sync::RwLock::new(Profiler::new(
+                MAX_PROFILE_SESSIONS,
+                trace_output.is_some(),
+            )),
+            metrics: Arc::new(MetricsCollector::new(MAX_METRICS_PER_OPERATION)),
+            test_runner: Arc::new(TestRunner::new()),
+            errors: Arc::new(ErrorLog::new(MAX_RECENT_ERRORS)),
+            trace_output,
+            session: tokio::sync::RwLock::new(None),
+            restored_session: tokio::sync::RwLock::new(None),
+            tasks: Arc::new(TaskRegistry::new()),
+            protocol_trace: None,
+            workspace_folders: tokio::sync::RwLock::new(Vec::new()),
+            parent_pid: None,
+            workspace_trust,
+            document_kinds,
+        }
+    }
+
+    /// Serve the messages captured by a protocol trace to `rez/dumpTrace`.
+    pub fn with_protocol_trace(mut self, protocol_trace: Arc<ProtocolTrace>) -> Self {
+        self.protocol_trace = Some(protocol_trace);
+        self
+    }
+
+    /// Exit when the process `pid` is gone, unless the client names another
+    /// process in `initialize`.
+    pub fn with_parent_pid(mut self, pid: u32) -> Self {
+        self.parent_pid = Some(pid);
+        self
+    }
+
+    /// Replace the provider of package completions.
+    ///
+    /// Defaults to [`IndexProvider`] over the discovered packages.
+    pub fn with_completion_provider(mut self, provider: Arc<dyn CompletionProvider>) -> Self {
+        self.completion_provider = provider;
+        self
+    }
+
+    /// Replace the provider of package hover information.
+    ///
+    /// Defaults to [`IndexProvider`] over the discovered packages.
+    pub fn with_hover_provider(mut self, provider: Arc<dyn HoverProvider>) -> Self {
+        self.hover_provider = provider;
+        self
+    }
+
+    /// Get the current health of the package index.
+    pub async fn health(&self) -> ServerHealth {
+        self.health.read().await.clone()
+    }
+
+    /// Get the state shared with the HTTP introspection endpoint.
+    #[cfg(feature = "http")]
+    pub fn introspection_state(&self) -> crate::server::http::IntrospectionState {
+        crate::server::http::IntrospectionState {
+            health: self.health.clone(),
+            package_discovery: self.package_discovery.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+
+    /// Build the LSP service with all custom `rez/*` requests registered.
+    pub fn build_service() -> (LspService<Self>, ClientSocket) {
+        Self::build_service_with_trace_output(None)
+    }
+
+    /// Build the LSP service, writing a profiling trace to `trace_output` on
+    /// shutdown.
+    pub fn build_service_with_trace_output(
+        trace_output: Option<PathBuf>,
+    ) -> (LspService<Self>, ClientSocket) {
+        Self::build_service_with_protocol_trace(trace_output, None)
+    }
+
+    /// Build the LSP service, writing a profiling trace to `trace_output` on
+    /// shutdown and serving the messages of `protocol_trace` to
+    /// `rez/dumpTrace`.
+    pub fn build_service_with_protocol_trace(
+        trace_output: Option<PathBuf>,
+        protocol_trace: Option<Arc<ProtocolTrace>>,
+    ) -> (LspService<Self>, ClientSocket) {
+        Self::build_service_with(trace_output, |server| match protocol_trace {
+            Some(protocol_trace) => server.with_protocol_trace(protocol_trace),
+            None => server,
+        })
+    }
+
+    /// Build the LSP service, writing a profiling trace to `trace_output` on
+    /// shutdown and customizing the server with `configure`, e.g. with
+    /// [`Self::with_parent_pid`].
+    pub fn build_service_with(
+        trace_output: Option<PathBuf>,
+        configure: impl FnOnce(Self) -> Self,
+    ) -> (LspService<Self>, ClientSocket) {
+        LspService::build(|client| configure(Self::with_trace_output(client, trace_output)))
+            .custom_method("rez/diagnosticCodes", Self::diagnostic_codes)
+            .custom_method("rez/packageUsageStats", Self::package_usage_stats)
+            .custom_method("rez/exportTrace", Self::export_trace)
+            .custom_method("rez/previewEnvironment", Self::preview_environment)
+            .custom_method("rez/performanceReport", Self::performance_report)
+            .custom_method("rez/loadAllVersions", Self::load_all_versions)
+            .custom_method("rez/indexUpdate", Self::index_update)
+            .custom_method("rez/staleEnvironments", Self::stale_environments)
+            .custom_method("rez/lastErrors", Self::last_errors)
+            .custom_method("rez/dumpTrace", Self::dump_trace)
+            .custom_method("rez/changelog", Self::changelog)
+            .custom_method("rez/packageContent", Self::package_content)
+            .custom_method("rez/listFamilies", Self::list_families)
+            .finish()
+    }
+
+    /// Handle the `rez/diagnosticCodes` request.
+    ///
+    /// Returns the catalog of every diagnostic code the server can emit.
+    pub async fn diagnostic_codes(&self) -> Result<Vec<DiagnosticCodeInfo>> {
+        Ok(diagnostic_codes().to_vec())
+    }
+
+    /// Handle the `rez/lastErrors` request.
+    ///
+    /// Returns the most recent errors reported to the user, newest first,
+    /// for attaching to bug reports.
+    pub async fn last_errors(&self, params: LastErrorsParams) -> Result<Vec<ErrorReport>> {
+        Ok(self.errors.recent(params.limit).await)
+    }
+
+    /// Handle the `rez/dumpTrace` request.
+    ///
+    /// Returns the last JSON-RPC messages captured with `--stdio-trace`,
+    /// oldest first.
+    pub async fn dump_trace(&self, params: DumpTraceParams) -> Result<Vec<TracedMessage>> {
+        let Some(protocol_trace) = &self.protocol_trace else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Protocol tracing is off; start the server with --stdio-trace <FILE>",
+            ));
+        };
+        Ok(protocol_trace.last(params.limit))
+    }
+
+    /// Handle the `rez/packageUsageStats` request.
+    ///
+    /// Returns how many indexed packages depend on each family and which
+    /// constraints they request, optionally narrowed to a single family.
+    pub async fn package_usage_stats(
+        &self,
+        params: PackageUsageParams,
+    ) -> Result<PackageUsageStats> {
+        let discovery = self.package_discovery.read().await;
+        let Some(discovery) = discovery.as_ref() else {
+            return Ok(PackageUsageStats::default());
+        };
+
+        let index = discovery.usage_index();
+        Ok(match params.family {
+            Some(family) => PackageUsageStats {
+                analyzed_families: index.analyzed_families(),
+                families: vec![index.family_usage(&family)],
+            },
+            None => index.stats(params.limit),
+        })
+    }
+
+    /// Handle the `rez/exportTrace` request.
+    ///
+    /// Returns completed profiling sessions as a Chrome trace-event document
+    /// and optionally writes it to a file.
+    pub async fn export_trace(&self, params: ExportTraceParams) -> Result<ExportTraceResponse> {
+        let sessions = self.profiler.read().await.get_completed_sessions().await;
+        let trace = ChromeTrace::from_sessions(&sessions);
+
+        if let Some(path) = &params.path {
+            trace.write_to_file(path).map_err(|e| {
+                tower_lsp::jsonrpc::Error::invalid_params(format!(
+                    "Failed to write trace to {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        }
+
+        Ok(ExportTraceResponse {
+            session_count: sessions.len(),
+            path: params.path,
+            trace: trace.to_json(),
+        })
+    }
+
+    /// Handle the `rez/performanceReport` request.
+    ///
+    /// Reports server health, profiler statistics and per-repository scan
+    /// metrics so slow or broken package repositories can be pinpointed.
+    pub async fn performance_report(&self) -> Result<PerformanceReport> {
+        let discovery = self
+            .package_discovery
+            .read()
+            .await
+            .as_ref()
+            .map(|discovery| discovery.metrics().clone());
+
+        Ok(PerformanceReport {
+            health: self.health().await,
+            profiler: self.profiler.read().await.get_stats().await,
+            discovery,
+        })
+    }
+
+    /// Handle the `rez/loadAllVersions` request.
+    ///
+    /// Loads every version of a family into the index, including versions
+    /// left out by the per-family version cap.
+    pub async fn load_all_versions(
+        &self,
+        params: LoadAllVersionsParams,
+    ) -> Result<Vec<Arc<Package>>> {
+        // Scan the family with the index still readable by other requests,
+        // and lock it only to add the versions
+        let read = read_families(&self.package_discovery, vec![params.family.clone()]).await?;
+        let versions: Vec<Arc<Package>> = read
+            .into_iter()
+            .next()
+            .map_or(Ok(Vec::new()), |(_, versions)| versions)
+            .map_err(|e| {
+                let mut error = tower_lsp::jsonrpc::Error::internal_error();
+                error.message =
+                    format!("Failed to load versions of {}: {}", params.family, e).into();
+                error
+            })?
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+
+        let mut discovery = self.package_discovery.write().await;
+        let Some(discovery) = discovery.as_mut() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package index is not available yet",
+            ));
+        };
+        discovery.insert_all_versions(&params.family, versions.clone());
+        Ok(versions)
+    }
+
+    /// Handle the `rez/changelog` request.
+    ///
+    /// Collects the changelogs of the versions of a family newer than a
+    /// pinned version, as opened by the "view changes" code lenses.
+    pub async fn changelog(&self, params: ChangelogParams) -> Result<ChangelogDocument> {
+        let discovery = self.package_discovery.read().await;
+        let Some(discovery) = discovery.as_ref() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package index is not available yet",
+            ));
+        };
+
+        Ok(changelog::changelog_document(
+            discovery,
+            &params.family,
+            &Version::new(params.since),
+        ))
+    }
+
+    /// Handle the `rez/listFamilies` request.
+    ///
+    /// Returns a page of family summaries for package browser views.
+    pub async fn list_families(&self, params: ListFamiliesParams) -> Result<FamilyPage> {
+        let discovery = self.package_discovery.read().await;
+        let Some(discovery) = discovery.as_ref() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package index is not available yet",
+            ));
+        };
+
+        Ok(package_browser::list_families(discovery, &params))
+    }
+
+    /// Handle the `rez/packageContent` request.
+    ///
+    /// Serves the package.py of an indexed package for a `rezpkg://` virtual
+    /// document.
+    pub async fn package_content(&self, params: PackageContentParams) -> Result<PackageContent> {
+        let discovery = self.package_discovery.read().await;
+        let Some(discovery) = discovery.as_ref() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package index is not available yet",
+            ));
+        };
+
+        virtual_documents::package_content(discovery, &params.uri).map_err(|e| {
+            tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Failed to read {}: {}",
+                params.uri, e
+            ))
+        })
+    }
+
+    /// Handle the `rez/indexUpdate` request.
+    ///
+    /// Lets release tooling report an added or removed package version, so
+    /// the index is updated without a rescan even when file system events
+    /// are not seen, e.g. on network file systems. An added version is read
+    /// from the repositories; a removed one is dropped from the index.
+    pub async fn index_update(&self, params: IndexUpdateParams) -> Result<IndexUpdateResponse> {
+        if params.version.is_empty() || params.version.contains(['/', '\\']) {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Invalid version: '{}'",
+                params.version
+            )));
+        }
+        let not_ready =
+            || tower_lsp::jsonrpc::Error::invalid_params("Package index is not available yet");
+
+        // Read an added version with the index still readable by other
+        // requests, and lock it only to insert the result
+        let loaded = match params.change {
+            IndexChange::Added => {
+                let guard = self.package_discovery.read().await;
+                let discovery = guard.as_ref().ok_or_else(not_ready)?;
+                let loaded = discovery
+                    .read_version(&params.family, &params.version)
+                    .await
+                    .map_err(|e| {
+                        let mut error = tower_lsp::jsonrpc::Error::internal_error();
+                        error.message =
+                            format!("Failed to load {}-{}: {}", params.family, params.version, e)
+                                .into();
+                        error
+                    })?;
+                if loaded.is_none() {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                        "No repository contains {}-{}",
+                        params.family, params.version
+                    )));
+                }
+                loaded
+            }
+            IndexChange::Removed => None,
+        };
+
+        let mut guard = self.package_discovery.write().await;
+        let discovery = guard.as_mut().ok_or_else(not_ready)?;
+        let updated = match loaded {
+            Some(package) => {
+                discovery.insert_package(package);
+                true
+            }
+            None => discovery.remove_package(&params.family, &Version::new(&params.version)),
+        };
+        let family = discovery.config().canonical_package_name(&params.family);
+        let versions = discovery.packages().get(family).map_or(0, Vec::len);
+        info!(
+            "Index update: {}-{} {:?} ({} versions indexed)",
+            params.family, params.version, params.change, versions
+        );
+
+        if updated {
+            send_index_stats(&self.client, discovery).await;
+            drop(guard);
+            self.diagnostics_manager.package_index_changed().await;
+            if self.pull_diagnostics.load(Ordering::Relaxed) {
+                let _ = self.client.workspace_diagnostic_refresh().await;
+            }
+        }
+        Ok(IndexUpdateResponse { updated, versions })
+    }
+
+    /// Handle the `rez/staleEnvironments` request.
+    ///
+    /// Reports resolved packages of the contexts and lock files in a
+    /// directory that no longer exist or are superseded beyond their request.
+    pub async fn stale_environments(
+        &self,
+        params: StaleEnvironmentsParams,
+    ) -> Result<StaleEnvironmentReport> {
+        if !params.directory.is_dir() {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Not a directory: {}",
+                params.directory.display()
+            )));
+        }
+
+        let Some(config) = self
+            .package_discovery
+            .read()
+            .await
+            .as_ref()
+            .map(|discovery| discovery.config().clone())
+        else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package index is not available yet",
+            ));
+        };
+
+        // Read the environments and the capped families without locking the
+        // index for writing, off the async workers
+        let walker = WorkspaceWalker::from_config(&config);
+        let directory = params.directory.clone();
+        let (environments, errors) =
+            tokio::task::spawn_blocking(move || load_environments(&directory, &walker))
+                .await
+                .map_err(|e| {
+                    let mut error = tower_lsp::jsonrpc::Error::internal_error();
+                    error.message = format!("Failed to read environments: {}", e).into();
+                    error
+                })?;
+
+        let capped = match self.package_discovery.read().await.as_ref() {
+            Some(discovery) => discovery.capped_families(&environments),
+            None => Vec::new(),
+        };
+        let loaded = if capped.is_empty() {
+            Vec::new()
+        } else {
+            read_families(&self.package_discovery, capped).await?
+        };
+
+        let mut guard = self.package_discovery.write().await;
+        if let Some(discovery) = guard.as_mut() {
+            for (family, versions) in loaded {
+                match versions {
+                    Ok(versions) => discovery
+                        .insert_all_versions(&family, versions.into_iter().map(Arc::new).collect()),
+                    Err(e) => warn!("Failed to load all versions of {}: {}", family, e),
+                }
+            }
         }
+
+        let guard = guard.downgrade();
+        let Some(discovery) = guard.as_ref() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package index is not available yet",
+            ));
+        };
+        let mut report = StaleEnvironmentReport::audit(&environments, discovery.packages());
+        report.errors = errors;
+        Ok(report)
+    }
+
+    /// Handle the `rez/previewEnvironment` request.
+    ///
+    /// Resolves the requirements of the given package definition against the
+    /// package index and returns the environment the resolved packages'
+    /// `commands` would produce.
+    pub async fn preview_environment(
+        &self,
+        params: PreviewEnvironmentParams,
+    ) -> Result<EnvironmentPreview> {
+        let uri = &params.text_document.uri;
+        let Some(content) = self.document_map.read().await.get(uri) else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Document is not open: {}",
+                uri
+            )));
+        };
+
+        let discovery = self.package_discovery.read().await;
+        let Some(discovery) = discovery.as_ref() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package index is not available yet",
+            ));
+        };
+
+        let requirements = discovery.parse_requires(&content);
+        let resolver = create_resolver(discovery.config(), discovery.packages().clone());
+
+        let context = self.timed_resolve(resolver.as_ref(), &requirements).await;
+        let context = context.map_err(|e| {
+            let mut error = tower_lsp::jsonrpc::Error::internal_error();
+            error.message = format!("Failed to resolve requirements: {}", e).into();
+            error
+        })?;
+
+        Ok(EnvironmentPreview::from_context(&context))
     }
 
     /// Initialize the server components.
@@ -54,83 +804,1255 @@ impl RezLanguageServer {
         // Load configuration
         let mut config_provider = self.config_provider.write().await;
         if let Err(e) = config_provider.load_from_environment().await {
-            self.client
-                .log_message(
-                    MessageType::WARNING,
-                    format!("Failed to load configuration: {}", e),
-                )
-                .await;
+            let report = self.report_error("Configuration loading", &e, true).await;
+            *self.health.write().await = ServerHealth::Degraded(report.user_message());
             return Ok(());
         }
 
+        if config_provider.config().trust_workspace {
+            self.workspace_trust.trust();
+        }
+        self.diagnostics_manager
+            .set_package_evaluation(config_provider.config().package_evaluation)
+            .await;
+        self.document_kinds
+            .set_patterns(&config_provider.config().package_file_patterns);
+        self.diagnostics_manager
+            .set_package_aliases(config_provider.config().package_aliases.clone())
+            .await;
+        self.diagnostics_manager
+            .set_release_packages_path(config_provider.config().release_packages_path.clone())
+            .await;
+        self.diagnostics_manager
+            .set_path_mappings(config_provider.config().path_mappings.clone())
+            .await;
+        if let Err(e) = self
+            .diagnostics_manager
+            .set_version_policy(config_provider.config().version_policy.clone())
+            .await
+        {
+            warn!("Failed to apply version schemes: {}", e);
+        }
+
+        if config_provider.config().resolve_introspection {
+            let environment = ResolvedEnvironment::from_env();
+            match &environment {
+                Some(env) => info!(
+                    "Resolve introspection enabled with {} resolved packages",
+                    env.packages().len()
+                ),
+                None => info!("Resolve introspection enabled but REZ_USED_RESOLVE is not set"),
+            }
+            *self.resolved_environment.write().await = environment;
+        }
+
+        if config_provider.config().enable_profiling {
+            self.profiler.write().await.set_enabled(true).await;
+        }
+        let profiler = self.profiler.read().await.clone();
+        let root_guard = profiler.profile("initialize");
+
         // Validate configuration
+        let validate_guard = root_guard.as_ref().map(|g| g.child("validate_config"));
         if let Err(e) = config_provider.validate().await {
-            self.client
-                .log_message(
-                    MessageType::WARNING,
-                    format!("Configuration validation failed: {}", e),
-                )
+            let report = self
+                .report_error("Configuration validation", &e, true)
                 .await;
+            *self.health.write().await = ServerHealth::Degraded(report.user_message());
             return Ok(());
         }
 
+        drop(validate_guard);
+
         // Initialize package discovery
         let config = config_provider.config().clone();
         drop(config_provider); // Release the lock
 
-        let mut discovery = PackageDiscoveryImpl::new(config);
-        if let Err(e) = discovery.scan_packages().await {
-            self.client
-                .log_message(
-                    MessageType::WARNING,
-                    format!("Failed to scan packages: {}", e),
-                )
+        if let Some(path) = &config.metrics_textfile {
+            let exporter = TextfileExporter::new(
+                self.metrics.clone(),
+                path,
+                std::time::Duration::from_secs(config.metrics_textfile_interval_secs),
+            );
+            info!("Writing metrics to {}", exporter.path().display());
+            self.tasks.spawn("metrics-textfile", exporter.run());
+        }
+
+        if let Some(location) = &config.advisories {
+            // Fetching may take a while; the scan does not need the list
+            let source = advisory_source(location);
+            let client = self.client.clone();
+            let errors = self.errors.clone();
+            let diagnostics_manager = self.diagnostics_manager.clone();
+            self.tasks.spawn("advisories", async move {
+                match source.load().await {
+                    Ok(advisories) => {
+                        info!(
+                            "Loaded {} package advisories from {}",
+                            advisories.len(),
+                            source.describe()
+                        );
+                        diagnostics_manager.set_advisories(advisories).await;
+                    }
+                    Err(e) => {
+                        report_error(&client, &errors, "Loading package advisories", &e, false)
+                            .await;
+                    }
+                }
+            });
+        }
+
+        let scan_guard = root_guard.as_ref().map(|g| g.child("scan_packages"));
+        self.scan_packages(&config).await;
+        drop(scan_guard);
+
+        if let Some(guard) = root_guard {
+            let session_id = guard.session_id();
+            drop(guard);
+            profiler
+                .complete_session(session_id, "initialize".to_string())
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// Scan the package repositories of `config` and install the new
+    /// package index.
+    ///
+    /// When the scan times out the server is degraded and the index is
+    /// installed by a recovery task instead.
+    async fn scan_packages(&self, config: &Config) {
+        // Trust granted after the configuration was loaded applies as well
+        let config = &self.workspace_trust.restrict(config);
+        let timeout = std::time::Duration::from_secs(config.scan_timeout_secs);
+        let scan_start = std::time::Instant::now();
+        let outcome =
+            watchdog::scan_with_timeout(config.clone(), timeout, &self.tasks, &self.scan_caches)
                 .await;
+        self.metrics
+            .record_duration("scan_packages", scan_start.elapsed())
+            .await;
+
+        let discovery = match outcome {
+            ScanOutcome::Completed(discovery, result) => {
+                if let Err(e) = result {
+                    self.report_error("Package scan", &e, true).await;
+                } else {
+                    let (families, total) = discovery.get_stats().await.unwrap_or((0, 0));
+                    self.client
+                        .log_message(
+                            MessageType::INFO,
+                            format!(
+                                "Discovered {} package families ({} total packages): {}",
+                                families,
+                                total,
+                                discovery.metrics().summary()
+                            ),
+                        )
+                        .await;
+                    send_index_stats(&self.client, &discovery).await;
+                }
+                discovery
+            }
+            ScanOutcome::TimedOut(pending) => {
+                let message = watchdog::degraded_message(config, timeout);
+                *self.health.write().await = ServerHealth::Degraded(message.clone());
+                self.client
+                    .show_message(MessageType::WARNING, message)
+                    .await;
+
+                watchdog::spawn_recovery(
+                    &self.tasks,
+                    &self.scan_caches,
+                    self.client.clone(),
+                    pending,
+                    config.clone(),
+                    self.health.clone(),
+                );
+                return;
+            }
+        };
+
+        self.scan_caches.install(*discovery).await;
+        *self.health.write().await = ServerHealth::Ready;
+        self.diagnostics_manager.package_index_changed().await;
+
+        // Diagnostics pulled before the configuration was loaded are stale
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            let _ = self.client.workspace_diagnostic_refresh().await;
         } else {
-            let (families, total) = discovery.get_stats().await.unwrap_or((0, 0));
-            self.client
-                .log_message(
-                    MessageType::INFO,
-                    format!(
-                        "Discovered {} package families ({} total packages)",
-                        families, total
-                    ),
+            self.republish_open_documents().await;
+        }
+    }
+
+    /// Reload the configuration after `workspace/didChangeConfiguration`,
+    /// rescanning the package repositories when their paths changed.
+    async fn reload_configuration(&self, settings: &serde_json::Value) {
+        let package_paths = settings
+            .get("rezLsp")
+            .unwrap_or(settings)
+            .get("packagePaths")
+            .and_then(serde_json::Value::as_array)
+            .map(|paths| {
+                paths
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            });
+
+        let mut config_provider = self.config_provider.write().await;
+        let previous = config_provider.config().clone();
+        if let Some(paths) = package_paths {
+            config_provider.set_package_paths(paths);
+        }
+        let loaded = match config_provider.load_from_environment().await {
+            Ok(()) => config_provider.validate().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = loaded {
+            let report = self.report_error("Configuration loading", &e, true).await;
+            *self.health.write().await = ServerHealth::Degraded(report.user_message());
+            return;
+        }
+        let config = config_provider.config().clone();
+        drop(config_provider);
+        self.diagnostics_manager
+            .set_package_evaluation(config.package_evaluation)
+            .await;
+
+        let paths_changed = config.packages_path != previous.packages_path
+            || config.local_packages_path != previous.local_packages_path
+            || config.release_packages_path != previous.release_packages_path;
+        if paths_changed || matches!(*self.health.read().await, ServerHealth::Degraded(_)) {
+            info!("Package paths changed, rescanning package repositories");
+            self.scan_packages(&config).await;
+        } else {
+            // Picks and resolves follow the new resolve settings
+            let config = self.workspace_trust.restrict(&config);
+            if let Some(discovery) = self.package_discovery.write().await.as_mut() {
+                discovery.set_config(config);
+            }
+            self.diagnostics_manager.package_index_changed().await;
+        }
+    }
+
+    /// Validate the open package definitions again and push their
+    /// diagnostics, once the package index is ready.
+    ///
+    /// Documents opened before were validated without the index, or got the
+    /// diagnostics of the restored session; both are replaced.
+    async fn republish_open_documents(&self) {
+        self.restored_session.write().await.take();
+        publish_package_diagnostics(
+            &self.client,
+            &self.document_map,
+            &self.document_kinds,
+            &self.diagnostics_manager,
+        )
+        .await;
+        self.save_session().await;
+    }
+
+    /// Schedule saving the open package definitions and their diagnostics
+    /// to the session store.
+    ///
+    /// The session is written in the background every
+    /// [`SESSION_SAVE_INTERVAL`] and on shutdown. Nothing is saved before
+    /// the package index is ready, so the restored session is not replaced
+    /// by diagnostics computed without the index.
+    async fn save_session(&self) {
+        let session = self.session.read().await;
+        let Some(saver) = session.as_ref() else {
+            return;
+        };
+        let index_generation = match self.package_discovery.read().await.as_ref() {
+            Some(discovery) => discovery.shared_generation(),
+            None => return,
+        };
+
+        let open: Vec<(Url, u64)> = self
+            .document_map
+            .read()
+            .await
+            .iter()
+            .filter(|(uri, _)| self.document_kinds.is_package_definition(uri))
+            .map(|(uri, content)| (uri.clone(), content_hash(content)))
+            .collect();
+        let mut documents = Vec::with_capacity(open.len());
+        for (uri, content_hash) in open {
+            let diagnostics = self.diagnostics_manager.get_diagnostics(&uri).await;
+            documents.push(SessionDocument {
+                uri,
+                content_hash,
+                diagnostics,
+            });
+        }
+
+        let state = SessionState {
+            index_generation,
+            documents,
+            ..Default::default()
+        };
+        saver.schedule(state);
+    }
+
+    /// Store the content of a document, dropping the least recently used
+    /// document once too many are open.
+    async fn store_document(&self, uri: Url, text: String) {
+        // Read the tokens before locking the index, which then only updates
+        // the postings of tokens added or removed by the edit
+        let path = server_path(&uri, &self.path_mappings().await).filter(|p| is_reference_file(p));
+        let indexed = self.reference_index.read().await.is_some();
+        if let Some(path) = path.filter(|_| indexed) {
+            let file = IndexedFile::new(&path, &text, self.position_encoding());
+            if let Some(index) = self.reference_index.write().await.as_mut() {
+                index.insert_file(path, file);
+            }
+        }
+        let evicted = self.document_map.write().await.insert(uri, text);
+        if let Some(evicted) = evicted {
+            tracing::debug!("Evicted document from cache: {}", evicted);
+            self.diagnostics_manager.clear_diagnostics(&evicted).await;
+        }
+    }
+
+    /// Build the reference index of the workspace folders on first use.
+    ///
+    /// Open documents are indexed from their content in the editor; the
+    /// index then follows document changes and watched file events.
+    async fn reference_index(&self) -> tokio::sync::RwLockReadGuard<'_, Option<ReferenceIndex>> {
+        if self.reference_index.read().await.is_none() {
+            let mappings = self.path_mappings().await;
+            let roots: Vec<PathBuf> = self
+                .workspace_folders
+                .read()
+                .await
+                .iter()
+                .filter_map(|uri| server_path(uri, &mappings))
+                .collect();
+            let walker = WorkspaceWalker::from_config(self.config_provider.read().await.config());
+            let encoding = self.position_encoding();
+            let start = std::time::Instant::now();
+            let mut index = tokio::task::spawn_blocking(move || {
+                ReferenceIndex::build(&roots, &walker, is_reference_file, encoding)
+            })
+            .await
+            .unwrap_or_else(|_| ReferenceIndex::new(encoding));
+            for (uri, content) in self.document_map.read().await.iter() {
+                if let Some(path) = server_path(uri, &mappings).filter(|p| is_reference_file(p)) {
+                    index.insert(path, content);
+                }
+            }
+            info!(
+                "Indexed {} reference files in {:?}",
+                index.len(),
+                start.elapsed()
+            );
+            self.reference_index.write().await.get_or_insert(index);
+        }
+        self.reference_index.read().await
+    }
+
+    /// Re-index a reference file from disk after it was closed, created,
+    /// changed or deleted outside the editor.
+    async fn reindex_from_disk(&self, uri: &Url) {
+        let mut guard = self.reference_index.write().await;
+        let Some(index) = guard.as_mut() else {
+            return;
+        };
+        let Some(path) = server_path(uri, &self.path_mappings().await) else {
+            return;
+        };
+        if is_reference_file(&path) && self.document_map.read().await.revision(uri).is_none() {
+            index.reload(&path);
+        }
+    }
+
+    /// Get the position encoding negotiated with the client.
+    fn position_encoding(&self) -> PositionEncoding {
+        if self.utf8_positions.load(Ordering::Relaxed) {
+            PositionEncoding::Utf8
+        } else {
+            PositionEncoding::Utf16
+        }
+    }
+
+    /// Resolve requirements, recording the duration and failures of the
+    /// `resolve` operation.
+    async fn timed_resolve(
+        &self,
+        resolver: &dyn DependencyResolver,
+        requirements: &[Requirement],
+    ) -> crate::core::Result<ResolvedContext> {
+        let start = std::time::Instant::now();
+        let result = resolver.resolve(requirements).await;
+        self.metrics
+            .record_duration("resolve", start.elapsed())
+            .await;
+        if result.is_err() {
+            self.metrics.record_error("resolve").await;
+        }
+        result
+    }
+
+    /// Resolve requirements against the index, reusing the context resolved
+    /// for the same requirements until the index changes.
+    ///
+    /// Returns `None` without an index or when resolving fails.
+    async fn cached_resolve(&self, requirements: &[Requirement]) -> Option<ResolvedContext> {
+        let pending = match self
+            .package_discovery
+            .read()
+            .await
+            .as_ref()?
+            .cached_resolve(requirements)
+        {
+            Ok(context) => return context,
+            Err(pending) => pending,
+        };
+        let context = match self.timed_resolve(pending.resolver(), requirements).await {
+            Ok(context) => Some(context),
+            Err(e) => {
+                tracing::debug!("Resolving {:?} failed: {}", requirements, e);
+                None
+            }
+        };
+        pending.finish(context)
+    }
+
+    /// Get the directories mapped between server paths and client URIs.
+    async fn path_mappings(&self) -> Vec<PathMapping> {
+        self.config_provider
+            .read()
+            .await
+            .config()
+            .path_mappings
+            .clone()
+    }
+
+    /// Forget a closed or deleted document and clear its diagnostics.
+    async fn forget_document(&self, uri: Url) {
+        self.document_map.write().await.remove(&uri);
+        self.diagnostics_manager.clear_diagnostics(&uri).await;
+        self.test_runner.forget(&uri).await;
+        self.client.publish_diagnostics(uri, Vec::new(), None).await;
+        self.save_session().await;
+    }
+
+    /// Build the pulled diagnostic report of a document.
+    ///
+    /// Reports the diagnostics as unchanged when the client already holds the
+    /// result of the current document revision.
+    async fn document_diagnostic_report(
+        &self,
+        uri: &Url,
+        previous_result_id: Option<String>,
+    ) -> Result<DocumentDiagnosticReport> {
+        let document = {
+            let documents = self.document_map.read().await;
+            documents.get(uri).zip(documents.revision(uri))
+        };
+        let Some((content, revision)) =
+            document.filter(|_| self.document_kinds.is_package_definition(uri))
+        else {
+            return Ok(DocumentDiagnosticReport::Full(
+                RelatedFullDocumentDiagnosticReport::default(),
+            ));
+        };
+
+        let result_id = self.diagnostics_manager.result_id(revision);
+        if previous_result_id.as_ref() == Some(&result_id) {
+            return Ok(DocumentDiagnosticReport::Unchanged(
+                RelatedUnchangedDocumentDiagnosticReport {
+                    related_documents: None,
+                    unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                        result_id,
+                    },
+                },
+            ));
+        }
+
+        let (result_id, items) = match self
+            .diagnostics_manager
+            .validate_revision(uri, &content, revision)
+            .await
+        {
+            Ok(validated) => validated,
+            Err(e) => {
+                let report = self.report_error("Validation", &e, false).await;
+                let mut error = tower_lsp::jsonrpc::Error::internal_error();
+                error.message = report.user_message().into();
+                return Err(error);
+            }
+        };
+        self.report_resolution_health(uri, &content).await;
+
+        Ok(DocumentDiagnosticReport::Full(
+            RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items,
+                },
+            },
+        ))
+    }
+
+    /// Apply the requested automated setup fixes and run the setup checks.
+    async fn setup_wizard(&self, fixes: Vec<String>) -> SetupReport {
+        let config = self.config_provider.read().await.config().clone();
+        let families = match self.package_discovery.read().await.as_ref() {
+            Some(discovery) => discovery
+                .get_stats()
+                .await
+                .ok()
+                .map(|(families, _)| families),
+            None => None,
+        };
+        let environment = SetupEnvironment::detect(config, families);
+
+        let applied = fixes
+            .iter()
+            .map(|id| setup_wizard::apply_fix(id, &environment))
+            .collect();
+        SetupReport {
+            checks: setup_wizard::run_checks(&environment),
+            applied,
+        }
+    }
+
+    /// Run the pre-release checks on a package definition, open or not.
+    async fn pre_release_check(&self, uri: Url) -> Result<ReleaseReport> {
+        let path = uri.to_file_path().ok();
+        let content = self.definition_content(&uri).await?;
+        let package = test_runner::package_name(&content);
+        let version = test_runner::string_attribute(&content, "version");
+
+        let diagnostics = match self.diagnostics_manager.check_file(&uri, &content).await {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => {
+                let report = self.report_error("Validation", &e, false).await;
+                let mut error = tower_lsp::jsonrpc::Error::internal_error();
+                error.message = report.user_message().into();
+                return Err(error);
+            }
+        };
+
+        let config = self
+            .workspace_trust
+            .restrict(self.config_provider.read().await.config());
+        let resolver = self
+            .package_discovery
+            .read()
+            .await
+            .as_ref()
+            .map(|discovery| create_resolver(&config, discovery.packages().clone()));
+        let contexts = release_check::release_contexts(&content);
+
+        let checks = vec![
+            release_check::check_validation(&diagnostics),
+            release_check::check_resolution(&contexts, resolver.as_deref()).await,
+            release_check::check_released(
+                package.as_deref(),
+                version.as_deref(),
+                config.release_packages_path.as_deref(),
+            ),
+            release_check::check_changelog(
+                path.as_deref().and_then(|path| path.parent()),
+                version.as_deref(),
+            ),
+        ];
+        Ok(ReleaseReport::new(package, version, checks))
+    }
+
+    /// Get the content of a package definition, open or not.
+    async fn definition_content(&self, uri: &Url) -> Result<String> {
+        if let Some(content) = self.document_map.read().await.get(uri) {
+            return Ok(content.to_string());
+        }
+        let Ok(path) = uri.to_file_path() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Not a local file: {}",
+                uri
+            )));
+        };
+        read_source(&path).map_err(|e| {
+            tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Resolve the requirements of a package definition and describe the
+    /// resolved closure in `format`.
+    async fn dependency_manifest(
+        &self,
+        uri: Url,
+        format: ManifestFormat,
+    ) -> Result<serde_json::Value> {
+        let content = self.definition_content(&uri).await?;
+        let package = test_runner::package_name(&content).unwrap_or_default();
+        let version = test_runner::string_attribute(&content, "version").unwrap_or_default();
+
+        let (requirements, resolver) = {
+            let discovery = self.package_discovery.read().await;
+            let Some(discovery) = discovery.as_ref() else {
+                return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                    "Package index is not available yet",
+                ));
+            };
+            (
+                discovery.parse_requires(&content),
+                create_resolver(discovery.config(), discovery.packages().clone()),
+            )
+        };
+
+        let context = self.timed_resolve(resolver.as_ref(), &requirements).await;
+        let context = context.map_err(|e| {
+            let mut error = tower_lsp::jsonrpc::Error::internal_error();
+            error.message = format!("Failed to resolve requirements: {}", e).into();
+            error
+        })?;
+        Ok(DependencyManifest::new(&package, &version, &requirements, &context).render(format))
+    }
+
+    /// Bump the version of a package definition under the version scheme of
+    /// its repository, returning the new version.
+    async fn bump_version(&self, uri: Url, part: BumpPart) -> Result<String> {
+        let Some(content) = self.document_map.read().await.get(&uri) else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Document is not open: {}",
+                uri
+            )));
+        };
+        let scheme = match uri.to_file_path() {
+            Ok(path) => self
+                .config_provider
+                .read()
+                .await
+                .config()
+                .version_policy
+                .scheme_for(&path),
+            Err(()) => Default::default(),
+        };
+
+        let today = chrono::Local::now().date_naive();
+        let Some((version, edit)) =
+            bump_version_edit(&content, scheme, part, today, self.position_encoding())
+        else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package does not declare a version",
+            ));
+        };
+
+        let edit = WorkspaceEdit {
+            changes: Some(std::collections::HashMap::from([(uri, vec![edit])])),
+            ..Default::default()
+        };
+        match self.client.apply_edit(edit).await {
+            Ok(response) if response.applied => Ok(version),
+            Ok(response) => Err(tower_lsp::jsonrpc::Error::invalid_params(
+                response
+                    .failure_reason
+                    .unwrap_or_else(|| "The client rejected the version edit".to_string()),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Copy a package version into the local package repository, add the
+    /// copy to the index and pin the requirements of `uri` to it.
+    async fn localize_package(
+        &self,
+        name: String,
+        version: Version,
+        uri: Option<Url>,
+    ) -> Result<LocalizedPackage> {
+        let guard = self.package_discovery.read().await;
+        let Some(discovery) = guard.as_ref() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package index is not available yet",
+            ));
+        };
+        let Some(local_path) = discovery.config().local_packages_path.clone() else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "REZ_LOCAL_PACKAGES_PATH is not set",
+            ));
+        };
+        let package = match discovery.get_package(&name, &version).await {
+            Ok(Some(package)) if !package.version.is_unversioned() => package,
+            _ => {
+                return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                    "Package {}-{} is not indexed",
+                    name, version
+                )))
+            }
+        };
+        // The payload can be large, so the index is not locked while copying
+        drop(guard);
+
+        let source = package.path.clone();
+        let destination = local_path.join(&*package.name).join(&package.version.value);
+        let copy = {
+            let (source, destination) = (source.clone(), destination.clone());
+            tokio::task::spawn_blocking(move || localize::copy_package(&source, &destination))
+        };
+        let files = match copy.await {
+            Ok(Ok(files)) => files,
+            Ok(Err(e)) => {
+                return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                    "Failed to copy {} to {}: {}",
+                    source.display(),
+                    destination.display(),
+                    e
+                )))
+            }
+            Err(_) => return Err(tower_lsp::jsonrpc::Error::internal_error()),
+        };
+        info!(
+            "Localized {}-{} to {} ({} files)",
+            package.name,
+            package.version,
+            destination.display(),
+            files
+        );
+
+        let mut local = (*package).clone();
+        local.path = destination.clone();
+        if let Some(discovery) = self.package_discovery.write().await.as_mut() {
+            discovery.insert_package(local);
+        }
+
+        let mut pinned = 0;
+        if let Some(uri) = uri {
+            if let Some(content) = self.document_map.read().await.get(&uri) {
+                let edits = localize::pin_requirement_edits(
+                    &content,
+                    &name,
+                    &package.version.value,
+                    self.position_encoding(),
+                );
+                pinned = edits.len();
+                if !edits.is_empty() {
+                    let edit = WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri, edits)])),
+                        ..Default::default()
+                    };
+                    if !self.client.apply_edit(edit).await.is_ok_and(|r| r.applied) {
+                        pinned = 0;
+                    }
+                }
+            }
+        }
+
+        Ok(LocalizedPackage {
+            package: package.name.to_string(),
+            version: package.version.value.clone(),
+            source,
+            destination,
+            files,
+            pinned,
+        })
+    }
+
+    /// Leave restricted mode, returning whether the workspace was restricted.
+    ///
+    /// Packages are rescanned in the background when their evaluation was
+    /// held back, dropping the statically parsed families cached by earlier
+    /// scans; otherwise the package index switches to the configured resolver
+    /// and the restricted mode diagnostics and test code lenses are refreshed.
+    async fn trust_workspace(&self) -> bool {
+        if !self.workspace_trust.trust() {
+            return false;
+        }
+        self.client
+            .log_message(
+                MessageType::INFO,
+                "Workspace trusted: package evaluation and tests are enabled",
+            )
+            .await;
+
+        self.scan_caches.families.clear_all().await;
+        let config = self.config_provider.read().await.config().clone();
+        if config.package_evaluation == PackageEvaluation::Sandbox {
+            self.spawn_trusted_scan(config);
+        } else {
+            if let Some(discovery) = self.package_discovery.write().await.as_mut() {
+                discovery.set_config(config);
+            }
+            self.diagnostics_manager.package_index_changed().await;
+            if self.pull_diagnostics.load(Ordering::Relaxed) {
+                let _ = self.client.workspace_diagnostic_refresh().await;
+            } else {
+                self.republish_open_documents().await;
+            }
+        }
+        let _ = self.client.code_lens_refresh().await;
+        true
+    }
+
+    /// Rescan the package repositories of `config` in the background and
+    /// install the evaluated package index, refreshing the diagnostics of
+    /// the open package definitions.
+    fn spawn_trusted_scan(&self, config: Config) {
+        let pending = watchdog::spawn_scan(config, &self.tasks, &self.scan_caches);
+        let caches = self.scan_caches.clone();
+        let client = self.client.clone();
+        let diagnostics_manager = self.diagnostics_manager.clone();
+        let documents = self.document_map.clone();
+        let document_kinds = self.document_kinds.clone();
+        let errors = self.errors.clone();
+        let pull_diagnostics = self.pull_diagnostics.load(Ordering::Relaxed);
+        self.tasks.spawn("trusted-scan", async move {
+            match pending.await {
+                Ok((discovery, Ok(()))) => {
+                    send_index_stats(&client, &discovery).await;
+                    caches.install(discovery).await;
+                }
+                Ok((_, Err(e))) => {
+                    report_error(&client, &errors, "Package scan", &e, true).await;
+                    return;
+                }
+                Err(join_error) => {
+                    warn!("Package scan task failed: {}", join_error);
+                    return;
+                }
+            }
+            diagnostics_manager.package_index_changed().await;
+            if pull_diagnostics {
+                let _ = client.workspace_diagnostic_refresh().await;
+            } else {
+                publish_package_diagnostics(
+                    &client,
+                    &documents,
+                    &document_kinds,
+                    &diagnostics_manager,
                 )
                 .await;
+            }
+        });
+    }
+
+    /// Start a test of a package in the background, logging its output to
+    /// the client and refreshing code lenses when its status changes.
+    async fn run_test(&self, uri: Url, test: String) -> Result<()> {
+        if !self.workspace_trust.is_trusted() {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Tests are disabled until the workspace is trusted; run {}",
+                TRUST_WORKSPACE_COMMAND
+            )));
         }
+        let Some(content) = self.document_map.read().await.get(&uri) else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Document is not open: {}",
+                uri
+            )));
+        };
+        let Some(package) = test_runner::package_name(&content) else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "Package does not declare a name",
+            ));
+        };
+        let Some(dir) = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| path.parent().map(|dir| dir.to_path_buf()))
+        else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Not a local file: {}",
+                uri
+            )));
+        };
+        let runner = self
+            .config_provider
+            .read()
+            .await
+            .config()
+            .test_runner
+            .clone();
+
+        self.test_runner.start(&uri, &test).await;
+        let _ = self.client.code_lens_refresh().await;
+
+        let client = self.client.clone();
+        let test_runner = self.test_runner.clone();
+        self.tasks.spawn("test-run", async move {
+            let (output, mut lines) = tokio::sync::mpsc::unbounded_channel();
+            let log_client = client.clone();
+            let prefix = format!("[{}:{}]", package, test);
+            let forward = tokio::spawn(async move {
+                while let Some(line) = lines.recv().await {
+                    log_client
+                        .log_message(MessageType::LOG, format!("{} {}", prefix, line))
+                        .await;
+                }
+            });
+
+            let status = test_runner
+                .run(&runner, &dir, &uri, &package, &test, output)
+                .await;
+            let _ = forward.await;
 
-        let mut package_discovery = self.package_discovery.write().await;
-        *package_discovery = Some(discovery);
+            let (level, outcome) = match status {
+                TestStatus::Passed => (MessageType::INFO, "passed"),
+                _ => (MessageType::ERROR, "failed"),
+            };
+            client
+                .log_message(level, format!("Test '{}' of {} {}", test, package, outcome))
+                .await;
+            let _ = client.code_lens_refresh().await;
+        });
 
         Ok(())
     }
 
-    /// Handle document changes.
-    async fn on_change(&self, params: TextDocumentItem) {
-        let mut document_map = self.document_map.write().await;
-        document_map.insert(params.uri.clone(), params.text.clone());
-        drop(document_map); // Release the lock early
-
-        // Run diagnostics for package.py files
-        if params.uri.path().ends_with("package.py") {
-            if let Ok(diagnostics) = self
-                .diagnostics_manager
-                .validate_file(&params.uri, &params.text)
-                .await
-            {
-                // Publish diagnostics to the client
-                self.client
-                    .publish_diagnostics(params.uri, diagnostics, None)
-                    .await;
+    /// Handle document changes.
+    ///
+    /// A leading byte order mark is removed, as editors do not count it in
+    /// positions.
+    async fn on_change(&self, mut params: TextDocumentItem) {
+        strip_bom_in_place(&mut params.text);
+        self.store_document(params.uri.clone(), params.text.clone())
+            .await;
+
+        // Push diagnostics for package.py files unless the client pulls them
+        if self.document_kinds.is_package_definition(&params.uri)
+            && !self.pull_diagnostics.load(Ordering::Relaxed)
+        {
+            match self.push_validation(&params.uri, &params.text).await {
+                Ok(mut diagnostics) => {
+                    // Until the index is ready, unchanged documents get back
+                    // the diagnostics they had before the restart
+                    if self.package_discovery.read().await.is_none() {
+                        if let Some(restored) = self
+                            .restored_session
+                            .read()
+                            .await
+                            .as_ref()
+                            .and_then(|session| session.diagnostics(&params.uri, &params.text))
+                        {
+                            diagnostics = restored.to_vec();
+                        }
+                    }
+                    self.report_resolution_health(&params.uri, &params.text)
+                        .await;
+                    // Publish diagnostics to the client
+                    self.client
+                        .publish_diagnostics(params.uri, diagnostics, None)
+                        .await;
+                    self.save_session().await;
+                }
+                Err(e) => {
+                    let report = self.report_error("Validation", &e, false).await;
+                    self.client
+                        .publish_diagnostics(params.uri, vec![report.to_diagnostic()], None)
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Build the actions pinning the unpinned requirements on the lines of
+    /// `range` to the versions they resolve to.
+    ///
+    /// The `requires` of the document are resolved together; families the
+    /// resolve leaves out, such as build requirements, or all of them when it
+    /// fails, get their latest indexed version. The resolve is reused until
+    /// the `requires` or the index change.
+    async fn pin_requirement_actions(
+        &self,
+        uri: &Url,
+        content: &str,
+        range: Range,
+    ) -> Vec<CodeActionOrCommand> {
+        if code_actions::unpinned_requirements(content, range).is_empty() {
+            return Vec::new();
+        }
+        let Some(requirements) = self
+            .package_discovery
+            .read()
+            .await
+            .as_ref()
+            .map(|discovery| discovery.parse_requires(content))
+        else {
+            return Vec::new();
+        };
+
+        let mut resolved: HashMap<String, Version> = self
+            .cached_resolve(&requirements)
+            .await
+            .map(|context| {
+                context
+                    .packages
+                    .into_iter()
+                    .map(|package| (package.name.to_string(), package.version))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let guard = self.package_discovery.read().await;
+        let Some(discovery) = guard.as_ref() else {
+            return Vec::new();
+        };
+        for family in discovery.packages().keys() {
+            if !resolved.contains_key(family) {
+                if let Some(latest) = discovery.pick_version(family, &VersionConstraint::Any) {
+                    resolved.insert(family.clone(), latest.version.clone());
+                }
+            }
+        }
+
+        code_actions::pin_requirement_actions(
+            uri,
+            content,
+            range,
+            &resolved,
+            &discovery.config().package_aliases,
+            self.position_encoding(),
+        )
+    }
+
+    /// Validate a document whose diagnostics are pushed to the client.
+    ///
+    /// Files above [`LARGE_FILE_THRESHOLD`] take long enough to validate that
+    /// the diagnostics of each finished validator are published right away.
+    async fn push_validation(
+        &self,
+        uri: &Url,
+        content: &str,
+    ) -> crate::core::Result<Vec<Diagnostic>> {
+        if content.len() <= LARGE_FILE_THRESHOLD {
+            return self.diagnostics_manager.validate_file(uri, content).await;
+        }
+
+        let (partial, mut batches) = tokio::sync::mpsc::unbounded_channel();
+        let client = self.client.clone();
+        let batch_uri = uri.clone();
+        let forward = tokio::spawn(async move {
+            while let Some(diagnostics) = batches.recv().await {
+                client
+                    .publish_diagnostics(batch_uri.clone(), diagnostics, None)
+                    .await;
+            }
+        });
+        let diagnostics = self
+            .diagnostics_manager
+            .validate_file_progressively(uri, content, partial)
+            .await;
+        let _ = forward.await;
+        diagnostics
+    }
+
+    /// Record an error, log it to the client and optionally show it to the
+    /// user.
+    async fn report_error(&self, operation: &str, error: &Error, show: bool) -> ErrorReport {
+        report_error(&self.client, &self.errors, operation, error, show).await
+    }
+
+    /// Send the `rez/resolutionHealth` notification for a validated package
+    /// definition, once the package index is available.
+    async fn report_resolution_health(&self, uri: &Url, content: &str) {
+        let discovery = self.package_discovery.read().await;
+        if let Some(discovery) = discovery.as_ref() {
+            send_resolution_health(&self.client, uri.clone(), content, discovery).await;
+        }
+    }
+
+    /// Stop background tasks and exit once the editor process `pid` is gone.
+    fn monitor_parent_process(&self, pid: u32) {
+        if !is_process_alive(pid) {
+            // Likely in another PID namespace, e.g. with the server in docker
+            warn!("Parent process {} is not visible; not monitoring it", pid);
+            return;
+        }
+        info!("Monitoring parent process {}", pid);
+        let tasks = self.tasks.clone();
+        self.tasks.spawn("parent-monitor", async move {
+            wait_for_exit(pid, PARENT_POLL_INTERVAL).await;
+            warn!("Parent process {} exited; shutting down", pid);
+            // Run outside the registry, whose shutdown cancels this task
+            tokio::spawn(async move {
+                tasks.shutdown(SHUTDOWN_GRACE_PERIOD).await;
+                std::process::exit(0);
+            });
+        });
+    }
+}
+
+/// Record an error in `errors`, log it to the client and optionally show it
+/// to the user.
+async fn report_error(
+    client: &Client,
+    errors: &ErrorLog,
+    operation: &str,
+    error: &Error,
+    show: bool,
+) -> ErrorReport {
+    let report = ErrorReport::new(operation, error);
+    client
+        .log_message(report.message_type(), report.log_message())
+        .await;
+    if show {
+        client
+            .show_message(report.message_type(), report.user_message())
+            .await;
+    }
+    errors.record(report.clone()).await;
+    report
+}
+
+/// Scan every version of `families` without adding them to the index.
+///
+/// Discovery reads the file system synchronously, so the families are read
+/// on the blocking thread pool, under a read lock leaving the index
+/// available to other requests.
+async fn read_families(
+    package_discovery: &Arc<tokio::sync::RwLock<Option<PackageDiscoveryImpl>>>,
+    families: Vec<String>,
+) -> Result<Vec<(String, crate::core::Result<Vec<Package>>)>> {
+    let guard = Arc::clone(package_discovery).read_owned().await;
+    let handle = tokio::runtime::Handle::current();
+    let read = tokio::task::spawn_blocking(move || {
+        let discovery = guard.as_ref()?;
+        Some(
+            families
+                .into_iter()
+                .map(|family| {
+                    let versions = handle.block_on(discovery.read_all_versions(&family));
+                    (family, versions)
+                })
+                .collect::<Vec<_>>(),
+        )
+    })
+    .await
+    .map_err(|e| {
+        let mut error = tower_lsp::jsonrpc::Error::internal_error();
+        error.message = format!("Failed to read package versions: {}", e).into();
+        error
+    })?;
+
+    read.ok_or_else(|| {
+        tower_lsp::jsonrpc::Error::invalid_params("Package index is not available yet")
+    })
+}
+
+/// Validate the open package definitions again and push their diagnostics.
+async fn publish_package_diagnostics(
+    client: &Client,
+    documents: &tokio::sync::RwLock<DocumentStore>,
+    document_kinds: &DocumentKinds,
+    diagnostics_manager: &DiagnosticsManager,
+) {
+    let documents: Vec<(Url, String)> = documents
+        .read()
+        .await
+        .iter()
+        .filter(|(uri, _)| document_kinds.is_package_definition(uri))
+        .map(|(uri, content)| (uri.clone(), content.to_string()))
+        .collect();
+
+    for (uri, content) in documents {
+        if let Ok(diagnostics) = diagnostics_manager.validate_file(&uri, &content).await {
+            client.publish_diagnostics(uri, diagnostics, None).await;
+        }
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for RezLanguageServer {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        info!("Rez LSP Server initializing...");
+
+        let pull_diagnostics = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .is_some_and(|text_document| text_document.diagnostic.is_some());
+        self.pull_diagnostics
+            .store(pull_diagnostics, Ordering::Relaxed);
+
+        let rename_resources = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.workspace_edit.as_ref())
+            .is_some_and(|edit| {
+                edit.document_changes == Some(true)
+                    && edit
+                        .resource_operations
+                        .as_ref()
+                        .is_some_and(|ops| ops.contains(&ResourceOperationKind::Rename))
+            });
+        self.rename_resources
+            .store(rename_resources, Ordering::Relaxed);
+
+        let position_encoding = PositionEncoding::negotiate(&params.capabilities);
+        self.utf8_positions.store(
+            position_encoding == PositionEncoding::Utf8,
+            Ordering::Relaxed,
+        );
+        self.diagnostics_manager
+            .set_position_encoding(position_encoding)
+            .await;
+        info!("Using {} positions", position_encoding.kind().as_str());
+
+        let client_features = ClientFeatures::from_capabilities(&params.capabilities);
+        info!("Client features: {:?}", client_features);
+        *self.client_features.write().await = client_features;
+
+        let folders: Vec<Url> = match &params.workspace_folders {
+            Some(folders) => folders.iter().map(|folder| folder.uri.clone()).collect(),
+            None => params.root_uri.iter().cloned().collect(),
+        };
+        *self.workspace_folders.write().await = folders;
+
+        if let Some(pid) = params.process_id.or(self.parent_pid) {
+            self.monitor_parent_process(pid);
+        }
+
+        // Pulled diagnostics are computed on request, so only pushed ones are
+        // restored
+        let root = params.root_uri.clone().or_else(|| {
+            params
+                .workspace_folders
+                .as_ref()
+                .and_then(|folders| folders.first())
+                .map(|folder| folder.uri.clone())
+        });
+        let restore_session = self
+            .config_provider
+            .read()
+            .await
+            .get_restore_session_from_env()
+            .await;
+        if let Some(store) = root
+            .filter(|_| restore_session && !pull_diagnostics)
+            .and_then(|root| SessionStore::for_workspace(&root))
+        {
+            let restored = store.load();
+            if let Some(restored) = &restored {
+                info!(
+                    "Restored session with {} documents from {}",
+                    restored.documents.len(),
+                    store.path().display()
+                );
             }
+            *self.restored_session.write().await = restored;
+            let saver = Arc::new(SessionSaver::new(store));
+            self.tasks
+                .spawn("session-saver", saver.clone().run(SESSION_SAVE_INTERVAL));
+            *self.session.write().await = Some(saver);
         }
-    }
-}
 
-#[tower_lsp::async_trait]
-impl LanguageServer for RezLanguageServer {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
-        info!("Rez LSP Server initializing...");
+        let trigger_characters = self
+            .config_provider
+            .read()
+            .await
+            .get_completion_trigger_characters_from_env()
+            .await;
 
         Ok(InitializeResult {
             server_info: Some(ServerInfo {
@@ -138,17 +2060,13 @@ impl LanguageServer for RezLanguageServer {
                 version: Some(env!("CARGO_PKG_VERSION").to_string()),
             }),
             capabilities: ServerCapabilities {
+                position_encoding: Some(position_encoding.kind()),
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::FULL,
                 )),
                 completion_provider: Some(CompletionOptions {
                     resolve_provider: Some(false),
-                    trigger_characters: Some(vec![
-                        "\"".to_string(),
-                        "'".to_string(),
-                        "-".to_string(),
-                        ".".to_string(),
-                    ]),
+                    trigger_characters: Some(trigger_characters),
                     work_done_progress_options: Default::default(),
                     all_commit_characters: None,
                     completion_item: None,
@@ -163,9 +2081,62 @@ impl LanguageServer for RezLanguageServer {
                     },
                 )),
                 definition_provider: Some(OneOf::Left(true)),
+                type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
                 references_provider: Some(OneOf::Left(true)),
                 document_symbol_provider: Some(OneOf::Left(true)),
-                workspace_symbol_provider: Some(OneOf::Left(true)),
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: formatting::TRIGGER_CHARACTERS[0].to_string(),
+                    more_trigger_character: Some(
+                        formatting::TRIGGER_CHARACTERS[1..]
+                            .iter()
+                            .map(|c| c.to_string())
+                            .collect(),
+                    ),
+                }),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                }),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![
+                        RUN_TEST_COMMAND.to_string(),
+                        BUMP_VERSION_COMMAND.to_string(),
+                        SETUP_WIZARD_COMMAND.to_string(),
+                        PRE_RELEASE_CHECK_COMMAND.to_string(),
+                        LOCALIZE_PACKAGE_COMMAND.to_string(),
+                        DEPENDENCY_MANIFEST_COMMAND.to_string(),
+                        TRUST_WORKSPACE_COMMAND.to_string(),
+                    ],
+                    ..Default::default()
+                }),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![
+                            CodeActionKind::QUICKFIX,
+                            CodeActionKind::REFACTOR,
+                            CodeActionKind::REFACTOR_REWRITE,
+                            CodeActionKind::SOURCE_FIX_ALL,
+                            CodeActionKind::new(code_actions::FIX_ALL_KIND),
+                        ]),
+                        ..Default::default()
+                    },
+                )),
+                workspace_symbol_provider: Some(OneOf::Right(WorkspaceSymbolOptions {
+                    resolve_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: None,
+                    file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                        will_rename: Some(directory_rename_filter()),
+                        did_rename: Some(directory_rename_filter()),
+                        ..Default::default()
+                    }),
+                }),
                 ..ServerCapabilities::default()
             },
         })
@@ -178,6 +2149,30 @@ impl LanguageServer for RezLanguageServer {
             .log_message(MessageType::INFO, "Rez LSP Server initialized")
             .await;
 
+        // Watch package definitions so deleted files drop their diagnostics,
+        // and reference files to keep the reference index up to date
+        let client = self.client.clone();
+        self.tasks.spawn("watcher-registration", async move {
+            let all = WatchKind::Create | WatchKind::Change | WatchKind::Delete;
+            let options = DidChangeWatchedFilesRegistrationOptions {
+                watchers: ["**/package.py", "**/*.lock"]
+                    .into_iter()
+                    .map(|glob| FileSystemWatcher {
+                        glob_pattern: GlobPattern::String(glob.to_string()),
+                        kind: Some(all),
+                    })
+                    .collect(),
+            };
+            let registration = Registration {
+                id: "rez-package-watcher".to_string(),
+                method: "workspace/didChangeWatchedFiles".to_string(),
+                register_options: serde_json::to_value(options).ok(),
+            };
+            if let Err(e) = client.register_capability(vec![registration]).await {
+                tracing::debug!("Client does not support watching files: {}", e);
+            }
+        });
+
         // Initialize components in the background
         if let Err(e) = self.initialize_components().await {
             self.client
@@ -191,10 +2186,37 @@ impl LanguageServer for RezLanguageServer {
 
     async fn shutdown(&self) -> Result<()> {
         info!("Rez LSP Server shutting down...");
+        self.save_session().await;
+        if let Some(saver) = self.session.read().await.as_ref() {
+            saver.flush().await;
+        }
+
+        let report = self.tasks.shutdown(SHUTDOWN_GRACE_PERIOD).await;
+        if report.is_clean() {
+            info!("Stopped {} background tasks", report.stopped);
+        } else {
+            warn!(
+                "Aborted background tasks that did not stop in time: {}",
+                report.aborted.join(", ")
+            );
+        }
+
+        if let Some(path) = &self.trace_output {
+            let sessions = self.profiler.read().await.get_completed_sessions().await;
+            match ChromeTrace::from_sessions(&sessions).write_to_file(path) {
+                Ok(()) => info!("Wrote profiling trace to {}", path.display()),
+                Err(e) => warn!("Failed to write profiling trace: {}", e),
+            }
+        }
+
         Ok(())
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        if !is_supported_document(&params.text_document.uri) {
+            tracing::trace!("Ignoring document: {}", params.text_document.uri);
+            return;
+        }
         let filename = params
             .text_document
             .uri
@@ -203,10 +2225,24 @@ impl LanguageServer for RezLanguageServer {
             .next_back()
             .unwrap_or("unknown");
         info!("Opened: {}", filename);
+        self.document_kinds.open(
+            params.text_document.uri.clone(),
+            &params.text_document.language_id,
+        );
+        if self
+            .diagnostics_manager
+            .is_released_definition(&params.text_document.uri)
+            .await
+        {
+            send_read_only_hint(&self.client, params.text_document.uri.clone()).await;
+        }
         self.on_change(params.text_document).await;
     }
 
     async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
+        if !is_supported_document(&params.text_document.uri) {
+            return;
+        }
         // Use debug level for frequent document changes
         let filename = params
             .text_document
@@ -217,18 +2253,23 @@ impl LanguageServer for RezLanguageServer {
             .unwrap_or("unknown");
         tracing::debug!("Document changed: {}", filename);
 
-        if let Some(change) = params.content_changes.pop() {
-            let mut document_map = self.document_map.write().await;
-            document_map.insert(params.text_document.uri.clone(), change.text.clone());
-            drop(document_map); // Release the lock early
+        if let Some(mut change) = params.content_changes.pop() {
+            strip_bom_in_place(&mut change.text);
+            self.store_document(params.text_document.uri.clone(), change.text.clone())
+                .await;
 
-            // Run diagnostics for package.py files
-            if params.text_document.uri.path().ends_with("package.py") {
+            // Push diagnostics for package.py files unless the client pulls them
+            if self
+                .document_kinds
+                .is_package_definition(&params.text_document.uri)
+                && !self.pull_diagnostics.load(Ordering::Relaxed)
+            {
                 if let Ok(diagnostics) = self
-                    .diagnostics_manager
-                    .validate_file(&params.text_document.uri, &change.text)
+                    .push_validation(&params.text_document.uri, &change.text)
                     .await
                 {
+                    self.report_resolution_health(&params.text_document.uri, &change.text)
+                        .await;
                     // Publish diagnostics to the client
                     self.client
                         .publish_diagnostics(params.text_document.uri, diagnostics, None)
@@ -239,6 +2280,9 @@ impl LanguageServer for RezLanguageServer {
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        if !is_supported_document(&params.text_document.uri) {
+            return;
+        }
         let filename = params
             .text_document
             .uri
@@ -247,9 +2291,13 @@ impl LanguageServer for RezLanguageServer {
             .next_back()
             .unwrap_or("unknown");
         info!("Saved: {}", filename);
+        self.save_session().await;
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        if !is_supported_document(&params.text_document.uri) {
+            return;
+        }
         let filename = params
             .text_document
             .uri
@@ -259,51 +2307,294 @@ impl LanguageServer for RezLanguageServer {
             .unwrap_or("unknown");
         tracing::debug!("Closed: {}", filename);
 
-        let mut document_map = self.document_map.write().await;
-        document_map.remove(&params.text_document.uri);
+        let uri = params.text_document.uri;
+        self.forget_document(uri.clone()).await;
+        self.document_kinds.close(&uri);
+        self.reindex_from_disk(&uri).await;
+    }
+
+    async fn will_rename_files(&self, params: RenameFilesParams) -> Result<Option<WorkspaceEdit>> {
+        let mappings = self.path_mappings().await;
+        let moves = package_moves(&params, &mappings);
+        if moves.is_empty() {
+            return Ok(None);
+        }
+
+        // Open documents take precedence over their files on disk
+        let mut documents: Vec<(PathBuf, Url, String)> = self
+            .document_map
+            .read()
+            .await
+            .iter()
+            .filter_map(|(uri, content)| {
+                let path = server_path(uri, &mappings)?;
+                is_reference_file(&path).then(|| (path, uri.clone(), content.to_string()))
+            })
+            .collect();
+        // Only files mentioning a renamed family can reference it
+        let mut files: Vec<PathBuf> = match self.reference_index().await.as_ref() {
+            Some(index) => moves
+                .iter()
+                .flat_map(|moved| index.files_with_token(moved.family()))
+                .map(std::path::Path::to_path_buf)
+                .collect(),
+            None => Vec::new(),
+        };
+        files.sort();
+        files.dedup();
+        for path in files {
+            if documents.iter().any(|(open, _, _)| *open == path) {
+                continue;
+            }
+            let (Ok(content), Some(uri)) = (read_source(&path), client_uri(&path, &mappings))
+            else {
+                continue;
+            };
+            documents.push((path, uri, content));
+        }
+
+        let encoding = self.position_encoding();
+        let mut changes = HashMap::new();
+        for (path, uri, content) in documents {
+            let edits: Vec<TextEdit> = moves
+                .iter()
+                .flat_map(|moved| moved.edits(&path, &content, encoding))
+                .collect();
+            if !edits.is_empty() {
+                changes.insert(uri, edits);
+            }
+        }
+        info!(
+            "Renaming {} package directories updates {} files",
+            moves.len(),
+            changes.len()
+        );
+        Ok((!changes.is_empty()).then(|| WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
+    async fn did_rename_files(&self, params: RenameFilesParams) {
+        let moves = package_moves(&params, &self.path_mappings().await);
+        if moves.is_empty() {
+            return;
+        }
+
+        // Read the renamed packages with the index still readable by other
+        // requests, and lock it only to update it
+        let mut loaded = Vec::with_capacity(moves.len());
+        {
+            let guard = self.package_discovery.read().await;
+            let Some(discovery) = guard.as_ref() else {
+                return;
+            };
+            for moved in moves {
+                let packages: Vec<Arc<Package>> = match &moved {
+                    PackageMove::Version { family, new, .. } => discovery
+                        .read_version(family, new)
+                        .await
+                        .ok()
+                        .flatten()
+                        .map(Arc::new)
+                        .into_iter()
+                        .collect(),
+                    PackageMove::Family { new, .. } => discovery
+                        .read_all_versions(new)
+                        .await
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(Arc::new)
+                        .collect(),
+                };
+                loaded.push((moved, packages));
+            }
+        }
+
+        let mut guard = self.package_discovery.write().await;
+        let Some(discovery) = guard.as_mut() else {
+            return;
+        };
+        let mut updated = false;
+        for (moved, packages) in loaded {
+            updated |= !packages.is_empty();
+            match moved {
+                PackageMove::Version { family, old, .. } => {
+                    updated |= discovery.remove_package(&family, &Version::new(&old));
+                    packages
+                        .into_iter()
+                        .for_each(|package| discovery.insert_package(package));
+                }
+                PackageMove::Family { old, new, .. } => {
+                    updated |= discovery.remove_family(&old);
+                    discovery.insert_all_versions(&new, packages);
+                }
+            }
+        }
+
+        if updated {
+            send_index_stats(&self.client, discovery).await;
+            drop(guard);
+            self.diagnostics_manager.package_index_changed().await;
+            if self.pull_diagnostics.load(Ordering::Relaxed) {
+                let _ = self.client.workspace_diagnostic_refresh().await;
+            }
+        }
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        self.reload_configuration(&params.settings).await;
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let mappings = self.path_mappings().await;
+        let changed: Vec<PathBuf> = params
+            .changes
+            .iter()
+            .filter_map(|change| server_path(&change.uri, &mappings))
+            .collect();
+        let repositories = self
+            .config_provider
+            .read()
+            .await
+            .config()
+            .get_all_package_paths();
+        self.scan_caches
+            .families
+            .invalidate_changed_paths(&changed, &repositories)
+            .await;
+
+        for change in params.changes {
+            if change.typ == FileChangeType::DELETED && is_supported_document(&change.uri) {
+                tracing::debug!("Deleted: {}", change.uri);
+                self.forget_document(change.uri.clone()).await;
+            }
+            self.reindex_from_disk(&change.uri).await;
+        }
     }
 
     async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
-        super::completion::handle_completion(&params, &self.package_discovery).await
+        let start = std::time::Instant::now();
+        let response = super::completion::handle_completion(
+            &params,
+            &self.document_map,
+            self.completion_provider.as_ref(),
+            self.position_encoding(),
+            &self.path_mappings().await,
+        )
+        .await;
+        self.metrics
+            .record_duration("completion", start.elapsed())
+            .await;
+
+        // Completions from a warming index are partial, so the client asks
+        // again as the user keeps typing
+        let warming = self.package_discovery.read().await.is_none()
+            && self.scan_caches.warming.read().await.is_some();
+        let features = *self.client_features.read().await;
+        let response =
+            response.map(|response| response.map(|response| features.completion(response)));
+        match response {
+            Ok(Some(CompletionResponse::Array(items))) if warming => {
+                Ok(Some(CompletionResponse::List(CompletionList {
+                    is_incomplete: true,
+                    items,
+                })))
+            }
+            Ok(Some(CompletionResponse::List(list))) if warming => {
+                Ok(Some(CompletionResponse::List(CompletionList {
+                    is_incomplete: true,
+                    ..list
+                })))
+            }
+            response => response,
+        }
     }
 
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
-        super::hover::handle_hover(&params).await
+        let start = std::time::Instant::now();
+        let response = super::hover::handle_hover(
+            &params,
+            &self.document_map,
+            self.hover_provider.as_ref(),
+            &self.resolved_environment,
+            &self.document_kinds,
+            self.position_encoding(),
+        )
+        .await;
+        self.metrics.record_duration("hover", start.elapsed()).await;
+        let features = *self.client_features.read().await;
+        response.map(|hover| hover.map(|hover| features.hover(hover)))
     }
 
     async fn goto_definition(
         &self,
         params: GotoDefinitionParams,
     ) -> Result<Option<GotoDefinitionResponse>> {
+        let content = self
+            .document_map
+            .read()
+            .await
+            .get(&params.text_document_position_params.text_document.uri);
         match self
             .navigation_handler
-            .handle_goto_definition(&params)
+            .handle_goto_definition(&params, content.as_deref(), self.position_encoding())
             .await
         {
             Ok(response) => Ok(response),
             Err(e) => {
-                self.client
-                    .log_message(
-                        MessageType::ERROR,
-                        format!("Go to definition failed: {}", e),
-                    )
-                    .await;
+                self.report_error("Go to definition", &e, false).await;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn goto_type_definition(
+        &self,
+        params: request::GotoTypeDefinitionParams,
+    ) -> Result<Option<request::GotoTypeDefinitionResponse>> {
+        let content = self
+            .document_map
+            .read()
+            .await
+            .get(&params.text_document_position_params.text_document.uri);
+        match self
+            .navigation_handler
+            .handle_goto_type_definition(&params, content.as_deref(), self.position_encoding())
+            .await
+        {
+            Ok(response) => Ok(response),
+            Err(e) => {
+                self.report_error("Go to type definition", &e, false).await;
                 Ok(None)
             }
         }
     }
 
     async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let content = self
+            .document_map
+            .read()
+            .await
+            .get(&params.text_document_position.text_document.uri);
+        let index = self.reference_index().await;
+        let Some(index) = index.as_ref() else {
+            return Ok(None);
+        };
         match self
             .navigation_handler
-            .handle_find_references(&params)
+            .handle_find_references(
+                &params,
+                content.as_deref(),
+                self.position_encoding(),
+                index,
+                &self.path_mappings().await,
+            )
             .await
         {
             Ok(response) => Ok(response),
             Err(e) => {
-                self.client
-                    .log_message(MessageType::ERROR, format!("Find references failed: {}", e))
-                    .await;
+                self.report_error("Find references", &e, false).await;
                 Ok(None)
             }
         }
@@ -313,19 +2604,23 @@ impl LanguageServer for RezLanguageServer {
         &self,
         params: DocumentSymbolParams,
     ) -> Result<Option<DocumentSymbolResponse>> {
+        let content = self
+            .document_map
+            .read()
+            .await
+            .get(&params.text_document.uri);
         match self
             .navigation_handler
-            .handle_document_symbols(&params)
+            .handle_document_symbols(&params, content.as_deref(), self.position_encoding())
             .await
         {
-            Ok(response) => Ok(response),
+            Ok(response) => {
+                let features = *self.client_features.read().await;
+                let uri = &params.text_document.uri;
+                Ok(response.map(|response| features.document_symbols(uri, response)))
+            }
             Err(e) => {
-                self.client
-                    .log_message(
-                        MessageType::ERROR,
-                        format!("Document symbols failed: {}", e),
-                    )
-                    .await;
+                self.report_error("Document symbols", &e, false).await;
                 Ok(None)
             }
         }
@@ -337,27 +2632,328 @@ impl LanguageServer for RezLanguageServer {
     ) -> Result<Option<Vec<SymbolInformation>>> {
         match self
             .navigation_handler
-            .handle_workspace_symbols(&params)
+            .handle_workspace_symbols(&params, self.position_encoding())
             .await
         {
             Ok(response) => Ok(response),
             Err(e) => {
-                self.client
-                    .log_message(
-                        MessageType::ERROR,
-                        format!("Workspace symbols failed: {}", e),
-                    )
-                    .await;
+                self.report_error("Workspace symbols", &e, false).await;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReportResult> {
+        let start = std::time::Instant::now();
+        let report = self
+            .document_diagnostic_report(&params.text_document.uri, params.previous_result_id)
+            .await;
+        self.metrics
+            .record_duration("diagnostic", start.elapsed())
+            .await;
+        report.map(DocumentDiagnosticReportResult::Report)
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let uri = params.text_document.uri;
+        if !self.document_kinds.is_package_definition(&uri) {
+            return Ok(None);
+        }
+        let Some(content) = self.document_map.read().await.get(&uri) else {
+            return Ok(None);
+        };
+
+        let statuses = self.test_runner.statuses(&uri).await;
+        let mut lenses = test_runner::test_code_lenses(
+            &uri,
+            &content,
+            &statuses,
+            self.workspace_trust.is_trusted(),
+            self.position_encoding(),
+        );
+        if let Some(discovery) = self.package_discovery.read().await.as_ref() {
+            lenses.extend(changelog::changelog_code_lenses(
+                &content,
+                discovery,
+                self.position_encoding(),
+            ));
+        }
+        Ok((!lenses.is_empty()).then_some(lenses))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let uri = params.text_document.uri;
+        if !self.document_kinds.is_package_definition(&uri) {
+            return Ok(None);
+        }
+        let Some(content) = self.document_map.read().await.get(&uri) else {
+            return Ok(None);
+        };
+        let guard = self.package_discovery.read().await;
+        let Some(discovery) = guard.as_ref() else {
+            return Ok(None);
+        };
+
+        let hints = inlay_hints::requirement_inlay_hints(
+            &content,
+            params.range,
+            discovery,
+            self.position_encoding(),
+        );
+        Ok((!hints.is_empty()).then_some(hints))
+    }
+
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document_position.text_document.uri;
+        if !self.document_kinds.is_package_definition(&uri) {
+            return Ok(None);
+        }
+        let Some(content) = self.document_map.read().await.get(&uri) else {
+            return Ok(None);
+        };
+        let position = params.text_document_position.position;
+        let encoding = self.position_encoding();
+        let character = LineIndex::new(&content, encoding).char_column(&position);
+        let style = self.config_provider.read().await.config().trailing_comma;
+
+        let edits = formatting::on_type_edits(
+            &content,
+            position.line,
+            character,
+            &params.ch,
+            style,
+            encoding,
+        );
+        Ok((!edits.is_empty()).then_some(edits))
+    }
+
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        let uri = params.text_document.uri;
+        if !self.document_kinds.is_package_definition(&uri) {
+            return Ok(None);
+        }
+        let Some(content) = self.document_map.read().await.get(&uri) else {
+            return Ok(None);
+        };
+
+        let mappings = self.path_mappings().await;
+        let links = links::document_links(&uri, &content, self.position_encoding(), &mappings);
+        Ok((!links.is_empty()).then_some(links))
+    }
+
+    async fn document_link_resolve(&self, params: DocumentLink) -> Result<DocumentLink> {
+        Ok(links::resolve_link(params, &self.path_mappings().await))
+    }
+
+    async fn execute_command(
+        &self,
+        params: ExecuteCommandParams,
+    ) -> Result<Option<serde_json::Value>> {
+        match params.command.as_str() {
+            RUN_TEST_COMMAND => {
+                let (uri, test) = match params.arguments.as_slice() {
+                    [uri, test] => (
+                        serde_json::from_value::<Url>(uri.clone()),
+                        serde_json::from_value::<String>(test.clone()),
+                    ),
+                    _ => {
+                        return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                            "Expected the document URI and test name",
+                        ))
+                    }
+                };
+                let (Ok(uri), Ok(test)) = (uri, test) else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "Expected the document URI and test name",
+                    ));
+                };
+
+                self.run_test(uri, test).await?;
                 Ok(None)
             }
+            BUMP_VERSION_COMMAND => {
+                let (uri, part) = match params.arguments.as_slice() {
+                    [uri] => (
+                        serde_json::from_value::<Url>(uri.clone()),
+                        Some(BumpPart::Patch),
+                    ),
+                    [uri, part] => (
+                        serde_json::from_value::<Url>(uri.clone()),
+                        part.as_str().and_then(BumpPart::parse),
+                    ),
+                    _ => {
+                        return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                            "Expected the document URI and optionally major, minor or patch",
+                        ))
+                    }
+                };
+                let (Ok(uri), Some(part)) = (uri, part) else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "Expected the document URI and optionally major, minor or patch",
+                    ));
+                };
+
+                let version = self.bump_version(uri, part).await?;
+                Ok(Some(serde_json::json!(version)))
+            }
+            SETUP_WIZARD_COMMAND => {
+                let fixes = params
+                    .arguments
+                    .into_iter()
+                    .flat_map(|argument| match argument {
+                        serde_json::Value::Array(ids) => ids,
+                        id => vec![id],
+                    })
+                    .map(serde_json::from_value::<String>)
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|_| {
+                        tower_lsp::jsonrpc::Error::invalid_params("Expected a list of fix ids")
+                    })?;
+
+                let report = self.setup_wizard(fixes).await;
+                serde_json::to_value(report)
+                    .map(Some)
+                    .map_err(|_| tower_lsp::jsonrpc::Error::internal_error())
+            }
+            PRE_RELEASE_CHECK_COMMAND => {
+                let uri = match params.arguments.as_slice() {
+                    [uri] => serde_json::from_value::<Url>(uri.clone()).ok(),
+                    _ => None,
+                };
+                let Some(uri) = uri else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "Expected the package definition URI",
+                    ));
+                };
+
+                let report = self.pre_release_check(uri).await?;
+                serde_json::to_value(report)
+                    .map(Some)
+                    .map_err(|_| tower_lsp::jsonrpc::Error::internal_error())
+            }
+            LOCALIZE_PACKAGE_COMMAND => {
+                let (name, version, uri) = match params.arguments.as_slice() {
+                    [name, version] => (name.as_str(), version.as_str(), None),
+                    [name, version, uri] => (
+                        name.as_str(),
+                        version.as_str(),
+                        serde_json::from_value::<Url>(uri.clone()).ok(),
+                    ),
+                    _ => (None, None, None),
+                };
+                let (Some(name), Some(version)) = (name, version) else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "Expected the package name, version and optionally a document URI",
+                    ));
+                };
+
+                let localized = self
+                    .localize_package(name.to_string(), Version::new(version), uri)
+                    .await?;
+                serde_json::to_value(localized)
+                    .map(Some)
+                    .map_err(|_| tower_lsp::jsonrpc::Error::internal_error())
+            }
+            DEPENDENCY_MANIFEST_COMMAND => {
+                let (uri, format) = match params.arguments.as_slice() {
+                    [uri] => (
+                        serde_json::from_value::<Url>(uri.clone()).ok(),
+                        Some(ManifestFormat::default()),
+                    ),
+                    [uri, format] => (
+                        serde_json::from_value::<Url>(uri.clone()).ok(),
+                        format.as_str().and_then(ManifestFormat::parse),
+                    ),
+                    _ => (None, None),
+                };
+                let (Some(uri), Some(format)) = (uri, format) else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "Expected the package definition URI and optionally spdx or cyclonedx",
+                    ));
+                };
+
+                self.dependency_manifest(uri, format).await.map(Some)
+            }
+            TRUST_WORKSPACE_COMMAND => Ok(Some(serde_json::json!(self.trust_workspace().await))),
+            _ => Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Unknown command: {}",
+                params.command
+            ))),
+        }
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = &params.text_document.uri;
+        let mut actions = code_actions::quick_fixes(uri, &params.context.diagnostics);
+
+        let content = self.document_map.read().await.get(uri);
+        let discovery = self.package_discovery.read().await;
+        if let Some((content, discovery)) = content.zip(discovery.as_ref()) {
+            actions.extend(code_actions::missing_requirement_actions(
+                uri,
+                &content,
+                params.range,
+                discovery.packages(),
+                &discovery.config().package_aliases,
+                self.position_encoding(),
+            ));
+        }
+
+        if let Some(content) = self.document_map.read().await.get(uri) {
+            actions.extend(
+                self.pin_requirement_actions(uri, &content, params.range)
+                    .await,
+            );
+            if self.document_kinds.is_package_definition(uri) {
+                actions.extend(code_actions::fix_all_action(
+                    uri,
+                    &content,
+                    params.context.only.as_deref(),
+                    self.position_encoding(),
+                ));
+            }
         }
+
+        if self.rename_resources.load(Ordering::Relaxed) && uri.path().ends_with("package.py") {
+            let documents: Vec<(Url, String)> = self
+                .document_map
+                .read()
+                .await
+                .iter()
+                .filter(|(uri, _)| uri.path().ends_with("package.py"))
+                .map(|(uri, content)| (uri.clone(), content.to_string()))
+                .collect();
+            if let Some((_, content)) = documents.iter().find(|(document, _)| document == uri) {
+                actions.extend(code_actions::version_directory_rename(
+                    uri,
+                    content,
+                    &documents,
+                    self.position_encoding(),
+                    &self.path_mappings().await,
+                ));
+            }
+        }
+
+        Ok((!actions.is_empty()).then_some(actions))
+    }
+
+    async fn symbol_resolve(&self, params: WorkspaceSymbol) -> Result<WorkspaceSymbol> {
+        Ok(self
+            .navigation_handler
+            .handle_workspace_symbol_resolve(params, self.position_encoding())
+            .await)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tower_lsp::LspService;
 
     #[tokio::test]
     async fn test_server_creation() {
@@ -365,4 +2961,293 @@ mod tests {
         // Basic smoke test - server should be created without panicking
         drop(client);
     }
+
+    #[tokio::test]
+    async fn test_diagnostic_codes_request() {
+        let (service, _) = RezLanguageServer::build_service();
+        let codes = service.inner().diagnostic_codes().await.unwrap();
+        assert!(codes.iter().any(|c| c.code == "R001"));
+    }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_position_encoding() {
+        let (service, _) = RezLanguageServer::build_service();
+        let server = service.inner();
+        let result = server
+            .initialize(InitializeParams::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            result.capabilities.position_encoding,
+            Some(PositionEncodingKind::UTF16)
+        );
+
+        let params = InitializeParams {
+            capabilities: ClientCapabilities {
+                general: Some(GeneralClientCapabilities {
+                    position_encodings: Some(vec![
+                        PositionEncodingKind::UTF8,
+                        PositionEncodingKind::UTF16,
+                    ]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = server.initialize(params).await.unwrap();
+        assert_eq!(
+            result.capabilities.position_encoding,
+            Some(PositionEncodingKind::UTF8)
+        );
+        assert_eq!(server.position_encoding(), PositionEncoding::Utf8);
+    }
+
+    #[tokio::test]
+    async fn test_preview_environment_requires_open_document() {
+        let (service, _) = RezLanguageServer::build_service();
+        let result = service
+            .inner()
+            .preview_environment(PreviewEnvironmentParams {
+                text_document: TextDocumentIdentifier {
+                    uri: Url::parse("file:///tmp/package.py").unwrap(),
+                },
+            })
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_background_tasks() {
+        let (service, _) = RezLanguageServer::build_service();
+        let server = service.inner();
+        server
+            .initialize(InitializeParams::default())
+            .await
+            .unwrap();
+        server.initialized(InitializedParams {}).await;
+        for _ in 0..50 {
+            server.tasks.spawn("load", async {
+                loop {
+                    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                }
+            });
+        }
+        assert!(server.tasks.active() > 0);
+
+        server.shutdown().await.unwrap();
+        assert_eq!(server.tasks.active(), 0);
+        assert!(server.tasks.is_shutting_down());
+    }
+
+    #[tokio::test]
+    async fn test_did_close_clears_document_state() {
+        let (service, _) = RezLanguageServer::build_service();
+        let server = service.inner();
+        let uri = Url::parse("file:///packages/tools/1.0.0/package.py").unwrap();
+
+        server
+            .did_open(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem {
+                    uri: uri.clone(),
+                    language_id: "python".to_string(),
+                    version: 1,
+                    text: "name = 'tools'\nrequires = ['python-3+<']\n".to_string(),
+                },
+            })
+            .await;
+        assert!(server.document_map.read().await.get(&uri).is_some());
+        assert!(server
+            .diagnostics_manager
+            .get_all_diagnostics()
+            .await
+            .contains_key(&uri));
+
+        server
+            .did_close(DidCloseTextDocumentParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+            })
+            .await;
+        assert!(server.document_map.read().await.get(&uri).is_none());
+        assert!(server
+            .diagnostics_manager
+            .get_diagnostics(&uri)
+            .await
+            .is_empty());
+        assert!(!server
+            .diagnostics_manager
+            .get_all_diagnostics()
+            .await
+            .contains_key(&uri));
+    }
+
+    #[tokio::test]
+    async fn test_pulled_diagnostics_are_unchanged_until_edited() {
+        let (service, _) = RezLanguageServer::build_service();
+        let server = service.inner();
+        server.pull_diagnostics.store(true, Ordering::Relaxed);
+        let uri = Url::parse("file:///packages/tools/1.0.0/package.py").unwrap();
+
+        server
+            .did_open(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem {
+                    uri: uri.clone(),
+                    language_id: "python".to_string(),
+                    version: 1,
+                    text: "name = 'tools'\nrequires = ['python-3+<']\n".to_string(),
+                },
+            })
+            .await;
+        // Pulling clients validate on request only
+        assert!(!server
+            .diagnostics_manager
+            .get_all_diagnostics()
+            .await
+            .contains_key(&uri));
+
+        let DocumentDiagnosticReport::Full(full) =
+            server.document_diagnostic_report(&uri, None).await.unwrap()
+        else {
+            panic!("expected a full report");
+        };
+        let result_id = full.full_document_diagnostic_report.result_id.unwrap();
+
+        let report = server
+            .document_diagnostic_report(&uri, Some(result_id.clone()))
+            .await
+            .unwrap();
+        assert!(matches!(report, DocumentDiagnosticReport::Unchanged(_)));
+
+        server
+            .did_change(DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version: 2,
+                },
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text: "name = 'tools'\n".to_string(),
+                }],
+            })
+            .await;
+        let report = server
+            .document_diagnostic_report(&uri, Some(result_id.clone()))
+            .await
+            .unwrap();
+        let DocumentDiagnosticReport::Full(full) = report else {
+            panic!("expected a full report");
+        };
+        assert_ne!(
+            full.full_document_diagnostic_report.result_id,
+            Some(result_id)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_environments_requires_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (service, _) = RezLanguageServer::build_service();
+        let result = service
+            .inner()
+            .stale_environments(StaleEnvironmentsParams {
+                directory: temp_dir.path().to_path_buf(),
+            })
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stale_environments_loads_capped_families() {
+        use crate::discovery::test_support::{repository_config, write_package};
+
+        let repo = tempfile::tempdir().unwrap();
+        for version in ["1.0.0", "2.0.0"] {
+            write_package(repo.path(), "tools", version, "name = 'tools'\n");
+        }
+        let mut config = repository_config(&[repo.path()]);
+        config.max_versions_per_family = Some(1);
+        let mut discovery = PackageDiscoveryImpl::new(config);
+        discovery.scan_shared().await.unwrap();
+
+        let contexts = tempfile::tempdir().unwrap();
+        std::fs::write(contexts.path().join("shot.lock"), "tools-1.0.0\n").unwrap();
+
+        let (service, _) = RezLanguageServer::build_service();
+        let server = service.inner();
+        *server.package_discovery.write().await = Some(discovery);
+        let report = server
+            .stale_environments(StaleEnvironmentsParams {
+                directory: contexts.path().to_path_buf(),
+            })
+            .await
+            .unwrap();
+
+        assert!(report.errors.is_empty());
+        let discovery = server.package_discovery.read().await;
+        assert_eq!(discovery.as_ref().unwrap().elided_versions("tools"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_load_all_versions_request() {
+        use crate::discovery::test_support::{repository_config, write_package};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        for version in ["1.0.0", "2.0.0", "3.0.0"] {
+            write_package(temp_dir.path(), "tools", version, "name = 'tools'\n");
+        }
+        let mut config = repository_config(&[temp_dir.path()]);
+        config.max_versions_per_family = Some(1);
+        let mut discovery = PackageDiscoveryImpl::new(config);
+        discovery.scan_shared().await.unwrap();
+
+        let (service, _) = RezLanguageServer::build_service();
+        let server = service.inner();
+        *server.package_discovery.write().await = Some(discovery);
+        let versions = server
+            .load_all_versions(LoadAllVersionsParams {
+                family: "tools".to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(versions.len(), 3);
+        let discovery = server.package_discovery.read().await;
+        let discovery = discovery.as_ref().unwrap();
+        assert_eq!(discovery.elided_versions("tools"), 0);
+        assert_eq!(discovery.packages()["tools"].len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_performance_report_request() {
+        let (service, _) = RezLanguageServer::build_service();
+        let report = service.inner().performance_report().await.unwrap();
+
+        assert_eq!(report.health, ServerHealth::Initializing);
+        assert!(report.discovery.is_none());
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["health"]["state"], "initializing");
+    }
+
+    #[tokio::test]
+    async fn test_export_trace_request() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("trace.json");
+
+        let (service, _) = RezLanguageServer::build_service();
+        let response = service
+            .inner()
+            .export_trace(ExportTraceParams {
+                path: Some(path.clone()),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.session_count, 0);
+        assert!(response.trace["traceEvents"].is_array());
+        assert!(path.is_file());
+    }
 }
@@ -1,10 +1,59 @@
 //! LSP server implementation for Rez.
 
+mod advisories;
+mod changelog;
+mod client_features;
+mod code_actions;
 mod completion;
 mod diagnostics;
+mod documents;
+mod errors;
+mod file_renames;
+mod formatting;
 mod hover;
+#[cfg(feature = "http")]
+pub mod http;
+mod inlay_hints;
+mod links;
+mod localize;
 mod lsp_server;
 mod navigation;
+mod notifications;
+mod package_browser;
+mod parent_process;
+mod path_mapping;
+mod positions;
+mod protocol_trace;
+mod providers;
+mod reference_index;
+mod release_check;
+pub mod session;
+mod setup_wizard;
+mod tasks;
+mod test_runner;
+mod trust;
+mod version_bump;
+mod virtual_documents;
+mod watchdog;
 
+pub use advisories::{
+    advisory_source, Advisory, AdvisoryList, AdvisorySeverity, AdvisorySource, FileAdvisorySource,
+    HttpAdvisorySource,
+};
+pub use changelog::{ChangelogDocument, ChangelogParams};
+pub use client_features::ClientFeatures;
 pub use diagnostics::{DiagnosticsManager, ValidationStats};
+pub use errors::{ErrorLog, ErrorReport, ErrorSeverity};
 pub use lsp_server::RezLanguageServer;
+pub use notifications::{IndexStats, IndexStatsNotification};
+pub use package_browser::{FamilyPage, FamilySort, FamilySummary, ListFamiliesParams};
+pub use positions::{LineIndex, PositionEncoding};
+pub use protocol_trace::{
+    ProtocolTrace, TraceDirection, TracedMessage, TracedReader, TracedWriter, MAX_TRACED_MESSAGES,
+    MAX_TRACED_MESSAGE_BYTES,
+};
+pub use providers::IndexProvider;
+pub use tasks::{CancellationToken, ShutdownReport, TaskRegistry};
+pub use trust::WorkspaceTrust;
+pub use virtual_documents::{PackageContent, PackageContentParams, PACKAGE_SCHEME};
+pub use watchdog::ServerHealth;
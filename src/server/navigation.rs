@@ -1,7 +1,16 @@
 //! Navigation features for the LSP server (Go to Definition, Find References, etc.).
 
-use crate::core::{traits::PackageDiscovery, types::Package, Result};
+use crate::config::{Config, PathMapping};
+use crate::core::{traits::PackageDiscovery, types::Package, Requirement, Result};
 use crate::discovery::PackageDiscoveryImpl;
+use crate::parser::commands::{find_commands, CommandsBlock, CommandsForm};
+use crate::parser::encoding::read_source;
+use crate::parser::{find_requirements, requirement_part_at, RequirementSpan};
+use crate::server::documents::DocumentKinds;
+use crate::server::path_mapping::{client_uri, server_path};
+use crate::server::positions::{LineIndex, PositionEncoding};
+use crate::server::reference_index::ReferenceIndex;
+use crate::server::virtual_documents::{package_content, package_uri, PACKAGE_SCHEME};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_lsp::lsp_types::*;
@@ -10,32 +19,58 @@ use tower_lsp::lsp_types::*;
 pub struct NavigationHandler {
     /// Package discovery service
     package_discovery: Arc<RwLock<Option<PackageDiscoveryImpl>>>,
+    /// Classifier of the documents navigation applies to
+    document_kinds: DocumentKinds,
 }
 
 impl NavigationHandler {
     /// Create a new navigation handler.
     pub fn new(package_discovery: Arc<RwLock<Option<PackageDiscoveryImpl>>>) -> Self {
-        Self { package_discovery }
+        Self {
+            package_discovery,
+            document_kinds: DocumentKinds::default(),
+        }
+    }
+
+    /// Classify documents with `document_kinds`, shared with the server.
+    pub fn with_document_kinds(mut self, document_kinds: DocumentKinds) -> Self {
+        self.document_kinds = document_kinds;
+        self
     }
 
     /// Handle "Go to Definition" requests.
+    ///
+    /// Requirements of `requires`-style lists and of variants navigate to the
+    /// package.py of the latest version satisfying their constraint.
     pub async fn handle_goto_definition(
         &self,
         params: &GotoDefinitionParams,
+        content: Option<&str>,
+        encoding: PositionEncoding,
     ) -> Result<Option<GotoDefinitionResponse>> {
         let uri = &params.text_document_position_params.text_document.uri;
         let position = &params.text_document_position_params.position;
 
         // Only handle package.py files
-        if !uri.path().ends_with("package.py") {
+        if !self.document_kinds.is_package_definition(uri) {
             return Ok(None);
         }
 
-        // Extract the word at the cursor position
-        if let Some(word) = self.extract_word_at_position(uri, position).await? {
-            // Check if it's a package reference
-            if let Some(package) = self.find_package_definition(&word).await? {
-                let location = self.package_to_location(&package)?;
+        let Some(content) = content else {
+            return Ok(None);
+        };
+
+        // Extract the requirement at the cursor position
+        if let Some(requirement) = extract_requirement_at_position(content, position, encoding)
+            .filter(|requirement| !requirement.is_ephemeral())
+        {
+            // Check if it's a package reference; ephemerals have no definition
+            if let Some(package) = self.find_package_definition(&requirement).await? {
+                let discovery = self.package_discovery.read().await;
+                let Some(discovery) = discovery.as_ref() else {
+                    return Ok(None);
+                };
+                let location = self.package_to_location(&package, encoding, discovery.config())?;
                 return Ok(Some(GotoDefinitionResponse::Scalar(location)));
             }
         }
@@ -43,23 +78,76 @@ impl NavigationHandler {
         Ok(None)
     }
 
+    /// Handle "Go to Type Definition" requests.
+    ///
+    /// Requirements navigate to the directory of their package family, which
+    /// lists every available version. Families spread across several
+    /// repositories return one location per repository.
+    pub async fn handle_goto_type_definition(
+        &self,
+        params: &request::GotoTypeDefinitionParams,
+        content: Option<&str>,
+        encoding: PositionEncoding,
+    ) -> Result<Option<request::GotoTypeDefinitionResponse>> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let position = &params.text_document_position_params.position;
+
+        // Only handle package.py files
+        if !self.document_kinds.is_package_definition(uri) {
+            return Ok(None);
+        }
+
+        let Some(content) = content else {
+            return Ok(None);
+        };
+
+        let Some(requirement) = extract_requirement_at_position(content, position, encoding)
+            .filter(|requirement| !requirement.is_ephemeral())
+        else {
+            return Ok(None);
+        };
+
+        let mut locations = self.find_family_locations(&requirement.name).await?;
+        Ok(match locations.len() {
+            0 => None,
+            1 => locations.pop().map(GotoDefinitionResponse::Scalar),
+            _ => Some(GotoDefinitionResponse::Array(locations)),
+        })
+    }
+
     /// Handle "Find References" requests.
+    ///
+    /// Returns the requirements on the family under the cursor in the
+    /// workspace files of `index`, and the `name` attributes declaring the
+    /// family when the client asks for declarations.
     pub async fn handle_find_references(
         &self,
         params: &ReferenceParams,
+        content: Option<&str>,
+        encoding: PositionEncoding,
+        index: &ReferenceIndex,
+        mappings: &[PathMapping],
     ) -> Result<Option<Vec<Location>>> {
         let uri = &params.text_document_position.text_document.uri;
         let position = &params.text_document_position.position;
 
         // Only handle package.py files
-        if !uri.path().ends_with("package.py") {
+        if !self.document_kinds.is_package_definition(uri) {
             return Ok(None);
         }
 
-        // Extract the word at the cursor position
-        if let Some(word) = self.extract_word_at_position(uri, position).await? {
+        let Some(content) = content else {
+            return Ok(None);
+        };
+
+        // Extract the requirement at the cursor position
+        if let Some(requirement) = extract_requirement_at_position(content, position, encoding) {
             // Find all references to this package
-            let references = self.find_package_references(&word).await?;
+            let references: Vec<Location> = index
+                .references(&requirement.name, params.context.include_declaration)
+                .into_iter()
+                .filter_map(|(path, range)| Some(Location::new(client_uri(path, mappings)?, range)))
+                .collect();
             if !references.is_empty() {
                 return Ok(Some(references));
             }
@@ -72,16 +160,21 @@ impl NavigationHandler {
     pub async fn handle_document_symbols(
         &self,
         params: &DocumentSymbolParams,
+        content: Option<&str>,
+        encoding: PositionEncoding,
     ) -> Result<Option<DocumentSymbolResponse>> {
         let uri = &params.text_document.uri;
 
         // Only handle package.py files
-        if !uri.path().ends_with("package.py") {
+        if !self.document_kinds.is_package_definition(uri) {
             return Ok(None);
         }
 
         // Parse the document and extract symbols
-        let symbols = self.extract_document_symbols(uri).await?;
+        let Some(content) = content else {
+            return Ok(None);
+        };
+        let symbols = extract_document_symbols(content, encoding);
         if !symbols.is_empty() {
             return Ok(Some(DocumentSymbolResponse::Nested(symbols)));
         }
@@ -93,11 +186,12 @@ impl NavigationHandler {
     pub async fn handle_workspace_symbols(
         &self,
         params: &WorkspaceSymbolParams,
+        encoding: PositionEncoding,
     ) -> Result<Option<Vec<SymbolInformation>>> {
         let query = &params.query;
 
         // Search for packages matching the query
-        let symbols = self.find_workspace_symbols(query).await?;
+        let symbols = self.find_workspace_symbols(query, encoding).await?;
         if !symbols.is_empty() {
             return Ok(Some(symbols));
         }
@@ -105,127 +199,89 @@ impl NavigationHandler {
         Ok(None)
     }
 
-    /// Extract the word at a specific position in a document.
-    async fn extract_word_at_position(
-        &self,
-        _uri: &Url,
-        _position: &Position,
-    ) -> Result<Option<String>> {
-        // TODO: Implement actual text parsing
-        // For now, return a placeholder
-        Ok(Some("example_package".to_string()))
+    /// Get the directories mapped between server paths and client URIs.
+    async fn path_mappings(&self) -> Vec<PathMapping> {
+        self.package_discovery
+            .read()
+            .await
+            .as_ref()
+            .map(|discovery| discovery.config().path_mappings.clone())
+            .unwrap_or_default()
     }
 
-    /// Find the definition of a package.
-    async fn find_package_definition(&self, package_name: &str) -> Result<Option<Package>> {
+    /// Find the package a requirement resolves to.
+    ///
+    /// Returns the latest version satisfying the requirement's constraint,
+    /// falling back to the latest version of the family when none does.
+    async fn find_package_definition(&self, requirement: &Requirement) -> Result<Option<Package>> {
         let discovery = self.package_discovery.read().await;
         if let Some(discovery) = discovery.as_ref() {
-            let packages = discovery.find_packages(package_name).await?;
-            // Return the latest version
-            Ok(packages.into_iter().max_by_key(|p| p.version.clone()))
+            let packages = discovery.get_package_versions(&requirement.name).await?;
+            let satisfying = packages
+                .iter()
+                .filter(|p| requirement.constraint.satisfies(&p.version))
+                .max_by(|a, b| a.version.cmp(&b.version));
+            Ok(satisfying
+                .or_else(|| packages.iter().max_by(|a, b| a.version.cmp(&b.version)))
+                .map(|package| (**package).clone()))
         } else {
             Ok(None)
         }
     }
 
-    /// Find all references to a package.
-    async fn find_package_references(&self, _package_name: &str) -> Result<Vec<Location>> {
-        // TODO: Implement reference finding
-        // This would involve scanning all package.py files for references
-        Ok(Vec::new())
-    }
-
-    /// Extract document symbols from a package.py file.
-    async fn extract_document_symbols(&self, _uri: &Url) -> Result<Vec<DocumentSymbol>> {
-        // TODO: Implement symbol extraction
-        // This would parse the package.py file and extract:
-        // - Package name
-        // - Version
-        // - Requirements
-        // - Tools
-        // - Functions (build, commands, etc.)
-
-        #[allow(deprecated)]
-        let symbols = vec![
-            DocumentSymbol {
-                name: "name".to_string(),
-                detail: Some("Package name".to_string()),
-                kind: SymbolKind::VARIABLE,
-                tags: None,
-                deprecated: None,
-
-                range: Range {
-                    start: Position {
-                        line: 0,
-                        character: 0,
-                    },
-                    end: Position {
-                        line: 0,
-                        character: 20,
-                    },
-                },
-                selection_range: Range {
-                    start: Position {
-                        line: 0,
-                        character: 0,
-                    },
-                    end: Position {
-                        line: 0,
-                        character: 4,
-                    },
-                },
-                children: None,
-            },
-            DocumentSymbol {
-                name: "version".to_string(),
-                detail: Some("Package version".to_string()),
-                kind: SymbolKind::VARIABLE,
-                tags: None,
-                deprecated: None,
-                range: Range {
-                    start: Position {
-                        line: 1,
-                        character: 0,
-                    },
-                    end: Position {
-                        line: 1,
-                        character: 20,
-                    },
-                },
-                selection_range: Range {
-                    start: Position {
-                        line: 1,
-                        character: 0,
-                    },
-                    end: Position {
-                        line: 1,
-                        character: 7,
-                    },
-                },
-                children: None,
-            },
-        ];
+    /// Find the family directories of a package in every repository providing it.
+    ///
+    /// Repositories are looked up directly because the index only keeps the
+    /// versions of the repository a family was last scanned from.
+    async fn find_family_locations(&self, name: &str) -> Result<Vec<Location>> {
+        let discovery = self.package_discovery.read().await;
+        let Some(discovery) = discovery.as_ref() else {
+            return Ok(Vec::new());
+        };
 
-        Ok(symbols)
+        let config = discovery.config();
+        let name = config.canonical_package_name(name);
+        config
+            .get_all_package_paths()
+            .into_iter()
+            .map(|repo_path| repo_path.join(name))
+            .filter(|directory| directory.is_dir())
+            .map(|directory| {
+                let uri = client_uri(&directory, &config.path_mappings).ok_or_else(|| {
+                    crate::core::Error::InvalidPath(directory.to_string_lossy().to_string())
+                })?;
+                Ok(Location {
+                    uri,
+                    range: Range::default(),
+                })
+            })
+            .collect()
     }
 
     /// Find workspace symbols matching a query.
-    async fn find_workspace_symbols(&self, query: &str) -> Result<Vec<SymbolInformation>> {
+    async fn find_workspace_symbols(
+        &self,
+        query: &str,
+        encoding: PositionEncoding,
+    ) -> Result<Vec<SymbolInformation>> {
         let discovery = self.package_discovery.read().await;
         if let Some(discovery) = discovery.as_ref() {
             let packages = discovery.find_packages(query).await?;
+            let repositories = discovery.config().get_all_package_paths();
             let mut symbols = Vec::new();
 
             for package in packages {
-                if let Ok(location) = self.package_to_location(&package) {
+                if let Ok(location) =
+                    self.package_to_location(&package, encoding, discovery.config())
+                {
                     #[allow(deprecated)]
                     let symbol = SymbolInformation {
-                        name: package.name.clone(),
+                        name: package.name.to_string(),
                         kind: SymbolKind::PACKAGE,
                         tags: None,
                         deprecated: None,
                         location,
-                        container_name: None,
+                        container_name: Some(symbol_container_name(&package, &repositories)),
                     };
                     symbols.push(symbol);
                 }
@@ -237,28 +293,278 @@ impl NavigationHandler {
         }
     }
 
-    /// Convert a package to a location.
-    fn package_to_location(&self, package: &Package) -> Result<Location> {
-        let uri = Url::from_file_path(&package.path).map_err(|_| {
-            crate::core::Error::InvalidPath(package.path.to_string_lossy().to_string())
+    /// Handle "Workspace Symbol Resolve" requests.
+    ///
+    /// Fills in the range of the `name` attribute for symbols whose location
+    /// only carries the package.py URI.
+    pub async fn handle_workspace_symbol_resolve(
+        &self,
+        mut symbol: WorkspaceSymbol,
+        encoding: PositionEncoding,
+    ) -> WorkspaceSymbol {
+        if let OneOf::Right(WorkspaceLocation { uri }) = &symbol.location {
+            let content = if uri.scheme() == PACKAGE_SCHEME {
+                let discovery = self.package_discovery.read().await;
+                discovery
+                    .as_ref()
+                    .and_then(|discovery| package_content(discovery, uri).ok())
+                    .map(|package| package.content)
+            } else {
+                server_path(uri, &self.path_mappings().await)
+                    .and_then(|path| read_source(&path).ok())
+            };
+            let range = content
+                .and_then(|content| name_attribute_range(&content, encoding))
+                .unwrap_or_default();
+            symbol.location = OneOf::Left(Location {
+                uri: uri.clone(),
+                range,
+            });
+        }
+        symbol
+    }
+
+    /// Convert a package to the location of its `name` attribute.
+    ///
+    /// The package.py is parsed on demand; if it cannot be read the location
+    /// points at the start of the file. With
+    /// [`Config::virtual_documents`] the location is a `rezpkg://` document.
+    fn package_to_location(
+        &self,
+        package: &Package,
+        encoding: PositionEncoding,
+        config: &Config,
+    ) -> Result<Location> {
+        let package_py = package.path.join("package.py");
+        let uri = if config.virtual_documents {
+            package_uri(package)
+        } else {
+            client_uri(&package_py, &config.path_mappings)
+        };
+        let uri = uri.ok_or_else(|| {
+            crate::core::Error::InvalidPath(package_py.to_string_lossy().to_string())
         })?;
 
-        Ok(Location {
-            uri,
-            range: Range {
+        let range = read_source(&package_py)
+            .ok()
+            .and_then(|content| name_attribute_range(&content, encoding))
+            .unwrap_or_default();
+
+        Ok(Location { uri, range })
+    }
+}
+
+/// Find the range of the top-level `name = ...` assignment in package.py content.
+fn name_attribute_range(content: &str, encoding: PositionEncoding) -> Option<Range> {
+    let index = LineIndex::new(content, encoding);
+    content.lines().enumerate().find_map(|(line_num, line)| {
+        let rest = line.strip_prefix("name")?.trim_start();
+        if !rest.starts_with('=') || rest.starts_with("==") {
+            return None;
+        }
+        let line_num = line_num as u32;
+        Some(Range::new(
+            Position::new(line_num, 0),
+            index.trimmed_line_end(line_num),
+        ))
+    })
+}
+
+/// Describe a package's version and repository for disambiguating symbols.
+fn symbol_container_name(package: &Package, repositories: &[std::path::PathBuf]) -> String {
+    let version = if package.version.is_unversioned() {
+        "unversioned".to_string()
+    } else {
+        package.version.to_string()
+    };
+
+    match repositories
+        .iter()
+        .find(|repository| package.path.starts_with(repository))
+    {
+        Some(repository) => format!("{} ({})", version, repository.display()),
+        None => version,
+    }
+}
+
+/// Extract top-level symbols from package.py content.
+///
+/// Attributes become variables and functions become functions. The
+/// `commands` attribute is reported as a function in both its function and
+/// string forms, with the environment variables it changes as children.
+pub fn extract_document_symbols(content: &str, encoding: PositionEncoding) -> Vec<DocumentSymbol> {
+    let lines: Vec<&str> = content.lines().collect();
+    let index = LineIndex::new(content, encoding);
+    let commands = find_commands(content);
+    let ephemerals: Vec<RequirementSpan> = find_requirements(content)
+        .into_iter()
+        .filter(RequirementSpan::is_ephemeral)
+        .collect();
+
+    // Start line of each top-level statement, skipping multi-line strings
+    let mut starts = Vec::new();
+    let mut in_string = false;
+    for (index, line) in lines.iter().enumerate() {
+        let starts_statement = !in_string
+            && !line.trim().is_empty()
+            && !line.starts_with([' ', '\t', '#', ')', ']', '}']);
+        if starts_statement {
+            starts.push(index);
+        }
+        let quotes = line.matches("\"\"\"").count() + line.matches("'''").count();
+        if quotes % 2 == 1 {
+            in_string = !in_string;
+        }
+    }
+
+    let mut symbols = Vec::new();
+    for (position, &start) in starts.iter().enumerate() {
+        let line = lines[start];
+        let next_start = starts.get(position + 1).copied().unwrap_or(lines.len());
+        let end = (start..next_start)
+            .rev()
+            .find(|&index| !lines[index].trim().is_empty())
+            .unwrap_or(start);
+        let range = Range::new(Position::new(start as u32, 0), index.line_end(end as u32));
+
+        let (name, kind) = if let Some(rest) = line.strip_prefix("def ") {
+            let name = rest.split('(').next().unwrap_or(rest).trim();
+            (name, SymbolKind::FUNCTION)
+        } else if let Some((name, _)) = line.split_once('=') {
+            let name = name.trim();
+            if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            (name, SymbolKind::VARIABLE)
+        } else {
+            continue;
+        };
+        if name.is_empty() {
+            continue;
+        }
+
+        let name_start = line.find(name).unwrap_or(0);
+        let selection_range = index.byte_range(start as u32, name_start, name_start + name.len());
+
+        let commands_block = commands
+            .as_ref()
+            .filter(|block| name == "commands" && block.line == start as u32);
+        let symbol = match commands_block {
+            Some(block) => commands_symbol(block, range, selection_range),
+            None =>
+            {
+                #[allow(deprecated)]
+                DocumentSymbol {
+                    name: name.to_string(),
+                    detail: None,
+                    kind,
+                    tags: None,
+                    deprecated: None,
+                    range,
+                    selection_range,
+                    children: ephemeral_symbols(&ephemerals, name, &index),
+                }
+            }
+        };
+        symbols.push(symbol);
+    }
+
+    symbols
+}
+
+/// Build the symbols of the ephemerals requested by a requirement list
+/// attribute, if any.
+fn ephemeral_symbols(
+    ephemerals: &[RequirementSpan],
+    attribute: &str,
+    index: &LineIndex<'_>,
+) -> Option<Vec<DocumentSymbol>> {
+    #[allow(deprecated)]
+    let children: Vec<DocumentSymbol> = ephemerals
+        .iter()
+        .filter(|span| span.attribute == attribute)
+        .map(|span| DocumentSymbol {
+            name: span.text.trim().to_string(),
+            detail: Some("ephemeral".to_string()),
+            kind: SymbolKind::CONSTANT,
+            tags: None,
+            deprecated: None,
+            range: index.range(span.range),
+            selection_range: index.range(span.name),
+            children: None,
+        })
+        .collect();
+    (!children.is_empty()).then_some(children)
+}
+
+/// Build the symbol for the `commands` attribute.
+fn commands_symbol(block: &CommandsBlock, range: Range, selection_range: Range) -> DocumentSymbol {
+    let detail = match block.form {
+        CommandsForm::Function => "commands",
+        CommandsForm::String => "commands (string form)",
+    };
+
+    #[allow(deprecated)]
+    let children = block
+        .operations
+        .iter()
+        .map(|operation| {
+            let line_range = Range {
                 start: Position {
-                    line: 0,
+                    line: operation.line,
                     character: 0,
                 },
                 end: Position {
-                    line: 0,
+                    line: operation.line,
                     character: 0,
                 },
-            },
+            };
+            DocumentSymbol {
+                name: operation.variable.clone(),
+                detail: Some(operation.action.as_str().to_string()),
+                kind: SymbolKind::VARIABLE,
+                tags: None,
+                deprecated: None,
+                range: line_range,
+                selection_range: line_range,
+                children: None,
+            }
         })
+        .collect();
+
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name: "commands".to_string(),
+        detail: Some(detail.to_string()),
+        kind: SymbolKind::FUNCTION,
+        tags: None,
+        deprecated: None,
+        range: Range {
+            start: range.start,
+            end: Position {
+                line: block.end_line.max(range.end.line),
+                character: range.end.character,
+            },
+        },
+        selection_range,
+        children: Some(children),
     }
 }
 
+/// Parse the requirement string under a position of a package definition.
+///
+/// Entries of `requires`-style lists and of single- or multi-line variant
+/// lists are recognized.
+fn extract_requirement_at_position(
+    content: &str,
+    position: &Position,
+    encoding: PositionEncoding,
+) -> Option<Requirement> {
+    let character = LineIndex::new(content, encoding).char_column(position);
+    let (span, _) = requirement_part_at(content, position.line, character)?;
+    Requirement::parse(&span.text).ok()
+}
+
 /// Extract package references from text content.
 #[allow(dead_code)]
 pub fn extract_package_references(content: &str) -> Vec<PackageReference> {
@@ -342,6 +648,7 @@ impl PackageReference {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::discovery::test_support::{scan_repositories, write_package};
 
     #[test]
     fn test_extract_package_references() {
@@ -375,6 +682,255 @@ requires = ["python>=3.7", "maya>=2020", "houdini"]
         assert_eq!(range.end.character, 14);
     }
 
+    #[test]
+    fn test_extract_document_symbols() {
+        let content = r#"name = "tools"
+version = "1.0.0"
+requires = [
+    "python",
+]
+description = "工具"
+
+def commands():
+    env.PATH.append("{root}/bin")
+"#;
+
+        let symbols = extract_document_symbols(content, PositionEncoding::Utf16);
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["name", "version", "requires", "description", "commands"]
+        );
+        assert_eq!(symbols[2].range.end.line, 4);
+        assert_eq!(symbols[3].range.end, Position::new(5, 18));
+        assert_eq!(symbols[4].kind, SymbolKind::FUNCTION);
+        assert_eq!(symbols[4].children.as_ref().unwrap()[0].name, "PATH");
+
+        let symbols = extract_document_symbols(content, PositionEncoding::Utf8);
+        assert_eq!(symbols[3].range.end, Position::new(5, 22));
+    }
+
+    #[test]
+    fn test_ephemeral_symbols() {
+        let content =
+            "requires = [\"python\", \".foo.bar-1\"]\nvariants = [[\".platform-linux\"]]\n";
+
+        let symbols = extract_document_symbols(content, PositionEncoding::Utf16);
+        let requires = symbols[0].children.as_ref().unwrap();
+        assert_eq!(requires.len(), 1);
+        assert_eq!(requires[0].name, ".foo.bar-1");
+        assert_eq!(requires[0].kind, SymbolKind::CONSTANT);
+        assert_eq!(requires[0].detail.as_deref(), Some("ephemeral"));
+        assert_eq!(requires[0].selection_range.start, Position::new(0, 23));
+        assert_eq!(requires[0].selection_range.end, Position::new(0, 31));
+
+        let variants = symbols[1].children.as_ref().unwrap();
+        assert_eq!(variants[0].name, ".platform-linux");
+    }
+
+    #[test]
+    fn test_string_commands_symbol() {
+        let content =
+            "name = \"legacy\"\ncommands = \"\"\"\nexport PATH=$PATH:{root}/bin\n\"\"\"\n";
+
+        let symbols = extract_document_symbols(content, PositionEncoding::Utf16);
+        assert_eq!(symbols.len(), 2);
+
+        let commands = &symbols[1];
+        assert_eq!(commands.kind, SymbolKind::FUNCTION);
+        assert_eq!(commands.detail.as_deref(), Some("commands (string form)"));
+        assert_eq!(commands.range.end.line, 3);
+        assert_eq!(commands.children.as_ref().unwrap()[0].name, "PATH");
+    }
+
+    #[test]
+    fn test_name_attribute_range() {
+        let content = "# -*- coding: utf-8 -*-\nname = \"tools\"  \nversion = \"1.0\"\n";
+        let range = name_attribute_range(content, PositionEncoding::Utf16).unwrap();
+        assert_eq!(range.start, Position::new(1, 0));
+        assert_eq!(range.end, Position::new(1, 14));
+
+        let range = name_attribute_range("name = \"工具\"\n", PositionEncoding::Utf16).unwrap();
+        assert_eq!(range.end, Position::new(0, 11));
+
+        assert!(
+            name_attribute_range("names = []\n    name = 'x'\n", PositionEncoding::Utf16).is_none()
+        );
+    }
+
+    #[test]
+    fn test_symbol_container_name() {
+        let repositories = vec![
+            std::path::PathBuf::from("/home/dev/packages"),
+            std::path::PathBuf::from("/studio/packages"),
+        ];
+        let package = Package::builder()
+            .name("maya_tools")
+            .version("1.2.0")
+            .path("/studio/packages/maya_tools/1.2.0")
+            .build();
+        assert_eq!(
+            symbol_container_name(&package, &repositories),
+            "1.2.0 (/studio/packages)"
+        );
+
+        let unversioned = Package::builder()
+            .name("scratch")
+            .version(crate::core::Version::unversioned())
+            .path("/elsewhere/scratch")
+            .build();
+        assert_eq!(
+            symbol_container_name(&unversioned, &repositories),
+            "unversioned"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_workspace_symbol_points_at_name_line() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package_dir = write_package(
+            temp_dir.path(),
+            "tools",
+            "1.0.0",
+            "# comment\nname = \"tools\"\nversion = \"1.0.0\"\n",
+        );
+
+        let handler = NavigationHandler::new(Arc::new(RwLock::new(None)));
+        let package = Package::builder()
+            .name("tools")
+            .version("1.0.0")
+            .path(&package_dir)
+            .build();
+        let location = handler
+            .package_to_location(&package, PositionEncoding::Utf16, &Config::new())
+            .unwrap();
+        assert!(location.uri.path().ends_with("tools/1.0.0/package.py"));
+        assert_eq!(location.range.start.line, 1);
+
+        let mut config = Config::new();
+        config.virtual_documents = true;
+        let preview = handler
+            .package_to_location(&package, PositionEncoding::Utf16, &config)
+            .unwrap();
+        assert_eq!(preview.uri.as_str(), "rezpkg://tools/1.0.0/package.py");
+        assert_eq!(preview.range.start.line, 1);
+
+        let symbol = WorkspaceSymbol {
+            name: "tools".to_string(),
+            kind: SymbolKind::PACKAGE,
+            tags: None,
+            container_name: None,
+            location: OneOf::Right(WorkspaceLocation {
+                uri: location.uri.clone(),
+            }),
+            data: None,
+        };
+        let resolved = handler
+            .handle_workspace_symbol_resolve(symbol, PositionEncoding::Utf16)
+            .await;
+        let OneOf::Left(resolved) = resolved.location else {
+            panic!("Expected a full location");
+        };
+        assert_eq!(resolved.range, location.range);
+    }
+
+    #[tokio::test]
+    async fn test_goto_definition_from_variant_entry() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+        for version in ["2023", "2024", "2025"] {
+            let content = format!("name = \"maya\"\nversion = \"{}\"\n", version);
+            write_package(repo, "maya", version, &content);
+        }
+
+        let discovery = scan_repositories(&[repo]).await;
+        let handler = NavigationHandler::new(Arc::new(RwLock::new(Some(discovery))));
+
+        let content = "name = 'tools'\nvariants = [\n    [\n        \"maya-2024\",\n        \"python-3.10\",\n    ],\n]\n";
+        let params = |line, character| GotoDefinitionParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: Url::parse("file:///tools/package.py").unwrap(),
+                },
+                position: Position::new(line, character),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        let response = handler
+            .handle_goto_definition(&params(3, 11), Some(content), PositionEncoding::Utf16)
+            .await
+            .unwrap();
+        let Some(GotoDefinitionResponse::Scalar(location)) = response else {
+            panic!("Expected a definition");
+        };
+        assert!(location.uri.path().ends_with("maya/2024/package.py"));
+
+        let response = handler
+            .handle_goto_definition(&params(1, 2), Some(content), PositionEncoding::Utf16)
+            .await
+            .unwrap();
+        assert!(response.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_goto_type_definition_targets_family_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let studio = temp_dir.path().join("studio");
+        let local = temp_dir.path().join("local");
+        for (repo, version) in [(&studio, "2023"), (&studio, "2024"), (&local, "2025")] {
+            let content = format!("name = \"maya\"\nversion = \"{}\"\n", version);
+            write_package(repo, "maya", version, &content);
+        }
+        write_package(&studio, "scratch", "", "name = \"scratch\"\n");
+
+        let discovery = scan_repositories(&[&studio, &local]).await;
+        let handler = NavigationHandler::new(Arc::new(RwLock::new(Some(discovery))));
+
+        let content = "name = 'tools'\nrequires = [\"maya-2024\", \"scratch\", \"~ocio\"]\n";
+        let params = |character| request::GotoTypeDefinitionParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: Url::parse("file:///tools/package.py").unwrap(),
+                },
+                position: Position::new(1, character),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+
+        let response = handler
+            .handle_goto_type_definition(&params(14), Some(content), PositionEncoding::Utf16)
+            .await
+            .unwrap();
+        let Some(GotoDefinitionResponse::Array(locations)) = response else {
+            panic!("Expected one location per repository");
+        };
+        let paths: Vec<_> = locations
+            .iter()
+            .map(|location| location.uri.to_file_path().unwrap())
+            .collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&studio.join("maya")));
+        assert!(paths.contains(&local.join("maya")));
+
+        let response = handler
+            .handle_goto_type_definition(&params(27), Some(content), PositionEncoding::Utf16)
+            .await
+            .unwrap();
+        let Some(GotoDefinitionResponse::Scalar(location)) = response else {
+            panic!("Expected the unversioned family directory");
+        };
+        assert_eq!(location.uri.to_file_path().unwrap(), studio.join("scratch"));
+
+        let response = handler
+            .handle_goto_type_definition(&params(39), Some(content), PositionEncoding::Utf16)
+            .await
+            .unwrap();
+        assert!(response.is_none());
+    }
+
     #[tokio::test]
     async fn test_navigation_handler_creation() {
         use std::sync::Arc;
@@ -0,0 +1,212 @@
+//! Custom `rez/*` notifications sent to the client.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tower_lsp::lsp_types::notification::Notification;
+use tower_lsp::lsp_types::Url;
+use tower_lsp::Client;
+
+use crate::analysis::GraphStats;
+use crate::core::{PackageDiscovery, Requirement};
+use crate::discovery::{DiscoveryMetrics, PackageDiscoveryImpl};
+use crate::parser::find_requirements;
+use crate::resolver::{ConflictDetector, ConflictSeverity};
+
+/// Statistics of the package index after a completed scan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStats {
+    /// Number of indexed package families
+    pub families: usize,
+    /// Number of indexed packages
+    pub packages: usize,
+    /// Per-repository scan metrics
+    pub discovery: DiscoveryMetrics,
+    /// Cycles, depth and hubs of the requirement graph
+    pub graph: GraphStats,
+}
+
+impl IndexStats {
+    /// Collect the statistics of a package index.
+    pub async fn from_discovery(discovery: &PackageDiscoveryImpl) -> Self {
+        let (families, packages) = discovery.get_stats().await.unwrap_or((0, 0));
+        // Large indexes take a while to analyze, off the async workers
+        let requirements = discovery.usage_index().requirement_graph();
+        let graph = tokio::task::spawn_blocking(move || GraphStats::analyze(&requirements))
+            .await
+            .unwrap_or_default();
+        Self {
+            families,
+            packages,
+            discovery: discovery.metrics().clone(),
+            graph,
+        }
+    }
+}
+
+/// The `rez/indexStats` notification, sent after every completed package scan.
+pub enum IndexStatsNotification {}
+
+impl Notification for IndexStatsNotification {
+    type Params = IndexStats;
+    const METHOD: &'static str = "rez/indexStats";
+}
+
+/// Send the statistics of a freshly scanned package index to the client.
+pub async fn send_index_stats(client: &Client, discovery: &PackageDiscoveryImpl) {
+    let stats = IndexStats::from_discovery(discovery).await;
+    client
+        .send_notification::<IndexStatsNotification>(stats)
+        .await;
+}
+
+/// Conflict health of the requirements of a package definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionHealth {
+    /// Analyzed package definition
+    pub uri: Url,
+    /// Overall severity: `ok`, `minor`, `moderate` or `severe`
+    pub status: ConflictSeverity,
+    /// Number of detected conflicts
+    pub conflicts: usize,
+    /// Number of analyzed requirements
+    pub requirements: usize,
+    /// Number of distinct required families
+    pub packages: usize,
+}
+
+impl ResolutionHealth {
+    /// Analyze the `requires` list of a package definition for conflicts
+    /// against the package index.
+    pub fn analyze(uri: Url, content: &str, discovery: &PackageDiscoveryImpl) -> Self {
+        let requirements: Vec<Requirement> = find_requirements(content)
+            .iter()
+            .filter(|requirement| {
+                requirement.attribute == "requires" && !requirement.is_ephemeral()
+            })
+            .filter_map(|requirement| Requirement::parse(&requirement.text).ok())
+            .map(|mut requirement| {
+                requirement.name = discovery
+                    .config()
+                    .canonical_package_name(&requirement.name)
+                    .to_string();
+                requirement
+            })
+            .collect();
+
+        // Only the required families matter for the analysis
+        let packages: HashMap<_, _> = requirements
+            .iter()
+            .filter_map(|requirement| {
+                let versions = discovery.packages().get(&requirement.name)?;
+                Some((requirement.name.clone(), versions.clone()))
+            })
+            .collect();
+        let mut detector = ConflictDetector::new();
+        detector.set_packages(packages);
+        let analysis = detector.analyze_conflicts(&requirements);
+
+        Self {
+            uri,
+            status: analysis.severity,
+            conflicts: analysis.conflicts.len(),
+            requirements: analysis.total_requirements,
+            packages: analysis.total_packages,
+        }
+    }
+}
+
+/// The `rez/resolutionHealth` notification, sent after each validation of a
+/// package definition.
+pub enum ResolutionHealthNotification {}
+
+impl Notification for ResolutionHealthNotification {
+    type Params = ResolutionHealth;
+    const METHOD: &'static str = "rez/resolutionHealth";
+}
+
+/// Send the conflict health of a validated package definition to the client.
+pub async fn send_resolution_health(
+    client: &Client,
+    uri: Url,
+    content: &str,
+    discovery: &PackageDiscoveryImpl,
+) {
+    let health = ResolutionHealth::analyze(uri, content, discovery);
+    client
+        .send_notification::<ResolutionHealthNotification>(health)
+        .await;
+}
+
+/// Presentation hint for a document that should not be edited in place.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadOnlyHint {
+    /// Opened document
+    pub uri: Url,
+    /// Whether the client should present the document as read-only
+    pub read_only: bool,
+    /// Why the document should not be edited
+    pub reason: String,
+}
+
+/// The `rez/readOnlyHint` notification, sent when a package definition of
+/// the release repository is opened.
+pub enum ReadOnlyHintNotification {}
+
+impl Notification for ReadOnlyHintNotification {
+    type Params = ReadOnlyHint;
+    const METHOD: &'static str = "rez/readOnlyHint";
+}
+
+/// Ask the client to present a released package definition as read-only.
+pub async fn send_read_only_hint(client: &Client, uri: Url) {
+    let hint = ReadOnlyHint {
+        uri,
+        read_only: true,
+        reason: "Editing a released package: changes won't take effect without a re-release"
+            .to_string(),
+    };
+    client
+        .send_notification::<ReadOnlyHintNotification>(hint)
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::{scan_repositories, write_package};
+
+    #[tokio::test]
+    async fn test_resolution_health() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for version in ["3.9", "3.11"] {
+            let content = format!("name = 'python'\nversion = '{}'\n", version);
+            write_package(temp_dir.path(), "python", version, &content);
+        }
+        let discovery = scan_repositories(&[temp_dir.path()]).await;
+
+        let uri = Url::parse("file:///tools/package.py").unwrap();
+        let healthy = ResolutionHealth::analyze(
+            uri.clone(),
+            "requires = ['python-3.9+', 'maya']\n",
+            &discovery,
+        );
+        assert_eq!(healthy.status, ConflictSeverity::None);
+        assert_eq!(healthy.requirements, 2);
+        assert_eq!(
+            serde_json::to_value(&healthy).unwrap()["status"],
+            serde_json::json!("ok")
+        );
+
+        let conflicting = ResolutionHealth::analyze(
+            uri,
+            "requires = [\n    'python-3.11+',\n    'python<3.10',\n]\n",
+            &discovery,
+        );
+        assert_eq!(conflicting.status, ConflictSeverity::Minor);
+        assert_eq!(conflicting.conflicts, 1);
+        assert_eq!(conflicting.packages, 1);
+    }
+}
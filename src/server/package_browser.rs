@@ -0,0 +1,290 @@
+//! Paged listing of package families for browser views.
+//!
+//! The `rez/listFamilies` request backs tree views and package browsers in
+//! editor extensions. Instead of transferring the whole index, it returns
+//! one page of family summaries at a time, filtered and sorted by the
+//! server.
+
+use std::path::PathBuf;
+
+use crate::discovery::PackageDiscoveryImpl;
+
+/// Families returned when a request does not set a limit.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Largest page a request may ask for.
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Order of the families of a `rez/listFamilies` response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FamilySort {
+    /// Alphabetically by name
+    #[default]
+    Name,
+    /// Most indexed versions first
+    VersionCount,
+    /// By repository in search order, then by name
+    Repository,
+}
+
+/// Parameters for the `rez/listFamilies` request.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ListFamiliesParams {
+    /// Only list families whose name starts with this prefix
+    pub prefix: Option<String>,
+    /// Only list families whose latest version lives in this repository
+    pub repository: Option<PathBuf>,
+    /// Only list families whose latest version declares tools, or none
+    pub has_tools: Option<bool>,
+    /// Order of the families
+    pub sort: FamilySort,
+    /// Number of matching families to skip
+    pub offset: usize,
+    /// Maximum number of families to return, clamped to at least one and at
+    /// most [`MAX_PAGE_SIZE`]
+    pub limit: Option<usize>,
+}
+
+/// Summary of a package family.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FamilySummary {
+    /// Family name
+    pub name: String,
+    /// Latest indexed version, absent for unversioned families
+    pub latest_version: Option<String>,
+    /// Number of indexed versions
+    pub version_count: usize,
+    /// Repository of the latest version
+    pub repository: Option<PathBuf>,
+    /// Description of the latest version
+    pub description: Option<String>,
+    /// Tools of the latest version
+    pub tools: Vec<String>,
+}
+
+/// Response of the `rez/listFamilies` request.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FamilyPage {
+    /// Families of the page
+    pub families: Vec<FamilySummary>,
+    /// Number of families matching the filters, across all pages
+    pub total: usize,
+    /// Offset of the next page, absent on the last page
+    pub next_offset: Option<usize>,
+}
+
+/// List a page of the indexed families matching the filters of `params`.
+///
+/// Families are filtered and sorted on their latest version, so summaries
+/// are only built for the families of the page.
+pub fn list_families(discovery: &PackageDiscoveryImpl, params: &ListFamiliesParams) -> FamilyPage {
+    let repositories = discovery.config().get_all_package_paths();
+    let prefix = params.prefix.as_deref().unwrap_or_default();
+
+    // Name, latest version, version count and repository index of each
+    // matching family
+    let mut families: Vec<_> = discovery
+        .packages()
+        .iter()
+        .filter(|(name, _)| name.starts_with(prefix))
+        .filter_map(|(name, versions)| {
+            let latest = discovery.latest_version(name)?;
+            let repository = repositories
+                .iter()
+                .position(|repository| latest.path.starts_with(repository));
+            Some((name, latest, versions.len(), repository))
+        })
+        .filter(|(_, _, _, repository)| {
+            params.repository.as_ref().is_none_or(|expected| {
+                repository.is_some_and(|index| &repositories[index] == expected)
+            })
+        })
+        .filter(|(_, latest, _, _)| {
+            params
+                .has_tools
+                .is_none_or(|has_tools| has_tools != latest.tools.is_empty())
+        })
+        .collect();
+
+    match params.sort {
+        FamilySort::Name => families.sort_by(|a, b| a.0.cmp(b.0)),
+        FamilySort::VersionCount => {
+            families.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(b.0)))
+        }
+        FamilySort::Repository => families.sort_by(|a, b| {
+            let rank = |repository: Option<usize>| repository.unwrap_or(usize::MAX);
+            rank(a.3).cmp(&rank(b.3)).then_with(|| a.0.cmp(b.0))
+        }),
+    }
+
+    let total = families.len();
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let families: Vec<FamilySummary> = families
+        .into_iter()
+        .skip(params.offset)
+        .take(limit)
+        .map(|(name, latest, version_count, repository)| FamilySummary {
+            name: name.clone(),
+            latest_version: (!latest.version.is_unversioned()).then(|| latest.version.to_string()),
+            version_count,
+            repository: repository.map(|index| repositories[index].clone()),
+            description: latest.description.clone(),
+            tools: latest.tools.iter().map(|tool| tool.to_string()).collect(),
+        })
+        .collect();
+    let end = params.offset + families.len();
+    FamilyPage {
+        families,
+        total,
+        next_offset: (end < total).then_some(end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::{scan_repositories, write_package};
+
+    /// Name, version and extra attributes of a test package.
+    type TestPackage<'a> = (&'a str, &'a str, &'a str);
+
+    async fn discovery(
+        repositories: &[(&std::path::Path, &[TestPackage<'_>])],
+    ) -> PackageDiscoveryImpl {
+        for (root, packages) in repositories {
+            for (name, version, extra) in *packages {
+                write_package(
+                    root,
+                    name,
+                    version,
+                    &format!("name = '{}'\nversion = '{}'\n{}", name, version, extra),
+                );
+            }
+        }
+        let roots: Vec<&std::path::Path> = repositories.iter().map(|(root, _)| *root).collect();
+        scan_repositories(&roots).await
+    }
+
+    #[tokio::test]
+    async fn test_list_families() {
+        let studio = tempfile::tempdir().unwrap();
+        let external = tempfile::tempdir().unwrap();
+        let discovery = discovery(&[
+            (
+                studio.path(),
+                &[
+                    ("maya_tools", "1.0", "tools = ['mt']\n"),
+                    (
+                        "maya_tools",
+                        "1.1",
+                        "description = 'Maya helpers'\ntools = ['mt']\n",
+                    ),
+                    ("nuke_tools", "2.0", ""),
+                ],
+            ),
+            (
+                external.path(),
+                &[
+                    ("maya", "2023", "tools = ['maya']\n"),
+                    ("maya", "2024", "tools = ['maya']\n"),
+                    ("maya", "2025", "tools = ['maya', 'mayapy']\n"),
+                    ("python", "3.11", ""),
+                ],
+            ),
+        ])
+        .await;
+
+        let names = |page: &FamilyPage| -> Vec<String> {
+            page.families.iter().map(|f| f.name.clone()).collect()
+        };
+        let page = list_families(
+            &discovery,
+            &ListFamiliesParams {
+                prefix: Some("maya".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(names(&page), vec!["maya", "maya_tools"]);
+        assert_eq!(
+            page.families[1],
+            FamilySummary {
+                name: "maya_tools".to_string(),
+                latest_version: Some("1.1".to_string()),
+                version_count: 2,
+                repository: Some(studio.path().to_path_buf()),
+                description: Some("Maya helpers".to_string()),
+                tools: vec!["mt".to_string()],
+            }
+        );
+
+        let page = list_families(
+            &discovery,
+            &ListFamiliesParams {
+                has_tools: Some(false),
+                sort: FamilySort::Repository,
+                ..Default::default()
+            },
+        );
+        assert_eq!(names(&page), vec!["nuke_tools", "python"]);
+
+        let page = list_families(
+            &discovery,
+            &ListFamiliesParams {
+                repository: Some(external.path().to_path_buf()),
+                sort: FamilySort::VersionCount,
+                ..Default::default()
+            },
+        );
+        assert_eq!(names(&page), vec!["maya", "python"]);
+
+        // Pages follow each other until the last one
+        let first = list_families(
+            &discovery,
+            &ListFamiliesParams {
+                limit: Some(3),
+                ..Default::default()
+            },
+        );
+        assert_eq!(names(&first), vec!["maya", "maya_tools", "nuke_tools"]);
+        assert_eq!((first.total, first.next_offset), (4, Some(3)));
+        let last = list_families(
+            &discovery,
+            &ListFamiliesParams {
+                offset: 3,
+                limit: Some(3),
+                ..Default::default()
+            },
+        );
+        assert_eq!(names(&last), vec!["python"]);
+        assert_eq!(last.next_offset, None);
+
+        // Empty pages would leave clients following next_offset in place
+        let page = list_families(
+            &discovery,
+            &ListFamiliesParams {
+                offset: 1,
+                limit: Some(0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(names(&page), vec!["maya_tools"]);
+        assert_eq!(page.next_offset, Some(2));
+    }
+
+    #[test]
+    fn test_params_defaults() {
+        let params: ListFamiliesParams =
+            serde_json::from_value(serde_json::json!({ "sort": "versionCount", "hasTools": true }))
+                .unwrap();
+        assert_eq!(params.sort, FamilySort::VersionCount);
+        assert_eq!(params.has_tools, Some(true));
+        assert_eq!((params.offset, params.limit), (0, None));
+    }
+}
@@ -0,0 +1,80 @@
+//! Monitoring of the editor process that started the server.
+//!
+//! When the editor crashes it never sends `shutdown`, and the orphaned server
+//! would keep its package index in memory indefinitely. The process id given
+//! in `initialize` (or with `--parent-pid`) is polled, and the server exits
+//! once that process is gone.
+
+use std::time::Duration;
+
+/// Interval between checks of the parent process.
+pub const PARENT_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Check whether a process is running.
+#[cfg(unix)]
+pub fn is_process_alive(pid: u32) -> bool {
+    let proc = std::path::Path::new("/proc");
+    if proc.is_dir() {
+        return proc.join(pid.to_string()).exists();
+    }
+    // No procfs, e.g. on macOS; signal 0 only checks the process exists
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Check whether a process is running.
+#[cfg(windows)]
+pub fn is_process_alive(pid: u32) -> bool {
+    let output = std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .output();
+    match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)),
+        // Without tasklist the parent cannot be monitored; assume it runs
+        Err(_) => true,
+    }
+}
+
+/// Check whether a process is running.
+#[cfg(not(any(unix, windows)))]
+pub fn is_process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Wait until a process is no longer running, checking every `interval`.
+pub async fn wait_for_exit(pid: u32, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let alive = tokio::task::spawn_blocking(move || is_process_alive(pid))
+            .await
+            .unwrap_or(true);
+        if !alive {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_wait_for_exit() {
+        assert!(is_process_alive(std::process::id()));
+
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(!is_process_alive(pid));
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            wait_for_exit(pid, Duration::from_millis(10)),
+        )
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+//! Path mapping between the filesystems of the server and the client.
+//!
+//! In docker or SSH remote setups the server sees package repositories under
+//! other paths than the client, e.g. `/packages` inside a container mounted
+//! from `C:\studio\packages`. URIs sent to the client are mapped to its view
+//! and URIs received from it back to the server's.
+
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::Url;
+
+use crate::config::PathMapping;
+
+/// Convert a server path into a URI the client can open.
+///
+/// The mapping with the longest matching server directory applies; paths
+/// outside every mapped directory are converted unchanged.
+pub fn client_uri(path: &Path, mappings: &[PathMapping]) -> Option<Url> {
+    let mapped = mappings
+        .iter()
+        .filter_map(|mapping| {
+            let rest = path.strip_prefix(&mapping.server).ok()?;
+            Some((mapping.server.components().count(), mapping, rest))
+        })
+        .max_by_key(|(depth, _, _)| *depth)
+        .map(|(_, mapping, rest)| client_root(&mapping.client).join(rest));
+    Url::from_file_path(mapped.as_deref().unwrap_or(path)).ok()
+}
+
+/// Convert a URI received from the client into a server path.
+///
+/// The mapping with the longest matching client directory applies; URIs
+/// outside every mapped directory are converted unchanged.
+pub fn server_path(uri: &Url, mappings: &[PathMapping]) -> Option<PathBuf> {
+    let path = uri.to_file_path().ok()?;
+    if mappings.is_empty() {
+        return Some(path);
+    }
+
+    let path = PathBuf::from(lowercase_drive(&path.to_string_lossy()));
+    let mapped = mappings
+        .iter()
+        .filter_map(|mapping| {
+            let root = client_root(&mapping.client);
+            let rest = path.strip_prefix(&root).ok()?;
+            Some((root.components().count(), mapping.server.join(rest)))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, mapped)| mapped);
+    Some(mapped.unwrap_or(path))
+}
+
+/// Get the client directory of a mapping as a path of the server.
+///
+/// On Unix servers a Windows client directory such as `C:\packages` becomes
+/// `/c:/packages`, the path of its `file:///c:/packages` URI.
+fn client_root(client: &str) -> PathBuf {
+    if cfg!(windows) {
+        return PathBuf::from(client);
+    }
+    let client = client.replace('\\', "/");
+    let client = client.trim_end_matches('/');
+    let bytes = client.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        PathBuf::from(lowercase_drive(&format!("/{}", client)))
+    } else if client.is_empty() {
+        PathBuf::from("/")
+    } else {
+        PathBuf::from(client)
+    }
+}
+
+/// Lowercase the drive letter of a `/C:/...` path; clients differ in the case
+/// of the drive letters of their URIs.
+fn lowercase_drive(path: &str) -> String {
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        format!("/{}{}", path[1..2].to_lowercase(), &path[2..])
+    } else {
+        path.to_string()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn mapping(server: &str, client: &str) -> PathMapping {
+        PathMapping {
+            server: PathBuf::from(server),
+            client: client.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_path_mapping_round_trip() {
+        let mappings = vec![
+            mapping("/packages", "/Users/artist/mnt/packages"),
+            mapping("/packages/dev", "C:\\studio\\dev"),
+        ];
+
+        let uri = client_uri(Path::new("/packages/maya/2024/package.py"), &mappings).unwrap();
+        assert_eq!(
+            uri.as_str(),
+            "file:///Users/artist/mnt/packages/maya/2024/package.py"
+        );
+        assert_eq!(
+            server_path(&uri, &mappings).unwrap(),
+            PathBuf::from("/packages/maya/2024/package.py")
+        );
+
+        // The longest server directory wins
+        let uri = client_uri(Path::new("/packages/dev/my tool/package.py"), &mappings).unwrap();
+        assert_eq!(uri.as_str(), "file:///c:/studio/dev/my%20tool/package.py");
+        let from_client = Url::parse("file:///C%3A/studio/dev/my%20tool/package.py").unwrap();
+        assert_eq!(
+            server_path(&from_client, &mappings).unwrap(),
+            PathBuf::from("/packages/dev/my tool/package.py")
+        );
+
+        // Unmapped paths are left alone
+        let uri = client_uri(Path::new("/opt/tools/bin"), &mappings).unwrap();
+        assert_eq!(uri.as_str(), "file:///opt/tools/bin");
+        assert_eq!(
+            server_path(&uri, &mappings).unwrap(),
+            PathBuf::from("/opt/tools/bin")
+        );
+        assert!(server_path(&Url::parse("untitled:Untitled-1").unwrap(), &mappings).is_none());
+    }
+}
@@ -0,0 +1,244 @@
+//! Conversion between source columns and LSP positions.
+//!
+//! The parsers report columns as characters and string searches yield byte
+//! offsets, while LSP positions count code units of the encoding negotiated
+//! with the client: UTF-16 unless the client offers UTF-8. Every range sent
+//! to the client and every position received from it goes through a
+//! [`LineIndex`], so ranges stay correct on lines with non-ASCII text such as
+//! CJK descriptions.
+
+use tower_lsp::lsp_types::{ClientCapabilities, Position, PositionEncodingKind, Range};
+
+use crate::parser::SourceRange;
+
+/// Unit in which LSP position columns are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    /// UTF-8 code units, i.e. bytes
+    Utf8,
+    /// UTF-16 code units, the LSP default
+    #[default]
+    Utf16,
+}
+
+impl PositionEncoding {
+    /// Pick the encoding for a client: UTF-8 when it supports it, UTF-16
+    /// otherwise.
+    pub fn negotiate(capabilities: &ClientCapabilities) -> Self {
+        let offers_utf8 = capabilities
+            .general
+            .as_ref()
+            .and_then(|general| general.position_encodings.as_ref())
+            .is_some_and(|encodings| encodings.contains(&PositionEncodingKind::UTF8));
+        if offers_utf8 {
+            Self::Utf8
+        } else {
+            Self::Utf16
+        }
+    }
+
+    /// Get the kind advertised in the server capabilities.
+    pub fn kind(self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+        }
+    }
+
+    /// Get the number of code units of a character.
+    fn units(self, c: char) -> u32 {
+        match self {
+            Self::Utf8 => c.len_utf8() as u32,
+            Self::Utf16 => c.len_utf16() as u32,
+        }
+    }
+
+    /// Convert a 0-based character column of a line to code units.
+    ///
+    /// Columns past the end of the line are extended by one unit per column.
+    pub fn column(self, line: &str, char_column: u32) -> u32 {
+        let mut units = 0;
+        let mut chars = line.chars();
+        for counted in 0..char_column {
+            match chars.next() {
+                Some(c) => units += self.units(c),
+                None => return units + (char_column - counted),
+            }
+        }
+        units
+    }
+
+    /// Convert a byte offset into a line to code units.
+    ///
+    /// Offsets inside a character or past the end of the line are clamped.
+    pub fn byte_column(self, line: &str, byte: usize) -> u32 {
+        line.char_indices()
+            .take_while(|(index, c)| index + c.len_utf8() <= byte)
+            .map(|(_, c)| self.units(c))
+            .sum()
+    }
+
+    /// Convert a column in code units to a 0-based character column.
+    ///
+    /// A column inside a character maps to that character. Columns past the
+    /// end of the line are extended by one character per unit.
+    pub fn char_column(self, line: &str, column: u32) -> u32 {
+        let mut units = 0;
+        let mut chars = 0;
+        for c in line.chars() {
+            let next = units + self.units(c);
+            if next > column {
+                return chars;
+            }
+            units = next;
+            chars += 1;
+        }
+        chars + (column - units)
+    }
+}
+
+/// Lines of a document, converting its columns to and from LSP positions.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    lines: Vec<&'a str>,
+    encoding: PositionEncoding,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Index the lines of `content` for positions in `encoding`.
+    pub fn new(content: &'a str, encoding: PositionEncoding) -> Self {
+        Self {
+            lines: content.lines().collect(),
+            encoding,
+        }
+    }
+
+    /// Get a line of the document, empty past its end.
+    fn line(&self, line: u32) -> &'a str {
+        self.lines.get(line as usize).copied().unwrap_or_default()
+    }
+
+    /// Get the position of a 0-based character column.
+    pub fn position(&self, line: u32, char_column: u32) -> Position {
+        Position::new(line, self.encoding.column(self.line(line), char_column))
+    }
+
+    /// Get the position of a byte offset into a line.
+    pub fn byte_position(&self, line: u32, byte: usize) -> Position {
+        Position::new(line, self.encoding.byte_column(self.line(line), byte))
+    }
+
+    /// Get the position after the last character of a line.
+    pub fn line_end(&self, line: u32) -> Position {
+        let text = self.line(line);
+        self.byte_position(line, text.len())
+    }
+
+    /// Get the position after the last non-whitespace character of a line.
+    pub fn trimmed_line_end(&self, line: u32) -> Position {
+        let text = self.line(line);
+        self.byte_position(line, text.trim_end().len())
+    }
+
+    /// Convert a range of character columns on one line.
+    pub fn range(&self, range: SourceRange) -> Range {
+        Range::new(
+            self.position(range.line, range.start),
+            self.position(range.line, range.end),
+        )
+    }
+
+    /// Convert a range of byte offsets into one line.
+    pub fn byte_range(&self, line: u32, start: usize, end: usize) -> Range {
+        Range::new(
+            self.byte_position(line, start),
+            self.byte_position(line, end),
+        )
+    }
+
+    /// Get the 0-based character column of a position received from the
+    /// client.
+    pub fn char_column(&self, position: &Position) -> u32 {
+        self.encoding
+            .char_column(self.line(position.line), position.character)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::GeneralClientCapabilities;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(
+            PositionEncoding::negotiate(&ClientCapabilities::default()),
+            PositionEncoding::Utf16
+        );
+
+        let capabilities = |encodings: Vec<PositionEncodingKind>| ClientCapabilities {
+            general: Some(GeneralClientCapabilities {
+                position_encodings: Some(encodings),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            PositionEncoding::negotiate(&capabilities(vec![
+                PositionEncodingKind::UTF16,
+                PositionEncodingKind::UTF8,
+            ])),
+            PositionEncoding::Utf8
+        );
+        assert_eq!(
+            PositionEncoding::negotiate(&capabilities(vec![PositionEncodingKind::UTF32])),
+            PositionEncoding::Utf16
+        );
+    }
+
+    #[test]
+    fn test_columns() {
+        let line = "description = '🎬 工具' # x";
+        let utf16 = PositionEncoding::Utf16;
+        assert_eq!(utf16.column(line, 15), 15);
+        // The emoji takes two UTF-16 code units and four bytes
+        assert_eq!(utf16.column(line, 16), 17);
+        assert_eq!(utf16.column(line, 19), 20);
+        assert_eq!(PositionEncoding::Utf8.column(line, 19), 26);
+        assert_eq!(utf16.column("ab", 4), 4);
+
+        assert_eq!(utf16.byte_column(line, 26), 20);
+        assert_eq!(utf16.byte_column(line, 16), 15);
+        assert_eq!(PositionEncoding::Utf8.byte_column(line, 26), 26);
+        // Inside a character
+        assert_eq!(PositionEncoding::Utf8.byte_column(line, 25), 23);
+
+        assert_eq!(utf16.char_column(line, 17), 16);
+        // Inside the emoji
+        assert_eq!(utf16.char_column(line, 16), 15);
+        assert_eq!(PositionEncoding::Utf8.char_column(line, 26), 19);
+        assert_eq!(utf16.char_column("ab", 4), 4);
+    }
+
+    #[test]
+    fn test_line_index() {
+        let content = "name = 'tools'\ndescription = '工具'  \n";
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+        assert_eq!(index.line_end(1), Position::new(1, 20));
+        assert_eq!(index.trimmed_line_end(1), Position::new(1, 18));
+        assert_eq!(
+            index.range(SourceRange {
+                line: 1,
+                start: 15,
+                end: 17,
+            }),
+            Range::new(Position::new(1, 15), Position::new(1, 17))
+        );
+        assert_eq!(index.char_column(&Position::new(1, 17)), 17);
+
+        let index = LineIndex::new(content, PositionEncoding::Utf8);
+        assert_eq!(index.trimmed_line_end(1), Position::new(1, 22));
+        assert_eq!(index.char_column(&Position::new(1, 21)), 17);
+        assert_eq!(index.byte_range(1, 15, 21).end, Position::new(1, 21));
+    }
+}
@@ -0,0 +1,383 @@
+//! Capture of the JSON-RPC traffic between the client and the server.
+//!
+//! With `--stdio-trace <FILE>`, stdin and stdout are wrapped in a
+//! [`TracedReader`] and a [`TracedWriter`] that split the byte streams into
+//! LSP messages and record them in a [`ProtocolTrace`]. Every message is
+//! appended to the file with a timestamp and a direction marker (`-->` from
+//! the client, `<--` to the client), and the last messages are kept in memory
+//! for the `rez/dumpTrace` request. Document contents are redacted and long
+//! messages truncated, so traces can be attached to bug reports.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::thread::JoinHandle;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Number of messages kept in memory for `rez/dumpTrace`.
+pub const MAX_TRACED_MESSAGES: usize = 1000;
+
+/// Bytes of a message kept before it is truncated.
+pub const MAX_TRACED_MESSAGE_BYTES: usize = 8 * 1024;
+
+/// Direction of a traced message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TraceDirection {
+    /// Sent by the client to the server
+    Incoming,
+    /// Sent by the server to the client
+    Outgoing,
+}
+
+impl TraceDirection {
+    /// Get the marker of the direction in trace files.
+    pub fn marker(self) -> &'static str {
+        match self {
+            Self::Incoming => "-->",
+            Self::Outgoing => "<--",
+        }
+    }
+}
+
+/// A traced JSON-RPC message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TracedMessage {
+    /// RFC 3339 time the message was read or written
+    pub timestamp: String,
+    /// Direction of the message
+    pub direction: TraceDirection,
+    /// Size of the original message body in bytes
+    pub size: usize,
+    /// Sanitized and possibly truncated message body
+    pub message: String,
+}
+
+/// Recorder of the traced messages.
+///
+/// Lines are appended to the trace file by a background thread, so reading
+/// and writing messages never waits on the disk.
+#[derive(Debug)]
+pub struct ProtocolTrace {
+    recent: Mutex<VecDeque<TracedMessage>>,
+    file: Option<TraceFile>,
+}
+
+/// Background writer of the trace file.
+#[derive(Debug)]
+struct TraceFile {
+    lines: Option<Sender<String>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl TraceFile {
+    fn spawn(file: File) -> std::io::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<String>();
+        let writer = std::thread::Builder::new()
+            .name("protocol-trace".to_string())
+            .spawn(move || {
+                let mut file = BufWriter::new(file);
+                while let Ok(line) = receiver.recv() {
+                    // Write what is queued before flushing
+                    let written = std::iter::once(line)
+                        .chain(receiver.try_iter())
+                        .try_for_each(|line| writeln!(file, "{}", line))
+                        .and_then(|_| file.flush());
+                    if let Err(e) = written {
+                        tracing::debug!("Failed to write protocol trace: {}", e);
+                    }
+                }
+            })?;
+        Ok(Self {
+            lines: Some(sender),
+            writer: Some(writer),
+        })
+    }
+
+    fn write(&self, line: String) {
+        if let Some(lines) = &self.lines {
+            // The writer only stops once the sender is dropped
+            let _ = lines.send(line);
+        }
+    }
+}
+
+impl Drop for TraceFile {
+    fn drop(&mut self) {
+        // Close the channel and wait for the queued lines to be written
+        self.lines.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl ProtocolTrace {
+    /// Create a trace keeping the last messages in memory only.
+    pub fn in_memory() -> Self {
+        Self {
+            recent: Mutex::new(VecDeque::new()),
+            file: None,
+        }
+    }
+
+    /// Create a trace also appending every message to `path`.
+    pub fn to_file(path: &Path) -> std::io::Result<Self> {
+        let file = File::options().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Some(TraceFile::spawn(file)?),
+            ..Self::in_memory()
+        })
+    }
+
+    /// Record a message body.
+    pub fn record(&self, direction: TraceDirection, body: &[u8]) {
+        let message = TracedMessage {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            direction,
+            size: body.len(),
+            message: sanitize(body),
+        };
+
+        if let Some(file) = &self.file {
+            file.write(format!(
+                "{} {} {}",
+                message.timestamp,
+                direction.marker(),
+                message.message
+            ));
+        }
+
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == MAX_TRACED_MESSAGES {
+            recent.pop_front();
+        }
+        recent.push_back(message);
+    }
+
+    /// Get the last `limit` messages, oldest first.
+    pub fn last(&self, limit: Option<usize>) -> Vec<TracedMessage> {
+        let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        let skip = limit.map_or(0, |limit| recent.len().saturating_sub(limit));
+        recent.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Redact document contents from a message body and truncate it.
+///
+/// Any `text` or `newText` string, such as the content of opened or changed
+/// documents and of the edits sent back, is replaced by its size.
+fn sanitize(body: &[u8]) -> String {
+    let mut message = match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(mut value) => {
+            redact_text(&mut value);
+            value.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(body).into_owned(),
+    };
+    if message.len() > MAX_TRACED_MESSAGE_BYTES {
+        let mut end = MAX_TRACED_MESSAGE_BYTES;
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        let dropped = message.len() - end;
+        message.truncate(end);
+        message.push_str(&format!("… ({} more bytes)", dropped));
+    }
+    message
+}
+
+fn redact_text(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                match field {
+                    serde_json::Value::String(text) if key == "text" || key == "newText" => {
+                        *field = format!("<{} bytes redacted>", text.len()).into();
+                    }
+                    _ => redact_text(field),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_text),
+        _ => {}
+    }
+}
+
+/// Splitter of a byte stream into the bodies of `Content-Length` framed LSP
+/// messages.
+#[derive(Debug, Default)]
+struct Frames {
+    buffer: Vec<u8>,
+}
+
+impl Frames {
+    /// Add bytes of the stream, returning the bodies of the messages they
+    /// complete.
+    fn push(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut bodies = Vec::new();
+        while let Some(header_end) = self
+            .buffer
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+        {
+            let length = String::from_utf8_lossy(&self.buffer[..header_end])
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok());
+            let body_start = header_end + 4;
+            let Some(length) = length else {
+                // Not a header the client would accept either; resynchronize
+                self.buffer.drain(..body_start);
+                continue;
+            };
+            if self.buffer.len() < body_start + length {
+                break;
+            }
+            bodies.push(self.buffer[body_start..body_start + length].to_vec());
+            self.buffer.drain(..body_start + length);
+        }
+        bodies
+    }
+}
+
+/// Reader recording the messages read from the client.
+pub struct TracedReader<R> {
+    inner: R,
+    trace: Arc<ProtocolTrace>,
+    frames: Frames,
+}
+
+impl<R> TracedReader<R> {
+    /// Wrap a reader of client messages.
+    pub fn new(inner: R, trace: Arc<ProtocolTrace>) -> Self {
+        Self {
+            inner,
+            trace,
+            frames: Frames::default(),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for TracedReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            for body in this.frames.push(&buf.filled()[before..]) {
+                this.trace.record(TraceDirection::Incoming, &body);
+            }
+        }
+        poll
+    }
+}
+
+/// Writer recording the messages written to the client.
+pub struct TracedWriter<W> {
+    inner: W,
+    trace: Arc<ProtocolTrace>,
+    frames: Frames,
+}
+
+impl<W> TracedWriter<W> {
+    /// Wrap a writer of server messages.
+    pub fn new(inner: W, trace: Arc<ProtocolTrace>) -> Self {
+        Self {
+            inner,
+            trace,
+            frames: Frames::default(),
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for TracedWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            for body in this.frames.push(&buf[..written]) {
+                this.trace.record(TraceDirection::Outgoing, &body);
+            }
+        }
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn frame(body: &str) -> Vec<u8> {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body).into_bytes()
+    }
+
+    #[tokio::test]
+    async fn test_traces_both_directions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("trace.log");
+        let trace = Arc::new(ProtocolTrace::to_file(&path).unwrap());
+
+        let open = r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"text":"name = 'secret'"}}}"#;
+        let mut input = frame(open);
+        input.extend(frame(r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#));
+        let mut reader = TracedReader::new(&input[..], trace.clone());
+        let mut read = Vec::new();
+        reader.read_to_end(&mut read).await.unwrap();
+        assert_eq!(read, input);
+
+        let mut writer = TracedWriter::new(Vec::new(), trace.clone());
+        let edit = frame(r#"{"id":2,"result":[{"newText":"name = 'secret'"}]}"#);
+        writer.write_all(&edit).await.unwrap();
+        let reply = frame(&format!(r#"{{"id":1,"result":"{}"}}"#, "x".repeat(10_000)));
+        // Messages split across writes are reassembled
+        writer.write_all(&reply[..20]).await.unwrap();
+        writer.write_all(&reply[20..]).await.unwrap();
+        assert_eq!(writer.inner, [edit, reply].concat());
+
+        let messages = trace.last(None);
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].direction, TraceDirection::Incoming);
+        assert!(!messages[0].message.contains("secret"));
+        assert!(messages[0].message.contains("<15 bytes redacted>"));
+        assert!(!messages[2].message.contains("secret"));
+        assert!(messages[2].message.contains("<15 bytes redacted>"));
+        assert_eq!(messages[3].direction, TraceDirection::Outgoing);
+        assert!(messages[3].message.ends_with("more bytes)"));
+        assert_eq!(trace.last(Some(1)), messages[3..]);
+
+        // Dropping the trace waits for the file to be written
+        drop((reader, writer, trace));
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].contains(" --> ") && lines[1].contains("shutdown"));
+        assert!(lines[3].contains(" <-- "));
+    }
+}
@@ -0,0 +1,700 @@
+//! Completion and hover providers backed by the package index.
+//!
+//! The server consumes [`CompletionProvider`] and [`HoverProvider`] through
+//! trait objects, so handlers can be driven by alternative providers such as
+//! remote indexes or test fakes. [`IndexProvider`] is the default
+//! implementation over the discovered packages.
+
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+use crate::core::{
+    explain_requirement, package_category, CompletionItem, CompletionItemKind, CompletionProvider,
+    HoverInfo, HoverProvider, KnownFamily, LspError, Package, PackageCategory, PackageDiscovery,
+    Requirement, Result, Version, VersionConstraint,
+};
+use crate::discovery::{PackageDiscoveryImpl, PayloadCache};
+use crate::server::completion::{render_detail, DetailFields};
+
+/// Provider answering completion and hover queries from the package index.
+///
+/// Queries fail with [`LspError::NotInitialized`] until the index is built,
+/// or are answered from the families scanned so far with a warming index.
+#[derive(Clone)]
+pub struct IndexProvider {
+    package_discovery: Arc<RwLock<Option<PackageDiscoveryImpl>>>,
+    /// Families indexed so far while the index is built
+    warming_index: Arc<RwLock<Option<PackageDiscoveryImpl>>>,
+    /// Payload statistics of hovered requirements
+    payloads: Arc<PayloadCache>,
+}
+
+impl IndexProvider {
+    /// Create a provider over the shared package index.
+    pub fn new(package_discovery: Arc<RwLock<Option<PackageDiscoveryImpl>>>) -> Self {
+        Self {
+            package_discovery,
+            warming_index: Arc::new(RwLock::new(None)),
+            payloads: Arc::new(PayloadCache::new()),
+        }
+    }
+
+    /// Answer queries from the families indexed so far while the index is
+    /// built.
+    pub fn with_warming_index(
+        mut self,
+        warming_index: Arc<RwLock<Option<PackageDiscoveryImpl>>>,
+    ) -> Self {
+        self.warming_index = warming_index;
+        self
+    }
+
+    /// Read the package index, or the warming index while it is built.
+    async fn index(&self) -> RwLockReadGuard<'_, Option<PackageDiscoveryImpl>> {
+        let guard = self.package_discovery.read().await;
+        if guard.is_some() {
+            return guard;
+        }
+        drop(guard);
+        self.warming_index.read().await
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for IndexProvider {
+    async fn complete_package_names(&self, prefix: &str) -> Result<Vec<CompletionItem>> {
+        let guard = self.index().await;
+        let discovery = guard.as_ref().ok_or(LspError::NotInitialized)?;
+
+        let template = discovery.config().completion_detail.as_deref();
+        let repositories = discovery.config().get_all_package_paths();
+
+        let mut items = Vec::new();
+        for name in discovery.get_all_package_names().await? {
+            if !name.starts_with(prefix) {
+                continue;
+            }
+            let versions = discovery.get_package_versions(&name).await?;
+            let Some(latest) = discovery.latest_version(&name) else {
+                continue;
+            };
+            let detail = match template {
+                Some(template) => render_detail(
+                    template,
+                    &DetailFields {
+                        latest: if latest.version.is_unversioned() {
+                            "unversioned".to_string()
+                        } else {
+                            latest.version.to_string()
+                        },
+                        count: versions.len(),
+                        repo: repositories
+                            .iter()
+                            .find(|repository| latest.path.starts_with(repository))
+                            .map(PathBuf::as_path),
+                        description: latest.description.as_deref(),
+                    },
+                ),
+                None if latest.version.is_unversioned() => "Rez package (unversioned)".to_string(),
+                None => format!("Rez package (latest: {})", latest.version),
+            };
+            items.push(CompletionItem {
+                label: name.clone(),
+                kind: CompletionItemKind::Package,
+                detail: Some(detail),
+                documentation: latest.description.clone().or_else(|| {
+                    discovery
+                        .config()
+                        .known_families
+                        .get(&name)
+                        .and_then(KnownFamily::summary)
+                }),
+                insert_text: Some(name),
+                sort_text: None,
+            });
+        }
+
+        Ok(items)
+    }
+
+    async fn complete_package_names_in(
+        &self,
+        prefix: &str,
+        attribute: Option<&str>,
+    ) -> Result<Vec<CompletionItem>> {
+        let mut items = self.complete_package_names(prefix).await?;
+        let Some(preferred) = attribute.and_then(PackageCategory::for_attribute) else {
+            return Ok(items);
+        };
+        let guard = self.index().await;
+        let discovery = guard.as_ref().ok_or(LspError::NotInitialized)?;
+
+        // Families of the category the list needs rank first
+        let categories = &discovery.config().package_categories;
+        for item in &mut items {
+            let category = discovery
+                .pick_version(&item.label, &VersionConstraint::Any)
+                .map_or(PackageCategory::Runtime, |package| {
+                    package_category(package, categories)
+                });
+            let rank = if category == preferred { 0 } else { 1 };
+            item.sort_text = Some(format!("{}_{}", rank, item.label));
+        }
+        Ok(items)
+    }
+
+    async fn complete_versions(
+        &self,
+        package_name: &str,
+        prefix: &str,
+    ) -> Result<Vec<CompletionItem>> {
+        let guard = self.index().await;
+        let discovery = guard.as_ref().ok_or(LspError::NotInitialized)?;
+
+        // A prefix with wildcards lists the versions matching it
+        let pattern = prefix
+            .contains('*')
+            .then(|| VersionConstraint::Wildcard(Version::new(prefix)));
+        let mut versions = discovery.get_package_versions(package_name).await?;
+        let scheme = versions
+            .first()
+            .map(|package| discovery.config().version_policy.scheme_for(&package.path))
+            .unwrap_or_default();
+        // Versions following the repository's scheme rank before the others
+        versions.sort_by(|a, b| scheme.compare(&b.version, &a.version));
+
+        Ok(versions
+            .iter()
+            .filter(|package| !package.version.is_unversioned())
+            .filter(|package| {
+                pattern
+                    .as_ref()
+                    .is_none_or(|pattern| pattern.satisfies(&package.version))
+            })
+            .map(|package| package.version.to_string())
+            .filter(|version| pattern.is_some() || version.starts_with(prefix))
+            .enumerate()
+            .map(|(rank, version)| CompletionItem {
+                label: version.clone(),
+                kind: CompletionItemKind::Version,
+                detail: Some(format!("{} version", package_name)),
+                documentation: None,
+                insert_text: Some(version),
+                // Newest first
+                sort_text: Some(format!("{:06}", rank)),
+            })
+            .collect())
+    }
+
+    async fn complete_requirements(&self, prefix: &str) -> Result<Vec<CompletionItem>> {
+        // A requirement starts with its family name, optionally followed by
+        // `-` and a version once the family is complete. After a `|` the
+        // version of the next alternative is completed.
+        let Some(name_end) = prefix.find(['-', '<', '=']) else {
+            return self.complete_package_names(prefix).await;
+        };
+        let (family, range) = prefix.split_at(name_end);
+        let (typed, version) = match range.rfind('|') {
+            Some(bar) => prefix.split_at(name_end + bar + 1),
+            None if range.starts_with('-') => prefix.split_at(name_end + 1),
+            None => return self.complete_package_names(prefix).await,
+        };
+        // Versions already accepted by the earlier alternatives are left out
+        let accepted = range
+            .contains('|')
+            .then(|| VersionConstraint::parse(typed.trim_end_matches('|')))
+            .and_then(|parsed| parsed.ok())
+            .map(|(_, constraint)| constraint);
+
+        let mut items = self.complete_versions(family, version).await?;
+        items.retain(|item| {
+            accepted
+                .as_ref()
+                .is_none_or(|constraint| !constraint.satisfies(&Version::new(&item.label)))
+        });
+        for item in &mut items {
+            item.label = format!("{}{}", typed, item.label);
+            item.insert_text = Some(item.label.clone());
+            item.kind = CompletionItemKind::Requirement;
+        }
+        Ok(items)
+    }
+
+    async fn complete_tools(&self, prefix: &str) -> Result<Vec<CompletionItem>> {
+        let guard = self.index().await;
+        let discovery = guard.as_ref().ok_or(LspError::NotInitialized)?;
+
+        let tools = tools_by_name(
+            discovery.packages().values().flatten().map(|p| &**p),
+            prefix,
+        );
+        Ok(tools
+            .into_iter()
+            .map(|(tool, package)| CompletionItem {
+                label: tool.clone(),
+                kind: CompletionItemKind::Tool,
+                detail: Some(format!("Tool of {}", package)),
+                documentation: None,
+                insert_text: Some(tool),
+                sort_text: None,
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl HoverProvider for IndexProvider {
+    async fn hover_package(
+        &self,
+        name: &str,
+        version: Option<&Version>,
+    ) -> Result<Option<HoverInfo>> {
+        let guard = self.index().await;
+        let discovery = guard.as_ref().ok_or(LspError::NotInitialized)?;
+
+        let versions = discovery.get_package_versions(name).await?;
+        let package = match version {
+            Some(version) => versions
+                .iter()
+                .map(|p| &**p)
+                .find(|p| &p.version == version),
+            None => discovery.latest_version(name),
+        };
+        let known = discovery.config().known_families.get(name);
+        Ok(package.map(|package| HoverInfo {
+            content: package_markdown(package, versions.len(), known),
+            range: None,
+        }))
+    }
+
+    async fn hover_working_copy(
+        &self,
+        name: &str,
+        version: &Version,
+        location: &Path,
+    ) -> Result<Option<HoverInfo>> {
+        let guard = self.index().await;
+        let discovery = guard.as_ref().ok_or(LspError::NotInitialized)?;
+
+        let siblings = discovery.get_package_versions(name).await?;
+        let mut content = super::hover::version_hover_markdown(
+            name,
+            version,
+            &siblings,
+            Some(location),
+            discovery.config().release_packages_path.as_deref(),
+        );
+        let elided = discovery.elided_versions(name);
+        if elided > 0 {
+            content.push_str(&super::hover::elided_versions_note(elided));
+        }
+
+        Ok(Some(HoverInfo {
+            content,
+            range: None,
+        }))
+    }
+
+    async fn hover_requirement(&self, requirement: &str) -> Result<Option<HoverInfo>> {
+        let Ok(requirement) = Requirement::parse(requirement) else {
+            return Ok(None);
+        };
+        if requirement.is_ephemeral() {
+            return Ok(None);
+        }
+        let guard = self.index().await;
+        let discovery = guard.as_ref().ok_or(LspError::NotInitialized)?;
+
+        let versions = discovery.get_package_versions(&requirement.name).await?;
+        let matching = versions
+            .iter()
+            .filter(|package| requirement.constraint.satisfies(&package.version))
+            .count();
+        let best = discovery.pick_version(&requirement.name, &requirement.constraint);
+
+        let mut content = format!("**{}**\n", requirement);
+        match best {
+            Some(best) => content.push_str(&format!(
+                "\nResolves to `{}`{} ({} of {} indexed version(s) match).",
+                best.version,
+                if best.version.is_prerelease() {
+                    " *(pre-release)*"
+                } else {
+                    ""
+                },
+                matching,
+                versions.len()
+            )),
+            None if versions.is_empty() => {
+                content.push_str("\nNo versions of this package are indexed.")
+            }
+            None => content.push_str("\nNo indexed version satisfies this requirement."),
+        }
+
+        let config = discovery.config();
+        let family = config.canonical_package_name(&requirement.name);
+        if let Some(known) = config.known_families.get(family) {
+            if best.is_none_or(|best| best.description.is_none()) {
+                content.push_str(&format!("\n\n{}", known.markdown()));
+            }
+        }
+
+        if config.hover_explain {
+            content.push_str(&format!(
+                "\n\n**{}:** {}",
+                config.locale.explanation_heading(),
+                explain_requirement(&requirement, config.locale)
+            ));
+        }
+
+        // Statting the payload can be slow, so it is opt-in and done without
+        // holding the index lock
+        let payload = best
+            .filter(|_| config.hover_payload_stats)
+            .map(|best| (best.path.clone(), config.payload_max_files));
+        drop(guard);
+        if let Some((root, max_files)) = payload {
+            let stats = self.payloads.get(&root, max_files).await;
+            content.push_str(&format!("\n\n**Payload:** {}", stats.summary()));
+        }
+
+        Ok(Some(HoverInfo {
+            content,
+            range: None,
+        }))
+    }
+
+    async fn hover_tool(&self, tool: &str) -> Result<Option<HoverInfo>> {
+        let guard = self.index().await;
+        let discovery = guard.as_ref().ok_or(LspError::NotInitialized)?;
+
+        let mut providers: Vec<String> = discovery
+            .packages()
+            .values()
+            .flatten()
+            .filter(|package| package.tools.iter().any(|t| &**t == tool))
+            .map(|package| format!("{}-{}", package.name, package.version))
+            .collect();
+        if providers.is_empty() {
+            return Ok(None);
+        }
+        providers.sort();
+
+        let mut content = format!("**{}** is provided by:\n", tool);
+        for provider in providers {
+            content.push_str(&format!("- `{}`\n", provider));
+        }
+        Ok(Some(HoverInfo {
+            content,
+            range: None,
+        }))
+    }
+}
+
+/// Collect the tools starting with `prefix`, each with the first package
+/// providing it.
+fn tools_by_name<'a>(
+    packages: impl Iterator<Item = &'a Package>,
+    prefix: &str,
+) -> BTreeMap<String, String> {
+    let mut tools = BTreeMap::new();
+    for package in packages {
+        for tool in package.tools.iter().filter(|tool| tool.starts_with(prefix)) {
+            tools
+                .entry(tool.to_string())
+                .or_insert_with(|| package.name.to_string());
+        }
+    }
+    tools
+}
+
+/// Describe a package for hover.
+///
+/// A well-known family's description fills in when the package has none.
+fn package_markdown(
+    package: &Package,
+    version_count: usize,
+    known: Option<&KnownFamily>,
+) -> String {
+    let mut markdown = format!("**{}** `{}`\n", package.name, package.version);
+    match (&package.description, known) {
+        (Some(description), _) => markdown.push_str(&format!("\n{}\n", description)),
+        (None, Some(known)) => markdown.push_str(&format!("\n{}\n", known.markdown())),
+        (None, None) => {}
+    }
+    if !package.tools.is_empty() {
+        markdown.push_str(&format!("\n**Tools:** {}\n", package.tools.join(", ")));
+    }
+    markdown.push_str(&format!(
+        "\n{} indexed version(s) · `{}`",
+        version_count,
+        package.path.display()
+    ));
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::core::Locale;
+    use crate::discovery::test_support::{scan_repositories, write_package};
+
+    fn provider_without_index() -> IndexProvider {
+        IndexProvider::new(Arc::new(RwLock::new(None)))
+    }
+
+    #[tokio::test]
+    async fn test_queries_fail_before_indexing() {
+        let provider = provider_without_index();
+        assert!(provider.complete_package_names("").await.is_err());
+        assert!(provider.hover_tool("maya").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_queries_use_warming_index() {
+        let mut warming = PackageDiscoveryImpl::new(Config::new());
+        warming.insert_family(
+            "maya".to_string(),
+            vec![Package::builder().name("maya").version("2024.1").build()],
+        );
+        let provider =
+            provider_without_index().with_warming_index(Arc::new(RwLock::new(Some(warming))));
+
+        let items = provider.complete_package_names("ma").await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].detail.as_deref(),
+            Some("Rez package (latest: 2024.1)")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_index() {
+        let discovery = PackageDiscoveryImpl::new(Config::new());
+        let provider = IndexProvider::new(Arc::new(RwLock::new(Some(discovery))));
+
+        assert!(provider
+            .complete_package_names("")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(provider
+            .hover_package("maya", None)
+            .await
+            .unwrap()
+            .is_none());
+        let info = provider
+            .hover_requirement("maya-2024+")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(info
+            .content
+            .contains("No versions of this package are indexed"));
+    }
+
+    #[tokio::test]
+    async fn test_requirement_hover_payload() {
+        let repo = tempfile::tempdir().unwrap();
+        let package_dir = write_package(
+            repo.path(),
+            "ocio",
+            "2.3.0",
+            "name = 'ocio'\nversion = '2.3.0'\n",
+        );
+        std::fs::create_dir_all(package_dir.join("lib")).unwrap();
+        std::fs::write(package_dir.join("lib/libOpenColorIO.so"), vec![0u8; 4096]).unwrap();
+
+        let discovery = scan_repositories(&[repo.path()]).await;
+        let mut config = discovery.config().clone();
+        let provider = IndexProvider::new(Arc::new(RwLock::new(Some(discovery))));
+
+        let info = provider.hover_requirement("ocio-2").await.unwrap().unwrap();
+        assert!(info.content.contains("Resolves to `2.3.0`"));
+        assert!(!info.content.contains("Payload"));
+
+        config.hover_payload_stats = true;
+        let mut discovery = PackageDiscoveryImpl::new(config);
+        discovery.scan_packages().await.unwrap();
+        *provider.package_discovery.write().await = Some(discovery);
+
+        let info = provider.hover_requirement("ocio-2").await.unwrap().unwrap();
+        assert!(info.content.contains("**Payload:** 4.0 KiB in 2 file(s)"));
+        assert!(!info.content.contains("What this means"));
+    }
+
+    #[tokio::test]
+    async fn test_requirement_hover_explanation() {
+        let mut config = Config::new();
+        config.hover_explain = true;
+        let provider = IndexProvider::new(Arc::new(RwLock::new(Some(PackageDiscoveryImpl::new(
+            config.clone(),
+        )))));
+
+        let info = provider
+            .hover_requirement("python-3.7+<4")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(info.content.ends_with(
+            "**What this means:** Requires any 3.x version at or above 3.7 and below 4 of `python`."
+        ));
+
+        config.locale = Locale::Chinese;
+        *provider.package_discovery.write().await = Some(PackageDiscoveryImpl::new(config));
+        let info = provider
+            .hover_requirement("~python")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(info.content.contains("**含义:** 不会引入 `python`"));
+    }
+
+    #[tokio::test]
+    async fn test_known_families_fill_in_sparse_metadata() {
+        let mut discovery = PackageDiscoveryImpl::new(Config::new());
+        discovery.insert_family(
+            "houdini".to_string(),
+            vec![Package::builder().name("houdini").version("20.5").build()],
+        );
+        let provider = IndexProvider::new(Arc::new(RwLock::new(Some(discovery))));
+
+        let info = provider
+            .hover_package("houdini", None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(info.content.contains("**Vendor:** SideFX"));
+        let items = provider.complete_package_names("hou").await.unwrap();
+        assert_eq!(
+            items[0].documentation.as_deref(),
+            Some("Procedural 3D animation and visual effects application (SideFX)")
+        );
+
+        let info = provider
+            .hover_requirement("nuke-15")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(info
+            .content
+            .contains("No versions of this package are indexed"));
+        assert!(info
+            .content
+            .contains("[Documentation](https://learn.foundry.com/nuke/)"));
+    }
+
+    #[tokio::test]
+    async fn test_or_requirement_completion() {
+        let repo = tempfile::tempdir().unwrap();
+        for version in ["2023.1", "2024", "2025"] {
+            let content = format!("name = 'maya'\nversion = '{}'\n", version);
+            write_package(repo.path(), "maya", version, &content);
+        }
+
+        let discovery = scan_repositories(&[repo.path()]).await;
+        let provider = IndexProvider::new(Arc::new(RwLock::new(Some(discovery))));
+
+        let labels = |items: Vec<CompletionItem>| -> Vec<String> {
+            items.into_iter().map(|item| item.label).collect()
+        };
+        assert_eq!(
+            labels(provider.complete_requirements("maya-202").await.unwrap()),
+            vec!["maya-2025", "maya-2024", "maya-2023.1"]
+        );
+        // Versions accepted by an earlier alternative are not offered again
+        assert_eq!(
+            labels(
+                provider
+                    .complete_requirements("maya-2023|20")
+                    .await
+                    .unwrap()
+            ),
+            vec!["maya-2023|2025", "maya-2023|2024"]
+        );
+        assert_eq!(
+            labels(provider.complete_requirements("maya==2024|").await.unwrap()),
+            vec!["maya==2024|2025", "maya==2024|2023.1"]
+        );
+        // Wildcards list the matching versions, newest first
+        assert_eq!(
+            labels(provider.complete_requirements("maya-*").await.unwrap()),
+            vec!["maya-2025", "maya-2024", "maya-2023.1"]
+        );
+        assert_eq!(
+            labels(provider.complete_requirements("maya-2023.*").await.unwrap()),
+            vec!["maya-2023.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_package_names_ranked_by_category() {
+        let repo = tempfile::tempdir().unwrap();
+        for (name, tools) in [
+            ("cmake", "[]"),
+            ("openexr", "[]"),
+            ("devtoolset", "['gcc']"),
+        ] {
+            let content = format!("name = '{}'\ntools = {}\n", name, tools);
+            write_package(repo.path(), name, "1.0", &content);
+        }
+
+        let discovery = scan_repositories(&[repo.path()]).await;
+        let provider = IndexProvider::new(Arc::new(RwLock::new(Some(discovery))));
+
+        let ranked = |items: Vec<CompletionItem>| -> Vec<String> {
+            let mut items = items;
+            items.sort_by(|a, b| a.sort_text.cmp(&b.sort_text));
+            items.into_iter().map(|item| item.label).collect()
+        };
+        assert_eq!(
+            ranked(
+                provider
+                    .complete_package_names_in("", Some("private_build_requires"))
+                    .await
+                    .unwrap()
+            ),
+            vec!["cmake", "devtoolset", "openexr"]
+        );
+        assert_eq!(
+            ranked(
+                provider
+                    .complete_package_names_in("", Some("requires"))
+                    .await
+                    .unwrap()
+            ),
+            vec!["openexr", "cmake", "devtoolset"]
+        );
+        // Outside requirement lists no ranking is applied
+        assert!(provider
+            .complete_package_names_in("", None)
+            .await
+            .unwrap()
+            .iter()
+            .all(|item| item.sort_text.is_none()));
+    }
+
+    #[test]
+    fn test_tools_by_name() {
+        let packages = [
+            Package::builder()
+                .name("maya")
+                .version("2024")
+                .tools(["maya", "mayapy"])
+                .build(),
+            Package::builder()
+                .name("maya_tools")
+                .version("1.0")
+                .tools(["mayapy", "mt"])
+                .build(),
+        ];
+
+        let tools = tools_by_name(packages.iter(), "may");
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools["mayapy"], "maya");
+    }
+}
@@ -0,0 +1,307 @@
+//! Token index of the workspace files referencing packages.
+//!
+//! Finding the references of a family, or the files to edit when a package
+//! directory is renamed, would otherwise read every package.py and lock file
+//! of the workspace on each request. The index is built once from disk and
+//! then kept up to date from document changes and file watcher events.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::Range;
+
+use crate::discovery::WorkspaceWalker;
+use crate::parser::encoding::read_source;
+use crate::parser::{find_requirements, string_attribute_range};
+use crate::server::positions::{LineIndex, PositionEncoding};
+
+/// Identifier of an indexed path, so postings do not repeat the path.
+type FileId = u32;
+
+/// Tokens and requirement ranges of the indexed reference files.
+#[derive(Debug)]
+pub struct ReferenceIndex {
+    encoding: PositionEncoding,
+    /// Paths by identifier; identifiers of dropped files are kept for when
+    /// the file comes back
+    paths: Vec<PathBuf>,
+    ids: HashMap<PathBuf, FileId>,
+    files: HashMap<FileId, IndexedFile>,
+    /// Files containing each token
+    postings: HashMap<String, HashSet<FileId>>,
+}
+
+/// Tokens and ranges of a reference file, read before they are added to a
+/// [`ReferenceIndex`].
+#[derive(Debug, Default)]
+pub struct IndexedFile {
+    tokens: HashSet<String>,
+    /// Family and name range of each requirement of a package.py
+    requirements: Vec<(String, Range)>,
+    /// Family and range of the `name` attribute of a package.py
+    declaration: Option<(String, Range)>,
+}
+
+impl IndexedFile {
+    /// Read the tokens and ranges of the file at `path` from its content,
+    /// with ranges counting `encoding` code units.
+    pub fn new(path: &Path, content: &str, encoding: PositionEncoding) -> Self {
+        let mut file = Self {
+            tokens: tokens(content),
+            ..Default::default()
+        };
+        if path.file_name().is_some_and(|name| name == "package.py") {
+            let index = LineIndex::new(content, encoding);
+            file.requirements = find_requirements(content)
+                .iter()
+                .filter(|span| !span.is_ephemeral())
+                .map(|span| (span.name_text().to_string(), index.range(span.name)))
+                .collect();
+            file.declaration = string_attribute_range(content, "name").map(|(line, range)| {
+                let text = content.lines().nth(line as usize).unwrap_or_default();
+                let name = text[range.clone()].to_string();
+                (name, index.byte_range(line, range.start, range.end))
+            });
+        }
+        file
+    }
+}
+
+impl ReferenceIndex {
+    /// Create an empty index whose ranges count `encoding` code units.
+    pub fn new(encoding: PositionEncoding) -> Self {
+        Self {
+            encoding,
+            paths: Vec::new(),
+            ids: HashMap::new(),
+            files: HashMap::new(),
+            postings: HashMap::new(),
+        }
+    }
+
+    /// Index the reference files below `roots` accepted by `filter`.
+    ///
+    /// Unreadable files are skipped.
+    pub fn build(
+        roots: &[PathBuf],
+        walker: &WorkspaceWalker,
+        filter: impl Fn(&Path) -> bool + Copy,
+        encoding: PositionEncoding,
+    ) -> Self {
+        let mut index = Self::new(encoding);
+        for root in roots {
+            for path in walker.files(root, filter).files {
+                index.reload(&path);
+            }
+        }
+        index
+    }
+
+    /// Number of indexed files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Index or re-index a file from its content.
+    pub fn insert(&mut self, path: PathBuf, content: &str) {
+        let file = IndexedFile::new(&path, content, self.encoding);
+        self.insert_file(path, file);
+    }
+
+    /// Index or re-index a file read with [`IndexedFile::new`], e.g. outside
+    /// the lock of the index.
+    ///
+    /// Only the postings of tokens added or removed since the file was last
+    /// indexed change.
+    pub fn insert_file(&mut self, path: PathBuf, file: IndexedFile) {
+        let id = match self.ids.get(&path) {
+            Some(id) => *id,
+            None => {
+                let id = self.paths.len() as FileId;
+                self.paths.push(path.clone());
+                self.ids.insert(path, id);
+                id
+            }
+        };
+        let previous = self.files.remove(&id).unwrap_or_default();
+        for token in previous.tokens.difference(&file.tokens) {
+            self.remove_posting(token, id);
+        }
+        for token in file.tokens.difference(&previous.tokens) {
+            self.postings.entry(token.clone()).or_default().insert(id);
+        }
+        self.files.insert(id, file);
+    }
+
+    /// Re-index a file from disk, or drop it if it cannot be read.
+    pub fn reload(&mut self, path: &Path) {
+        match read_source(path) {
+            Ok(content) => self.insert(path.to_path_buf(), &content),
+            Err(_) => {
+                self.remove(path);
+            }
+        }
+    }
+
+    /// Drop a file from the index, returning whether it was indexed.
+    pub fn remove(&mut self, path: &Path) -> bool {
+        let Some(&id) = self.ids.get(path) else {
+            return false;
+        };
+        let Some(file) = self.files.remove(&id) else {
+            return false;
+        };
+        for token in &file.tokens {
+            self.remove_posting(token, id);
+        }
+        true
+    }
+
+    fn remove_posting(&mut self, token: &str, id: FileId) {
+        if let Some(ids) = self.postings.get_mut(token) {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.postings.remove(token);
+            }
+        }
+    }
+
+    /// Get the indexed files containing `token`, sorted by path.
+    pub fn files_with_token(&self, token: &str) -> Vec<&Path> {
+        let mut paths: Vec<&Path> = self
+            .postings
+            .get(token)
+            .into_iter()
+            .flatten()
+            .map(|id| self.paths[*id as usize].as_path())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Get the name ranges of the requirements on `family`, sorted by path.
+    ///
+    /// With `include_declaration`, the `name` attributes of the packages of
+    /// the family are included too, before the requirements of their file.
+    pub fn references(&self, family: &str, include_declaration: bool) -> Vec<(&Path, Range)> {
+        self.files_with_token(family)
+            .into_iter()
+            .flat_map(|path| {
+                let file = &self.files[&self.ids[path]];
+                let declaration = file
+                    .declaration
+                    .iter()
+                    .filter(move |(name, _)| include_declaration && name == family);
+                declaration
+                    .chain(file.requirements.iter().filter(|(name, _)| name == family))
+                    .map(move |(_, range)| (path, *range))
+            })
+            .collect()
+    }
+}
+
+/// Split content into the words a family name can match.
+fn tokens(content: &str) -> HashSet<String> {
+    content
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::write_package;
+    use tower_lsp::lsp_types::Position;
+
+    #[test]
+    fn test_incremental_updates() {
+        let mut index = ReferenceIndex::new(PositionEncoding::Utf16);
+        let tool = PathBuf::from("/ws/tool/package.py");
+        index.insert(
+            tool.clone(),
+            "name = 'tool'\nrequires = ['python-3', '~maya']\n",
+        );
+        index.insert(
+            PathBuf::from("/ws/shot/package.py"),
+            "requires = ['maya_tools', 'maya-2024']\n",
+        );
+        index.insert(PathBuf::from("/ws/env.lock"), "maya-2024.1\n");
+        assert_eq!(index.len(), 3);
+
+        let references = index.references("maya", false);
+        assert_eq!(
+            references,
+            vec![
+                (
+                    Path::new("/ws/shot/package.py"),
+                    Range::new(Position::new(0, 27), Position::new(0, 31))
+                ),
+                (
+                    tool.as_path(),
+                    Range::new(Position::new(1, 26), Position::new(1, 30))
+                ),
+            ]
+        );
+        assert_eq!(index.files_with_token("maya").len(), 3);
+
+        // Edits replace the postings of the file
+        index.insert(tool.clone(), "name = 'tool'\nrequires = ['python-3']\n");
+        assert_eq!(index.references("maya", false).len(), 1);
+        assert!(index.remove(Path::new("/ws/env.lock")));
+        assert!(!index.remove(Path::new("/ws/env.lock")));
+        assert_eq!(
+            index.files_with_token("maya"),
+            vec![Path::new("/ws/shot/package.py")]
+        );
+        assert!(index.files_with_token("nuke").is_empty());
+
+        // Declarations are only included on request
+        assert!(index.references("tool", false).is_empty());
+        assert_eq!(
+            index.references("tool", true),
+            vec![(
+                tool.as_path(),
+                Range::new(Position::new(0, 8), Position::new(0, 12))
+            )]
+        );
+    }
+
+    #[test]
+    fn test_references_across_many_files() {
+        let mut index = ReferenceIndex::new(PositionEncoding::Utf16);
+        for i in 0..5000 {
+            let family = if i % 10 == 0 { "maya" } else { "nuke" };
+            index.insert(
+                PathBuf::from(format!("/ws/tool_{}/package.py", i)),
+                &format!(
+                    "name = 'tool_{}'\nrequires = ['python-3', '{}']\n",
+                    i, family
+                ),
+            );
+        }
+        assert_eq!(index.references("maya", false).len(), 500);
+        assert_eq!(index.references("python", false).len(), 5000);
+    }
+
+    #[test]
+    fn test_build_from_disk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package_py = write_package(temp_dir.path(), "tool", "", "requires = ['python']\n")
+            .join("package.py");
+        std::fs::write(temp_dir.path().join("notes.txt"), "python\n").unwrap();
+
+        let mut index = ReferenceIndex::build(
+            &[temp_dir.path().to_path_buf()],
+            &WorkspaceWalker::new(&[]),
+            |path| path.file_name().is_some_and(|name| name == "package.py"),
+            PositionEncoding::Utf16,
+        );
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.references("python", false).len(), 1);
+
+        std::fs::remove_file(&package_py).unwrap();
+        index.reload(&package_py);
+        assert_eq!(index.len(), 0);
+    }
+}
@@ -0,0 +1,442 @@
+//! Checks run before releasing a package.
+//!
+//! [`PRE_RELEASE_CHECK_COMMAND`] gathers everything that makes a
+//! `rez-release` fail late or publish a broken package into one report:
+//! validation errors, requirements that do not resolve, a version that was
+//! already released and a missing changelog entry. Release scripts and
+//! editor tasks can refuse to release unless the report is ready.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+use crate::core::{DependencyResolver, Requirement};
+use crate::parser::{find_requirements, REQUIREMENT_ATTRIBUTES};
+use crate::server::setup_wizard::CheckStatus;
+
+/// Command running the pre-release checks.
+///
+/// The argument is the URI of the package definition, which does not need to
+/// be open.
+pub const PRE_RELEASE_CHECK_COMMAND: &str = "rez.preReleaseCheck";
+
+/// Changelog file names looked up next to the package definition.
+const CHANGELOG_NAMES: &[&str] = &[
+    "CHANGELOG.md",
+    "CHANGELOG.rst",
+    "CHANGELOG.txt",
+    "CHANGELOG",
+    "CHANGES.md",
+    "HISTORY.md",
+];
+
+/// Result of a single pre-release check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseCheck {
+    /// Check id
+    pub id: String,
+    /// Short description of what is checked
+    pub title: String,
+    /// Outcome of the check
+    pub status: CheckStatus,
+    /// Details of the outcome
+    pub detail: String,
+    /// Individual problems found by the check
+    pub items: Vec<String>,
+}
+
+/// Response of the pre-release check command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseReport {
+    /// Declared package name
+    pub package: Option<String>,
+    /// Declared package version
+    pub version: Option<String>,
+    /// Whether no check failed with an error
+    pub ready: bool,
+    /// Checks in display order
+    pub checks: Vec<ReleaseCheck>,
+}
+
+impl ReleaseReport {
+    /// Build a report from the results of the checks.
+    pub fn new(
+        package: Option<String>,
+        version: Option<String>,
+        checks: Vec<ReleaseCheck>,
+    ) -> Self {
+        Self {
+            package,
+            version,
+            ready: checks
+                .iter()
+                .all(|check| check.status != CheckStatus::Error),
+            checks,
+        }
+    }
+}
+
+/// Build a check result.
+fn check(
+    id: &str,
+    title: &str,
+    status: CheckStatus,
+    detail: impl Into<String>,
+    items: Vec<String>,
+) -> ReleaseCheck {
+    ReleaseCheck {
+        id: id.to_string(),
+        title: title.to_string(),
+        status,
+        detail: detail.into(),
+        items,
+    }
+}
+
+/// Report the errors and warnings of a full validation.
+pub fn check_validation(diagnostics: &[Diagnostic]) -> ReleaseCheck {
+    let title = "Package definition is valid";
+    let describe = |diagnostic: &Diagnostic| {
+        let code = match &diagnostic.code {
+            Some(NumberOrString::String(code)) => format!("[{}] ", code),
+            Some(NumberOrString::Number(code)) => format!("[{}] ", code),
+            None => String::new(),
+        };
+        format!(
+            "line {}: {}{}",
+            diagnostic.range.start.line + 1,
+            code,
+            diagnostic.message
+        )
+    };
+    let count = |severity: DiagnosticSeverity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Some(severity))
+            .count()
+    };
+
+    let errors = count(DiagnosticSeverity::ERROR);
+    let warnings = count(DiagnosticSeverity::WARNING);
+    let status = if errors > 0 {
+        CheckStatus::Error
+    } else if warnings > 0 {
+        CheckStatus::Warning
+    } else {
+        CheckStatus::Ok
+    };
+    let items = diagnostics
+        .iter()
+        .filter(|diagnostic| {
+            matches!(
+                diagnostic.severity,
+                Some(DiagnosticSeverity::ERROR | DiagnosticSeverity::WARNING)
+            )
+        })
+        .map(describe)
+        .collect();
+    check(
+        "validation",
+        title,
+        status,
+        format!("{} error(s), {} warning(s)", errors, warnings),
+        items,
+    )
+}
+
+/// Get the requirements resolved by a release, per variant.
+///
+/// Runtime and build requirements are combined with the requirements of each
+/// variant, or resolved on their own when the package has no variants.
+pub fn release_contexts(content: &str) -> Vec<(String, Vec<Requirement>)> {
+    let mut shared = Vec::new();
+    let mut variants: BTreeMap<usize, Vec<Requirement>> = BTreeMap::new();
+    for span in find_requirements(content) {
+        let Ok(requirement) = Requirement::parse(&span.text) else {
+            continue;
+        };
+        match span.variant {
+            Some(variant) => variants.entry(variant).or_default().push(requirement),
+            None if REQUIREMENT_ATTRIBUTES.contains(&span.attribute) => shared.push(requirement),
+            None => {}
+        }
+    }
+
+    if variants.is_empty() {
+        return vec![("package".to_string(), shared)];
+    }
+    variants
+        .into_iter()
+        .map(|(index, variant)| {
+            let requirements = shared.iter().cloned().chain(variant).collect();
+            (format!("variant {}", index), requirements)
+        })
+        .collect()
+}
+
+/// Resolve the requirements of every variant, including build requirements.
+///
+/// `resolver` is `None` while the package index is unavailable.
+pub async fn check_resolution(
+    contexts: &[(String, Vec<Requirement>)],
+    resolver: Option<&dyn DependencyResolver>,
+) -> ReleaseCheck {
+    let title = "Requirements resolve";
+    let Some(resolver) = resolver else {
+        return check(
+            "resolution",
+            title,
+            CheckStatus::Warning,
+            "The package index is not available yet",
+            Vec::new(),
+        );
+    };
+
+    let mut failures = Vec::new();
+    for (label, requirements) in contexts {
+        if let Err(e) = resolver.resolve(requirements).await {
+            failures.push(format!("{}: {}", label, e));
+        }
+    }
+    if failures.is_empty() {
+        check(
+            "resolution",
+            title,
+            CheckStatus::Ok,
+            format!("{} context(s) resolve", contexts.len()),
+            failures,
+        )
+    } else {
+        check(
+            "resolution",
+            title,
+            CheckStatus::Error,
+            format!(
+                "{} of {} context(s) fail to resolve",
+                failures.len(),
+                contexts.len()
+            ),
+            failures,
+        )
+    }
+}
+
+/// Check that the version was not released to the release repository yet.
+pub fn check_released(
+    package: Option<&str>,
+    version: Option<&str>,
+    release_path: Option<&Path>,
+) -> ReleaseCheck {
+    let title = "Version is not released yet";
+    let (Some(package), Some(version)) = (package, version) else {
+        return check(
+            "versionReleased",
+            title,
+            CheckStatus::Error,
+            "The package does not declare a name and version",
+            Vec::new(),
+        );
+    };
+    let Some(release_path) = release_path else {
+        return check(
+            "versionReleased",
+            title,
+            CheckStatus::Warning,
+            "REZ_RELEASE_PACKAGES_PATH is not set; the release repository was not checked",
+            Vec::new(),
+        );
+    };
+
+    let released: PathBuf = release_path.join(package).join(version);
+    if released.is_dir() {
+        check(
+            "versionReleased",
+            title,
+            CheckStatus::Error,
+            format!("{}-{} is already released", package, version),
+            vec![released.display().to_string()],
+        )
+    } else {
+        check(
+            "versionReleased",
+            title,
+            CheckStatus::Ok,
+            format!(
+                "{}-{} is not in {}",
+                package,
+                version,
+                release_path.display()
+            ),
+            Vec::new(),
+        )
+    }
+}
+
+/// Check that a changelog next to the package definition mentions the
+/// version.
+pub fn check_changelog(directory: Option<&Path>, version: Option<&str>) -> ReleaseCheck {
+    let title = "Changelog is up to date";
+    let changelog = directory.and_then(|directory| {
+        CHANGELOG_NAMES
+            .iter()
+            .map(|name| directory.join(name))
+            .find(|path| path.is_file())
+    });
+    let Some(changelog) = changelog else {
+        return check(
+            "changelog",
+            title,
+            CheckStatus::Warning,
+            format!(
+                "No changelog found, looked for {}",
+                CHANGELOG_NAMES.join(", ")
+            ),
+            Vec::new(),
+        );
+    };
+
+    let mentions_version = version.is_some_and(|version| {
+        crate::parser::encoding::read_source(&changelog)
+            .is_ok_and(|content| content.contains(version))
+    });
+    if mentions_version {
+        check(
+            "changelog",
+            title,
+            CheckStatus::Ok,
+            changelog.display().to_string(),
+            Vec::new(),
+        )
+    } else {
+        check(
+            "changelog",
+            title,
+            CheckStatus::Warning,
+            format!(
+                "{} has no entry for version {}",
+                changelog.display(),
+                version.unwrap_or("?")
+            ),
+            Vec::new(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Package, VersionConstraint};
+    use crate::resolver::DependencyResolverImpl;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::sync::Arc;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    #[test]
+    fn test_release_contexts() {
+        let content = "requires = ['python-3']\nbuild_requires = ['cmake']\nvariants = [['maya-2024'], ['houdini-20']]\n";
+
+        let contexts = release_contexts(content);
+        assert_eq!(contexts.len(), 2);
+        assert_eq!(contexts[1].0, "variant 1");
+        let names: Vec<&str> = contexts[1].1.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["python", "cmake", "houdini"]);
+
+        let contexts = release_contexts("requires = ['python']\n");
+        assert_eq!(contexts[0].0, "package");
+        assert_eq!(contexts[0].1.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolution_check() {
+        let python = Package::builder()
+            .name("python")
+            .version("3.9.0")
+            .path("/packages/python/3.9.0")
+            .build();
+        let mut resolver = DependencyResolverImpl::new();
+        resolver.set_packages(HashMap::from([(
+            "python".to_string(),
+            vec![Arc::new(python)],
+        )]));
+
+        let contexts = vec![
+            (
+                "variant 0".to_string(),
+                vec![Requirement::new("python", VersionConstraint::Any)],
+            ),
+            (
+                "variant 1".to_string(),
+                vec![Requirement::new("cmake", VersionConstraint::Any)],
+            ),
+        ];
+        let result = check_resolution(&contexts, Some(&resolver)).await;
+        assert_eq!(result.status, CheckStatus::Error);
+        assert_eq!(result.items.len(), 1);
+        assert!(result.items[0].starts_with("variant 1: "));
+
+        let result = check_resolution(&contexts, None).await;
+        assert_eq!(result.status, CheckStatus::Warning);
+    }
+
+    #[test]
+    fn test_release_and_changelog_checks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let release = temp_dir.path().join("release");
+        fs::create_dir_all(release.join("tools").join("1.0.0")).unwrap();
+
+        let released = check_released(Some("tools"), Some("1.0.0"), Some(&release));
+        assert_eq!(released.status, CheckStatus::Error);
+        let next = check_released(Some("tools"), Some("1.1.0"), Some(&release));
+        assert_eq!(next.status, CheckStatus::Ok);
+        assert_eq!(
+            check_released(Some("tools"), None, Some(&release)).status,
+            CheckStatus::Error
+        );
+        assert_eq!(
+            check_released(Some("tools"), Some("1.1.0"), None).status,
+            CheckStatus::Warning
+        );
+
+        let source = temp_dir.path().join("source");
+        fs::create_dir_all(&source).unwrap();
+        assert_eq!(
+            check_changelog(Some(&source), Some("1.1.0")).status,
+            CheckStatus::Warning
+        );
+        fs::write(source.join("CHANGELOG.md"), "## 1.1.0\n- Fix\n").unwrap();
+        assert_eq!(
+            check_changelog(Some(&source), Some("1.1.0")).status,
+            CheckStatus::Ok
+        );
+        assert_eq!(
+            check_changelog(Some(&source), Some("1.2.0")).status,
+            CheckStatus::Warning
+        );
+    }
+
+    #[test]
+    fn test_report_readiness() {
+        let warning = Diagnostic {
+            range: Range::new(Position::new(2, 0), Position::new(2, 4)),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String("R105".to_string())),
+            message: "Duplicate requirement: 'python'".to_string(),
+            ..Default::default()
+        };
+        let validation = check_validation(&[warning]);
+        assert_eq!(validation.status, CheckStatus::Warning);
+        assert_eq!(
+            validation.items,
+            vec!["line 3: [R105] Duplicate requirement: 'python'"]
+        );
+
+        let report = ReleaseReport::new(None, None, vec![validation]);
+        assert!(report.ready);
+        let report = ReleaseReport::new(None, None, vec![check_released(None, None, None)]);
+        assert!(!report.ready);
+    }
+}
@@ -0,0 +1,244 @@
+//! Document state persisted across server restarts.
+//!
+//! When the editor restarts the server, e.g. after a crash or an update, the
+//! documents it reopens are validated before the package index is scanned
+//! again, so diagnostics needing the index are missing until the document is
+//! edited. The server saves the open documents and their last published
+//! diagnostics to the user cache directory; a document reopened unchanged
+//! gets its saved diagnostics back until the index is ready.
+//!
+//! Sessions are keyed by workspace root, so editor windows on different
+//! workspaces do not share one.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tower_lsp::lsp_types::{Diagnostic, Url};
+
+/// Diagnostics of an open document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDocument {
+    /// URI of the document
+    pub uri: Url,
+    /// Hash of the content the diagnostics were computed for
+    pub content_hash: u64,
+    /// Last published diagnostics
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// State of a server session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionState {
+    /// Generation of the shared package index the diagnostics were computed
+    /// with, if the index is shared
+    pub index_generation: Option<u64>,
+    /// Save time in seconds since the Unix epoch
+    pub saved_at: u64,
+    /// Open package definitions
+    pub documents: Vec<SessionDocument>,
+}
+
+impl SessionState {
+    /// Get the saved diagnostics of a document, if its content is unchanged.
+    pub fn diagnostics(&self, uri: &Url, content: &str) -> Option<&[Diagnostic]> {
+        let hash = content_hash(content);
+        self.documents
+            .iter()
+            .find(|document| &document.uri == uri && document.content_hash == hash)
+            .map(|document| document.diagnostics.as_slice())
+    }
+}
+
+/// Location of the saved session of a workspace.
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    path: PathBuf,
+}
+
+impl SessionStore {
+    /// Get the store of the session of a workspace root in `dir`.
+    pub fn new(dir: &Path, root: &Url) -> Self {
+        let hash = content_hash(root.as_str());
+        Self {
+            path: dir.join(format!("session-{:016x}.json", hash)),
+        }
+    }
+
+    /// Get the store of the session of a workspace root in the user cache
+    /// directory.
+    pub fn for_workspace(root: &Url) -> Option<Self> {
+        let dir = dirs::cache_dir()?.join("rez-lsp").join("sessions");
+        Some(Self::new(&dir, root))
+    }
+
+    /// Get the path of the saved session.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the saved session, if any.
+    pub fn load(&self) -> Option<SessionState> {
+        let content = fs::read(&self.path).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    /// Save the session, stamping it with the current time.
+    ///
+    /// The session is written to a temporary file and renamed into place, so
+    /// a crash while saving leaves the previous session intact.
+    pub fn save(&self, state: &SessionState) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let state = SessionState {
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            ..state.clone()
+        };
+        let content = serde_json::to_vec(&state).map_err(io::Error::other)?;
+
+        let temp_path = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &self.path)
+    }
+}
+
+/// Writer of the sessions of a [`SessionStore`] in the background.
+///
+/// Documents change on every keystroke; the server schedules the latest
+/// state and [`SessionSaver::run`] writes it at most once per interval, off
+/// the async runtime. Shutdown flushes the last state.
+#[derive(Debug)]
+pub struct SessionSaver {
+    store: SessionStore,
+    pending: Mutex<Option<SessionState>>,
+}
+
+impl SessionSaver {
+    /// Create a saver writing to `store`.
+    pub fn new(store: SessionStore) -> Self {
+        Self {
+            store,
+            pending: Mutex::new(None),
+        }
+    }
+
+    /// Get the store sessions are written to.
+    pub fn store(&self) -> &SessionStore {
+        &self.store
+    }
+
+    /// Schedule a state to be written, replacing any state not written yet.
+    pub fn schedule(&self, state: SessionState) {
+        *self.pending.lock().unwrap_or_else(|e| e.into_inner()) = Some(state);
+    }
+
+    /// Write the scheduled state, if any.
+    pub async fn flush(&self) {
+        let Some(state) = self
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+        else {
+            return;
+        };
+        let store = self.store.clone();
+        let result = tokio::task::spawn_blocking(move || store.save(&state))
+            .await
+            .unwrap_or_else(|e| Err(io::Error::other(e)));
+        if let Err(e) = result {
+            tracing::debug!(
+                "Failed to save session to {}: {}",
+                self.store.path().display(),
+                e
+            );
+        }
+    }
+
+    /// Write the scheduled states every `interval` until cancelled.
+    pub async fn run(self: Arc<Self>, interval: Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            self.flush().await;
+        }
+    }
+}
+
+/// Hash document content with FNV-1a, which is stable across builds unlike
+/// the standard hasher.
+pub fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_restore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = Url::parse("file:///studio/tools").unwrap();
+        let store = SessionStore::new(temp_dir.path(), &root);
+        assert!(store.load().is_none());
+
+        let uri = Url::parse("file:///studio/tools/package.py").unwrap();
+        let diagnostic = Diagnostic {
+            message: "Duplicate requirement: 'maya'".to_string(),
+            ..Default::default()
+        };
+        let state = SessionState {
+            index_generation: Some(3),
+            documents: vec![SessionDocument {
+                uri: uri.clone(),
+                content_hash: content_hash("name = 'tools'\n"),
+                diagnostics: vec![diagnostic.clone()],
+            }],
+            ..Default::default()
+        };
+        store.save(&state).unwrap();
+
+        let restored = store.load().unwrap();
+        assert_eq!(restored.index_generation, Some(3));
+        assert!(restored.saved_at > 0);
+        assert_eq!(
+            restored.diagnostics(&uri, "name = 'tools'\n"),
+            Some(&[diagnostic][..])
+        );
+        // Edited documents are validated again instead
+        assert_eq!(restored.diagnostics(&uri, "name = 'tool'\n"), None);
+
+        // Other workspaces have their own session
+        let other = Url::parse("file:///studio/other").unwrap();
+        assert!(SessionStore::new(temp_dir.path(), &other).load().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_saver_writes_the_latest_state() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = Url::parse("file:///studio/tools").unwrap();
+        let saver = SessionSaver::new(SessionStore::new(temp_dir.path(), &root));
+
+        for generation in 1..=3 {
+            saver.schedule(SessionState {
+                index_generation: Some(generation),
+                ..Default::default()
+            });
+        }
+        assert!(saver.store().load().is_none());
+        saver.flush().await;
+        assert_eq!(saver.store().load().unwrap().index_generation, Some(3));
+    }
+}
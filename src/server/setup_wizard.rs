@@ -0,0 +1,442 @@
+//! First-run setup checks.
+//!
+//! [`SETUP_WIZARD_COMMAND`] reports the problems that keep the server from
+//! finding packages, each with a suggested fix the client can render as a
+//! checklist. Fixes that only create missing files or directories are applied
+//! by the server on request.
+
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+/// Command running the setup checks.
+///
+/// Arguments are optional ids of automated fixes to apply before checking,
+/// e.g. `["createLocalPackages", "writeRezconfig"]`.
+pub const SETUP_WIZARD_COMMAND: &str = "rez.setupWizard";
+
+/// Fix creating the `~/packages` directory.
+pub const CREATE_LOCAL_PACKAGES_FIX: &str = "createLocalPackages";
+
+/// Fix writing a starter `~/.rezconfig.py`.
+pub const WRITE_REZCONFIG_FIX: &str = "writeRezconfig";
+
+/// Outcome of a setup check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    /// Nothing to do
+    Ok,
+    /// The server works but misses packages or features
+    Warning,
+    /// The server cannot find packages
+    Error,
+}
+
+/// Suggested fix of a failed check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupFix {
+    /// Fix id, passed back to the command to apply automated fixes
+    pub id: String,
+    /// What the fix does
+    pub title: String,
+    /// Whether the server can apply the fix itself
+    pub automated: bool,
+}
+
+/// Result of a single setup check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupCheck {
+    /// Check id
+    pub id: String,
+    /// Short description of what is checked
+    pub title: String,
+    /// Outcome of the check
+    pub status: CheckStatus,
+    /// Details of the outcome
+    pub detail: String,
+    /// Suggested fix when the check did not pass
+    pub fix: Option<SetupFix>,
+}
+
+/// Result of applying an automated fix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedFix {
+    /// Fix id
+    pub id: String,
+    /// Whether the fix was applied
+    pub success: bool,
+    /// What was done, or why it failed
+    pub message: String,
+}
+
+/// Response of the setup wizard command.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupReport {
+    /// Checks in display order
+    pub checks: Vec<SetupCheck>,
+    /// Fixes applied before checking
+    pub applied: Vec<AppliedFix>,
+}
+
+/// State of the environment inspected by the setup checks.
+#[derive(Debug, Clone)]
+pub struct SetupEnvironment {
+    /// Value of `REZ_PACKAGES_PATH`, if set
+    pub packages_path_var: Option<String>,
+    /// Active server configuration
+    pub config: Config,
+    /// Home directory of the user
+    pub home: Option<PathBuf>,
+    /// Value of `REZ_CONFIG_FILE`, if set
+    pub rez_config_file: Option<PathBuf>,
+    /// Located `rez` binary
+    pub rez_binary: Option<PathBuf>,
+    /// Number of indexed package families, once the index is available
+    pub package_families: Option<usize>,
+}
+
+impl SetupEnvironment {
+    /// Inspect the process environment of the server.
+    pub fn detect(config: Config, package_families: Option<usize>) -> Self {
+        Self {
+            packages_path_var: std::env::var("REZ_PACKAGES_PATH").ok(),
+            home: dirs::home_dir(),
+            rez_config_file: std::env::var_os("REZ_CONFIG_FILE").map(PathBuf::from),
+            rez_binary: find_in_path("rez"),
+            config,
+            package_families,
+        }
+    }
+
+    /// Get the `~/packages` directory.
+    fn local_packages(&self) -> Option<PathBuf> {
+        self.home.as_ref().map(|home| home.join("packages"))
+    }
+
+    /// Get the user rezconfig files rez reads.
+    fn rezconfig_candidates(&self) -> Vec<PathBuf> {
+        let mut candidates: Vec<PathBuf> = self.rez_config_file.iter().cloned().collect();
+        if let Some(home) = &self.home {
+            candidates.push(home.join(".rezconfig"));
+            candidates.push(home.join(".rezconfig.py"));
+        }
+        candidates
+    }
+}
+
+/// Run every setup check.
+pub fn run_checks(environment: &SetupEnvironment) -> Vec<SetupCheck> {
+    vec![
+        check_packages_path_var(environment),
+        check_packages_paths_exist(environment),
+        check_packages_found(environment),
+        check_rez_binary(environment),
+        check_rezconfig(environment),
+    ]
+}
+
+/// Apply an automated fix.
+pub fn apply_fix(id: &str, environment: &SetupEnvironment) -> AppliedFix {
+    let result = match id {
+        CREATE_LOCAL_PACKAGES_FIX => create_local_packages(environment),
+        WRITE_REZCONFIG_FIX => write_rezconfig(environment),
+        _ => Err(format!("Unknown fix: {}", id)),
+    };
+
+    let (success, message) = match result {
+        Ok(message) => (true, message),
+        Err(message) => (false, message),
+    };
+    AppliedFix {
+        id: id.to_string(),
+        success,
+        message,
+    }
+}
+
+/// Build a check result.
+fn check(
+    id: &str,
+    title: &str,
+    status: CheckStatus,
+    detail: impl Into<String>,
+    fix: Option<SetupFix>,
+) -> SetupCheck {
+    SetupCheck {
+        id: id.to_string(),
+        title: title.to_string(),
+        status,
+        detail: detail.into(),
+        fix: (status != CheckStatus::Ok).then_some(fix).flatten(),
+    }
+}
+
+/// Build a suggested fix.
+fn fix(id: &str, title: impl Into<String>, automated: bool) -> Option<SetupFix> {
+    Some(SetupFix {
+        id: id.to_string(),
+        title: title.into(),
+        automated,
+    })
+}
+
+fn check_packages_path_var(environment: &SetupEnvironment) -> SetupCheck {
+    let title = "REZ_PACKAGES_PATH is set";
+    match environment.packages_path_var.as_deref().map(str::trim) {
+        Some(value) if !value.is_empty() => {
+            check("packagesPathSet", title, CheckStatus::Ok, value, None)
+        }
+        _ => check(
+            "packagesPathSet",
+            title,
+            CheckStatus::Warning,
+            "REZ_PACKAGES_PATH is not set; default package locations are searched",
+            fix(
+                "setPackagesPath",
+                "Set REZ_PACKAGES_PATH to your package repositories and restart the server",
+                false,
+            ),
+        ),
+    }
+}
+
+fn check_packages_paths_exist(environment: &SetupEnvironment) -> SetupCheck {
+    let title = "Package repositories exist";
+    let paths = environment.config.get_all_package_paths();
+    let missing: Vec<&PathBuf> = paths.iter().filter(|path| !path.is_dir()).collect();
+
+    if paths.is_empty() {
+        return check(
+            "packagesPathsExist",
+            title,
+            CheckStatus::Error,
+            "No package repositories are configured",
+            fix(
+                "setPackagesPath",
+                "Set REZ_PACKAGES_PATH to your package repositories",
+                false,
+            ),
+        );
+    }
+    if missing.is_empty() {
+        return check(
+            "packagesPathsExist",
+            title,
+            CheckStatus::Ok,
+            format!("{} repositories found", paths.len()),
+            None,
+        );
+    }
+
+    let status = if missing.len() == paths.len() {
+        CheckStatus::Error
+    } else {
+        CheckStatus::Warning
+    };
+    let detail = format!(
+        "Missing: {}",
+        missing
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let local_packages = environment.local_packages();
+    let suggested = match local_packages {
+        Some(local) if missing.contains(&&local) => fix(
+            CREATE_LOCAL_PACKAGES_FIX,
+            format!("Create {}", local.display()),
+            true,
+        ),
+        _ => fix(
+            "setPackagesPath",
+            "Point REZ_PACKAGES_PATH at existing repositories",
+            false,
+        ),
+    };
+    check("packagesPathsExist", title, status, detail, suggested)
+}
+
+fn check_packages_found(environment: &SetupEnvironment) -> SetupCheck {
+    let title = "Packages are indexed";
+    match environment.package_families {
+        None => check(
+            "packagesFound",
+            title,
+            CheckStatus::Warning,
+            "The package index is not available yet",
+            None,
+        ),
+        Some(0) => check(
+            "packagesFound",
+            title,
+            CheckStatus::Warning,
+            "No packages were found in the configured repositories",
+            fix(
+                "addPackages",
+                "Release or build packages into a configured repository",
+                false,
+            ),
+        ),
+        Some(families) => check(
+            "packagesFound",
+            title,
+            CheckStatus::Ok,
+            format!("{} package families indexed", families),
+            None,
+        ),
+    }
+}
+
+fn check_rez_binary(environment: &SetupEnvironment) -> SetupCheck {
+    let title = "rez is installed";
+    match &environment.rez_binary {
+        Some(path) => check(
+            "rezBinary",
+            title,
+            CheckStatus::Ok,
+            path.display().to_string(),
+            None,
+        ),
+        None => check(
+            "rezBinary",
+            title,
+            CheckStatus::Warning,
+            "The rez command was not found on PATH; resolving with rez-env and running tests is unavailable",
+            fix(
+                "installRez",
+                "Install rez and add its bin directory to PATH",
+                false,
+            ),
+        ),
+    }
+}
+
+fn check_rezconfig(environment: &SetupEnvironment) -> SetupCheck {
+    let title = "A user rezconfig exists";
+    match environment
+        .rezconfig_candidates()
+        .into_iter()
+        .find(|path| path.is_file())
+    {
+        Some(path) => check(
+            "rezconfig",
+            title,
+            CheckStatus::Ok,
+            path.display().to_string(),
+            None,
+        ),
+        None => check(
+            "rezconfig",
+            title,
+            CheckStatus::Warning,
+            "No rezconfig was found; rez uses its built-in defaults",
+            fix(
+                WRITE_REZCONFIG_FIX,
+                "Write a starter ~/.rezconfig.py using ~/packages",
+                environment.home.is_some(),
+            ),
+        ),
+    }
+}
+
+fn create_local_packages(environment: &SetupEnvironment) -> Result<String, String> {
+    let path = environment
+        .local_packages()
+        .ok_or("The home directory is unknown")?;
+    fs::create_dir_all(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    Ok(format!("Created {}", path.display()))
+}
+
+fn write_rezconfig(environment: &SetupEnvironment) -> Result<String, String> {
+    if let Some(existing) = environment
+        .rezconfig_candidates()
+        .into_iter()
+        .find(|path| path.exists())
+    {
+        return Err(format!("{} already exists", existing.display()));
+    }
+    let path = environment
+        .home
+        .as_ref()
+        .ok_or("The home directory is unknown")?
+        .join(".rezconfig.py");
+
+    let content = "# Starter rez configuration written by rez-lsp-server.\n\
+                   packages_path = [\"~/packages\"]\n\
+                   local_packages_path = \"~/packages\"\n";
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(format!("Wrote {}", path.display()))
+}
+
+/// Find an executable on `PATH`.
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    let names: Vec<String> = if cfg!(windows) {
+        vec![format!("{}.exe", program), format!("{}.bat", program)]
+    } else {
+        vec![program.to_string()]
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn environment(home: &Path) -> SetupEnvironment {
+        let mut config = Config::new();
+        config.packages_path = vec![home.join("packages")];
+        SetupEnvironment {
+            packages_path_var: None,
+            config,
+            home: Some(home.to_path_buf()),
+            rez_config_file: None,
+            rez_binary: None,
+            package_families: Some(0),
+        }
+    }
+
+    fn status(checks: &[SetupCheck], id: &str) -> CheckStatus {
+        checks.iter().find(|check| check.id == id).unwrap().status
+    }
+
+    #[test]
+    fn test_setup_checks_and_fixes() {
+        let home = tempfile::tempdir().unwrap();
+        let environment = environment(home.path());
+
+        let checks = run_checks(&environment);
+        assert_eq!(status(&checks, "packagesPathSet"), CheckStatus::Warning);
+        assert_eq!(status(&checks, "packagesPathsExist"), CheckStatus::Error);
+        assert_eq!(status(&checks, "packagesFound"), CheckStatus::Warning);
+        assert_eq!(status(&checks, "rezBinary"), CheckStatus::Warning);
+        let paths_fix = checks[1].fix.as_ref().unwrap();
+        assert_eq!(paths_fix.id, CREATE_LOCAL_PACKAGES_FIX);
+        assert!(paths_fix.automated);
+
+        for id in [CREATE_LOCAL_PACKAGES_FIX, WRITE_REZCONFIG_FIX] {
+            assert!(apply_fix(id, &environment).success);
+        }
+        assert!(home.path().join("packages").is_dir());
+        assert!(home.path().join(".rezconfig.py").is_file());
+
+        let checks = run_checks(&environment);
+        assert_eq!(status(&checks, "packagesPathsExist"), CheckStatus::Ok);
+        assert_eq!(status(&checks, "rezconfig"), CheckStatus::Ok);
+        assert!(checks[1].fix.is_none());
+
+        // Existing configuration is never overwritten
+        assert!(!apply_fix(WRITE_REZCONFIG_FIX, &environment).success);
+        assert!(!apply_fix("formatDisk", &environment).success);
+    }
+}
@@ -0,0 +1,212 @@
+//! Background tasks of the server.
+//!
+//! Every task the server spawns goes through a [`TaskRegistry`], so shutdown
+//! can cancel them and wait for them to finish instead of leaving them to
+//! run, and possibly panic, while the runtime is dropped.
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Signal telling tasks to stop.
+///
+/// Clones observe the same cancellation.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    receiver: watch::Receiver<bool>,
+}
+
+impl CancellationToken {
+    /// Check whether cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Wait until cancellation is requested.
+    pub async fn cancelled(&self) {
+        let mut receiver = self.receiver.clone();
+        // The registry owning the sender is gone, which cancels as well
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+/// Outcome of shutting down the tasks of a registry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Tasks that stopped within the grace period
+    pub stopped: usize,
+    /// Names of the tasks that had to be aborted
+    pub aborted: Vec<&'static str>,
+}
+
+impl ShutdownReport {
+    /// Check whether every task stopped within the grace period.
+    pub fn is_clean(&self) -> bool {
+        self.aborted.is_empty()
+    }
+}
+
+/// Registry of the background tasks of the server.
+#[derive(Debug)]
+pub struct TaskRegistry {
+    cancel: watch::Sender<bool>,
+    tasks: Mutex<Vec<(&'static str, JoinHandle<()>)>>,
+}
+
+impl TaskRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self {
+            cancel: watch::Sender::new(false),
+            tasks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Get a token cancelled when the registry shuts down.
+    pub fn token(&self) -> CancellationToken {
+        CancellationToken {
+            receiver: self.cancel.subscribe(),
+        }
+    }
+
+    /// Check whether the registry is shutting down.
+    pub fn is_shutting_down(&self) -> bool {
+        *self.cancel.borrow()
+    }
+
+    /// Spawn a task that is dropped at its next await point on shutdown.
+    ///
+    /// Nothing is spawned once the registry is shutting down.
+    pub fn spawn<F>(&self, name: &'static str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let token = self.token();
+        self.track(name, async move {
+            tokio::select! {
+                _ = token.cancelled() => tracing::debug!("Cancelled task '{}'", name),
+                _ = task => {}
+            }
+        });
+    }
+
+    /// Spawn a task that watches [`CancellationToken`]s itself, e.g. to
+    /// finish writing a file before stopping.
+    pub fn spawn_cooperative<F>(&self, name: &'static str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.track(name, task);
+    }
+
+    fn track<F>(&self, name: &'static str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        if self.is_shutting_down() {
+            tracing::debug!("Not spawning task '{}' during shutdown", name);
+            return;
+        }
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        tasks.retain(|(_, handle)| !handle.is_finished());
+        tasks.push((name, tokio::spawn(task)));
+    }
+
+    /// Get the number of tasks still running.
+    pub fn active(&self) -> usize {
+        let tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        tasks
+            .iter()
+            .filter(|(_, handle)| !handle.is_finished())
+            .count()
+    }
+
+    /// Cancel every task and wait up to `grace` for them to stop.
+    ///
+    /// Tasks still running after the grace period are aborted.
+    pub async fn shutdown(&self, grace: Duration) -> ShutdownReport {
+        self.cancel.send_replace(true);
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(|e| e.into_inner()));
+
+        let deadline = tokio::time::Instant::now() + grace;
+        let mut report = ShutdownReport::default();
+        for (name, mut handle) in tasks {
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(Err(e)) if e.is_panic() => {
+                    warn!("Task '{}' panicked: {}", name, e);
+                    report.stopped += 1;
+                }
+                Ok(_) => report.stopped += 1,
+                Err(_) => {
+                    warn!("Task '{}' did not stop within {:?}", name, grace);
+                    handle.abort();
+                    report.aborted.push(name);
+                }
+            }
+        }
+        report
+    }
+}
+
+impl Default for TaskRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_shutdown_cancels_tasks_under_load() {
+        let registry = TaskRegistry::new();
+        let ticks = Arc::new(AtomicUsize::new(0));
+        for _ in 0..100 {
+            let ticks = ticks.clone();
+            registry.spawn("ticker", async move {
+                loop {
+                    ticks.fetch_add(1, Ordering::Relaxed);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+            });
+        }
+        let token = registry.token();
+        registry.spawn_cooperative("cooperative", async move {
+            token.cancelled().await;
+        });
+        registry.spawn("finished", async {});
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(registry.active() >= 101);
+
+        let report = registry.shutdown(Duration::from_secs(5)).await;
+        assert!(report.is_clean());
+        assert_eq!(report.stopped, 102);
+        assert_eq!(registry.active(), 0);
+
+        // Cancelled tasks no longer run and new ones are not started
+        let after = ticks.load(Ordering::Relaxed);
+        registry.spawn("late", async {});
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(ticks.load(Ordering::Relaxed), after);
+        assert_eq!(registry.active(), 0);
+        assert!(registry.token().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_aborts_stuck_tasks() {
+        let registry = TaskRegistry::new();
+        registry.spawn_cooperative("stuck", async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+
+        let report = registry.shutdown(Duration::from_millis(10)).await;
+        assert_eq!(report.aborted, vec!["stuck"]);
+        assert!(!report.is_clean());
+    }
+}
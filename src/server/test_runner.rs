@@ -0,0 +1,274 @@
+//! Running the tests declared in a package's `tests` attribute.
+//!
+//! Each test gets a "Run test" code lens executing [`RUN_TEST_COMMAND`].
+//! The configured runner (`rez-test` by default) is spawned in the package
+//! directory and its output is forwarded line by line.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::{mpsc, RwLock};
+use tower_lsp::lsp_types::{CodeLens, Command as LspCommand, Url};
+
+pub use crate::parser::string_attribute;
+use crate::parser::{find_tests, SourceRange};
+use crate::server::positions::{LineIndex, PositionEncoding};
+use crate::server::trust::TRUST_WORKSPACE_COMMAND;
+
+/// Command executed by the "Run test" code lenses.
+pub const RUN_TEST_COMMAND: &str = "rez.runTest";
+
+/// Outcome of the last run of a test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestStatus {
+    /// The test is currently running
+    Running,
+    /// The runner exited successfully
+    Passed,
+    /// The runner failed or could not be started
+    Failed,
+}
+
+/// Runs package tests and caches their status per document.
+#[derive(Debug, Default)]
+pub struct TestRunner {
+    results: RwLock<HashMap<Url, HashMap<String, TestStatus>>>,
+}
+
+impl TestRunner {
+    /// Create a runner without cached results.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the cached status of every test of a document.
+    pub async fn statuses(&self, uri: &Url) -> HashMap<String, TestStatus> {
+        self.results
+            .read()
+            .await
+            .get(uri)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Drop the cached results of a document.
+    pub async fn forget(&self, uri: &Url) {
+        self.results.write().await.remove(uri);
+    }
+
+    /// Mark a test as running.
+    pub async fn start(&self, uri: &Url, test: &str) {
+        self.set_status(uri, test, TestStatus::Running).await;
+    }
+
+    /// Run `<runner> <package> <test>` in `dir`, sending each line of output
+    /// to `output` and caching the outcome.
+    pub async fn run(
+        &self,
+        runner: &str,
+        dir: &Path,
+        uri: &Url,
+        package: &str,
+        test: &str,
+        output: mpsc::UnboundedSender<String>,
+    ) -> TestStatus {
+        self.start(uri, test).await;
+
+        let status = match Command::new(runner)
+            .arg(package)
+            .arg(test)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(mut child) => {
+                let stdout = child
+                    .stdout
+                    .take()
+                    .map(|s| forward_lines(s, output.clone()));
+                let stderr = child
+                    .stderr
+                    .take()
+                    .map(|s| forward_lines(s, output.clone()));
+                let exit = child.wait().await;
+                for task in [stdout, stderr].into_iter().flatten() {
+                    let _ = task.await;
+                }
+
+                match exit {
+                    Ok(exit) if exit.success() => TestStatus::Passed,
+                    Ok(exit) => {
+                        let _ = output.send(format!("{} exited with {}", runner, exit));
+                        TestStatus::Failed
+                    }
+                    Err(e) => {
+                        let _ = output.send(format!("Failed to wait for {}: {}", runner, e));
+                        TestStatus::Failed
+                    }
+                }
+            }
+            Err(e) => {
+                let _ = output.send(format!("Failed to start {}: {}", runner, e));
+                TestStatus::Failed
+            }
+        };
+
+        self.set_status(uri, test, status).await;
+        status
+    }
+
+    async fn set_status(&self, uri: &Url, test: &str, status: TestStatus) {
+        self.results
+            .write()
+            .await
+            .entry(uri.clone())
+            .or_default()
+            .insert(test.to_string(), status);
+    }
+}
+
+/// Forward each line read from `stream` to `output`.
+fn forward_lines(
+    stream: impl AsyncRead + Unpin + Send + 'static,
+    output: mpsc::UnboundedSender<String>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if output.send(line).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Build a "Run test" code lens above each entry of the `tests` attribute.
+pub fn test_code_lenses(
+    uri: &Url,
+    content: &str,
+    statuses: &HashMap<String, TestStatus>,
+    trusted: bool,
+    encoding: PositionEncoding,
+) -> Vec<CodeLens> {
+    let index = LineIndex::new(content, encoding);
+    find_tests(content)
+        .into_iter()
+        .map(|test| {
+            let title = match statuses.get(&test.name) {
+                None => format!("Run test '{}'", test.name),
+                Some(TestStatus::Running) => format!("Running test '{}'...", test.name),
+                Some(TestStatus::Passed) => format!("Run test '{}' (passed)", test.name),
+                Some(TestStatus::Failed) => format!("Run test '{}' (failed)", test.name),
+            };
+            // Span the quoted test name
+            let end = test.column + test.name.chars().count() as u32 + 2;
+
+            // In restricted mode the lens offers to trust the workspace
+            let command = if trusted {
+                LspCommand {
+                    title,
+                    command: RUN_TEST_COMMAND.to_string(),
+                    arguments: Some(vec![serde_json::json!(uri), serde_json::json!(test.name)]),
+                }
+            } else {
+                LspCommand {
+                    title: format!("Trust workspace to run test '{}'", test.name),
+                    command: TRUST_WORKSPACE_COMMAND.to_string(),
+                    arguments: None,
+                }
+            };
+
+            CodeLens {
+                range: index.range(SourceRange {
+                    line: test.line,
+                    start: test.column,
+                    end,
+                }),
+                command: Some(command),
+                data: None,
+            }
+        })
+        .collect()
+}
+
+/// Get the package name declared by package.py content.
+pub fn package_name(content: &str) -> Option<String> {
+    string_attribute(content, "name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Position;
+
+    fn uri() -> Url {
+        Url::parse("file:///packages/my_tool/package.py").unwrap()
+    }
+
+    #[test]
+    fn test_code_lenses_show_status() {
+        let content = "name = 'my_tool'\ntests = {\n    \"unit\": \"pytest\",\n    \"lint\": \"pylint\",\n}\n";
+        let statuses = HashMap::from([("unit".to_string(), TestStatus::Failed)]);
+
+        let lenses = test_code_lenses(&uri(), content, &statuses, true, PositionEncoding::Utf16);
+        assert_eq!(lenses.len(), 2);
+        assert_eq!(lenses[0].range.start, Position::new(2, 4));
+        assert_eq!(lenses[0].range.end, Position::new(2, 10));
+
+        let command = lenses[0].command.as_ref().unwrap();
+        assert_eq!(command.title, "Run test 'unit' (failed)");
+        assert_eq!(command.command, RUN_TEST_COMMAND);
+        assert_eq!(
+            command.arguments.as_ref().unwrap()[1],
+            serde_json::json!("unit")
+        );
+        assert_eq!(lenses[1].command.as_ref().unwrap().title, "Run test 'lint'");
+
+        // Restricted mode offers to trust the workspace instead
+        let lenses = test_code_lenses(&uri(), content, &statuses, false, PositionEncoding::Utf16);
+        let command = lenses[0].command.as_ref().unwrap();
+        assert_eq!(command.title, "Trust workspace to run test 'unit'");
+        assert_eq!(command.command, TRUST_WORKSPACE_COMMAND);
+    }
+
+    #[test]
+    fn test_package_name() {
+        assert_eq!(
+            package_name("name = 'my_tool'\nversion = \"1.0\"").as_deref(),
+            Some("my_tool")
+        );
+        assert_eq!(package_name("version = \"1.0\""), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_streams_output_and_caches_status() {
+        let runner = TestRunner::new();
+        let dir = std::env::temp_dir();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let status = runner
+            .run("echo", &dir, &uri(), "my_tool", "unit", tx)
+            .await;
+        assert_eq!(status, TestStatus::Passed);
+        assert_eq!(rx.recv().await.as_deref(), Some("my_tool unit"));
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let status = runner
+            .run("false", &dir, &uri(), "my_tool", "lint", tx)
+            .await;
+        assert_eq!(status, TestStatus::Failed);
+
+        let statuses = runner.statuses(&uri()).await;
+        assert_eq!(statuses["unit"], TestStatus::Passed);
+        assert_eq!(statuses["lint"], TestStatus::Failed);
+
+        runner.forget(&uri()).await;
+        assert!(runner.statuses(&uri()).await.is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! Workspace trust.
+//!
+//! Like the restricted mode of editors, features that execute or evaluate
+//! package content are disabled until the workspace is trusted: sandboxed
+//! evaluation of package.py files, resolving with `rez-env` and running
+//! package tests. Trust comes
+//! from [`Config::trust_workspace`] or the [`TRUST_WORKSPACE_COMMAND`], and
+//! is never withdrawn while the server runs.
+//!
+//! [`Config::trust_workspace`]: crate::config::Config::trust_workspace
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Range};
+
+use crate::config::{Config, PackageEvaluation, ResolverBackend};
+use crate::parser::{find_tests, SourceRange};
+use crate::server::positions::LineIndex;
+
+/// Command trusting the workspace for the rest of the session.
+pub const TRUST_WORKSPACE_COMMAND: &str = "rez.trustWorkspace";
+
+/// Diagnostic code of package definitions with features disabled until the
+/// workspace is trusted.
+pub const RESTRICTED_MODE_CODE: &str = "R117";
+
+/// Whether the workspace is trusted, shared by the server components.
+///
+/// Clones observe the same trust.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceTrust {
+    trusted: Arc<AtomicBool>,
+}
+
+impl WorkspaceTrust {
+    /// Create a trust that starts `trusted` or restricted.
+    pub fn new(trusted: bool) -> Self {
+        Self {
+            trusted: Arc::new(AtomicBool::new(trusted)),
+        }
+    }
+
+    /// Check whether the workspace is trusted.
+    pub fn is_trusted(&self) -> bool {
+        self.trusted.load(Ordering::Relaxed)
+    }
+
+    /// Trust the workspace, returning whether it was restricted before.
+    pub fn trust(&self) -> bool {
+        !self.trusted.swap(true, Ordering::Relaxed)
+    }
+
+    /// Get the configuration of the package index under this trust.
+    ///
+    /// While the workspace is restricted, package definitions are parsed
+    /// statically and resolved by the native resolver, as `rez-env` runs
+    /// their code as well.
+    pub fn restrict(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+        if !self.is_trusted() {
+            config.package_evaluation = PackageEvaluation::Static;
+            config.resolver_backend = ResolverBackend::Native;
+        }
+        config
+    }
+}
+
+/// Report the features of a package definition disabled in restricted mode,
+/// if any.
+///
+/// The diagnostic spans the first declared test, or the start of the file
+/// when only evaluation is disabled.
+pub fn restricted_mode_diagnostic(
+    content: &str,
+    index: &LineIndex,
+    evaluation: PackageEvaluation,
+) -> Option<Diagnostic> {
+    let tests = find_tests(content);
+    let mut disabled = Vec::new();
+    if !tests.is_empty() {
+        disabled.push("running its tests");
+    }
+    if evaluation == PackageEvaluation::Sandbox {
+        disabled.push("evaluating package definitions");
+    }
+    if disabled.is_empty() {
+        return None;
+    }
+
+    let range = tests.first().map_or(Range::default(), |test| {
+        index.range(SourceRange {
+            line: test.line,
+            start: test.column,
+            end: test.column + test.name.chars().count() as u32 + 2,
+        })
+    });
+    Some(Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::INFORMATION),
+        code: Some(NumberOrString::String(RESTRICTED_MODE_CODE.to_string())),
+        source: Some("rez-lsp".to_string()),
+        message: format!(
+            "Restricted mode: {} is disabled until the workspace is trusted (run {})",
+            disabled.join(" and "),
+            TRUST_WORKSPACE_COMMAND
+        ),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::positions::PositionEncoding;
+    use tower_lsp::lsp_types::Position;
+
+    #[test]
+    fn test_trust_is_shared() {
+        let trust = WorkspaceTrust::new(false);
+        let shared = trust.clone();
+        assert!(!shared.is_trusted());
+        assert!(trust.trust());
+        assert!(shared.is_trusted());
+        assert!(!shared.trust());
+    }
+
+    #[test]
+    fn test_restricted_config() {
+        let mut config = Config::new();
+        config.package_evaluation = PackageEvaluation::Sandbox;
+        config.resolver_backend = ResolverBackend::RezBinary;
+
+        let trust = WorkspaceTrust::new(false);
+        let restricted = trust.restrict(&config);
+        assert_eq!(restricted.package_evaluation, PackageEvaluation::Static);
+        assert_eq!(restricted.resolver_backend, ResolverBackend::Native);
+
+        trust.trust();
+        let trusted = trust.restrict(&config);
+        assert_eq!(trusted.package_evaluation, PackageEvaluation::Sandbox);
+        assert_eq!(trusted.resolver_backend, ResolverBackend::RezBinary);
+    }
+
+    #[test]
+    fn test_restricted_mode_diagnostic() {
+        let content = "name = 'tool'\ntests = {\n    'unit': 'pytest',\n}\n";
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+        let diagnostic =
+            restricted_mode_diagnostic(content, &index, PackageEvaluation::Sandbox).unwrap();
+        assert_eq!(diagnostic.range.start, Position::new(2, 4));
+        assert_eq!(
+            diagnostic.message,
+            "Restricted mode: running its tests and evaluating package definitions is disabled \
+             until the workspace is trusted (run rez.trustWorkspace)"
+        );
+
+        let content = "name = 'tool'\n";
+        let index = LineIndex::new(content, PositionEncoding::Utf16);
+        assert!(restricted_mode_diagnostic(content, &index, PackageEvaluation::Static).is_none());
+        let diagnostic =
+            restricted_mode_diagnostic(content, &index, PackageEvaluation::Sandbox).unwrap();
+        assert_eq!(diagnostic.range, Range::default());
+    }
+}
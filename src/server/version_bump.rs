@@ -0,0 +1,88 @@
+//! Bumping the version of a package definition.
+//!
+//! [`BUMP_VERSION_COMMAND`] rewrites the `version` attribute of a package.py
+//! to the next version under the version scheme of its repository.
+
+use chrono::NaiveDate;
+use tower_lsp::lsp_types::TextEdit;
+
+use crate::core::{BumpPart, Version, VersionScheme};
+use crate::server::positions::{LineIndex, PositionEncoding};
+
+/// Command bumping the version of a package definition.
+///
+/// Arguments are the document URI and optionally the part to increment
+/// (`major`, `minor` or `patch`, defaulting to `patch`).
+pub const BUMP_VERSION_COMMAND: &str = "rez.bumpVersion";
+
+/// Build the edit replacing the version of a package definition with the
+/// next version under `scheme`.
+///
+/// Returns the new version with the edit, or `None` when the package does
+/// not declare a version string.
+pub fn bump_version_edit(
+    content: &str,
+    scheme: VersionScheme,
+    part: BumpPart,
+    today: NaiveDate,
+    encoding: PositionEncoding,
+) -> Option<(String, TextEdit)> {
+    let index = LineIndex::new(content, encoding);
+    content.lines().enumerate().find_map(|(line_num, line)| {
+        let value = line
+            .strip_prefix("version")?
+            .trim_start()
+            .strip_prefix('=')?;
+        let quote_start = line.len() - value.trim_start().len();
+        let quote = line[quote_start..].chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let start = quote_start + 1;
+        let len = line[start..].find(quote)?;
+
+        let current = Version::new(&line[start..start + len]);
+        let next = scheme.bump(&current, part, today);
+        let edit = TextEdit {
+            range: index.byte_range(line_num as u32, start, start + len),
+            new_text: next.clone(),
+        };
+        Some((next, edit))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    #[test]
+    fn test_bump_version_edit() {
+        let content = "name = 'tools'\n    version = '9.9.9'\nversion = \"1.4.2\"\n";
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+
+        let (version, edit) = bump_version_edit(
+            content,
+            VersionScheme::Semver,
+            BumpPart::Minor,
+            today,
+            PositionEncoding::Utf16,
+        )
+        .unwrap();
+        assert_eq!(version, "1.5.0");
+        assert_eq!(edit.new_text, "1.5.0");
+        assert_eq!(
+            edit.range,
+            Range::new(Position::new(2, 11), Position::new(2, 16))
+        );
+
+        assert!(bump_version_edit(
+            "name = 'tools'\n",
+            VersionScheme::Rez,
+            BumpPart::Patch,
+            today,
+            PositionEncoding::Utf16
+        )
+        .is_none());
+    }
+}
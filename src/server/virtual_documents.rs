@@ -0,0 +1,128 @@
+//! Read-only virtual documents of indexed packages.
+//!
+//! With [`Config::virtual_documents`](crate::config::Config) set, definition
+//! targets point at `rezpkg://family/version/package.py` instead of the file
+//! in the repository. The client fetches the content with the
+//! `rez/packageContent` request, so previews open even when the repository
+//! sits on a slow mount or is not visible to the client at all.
+
+use tower_lsp::lsp_types::Url;
+
+use crate::core::{DiscoveryError, Error, Package, Result, Version};
+use crate::discovery::PackageDiscoveryImpl;
+use crate::parser::encoding::read_source;
+
+/// URI scheme of package virtual documents.
+pub const PACKAGE_SCHEME: &str = "rezpkg";
+
+/// Parameters for the `rez/packageContent` request.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageContentParams {
+    /// `rezpkg://` URI of the package definition
+    pub uri: Url,
+}
+
+/// Response of the `rez/packageContent` request.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageContent {
+    /// Content of the package.py
+    pub content: String,
+}
+
+/// Get the virtual document URI of a package's definition.
+///
+/// Unversioned packages have no version segment, e.g.
+/// `rezpkg://dev_tool/package.py`.
+pub fn package_uri(package: &Package) -> Option<Url> {
+    let uri = if package.version.is_unversioned() {
+        format!("{}://{}/package.py", PACKAGE_SCHEME, package.name)
+    } else {
+        format!(
+            "{}://{}/{}/package.py",
+            PACKAGE_SCHEME, package.name, package.version
+        )
+    };
+    Url::parse(&uri).ok()
+}
+
+/// Parse the family and version of a package virtual document URI.
+///
+/// The version is `None` for unversioned packages.
+pub fn parse_package_uri(uri: &Url) -> Option<(String, Option<Version>)> {
+    if uri.scheme() != PACKAGE_SCHEME {
+        return None;
+    }
+    let family = uri.host_str().filter(|family| !family.is_empty())?;
+    let segments: Vec<&str> = uri.path_segments()?.collect();
+    let version = match segments.as_slice() {
+        ["package.py"] => None,
+        [version, "package.py"] if !version.is_empty() => Some(Version::new(*version)),
+        _ => return None,
+    };
+    Some((family.to_string(), version))
+}
+
+/// Read the package.py of the indexed package named by a virtual document
+/// URI.
+pub fn package_content(discovery: &PackageDiscoveryImpl, uri: &Url) -> Result<PackageContent> {
+    let (family, version) = parse_package_uri(uri)
+        .ok_or_else(|| Error::InvalidPath(format!("Not a package document: {}", uri)))?;
+    let name = discovery.config().canonical_package_name(&family);
+    let package = discovery
+        .packages()
+        .get(name)
+        .into_iter()
+        .flatten()
+        .find(|package| match &version {
+            Some(version) => package.version == *version,
+            None => package.version.is_unversioned(),
+        })
+        .ok_or_else(|| DiscoveryError::PackageNotFound(uri.to_string()))?;
+
+    let content = read_source(&package.path.join("package.py"))?;
+    Ok(PackageContent { content })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::{scan_repositories, write_package};
+
+    #[test]
+    fn test_package_uri_round_trip() {
+        let package = Package::builder().name("maya").version("2024.1").build();
+        let uri = package_uri(&package).unwrap();
+        assert_eq!(uri.as_str(), "rezpkg://maya/2024.1/package.py");
+        assert_eq!(
+            parse_package_uri(&uri),
+            Some(("maya".to_string(), Some(Version::new("2024.1"))))
+        );
+
+        let uri = Url::parse("rezpkg://dev_tool/package.py").unwrap();
+        assert_eq!(
+            parse_package_uri(&uri),
+            Some(("dev_tool".to_string(), None))
+        );
+        let uri = Url::parse("file:///maya/2024.1/package.py").unwrap();
+        assert_eq!(parse_package_uri(&uri), None);
+        let uri = Url::parse("rezpkg://maya/2024.1/README.md").unwrap();
+        assert_eq!(parse_package_uri(&uri), None);
+    }
+
+    #[tokio::test]
+    async fn test_package_content() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        write_package(temp_dir.path(), "maya", "2024.1", "name = 'maya'\n");
+        let discovery = scan_repositories(&[temp_dir.path()]).await;
+
+        let uri = Url::parse("rezpkg://maya/2024.1/package.py").unwrap();
+        assert_eq!(
+            package_content(&discovery, &uri).unwrap().content,
+            "name = 'maya'\n"
+        );
+        let uri = Url::parse("rezpkg://maya/2025.0/package.py").unwrap();
+        assert!(package_content(&discovery, &uri).is_err());
+    }
+}
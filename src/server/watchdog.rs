@@ -0,0 +1,320 @@
+//! Initialization watchdog for package discovery.
+//!
+//! Scanning package repositories can hang indefinitely on dead network mounts.
+//! The watchdog bounds how long initialization waits for the scan, switches the
+//! server into a degraded-but-usable state when the limit is exceeded, and
+//! keeps the scan going in the background until it succeeds.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tower_lsp::lsp_types::MessageType;
+use tower_lsp::Client;
+use tracing::{info, warn};
+
+use super::notifications::send_index_stats;
+use super::tasks::TaskRegistry;
+
+use crate::config::Config;
+use crate::core::{PackageDiscovery, Result};
+use crate::discovery::{FamilyStream, PackageDiscoveryImpl};
+use crate::performance::CacheManager;
+
+/// Health of the server's package index.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "state", content = "message", rename_all = "camelCase")]
+pub enum ServerHealth {
+    /// Package discovery has not finished yet
+    Initializing,
+    /// Package discovery completed and all features are available
+    Ready,
+    /// Package discovery is unavailable; only static completions and
+    /// syntax validation are provided
+    Degraded(String),
+}
+
+/// A package scan running on the blocking thread pool.
+pub type PendingScan = JoinHandle<(PackageDiscoveryImpl, Result<()>)>;
+
+/// Families indexed by a running scan, served until the scan finishes.
+pub type WarmingIndex = Arc<RwLock<Option<PackageDiscoveryImpl>>>;
+
+/// Indexes and caches shared by the package scans of a server.
+#[derive(Clone)]
+pub struct ScanCaches {
+    /// Package index installed once a scan finishes
+    pub index: Arc<RwLock<Option<PackageDiscoveryImpl>>>,
+    /// Families indexed by the running scan, only while no package index is
+    /// installed
+    pub warming: WarmingIndex,
+    /// Families scanned before, reused until they expire or change
+    pub families: Arc<CacheManager>,
+}
+
+impl ScanCaches {
+    /// Create empty scan caches for the package index `index`.
+    pub fn new(
+        index: Arc<RwLock<Option<PackageDiscoveryImpl>>>,
+        families: Arc<CacheManager>,
+    ) -> Self {
+        Self {
+            index,
+            warming: WarmingIndex::default(),
+            families,
+        }
+    }
+
+    /// Install the package index of a finished scan and drop the warming
+    /// index.
+    ///
+    /// Families the warming task still has queued are not added afterwards,
+    /// as it checks for the installed index under the warming index lock.
+    pub async fn install(&self, discovery: PackageDiscoveryImpl) {
+        *self.index.write().await = Some(discovery);
+        *self.warming.write().await = None;
+    }
+}
+
+/// Outcome of a scan guarded by the watchdog.
+pub enum ScanOutcome {
+    /// The scan finished within the timeout
+    Completed(Box<PackageDiscoveryImpl>, Result<()>),
+    /// The scan did not finish in time and is still running
+    TimedOut(PendingScan),
+}
+
+/// Start a package scan on the blocking thread pool.
+///
+/// Discovery performs synchronous file system calls, so it must not run on
+/// the async workers where a hung mount would stall the whole server. The
+/// families are added to the warming index as they are indexed, and families
+/// still cached are not read again.
+pub fn spawn_scan(config: Config, tasks: &TaskRegistry, caches: &ScanCaches) -> PendingScan {
+    let mut discovery =
+        PackageDiscoveryImpl::new(config.clone()).with_family_cache(Arc::clone(&caches.families));
+    spawn_warming(tasks, caches.clone(), config, discovery.stream_families());
+
+    let handle = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        let result = handle.block_on(discovery.scan_shared());
+        (discovery, result)
+    })
+}
+
+/// Add the families streamed by a scan to the warming index until a package
+/// index is installed.
+fn spawn_warming(
+    tasks: &TaskRegistry,
+    caches: ScanCaches,
+    config: Config,
+    mut families: FamilyStream,
+) {
+    tasks.spawn("index-warming", async move {
+        while let Some(family) = families.recv().await {
+            let mut warming = caches.warming.write().await;
+            if caches.index.read().await.is_some() {
+                continue;
+            }
+            warming
+                .get_or_insert_with(|| PackageDiscoveryImpl::new(config.clone()))
+                .insert_family(family.name, family.versions);
+        }
+    });
+}
+
+/// Run a package scan, giving up waiting after `timeout`.
+pub async fn scan_with_timeout(
+    config: Config,
+    timeout: Duration,
+    tasks: &TaskRegistry,
+    caches: &ScanCaches,
+) -> ScanOutcome {
+    let mut pending = spawn_scan(config.clone(), tasks, caches);
+
+    match tokio::time::timeout(timeout, &mut pending).await {
+        Ok(Ok((discovery, result))) => ScanOutcome::Completed(Box::new(discovery), result),
+        Ok(Err(join_error)) => {
+            warn!("Package scan task failed: {}", join_error);
+            ScanOutcome::Completed(
+                Box::new(PackageDiscoveryImpl::new(config)),
+                Err(crate::core::DiscoveryError::ScanFailed(join_error.to_string()).into()),
+            )
+        }
+        Err(_) => ScanOutcome::TimedOut(pending),
+    }
+}
+
+/// Build the message shown to users when the server enters degraded mode.
+pub fn degraded_message(config: &Config, timeout: Duration) -> String {
+    let paths = config
+        .get_all_package_paths()
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "Rez package scan did not finish within {}s. Check that these package paths are reachable (e.g. no stale network mounts): {}. \
+         Running in degraded mode with static completions and syntax validation; the scan keeps retrying in the background.",
+        timeout.as_secs(),
+        paths
+    )
+}
+
+/// Keep waiting for a timed-out scan in the background and install the
+/// package index once a scan succeeds, retrying failed scans after
+/// [`Config::scan_retry_interval_secs`].
+///
+/// The recovery stops retrying when `tasks` shuts down; a scan already
+/// running on the blocking thread pool is left to finish. The warming index
+/// is dropped once the package index is installed.
+pub fn spawn_recovery(
+    tasks: &Arc<TaskRegistry>,
+    caches: &ScanCaches,
+    client: Client,
+    pending: PendingScan,
+    config: Config,
+    health: Arc<RwLock<ServerHealth>>,
+) {
+    let retry_interval = Duration::from_secs(config.scan_retry_interval_secs);
+    let retry_tasks = Arc::clone(tasks);
+    let caches = caches.clone();
+    tasks.spawn("scan-recovery", async move {
+        let mut pending = pending;
+        let mut attempt = 1;
+
+        loop {
+            match pending.await {
+                Ok((discovery, Ok(()))) => {
+                    let (families, total) = discovery.get_stats().await.unwrap_or((0, 0));
+                    info!("Discovery metrics: {}", discovery.metrics().summary());
+                    send_index_stats(&client, &discovery).await;
+                    caches.install(discovery).await;
+                    *health.write().await = ServerHealth::Ready;
+
+                    info!("Package scan recovered after {} attempt(s)", attempt);
+                    client
+                        .show_message(
+                            MessageType::INFO,
+                            format!(
+                                "Rez package scan completed: {} package families ({} total packages). All features are available.",
+                                families, total
+                            ),
+                        )
+                        .await;
+                    return;
+                }
+                Ok((_, Err(e))) => {
+                    warn!("Background package scan failed: {}", e);
+                }
+                Err(join_error) => {
+                    warn!("Background package scan task failed: {}", join_error);
+                }
+            }
+
+            tokio::time::sleep(retry_interval).await;
+            attempt += 1;
+            pending = spawn_scan(config.clone(), &retry_tasks, &caches);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::test_support::{repository_config, write_package};
+
+    #[tokio::test]
+    async fn test_scan_completes_within_timeout() {
+        let tasks = TaskRegistry::new();
+        let caches = ScanCaches::new(
+            Arc::default(),
+            Arc::new(CacheManager::new(&Default::default())),
+        );
+        let outcome =
+            scan_with_timeout(Config::new(), Duration::from_secs(5), &tasks, &caches).await;
+        match outcome {
+            ScanOutcome::Completed(discovery, result) => {
+                assert!(result.is_ok());
+                assert_eq!(discovery.get_stats().await.unwrap(), (0, 0));
+            }
+            ScanOutcome::TimedOut(_) => panic!("Empty scan should not time out"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_fills_warming_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        write_package(temp_dir.path(), "maya", "2024.1", "name = 'maya'\n");
+        let config = repository_config(&[temp_dir.path()]);
+
+        let tasks = TaskRegistry::new();
+        let caches = ScanCaches::new(
+            Arc::default(),
+            Arc::new(CacheManager::new(&Default::default())),
+        );
+        let outcome = scan_with_timeout(config, Duration::from_secs(5), &tasks, &caches).await;
+        assert!(matches!(outcome, ScanOutcome::Completed(_, Ok(()))));
+
+        // The stream ends with the scan, so the warming task finishes
+        for _ in 0..100 {
+            if tasks.active() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(tasks.active(), 0);
+        let warming = caches.warming.read().await;
+        let families = warming.as_ref().unwrap().packages();
+        assert_eq!(families["maya"][0].version.to_string(), "2024.1");
+    }
+
+    #[tokio::test]
+    async fn test_warming_stops_once_index_is_installed() {
+        use crate::discovery::ScannedFamily;
+
+        let tasks = TaskRegistry::new();
+        let caches = ScanCaches::new(
+            Arc::default(),
+            Arc::new(CacheManager::new(&Default::default())),
+        );
+        let (sender, families) = tokio::sync::mpsc::unbounded_channel();
+        spawn_warming(&tasks, caches.clone(), Config::new(), families);
+        caches
+            .install(PackageDiscoveryImpl::new(Config::new()))
+            .await;
+
+        // Families still streamed after the index is installed are dropped
+        let package = crate::core::Package::builder()
+            .name("maya")
+            .version("2024.1")
+            .build();
+        sender
+            .send(ScannedFamily {
+                name: "maya".to_string(),
+                versions: vec![Arc::new(package)],
+            })
+            .unwrap();
+        drop(sender);
+        for _ in 0..100 {
+            if tasks.active() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(tasks.active(), 0);
+        assert!(caches.warming.read().await.is_none());
+    }
+
+    #[test]
+    fn test_degraded_message_lists_paths() {
+        let mut config = Config::new();
+        config.packages_path = vec![std::path::PathBuf::from("/mnt/dead")];
+
+        let message = degraded_message(&config, Duration::from_secs(30));
+        assert!(message.contains("30s"));
+        assert!(message.contains("/mnt/dead"));
+        assert!(message.contains("degraded mode"));
+    }
+}
@@ -0,0 +1,330 @@
+//! Registry of all diagnostic codes emitted by the validators.
+//!
+//! This is the single source of truth for diagnostic code documentation.
+//! Editor integrations can fetch the catalog through the `rez/diagnosticCodes`
+//! request instead of hardcoding explanations.
+
+use super::Severity;
+use serde::Serialize;
+
+/// Documentation for a single diagnostic code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCodeInfo {
+    /// The diagnostic code (e.g. `R001`)
+    pub code: &'static str,
+    /// Severity the code is reported with by default
+    pub default_severity: Severity,
+    /// Human-readable description of the issue
+    pub description: &'static str,
+    /// Example snippet that triggers the diagnostic
+    pub example: &'static str,
+    /// Whether the server offers a quick fix for this code
+    pub has_quick_fix: bool,
+}
+
+/// All diagnostic codes known to the server.
+pub const DIAGNOSTIC_CODES: &[DiagnosticCodeInfo] = &[
+    DiagnosticCodeInfo {
+        code: "E101",
+        default_severity: Severity::Error,
+        description: "Indentation mixes tabs and spaces",
+        example: "def commands():\n\t  env.PATH.append('{root}/bin')",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "E111",
+        default_severity: Severity::Error,
+        description: "A block statement is not followed by an indented block",
+        example: "def commands():\npass",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "E902",
+        default_severity: Severity::Error,
+        description: "A string literal is not closed on the same line",
+        example: "name = \"my_package",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "E999",
+        default_severity: Severity::Error,
+        description: "Python syntax error such as a missing colon or mismatched bracket",
+        example: "requires = [\"python\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "W291",
+        default_severity: Severity::Warning,
+        description: "Line ends with trailing whitespace",
+        example: "name = \"my_package\"   ",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "W501",
+        default_severity: Severity::Warning,
+        description: "Line is longer than 79 characters",
+        example: "description = \"a very long description that goes well past the PEP 8 limit\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R001",
+        default_severity: Severity::Error,
+        description: "A required package attribute (name or version) is missing",
+        example: "description = \"A package without a name\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R002",
+        default_severity: Severity::Error,
+        description: "Package name does not start with a letter or contains invalid characters",
+        example: "name = \"123package\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R003",
+        default_severity: Severity::Error,
+        description: "Package version cannot be parsed",
+        example: "version = \"\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R004",
+        default_severity: Severity::Error,
+        description: "Requirement string is not a valid Rez requirement",
+        example: "requires = [\"python 3.7\"]",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R005",
+        default_severity: Severity::Error,
+        description: "The tools attribute is not a dictionary",
+        example: "tools = \"maya\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R006",
+        default_severity: Severity::Error,
+        description:
+            "Commands use an invalid environment variable name or a malformed variable reference",
+        example: "commands = \"export 1PATH=${PATH\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R007",
+        default_severity: Severity::Error,
+        description: "Ephemeral requirement is not a valid dot-prefixed name and range",
+        example: "requires = [\".foo..bar-1\"]",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R101",
+        default_severity: Severity::Warning,
+        description: "A recommended package attribute is missing",
+        example: "name = \"my_package\"\nversion = \"1.0.0\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R102",
+        default_severity: Severity::Warning,
+        description: "Package name is a reserved word",
+        example: "name = \"test\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R103",
+        default_severity: Severity::Warning,
+        description: "Version does not follow semantic versioning",
+        example: "version = \"v1-final\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R104",
+        default_severity: Severity::Warning,
+        description: "Requirement uses a package name containing hyphens",
+        example: "requires = [\"my-lib\"]",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R105",
+        default_severity: Severity::Warning,
+        description: "The same package is required more than once",
+        example: "requires = [\"python\", \"python-3\"]",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R106",
+        default_severity: Severity::Warning,
+        description:
+            "String-form commands contain a statement that is not an environment operation",
+        example: "commands = \"echo hello\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R107",
+        default_severity: Severity::Info,
+        description: "Requirement uses an alias of a renamed package family",
+        example: "requires = [\"usd\"]  # with REZ_LSP_PACKAGE_ALIASES=usd=openusd",
+        has_quick_fix: true,
+    },
+    DiagnosticCodeInfo {
+        code: "R108",
+        default_severity: Severity::Info,
+        description: "Requirement resolves to a pre-release (alpha, beta, rc) version",
+        example: "requires = [\"maya-2025\"]  # with only maya-2025.0-beta1 indexed",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R109",
+        default_severity: Severity::Warning,
+        description: "Variants exceed the limits of the resolver and are not all tried",
+        example: "variants = [[\"maya-2023\"], ...]  # with more variants than REZ_LSP_MAX_VARIANT_FANOUT",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R110",
+        default_severity: Severity::Warning,
+        description: "Package definition belongs to a released package, so edits take effect only after a re-release",
+        example: "# package.py opened from REZ_RELEASE_PACKAGES_PATH",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R111",
+        default_severity: Severity::Warning,
+        description: "Requirement can resolve to a version flagged by the package advisories, with the severity of the advisory",
+        example: "requires = [\"openssl-1\"]  # with openssl-<1.1.1n banned in REZ_LSP_ADVISORIES",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R112",
+        default_severity: Severity::Warning,
+        description: "Script run by build_command does not exist in the package directory",
+        example: "build_command = \"python {root}/build.py\"  # without a build.py next to package.py",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R113",
+        default_severity: Severity::Warning,
+        description: "A package attribute is assigned again or overridden in a conditional block, shadowing an earlier value",
+        example: "version = \"1.0.0\"\nif building:\n    version = \"1.0.0.dev\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R114",
+        default_severity: Severity::Info,
+        description: "A newer version within the requirement's constraint is indexed and resolves without an edit",
+        example: "requires = [\"python-3.9+<4\"]  # with python-3.9 and python-3.11 indexed",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R115",
+        default_severity: Severity::Info,
+        description: "A newer version outside the requirement's constraint is indexed",
+        example: "requires = [\"pyside-2\"]  # with pyside-6.5 indexed",
+        has_quick_fix: true,
+    },
+    DiagnosticCodeInfo {
+        code: "R116",
+        default_severity: Severity::Info,
+        description: "Several packages of the resolved context provide the same tool",
+        example: "requires = [\"maya-2024\", \"python-3.11\"]  # both providing 'python'",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R117",
+        default_severity: Severity::Info,
+        description: "Tests or package evaluation are disabled until the workspace is trusted",
+        example: "tests = {\"unit\": \"pytest\"}  # in an untrusted workspace",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "R201",
+        default_severity: Severity::Warning,
+        description: "Package uses a deprecated attribute",
+        example: "uuid = \"9e7ff5b2-4c2b-4b3e-9a8e-2f0c5c6a1d77\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "V001",
+        default_severity: Severity::Warning,
+        description: "Too many issues were found and the list was truncated",
+        example: "",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "V002",
+        default_severity: Severity::Info,
+        description:
+            "The file is too large for style checks and bracket matching, which were skipped",
+        example: "",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "V003",
+        default_severity: Severity::Warning,
+        description: "The file contains characters that could not be decoded as UTF-8",
+        example: "description = \"Caf\u{fffd}\"",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "V004",
+        default_severity: Severity::Info,
+        description: "The file is not a rez package definition, and rez checks were skipped",
+        example: "import os\nimport sys\nimport setuptools",
+        has_quick_fix: false,
+    },
+    DiagnosticCodeInfo {
+        code: "V999",
+        default_severity: Severity::Critical,
+        description: "The validation engine failed to validate the file",
+        example: "",
+        has_quick_fix: false,
+    },
+];
+
+/// Get the catalog of all diagnostic codes.
+pub fn diagnostic_codes() -> &'static [DiagnosticCodeInfo] {
+    DIAGNOSTIC_CODES
+}
+
+/// Look up the documentation for a diagnostic code.
+pub fn lookup_code(code: &str) -> Option<&'static DiagnosticCodeInfo> {
+    DIAGNOSTIC_CODES.iter().find(|info| info.code == code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::ValidationEngine;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_codes_are_unique() {
+        let mut seen = HashSet::new();
+        for info in diagnostic_codes() {
+            assert!(seen.insert(info.code), "duplicate code {}", info.code);
+        }
+    }
+
+    #[test]
+    fn test_lookup_code() {
+        let info = lookup_code("R001").unwrap();
+        assert_eq!(info.default_severity, Severity::Error);
+        assert!(lookup_code("X000").is_none());
+    }
+
+    #[test]
+    fn test_emitted_codes_are_registered() {
+        let engine = ValidationEngine::new().unwrap();
+        let content = "name = \"123bad\" \nuuid = \"x\"\nrequires = [\"my-lib\", \"my-lib\"]\ndescription = \"\u{fffd}\"\ndef commands(\n";
+        let result = engine.validate_file(content, "package.py").unwrap();
+
+        for issue in &result.issues {
+            assert!(
+                lookup_code(&issue.code).is_some(),
+                "code {} is not registered",
+                issue.code
+            );
+        }
+    }
+}
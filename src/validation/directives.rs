@@ -0,0 +1,93 @@
+//! Inline suppression of diagnostics.
+//!
+//! A `# rez-lsp: disable=W501,R104` comment at the end of a line silences
+//! those codes on that line; on a line of its own, it silences them in the
+//! whole file. Packages with intentional long lines or hyphenated names keep
+//! the checks enabled everywhere else.
+
+use std::collections::{HashMap, HashSet};
+
+/// Marker opening a directive comment.
+const DIRECTIVE_MARKER: &str = "rez-lsp:";
+
+/// Codes disabled by the directives of a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Suppressions {
+    /// Codes disabled in the whole file
+    file: HashSet<String>,
+    /// Codes disabled on single lines, by 1-based line number
+    lines: HashMap<u32, HashSet<String>>,
+}
+
+impl Suppressions {
+    /// Parse the directives of package.py content.
+    pub fn parse(content: &str) -> Self {
+        let mut suppressions = Self::default();
+        for (line_num, line) in content.lines().enumerate() {
+            let Some((statement, codes)) = parse_directive(line) else {
+                continue;
+            };
+            let codes = codes
+                .split(',')
+                .filter_map(|code| code.split_whitespace().next())
+                .map(str::to_ascii_uppercase);
+            if statement.trim().is_empty() {
+                suppressions.file.extend(codes);
+            } else {
+                suppressions
+                    .lines
+                    .entry(line_num as u32 + 1)
+                    .or_default()
+                    .extend(codes);
+            }
+        }
+        suppressions
+    }
+
+    /// Check whether there are no directives.
+    pub fn is_empty(&self) -> bool {
+        self.file.is_empty() && self.lines.is_empty()
+    }
+
+    /// Check whether a code is disabled on a 1-based line.
+    pub fn is_suppressed(&self, code: &str, line: u32) -> bool {
+        let code = code.to_ascii_uppercase();
+        self.file.contains(&code)
+            || self
+                .lines
+                .get(&line)
+                .is_some_and(|codes| codes.contains(&code))
+    }
+}
+
+/// Split a line holding a directive into the statement before the comment
+/// and the disabled codes.
+fn parse_directive(line: &str) -> Option<(&str, &str)> {
+    let marker = line.find(DIRECTIVE_MARKER)?;
+    let statement = line[..marker].trim_end().strip_suffix('#')?;
+    let codes = line[marker + DIRECTIVE_MARKER.len()..]
+        .trim_start()
+        .strip_prefix("disable")?
+        .trim_start()
+        .strip_prefix('=')?;
+    Some((statement, codes.split('#').next().unwrap_or("")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directives() {
+        let content = "# rez-lsp: disable=W501\nname = \"my-tool\"  # rez-lsp: disable=r104, W291 long name\ndescription = \"# rez-lsp: not a directive\"\n";
+        let suppressions = Suppressions::parse(content);
+
+        assert!(suppressions.is_suppressed("W501", 1));
+        assert!(suppressions.is_suppressed("W501", 3));
+        assert!(suppressions.is_suppressed("R104", 2));
+        assert!(suppressions.is_suppressed("W291", 2));
+        assert!(!suppressions.is_suppressed("R104", 3));
+        assert!(!suppressions.is_suppressed("R001", 2));
+        assert!(Suppressions::parse("name = \"tool\"\n").is_empty());
+    }
+}
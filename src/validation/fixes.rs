@@ -0,0 +1,282 @@
+//! Automatic fixes of validation issues.
+//!
+//! Only issues with a single safe fix are fixed, so applying every fix of a
+//! document at once (the `source.fixAll.rezLsp` code action and the `fix`
+//! subcommand) never changes what a package resolves to:
+//!
+//! - deprecated attributes assigned on one line are removed (R201)
+//! - hyphens inside the family name of a requirement become underscores,
+//!   e.g. `my-tool-1.0` becomes `my_tool-1.0` (R104); only words without
+//!   digits followed by a numeric version are taken for the name, so
+//!   `foo-v1` is left alone
+//! - requirements repeated verbatim in the same list are removed (R105)
+//! - requirements are normalized: surrounding whitespace is trimmed and
+//!   `name>=1.0` is written `name-1.0+`
+//!
+//! `requires`, `build_requires` and `private_build_requires` are fixed.
+
+use std::collections::HashSet;
+
+use super::rez_validator::DEPRECATED_FIELDS;
+use crate::parser::{find_requirements, RequirementSpan, REQUIREMENT_ATTRIBUTES};
+
+/// A replacement fixing one issue.
+///
+/// Positions are 0-based `(line, column)` pairs counting characters; `end`
+/// is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// Code of the fixed issue, e.g. `R105`
+    pub code: &'static str,
+    /// Start of the replaced text
+    pub start: (u32, u32),
+    /// End of the replaced text
+    pub end: (u32, u32),
+    /// Replacement text
+    pub new_text: String,
+}
+
+/// Find the fixes of every auto-fixable issue of a package definition.
+///
+/// The fixes do not overlap and are sorted by position.
+pub fn find_fixes(content: &str) -> Vec<Fix> {
+    let mut fixes = deprecated_field_fixes(content);
+
+    let requirements = find_requirements(content);
+    for attribute in REQUIREMENT_ATTRIBUTES {
+        let requirements: Vec<&RequirementSpan> = requirements
+            .iter()
+            .filter(|requirement| requirement.attribute == *attribute)
+            .collect();
+        let mut seen = HashSet::new();
+        for (index, requirement) in requirements.iter().enumerate() {
+            if !seen.insert(requirement.text.trim()) {
+                let previous = index.checked_sub(1).map(|i| requirements[i]);
+                fixes.extend(duplicate_fix(content, requirement, previous));
+            } else if let Some(fix) = requirement_fix(requirement) {
+                fixes.push(fix);
+            }
+        }
+    }
+
+    fixes.sort_by_key(|fix| fix.start);
+    fixes
+}
+
+/// Apply fixes found by [`find_fixes`] to the content they were found in.
+pub fn apply_fixes(content: &str, fixes: &[Fix]) -> String {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+        .collect();
+    let offset = |(line, column): (u32, u32)| {
+        let Some(&start) = line_starts.get(line as usize) else {
+            return content.len();
+        };
+        let text = content[start..].split('\n').next().unwrap_or_default();
+        start
+            + text
+                .char_indices()
+                .nth(column as usize)
+                .map_or(text.len(), |(index, _)| index)
+    };
+
+    let mut fixed = content.to_string();
+    for fix in fixes.iter().rev() {
+        fixed.replace_range(offset(fix.start)..offset(fix.end), &fix.new_text);
+    }
+    fixed
+}
+
+/// Remove deprecated attributes assigned on a single top-level line.
+fn deprecated_field_fixes(content: &str) -> Vec<Fix> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            DEPRECATED_FIELDS.iter().any(|(field, _)| {
+                line.strip_prefix(field)
+                    .and_then(|rest| rest.trim_start().strip_prefix('='))
+                    .is_some_and(|value| is_complete_value(value.trim()))
+            })
+        })
+        .map(|(line, _)| Fix {
+            code: "R201",
+            start: (line as u32, 0),
+            end: (line as u32 + 1, 0),
+            new_text: String::new(),
+        })
+        .collect()
+}
+
+/// Check whether a value ends on the line it starts on.
+fn is_complete_value(value: &str) -> bool {
+    let depth = value.chars().fold(0i32, |depth, c| match c {
+        '[' | '(' | '{' => depth + 1,
+        ']' | ')' | '}' => depth - 1,
+        _ => depth,
+    });
+    !value.is_empty() && depth == 0 && !value.ends_with('\\')
+}
+
+/// Remove a requirement repeating an earlier one.
+///
+/// A requirement alone on its line is removed with the line. After an entry
+/// on the same line, the text from the end of that entry is removed with it,
+/// taking the separating comma along; first on its line, it is removed with
+/// the comma and spaces following it. The edit never spans lines otherwise.
+fn duplicate_fix(
+    content: &str,
+    requirement: &RequirementSpan,
+    previous: Option<&RequirementSpan>,
+) -> Option<Fix> {
+    let line = requirement.range.line;
+    let text = content.lines().nth(line as usize)?;
+    let quoted_start = requirement.range.start - 1;
+    let quoted_end = requirement.range.end + 1;
+    let first_on_line = text
+        .chars()
+        .take(quoted_start as usize)
+        .all(char::is_whitespace);
+    let after: Vec<char> = text.chars().skip(quoted_end as usize).collect();
+    let rest = after.iter().collect::<String>();
+    let alone = first_on_line && rest.trim().trim_start_matches(',').trim().is_empty();
+    let (start, end) = if alone {
+        ((line, 0), (line + 1, 0))
+    } else if let Some(previous) = previous.filter(|previous| previous.range.line == line) {
+        ((line, previous.range.end + 1), (line, quoted_end))
+    } else {
+        // Up to the next entry, past the comma and spaces
+        let comma = after.iter().take_while(|c| c.is_whitespace()).count();
+        if after.get(comma) != Some(&',') {
+            return None;
+        }
+        let spaces = after[comma + 1..]
+            .iter()
+            .take_while(|c| c.is_whitespace())
+            .count();
+        (
+            (line, quoted_start),
+            (line, quoted_end + (comma + 1 + spaces) as u32),
+        )
+    };
+
+    Some(Fix {
+        code: "R105",
+        start,
+        end,
+        new_text: String::new(),
+    })
+}
+
+/// Rewrite a requirement with hyphens in its family name or a
+/// non-canonical version range.
+fn requirement_fix(requirement: &RequirementSpan) -> Option<Fix> {
+    if requirement.is_ephemeral() {
+        return None;
+    }
+    let text = requirement.text.trim();
+    let prefix_len = usize::from(text.starts_with(['~', '!']));
+    let (prefix, rest) = text.split_at(prefix_len);
+    let range_start = rest.find(['<', '>', '=']).unwrap_or(rest.len());
+    let (name_and_version, range) = rest.split_at(range_start);
+
+    // Words without digits between the first word and a numeric version
+    // still belong to the name, e.g. `tool` in `my-tool-1.0`; anything else
+    // may be a version, e.g. `v1` in `foo-v1`
+    let segments: Vec<&str> = name_and_version.split('-').collect();
+    let name_words = segments
+        .iter()
+        .skip(1)
+        .take_while(|segment| {
+            !segment.is_empty() && segment.chars().all(|c| c.is_alphabetic() || c == '_')
+        })
+        .count();
+    let numeric_version = segments
+        .get(1 + name_words)
+        .is_some_and(|segment| segment.starts_with(|c: char| c.is_ascii_digit()));
+    let name_words = if numeric_version { name_words } else { 0 };
+    let name = segments[..=name_words].join("_");
+    let version = &segments[1 + name_words..];
+    let renamed = name_words > 0;
+
+    let mut normalized = format!("{}{}", prefix, name);
+    if !version.is_empty() {
+        normalized.push('-');
+        normalized.push_str(&version.join("-"));
+    }
+    match range.strip_prefix(">=") {
+        Some(min)
+            if version.is_empty() && !min.is_empty() && !min.contains(['<', '>', '=', '|']) =>
+        {
+            normalized.push_str(&format!("-{}+", min));
+        }
+        _ => normalized.push_str(range),
+    }
+
+    (normalized != requirement.text).then_some(Fix {
+        code: if renamed { "R104" } else { "R004" },
+        start: (requirement.range.line, requirement.range.start),
+        end: (requirement.range.line, requirement.range.end),
+        new_text: normalized,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fix_all() {
+        let content = "name = 'tool'\nuuid = 'abc'\nrequires = [\n    'python-3.9',\n    ' my-tool-1.0 ',\n    'python-3.9',\n    'numpy>=1.2',\n]\nbuild_requires = ['cmake', 'my-lib']\n";
+        let fixes = find_fixes(content);
+        let codes: Vec<&str> = fixes.iter().map(|fix| fix.code).collect();
+        assert_eq!(codes, vec!["R201", "R104", "R105", "R004"]);
+        assert_eq!(
+            apply_fixes(content, &fixes),
+            "name = 'tool'\nrequires = [\n    'python-3.9',\n    'my_tool-1.0',\n    'numpy-1.2+',\n]\nbuild_requires = ['cmake', 'my-lib']\n"
+        );
+        // Fixed content has nothing left to fix
+        assert!(find_fixes(&apply_fixes(content, &fixes)).is_empty());
+    }
+
+    #[test]
+    fn test_version_words_are_not_renamed() {
+        let content = "requires = ['foo-v1', 'bar-beta', 'my-tool-2', 'my-tool']\n";
+        let fixes = find_fixes(content);
+        assert_eq!(
+            apply_fixes(content, &fixes),
+            "requires = ['foo-v1', 'bar-beta', 'my_tool-2', 'my-tool']\n"
+        );
+    }
+
+    #[test]
+    fn test_build_requirements_are_fixed() {
+        let content = "build_requires = ['cmake', ' cmake ', 'cmake']\nprivate_build_requires = ['gcc>=9', 'cmake']\n";
+        let fixes = find_fixes(content);
+        assert_eq!(
+            apply_fixes(content, &fixes),
+            "build_requires = ['cmake']\nprivate_build_requires = ['gcc-9+', 'cmake']\n"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_fixes_stay_on_their_line() {
+        let content = "requires = [\n    'maya', 'python',\n    'maya', 'numpy',\n]\n";
+        let fixes = find_fixes(content);
+        assert!(fixes.iter().all(|fix| fix.start.0 == fix.end.0));
+        assert_eq!(
+            apply_fixes(content, &fixes),
+            "requires = [\n    'maya', 'python',\n    'numpy',\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_single_line_duplicates() {
+        let content = "requires = ['maya', 'maya', 'python', 'maya']\nconfig = {\n    'a': 1,\n}\n";
+        let fixes = find_fixes(content);
+        assert_eq!(
+            apply_fixes(content, &fixes),
+            "requires = ['maya', 'python']\nconfig = {\n    'a': 1,\n}\n"
+        );
+    }
+}
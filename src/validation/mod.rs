@@ -1,12 +1,18 @@
 //! Syntax validation for Rez package.py files.
 
+pub mod codes;
+pub mod directives;
+pub mod fixes;
 pub mod python_validator;
 pub mod rez_validator;
 pub mod validation_engine;
 
+pub use codes::{diagnostic_codes, lookup_code, DiagnosticCodeInfo};
+pub use directives::Suppressions;
+pub use fixes::{apply_fixes, find_fixes, Fix};
 pub use python_validator::PythonValidator;
-pub use rez_validator::RezValidator;
-pub use validation_engine::ValidationEngine;
+pub use rez_validator::{foreign_file_reason, RezValidator};
+pub use validation_engine::{ValidationEngine, ValidationEvent};
 
 use crate::core::Result;
 use serde::{Deserialize, Serialize};
@@ -19,9 +25,9 @@ pub struct ValidationIssue {
     pub severity: Severity,
     /// The line number where the issue occurs (1-based)
     pub line: u32,
-    /// The column number where the issue occurs (1-based)
+    /// The column number where the issue occurs (1-based, in characters)
     pub column: u32,
-    /// The length of the problematic text
+    /// The length of the problematic text in characters
     pub length: u32,
     /// A human-readable message describing the issue
     pub message: String,
@@ -123,6 +129,20 @@ pub struct ValidationStats {
     pub info_count: usize,
     /// Time taken for validation in milliseconds
     pub validation_time_ms: u64,
+    /// Time taken by each validator, in the order they ran
+    #[serde(default)]
+    pub validator_timings: Vec<ValidatorTiming>,
+}
+
+/// Time taken by a single validator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorTiming {
+    /// Name of the validator, e.g. `RezValidator`
+    pub validator: String,
+    /// Time taken in microseconds
+    pub duration_us: u64,
+    /// Number of issues the validator reported
+    pub issues: usize,
 }
 
 impl ValidationResult {
@@ -175,8 +195,16 @@ impl ValidationStats {
             warning_count,
             info_count,
             validation_time_ms,
+            validator_timings: Vec::new(),
         }
     }
+
+    /// Get the validator that took longest.
+    pub fn slowest_validator(&self) -> Option<&ValidatorTiming> {
+        self.validator_timings
+            .iter()
+            .max_by_key(|timing| timing.duration_us)
+    }
 }
 
 #[cfg(test)]
@@ -1,9 +1,190 @@
 //! Rez-specific validation for package.py files.
 
 use super::{Severity, ValidationIssue, Validator};
-use crate::core::{types::Version, Result};
+use crate::core::{types::Version, Result, VersionPolicy, VersionScheme};
+use crate::parser::commands::{
+    find_commands, is_valid_env_name, malformed_env_references, CommandsForm, EnvAction,
+};
+use crate::parser::{find_requirements, RequirementSpan};
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Deprecated attributes, with the reason they should be avoided.
+pub const DEPRECATED_FIELDS: &[(&str, &str)] = &[
+    ("uuid", "UUIDs are no longer used in Rez packages"),
+    ("config", "Use 'private_build_requires' instead"),
+];
+
+/// Attributes of the package definition schema.
+pub const PACKAGE_ATTRIBUTES: &[&str] = &[
+    "name",
+    "version",
+    "description",
+    "authors",
+    "help",
+    "requires",
+    "build_requires",
+    "private_build_requires",
+    "variants",
+    "hashed_variants",
+    "tools",
+    "commands",
+    "pre_commands",
+    "post_commands",
+    "build_command",
+    "build_system",
+    "tests",
+    "relocatable",
+    "cachable",
+    "has_plugins",
+    "plugin_for",
+    "requires_rez_version",
+    "uuid",
+    "config",
+];
+
+/// Directories of Python environments, whose package.py files belong to
+/// installed Python distributions rather than rez packages.
+const PYTHON_ENVIRONMENT_DIRS: &[&str] = &[
+    "site-packages",
+    "dist-packages",
+    "venv",
+    ".venv",
+    "virtualenv",
+];
+
+/// Top-level imports from which a file without package attributes is taken
+/// for an ordinary Python module.
+const FOREIGN_IMPORT_THRESHOLD: usize = 3;
+
+/// Get why a file named package.py is not a rez package definition, if it
+/// looks like an ordinary Python module instead.
+///
+/// Files assigning or defining any package attribute are package
+/// definitions, even in a family named like a Python environment directory.
+/// Other files inside a Python environment, or importing heavily, are not.
+/// Empty and new files outside environments are, so they still get the
+/// required field checks.
+pub fn foreign_file_reason(content: &str, file_path: &str) -> Option<String> {
+    let mut imports = 0;
+    for line in content.lines() {
+        if line.starts_with("import ") || line.starts_with("from ") {
+            imports += 1;
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let target = line
+            .strip_prefix("def ")
+            .map(|rest| rest.split('(').next().unwrap_or(""))
+            .or_else(|| {
+                let (target, value) = line.split_once('=')?;
+                (!value.starts_with('=')).then_some(target)
+            });
+        let is_attribute = target.is_some_and(|target| {
+            let target = target.split(':').next().unwrap_or("").trim();
+            PACKAGE_ATTRIBUTES.contains(&target)
+        });
+        if is_attribute {
+            return None;
+        }
+    }
+
+    if let Some(dir) = file_path
+        .split(['/', '\\'])
+        .find(|component| PYTHON_ENVIRONMENT_DIRS.contains(component))
+    {
+        return Some(format!("it is inside a Python environment ({})", dir));
+    }
+    (imports >= FOREIGN_IMPORT_THRESHOLD).then(|| {
+        format!(
+            "it has {} top-level imports and no package attributes",
+            imports
+        )
+    })
+}
+
+/// Keywords opening a top-level block whose body runs conditionally.
+const CONDITIONAL_KEYWORDS: &[&str] = &[
+    "if", "elif", "else", "try", "except", "finally", "for", "while", "with",
+];
+
+/// Top-level block a line belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TopLevelBlock {
+    /// Body of a function or class, whose assignments are local
+    Function,
+    /// Body of a conditional statement or loop
+    Conditional,
+}
+
+/// Branch of a compound statement an assignment is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BranchKind {
+    /// Body of the `if`, or of the n-th `elif` or the `else`
+    If(usize),
+    /// Body of a `try`
+    Try,
+    /// Body of the n-th `except` of a `try`
+    Except(usize),
+    /// Body of the `else` of a `try`
+    TryElse,
+    /// Body of a `finally`, or of a loop or `with` statement
+    Always,
+}
+
+impl BranchKind {
+    /// Get the first branch of a compound statement starting with `keyword`.
+    fn start(keyword: &str) -> Option<Self> {
+        match keyword {
+            "if" => Some(Self::If(0)),
+            "try" => Some(Self::Try),
+            "for" | "while" | "with" => Some(Self::Always),
+            _ => None,
+        }
+    }
+
+    /// Get the branch following this one at a clause starting with
+    /// `keyword`, e.g. `elif` or `except`.
+    fn next(self, keyword: &str) -> Self {
+        match (self, keyword) {
+            (Self::If(n), "elif" | "else") => Self::If(n + 1),
+            (Self::Try, "except") => Self::Except(0),
+            (Self::Except(n), "except") => Self::Except(n + 1),
+            (Self::Try | Self::Except(_), "else") => Self::TryElse,
+            _ => Self::Always,
+        }
+    }
+
+    /// Check whether at most one of two branches of the same statement runs.
+    fn excludes(self, other: Self) -> bool {
+        match (self, other) {
+            (Self::If(a), Self::If(b)) | (Self::Except(a), Self::Except(b)) => a != b,
+            (Self::Try, Self::Except(_)) | (Self::Except(_), Self::Try) => true,
+            (Self::Except(_), Self::TryElse) | (Self::TryElse, Self::Except(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Branch of a compound statement, told apart from the branches of other
+/// statements by the number of the statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Branch {
+    statement: usize,
+    kind: BranchKind,
+}
+
+/// Check whether two assignments are on exclusive control-flow paths, e.g.
+/// in the `if` and `else` branches of one statement.
+fn exclusive_paths(a: &[Branch], b: &[Branch]) -> bool {
+    a.iter().any(|a| {
+        b.iter()
+            .any(|b| a.statement == b.statement && a.kind.excludes(b.kind))
+    })
+}
 
 /// Validates Rez-specific syntax and semantics in package.py files.
 pub struct RezValidator {
@@ -15,15 +196,17 @@ pub struct RezValidator {
     deprecated_fields: HashMap<String, String>,
     /// Regex patterns for validation
     patterns: RezPatterns,
+    /// Version schemes enforced per package repository
+    version_policy: VersionPolicy,
 }
 
 struct RezPatterns {
-    /// Pattern for version strings
-    version_pattern: Regex,
     /// Pattern for package names
     name_pattern: Regex,
     /// Pattern for requirement strings
     requirement_pattern: Regex,
+    /// Pattern for ephemeral requirement strings, e.g. `.foo.bar-1`
+    ephemeral_pattern: Regex,
     /// Pattern for tool definitions
     #[allow(dead_code)]
     tool_pattern: Regex,
@@ -41,20 +224,29 @@ impl RezValidator {
         recommended_fields.insert("authors".to_string());
         recommended_fields.insert("requires".to_string());
 
-        let mut deprecated_fields = HashMap::new();
-        deprecated_fields.insert(
-            "uuid".to_string(),
-            "UUIDs are no longer used in Rez packages".to_string(),
-        );
-        deprecated_fields.insert(
-            "config".to_string(),
-            "Use 'private_build_requires' instead".to_string(),
+        let deprecated_fields = DEPRECATED_FIELDS
+            .iter()
+            .map(|(field, reason)| (field.to_string(), reason.to_string()))
+            .collect();
+
+        // A version range is one or more `|` separated alternatives, e.g.
+        // `-2023|2025+`, `==3.9|3.11`, `<3|3.7+<4` or the wildcard `-1.*`
+        let alternative = r"([a-zA-Z0-9_.]+\+?(<[a-zA-Z0-9_.]+)?|[a-zA-Z0-9_.]*\*[a-zA-Z0-9_.*]*|==[a-zA-Z0-9_.]+|<[a-zA-Z0-9_.]+)";
+        let range = format!(
+            r"(-{alt}|==[a-zA-Z0-9_.]+|<[a-zA-Z0-9_.]+)(\|{alt})*",
+            alt = alternative
         );
 
         let patterns = RezPatterns {
-            version_pattern: Regex::new(r"^[0-9]+(\.[0-9]+)*([a-zA-Z][a-zA-Z0-9]*)?$")?,
             name_pattern: Regex::new(r"^[a-zA-Z][a-zA-Z0-9_]*$")?,
-            requirement_pattern: Regex::new(r"^[a-zA-Z][a-zA-Z0-9_]*([<>=!]+[0-9]+(\.[0-9]+)*)?$")?,
+            requirement_pattern: Regex::new(&format!(
+                r"^[~!]?[a-zA-Z][a-zA-Z0-9_]*([<>=!]+[0-9]+(\.[0-9]+)*|{})?$",
+                range
+            ))?,
+            ephemeral_pattern: Regex::new(&format!(
+                r"^[~!]?\.[a-zA-Z_][a-zA-Z0-9_]*(\.[a-zA-Z_][a-zA-Z0-9_]*)*({})?$",
+                range
+            ))?,
             tool_pattern: Regex::new(r"^[a-zA-Z][a-zA-Z0-9_]*$")?,
         };
 
@@ -63,9 +255,16 @@ impl RezValidator {
             recommended_fields,
             deprecated_fields,
             patterns,
+            version_policy: VersionPolicy::default(),
         })
     }
 
+    /// Enforce the version schemes of a policy on package versions.
+    pub fn with_version_policy(mut self, version_policy: VersionPolicy) -> Self {
+        self.version_policy = version_policy;
+        self
+    }
+
     /// Extract field assignments from Python code.
     fn extract_fields(&self, content: &str) -> HashMap<String, (u32, String)> {
         let mut fields = HashMap::new();
@@ -174,6 +373,129 @@ impl RezValidator {
         issues
     }
 
+    /// Check for schema attributes assigned more than once at the top level,
+    /// or overridden inside a conditional block.
+    ///
+    /// Only the last assignment executed is kept by rez, so earlier values are
+    /// silently shadowed. Each reassignment is reported with the line of the
+    /// assignment it shadows. Assignments in branches of which at most one
+    /// runs, such as `if`/`else` or `try`/`except`, do not shadow each other.
+    fn check_shadowed_assignments(&self, content: &str) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut previous: HashMap<&str, Vec<(u32, Vec<Branch>)>> = HashMap::new();
+        // Enclosing branches with the indentation of their clause
+        let mut branches: Vec<(usize, Branch)> = Vec::new();
+        let mut statements = 0;
+        let mut block = None;
+        let mut nested_function: Option<usize> = None;
+        let mut depth = 0;
+        let mut string = None;
+
+        for (index, line) in content.lines().enumerate() {
+            // Skip the continuation lines of brackets and multi-line strings
+            let continuation = depth > 0 || string.is_some();
+            update_nesting(line, &mut depth, &mut string);
+            let trimmed = line.trim_start();
+            if continuation || trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let indent = line.len() - trimmed.len();
+            let keyword = trimmed
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap_or_default();
+            while branches.last().is_some_and(|(clause, _)| *clause > indent) {
+                branches.pop();
+            }
+            match branches.last_mut() {
+                Some((clause, branch))
+                    if *clause == indent
+                        && matches!(keyword, "elif" | "else" | "except" | "finally") =>
+                {
+                    branch.kind = branch.kind.next(keyword);
+                }
+                _ => {
+                    while branches.last().is_some_and(|(clause, _)| *clause >= indent) {
+                        branches.pop();
+                    }
+                    if let Some(kind) = BranchKind::start(keyword) {
+                        statements += 1;
+                        branches.push((
+                            indent,
+                            Branch {
+                                statement: statements,
+                                kind,
+                            },
+                        ));
+                    }
+                }
+            }
+            if indent == 0 {
+                nested_function = None;
+                block = if trimmed.starts_with('@') || matches!(keyword, "def" | "class" | "async")
+                {
+                    Some(TopLevelBlock::Function)
+                } else if CONDITIONAL_KEYWORDS.contains(&keyword) {
+                    Some(TopLevelBlock::Conditional)
+                } else {
+                    None
+                };
+            } else if nested_function.is_some_and(|function| indent <= function) {
+                nested_function = None;
+            }
+            if block == Some(TopLevelBlock::Function) || nested_function.is_some() {
+                continue;
+            }
+            if matches!(keyword, "def" | "class" | "async") {
+                nested_function = Some(indent);
+                continue;
+            }
+
+            let Some(name) = assigned_attribute(trimmed) else {
+                continue;
+            };
+            let line_num = index as u32 + 1;
+            let conditional = indent > 0 && block == Some(TopLevelBlock::Conditional);
+            let path: Vec<Branch> = branches.iter().map(|(_, branch)| *branch).collect();
+            let assignments = previous.entry(name).or_default();
+            let shadowed = assignments
+                .iter()
+                .rev()
+                .find(|(_, other)| !exclusive_paths(&path, other))
+                .map(|(line, _)| *line);
+            assignments.push((line_num, path));
+            if let Some(shadowed) = shadowed {
+                let message = if conditional {
+                    format!(
+                        "'{}' is conditionally overridden, shadowing the value assigned on line {}",
+                        name, shadowed
+                    )
+                } else {
+                    format!(
+                        "'{}' is assigned again, shadowing the value assigned on line {}",
+                        name, shadowed
+                    )
+                };
+                issues.push(
+                    ValidationIssue::new(
+                        Severity::Warning,
+                        line_num,
+                        indent as u32 + 1,
+                        name.len() as u32,
+                        message,
+                        "R113",
+                    )
+                    .with_suggestion(
+                        "Assign the attribute once, e.g. with a conditional expression",
+                    ),
+                );
+            }
+        }
+
+        issues
+    }
+
     /// Validate package name.
     fn validate_name(&self, fields: &HashMap<String, (u32, String)>) -> Vec<ValidationIssue> {
         let mut issues = Vec::new();
@@ -212,8 +534,12 @@ impl RezValidator {
         issues
     }
 
-    /// Validate version field.
-    fn validate_version(&self, fields: &HashMap<String, (u32, String)>) -> Vec<ValidationIssue> {
+    /// Validate version field against the version scheme of the package.
+    fn validate_version(
+        &self,
+        fields: &HashMap<String, (u32, String)>,
+        scheme: VersionScheme,
+    ) -> Vec<ValidationIssue> {
         let mut issues = Vec::new();
 
         if let Some((line_num, value)) = fields.get("version") {
@@ -222,6 +548,14 @@ impl RezValidator {
             // Try to parse as Rez version
             match Version::new(&clean_value) {
                 version if version.tokens.is_empty() => {
+                    let suggestion = match scheme {
+                        VersionScheme::Rez => "Use semantic versioning (e.g., '1.0.0')".to_string(),
+                        _ => format!(
+                            "Use a {} version (e.g., '{}')",
+                            scheme.name(),
+                            scheme.example()
+                        ),
+                    };
                     issues.push(
                         ValidationIssue::new(
                             Severity::Error,
@@ -231,24 +565,38 @@ impl RezValidator {
                             "Invalid version format",
                             "R003",
                         )
-                        .with_suggestion("Use semantic versioning (e.g., '1.0.0')"),
+                        .with_suggestion(suggestion),
                     );
                 }
                 _ => {
-                    // Version is valid, check for best practices
-                    if !self.patterns.version_pattern.is_match(&clean_value) {
+                    // Version is valid, check it follows the repository's scheme
+                    if let Some(message) = scheme.violation(&clean_value) {
+                        // Stricter schemes are enforced rather than recommended
+                        let (severity, suggestion) = match scheme {
+                            VersionScheme::Rez => (
+                                Severity::Warning,
+                                "Consider using semantic versioning (major.minor.patch)"
+                                    .to_string(),
+                            ),
+                            _ => (
+                                Severity::Error,
+                                format!(
+                                    "This repository requires {} versions (e.g., '{}')",
+                                    scheme.name(),
+                                    scheme.example()
+                                ),
+                            ),
+                        };
                         issues.push(
                             ValidationIssue::new(
-                                Severity::Warning,
+                                severity,
                                 *line_num,
                                 1,
                                 value.len() as u32,
-                                "Version format doesn't follow semantic versioning",
+                                message,
                                 "R103",
                             )
-                            .with_suggestion(
-                                "Consider using semantic versioning (major.minor.patch)",
-                            ),
+                            .with_suggestion(suggestion),
                         );
                     }
                 }
@@ -259,85 +607,125 @@ impl RezValidator {
     }
 
     /// Validate requires field.
-    fn validate_requires(&self, fields: &HashMap<String, (u32, String)>) -> Vec<ValidationIssue> {
+    ///
+    /// Issues point at the requirement string, or the family name for
+    /// duplicates, rather than the whole `requires` assignment.
+    fn validate_requires(&self, content: &str) -> Vec<ValidationIssue> {
         let mut issues = Vec::new();
+        let requirements: Vec<RequirementSpan> = find_requirements(content)
+            .into_iter()
+            .filter(|requirement| requirement.attribute == "requires")
+            .collect();
 
-        if let Some((line_num, value)) = fields.get("requires") {
-            // Parse the requires list
-            if let Some(requirements) = self.parse_list_value(value) {
-                for requirement in requirements.iter() {
-                    let clean_req = self.clean_string_value(requirement);
+        for requirement in &requirements {
+            let clean_req = requirement.text.trim();
+            let line_num = requirement.range.line + 1;
+            let column = requirement.range.start + 1;
 
-                    // Validate requirement format
-                    if !self.patterns.requirement_pattern.is_match(&clean_req) {
-                        issues.push(
-                            ValidationIssue::new(
-                                Severity::Error,
-                                *line_num,
-                                1,
-                                requirement.len() as u32,
-                                format!("Invalid requirement format: '{}'", clean_req),
-                                "R004",
-                            )
-                            .with_suggestion(
-                                "Requirements should be in format 'package' or 'package>=1.0.0'",
-                            ),
-                        );
-                    }
+            // Ephemerals are checked by `validate_ephemerals`
+            if requirement.is_ephemeral() {
+                continue;
+            }
 
-                    // Check for common typos
-                    let common_packages = ["python", "maya", "houdini", "nuke", "blender"];
-                    if !common_packages
-                        .iter()
-                        .any(|&pkg| clean_req.starts_with(pkg))
-                    {
-                        // This is a custom package, check for naming conventions
-                        if clean_req.contains('-') {
-                            issues.push(
-                                ValidationIssue::new(
-                                    Severity::Warning,
-                                    *line_num,
-                                    1,
-                                    requirement.len() as u32,
-                                    "Package names with hyphens may cause issues",
-                                    "R104",
-                                )
-                                .with_suggestion("Consider using underscores instead of hyphens"),
-                            );
-                        }
-                    }
-                }
+            // Validate requirement format
+            if !self.patterns.requirement_pattern.is_match(clean_req) {
+                issues.push(
+                    ValidationIssue::new(
+                        Severity::Error,
+                        line_num,
+                        column,
+                        requirement.range.len(),
+                        format!("Invalid requirement format: '{}'", clean_req),
+                        "R004",
+                    )
+                    .with_suggestion(
+                        "Requirements should be in format 'package' or 'package>=1.0.0'",
+                    ),
+                );
+            }
 
-                // Check for duplicate requirements
-                let mut seen = HashSet::new();
-                for requirement in &requirements {
-                    let clean_req = self.clean_string_value(requirement);
-                    let package_name = clean_req
-                        .split(&['<', '>', '=', '!'][..])
-                        .next()
-                        .unwrap_or(&clean_req)
-                        .to_string();
-
-                    if !seen.insert(package_name.clone()) {
-                        issues.push(
-                            ValidationIssue::new(
-                                Severity::Warning,
-                                *line_num,
-                                1,
-                                value.len() as u32,
-                                format!("Duplicate requirement: '{}'", package_name),
-                                "R105",
-                            )
-                            .with_suggestion("Remove duplicate requirements"),
-                        );
-                    }
+            // Check for common typos
+            let common_packages = ["python", "maya", "houdini", "nuke", "blender"];
+            if !common_packages
+                .iter()
+                .any(|&pkg| clean_req.starts_with(pkg))
+            {
+                // This is a custom package, check for naming conventions
+                if clean_req.contains('-') {
+                    issues.push(
+                        ValidationIssue::new(
+                            Severity::Warning,
+                            line_num,
+                            column,
+                            requirement.range.len(),
+                            "Package names with hyphens may cause issues",
+                            "R104",
+                        )
+                        .with_suggestion("Consider using underscores instead of hyphens"),
+                    );
                 }
             }
         }
 
+        // Check for duplicate requirements
+        let mut seen = HashSet::new();
+        for requirement in &requirements {
+            let clean_req = requirement.text.trim();
+            let package_name = clean_req
+                .split(&['<', '>', '=', '!'][..])
+                .next()
+                .unwrap_or(clean_req)
+                .to_string();
+
+            if !seen.insert(package_name.clone()) {
+                issues.push(
+                    ValidationIssue::new(
+                        Severity::Warning,
+                        requirement.range.line + 1,
+                        requirement.name.start + 1,
+                        requirement.name.len(),
+                        format!("Duplicate requirement: '{}'", package_name),
+                        "R105",
+                    )
+                    .with_suggestion("Remove duplicate requirements"),
+                );
+            }
+        }
+
         issues
     }
 
+    /// Validate the syntax of ephemeral requirements in every requirement
+    /// list and variant.
+    fn validate_ephemerals(&self, content: &str) -> Vec<ValidationIssue> {
+        find_requirements(content)
+            .into_iter()
+            .filter(|requirement| requirement.is_ephemeral())
+            .filter(|requirement| {
+                !self
+                    .patterns
+                    .ephemeral_pattern
+                    .is_match(requirement.text.trim())
+            })
+            .map(|requirement| {
+                ValidationIssue::new(
+                    Severity::Error,
+                    requirement.range.line + 1,
+                    requirement.range.start + 1,
+                    requirement.range.len(),
+                    format!(
+                        "Invalid ephemeral requirement: '{}'",
+                        requirement.text.trim()
+                    ),
+                    "R007",
+                )
+                .with_suggestion(
+                    "Ephemerals are dot-separated names after a leading '.', e.g. '.foo.bar-1'",
+                )
+            })
+            .collect()
+    }
+
     /// Validate tools field.
     fn validate_tools(&self, fields: &HashMap<String, (u32, String)>) -> Vec<ValidationIssue> {
         let mut issues = Vec::new();
@@ -362,6 +750,74 @@ impl RezValidator {
         issues
     }
 
+    /// Validate environment variables used by `commands`.
+    ///
+    /// Both the function form and the legacy string form are checked.
+    fn validate_commands(&self, content: &str) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let Some(block) = find_commands(content) else {
+            return issues;
+        };
+
+        for operation in &block.operations {
+            let line_num = operation.line + 1;
+
+            if operation.action != EnvAction::Alias && !is_valid_env_name(&operation.variable) {
+                issues.push(
+                    ValidationIssue::new(
+                        Severity::Error,
+                        line_num,
+                        1,
+                        operation.variable.len() as u32,
+                        format!(
+                            "Invalid environment variable name '{}'",
+                            operation.variable
+                        ),
+                        "R006",
+                    )
+                    .with_suggestion(
+                        "Environment variable names must start with a letter or underscore and contain only letters, numbers, and underscores",
+                    ),
+                );
+            }
+
+            let value = operation.value.as_deref().unwrap_or_default();
+            for reference in malformed_env_references(value) {
+                issues.push(
+                    ValidationIssue::new(
+                        Severity::Error,
+                        line_num,
+                        1,
+                        reference.len() as u32,
+                        format!("Malformed environment variable reference '{}'", reference),
+                        "R006",
+                    )
+                    .with_suggestion("Reference variables as $NAME or ${NAME}"),
+                );
+            }
+        }
+
+        if block.form == CommandsForm::String {
+            for statement in &block.unrecognized {
+                issues.push(
+                    ValidationIssue::new(
+                        Severity::Warning,
+                        statement.line + 1,
+                        1,
+                        statement.text.len() as u32,
+                        format!("Unrecognized statement in string commands: '{}'", statement.text),
+                        "R106",
+                    )
+                    .with_suggestion(
+                        "Use export, setenv, appendenv, prependenv, unsetenv or alias, or convert commands to a function",
+                    ),
+                );
+            }
+        }
+
+        issues
+    }
+
     /// Clean string values by removing quotes.
     fn clean_string_value(&self, value: &str) -> String {
         value
@@ -372,22 +828,65 @@ impl RezValidator {
             .trim_end_matches('\'')
             .to_string()
     }
+}
 
-    /// Parse a list value from Python syntax.
-    fn parse_list_value(&self, value: &str) -> Option<Vec<String>> {
-        let trimmed = value.trim();
-        if !trimmed.starts_with('[') || !trimmed.ends_with(']') {
-            return None;
-        }
+/// Get the schema attribute assigned by a statement, e.g. `requires` for
+/// `requires = ["python"]`.
+fn assigned_attribute(statement: &str) -> Option<&'static str> {
+    let name_end = statement
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(statement.len());
+    let rest = statement[name_end..].trim_start();
+    if !rest.starts_with('=') || rest.starts_with("==") {
+        return None;
+    }
+    PACKAGE_ATTRIBUTES
+        .iter()
+        .find(|attribute| **attribute == &statement[..name_end])
+        .copied()
+}
 
-        let content = &trimmed[1..trimmed.len() - 1];
-        let items: Vec<String> = content
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+/// Track the bracket depth and open triple-quoted string across a line.
+fn update_nesting(line: &str, depth: &mut i32, string: &mut Option<&'static str>) {
+    let mut rest = line;
+    while !rest.is_empty() {
+        if let Some(delimiter) = *string {
+            match rest.find(delimiter) {
+                Some(end) => {
+                    rest = &rest[end + delimiter.len()..];
+                    *string = None;
+                }
+                None => return,
+            }
+            continue;
+        }
 
-        Some(items)
+        let Some(start) = rest.find(['#', '"', '\'', '(', '[', '{', ')', ']', '}']) else {
+            return;
+        };
+        let c = rest[start..].chars().next().unwrap_or_default();
+        rest = &rest[start + 1..];
+        match c {
+            '#' => return,
+            '(' | '[' | '{' => *depth += 1,
+            ')' | ']' | '}' => *depth = (*depth - 1).max(0),
+            quote => {
+                let triple = if quote == '"' { "\"\"\"" } else { "'''" };
+                if let Some(after) = rest.strip_prefix(&triple[1..]) {
+                    rest = after;
+                    *string = Some(triple);
+                    continue;
+                }
+                // Skip a single-line string, minding escaped quotes
+                let mut escaped = false;
+                let end = rest.find(|ch: char| {
+                    let closes = ch == quote && !escaped;
+                    escaped = ch == '\\' && !escaped;
+                    closes
+                });
+                rest = end.map_or("", |end| &rest[end + 1..]);
+            }
+        }
     }
 }
 
@@ -398,7 +897,7 @@ impl Default for RezValidator {
 }
 
 impl Validator for RezValidator {
-    fn validate(&self, content: &str, _file_path: &str) -> Result<Vec<ValidationIssue>> {
+    fn validate(&self, content: &str, file_path: &str) -> Result<Vec<ValidationIssue>> {
         let mut issues = Vec::new();
 
         // Extract field assignments
@@ -408,10 +907,14 @@ impl Validator for RezValidator {
         issues.extend(self.check_required_fields(&fields));
         issues.extend(self.check_recommended_fields(&fields));
         issues.extend(self.check_deprecated_fields(&fields));
+        issues.extend(self.check_shadowed_assignments(content));
         issues.extend(self.validate_name(&fields));
-        issues.extend(self.validate_version(&fields));
-        issues.extend(self.validate_requires(&fields));
+        let scheme = self.version_policy.scheme_for(Path::new(file_path));
+        issues.extend(self.validate_version(&fields, scheme));
+        issues.extend(self.validate_requires(content));
+        issues.extend(self.validate_ephemerals(content));
         issues.extend(self.validate_tools(&fields));
+        issues.extend(self.validate_commands(content));
 
         // Sort issues by line number
         issues.sort_by_key(|issue| issue.line);
@@ -490,4 +993,225 @@ uuid = "some-uuid"
         let issues = validator.validate(content, "package.py").unwrap();
         assert!(issues.iter().any(|i| i.code == "R201"));
     }
+
+    #[test]
+    fn test_shadowed_assignments() {
+        let validator = RezValidator::new().unwrap();
+        let content = r#"name = "test"
+version = "1.0.0"
+description = """
+name = "not an assignment"
+"""
+requires = ["python"]
+if building:
+    version = "1.0.0.dev"
+
+def commands():
+    name = "local"
+    env.PATH.append("{root}/bin")
+
+requires = [
+    "maya",
+]
+if version == "1.0.0":
+    pass
+"#;
+
+        let issues: Vec<_> = validator
+            .validate(content, "package.py")
+            .unwrap()
+            .into_iter()
+            .filter(|i| i.code == "R113")
+            .collect();
+        assert_eq!(issues.len(), 2);
+        assert_eq!((issues[0].line, issues[0].column), (8, 5));
+        assert_eq!(
+            issues[0].message,
+            "'version' is conditionally overridden, shadowing the value assigned on line 2"
+        );
+        assert_eq!(issues[1].line, 14);
+        assert_eq!(
+            issues[1].message,
+            "'requires' is assigned again, shadowing the value assigned on line 6"
+        );
+    }
+
+    #[test]
+    fn test_exclusive_branches_do_not_shadow_each_other() {
+        let validator = RezValidator::new().unwrap();
+        let content = r#"name = "tool"
+if building:
+    version = "1.0.0.dev"
+elif testing:
+    version = "1.0.0.test"
+else:
+    version = "1.0.0"
+try:
+    import studio
+    requires = studio.requires()
+except ImportError:
+    requires = ["python"]
+except ValueError:
+    requires = []
+else:
+    if studio.legacy:
+        requires = []
+finally:
+    requires = ["maya"]
+"#;
+
+        let issues: Vec<_> = validator
+            .validate(content, "package.py")
+            .unwrap()
+            .into_iter()
+            .filter(|i| i.code == "R113")
+            .collect();
+        // The `else` of a `try` runs after its body, and `finally` after
+        // every branch
+        let lines: Vec<u32> = issues.iter().map(|i| i.line).collect();
+        assert_eq!(lines, vec![17, 19]);
+        assert!(issues[0].message.ends_with("on line 10"));
+        assert!(issues[1].message.ends_with("on line 17"));
+    }
+
+    #[test]
+    fn test_string_commands_accepted() {
+        let validator = RezValidator::new().unwrap();
+        let content = r#"
+name = "legacy"
+version = "1.0.0"
+commands = """
+export PATH=$PATH:{root}/bin
+setenv LEGACY_ROOT {root}
+"""
+"#;
+
+        let issues = validator.validate(content, "package.py").unwrap();
+        assert!(issues.iter().all(|i| i.severity != Severity::Error));
+        assert!(!issues.iter().any(|i| i.code == "R106"));
+    }
+
+    #[test]
+    fn test_commands_env_vars() {
+        let validator = RezValidator::new().unwrap();
+        let content = r#"
+name = "legacy"
+version = "1.0.0"
+commands = """
+export 1PATH={root}/bin
+setenv TOOLS ${TOOLS_ROOT
+echo hello
+"""
+"#;
+
+        let issues = validator.validate(content, "package.py").unwrap();
+        let r006: Vec<_> = issues.iter().filter(|i| i.code == "R006").collect();
+        assert_eq!(r006.len(), 2);
+        assert_eq!(r006[0].line, 5);
+        assert_eq!(r006[1].line, 6);
+        assert!(issues.iter().any(|i| i.code == "R106" && i.line == 7));
+    }
+
+    #[test]
+    fn test_function_commands_env_vars() {
+        let validator = RezValidator::new().unwrap();
+        let content = r#"
+name = "tools"
+version = "1.0.0"
+
+def commands():
+    env.PATH.append("{root}/bin")
+    setenv("2BAD", "x")
+"#;
+
+        let issues = validator.validate(content, "package.py").unwrap();
+        assert!(issues.iter().any(|i| i.code == "R006" && i.line == 7));
+    }
+
+    #[test]
+    fn test_version_scheme_policy() {
+        let validator = RezValidator::new()
+            .unwrap()
+            .with_version_policy(VersionPolicy {
+                default_scheme: VersionScheme::Rez,
+                repositories: vec![(
+                    std::path::PathBuf::from("/studio/config"),
+                    VersionScheme::CalVer,
+                )],
+            });
+        let content = "name = \"show_config\"\nversion = \"1.2.0\"\n";
+
+        let issues = validator
+            .validate(content, "/studio/config/show_config/1.2.0/package.py")
+            .unwrap();
+        let r103 = issues.iter().find(|i| i.code == "R103").unwrap();
+        assert_eq!(r103.severity, Severity::Error);
+        assert!(r103.message.contains("calendar version"));
+
+        let issues = validator
+            .validate(content, "/studio/libs/show_config/1.2.0/package.py")
+            .unwrap();
+        assert!(!issues.iter().any(|i| i.code == "R103"));
+    }
+
+    #[test]
+    fn test_requirement_issue_ranges() {
+        let validator = RezValidator::new().unwrap();
+        let content = "name = \"tools\"\nversion = \"1.0.0\"\nrequires = [\n    \"python>=3.7\",\n    \"my lib\",\n    \"python\",\n]\n";
+
+        let issues = validator.validate(content, "package.py").unwrap();
+
+        let invalid = issues.iter().find(|i| i.code == "R004").unwrap();
+        assert_eq!((invalid.line, invalid.column, invalid.length), (5, 6, 6));
+
+        let duplicate = issues.iter().find(|i| i.code == "R105").unwrap();
+        assert_eq!(
+            (duplicate.line, duplicate.column, duplicate.length),
+            (6, 6, 6)
+        );
+    }
+
+    #[test]
+    fn test_half_typed_requires_reports_only_broken_region() {
+        let validator = RezValidator::new().unwrap();
+        let content = "name = \"tools\"\nversion = \"1.0.0\"\nrequires = [\n    \"python-3\",\n    \"my lib\ndescription = \"A tool for artists\"\ntools = [\"tools\"]\n";
+
+        let issues = validator.validate(content, "package.py").unwrap();
+        let invalid: Vec<(u32, &str)> = issues
+            .iter()
+            .filter(|i| i.code == "R004")
+            .map(|i| (i.line, i.message.as_str()))
+            .collect();
+        assert_eq!(invalid, vec![(5, "Invalid requirement format: 'my lib'")]);
+    }
+
+    #[test]
+    fn test_or_requirements() {
+        let validator = RezValidator::new().unwrap();
+        let content = "name = \"tools\"\nversion = \"1.0.0\"\nrequires = [\"maya-2023|2025+\", \"~python==3.9|3.11\", \".platform-linux|osx\", \"nuke-13|\", \"usd-23.*\"]\n";
+
+        let issues = validator.validate(content, "package.py").unwrap();
+        let invalid: Vec<&str> = issues
+            .iter()
+            .filter(|i| i.code == "R004" || i.code == "R007")
+            .map(|i| i.message.as_str())
+            .collect();
+        assert_eq!(invalid, vec!["Invalid requirement format: 'nuke-13|'"]);
+    }
+
+    #[test]
+    fn test_ephemeral_requirements() {
+        let validator = RezValidator::new().unwrap();
+        let content = "name = \"tools\"\nversion = \"1.0.0\"\nrequires = [\".foo.bar-1\", \"!.beta\", \".1st\"]\nvariants = [[\".platform-linux\", \".os..x\"]]\n";
+
+        let issues = validator.validate(content, "package.py").unwrap();
+        assert!(!issues.iter().any(|i| i.code == "R004" || i.code == "R104"));
+
+        let invalid: Vec<(u32, u32)> = issues
+            .iter()
+            .filter(|i| i.code == "R007")
+            .map(|i| (i.line, i.column))
+            .collect();
+        assert_eq!(invalid, vec![(3, 38), (4, 34)]);
+    }
 }
@@ -1,9 +1,17 @@
 //! Validation engine that coordinates multiple validators.
 
-use super::{PythonValidator, RezValidator, ValidationIssue, ValidationResult, Validator};
-use crate::core::Result;
+use super::{
+    foreign_file_reason, PythonValidator, RezValidator, Suppressions, ValidationIssue,
+    ValidationResult, Validator, ValidatorTiming,
+};
+use crate::core::{Result, VersionPolicy};
+use crate::parser::encoding::REPLACEMENT_CHARACTER;
 use std::sync::Arc;
 use std::time::Instant;
+use tokio::sync::mpsc;
+
+/// Default size in bytes above which files only get partial validation.
+pub const LARGE_FILE_THRESHOLD: usize = 512 * 1024;
 
 /// Configuration for the validation engine.
 #[derive(Debug, Clone)]
@@ -18,6 +26,10 @@ pub struct ValidationConfig {
     pub include_style_warnings: bool,
     /// Whether to include informational messages
     pub include_info_messages: bool,
+    /// Size in bytes above which style checks and bracket matching are skipped
+    pub large_file_threshold: usize,
+    /// Version schemes enforced per package repository
+    pub version_policy: VersionPolicy,
 }
 
 impl Default for ValidationConfig {
@@ -28,10 +40,28 @@ impl Default for ValidationConfig {
             max_issues_per_file: 100,
             include_style_warnings: true,
             include_info_messages: false,
+            large_file_threshold: LARGE_FILE_THRESHOLD,
+            version_policy: VersionPolicy::default(),
         }
     }
 }
 
+/// Progress of a validation streamed by [`ValidationEngine::validate_stream`].
+#[derive(Debug, Clone)]
+pub enum ValidationEvent {
+    /// A validator finished, with its issues left after filtering but before
+    /// sorting and truncation
+    Issues {
+        /// Time taken by the validator
+        timing: ValidatorTiming,
+        /// Issues the validator reported
+        issues: Vec<ValidationIssue>,
+    },
+    /// Every validator finished, with the same result as
+    /// [`ValidationEngine::validate_file`]
+    Finished(ValidationResult),
+}
+
 /// Main validation engine that coordinates multiple validators.
 pub struct ValidationEngine {
     /// Configuration for validation
@@ -57,7 +87,9 @@ impl ValidationEngine {
         };
 
         let rez_validator = if config.enable_rez_validation {
-            Some(Arc::new(RezValidator::new()?))
+            Some(Arc::new(
+                RezValidator::new()?.with_version_policy(config.version_policy.clone()),
+            ))
         } else {
             None
         };
@@ -71,38 +103,109 @@ impl ValidationEngine {
 
     /// Validate a package.py file and return all issues found.
     pub fn validate_file(&self, content: &str, file_path: &str) -> Result<ValidationResult> {
+        Ok(self.run_validators(content, file_path, |_, _| {}))
+    }
+
+    /// Validate a package.py file in the background, streaming the issues of
+    /// each validator as it finishes.
+    ///
+    /// Lets the diagnostics of very large files be published progressively.
+    /// The last event is always [`ValidationEvent::Finished`].
+    pub fn validate_stream(
+        self: Arc<Self>,
+        content: String,
+        file_path: String,
+    ) -> mpsc::UnboundedReceiver<ValidationEvent> {
+        let (events, receiver) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            let result = self.run_validators(&content, &file_path, |timing, issues| {
+                let _ = events.send(ValidationEvent::Issues {
+                    timing: timing.clone(),
+                    issues: issues.to_vec(),
+                });
+            });
+            let _ = events.send(ValidationEvent::Finished(result));
+        });
+        receiver
+    }
+
+    /// Run the enabled validators, calling `on_validator` with the filtered
+    /// issues of each one as it finishes.
+    fn run_validators(
+        &self,
+        content: &str,
+        file_path: &str,
+        mut on_validator: impl FnMut(&ValidatorTiming, &[ValidationIssue]),
+    ) -> ValidationResult {
         let start_time = Instant::now();
         let mut all_issues = Vec::new();
+        let mut timings = Vec::new();
+        let is_large = content.len() > self.config.large_file_threshold;
+
+        // Files such as vendored Python modules only get a note, regardless
+        // of the info message filter
+        if let Some(reason) = foreign_file_reason(content, file_path) {
+            let issue = ValidationIssue::new(
+                super::Severity::Info,
+                1,
+                1,
+                1,
+                format!(
+                    "Not a rez package definition: {}; rez checks were skipped",
+                    reason
+                ),
+                "V004",
+            )
+            .with_suggestion(
+                "Exclude this directory from the package paths if it is not a package repository",
+            );
+            let validation_time = start_time.elapsed().as_millis() as u64;
+            return ValidationResult::new(file_path, vec![issue], validation_time);
+        }
 
-        // Run Python validation if enabled
-        if let Some(validator) = &self.python_validator {
-            match validator.validate(content, file_path) {
+        let suppressions = Suppressions::parse(content);
+        let mut record = |name: &str, started: Instant, result: Result<Vec<ValidationIssue>>| {
+            match result {
                 Ok(mut issues) => {
-                    all_issues.append(&mut issues);
+                    issues.retain(|issue| !suppressions.is_suppressed(&issue.code, issue.line));
+                    let issues = self.filter_issues(issues);
+                    let timing = ValidatorTiming {
+                        validator: name.to_string(),
+                        duration_us: started.elapsed().as_micros() as u64,
+                        issues: issues.len(),
+                    };
+                    on_validator(&timing, &issues);
+                    timings.push(timing);
+                    all_issues.extend(issues);
                 }
                 Err(e) => {
                     // Log error but continue with other validators
-                    eprintln!("Python validation failed: {}", e);
+                    eprintln!("{} failed: {}", name, e);
                 }
             }
+        };
+
+        // Run Python validation if enabled
+        if let Some(validator) = &self.python_validator {
+            let started = Instant::now();
+            let result = if is_large {
+                validator.validate_partial(content)
+            } else {
+                validator.validate(content, file_path)
+            };
+            record(validator.name(), started, result);
         }
 
         // Run Rez validation if enabled
         if let Some(validator) = &self.rez_validator {
-            match validator.validate(content, file_path) {
-                Ok(mut issues) => {
-                    all_issues.append(&mut issues);
-                }
-                Err(e) => {
-                    // Log error but continue
-                    eprintln!("Rez validation failed: {}", e);
-                }
-            }
+            let started = Instant::now();
+            record(
+                validator.name(),
+                started,
+                validator.validate(content, file_path),
+            );
         }
 
-        // Filter issues based on configuration
-        all_issues = self.filter_issues(all_issues);
-
         // Sort issues by severity (critical first), then by line number
         all_issues.sort_by(|a, b| {
             b.severity
@@ -132,12 +235,31 @@ impl ValidationEngine {
             );
         }
 
+        // Report undecodable text regardless of the style warning filter
+        all_issues.extend(encoding_issues(content));
+
+        // Report skipped checks regardless of the info message filter
+        if is_large {
+            all_issues.push(
+                ValidationIssue::new(
+                    super::Severity::Info,
+                    1,
+                    1,
+                    1,
+                    format!(
+                        "File is larger than {} KiB; style checks and bracket matching were skipped",
+                        self.config.large_file_threshold / 1024
+                    ),
+                    "V002",
+                )
+                .with_suggestion("Split generated content out of package.py if possible"),
+            );
+        }
+
         let validation_time = start_time.elapsed().as_millis() as u64;
-        Ok(ValidationResult::new(
-            file_path,
-            all_issues,
-            validation_time,
-        ))
+        let mut result = ValidationResult::new(file_path, all_issues, validation_time);
+        result.stats.validator_timings = timings;
+        result
     }
 
     /// Validate multiple files concurrently.
@@ -227,8 +349,11 @@ impl ValidationEngine {
             self.python_validator = None;
         }
 
-        if config.enable_rez_validation && self.rez_validator.is_none() {
-            self.rez_validator = Some(Arc::new(RezValidator::new()?));
+        let policy_changed = config.version_policy != self.config.version_policy;
+        if config.enable_rez_validation && (self.rez_validator.is_none() || policy_changed) {
+            self.rez_validator = Some(Arc::new(
+                RezValidator::new()?.with_version_policy(config.version_policy.clone()),
+            ));
         } else if !config.enable_rez_validation {
             self.rez_validator = None;
         }
@@ -293,6 +418,25 @@ impl Default for ValidationEngine {
     }
 }
 
+/// Report the first replacement character left by a lossy decode of the
+/// file, which means it was not saved as UTF-8.
+fn encoding_issues(content: &str) -> Option<ValidationIssue> {
+    content.lines().enumerate().find_map(|(line_num, line)| {
+        let column = line.chars().position(|c| c == REPLACEMENT_CHARACTER)?;
+        Some(
+            ValidationIssue::new(
+                super::Severity::Warning,
+                line_num as u32 + 1,
+                column as u32 + 1,
+                1,
+                "File is not valid UTF-8; undecodable characters were replaced",
+                "V003",
+            )
+            .with_suggestion("Re-save the file as UTF-8"),
+        )
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -336,6 +480,46 @@ description = "Test package"
         assert!(result.stats.validation_time_ms > 0);
     }
 
+    #[test]
+    fn test_validator_timings() {
+        let engine = ValidationEngine::new().unwrap();
+        let result = engine
+            .validate_file("name = \"test\"\nversion = \"1.0.0\" \n", "package.py")
+            .unwrap();
+
+        let timings = &result.stats.validator_timings;
+        let names: Vec<&str> = timings.iter().map(|t| t.validator.as_str()).collect();
+        assert_eq!(names, vec!["PythonValidator", "RezValidator"]);
+        assert_eq!(timings[0].issues, 1); // W291 trailing whitespace
+        assert!(result.stats.slowest_validator().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_validation_stream() {
+        let engine = Arc::new(ValidationEngine::new().unwrap());
+        let content = "name = \"test\"\nversion = \"1.0.0\" \nrequires = [\"python-\"]\n";
+        let expected = engine.validate_file(content, "package.py").unwrap();
+
+        let mut events = engine.validate_stream(content.to_string(), "package.py".to_string());
+        let mut streamed = Vec::new();
+        let mut finished = None;
+        while let Some(event) = events.recv().await {
+            assert!(finished.is_none(), "no event follows the result");
+            match event {
+                ValidationEvent::Issues { timing, issues } => {
+                    assert_eq!(timing.issues, issues.len());
+                    streamed.push(timing.validator);
+                }
+                ValidationEvent::Finished(result) => finished = Some(result),
+            }
+        }
+
+        assert_eq!(streamed, vec!["PythonValidator", "RezValidator"]);
+        let finished = finished.unwrap();
+        assert_eq!(finished.issues, expected.issues);
+        assert_eq!(finished.stats.validator_timings.len(), 2);
+    }
+
     #[test]
     fn test_multiple_file_validation() {
         let engine = ValidationEngine::new().unwrap();
@@ -374,4 +558,96 @@ description = "Test package"
         assert_eq!(summary.total_files, 2);
         assert!(summary.total_validation_time_ms > 0);
     }
+
+    #[test]
+    fn test_large_file_partial_validation() {
+        let engine = ValidationEngine::with_config(ValidationConfig {
+            large_file_threshold: 64,
+            ..Default::default()
+        })
+        .unwrap();
+        let long_line = format!("description = \"{}\"   ", "x".repeat(100));
+        let content = format!(
+            "name = \"big\"\nversion = \"1.0.0\"\n{}\nrequires = [\n",
+            long_line
+        );
+
+        let result = engine.validate_file(&content, "package.py").unwrap();
+        let codes: Vec<&str> = result.issues.iter().map(|i| i.code.as_str()).collect();
+        assert!(codes.contains(&"V002"));
+        assert!(!codes.contains(&"W501"));
+        assert!(!codes.contains(&"W291"));
+        assert!(!codes.contains(&"E999"));
+
+        // The same content below the threshold gets every check
+        let result = ValidationEngine::new()
+            .unwrap()
+            .validate_file(&content, "package.py")
+            .unwrap();
+        let codes: Vec<&str> = result.issues.iter().map(|i| i.code.as_str()).collect();
+        assert!(!codes.contains(&"V002"));
+        assert!(codes.contains(&"W501"));
+        assert!(codes.contains(&"W291"));
+    }
+
+    #[test]
+    fn test_undecodable_text_warning() {
+        let engine = ValidationEngine::with_config(ValidationConfig {
+            include_style_warnings: false,
+            ..Default::default()
+        })
+        .unwrap();
+        let content = "name = \"test\"\ndescription = \"Caf\u{fffd} tools\"\n";
+
+        let result = engine.validate_file(content, "package.py").unwrap();
+        let issue = result.issues.iter().find(|i| i.code == "V003").unwrap();
+        assert_eq!((issue.line, issue.column), (2, 19));
+    }
+
+    #[test]
+    fn test_inline_suppressions() {
+        let engine = ValidationEngine::new().unwrap();
+        let long_line = format!("description = \"{}\"", "x".repeat(100));
+        let content = format!("name = \"my-tool\"\nversion = \"1.0.0\"\n{}\n", long_line);
+        let codes = |content: &str| -> Vec<String> {
+            let result = engine.validate_file(content, "package.py").unwrap();
+            result.issues.into_iter().map(|issue| issue.code).collect()
+        };
+        assert!(codes(&content).contains(&"W501".to_string()));
+
+        let suppressed = format!("# rez-lsp: disable=W501\n{}", content);
+        assert!(!codes(&suppressed).contains(&"W501".to_string()));
+        let suppressed = format!("{}  # rez-lsp: disable=W501\n", content.trim_end());
+        assert!(!codes(&suppressed).contains(&"W501".to_string()));
+        // Directives on other lines leave the issue in place
+        let elsewhere = content.replacen("\n", "  # rez-lsp: disable=W501\n", 1);
+        assert!(codes(&elsewhere).contains(&"W501".to_string()));
+    }
+
+    #[test]
+    fn test_foreign_file_skipped() {
+        let engine = ValidationEngine::new().unwrap();
+        let module =
+            "import os\nimport sys\nfrom setuptools import setup\n\ndef main():\n    pass\n";
+
+        let result = engine
+            .validate_file(module, "/ws/tools/package.py")
+            .unwrap();
+        assert_eq!(result.issues.len(), 1);
+        assert_eq!(result.issues[0].code, "V004");
+
+        let result = engine
+            .validate_file("", "/ws/.venv/lib/site-packages/tools/package.py")
+            .unwrap();
+        assert!(result.issues[0].message.contains(".venv"));
+
+        // Families named like environment directories are still packages
+        let package = "name = \"venv\"\n";
+        assert!(foreign_file_reason(package, "/repo/venv/1.0/package.py").is_none());
+
+        // Packages importing helpers and new files are still validated
+        let package = format!("{}name = \"tools\"\n", module);
+        assert!(foreign_file_reason(&package, "/ws/tools/package.py").is_none());
+        assert!(foreign_file_reason("", "/ws/tools/package.py").is_none());
+    }
 }
@@ -4,20 +4,16 @@ use rez_lsp_server::core::{Package, Requirement, Version, VersionConstraint};
 use rez_lsp_server::resolver::DependencyResolverImpl;
 use rez_lsp_server::core::DependencyResolver;
 use std::collections::HashMap;
-use std::path::PathBuf;
 
 fn create_test_package(name: &str, version: &str, requires: Vec<Requirement>) -> Package {
-    Package {
-        name: name.to_string(),
-        version: Version::new(version),
-        description: Some(format!("Test package {}", name)),
-        authors: vec!["Test Author".to_string()],
-        requires,
-        tools: vec![],
-        variants: vec![],
-        path: PathBuf::from("/test"),
-        metadata: HashMap::new(),
-    }
+    Package::builder()
+        .name(name)
+        .version(version)
+        .description(format!("Test package {}", name))
+        .author("Test Author")
+        .requires(requires)
+        .path("/test")
+        .build()
 }
 
 #[tokio::test]
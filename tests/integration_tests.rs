@@ -4,20 +4,19 @@ use rez_lsp_server::core::DependencyResolver;
 use rez_lsp_server::core::{Package, Requirement, Version, VersionConstraint};
 use rez_lsp_server::resolver::DependencyResolverImpl;
 use std::collections::HashMap;
-use std::path::PathBuf;
-
-fn create_test_package(name: &str, version: &str, requires: Vec<Requirement>) -> Package {
-    Package {
-        name: name.to_string(),
-        version: Version::new(version),
-        description: Some(format!("Test package {}", name)),
-        authors: vec!["Test Author".to_string()],
-        requires,
-        tools: vec![],
-        variants: vec![],
-        path: PathBuf::from("/test"),
-        metadata: HashMap::new(),
-    }
+use std::sync::Arc;
+
+fn create_test_package(name: &str, version: &str, requires: Vec<Requirement>) -> Arc<Package> {
+    Arc::new(
+        Package::builder()
+            .name(name)
+            .version(version)
+            .description(format!("Test package {}", name))
+            .author("Test Author")
+            .requires(requires)
+            .path("/test")
+            .build(),
+    )
 }
 
 #[tokio::test]
@@ -53,7 +52,7 @@ async fn test_simple_dependency_resolution() {
     assert_eq!(context.packages.len(), 2); // maya + python
 
     // Check that both packages are resolved
-    let package_names: Vec<&str> = context.packages.iter().map(|p| p.name.as_str()).collect();
+    let package_names: Vec<&str> = context.packages.iter().map(|p| &*p.name).collect();
     assert!(package_names.contains(&"maya"));
     assert!(package_names.contains(&"python"));
 }
@@ -89,7 +88,7 @@ async fn test_version_constraint_satisfaction() {
 
     // Should pick the latest version that satisfies the constraint
     let python_package = &context.packages[0];
-    assert_eq!(python_package.name, "python");
+    assert_eq!(&*python_package.name, "python");
     // The resolver should pick the highest version >= 3.8
     assert!(python_package.version >= Version::new("3.8.0"));
     // In our test data, this should be 3.10.0
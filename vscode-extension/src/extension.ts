@@ -252,6 +252,35 @@ export function activate(context: vscode.ExtensionContext) {
     // Get configuration
     const config = vscode.workspace.getConfiguration('rezLsp');
     const traceLevel = config.get<string>('trace.server', 'off');
+    const scanExclude = config.get<string[]>('scan.exclude', []).join(',');
+    const packageFilePatterns = config.get<string[]>('packageFilePatterns', []);
+    const hoverPayload = String(config.get<boolean>('hover.payloadSize', false));
+    const completionDetail = config.get<string>('completion.detail', '');
+    const advisories = config.get<string>('advisories', '');
+    const packageCategories = Object.entries(config.get<Record<string, string>>('completion.packageCategories', {}))
+        .map(([family, category]) => `${family}=${category}`)
+        .join(',');
+    const pathMappings = Object.entries(config.get<Record<string, string>>('remote.pathMappings', {}))
+        .map(([server, client]) => `${server}=${client}`)
+        .join(',');
+    const sharedIndex = String(config.get<boolean>('sharedIndex', false));
+    const excludePrereleases = String(config.get<boolean>('excludePrereleases', false));
+    const hoverExplain = String(config.get<boolean>('hover.explainRequirements', false));
+    const maxVariants = String(config.get<number>('resolver.maxVariants', 1000));
+    const maxVariantFanout = String(config.get<number>('resolver.maxVariantFanout', 16));
+    const resolveStrategy = config.get<string>('resolver.strategy', 'latest');
+    const preferredVersions = Object.entries(config.get<Record<string, string>>('resolver.preferredVersions', {}))
+        .map(([family, range]) => `${family}=${range}`)
+        .join(',');
+    const trailingComma = config.get<string>('format.trailingComma', 'multiline');
+    const triggerCharacters = config.get<string>('completion.triggerCharacters', '');
+    const virtualDocuments = String(config.get<boolean>('virtualDocuments', false));
+    const restoreSession = String(config.get<boolean>('restoreSession', true));
+    const evaluation = config.get<string>('evaluation', 'static');
+    const trustWorkspace = String(config.get<boolean>('trustWorkspace', false) || vscode.workspace.isTrusted);
+    const knownFamilies = JSON.stringify(config.get<Record<string, unknown>>('knownFamilies', {}));
+    const packagePaths = config.get<string[]>('packagePaths', [])
+        .join(process.platform === 'win32' ? ';' : ':');
 
     outputChannel.appendLine(`🚀 Starting Rez LSP Server: ${serverPath}`);
     outputChannel.appendLine(`📊 Trace level: ${traceLevel}`);
@@ -273,7 +302,30 @@ export function activate(context: vscode.ExtensionContext) {
                 env: {
                     ...process.env,
                     RUST_LOG: traceLevel === 'verbose' ? 'debug' : 'info',
-                    RUST_BACKTRACE: '1'
+                    RUST_BACKTRACE: '1',
+                    REZ_LSP_SCAN_EXCLUDE: scanExclude,
+                    REZ_LSP_PACKAGE_FILE_PATTERNS: packageFilePatterns.join(','),
+                    REZ_LSP_HOVER_PAYLOAD: hoverPayload,
+                    REZ_LSP_COMPLETION_DETAIL: completionDetail,
+                    REZ_LSP_ADVISORIES: advisories,
+                    REZ_LSP_SHARED_INDEX: sharedIndex,
+                    REZ_LSP_EXCLUDE_PRERELEASES: excludePrereleases,
+                    REZ_LSP_HOVER_EXPLAIN: hoverExplain,
+                    REZ_LSP_LOCALE: vscode.env.language,
+                    REZ_LSP_MAX_VARIANTS: maxVariants,
+                    REZ_LSP_MAX_VARIANT_FANOUT: maxVariantFanout,
+                    REZ_LSP_RESOLVE_STRATEGY: resolveStrategy,
+                    REZ_LSP_PREFERRED_VERSIONS: preferredVersions,
+                    REZ_LSP_TRAILING_COMMA: trailingComma,
+                    REZ_LSP_PACKAGE_CATEGORIES: packageCategories,
+                    REZ_LSP_PATH_MAPPINGS: pathMappings,
+                    REZ_LSP_COMPLETION_TRIGGER_CHARACTERS: triggerCharacters,
+                    REZ_LSP_VIRTUAL_DOCUMENTS: virtualDocuments,
+                    REZ_LSP_RESTORE_SESSION: restoreSession,
+                    REZ_LSP_EVALUATION: evaluation,
+                    REZ_LSP_TRUST_WORKSPACE: trustWorkspace,
+                    REZ_LSP_PACKAGE_PATHS: packagePaths,
+                    REZ_LSP_KNOWN_FAMILIES: knownFamilies
                 }
             }
         },
@@ -284,7 +336,30 @@ export function activate(context: vscode.ExtensionContext) {
                 env: {
                     ...process.env,
                     RUST_LOG: 'debug',
-                    RUST_BACKTRACE: 'full'
+                    RUST_BACKTRACE: 'full',
+                    REZ_LSP_SCAN_EXCLUDE: scanExclude,
+                    REZ_LSP_PACKAGE_FILE_PATTERNS: packageFilePatterns.join(','),
+                    REZ_LSP_HOVER_PAYLOAD: hoverPayload,
+                    REZ_LSP_COMPLETION_DETAIL: completionDetail,
+                    REZ_LSP_ADVISORIES: advisories,
+                    REZ_LSP_SHARED_INDEX: sharedIndex,
+                    REZ_LSP_EXCLUDE_PRERELEASES: excludePrereleases,
+                    REZ_LSP_HOVER_EXPLAIN: hoverExplain,
+                    REZ_LSP_LOCALE: vscode.env.language,
+                    REZ_LSP_MAX_VARIANTS: maxVariants,
+                    REZ_LSP_MAX_VARIANT_FANOUT: maxVariantFanout,
+                    REZ_LSP_RESOLVE_STRATEGY: resolveStrategy,
+                    REZ_LSP_PREFERRED_VERSIONS: preferredVersions,
+                    REZ_LSP_TRAILING_COMMA: trailingComma,
+                    REZ_LSP_PACKAGE_CATEGORIES: packageCategories,
+                    REZ_LSP_PATH_MAPPINGS: pathMappings,
+                    REZ_LSP_COMPLETION_TRIGGER_CHARACTERS: triggerCharacters,
+                    REZ_LSP_VIRTUAL_DOCUMENTS: virtualDocuments,
+                    REZ_LSP_RESTORE_SESSION: restoreSession,
+                    REZ_LSP_EVALUATION: evaluation,
+                    REZ_LSP_TRUST_WORKSPACE: trustWorkspace,
+                    REZ_LSP_PACKAGE_PATHS: packagePaths,
+                    REZ_LSP_KNOWN_FAMILIES: knownFamilies
                 }
             }
         }
@@ -296,7 +371,11 @@ export function activate(context: vscode.ExtensionContext) {
         documentSelector: [
             { scheme: 'file', language: 'python', pattern: '**/package.py' },
             { scheme: 'file', language: 'rez-package' },
-            { scheme: 'file', pattern: '**/*.rxt' }
+            { scheme: 'file', pattern: '**/*.rxt' },
+            ...packageFilePatterns.map(pattern => ({ scheme: 'file', pattern: `**/${pattern}` })),
+            // Unsaved buffers and notebook cells pasted with package.py content
+            { scheme: 'untitled', language: 'rez-package' },
+            { scheme: 'vscode-notebook-cell', language: 'rez-package' }
         ],
         synchronize: {
             // Notify the server about file changes to package.py and .rxt files
@@ -375,7 +454,7 @@ export function activate(context: vscode.ExtensionContext) {
         try {
             // Send workspace reload request to the server
             if (client && currentServerStatus === ServerStatus.Running) {
-                await client.sendRequest('workspace/didChangeConfiguration', {
+                await client.sendNotification('workspace/didChangeConfiguration', {
                     settings: vscode.workspace.getConfiguration('rezLsp')
                 });
                 outputChannel.appendLine('✅ Workspace reloaded successfully');
@@ -428,6 +507,175 @@ export function activate(context: vscode.ExtensionContext) {
         }
     });
 
+    const preReleaseCheckCommand = vscode.commands.registerCommand('rezLsp.preReleaseCheck', async (uri?: vscode.Uri) => {
+        const target = uri ?? vscode.window.activeTextEditor?.document.uri;
+        if (!target || !target.path.endsWith('package.py')) {
+            vscode.window.showWarningMessage('Rez LSP: Open a package.py to run the pre-release check');
+            return false;
+        }
+        if (!client || currentServerStatus !== ServerStatus.Running) {
+            vscode.window.showWarningMessage('Rez LSP Server is not running');
+            return false;
+        }
+
+        try {
+            const report: any = await client.sendRequest('workspace/executeCommand', {
+                command: 'rez.preReleaseCheck',
+                arguments: [target.toString()]
+            });
+            const icons: Record<string, string> = { ok: '✅', warning: '⚠️', error: '❌' };
+            outputChannel.appendLine(`🚀 Pre-release check of ${report.package ?? '?'}-${report.version ?? '?'}`);
+            for (const check of report.checks) {
+                outputChannel.appendLine(`${icons[check.status] ?? ''} ${check.title}: ${check.detail}`);
+                for (const item of check.items) {
+                    outputChannel.appendLine(`    ${item}`);
+                }
+            }
+            outputChannel.show();
+            if (report.ready) {
+                vscode.window.showInformationMessage('Rez LSP: Package is ready to release');
+            } else {
+                vscode.window.showErrorMessage('Rez LSP: Package is not ready to release, see the output channel');
+            }
+            return report.ready;
+        } catch (error) {
+            outputChannel.appendLine(`❌ Pre-release check failed: ${error}`);
+            vscode.window.showErrorMessage(`Pre-release check failed: ${error}`);
+            return false;
+        }
+    });
+
+    const localizePackageCommand = vscode.commands.registerCommand('rezLsp.localizePackage', async (name?: string, version?: string) => {
+        if (!client || currentServerStatus !== ServerStatus.Running) {
+            vscode.window.showWarningMessage('Rez LSP Server is not running');
+            return undefined;
+        }
+        name = name ?? await vscode.window.showInputBox({ prompt: 'Package to copy into the local repository', placeHolder: 'maya_tools' });
+        if (!name) {
+            return undefined;
+        }
+        version = version ?? await vscode.window.showInputBox({ prompt: `Version of ${name} to copy`, placeHolder: '1.2.0' });
+        if (!version) {
+            return undefined;
+        }
+
+        const args: string[] = [name, version];
+        const document = vscode.window.activeTextEditor?.document;
+        if (document && document.uri.path.endsWith('package.py')) {
+            const pin = await vscode.window.showQuickPick(['Yes', 'No'], { placeHolder: `Pin ${name} to ${version} in ${document.fileName}?` });
+            if (pin === 'Yes') {
+                args.push(document.uri.toString());
+            }
+        }
+
+        try {
+            const result: any = await client.sendRequest('workspace/executeCommand', {
+                command: 'rez.localizePackage',
+                arguments: args
+            });
+            outputChannel.appendLine(`📦 Copied ${result.package}-${result.version} to ${result.destination} (${result.files} files)`);
+            vscode.window.showInformationMessage(`Rez LSP: ${result.package}-${result.version} copied to the local repository`);
+            return result;
+        } catch (error) {
+            outputChannel.appendLine(`❌ Localizing ${name}-${version} failed: ${error}`);
+            vscode.window.showErrorMessage(`Localizing ${name}-${version} failed: ${error}`);
+            return undefined;
+        }
+    });
+
+    const dependencyManifestCommand = vscode.commands.registerCommand('rezLsp.dependencyManifest', async (uri?: vscode.Uri, format?: string) => {
+        const target = uri ?? vscode.window.activeTextEditor?.document.uri;
+        if (!target || !target.path.endsWith('package.py')) {
+            vscode.window.showWarningMessage('Rez LSP: Open a package.py to generate its dependency manifest');
+            return undefined;
+        }
+        if (!client || currentServerStatus !== ServerStatus.Running) {
+            vscode.window.showWarningMessage('Rez LSP Server is not running');
+            return undefined;
+        }
+        format = format ?? await vscode.window.showQuickPick(['spdx', 'cyclonedx'], { placeHolder: 'Manifest format' });
+        if (!format) {
+            return undefined;
+        }
+
+        try {
+            const manifest: any = await client.sendRequest('workspace/executeCommand', {
+                command: 'rez.dependencyManifest',
+                arguments: [target.toString(), format]
+            });
+            const document = await vscode.workspace.openTextDocument({ language: 'json', content: JSON.stringify(manifest, null, 2) });
+            await vscode.window.showTextDocument(document);
+            return manifest;
+        } catch (error) {
+            outputChannel.appendLine(`❌ Dependency manifest failed: ${error}`);
+            vscode.window.showErrorMessage(`Dependency manifest failed: ${error}`);
+            return undefined;
+        }
+    });
+
+    const trustWorkspaceCommand = vscode.commands.registerCommand('rezLsp.trustWorkspace', async () => {
+        if (!client || currentServerStatus !== ServerStatus.Running) {
+            vscode.window.showWarningMessage('Rez LSP Server is not running');
+            return;
+        }
+        try {
+            const changed: boolean = await client.sendRequest('workspace/executeCommand', {
+                command: 'rez.trustWorkspace',
+                arguments: []
+            });
+            if (changed) {
+                outputChannel.appendLine('🔓 Workspace trusted: package tests and evaluation are enabled');
+            }
+        } catch (error) {
+            outputChannel.appendLine(`❌ Trusting the workspace failed: ${error}`);
+        }
+    });
+
+    // Trusting the folder in VS Code leaves restricted mode as well
+    const grantTrustListener = vscode.workspace.onDidGrantWorkspaceTrust(() => {
+        vscode.commands.executeCommand('rezLsp.trustWorkspace');
+    });
+
+    const viewChangesCommand = vscode.commands.registerCommand('rezLsp.viewChanges', async (family?: string, since?: string) => {
+        if (!client || currentServerStatus !== ServerStatus.Running) {
+            vscode.window.showWarningMessage('Rez LSP Server is not running');
+            return;
+        }
+        family = family ?? await vscode.window.showInputBox({ prompt: 'Package family', placeHolder: 'maya' });
+        if (!family) {
+            return;
+        }
+        since = since ?? await vscode.window.showInputBox({ prompt: `Show the changes of ${family} since version`, placeHolder: '2023.1' });
+        if (!since) {
+            return;
+        }
+
+        try {
+            const changelog: any = await client.sendRequest('rez/changelog', { family, since });
+            const document = await vscode.workspace.openTextDocument({ content: changelog.content, language: 'markdown' });
+            await vscode.window.showTextDocument(document, { preview: true });
+        } catch (error) {
+            outputChannel.appendLine(`❌ Loading the changes of ${family} since ${since} failed: ${error}`);
+            vscode.window.showErrorMessage(`Loading the changes of ${family} failed: ${error}`);
+        }
+    });
+
+    // Read-only previews of indexed packages served by the server
+    const packageDocumentProvider = vscode.workspace.registerTextDocumentContentProvider('rezpkg', {
+        provideTextDocumentContent: async (uri: vscode.Uri) => {
+            if (!client || currentServerStatus !== ServerStatus.Running) {
+                return '# Rez LSP Server is not running';
+            }
+            try {
+                const result: any = await client.sendRequest('rez/packageContent', { uri: uri.toString() });
+                return result.content;
+            } catch (error) {
+                outputChannel.appendLine(`❌ Loading ${uri.toString()} failed: ${error}`);
+                return `# Failed to load ${uri.toString()}: ${error}`;
+            }
+        }
+    });
+
     // Remove duplicate showServerStatusCommand - already defined above
 
     // Register ALL commands together to avoid timing issues
@@ -439,9 +687,33 @@ export function activate(context: vscode.ExtensionContext) {
         reloadWorkspaceCommand,
         showOutputCommand,
         rebuildDependenciesCommand,
-        toggleDiagnosticsCommand
+        toggleDiagnosticsCommand,
+        preReleaseCheckCommand,
+        localizePackageCommand,
+        dependencyManifestCommand,
+        viewChangesCommand,
+        trustWorkspaceCommand,
+        grantTrustListener,
+        packageDocumentProvider
     );
 
+    // Released package definitions should not be edited in place
+    client.onNotification('rez/readOnlyHint', async (hint: { uri: string; readOnly: boolean; reason: string }) => {
+        outputChannel.appendLine(`🔒 ${hint.reason}: ${hint.uri}`);
+        if (!hint.readOnly || !vscode.workspace.getConfiguration('rezLsp').get<boolean>('releasedPackages.readOnly', true)) {
+            return;
+        }
+        const editor = vscode.window.activeTextEditor;
+        if (editor && editor.document.uri.toString() === vscode.Uri.parse(hint.uri).toString()) {
+            try {
+                await vscode.commands.executeCommand('workbench.action.files.setActiveEditorReadonlyInSession');
+            } catch {
+                // Read-only sessions need VS Code 1.79+; the R110 warning still shows
+            }
+        }
+        vscode.window.showWarningMessage(`${hint.reason}.`);
+    });
+
     // Start the client with better error handling
     outputChannel.appendLine('🔄 Starting LSP client...');
     setServerStatus(ServerStatus.Starting);